@@ -1,79 +1,182 @@
 //! Benchmarks for distributed cache operations.
 //!
-//! This module contains performance benchmarks for the Syros distributed cache system.
+//! This module contains performance benchmarks for the Syros distributed cache system,
+//! run against the plain in-memory cache and the journal-backed one.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use syros::config::{CachePersistenceConfig, FsyncPolicy};
 use syros::core::cache_manager::CacheManager;
-use syros::core::cache_manager::{CacheRequest, DeleteCacheRequest};
+use syros::core::cache_manager::{CacheRequest, CacheSetMode, DeleteCacheRequest};
+use syros::core::TaskTracker;
 use tokio::runtime::Runtime;
 
+/// Cache managers to benchmark, labelled by backend.
+///
+/// Both are memory backends: the journal only writes to a local file, so it
+/// runs without any external service.
+fn cache_backends(rt: &Runtime) -> Vec<(&'static str, CacheManager)> {
+    let journal_path = std::env::temp_dir().join(format!(
+        "syros-bench-cache-{}.journal",
+        uuid::Uuid::new_v4().simple()
+    ));
+    let persistence = CachePersistenceConfig {
+        path: journal_path.to_string_lossy().into_owned(),
+        flush_interval_ms: 100,
+        fsync: FsyncPolicy::Never,
+        compaction_interval_seconds: 300,
+    };
+    let journaled = rt
+        .block_on(CacheManager::with_persistence(
+            &persistence,
+            &TaskTracker::new(),
+        ))
+        .expect("failed to open cache journal");
+
+    vec![("memory", CacheManager::new()), ("journal", journaled)]
+}
+
 fn bench_cache_set(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let cache_manager = CacheManager::new();
+    let mut group = c.benchmark_group("cache/set");
 
-    c.bench_function("cache_set", |b| {
-        b.to_async(&rt).iter(|| async {
-            let request = CacheRequest {
-                key: black_box("benchmark-key".to_string()),
-                value: black_box(serde_json::json!({
-                    "data": "benchmark-value",
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                })),
-                ttl: Some(black_box(std::time::Duration::from_secs(60))),
-                tags: black_box(vec!["benchmark".to_string()]),
-            };
+    for (backend, cache_manager) in cache_backends(&rt) {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &cache_manager,
+            |b, cache_manager| {
+                b.to_async(&rt).iter(|| async {
+                    let request = CacheRequest {
+                        key: black_box("benchmark-key".to_string()),
+                        value: black_box(serde_json::json!({
+                            "data": "benchmark-value",
+                            "timestamp": chrono::Utc::now().to_rfc3339()
+                        })),
+                        ttl: Some(black_box(std::time::Duration::from_secs(60))),
+                        tags: black_box(vec!["benchmark".to_string()]),
+                        mode: CacheSetMode::Upsert,
+                        created_by: None,
+                        cache_negative: false,
+                    };
 
-            let _ = cache_manager.set(request).await;
-        })
-    });
+                    let _ = cache_manager.set(request).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
 }
 
 fn bench_cache_get(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let cache_manager = CacheManager::new();
+    let mut group = c.benchmark_group("cache/get");
 
-    c.bench_function("cache_get", |b| {
-        b.to_async(&rt).iter(|| async {
-            let _ = cache_manager.get(black_box("benchmark-key")).await;
-        })
-    });
+    for (backend, cache_manager) in cache_backends(&rt) {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &cache_manager,
+            |b, cache_manager| {
+                b.to_async(&rt).iter(|| async {
+                    let _ = cache_manager.get(black_box("benchmark-key")).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
 }
 
 fn bench_cache_delete(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let cache_manager = CacheManager::new();
+    let mut group = c.benchmark_group("cache/delete");
 
-    c.bench_function("cache_delete", |b| {
-        b.to_async(&rt).iter(|| async {
-            let request = DeleteCacheRequest {
-                key: black_box("benchmark-key".to_string()),
-            };
+    for (backend, cache_manager) in cache_backends(&rt) {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &cache_manager,
+            |b, cache_manager| {
+                b.to_async(&rt).iter(|| async {
+                    let request = DeleteCacheRequest {
+                        key: black_box("benchmark-key".to_string()),
+                    };
 
-            let _ = cache_manager.delete(request).await;
-        })
-    });
+                    let _ = cache_manager.delete(request).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
 }
 
 fn bench_cache_set_and_get(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let cache_manager = CacheManager::new();
+    let mut group = c.benchmark_group("cache/set_and_get");
 
-    c.bench_function("cache_set_and_get", |b| {
-        b.to_async(&rt).iter(|| async {
-            let set_request = CacheRequest {
-                key: black_box("benchmark-key".to_string()),
-                value: black_box(serde_json::json!({
-                    "data": "benchmark-value",
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                })),
-                ttl: Some(black_box(std::time::Duration::from_secs(60))),
-                tags: black_box(vec!["benchmark".to_string()]),
+    for (backend, cache_manager) in cache_backends(&rt) {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &cache_manager,
+            |b, cache_manager| {
+                b.to_async(&rt).iter(|| async {
+                    let set_request = CacheRequest {
+                        key: black_box("benchmark-key".to_string()),
+                        value: black_box(serde_json::json!({
+                            "data": "benchmark-value",
+                            "timestamp": chrono::Utc::now().to_rfc3339()
+                        })),
+                        ttl: Some(black_box(std::time::Duration::from_secs(60))),
+                        tags: black_box(vec!["benchmark".to_string()]),
+                        mode: CacheSetMode::Upsert,
+                        created_by: None,
+                        cache_negative: false,
+                    };
+
+                    let _ = cache_manager.set(set_request).await;
+                    let _ = cache_manager.get(black_box("benchmark-key")).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Reads 50 keys one at a time and with a single multi-get.
+fn bench_cache_get_many(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("cache/get_50");
+    let keys: Vec<String> = (0..50).map(|i| format!("benchmark-key-{}", i)).collect();
+    let cache_manager = CacheManager::new();
+    rt.block_on(async {
+        for key in &keys {
+            let request = CacheRequest {
+                key: key.clone(),
+                value: serde_json::json!({"data": "benchmark-value"}),
+                ttl: None,
+                tags: vec![],
+                mode: CacheSetMode::Upsert,
+                created_by: None,
+                cache_negative: false,
             };
+            cache_manager.set(request).await.unwrap();
+        }
+    });
 
-            let _ = cache_manager.set(set_request).await;
-            let _ = cache_manager.get(black_box("benchmark-key")).await;
+    group.bench_function("sequential", |b| {
+        b.to_async(&rt).iter(|| async {
+            for key in &keys {
+                let _ = cache_manager.get(black_box(key)).await;
+            }
         })
     });
+    group.bench_function("get_many", |b| {
+        b.to_async(&rt).iter(|| async {
+            let _ = cache_manager.get_many(black_box(&keys)).await;
+        })
+    });
+
+    group.finish();
 }
 
 criterion_group!(
@@ -81,6 +184,7 @@ criterion_group!(
     bench_cache_set,
     bench_cache_get,
     bench_cache_delete,
-    bench_cache_set_and_get
+    bench_cache_set_and_get,
+    bench_cache_get_many
 );
 criterion_main!(cache_benches);
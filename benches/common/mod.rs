@@ -0,0 +1,115 @@
+//! Backend selection shared by the Syros benchmarks.
+//!
+//! Every benchmark runs once per configured backend, with the backend name as
+//! the criterion parameter (e.g. `locks/acquire_release/redis`). In-memory
+//! backends always run; persistent ones only run when their connection URL is
+//! set, so a plain `cargo bench` stays memory-only.
+
+// Each bench target compiles this module separately and uses a subset of it.
+#![allow(dead_code)]
+
+use std::future::Future;
+use syros::storage::postgres::PostgresManager;
+use syros::storage::redis::RedisManager;
+use tokio::runtime::Runtime;
+
+/// Environment variable holding the Redis URL for persistent lock benchmarks.
+pub const REDIS_URL_ENV: &str = "SYROS_BENCH_REDIS_URL";
+
+/// Environment variable holding the Postgres URL for saga and event benchmarks.
+pub const DATABASE_URL_ENV: &str = "SYROS_BENCH_DATABASE_URL";
+
+const POSTGRES_POOL_SIZE: u32 = 16;
+const MIGRATIONS: [&str; 14] = [
+    include_str!("../../migrations/20240101000000_init_schema.sql"),
+    include_str!("../../migrations/20240301000000_saga_deadline.sql"),
+    include_str!("../../migrations/20240401000000_created_by.sql"),
+    include_str!("../../migrations/20240501000000_archived_streams.sql"),
+    include_str!("../../migrations/20240601000000_saga_services.sql"),
+    include_str!("../../migrations/20240701000000_saga_step_results.sql"),
+    include_str!("../../migrations/20240801000000_saga_definitions.sql"),
+    include_str!("../../migrations/20240901000000_saga_start_at.sql"),
+    include_str!("../../migrations/20241001000000_stream_updated_at.sql"),
+    include_str!("../../migrations/20241101000000_snapshots.sql"),
+    include_str!("../../migrations/20241201000000_event_positions.sql"),
+    include_str!("../../migrations/20250101000000_projections.sql"),
+    include_str!("../../migrations/20250201000000_stream_retention.sql"),
+    include_str!("../../migrations/20250301000000_streams.sql"),
+];
+
+/// Persistent backends reachable from this benchmark run.
+pub struct Backends {
+    pub redis: Option<RedisManager>,
+    pub postgres: Option<PostgresManager>,
+}
+
+impl Backends {
+    /// Connects to every backend whose URL is set, skipping the others.
+    ///
+    /// A backend that is configured but unreachable is skipped with a message
+    /// rather than failing the whole run.
+    pub fn from_env(rt: &Runtime) -> Self {
+        let redis = std::env::var(REDIS_URL_ENV)
+            .ok()
+            .and_then(|url| connect("redis", rt.block_on(connect_redis(&url))));
+        if redis.is_none() {
+            skip_notice("redis", REDIS_URL_ENV);
+        }
+
+        let postgres = std::env::var(DATABASE_URL_ENV)
+            .ok()
+            .and_then(|url| connect("postgres", rt.block_on(connect_postgres(&url))));
+        if postgres.is_none() {
+            skip_notice("postgres", DATABASE_URL_ENV);
+        }
+
+        Self { redis, postgres }
+    }
+}
+
+async fn connect_redis(url: &str) -> syros::Result<RedisManager> {
+    let redis = RedisManager::new(url)?;
+    // Fail fast on an unreachable server instead of inside the first sample.
+    redis.get_connection().await?;
+    Ok(redis)
+}
+
+async fn connect_postgres(url: &str) -> syros::Result<PostgresManager> {
+    let pg = PostgresManager::new(url, POSTGRES_POOL_SIZE).await?;
+    for migration in MIGRATIONS {
+        sqlx::Executor::execute(pg.get_pool(), migration)
+            .await
+            .map_err(|e| syros::SyrosError::StorageError(e.to_string()))?;
+    }
+    Ok(pg)
+}
+
+fn connect<T>(name: &str, result: syros::Result<T>) -> Option<T> {
+    match result {
+        Ok(backend) => Some(backend),
+        Err(e) => {
+            eprintln!("skipping {} benchmarks: {}", name, e);
+            None
+        }
+    }
+}
+
+fn skip_notice(name: &str, env: &str) {
+    if std::env::var(env).is_err() {
+        eprintln!("skipping {} benchmarks: {} is not set", name, env);
+    }
+}
+
+/// Returns a key prefix unique to this run so repeated runs never collide.
+pub fn run_prefix(kind: &str) -> String {
+    format!("bench:{}:{}", kind, uuid::Uuid::new_v4().simple())
+}
+
+/// Runs `count` futures produced by `make` concurrently and waits for all.
+pub async fn join_all<F, Fut>(count: usize, make: F)
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    futures::future::join_all((0..count).map(make)).await;
+}
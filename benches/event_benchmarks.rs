@@ -1,84 +1,219 @@
 //! Benchmarks for event store operations.
 //!
-//! This module contains performance benchmarks for the Syros event store system.
+//! This module contains performance benchmarks for the Syros event store system,
+//! run against every configured event backend (see `benches/README.md`).
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+mod common;
+
+use common::Backends;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::collections::HashMap;
-use syros::core::event_store::EventStore;
-use syros::core::event_store::{EventRequest, GetEventsRequest};
+use std::sync::atomic::{AtomicU64, Ordering};
+use syros::core::event_store::{
+    Event, EventFilter, EventRequest, EventStore, GetEventsRequest, ReadDirection,
+};
 use tokio::runtime::Runtime;
 
+/// Number of events in the streams read back by the stream workloads.
+const STREAM_LEN: usize = 1_000;
+
+/// Length of the stream whose tail is read by the range-read comparison.
+const LARGE_STREAM_LEN: usize = 100_000;
+/// Number of events read from the end of the large stream.
+const TAIL_LEN: i64 = 10;
+
+/// Event stores to benchmark, labelled by backend.
+fn event_backends(rt: &Runtime) -> Vec<(&'static str, EventStore)> {
+    let backends = Backends::from_env(rt);
+    let mut stores = vec![("memory", EventStore::in_memory())];
+    if let Some(pg) = backends.postgres {
+        stores.push(("postgres", EventStore::new(pg)));
+    }
+    stores
+}
+
+fn event_request(stream_id: &str, n: usize) -> EventRequest {
+    EventRequest {
+        stream_id: stream_id.to_string(),
+        event_type: "benchmark-event".to_string(),
+        data: serde_json::json!({
+            "message": "benchmark data",
+            "sequence": n,
+        }),
+        metadata: Some(HashMap::new()),
+        created_by: None,
+        expected_version: None,
+        event_id: None,
+        correlation_id: None,
+        causation_id: None,
+    }
+}
+
+async fn append_stream(event_store: &EventStore, stream_id: &str, len: usize) {
+    for n in 0..len {
+        event_store
+            .append_event(event_request(stream_id, n))
+            .await
+            .expect("failed to append event");
+    }
+}
+
+async fn read_stream(event_store: &EventStore, stream_id: &str) {
+    let response = event_store
+        .get_events(GetEventsRequest {
+            stream_id: stream_id.to_string(),
+            from_version: None,
+            from_snapshot: false,
+            to_version: None,
+            filter: EventFilter::default(),
+            direction: ReadDirection::Forward,
+            limit: Some(STREAM_LEN as i64),
+        })
+        .await
+        .expect("failed to read stream");
+    black_box(response.events);
+}
+
 fn bench_event_append(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let event_store = EventStore::new();
+    let mut group = c.benchmark_group("events/append");
 
-    c.bench_function("event_append", |b| {
-        b.to_async(&rt).iter(|| async {
-            let request = EventRequest {
-                stream_id: black_box("benchmark-stream".to_string()),
-                event_type: black_box("benchmark-event".to_string()),
-                data: black_box(serde_json::json!({
-                    "message": "benchmark data",
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                })),
-                metadata: black_box(HashMap::new()),
-            };
-
-            let _ = event_store.append_event(request).await;
-        })
-    });
+    for (backend, event_store) in event_backends(&rt) {
+        let stream_id = common::run_prefix("stream");
+        let counter = AtomicU64::new(0);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &event_store,
+            |b, es| {
+                b.to_async(&rt).iter(|| async {
+                    let n = counter.fetch_add(1, Ordering::Relaxed) as usize;
+                    let _ = es
+                        .append_event(black_box(event_request(&stream_id, n)))
+                        .await;
+                })
+            },
+        );
+    }
+
+    group.finish();
 }
 
-fn bench_event_get(c: &mut Criterion) {
+fn bench_event_read_stream(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let event_store = EventStore::new();
+    let mut group = c.benchmark_group("events/read_1k");
+    group.throughput(Throughput::Elements(STREAM_LEN as u64));
 
-    c.bench_function("event_get", |b| {
-        b.to_async(&rt).iter(|| async {
-            let request = GetEventsRequest {
-                stream_id: black_box("benchmark-stream".to_string()),
-                from_version: black_box(Some(1)),
-                limit: black_box(Some(100)),
-            };
+    for (backend, event_store) in event_backends(&rt) {
+        let stream_id = common::run_prefix("stream");
+        rt.block_on(append_stream(&event_store, &stream_id, STREAM_LEN));
 
-            let _ = event_store.get_events(request).await;
-        })
-    });
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &event_store,
+            |b, es| b.to_async(&rt).iter(|| read_stream(es, &stream_id)),
+        );
+    }
+
+    group.finish();
 }
 
-fn bench_event_append_and_get(c: &mut Criterion) {
+/// Mixed workload: fill a fresh stream with 1k events, then read it back.
+fn bench_event_append_and_read_stream(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let event_store = EventStore::new();
+    let mut group = c.benchmark_group("events/append_read_1k");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(STREAM_LEN as u64));
 
-    c.bench_function("event_append_and_get", |b| {
+    for (backend, event_store) in event_backends(&rt) {
+        let prefix = common::run_prefix("stream");
+        let counter = AtomicU64::new(0);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &event_store,
+            |b, es| {
+                b.to_async(&rt).iter(|| async {
+                    let stream_id =
+                        format!("{}:{}", prefix, counter.fetch_add(1, Ordering::Relaxed));
+                    append_stream(es, &stream_id, STREAM_LEN).await;
+                    read_stream(es, &stream_id).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Reads the last events of a 100k-event in-memory stream, comparing the
+/// version-indexed log against cloning the whole stream before filtering.
+fn bench_event_read_tail_of_large_stream(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("events/tail_10_of_100k");
+
+    let event_store = EventStore::in_memory();
+    let stream_id = common::run_prefix("stream");
+    let mut cloned_stream = Vec::with_capacity(LARGE_STREAM_LEN);
+    rt.block_on(async {
+        for n in 0..LARGE_STREAM_LEN {
+            event_store
+                .append_event(event_request(&stream_id, n))
+                .await
+                .expect("failed to append event");
+        }
+        cloned_stream = event_store
+            .get_events(GetEventsRequest {
+                stream_id: stream_id.clone(),
+                from_version: None,
+                from_snapshot: false,
+                to_version: None,
+                filter: EventFilter::default(),
+                direction: ReadDirection::Forward,
+                limit: None,
+            })
+            .await
+            .expect("failed to read stream")
+            .events;
+    });
+    let from_version = LARGE_STREAM_LEN as i64 - TAIL_LEN + 1;
+
+    group.bench_function("clone_then_filter", |b| {
+        b.iter(|| {
+            let events: Vec<Event> = black_box(&cloned_stream)
+                .clone()
+                .into_iter()
+                .filter(|event| event.version >= from_version)
+                .take(TAIL_LEN as usize)
+                .collect();
+            black_box(events)
+        })
+    });
+
+    group.bench_function("memory", |b| {
         b.to_async(&rt).iter(|| async {
-            let append_request = EventRequest {
-                stream_id: black_box("benchmark-stream".to_string()),
-                event_type: black_box("benchmark-event".to_string()),
-                data: black_box(serde_json::json!({
-                    "message": "benchmark data",
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                })),
-                metadata: black_box(HashMap::new()),
-            };
-
-            let _ = event_store.append_event(append_request).await;
-
-            let get_request = GetEventsRequest {
-                stream_id: black_box("benchmark-stream".to_string()),
-                from_version: black_box(Some(1)),
-                limit: black_box(Some(100)),
-            };
-
-            let _ = event_store.get_events(get_request).await;
+            let response = event_store
+                .get_events(GetEventsRequest {
+                    stream_id: stream_id.clone(),
+                    from_version: Some(black_box(from_version)),
+                    from_snapshot: false,
+                    to_version: None,
+                    filter: EventFilter::default(),
+                    direction: ReadDirection::Forward,
+                    limit: Some(TAIL_LEN),
+                })
+                .await
+                .expect("failed to read tail");
+            black_box(response.events)
         })
     });
+
+    group.finish();
 }
 
 criterion_group!(
     event_benches,
     bench_event_append,
-    bench_event_get,
-    bench_event_append_and_get
+    bench_event_read_stream,
+    bench_event_append_and_read_stream,
+    bench_event_read_tail_of_large_stream
 );
 criterion_main!(event_benches);
@@ -1,80 +1,179 @@
 //! Benchmarks for distributed lock operations.
 //!
-//! This module contains performance benchmarks for the Syros distributed lock system.
+//! This module contains performance benchmarks for the Syros distributed lock system,
+//! run against every configured lock backend (see `benches/README.md`).
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+mod common;
+
+use common::Backends;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::time::Duration;
-use syros::core::lock_manager::LockManager;
-use syros::core::lock_manager::{AcquireLockRequest, ReleaseLockRequest};
+use syros::core::lock_manager::{LockManager, LockRequest, ReleaseLockRequest};
+use syros::core::lock_queue::LockPriority;
+use syros::core::lock_table::{MemoryLockTable, DEFAULT_LOCK_SHARDS};
 use tokio::runtime::Runtime;
 
-fn bench_lock_acquire(c: &mut Criterion) {
+/// Concurrent clients in the churn workload.
+const CHURN_CLIENTS: usize = 32;
+
+/// Spawned tasks in the multi-key workload, each working on its own keys.
+const MULTI_KEY_TASKS: usize = 32;
+/// Acquire/release cycles per task in one multi-key sample.
+const MULTI_KEY_OPS_PER_TASK: usize = 64;
+
+/// Lock managers to benchmark, labelled by backend.
+fn lock_backends(rt: &Runtime) -> Vec<(&'static str, LockManager)> {
+    let backends = Backends::from_env(rt);
+    let mut managers = vec![("memory", LockManager::in_memory())];
+    if let Some(redis) = backends.redis {
+        managers.push(("redis", LockManager::new(redis)));
+    }
+    managers
+}
+
+fn lock_request(key: String, owner: &str) -> LockRequest {
+    LockRequest {
+        key,
+        ttl: Duration::from_secs(60),
+        metadata: None,
+        owner: owner.to_string(),
+        wait_timeout: None,
+        priority: LockPriority::Normal,
+        created_by: None,
+        reentrant: false,
+        session_id: None,
+    }
+}
+
+async fn acquire_and_release(lock_manager: &LockManager, key: String, owner: &str) {
+    if let Ok(response) = lock_manager
+        .acquire_lock(black_box(lock_request(key.clone(), owner)))
+        .await
+    {
+        if response.success {
+            let _ = lock_manager
+                .release_lock(ReleaseLockRequest {
+                    key,
+                    lock_id: response.lock_id,
+                    owner: owner.to_string(),
+                })
+                .await;
+        }
+    }
+}
+
+fn bench_lock_acquire_and_release(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let lock_manager = LockManager::new();
-
-    c.bench_function("lock_acquire", |b| {
-        b.to_async(&rt).iter(|| async {
-            let request = AcquireLockRequest {
-                key: black_box("benchmark-key".to_string()),
-                owner: black_box("benchmark-owner".to_string()),
-                ttl: Some(black_box(60)),
-                metadata: Some(black_box("benchmark-metadata".to_string())),
-                wait_timeout: Some(Duration::from_secs(1)),
-            };
-
-            let _ = lock_manager.acquire_lock(request).await;
-        })
-    });
+    let mut group = c.benchmark_group("locks/acquire_release");
+
+    for (backend, lock_manager) in lock_backends(&rt) {
+        let key = format!("{}:key", common::run_prefix("lock"));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &lock_manager,
+            |b, lm| {
+                b.to_async(&rt)
+                    .iter(|| acquire_and_release(lm, key.clone(), "benchmark-owner"))
+            },
+        );
+    }
+
+    group.finish();
 }
 
-fn bench_lock_release(c: &mut Criterion) {
+fn bench_lock_status(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let lock_manager = LockManager::new();
-
-    c.bench_function("lock_release", |b| {
-        b.to_async(&rt).iter(|| async {
-            let request = ReleaseLockRequest {
-                key: black_box("benchmark-key".to_string()),
-                lock_id: black_box("benchmark-lock-id".to_string()),
-                owner: black_box("benchmark-owner".to_string()),
-            };
-
-            let _ = lock_manager.release_lock(request).await;
-        })
-    });
+    let mut group = c.benchmark_group("locks/status");
+
+    for (backend, lock_manager) in lock_backends(&rt) {
+        let key = format!("{}:held", common::run_prefix("lock"));
+        rt.block_on(lock_manager.acquire_lock(lock_request(key.clone(), "benchmark-owner")))
+            .expect("failed to seed lock");
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &lock_manager,
+            |b, lm| {
+                b.to_async(&rt).iter(|| async {
+                    let _ = lm.get_lock_status(black_box(&key)).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
 }
 
-fn bench_lock_acquire_and_release(c: &mut Criterion) {
+/// Mixed workload: many clients acquiring and releasing their own keys at once.
+fn bench_lock_churn(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let lock_manager = LockManager::new();
-
-    c.bench_function("lock_acquire_and_release", |b| {
-        b.to_async(&rt).iter(|| async {
-            let acquire_request = AcquireLockRequest {
-                key: black_box("benchmark-key".to_string()),
-                owner: black_box("benchmark-owner".to_string()),
-                ttl: Some(black_box(60)),
-                metadata: Some(black_box("benchmark-metadata".to_string())),
-                wait_timeout: Some(Duration::from_secs(1)),
-            };
-
-            if let Ok(acquire_response) = lock_manager.acquire_lock(acquire_request).await {
-                let release_request = ReleaseLockRequest {
-                    key: black_box("benchmark-key".to_string()),
-                    lock_id: black_box(acquire_response.lock_id),
-                    owner: black_box("benchmark-owner".to_string()),
-                };
-
-                let _ = lock_manager.release_lock(release_request).await;
-            }
-        })
-    });
+    let mut group = c.benchmark_group("locks/churn");
+    group.throughput(Throughput::Elements(CHURN_CLIENTS as u64));
+
+    for (backend, lock_manager) in lock_backends(&rt) {
+        let prefix = common::run_prefix("lock");
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &lock_manager,
+            |b, lm| {
+                b.to_async(&rt).iter(|| {
+                    common::join_all(CHURN_CLIENTS, |i| {
+                        acquire_and_release(lm, format!("{}:{}", prefix, i), "churn-owner")
+                    })
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Many tasks on a multi-threaded runtime acquiring and releasing disjoint
+/// keys; compares a single-shard table (one global lock) with the sharded default.
+fn bench_lock_multi_key_concurrent(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("locks/multi_key_concurrent");
+    group.throughput(Throughput::Elements(
+        (MULTI_KEY_TASKS * MULTI_KEY_OPS_PER_TASK) as u64,
+    ));
+
+    for shards in [1, DEFAULT_LOCK_SHARDS] {
+        let lock_manager = LockManager::with_lock_table(MemoryLockTable::with_shards(shards));
+        let prefix = common::run_prefix("lock");
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("memory_{}_shards", shards)),
+            &lock_manager,
+            |b, lm| {
+                b.to_async(&rt).iter(|| async {
+                    let tasks: Vec<_> = (0..MULTI_KEY_TASKS)
+                        .map(|task| {
+                            let lm = lm.clone();
+                            let prefix = prefix.clone();
+                            tokio::spawn(async move {
+                                for op in 0..MULTI_KEY_OPS_PER_TASK {
+                                    let key = format!("{}:{}:{}", prefix, task, op % 8);
+                                    acquire_and_release(&lm, key, "multi-key-owner").await;
+                                }
+                            })
+                        })
+                        .collect();
+                    futures::future::join_all(tasks).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
 }
 
 criterion_group!(
     lock_benches,
-    bench_lock_acquire,
-    bench_lock_release,
-    bench_lock_acquire_and_release
+    bench_lock_acquire_and_release,
+    bench_lock_status,
+    bench_lock_churn,
+    bench_lock_multi_key_concurrent
 );
 criterion_main!(lock_benches);
@@ -0,0 +1,126 @@
+//! Benchmarks for RBAC permission checks.
+//!
+//! Permission checks sit on the hot path of every REST and GraphQL request,
+//! so this measures them under concurrency while user roles are updated.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::Arc;
+use syros::auth::{Permission, RBACManager, Role};
+use tokio::sync::Mutex;
+
+/// Spawned tasks checking permissions in one sample.
+const CHECK_TASKS: usize = 32;
+/// Permission checks per task in one sample.
+const CHECKS_PER_TASK: usize = 64;
+/// Users the checks and role updates are spread over.
+const USERS: usize = 16;
+
+/// How the manager is shared between tasks.
+#[derive(Clone)]
+enum Shared {
+    /// Behind one global mutex, as handlers used to share it
+    Mutex(Arc<Mutex<RBACManager>>),
+    /// Shared directly, relying on the manager's own read/write locks
+    Direct(Arc<RBACManager>),
+}
+
+impl Shared {
+    async fn check(&self, user_id: &str) -> bool {
+        let permission = Permission::LockRead;
+        match self {
+            Shared::Mutex(rbac) => {
+                rbac.lock()
+                    .await
+                    .check_permission(user_id, &permission)
+                    .await
+            }
+            Shared::Direct(rbac) => rbac.check_permission(user_id, &permission).await,
+        }
+        .unwrap_or(false)
+    }
+
+    async fn update_roles(&self, user_id: &str, roles: Vec<Role>) {
+        let _ = match self {
+            Shared::Mutex(rbac) => rbac.lock().await.update_user_roles(user_id, roles).await,
+            Shared::Direct(rbac) => rbac.update_user_roles(user_id, roles).await,
+        };
+    }
+}
+
+async fn create_users(rbac: &RBACManager) -> Vec<String> {
+    let mut user_ids = Vec::with_capacity(USERS);
+    for i in 0..USERS {
+        let user = rbac
+            .create_user(
+                format!("bench-user-{}", i),
+                format!("bench-user-{}@example.com", i),
+                vec![Role::Developer],
+            )
+            .await
+            .expect("failed to create user");
+        user_ids.push(user.id);
+    }
+    user_ids
+}
+
+/// Many tasks checking permissions on a multi-threaded runtime while one task
+/// keeps updating roles; compares a global mutex with the manager's own locks.
+fn bench_check_permission_concurrent(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("rbac/check_permission_concurrent");
+    group.throughput(Throughput::Elements((CHECK_TASKS * CHECKS_PER_TASK) as u64));
+
+    let rbac = RBACManager::new();
+    let user_ids = Arc::new(rt.block_on(create_users(&rbac)));
+    let backends = [
+        ("mutex", Shared::Mutex(Arc::new(Mutex::new(rbac.clone())))),
+        ("rwlock", Shared::Direct(Arc::new(rbac))),
+    ];
+
+    for (backend, shared) in backends {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(backend),
+            &shared,
+            |b, shared| {
+                b.to_async(&rt).iter(|| async {
+                    let writer = {
+                        let shared = shared.clone();
+                        let user_ids = user_ids.clone();
+                        tokio::spawn(async move {
+                            for (i, user_id) in user_ids.iter().enumerate() {
+                                let role = if i % 2 == 0 {
+                                    Role::Manager
+                                } else {
+                                    Role::Developer
+                                };
+                                shared.update_roles(user_id, vec![role]).await;
+                            }
+                        })
+                    };
+                    let readers: Vec<_> = (0..CHECK_TASKS)
+                        .map(|task| {
+                            let shared = shared.clone();
+                            let user_ids = user_ids.clone();
+                            tokio::spawn(async move {
+                                for check in 0..CHECKS_PER_TASK {
+                                    let user_id = &user_ids[(task + check) % USERS];
+                                    black_box(shared.check(user_id).await);
+                                }
+                            })
+                        })
+                        .collect();
+                    let _ = writer.await;
+                    futures::future::join_all(readers).await;
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(rbac_benches, bench_check_permission_concurrent);
+criterion_main!(rbac_benches);
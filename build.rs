@@ -1,4 +1,5 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
     volo_build::Builder::protobuf()
         .include_dirs(vec![std::path::PathBuf::from("proto")])
         .filename("proto/syros.proto".into());
@@ -0,0 +1,5 @@
+-- Saga-level deadline and failure reason
+ALTER TABLE sagas ADD COLUMN IF NOT EXISTS deadline_at TIMESTAMPTZ;
+ALTER TABLE sagas ADD COLUMN IF NOT EXISTS failure_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_sagas_deadline_at ON sagas(deadline_at) WHERE deadline_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Principal that created a saga or event stream, for listing and quotas
+CREATE INDEX IF NOT EXISTS idx_sagas_created_by ON sagas((metadata->>'owner')) WHERE metadata ? 'owner';
+CREATE INDEX IF NOT EXISTS idx_events_created_by ON events((metadata->>'created_by')) WHERE version = 1;
@@ -0,0 +1,9 @@
+-- Compact records of archived event streams, whose events were removed
+CREATE TABLE IF NOT EXISTS archived_streams (
+    stream_id VARCHAR(255) PRIMARY KEY,
+    version BIGINT NOT NULL,
+    event_count BIGINT NOT NULL,
+    created_by VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
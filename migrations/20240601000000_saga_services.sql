@@ -0,0 +1,2 @@
+-- Finds the sagas calling a service, e.g. steps @> '[{"service": "payments"}]'
+CREATE INDEX IF NOT EXISTS idx_sagas_steps ON sagas USING GIN (steps jsonb_path_ops);
@@ -0,0 +1,2 @@
+-- Execution state of every step: status, error, timestamps and attempts
+ALTER TABLE sagas ADD COLUMN IF NOT EXISTS step_results JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,9 @@
+-- Versions of the named saga definitions sagas can be started from
+CREATE TABLE IF NOT EXISTS saga_definitions (
+    name VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL,
+    definition JSONB NOT NULL,
+    created_by VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (name, version)
+);
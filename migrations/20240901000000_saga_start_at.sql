@@ -0,0 +1,4 @@
+-- Start time of sagas scheduled to begin executing later
+ALTER TABLE sagas ADD COLUMN IF NOT EXISTS start_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_sagas_scheduled_start_at ON sagas(start_at) WHERE status = 'Scheduled';
@@ -0,0 +1,5 @@
+-- When the latest event of an archived stream was appended; records archived
+-- before it was tracked are backfilled with their archival time
+ALTER TABLE archived_streams ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+UPDATE archived_streams SET updated_at = archived_at WHERE updated_at IS NULL;
+ALTER TABLE archived_streams ALTER COLUMN updated_at SET NOT NULL;
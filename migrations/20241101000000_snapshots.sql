@@ -0,0 +1,8 @@
+-- Latest snapshot of each stream: the state of its aggregate as of a version,
+-- so reads can start after it instead of replaying the whole stream
+CREATE TABLE IF NOT EXISTS snapshots (
+    stream_id VARCHAR(255) PRIMARY KEY,
+    version BIGINT NOT NULL,
+    state JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
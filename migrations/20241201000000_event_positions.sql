@@ -0,0 +1,6 @@
+-- Position of each event in the feed of all streams, taken on insert
+ALTER TABLE events ADD COLUMN IF NOT EXISTS position BIGSERIAL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_events_position ON events (position);
+-- Transaction that inserted the event; the feed holds back events until
+-- every transaction started before theirs has ended
+ALTER TABLE events ADD COLUMN IF NOT EXISTS transaction_id xid8 NOT NULL DEFAULT pg_current_xact_id();
@@ -0,0 +1,9 @@
+-- Projections folding the event feed into a JSON state, saved with the feed
+-- position of the last event processed so restarts resume from it
+CREATE TABLE IF NOT EXISTS projections (
+    name VARCHAR(255) PRIMARY KEY,
+    definition JSONB NOT NULL,
+    state JSONB NOT NULL,
+    checkpoint BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
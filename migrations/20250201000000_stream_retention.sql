@@ -0,0 +1,14 @@
+-- Event streams deleted without removing their events; appends to them are
+-- rejected
+CREATE TABLE IF NOT EXISTS deleted_streams (
+    stream_id VARCHAR(255) PRIMARY KEY,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Which events of a stream are kept; the others are removed by the
+-- event_retention background task, always keeping the latest one
+CREATE TABLE IF NOT EXISTS stream_retention (
+    stream_id VARCHAR(255) PRIMARY KEY,
+    max_events BIGINT,
+    max_age_seconds BIGINT
+);
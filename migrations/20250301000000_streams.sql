@@ -0,0 +1,16 @@
+-- When each active event stream's first and latest events were appended, and
+-- who appended the first, kept as retention removes events; backfilled from
+-- the events still stored
+CREATE TABLE IF NOT EXISTS streams (
+    stream_id VARCHAR(255) PRIMARY KEY,
+    created_by VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+INSERT INTO streams (stream_id, created_by, created_at, updated_at)
+SELECT stream_id, (ARRAY_AGG(metadata->>'created_by' ORDER BY version))[1],
+       MIN(created_at), MAX(created_at)
+FROM events
+GROUP BY stream_id
+ON CONFLICT (stream_id) DO NOTHING;
+CREATE INDEX IF NOT EXISTS idx_streams_created_at ON streams (created_at, stream_id);
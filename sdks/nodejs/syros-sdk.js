@@ -17,6 +17,18 @@ class SyrosClient {
                 ...(apiKey && { 'Authorization': `Bearer ${apiKey}` })
             }
         });
+        this.capabilities = null;
+        this.capabilitiesRequest = this.client.get('/api/v1/capabilities')
+            .then(response => (this.capabilities = response.data))
+            .catch(() => null);
+    }
+
+    /**
+     * Versão, APIs, backends e limites do servidor, consultados na construção
+     * (null se o servidor não os expõe)
+     */
+    async getCapabilities() {
+        return this.capabilities ?? this.capabilitiesRequest;
     }
 
     /**
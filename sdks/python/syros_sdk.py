@@ -94,9 +94,11 @@ class SyrosClient:
         self.endpoint = endpoint.rstrip('/')
         self.api_key = api_key
         self.session: Optional[aiohttp.ClientSession] = None
+        self.capabilities: Optional[Dict[str, Any]] = None
         
     async def __aenter__(self):
         self.session = aiohttp.ClientSession()
+        self.capabilities = await self.get_capabilities()
         return self
         
     async def __aexit__(self, exc_type, exc_val, exc_tb):
@@ -117,6 +119,19 @@ class SyrosClient:
         async with self.session.get(f"{self.endpoint}/health") as response:
             return await response.json()
     
+    async def get_capabilities(self) -> Optional[Dict[str, Any]]:
+        """Consulta versão, APIs, backends e limites do servidor (None se não suportado)"""
+        if not self.session:
+            raise RuntimeError("Cliente não inicializado. Use 'async with SyrosClient()'")
+            
+        async with self.session.get(
+            f"{self.endpoint}/api/v1/capabilities",
+            headers=self._get_headers()
+        ) as response:
+            if response.status != 200:
+                return None
+            return await response.json()
+    
     async def acquire_lock(self, request: LockRequest) -> LockResponse:
         """Adquire um lock distribuído"""
         if not self.session:
@@ -0,0 +1,17 @@
+//! Authorization guards for GraphQL resolvers.
+//!
+//! The GraphQL handler resolves the caller from the `Authorization` or
+//! `X-API-Key` header into a [`Principal`] and attaches it to the
+//! request; resolvers call [`require_permission`] before touching data.
+
+use crate::auth::{Permission, Principal};
+use async_graphql::{Context, Error, Result};
+
+/// Fails the resolver unless the caller holds `permission`.
+pub fn require_permission(ctx: &Context<'_>, permission: Permission) -> Result<()> {
+    match ctx.data_opt::<Principal>() {
+        Some(principal) if principal.has_permission(&permission) => Ok(()),
+        Some(_) => Err(Error::new(format!("Forbidden: {} required", permission))),
+        None => Err(Error::new("Unauthorized")),
+    }
+}
@@ -1,3 +1,4 @@
+pub mod guards;
 pub mod mutations;
 pub mod queries;
 pub mod schema;
@@ -3,9 +3,10 @@
 //! This module defines all GraphQL mutation operations for modifying data
 //! in the Syros distributed coordination service.
 
+use crate::api::graphql::guards::require_permission;
 use crate::api::graphql::types::*;
 use crate::api::rest::ApiState;
-use crate::auth::Role;
+use crate::auth::{Principal, Role};
 use async_graphql::{Context, Object, Result};
 
 /// Root mutation type for GraphQL operations.
@@ -31,21 +32,49 @@ impl MutationRoot {
                     .ttl
                     .map(|ttl| chrono::Utc::now() + chrono::Duration::seconds(ttl as i64)),
                 status: LockStatus::Locked,
+                lock_id: None,
+                fence_token: None,
+                remaining_ttl_seconds: None,
+                created_by: None,
             }),
         })
     }
 
-    async fn release_lock(&self, ctx: &Context<'_>, key: String) -> Result<LockResponse> {
-        Ok(LockResponse {
-            success: true,
-            message: "Lock released successfully".to_string(),
-            lock: Some(Lock {
+    /// Releases the lock `lock_id` on `key` held by `owner`.
+    ///
+    /// Requires the `LockRelease` permission; fails while the key's namespace
+    /// is frozen.
+    async fn release_lock(
+        &self,
+        ctx: &Context<'_>,
+        key: String,
+        lock_id: String,
+        owner: String,
+    ) -> Result<LockResponse> {
+        require_permission(ctx, crate::auth::Permission::LockRelease)?;
+        let state = ctx.data::<ApiState>()?;
+        if let Some(freeze) = state.namespace_freezes.check(&key) {
+            return Err(async_graphql::Error::new(format!(
+                "Namespace {} is frozen until {}",
+                freeze.namespace,
+                freeze.frozen_until.to_rfc3339()
+            )));
+        }
+
+        let response = state
+            .lock_manager
+            .release_lock(crate::core::lock_manager::ReleaseLockRequest {
                 key,
-                owner: "system".to_string(),
-                acquired_at: chrono::Utc::now(),
-                expires_at: None,
-                status: LockStatus::Unlocked,
-            }),
+                lock_id,
+                owner,
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(format!("Failed to release lock: {}", e)))?;
+
+        Ok(LockResponse {
+            success: response.success,
+            message: response.message,
+            lock: None,
         })
     }
 
@@ -62,6 +91,8 @@ impl MutationRoot {
                 status: StepStatus::Pending,
                 compensation: step.compensation,
                 executed_at: None,
+                compensation_attempts: 0,
+                compensated_at: None,
             })
             .collect();
 
@@ -75,6 +106,10 @@ impl MutationRoot {
                 steps,
                 created_at: now,
                 updated_at: now,
+                deadline_at: input
+                    .max_duration_seconds
+                    .map(|seconds| now + chrono::Duration::seconds(seconds.into())),
+                failure_reason: None,
             }),
         })
     }
@@ -92,6 +127,45 @@ impl MutationRoot {
         })
     }
 
+    /// Cancels a running or paused saga and compensates its completed steps.
+    ///
+    /// Requires the `SagaCompensate` permission. Sagas that already finished
+    /// are left as they are and reported with `success: false`.
+    async fn cancel_saga(
+        &self,
+        ctx: &Context<'_>,
+        saga_id: String,
+        reason: Option<String>,
+    ) -> Result<SagaResponse> {
+        require_permission(ctx, crate::auth::Permission::SagaCompensate)?;
+        let state = ctx.data::<ApiState>()?;
+        let reason = reason.unwrap_or_else(|| "cancelled".to_string());
+
+        let outcome = state.saga_orchestrator.cancel_saga(&saga_id, &reason).await;
+        let message = match &outcome {
+            Ok(()) => "Saga cancelled".to_string(),
+            Err(crate::SyrosError::NotFound(_)) => "Saga not found".to_string(),
+            Err(crate::SyrosError::Conflict(message)) => message.clone(),
+            Err(e) => {
+                return Err(async_graphql::Error::new(format!(
+                    "Failed to cancel saga: {}",
+                    e
+                )))
+            }
+        };
+        let saga = state
+            .saga_orchestrator
+            .get_saga_status(&saga_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(format!("Failed to get saga: {}", e)))?;
+
+        Ok(SagaResponse {
+            success: outcome.is_ok(),
+            message,
+            saga: saga.map(Saga::from_saga),
+        })
+    }
+
     async fn compensate_saga(&self, ctx: &Context<'_>, saga_id: String) -> Result<SagaResponse> {
         Ok(SagaResponse {
             success: true,
@@ -100,46 +174,124 @@ impl MutationRoot {
         })
     }
 
+    /// Appends an event to its stream.
+    ///
+    /// Requires the `EventCreate` permission; fails while the stream's
+    /// namespace is frozen. Retrying with the same `eventId` returns the
+    /// event already appended under it.
     async fn append_event(
         &self,
         ctx: &Context<'_>,
         input: AppendEventInput,
     ) -> Result<EventResponse> {
-        let event_id = uuid::Uuid::new_v4().to_string();
-        let now = chrono::Utc::now();
+        require_permission(ctx, crate::auth::Permission::EventCreate)?;
+        let state = ctx.data::<ApiState>()?;
+        if let Some(freeze) = state.namespace_freezes.check(&input.stream_id) {
+            return Err(async_graphql::Error::new(format!(
+                "Namespace {} is frozen until {}",
+                freeze.namespace,
+                freeze.frozen_until.to_rfc3339()
+            )));
+        }
+        let metadata = input.metadata.unwrap_or_else(|| "{}".to_string());
+        state
+            .metadata_policy
+            .check_text(&metadata)
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let data = serde_json::from_str(&input.data)
+            .map_err(|e| async_graphql::Error::new(format!("Invalid data: {}", e)))?;
+        let fields = serde_json::from_str(&metadata)
+            .map_err(|e| async_graphql::Error::new(format!("Invalid metadata: {}", e)))?;
+
+        let response = state
+            .event_store
+            .append_event(crate::core::event_store::EventRequest {
+                stream_id: input.stream_id.clone(),
+                event_type: input.event_type.clone(),
+                data,
+                metadata: Some(fields),
+                created_by: ctx
+                    .data_opt::<Principal>()
+                    .map(|principal| principal.subject.clone()),
+                expected_version: None,
+                event_id: input.event_id,
+                correlation_id: input.correlation_id,
+                causation_id: input.causation_id,
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(format!("Failed to append event: {}", e)))?;
 
         Ok(EventResponse {
-            success: true,
-            message: "Event appended successfully".to_string(),
+            success: response.success,
+            message: response.message,
             event: Some(Event {
-                id: event_id,
+                id: response.event_id,
                 stream_id: input.stream_id,
                 event_type: input.event_type,
                 data: input.data,
-                metadata: input.metadata.unwrap_or_else(|| "{}".to_string()),
-                version: 1,
-                created_at: now,
+                metadata: state.metadata_policy.redact_text(metadata),
+                version: response.version as i32,
+                created_at: chrono::Utc::now(),
             }),
         })
     }
 
+    /// Sets the cache entry `input.key`.
+    ///
+    /// Fails while the key's namespace is frozen.
     async fn set_cache(&self, ctx: &Context<'_>, input: SetCacheInput) -> Result<CacheResponse> {
+        let state = ctx.data::<ApiState>()?;
+        if let Some(freeze) = state.namespace_freezes.check(&input.key) {
+            return Err(async_graphql::Error::new(format!(
+                "Namespace {} is frozen until {}",
+                freeze.namespace,
+                freeze.frozen_until.to_rfc3339()
+            )));
+        }
         let now = chrono::Utc::now();
-        let expires_at = input
-            .ttl
-            .map(|ttl| now + chrono::Duration::seconds(ttl as i64));
 
-        Ok(CacheResponse {
-            success: true,
-            message: "Cache entry set successfully".to_string(),
-            entry: Some(CacheEntry {
-                key: input.key,
-                value: input.value,
-                ttl: input.ttl,
-                created_at: now,
-                expires_at,
+        let value = serde_json::from_str(&input.value)
+            .unwrap_or_else(|_| serde_json::Value::String(input.value.clone()));
+        let request = crate::core::cache_manager::CacheRequest {
+            key: input.key.clone(),
+            value,
+            ttl: input
+                .ttl
+                .map(|ttl| std::time::Duration::from_secs(ttl.max(0) as u64)),
+            tags: vec![],
+            mode: input.mode.unwrap_or_default().into(),
+            created_by: ctx
+                .data_opt::<Principal>()
+                .map(|principal| principal.subject.clone()),
+            cache_negative: false,
+        };
+
+        match state.cache_manager.set(request).await {
+            Ok(response) => Ok(CacheResponse {
+                success: true,
+                message: "Cache entry set successfully".to_string(),
+                entry: Some(CacheEntry {
+                    key: input.key,
+                    value: input.value,
+                    ttl: input.ttl,
+                    created_at: now,
+                    expires_at: response.expires_at,
+                    source: None,
+                    version: response.version,
+                }),
             }),
-        })
+            Err(e @ (crate::SyrosError::Conflict(_) | crate::SyrosError::NotFound(_))) => {
+                Ok(CacheResponse {
+                    success: false,
+                    message: e.to_string(),
+                    entry: None,
+                })
+            }
+            Err(e) => Err(async_graphql::Error::new(format!(
+                "Failed to set cache: {}",
+                e
+            ))),
+        }
     }
 
     async fn delete_cache(&self, ctx: &Context<'_>, key: String) -> Result<CacheResponse> {
@@ -152,19 +304,9 @@ impl MutationRoot {
 
     async fn create_user(&self, ctx: &Context<'_>, input: CreateUserInput) -> Result<UserResponse> {
         let state = ctx.data::<ApiState>()?;
-        let mut rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
-        let roles: Result<Vec<Role>, String> = input
-            .roles
-            .iter()
-            .map(|r| match r.as_str() {
-                "Admin" => Ok(Role::Admin),
-                "Manager" => Ok(Role::Manager),
-                "Developer" => Ok(Role::Developer),
-                "Viewer" => Ok(Role::Viewer),
-                custom => Ok(Role::Custom(custom.to_string())),
-            })
-            .collect();
+        let roles: crate::Result<Vec<Role>> = input.roles.iter().map(|r| r.parse()).collect();
 
         match roles {
             Ok(roles) => match rbac.create_user(input.username, input.email, roles).await {
@@ -175,7 +317,7 @@ impl MutationRoot {
                         id: user.id,
                         username: user.username,
                         email: user.email,
-                        roles: user.roles.iter().map(|r| format!("{:?}", r)).collect(),
+                        roles: user.roles.iter().map(|r| r.to_string()).collect(),
                         is_active: user.is_active,
                         created_at: user.created_at,
                         updated_at: user.updated_at,
@@ -201,19 +343,9 @@ impl MutationRoot {
         input: UpdateUserRolesInput,
     ) -> Result<UserResponse> {
         let state = ctx.data::<ApiState>()?;
-        let mut rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
-        let roles: Result<Vec<Role>, String> = input
-            .roles
-            .iter()
-            .map(|r| match r.as_str() {
-                "Admin" => Ok(Role::Admin),
-                "Manager" => Ok(Role::Manager),
-                "Developer" => Ok(Role::Developer),
-                "Viewer" => Ok(Role::Viewer),
-                custom => Ok(Role::Custom(custom.to_string())),
-            })
-            .collect();
+        let roles: crate::Result<Vec<Role>> = input.roles.iter().map(|r| r.parse()).collect();
 
         match roles {
             Ok(roles) => match rbac.update_user_roles(&input.user_id, roles).await {
@@ -238,7 +370,7 @@ impl MutationRoot {
 
     async fn activate_user(&self, ctx: &Context<'_>, user_id: String) -> Result<UserResponse> {
         let state = ctx.data::<ApiState>()?;
-        let mut rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
         match rbac.activate_user(&user_id).await {
             Ok(_) => Ok(UserResponse {
@@ -256,7 +388,7 @@ impl MutationRoot {
 
     async fn deactivate_user(&self, ctx: &Context<'_>, user_id: String) -> Result<UserResponse> {
         let state = ctx.data::<ApiState>()?;
-        let mut rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
         match rbac.deactivate_user(&user_id).await {
             Ok(_) => Ok(UserResponse {
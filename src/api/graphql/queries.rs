@@ -3,9 +3,22 @@
 //! This module defines all GraphQL query operations for retrieving data
 //! from the Syros distributed coordination service.
 
+use crate::api::graphql::guards::require_permission;
 use crate::api::graphql::types::*;
 use crate::api::rest::ApiState;
-use async_graphql::{Context, Object, Result};
+use crate::core::lock_manager::{LockFilter, LockState};
+use crate::core::saga_orchestrator::SagaFilter;
+use async_graphql::{Context, Error, Object, Result};
+use chrono::{DateTime, Utc};
+
+/// Page size used by the `locks` query when `first` is omitted.
+pub const DEFAULT_LOCKS_PAGE_SIZE: usize = 20;
+/// Upper bound on `first` for the `locks` query.
+pub const MAX_LOCKS_PAGE_SIZE: usize = 100;
+/// Page size used by the `cacheEntries` query when `first` is omitted.
+pub const DEFAULT_CACHE_ENTRIES_PAGE_SIZE: usize = 20;
+/// Upper bound on `first` for the `cacheEntries` query.
+pub const MAX_CACHE_ENTRIES_PAGE_SIZE: usize = 100;
 
 /// Root query type for GraphQL operations.
 ///
@@ -22,26 +35,119 @@ impl QueryRoot {
             acquired_at: chrono::Utc::now(),
             expires_at: None,
             status: LockStatus::Unlocked,
+            lock_id: None,
+            fence_token: None,
+            remaining_ttl_seconds: None,
+            created_by: None,
         })
     }
 
-    async fn locks(&self, ctx: &Context<'_>) -> Result<Vec<Lock>> {
-        Ok(vec![])
+    /// Lists locks sorted by expiry, paginated with `first`/`after`,
+    /// optionally only those in `namespace`.
+    ///
+    /// Requires the `LockRead` permission. Pages hold at most 100 locks.
+    #[allow(clippy::too_many_arguments)]
+    async fn locks(
+        &self,
+        ctx: &Context<'_>,
+        owner_filter: Option<String>,
+        key_prefix: Option<String>,
+        namespace: Option<String>,
+        created_by: Option<String>,
+        #[graphql(default)] include_expired: bool,
+        first: Option<i32>,
+        after: Option<String>,
+        #[graphql(default)] sort: LockSortOrder,
+    ) -> Result<LockConnection> {
+        require_permission(ctx, crate::auth::Permission::LockRead)?;
+        let state = ctx.data::<ApiState>()?;
+
+        let filter = LockFilter {
+            owner: owner_filter,
+            key_prefix,
+            pattern: None,
+            created_by,
+            namespace,
+            include_expired,
+        };
+        let locks = state
+            .lock_manager
+            .list_locks(&filter)
+            .await
+            .map_err(|e| Error::new(format!("Failed to list locks: {}", e)))?;
+
+        paginate_locks(locks, first, after.as_deref(), sort, Utc::now())
     }
 
+    /// A saga with the status of each of its steps; `null` if unknown.
     async fn saga(&self, ctx: &Context<'_>, id: String) -> Result<Option<Saga>> {
-        Ok(Some(Saga {
-            id: id.clone(),
-            name: "test-saga".to_string(),
-            status: SagaStatus::Pending,
-            steps: vec![],
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        }))
+        require_permission(ctx, crate::auth::Permission::SagaRead)?;
+        let state = ctx.data::<ApiState>()?;
+
+        let saga = state
+            .saga_orchestrator
+            .get_saga_status(&id)
+            .await
+            .map_err(|e| Error::new(format!("Failed to get saga: {}", e)))?;
+        Ok(saga.map(Saga::from_saga))
     }
 
-    async fn sagas(&self, ctx: &Context<'_>) -> Result<Vec<Saga>> {
-        Ok(vec![])
+    /// Lists sagas oldest first, optionally only those in `status` or whose
+    /// name starts with `name_prefix`.
+    ///
+    /// Requires the `SagaRead` permission.
+    async fn sagas(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<SagaStatus>,
+        name_prefix: Option<String>,
+        created_after: Option<DateTime<Utc>>,
+        limit: Option<i32>,
+        #[graphql(default)] offset: i32,
+    ) -> Result<Vec<Saga>> {
+        require_permission(ctx, crate::auth::Permission::SagaRead)?;
+        let state = ctx.data::<ApiState>()?;
+
+        let filter = SagaFilter {
+            status: status.map(Into::into),
+            name_prefix,
+            created_after,
+            limit: limit.map(|limit| limit.max(0) as usize),
+            offset: offset.max(0) as usize,
+            ..SagaFilter::default()
+        };
+        let sagas = state
+            .saga_orchestrator
+            .find_sagas(&filter)
+            .await
+            .map_err(|e| Error::new(format!("Failed to list sagas: {}", e)))?;
+        Ok(sagas.into_iter().map(Saga::from_saga).collect())
+    }
+
+    /// Lists event streams in the order they were created, optionally only
+    /// those whose ID starts with `prefix`.
+    ///
+    /// Requires the `EventRead` permission.
+    async fn streams(
+        &self,
+        ctx: &Context<'_>,
+        prefix: Option<String>,
+        limit: Option<i32>,
+        #[graphql(default)] offset: i32,
+    ) -> Result<Vec<Stream>> {
+        require_permission(ctx, crate::auth::Permission::EventRead)?;
+        let state = ctx.data::<ApiState>()?;
+
+        let streams = state
+            .event_store
+            .list_streams(
+                prefix.as_deref(),
+                limit.map(|limit| limit.max(0) as usize),
+                offset.max(0) as usize,
+            )
+            .await
+            .map_err(|e| Error::new(format!("Failed to list streams: {}", e)))?;
+        Ok(streams.into_iter().map(Stream::from_info).collect())
     }
 
     async fn events(&self, ctx: &Context<'_>, stream_id: String) -> Result<Vec<Event>> {
@@ -60,26 +166,135 @@ impl QueryRoot {
         }))
     }
 
+    /// Reads a cache entry through the backend chain; `source` tells which
+    /// layer served it.
     async fn cache_entry(&self, ctx: &Context<'_>, key: String) -> Result<Option<CacheEntry>> {
+        require_permission(ctx, crate::auth::Permission::CacheRead)?;
+        let state = ctx.data::<ApiState>()?;
+
+        let response = state
+            .cache_manager
+            .get(&key)
+            .await
+            .map_err(|e| Error::new(format!("Failed to get cache: {}", e)))?;
+        let Some(value) = response.value.filter(|_| response.found) else {
+            return Ok(None);
+        };
+        // Stale entries are no longer live, so their timestamps are unknown here
+        let entry = state
+            .cache_manager
+            .get_entry(&key)
+            .await
+            .map_err(|e| Error::new(format!("Failed to get cache: {}", e)))?;
+        let now = Utc::now();
+        let expires_at = entry.as_ref().and_then(|entry| entry.expires_at);
+
         Ok(Some(CacheEntry {
-            key: key.clone(),
-            value: "cached-value".to_string(),
-            ttl: Some(3600),
-            created_at: chrono::Utc::now(),
-            expires_at: Some(chrono::Utc::now() + chrono::Duration::hours(1)),
+            key,
+            value: value.to_string(),
+            ttl: expires_at.map(|expires_at| remaining_ttl(expires_at, now)),
+            created_at: entry.map(|entry| entry.created_at).unwrap_or(now),
+            expires_at,
+            source: response.source.map(|source| source.to_string()),
+            version: response.version,
         }))
     }
 
+    /// Lists live cache entries by key, optionally only those under `prefix`
+    /// or carrying `tag`, paginated with `first`/`after`.
+    ///
+    /// Requires the `CacheRead` permission. Pages hold at most 100 entries.
+    async fn cache_entries(
+        &self,
+        ctx: &Context<'_>,
+        prefix: Option<String>,
+        tag: Option<String>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<CacheEntryConnection> {
+        require_permission(ctx, crate::auth::Permission::CacheRead)?;
+        let state = ctx.data::<ApiState>()?;
+
+        let page_size = match first {
+            Some(first) if first < 0 => return Err(Error::new("first must not be negative")),
+            Some(first) => (first as usize).min(MAX_CACHE_ENTRIES_PAGE_SIZE),
+            None => DEFAULT_CACHE_ENTRIES_PAGE_SIZE,
+        };
+        let mut entries = state
+            .cache_manager
+            .list_entries(prefix.as_deref(), tag.as_deref())
+            .await
+            .map_err(|e| Error::new(format!("Failed to list cache entries: {}", e)))?;
+
+        // Entries are sorted by key, which doubles as the cursor
+        let start = match after {
+            Some(after) => entries.partition_point(|entry| entry.key <= after),
+            None => 0,
+        };
+        let total_count = entries.len();
+        let end = (start + page_size).min(total_count);
+        let end_cursor = entries[start..end].last().map(|entry| entry.key.clone());
+        let now = Utc::now();
+        let nodes = entries
+            .drain(start..end)
+            .map(|entry| CacheEntry::from_entry(entry, now))
+            .collect();
+
+        Ok(CacheEntryConnection {
+            nodes,
+            total_count: total_count as i32,
+            has_next_page: end < total_count,
+            end_cursor,
+        })
+    }
+
+    /// Lists the services with instances registered through this process,
+    /// or only `name`, with their discovered instances.
+    ///
+    /// Requires the `AdminSystem` permission. Fails while service discovery
+    /// is disabled.
+    async fn services(&self, ctx: &Context<'_>, name: Option<String>) -> Result<Vec<Service>> {
+        require_permission(ctx, crate::auth::Permission::AdminSystem)?;
+        let state = ctx.data::<ApiState>()?;
+        let discovery = state
+            .service_discovery
+            .as_ref()
+            .ok_or_else(|| Error::new("Service discovery is disabled"))?;
+
+        let names = match name {
+            Some(name) => vec![name],
+            None => discovery
+                .list_all_services()
+                .await
+                .map_err(|e| Error::new(format!("Failed to list services: {}", e)))?,
+        };
+        let mut services = Vec::with_capacity(names.len());
+        for name in names {
+            let instances = discovery
+                .discover_services(&name)
+                .await
+                .map_err(|e| Error::new(format!("Failed to discover {}: {}", name, e)))?;
+            services.push(Service {
+                name,
+                instances: instances
+                    .into_iter()
+                    .map(|info| ServiceInstance::from_info(info, &state.metadata_policy))
+                    .collect(),
+            });
+        }
+        Ok(services)
+    }
+
     async fn user(&self, ctx: &Context<'_>, id: String) -> Result<Option<User>> {
         let state = ctx.data::<ApiState>()?;
-        let rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
         match rbac.get_user(&id).await {
             Ok(Some(user)) => Ok(Some(User {
                 id: user.id.clone(),
                 username: user.username.clone(),
                 email: user.email.clone(),
-                roles: user.roles.iter().map(|r| format!("{:?}", r)).collect(),
+                roles: user.roles.iter().map(|r| r.to_string()).collect(),
                 is_active: user.is_active,
                 created_at: user.created_at,
                 updated_at: user.updated_at,
@@ -91,7 +306,7 @@ impl QueryRoot {
 
     async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
         let state = ctx.data::<ApiState>()?;
-        let rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
         match rbac.get_all_users().await {
             Ok(users) => Ok(users
@@ -100,7 +315,7 @@ impl QueryRoot {
                     id: user.id.clone(),
                     username: user.username.clone(),
                     email: user.email.clone(),
-                    roles: user.roles.iter().map(|r| format!("{:?}", r)).collect(),
+                    roles: user.roles.iter().map(|r| r.to_string()).collect(),
                     is_active: user.is_active,
                     created_at: user.created_at,
                     updated_at: user.updated_at,
@@ -112,23 +327,22 @@ impl QueryRoot {
 
     async fn roles(&self, ctx: &Context<'_>) -> Result<Vec<Role>> {
         let state = ctx.data::<ApiState>()?;
-        let rbac = state.rbac_manager.lock().await;
+        let rbac = &state.rbac_manager;
 
         match rbac.get_all_roles().await {
             Ok(roles) => Ok(roles
                 .into_iter()
                 .map(|role| Role {
-                    name: format!("{:?}", role.name),
+                    name: role.name.to_string(),
                     description: role.description.clone(),
-                    permissions: role
-                        .permissions
-                        .iter()
-                        .map(|p| format!("{:?}", p))
-                        .collect(),
+                    permissions: role.permissions.iter().map(|p| p.to_string()).collect(),
                     is_system: role.is_system,
                 })
                 .collect()),
-            Err(e) => Err(async_graphql::Error::new(format!("Failed to get all roles: {}", e))),
+            Err(e) => Err(async_graphql::Error::new(format!(
+                "Failed to get all roles: {}",
+                e
+            ))),
         }
     }
 
@@ -139,40 +353,11 @@ impl QueryRoot {
         permission: String,
     ) -> Result<PermissionCheckResponse> {
         let state = ctx.data::<ApiState>()?;
-        let rbac = state.rbac_manager.lock().await;
-
-        let perm = match permission.as_str() {
-            "LockCreate" => crate::auth::Permission::LockCreate,
-            "LockRead" => crate::auth::Permission::LockRead,
-            "LockUpdate" => crate::auth::Permission::LockUpdate,
-            "LockDelete" => crate::auth::Permission::LockDelete,
-            "LockAcquire" => crate::auth::Permission::LockAcquire,
-            "LockRelease" => crate::auth::Permission::LockRelease,
-            "SagaCreate" => crate::auth::Permission::SagaCreate,
-            "SagaRead" => crate::auth::Permission::SagaRead,
-            "SagaUpdate" => crate::auth::Permission::SagaUpdate,
-            "SagaDelete" => crate::auth::Permission::SagaDelete,
-            "SagaExecute" => crate::auth::Permission::SagaExecute,
-            "SagaCompensate" => crate::auth::Permission::SagaCompensate,
-            "EventCreate" => crate::auth::Permission::EventCreate,
-            "EventRead" => crate::auth::Permission::EventRead,
-            "EventUpdate" => crate::auth::Permission::EventUpdate,
-            "EventDelete" => crate::auth::Permission::EventDelete,
-            "EventQuery" => crate::auth::Permission::EventQuery,
-            "CacheCreate" => crate::auth::Permission::CacheCreate,
-            "CacheRead" => crate::auth::Permission::CacheRead,
-            "CacheUpdate" => crate::auth::Permission::CacheUpdate,
-            "CacheDelete" => crate::auth::Permission::CacheDelete,
-            "CacheClear" => crate::auth::Permission::CacheClear,
-            "AdminUsers" => crate::auth::Permission::AdminUsers,
-            "AdminRoles" => crate::auth::Permission::AdminRoles,
-            "AdminPermissions" => crate::auth::Permission::AdminPermissions,
-            "AdminSystem" => crate::auth::Permission::AdminSystem,
-            "ApiRest" => crate::auth::Permission::ApiRest,
-            "ApiGrpc" => crate::auth::Permission::ApiGrpc,
-            "ApiWebSocket" => crate::auth::Permission::ApiWebSocket,
-            "ApiGraphQL" => crate::auth::Permission::ApiGraphQL,
-            _ => {
+        let rbac = &state.rbac_manager;
+
+        let perm = match permission.parse::<crate::auth::Permission>() {
+            Ok(perm) => perm,
+            Err(_) => {
                 return Ok(PermissionCheckResponse {
                     has_permission: false,
                     user_id: user_id.clone(),
@@ -206,3 +391,264 @@ impl QueryRoot {
         Ok("1.0.0".to_string())
     }
 }
+
+/// Sorts `locks` by expiry and returns the page following the `after` cursor.
+fn paginate_locks(
+    mut locks: Vec<LockState>,
+    first: Option<i32>,
+    after: Option<&str>,
+    sort: LockSortOrder,
+    now: DateTime<Utc>,
+) -> Result<LockConnection> {
+    let page_size = match first {
+        Some(first) if first < 0 => return Err(Error::new("first must not be negative")),
+        Some(first) => (first as usize).min(MAX_LOCKS_PAGE_SIZE),
+        None => DEFAULT_LOCKS_PAGE_SIZE,
+    };
+
+    locks.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+    if sort == LockSortOrder::ExpiresAtDesc {
+        locks.reverse();
+    }
+
+    let start = match after {
+        Some(cursor) => {
+            let cursor = decode_lock_cursor(cursor)?;
+            locks
+                .iter()
+                .position(|lock| match sort {
+                    LockSortOrder::ExpiresAtAsc => sort_key(lock) > (cursor.0, cursor.1.as_str()),
+                    LockSortOrder::ExpiresAtDesc => sort_key(lock) < (cursor.0, cursor.1.as_str()),
+                })
+                .unwrap_or(locks.len())
+        }
+        None => 0,
+    };
+
+    let total_count = locks.len();
+    let end = (start + page_size).min(total_count);
+    let end_cursor = locks[start..end].last().map(encode_lock_cursor);
+    let nodes = locks
+        .drain(start..end)
+        .map(|lock| Lock::from_state(lock, now))
+        .collect();
+
+    Ok(LockConnection {
+        nodes,
+        total_count: total_count as i32,
+        has_next_page: end < total_count,
+        end_cursor,
+    })
+}
+
+fn sort_key(lock: &LockState) -> (i64, &str) {
+    (lock.expires_at.timestamp_millis(), lock.key.as_str())
+}
+
+fn encode_lock_cursor(lock: &LockState) -> String {
+    format!("{}:{}", lock.expires_at.timestamp_millis(), lock.key)
+}
+
+fn decode_lock_cursor(cursor: &str) -> Result<(i64, String)> {
+    cursor
+        .split_once(':')
+        .and_then(|(millis, key)| Some((millis.parse().ok()?, key.to_string())))
+        .ok_or_else(|| Error::new("Invalid cursor"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::graphql::schema::create_schema;
+    use crate::auth::{Principal, Role};
+
+    fn seed_locks(now: DateTime<Utc>) -> Vec<LockState> {
+        (0..30)
+            .map(|i| LockState {
+                id: format!("lock-{}", i),
+                key: if i % 3 == 0 {
+                    format!("payments:{}", i)
+                } else {
+                    format!("orders:{}", i)
+                },
+                owner: format!("worker-{}", i % 2),
+                acquired_at: now,
+                expires_at: now + chrono::Duration::seconds(60 + (i * 7 % 30)),
+                metadata: None,
+                fence_token: i as u64 + 1,
+                created_by: None,
+                hold_count: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_locks_paginated_by_prefix() {
+        let now = Utc::now();
+        let filter = LockFilter {
+            key_prefix: Some("orders:".to_string()),
+            ..Default::default()
+        };
+        let locks: Vec<LockState> = seed_locks(now)
+            .into_iter()
+            .filter(|lock| filter.matches(lock, now))
+            .collect();
+        assert_eq!(locks.len(), 20);
+
+        let mut seen = Vec::new();
+        let mut after: Option<String> = None;
+        let mut pages = 0;
+        loop {
+            let page = paginate_locks(
+                locks.clone(),
+                Some(8),
+                after.as_deref(),
+                LockSortOrder::ExpiresAtAsc,
+                now,
+            )
+            .unwrap();
+            assert_eq!(page.total_count, 20);
+            assert!(page.nodes.len() <= 8);
+            seen.extend(page.nodes);
+            pages += 1;
+            if !page.has_next_page {
+                break;
+            }
+            after = page.end_cursor;
+        }
+
+        assert_eq!(pages, 3);
+        assert_eq!(seen.len(), 20);
+        assert!(seen.iter().all(|lock| lock.key.starts_with("orders:")));
+        assert!(seen
+            .windows(2)
+            .all(|pair| pair[0].expires_at <= pair[1].expires_at));
+        let mut keys: Vec<&String> = seen.iter().map(|lock| &lock.key).collect();
+        keys.dedup();
+        assert_eq!(keys.len(), 20);
+
+        let first = &seen[0];
+        assert!(first.lock_id.is_some());
+        assert!(first.fence_token.unwrap() > 0);
+        assert!(first.remaining_ttl_seconds.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_locks_page_size_is_bounded() {
+        let now = Utc::now();
+        let page = paginate_locks(
+            seed_locks(now),
+            Some(1000),
+            None,
+            LockSortOrder::ExpiresAtDesc,
+            now,
+        )
+        .unwrap();
+        assert_eq!(page.nodes.len(), 30);
+        assert!(page
+            .nodes
+            .windows(2)
+            .all(|pair| pair[0].expires_at >= pair[1].expires_at));
+
+        assert!(paginate_locks(
+            seed_locks(now),
+            Some(-1),
+            None,
+            LockSortOrder::ExpiresAtAsc,
+            now
+        )
+        .is_err());
+        assert!(paginate_locks(
+            seed_locks(now),
+            None,
+            Some("bogus"),
+            LockSortOrder::ExpiresAtAsc,
+            now
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_cache_ttl_is_clamped_to_an_int() {
+        let now = Utc::now();
+        assert_eq!(remaining_ttl(now + chrono::Duration::seconds(30), now), 30);
+        assert_eq!(remaining_ttl(now - chrono::Duration::seconds(30), now), 0);
+        assert_eq!(
+            remaining_ttl(now + chrono::Duration::days(100 * 365), now),
+            i32::MAX
+        );
+    }
+
+    #[tokio::test]
+    async fn test_locks_query_pages_through_the_schema() {
+        let state = crate::server::build_api_state(
+            crate::config::Config::default(),
+            crate::server::CoreServices::in_memory(),
+        )
+        .unwrap();
+        let lock_manager = &state.lock_manager;
+        for key in ["orders:1", "orders:2", "orders:3", "payments:1"] {
+            let request = crate::core::lock_manager::LockRequest {
+                key: key.to_string(),
+                ttl: std::time::Duration::from_secs(60),
+                metadata: None,
+                owner: "worker".to_string(),
+                wait_timeout: None,
+                priority: Default::default(),
+                created_by: None,
+                reentrant: false,
+                session_id: None,
+            };
+            assert!(lock_manager.acquire_lock(request).await.unwrap().success);
+        }
+        let reader = Principal {
+            subject: "reader".to_string(),
+            permissions: Role::Viewer.get_permissions(),
+        };
+        let schema = create_schema();
+
+        let mut keys = Vec::new();
+        let mut after = String::new();
+        loop {
+            let query = format!(
+                "{{ locks(keyPrefix: \"orders:\", first: 2{}) {{ totalCount hasNextPage endCursor nodes {{ key }} }} }}",
+                after
+            );
+            let request = async_graphql::Request::new(query)
+                .data(state.clone())
+                .data(reader.clone());
+            let response = schema.execute(request).await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            let page = response.data.into_json().unwrap()["locks"].clone();
+            assert_eq!(page["totalCount"], 3);
+            for node in page["nodes"].as_array().unwrap() {
+                keys.push(node["key"].as_str().unwrap().to_string());
+            }
+            if page["hasNextPage"] == false {
+                break;
+            }
+            after = format!(", after: {}", page["endCursor"]);
+        }
+
+        keys.sort();
+        assert_eq!(keys, ["orders:1", "orders:2", "orders:3"]);
+    }
+
+    #[tokio::test]
+    async fn test_locks_query_requires_lock_read() {
+        let schema = create_schema();
+        let query = "{ locks(keyPrefix: \"orders:\") { totalCount } }";
+
+        let anonymous = schema.execute(query).await;
+        assert_eq!(anonymous.errors[0].message, "Unauthorized");
+
+        let outsider = Principal {
+            subject: "outsider".to_string(),
+            permissions: Role::Custom("outsider".to_string()).get_permissions(),
+        };
+        let forbidden = schema
+            .execute(async_graphql::Request::new(query).data(outsider))
+            .await;
+        assert!(forbidden.errors[0].message.starts_with("Forbidden"));
+    }
+}
@@ -5,8 +5,9 @@
 
 use crate::api::graphql::{mutations::MutationRoot, queries::QueryRoot};
 use crate::api::rest::ApiState;
-use async_graphql::{EmptySubscription, Schema};
-use axum::{extract::State, response::Html, Json};
+use crate::auth::Principal;
+use async_graphql::{EmptySubscription, Request, Schema, Variables};
+use axum::{extract::State, http::HeaderMap, response::Html, Json};
 use serde_json::Value;
 
 /// Type alias for the Syros GraphQL schema.
@@ -26,11 +27,13 @@ pub fn create_schema() -> SyrosSchema {
 /// Handles GraphQL requests.
 ///
 /// This handler processes GraphQL queries and mutations, executing them
-/// against the schema and returning the results.
+/// against the schema and returning the results. The API state and the
+/// authenticated caller, if any, are made available to resolvers.
 ///
 /// # Arguments
 ///
 /// * `state` - API state containing service dependencies
+/// * `headers` - Request headers carrying the caller's credentials
 /// * `payload` - GraphQL request payload
 ///
 /// # Returns
@@ -38,6 +41,7 @@ pub fn create_schema() -> SyrosSchema {
 /// Returns a JSON response with the GraphQL result.
 pub async fn graphql_handler(
     State(state): State<ApiState>,
+    headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Json<Value> {
     let schema = create_schema();
@@ -47,7 +51,14 @@ pub async fn graphql_handler(
         .cloned()
         .unwrap_or(serde_json::Value::Null);
 
-    let result = schema.execute(query).await;
+    let mut request = Request::new(query)
+        .variables(Variables::from_json(variables))
+        .data(state.clone());
+    if let Some(principal) = Principal::from_headers(&state, &headers).await {
+        request = request.data(principal);
+    }
+
+    let result = schema.execute(request).await;
     Json(serde_json::to_value(result).unwrap_or(serde_json::Value::Null))
 }
 
@@ -20,6 +20,49 @@ pub struct Lock {
     pub expires_at: Option<DateTime<Utc>>,
     /// Current status of the lock
     pub status: LockStatus,
+    /// Identifier of the current holder's lock, required to release it
+    pub lock_id: Option<String>,
+    /// Fencing token issued when the lock was acquired
+    pub fence_token: Option<u64>,
+    /// Seconds until the lock expires; zero once expired
+    pub remaining_ttl_seconds: Option<i64>,
+    /// Authenticated principal that acquired the lock
+    pub created_by: Option<String>,
+}
+
+impl Lock {
+    /// Builds the GraphQL view of a lock as observed at `now`.
+    pub fn from_state(state: crate::core::lock_manager::LockState, now: DateTime<Utc>) -> Self {
+        let expired = state.is_expired(now);
+        Self {
+            key: state.key,
+            owner: state.owner,
+            acquired_at: state.acquired_at,
+            expires_at: Some(state.expires_at),
+            status: if expired {
+                LockStatus::Expired
+            } else {
+                LockStatus::Locked
+            },
+            lock_id: Some(state.id),
+            fence_token: Some(state.fence_token),
+            remaining_ttl_seconds: Some((state.expires_at - now).num_seconds().max(0)),
+            created_by: state.created_by,
+        }
+    }
+}
+
+/// A page of locks returned by the `locks` query.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct LockConnection {
+    /// Locks in this page
+    pub nodes: Vec<Lock>,
+    /// Number of locks matching the filters across all pages
+    pub total_count: i32,
+    /// Whether another page follows this one
+    pub has_next_page: bool,
+    /// Cursor to pass as `after` to fetch the next page
+    pub end_cursor: Option<String>,
 }
 
 /// Represents a saga orchestration instance.
@@ -37,6 +80,57 @@ pub struct Saga {
     pub created_at: DateTime<Utc>,
     /// Timestamp when the saga was last updated
     pub updated_at: DateTime<Utc>,
+    /// Time by which the saga must finish, if it has a global budget
+    pub deadline_at: Option<DateTime<Utc>>,
+    /// Why the saga stopped before completing, e.g. its cancellation reason
+    pub failure_reason: Option<String>,
+}
+
+impl Saga {
+    /// Builds the GraphQL view of a stored saga.
+    pub fn from_saga(saga: crate::core::saga_orchestrator::Saga) -> Self {
+        let status = match saga.status.as_str() {
+            "Scheduled" => SagaStatus::Scheduled,
+            "Running" => SagaStatus::Running,
+            "Paused" => SagaStatus::Paused,
+            "Completed" => SagaStatus::Completed,
+            "Failed" => SagaStatus::Failed,
+            "Compensating" => SagaStatus::Compensating,
+            "Compensated" => SagaStatus::Compensated,
+            "CompensationFailed" => SagaStatus::CompensationFailed,
+            "Cancelled" => SagaStatus::Cancelled,
+            _ => SagaStatus::Pending,
+        };
+        let steps: Vec<crate::core::saga_orchestrator::SagaStep> =
+            serde_json::from_value(saga.steps).unwrap_or_default();
+        let steps = steps
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let execution = saga.step_results.get(index);
+                SagaStep {
+                    id: step.name.clone(),
+                    name: step.name,
+                    status: execution.map_or(StepStatus::Pending, |e| e.status.into()),
+                    compensation: Some(step.compensation),
+                    executed_at: execution.and_then(|e| e.completed_at),
+                    compensation_attempts: execution.map_or(0, |e| e.compensation_attempts),
+                    compensated_at: execution.and_then(|e| e.compensated_at),
+                }
+            })
+            .collect();
+
+        Self {
+            id: saga.id,
+            name: saga.name,
+            status,
+            steps,
+            created_at: saga.created_at,
+            updated_at: saga.updated_at,
+            deadline_at: saga.deadline_at,
+            failure_reason: saga.failure_reason,
+        }
+    }
 }
 
 /// Represents a single step in a saga.
@@ -52,6 +146,10 @@ pub struct SagaStep {
     pub compensation: Option<String>,
     /// Timestamp when the step was executed (optional)
     pub executed_at: Option<DateTime<Utc>>,
+    /// Attempts made at the step's compensation
+    pub compensation_attempts: u32,
+    /// Timestamp when the step's compensation succeeded or finally failed
+    pub compensated_at: Option<DateTime<Utc>>,
 }
 
 /// Represents an event in the event store.
@@ -73,6 +171,43 @@ pub struct Event {
     pub created_at: DateTime<Utc>,
 }
 
+/// Summary of an event stream.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct Stream {
+    /// Stream identifier
+    pub stream_id: String,
+    /// Version of the latest event
+    pub version: i64,
+    /// Number of events retained
+    pub event_count: i32,
+    /// Principal that appended the stream's first event
+    pub created_by: Option<String>,
+    /// Timestamp when the stream's first event was appended
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the stream's latest event was appended
+    pub last_updated: DateTime<Utc>,
+    /// Timestamp when the stream was archived, if it was
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Timestamp when the stream was deleted, if it was
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Stream {
+    /// Builds the GraphQL view of a stream's summary.
+    pub fn from_info(info: crate::core::event_store::StreamInfo) -> Self {
+        Self {
+            stream_id: info.stream_id,
+            version: info.version,
+            event_count: info.event_count.min(i32::MAX as usize) as i32,
+            created_by: info.created_by,
+            created_at: info.created_at,
+            last_updated: info.updated_at,
+            archived_at: info.archived_at,
+            deleted_at: info.deleted_at,
+        }
+    }
+}
+
 /// Represents a cache entry.
 #[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
 pub struct CacheEntry {
@@ -86,6 +221,98 @@ pub struct CacheEntry {
     pub created_at: DateTime<Utc>,
     /// Timestamp when the entry expires (optional)
     pub expires_at: Option<DateTime<Utc>>,
+    /// Layer that served a read: memory, redis, origin, negative or stale
+    pub source: Option<String>,
+    /// Writes of the key since it was last created, starting at 1
+    pub version: Option<u64>,
+}
+
+impl CacheEntry {
+    /// Builds the GraphQL view of a live cache entry as observed at `now`.
+    pub fn from_entry(entry: crate::core::cache_manager::CacheEntry, now: DateTime<Utc>) -> Self {
+        Self {
+            key: entry.key,
+            value: entry.value.to_string(),
+            ttl: entry
+                .expires_at
+                .map(|expires_at| remaining_ttl(expires_at, now)),
+            created_at: entry.created_at,
+            expires_at: entry.expires_at,
+            source: None,
+            version: Some(entry.version),
+        }
+    }
+}
+
+/// Seconds left until `expires_at`, clamped to the range of a GraphQL `Int`.
+pub fn remaining_ttl(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> i32 {
+    (expires_at - now)
+        .num_seconds()
+        .clamp(0, i64::from(i32::MAX)) as i32
+}
+
+/// A page of cache entries returned by the `cacheEntries` query.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct CacheEntryConnection {
+    /// Entries in this page
+    pub nodes: Vec<CacheEntry>,
+    /// Number of entries matching the filters across all pages
+    pub total_count: i32,
+    /// Whether another page follows this one
+    pub has_next_page: bool,
+    /// Cursor to pass as `after` to fetch the next page
+    pub end_cursor: Option<String>,
+}
+
+/// A service and its discovered instances.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct Service {
+    /// Name of the service
+    pub name: String,
+    /// Instances that are not critical
+    pub instances: Vec<ServiceInstance>,
+}
+
+/// An instance of a service.
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceInstance {
+    /// Instance identifier
+    pub id: String,
+    /// Address the instance listens on
+    pub address: String,
+    /// Port the instance listens on
+    pub port: i32,
+    /// Tags the instance registered with
+    pub tags: Vec<String>,
+    /// Metadata the instance registered with, redacted (JSON string)
+    pub meta: String,
+    /// Last observed health
+    pub health: ServiceHealth,
+    /// Timestamp when the instance's check last ran, if it has
+    pub last_checked: Option<DateTime<Utc>>,
+    /// Why the last check did not pass
+    pub failure_reason: Option<String>,
+}
+
+impl ServiceInstance {
+    /// Builds the GraphQL view of a discovered instance, redacting its
+    /// metadata per `policy`.
+    pub fn from_info(
+        mut info: crate::core::ServiceInfo,
+        policy: &crate::core::MetadataPolicy,
+    ) -> Self {
+        policy.redact(&mut info.meta);
+        Self {
+            id: info.id,
+            address: info.address,
+            port: i32::from(info.port),
+            tags: info.tags,
+            meta: serde_json::json!(info.meta).to_string(),
+            health: info.health.into(),
+            last_checked: info.last_checked,
+            failure_reason: info.failure_reason,
+        }
+    }
 }
 
 /// Represents a user in the system.
@@ -142,19 +369,78 @@ pub enum LockStatus {
     Expired,
 }
 
+/// Sort order for lock listings.
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum LockSortOrder {
+    /// Locks expiring soonest first
+    #[default]
+    ExpiresAtAsc,
+    /// Locks expiring latest first
+    ExpiresAtDesc,
+}
+
+/// Write mode for setting a cache entry.
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum CacheSetMode {
+    /// Insert or overwrite the entry
+    #[default]
+    Upsert,
+    /// Fail if a live entry already exists
+    CreateOnly,
+    /// Fail unless a live entry already exists
+    UpdateOnly,
+}
+
+impl From<CacheSetMode> for crate::core::cache_manager::CacheSetMode {
+    fn from(mode: CacheSetMode) -> Self {
+        match mode {
+            CacheSetMode::Upsert => Self::Upsert,
+            CacheSetMode::CreateOnly => Self::CreateOnly,
+            CacheSetMode::UpdateOnly => Self::UpdateOnly,
+        }
+    }
+}
+
 /// Status of a saga orchestration.
 #[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum SagaStatus {
+    /// Saga is waiting for its scheduled start time
+    Scheduled,
     /// Saga is waiting to start
     Pending,
     /// Saga is currently executing
     Running,
+    /// An operator paused the saga before its next step
+    Paused,
     /// Saga completed successfully
     Completed,
     /// Saga failed during execution
     Failed,
+    /// Saga is rolling back its completed steps
+    Compensating,
     /// Saga was compensated after failure
     Compensated,
+    /// Compensation failed and the saga was escalated
+    CompensationFailed,
+    /// Saga was cancelled on request and its started steps compensated
+    Cancelled,
+}
+
+impl From<SagaStatus> for crate::core::saga_orchestrator::SagaStatus {
+    fn from(status: SagaStatus) -> Self {
+        match status {
+            SagaStatus::Scheduled => Self::Scheduled,
+            SagaStatus::Pending => Self::Pending,
+            SagaStatus::Running => Self::Running,
+            SagaStatus::Paused => Self::Paused,
+            SagaStatus::Completed => Self::Completed,
+            SagaStatus::Failed => Self::Failed,
+            SagaStatus::Compensating => Self::Compensating,
+            SagaStatus::Compensated => Self::Compensated,
+            SagaStatus::CompensationFailed => Self::CompensationFailed,
+            SagaStatus::Cancelled => Self::Cancelled,
+        }
+    }
 }
 
 /// Status of a saga step.
@@ -170,6 +456,47 @@ pub enum StepStatus {
     Failed,
     /// Step was compensated after failure
     Compensated,
+    /// Step compensation failed and needs manual intervention
+    CompensationFailed,
+}
+
+impl From<crate::core::saga_orchestrator::StepStatus> for StepStatus {
+    fn from(status: crate::core::saga_orchestrator::StepStatus) -> Self {
+        use crate::core::saga_orchestrator::StepStatus as Core;
+        match status {
+            Core::Pending => StepStatus::Pending,
+            Core::Running => StepStatus::Running,
+            Core::Completed => StepStatus::Completed,
+            Core::Failed => StepStatus::Failed,
+            Core::Compensated => StepStatus::Compensated,
+            Core::CompensationFailed => StepStatus::CompensationFailed,
+        }
+    }
+}
+
+/// Health of a service instance.
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ServiceHealth {
+    /// Checks pass
+    Passing,
+    /// Checks fail, below the critical threshold
+    Warning,
+    /// Checks keep failing
+    Critical,
+    /// No check has run yet
+    Unknown,
+}
+
+impl From<crate::core::ServiceHealth> for ServiceHealth {
+    fn from(health: crate::core::ServiceHealth) -> Self {
+        use crate::core::ServiceHealth as Core;
+        match health {
+            Core::Passing => ServiceHealth::Passing,
+            Core::Warning => ServiceHealth::Warning,
+            Core::Critical => ServiceHealth::Critical,
+            Core::Unknown => ServiceHealth::Unknown,
+        }
+    }
 }
 
 /// Input for acquiring a distributed lock.
@@ -188,6 +515,8 @@ pub struct StartSagaInput {
     pub name: String,
     /// List of steps in the saga
     pub steps: Vec<SagaStepInput>,
+    /// Global budget for the whole saga in seconds (optional)
+    pub max_duration_seconds: Option<i32>,
 }
 
 /// Input for defining a saga step.
@@ -210,6 +539,13 @@ pub struct AppendEventInput {
     pub data: String,
     /// Event metadata (JSON string, optional)
     pub metadata: Option<String>,
+    /// Client-chosen event ID (UUID); retrying with it returns the event
+    /// already appended under it (optional)
+    pub event_id: Option<String>,
+    /// Correlation ID of the business transaction; generated if unset
+    pub correlation_id: Option<String>,
+    /// ID of the event that caused this one (optional)
+    pub causation_id: Option<String>,
 }
 
 /// Input for setting a cache entry.
@@ -221,6 +557,8 @@ pub struct SetCacheInput {
     pub value: String,
     /// Time-to-live in seconds (optional)
     pub ttl: Option<i32>,
+    /// How an existing entry is treated; defaults to UPSERT
+    pub mode: Option<CacheSetMode>,
 }
 
 /// Input for creating a new user.
@@ -4,10 +4,18 @@
 //! It provides high-performance RPC endpoints for distributed locks, saga orchestration,
 //! event sourcing, and caching operations.
 
-use crate::core::{CacheManager, EventStore, LockManager, SagaOrchestrator};
+use crate::auth::{AuthMiddleware, Principal};
+use crate::config::LockConfig;
+use crate::core::saga_orchestrator::{IdempotentStart, SagaFilter, SagaStatus, OWNER_METADATA_KEY};
+use crate::core::{
+    CacheManager, EventStore, LockManager, MetadataPolicy, NamespaceFreezes, SagaOrchestrator,
+};
 use crate::generated::*;
 use crate::generated::{SyrosService, SyrosServiceServer};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 use volo::FastStr;
 use volo_grpc::{Request, Response, Status};
 
@@ -20,8 +28,16 @@ pub struct SyrosGrpcService {
     saga_orchestrator: Arc<SagaOrchestrator>,
     event_store: Arc<EventStore>,
     cache_manager: Arc<CacheManager>,
+    max_deadline: Duration,
+    auth: Option<AuthMiddleware>,
+    metadata_policy: MetadataPolicy,
+    lock_limits: LockConfig,
+    namespace_freezes: NamespaceFreezes,
 }
 
+/// Default server-side cap on how long a call may run.
+pub const DEFAULT_MAX_DEADLINE: Duration = Duration::from_secs(30);
+
 impl SyrosGrpcService {
     /// Creates a new gRPC service instance.
     ///
@@ -46,14 +62,87 @@ impl SyrosGrpcService {
             saga_orchestrator: Arc::new(saga_orchestrator),
             event_store: Arc::new(event_store),
             cache_manager: Arc::new(cache_manager),
+            max_deadline: DEFAULT_MAX_DEADLINE,
+            auth: None,
+            metadata_policy: MetadataPolicy::default(),
+            lock_limits: LockConfig::default(),
+            namespace_freezes: NamespaceFreezes::new(),
+        }
+    }
+
+    /// Sets the server-side cap on how long a call may run.
+    ///
+    /// Client deadlines (`grpc-timeout`) can shorten but never extend it.
+    pub fn with_max_deadline(mut self, max_deadline: Duration) -> Self {
+        self.max_deadline = max_deadline;
+        self
+    }
+
+    /// Resolves callers from their `authorization` or `x-api-key` metadata,
+    /// so created resources record who created them.
+    pub fn with_auth(mut self, auth: AuthMiddleware) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Sets the limits that lock requests are checked against.
+    pub fn with_lock_limits(mut self, lock_limits: LockConfig) -> Self {
+        self.lock_limits = lock_limits;
+        self
+    }
+
+    /// Sets the limits that metadata in requests is checked against.
+    pub fn with_metadata_policy(mut self, metadata_policy: MetadataPolicy) -> Self {
+        self.metadata_policy = metadata_policy;
+        self
+    }
+
+    /// Refuses writes to the namespaces frozen in `namespace_freezes`.
+    pub fn with_namespace_freezes(mut self, namespace_freezes: NamespaceFreezes) -> Self {
+        self.namespace_freezes = namespace_freezes;
+        self
+    }
+
+    /// Fails with `unavailable` while the namespace of `key` is frozen.
+    #[allow(clippy::result_large_err)] // Returned as-is by the writes, like `within`
+    fn check_writable(&self, key: &str) -> Result<(), Status> {
+        match self.namespace_freezes.check(key) {
+            Some(freeze) => Err(Status::unavailable(format!(
+                "Namespace {} is frozen until {}",
+                freeze.namespace,
+                freeze.frozen_until.to_rfc3339()
+            ))),
+            None => Ok(()),
         }
     }
 
+    /// Subject of the authenticated caller, if any.
+    async fn caller<T>(&self, request: &Request<T>) -> Option<String> {
+        let auth = self.auth.as_ref()?;
+        let metadata = request.metadata();
+        let value = |name: &str| metadata.get(name).and_then(|v| v.to_str().ok());
+        Principal::from_credentials(auth, value("x-api-key"), value("authorization"))
+            .await
+            .map(|principal| principal.subject)
+    }
+
+    /// When a call received now must be done: after the client's deadline,
+    /// capped by the server's, shared by every step of the call.
+    fn deadline<T>(&self, request: &Request<T>) -> Instant {
+        let budget = request
+            .metadata()
+            .get("grpc-timeout")
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_grpc_timeout)
+            .map_or(self.max_deadline, |client| client.min(self.max_deadline));
+        Instant::now() + budget
+    }
+
     /// Starts the gRPC server on the specified address.
     ///
     /// This method creates a new gRPC server instance and starts it on the
     /// provided address. The server will handle all gRPC requests for the
-    /// Syros services.
+    /// Syros services until Ctrl-C is pressed.
     ///
     /// # Arguments
     ///
@@ -65,6 +154,19 @@ impl SyrosGrpcService {
     pub async fn start_grpc_server(
         &self,
         addr: std::net::SocketAddr,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.start_grpc_server_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+    }
+
+    /// Serves on `addr` until `signal` resolves, then stops accepting
+    /// connections and waits for the open ones to finish their calls.
+    pub async fn start_grpc_server_with_shutdown(
+        &self,
+        addr: std::net::SocketAddr,
+        signal: impl std::future::Future<Output = ()>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let svc = SyrosServiceServer::new(self.clone());
 
@@ -73,7 +175,10 @@ impl SyrosGrpcService {
         let address = volo::net::Address::from(addr);
 
         server
-            .run(address)
+            .run_with_shutdown(address, async {
+                signal.await;
+                Ok(())
+            })
             .await
             .map_err(|e| format!("gRPC server error: {}", e))?;
 
@@ -96,6 +201,7 @@ impl SyrosGrpcService {
             ttl_seconds: 60,
             metadata: Some(FastStr::from("test metadata")),
             wait_timeout_seconds: Some(10),
+            priority: LockPriority::Normal,
         };
 
         match self.acquire_lock(Request::new(lock_req)).await {
@@ -108,6 +214,8 @@ impl SyrosGrpcService {
             value: FastStr::from(serde_json::json!({"message": "Hello gRPC!"}).to_string()),
             ttl_seconds: Some(300),
             tags: vec![FastStr::from("test")],
+            mode: CacheSetMode::Upsert,
+            cache_negative: false,
         };
 
         match self.set_cache(Request::new(cache_req)).await {
@@ -122,6 +230,9 @@ impl SyrosGrpcService {
                 serde_json::json!({"action": "test", "timestamp": chrono::Utc::now()}).to_string(),
             ),
             metadata: std::collections::HashMap::new(),
+            expected_version: None,
+            event_id: None,
+            causation_id: None,
         };
 
         match self.append_event(Request::new(event_req)).await {
@@ -145,8 +256,15 @@ impl SyrosGrpcService {
                     factor: Some(2.0),
                 }),
                 payload: Some(FastStr::from("test_payload")),
+                acquired_locks: vec![],
+                cache_keys: vec![],
+                group: None,
+                executor: None,
             }],
             metadata: std::collections::HashMap::new(),
+            max_duration_seconds: None,
+            idempotency_key: None,
+            start_at: None,
         };
 
         match self.start_saga(Request::new(saga_req)).await {
@@ -169,10 +287,162 @@ impl Clone for SyrosGrpcService {
             saga_orchestrator: self.saga_orchestrator.clone(),
             event_store: self.event_store.clone(),
             cache_manager: self.cache_manager.clone(),
+            max_deadline: self.max_deadline,
+            auth: self.auth.clone(),
+            metadata_policy: self.metadata_policy.clone(),
+            lock_limits: self.lock_limits.clone(),
+            namespace_freezes: self.namespace_freezes.clone(),
         }
     }
 }
 
+/// Runs `future` until `deadline`, dropping it and failing with
+/// `DEADLINE_EXCEEDED` once the deadline passes.
+// The `Status` is returned as-is by the service methods, so boxing it here
+// would only move the allocation to every caller.
+#[allow(clippy::result_large_err)]
+async fn within<F: Future>(deadline: Instant, future: F) -> Result<F::Output, Status> {
+    tokio::time::timeout_at(deadline, future)
+        .await
+        .map_err(|_| Status::deadline_exceeded("Call did not complete within its deadline"))
+}
+
+/// Parses a `grpc-timeout` header value such as `250m` or `5S`.
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount * 3600),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
+/// Message of the `ALREADY_EXISTS` status for a lock held by another owner,
+/// e.g. `Lock orders is held until 2025-09-19T15:30:00+00:00, retry after 12s`.
+fn lock_held_message(key: &str, response: &crate::core::lock_manager::LockResponse) -> String {
+    match (
+        response.held_until,
+        response.retry_after(chrono::Utc::now()),
+    ) {
+        (Some(held_until), Some(retry_after)) => format!(
+            "Lock {} is held until {}, retry after {}s",
+            key,
+            held_until.to_rfc3339(),
+            retry_after.as_secs().max(1)
+        ),
+        _ => format!("Lock {} is no longer held, retry now", key),
+    }
+}
+
+/// The cache write described by `req`; fails if its value is not JSON.
+/// A negative write ignores the value.
+#[allow(clippy::result_large_err)] // Returned as-is by the cache writes, like `within`
+fn cache_request(
+    req: &SetCacheRequest,
+    created_by: Option<String>,
+) -> Result<crate::core::cache_manager::CacheRequest, Status> {
+    let value: serde_json::Value = if req.cache_negative {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(&req.value)
+            .map_err(|e| Status::invalid_argument(format!("Invalid JSON: {}", e)))?
+    };
+
+    Ok(crate::core::cache_manager::CacheRequest {
+        key: req.key.to_string(),
+        value,
+        ttl: req.ttl_seconds.map(std::time::Duration::from_secs),
+        tags: req.tags.iter().map(|t| t.to_string()).collect(),
+        mode: match req.mode {
+            CacheSetMode::Upsert => crate::core::cache_manager::CacheSetMode::Upsert,
+            CacheSetMode::CreateOnly => crate::core::cache_manager::CacheSetMode::CreateOnly,
+            CacheSetMode::UpdateOnly => crate::core::cache_manager::CacheSetMode::UpdateOnly,
+        },
+        created_by,
+        cache_negative: req.cache_negative,
+    })
+}
+
+fn cache_state(state: crate::core::cache_manager::CacheState) -> CacheLookupState {
+    match state {
+        crate::core::cache_manager::CacheState::Hit => CacheLookupState::Hit,
+        crate::core::cache_manager::CacheState::Miss => CacheLookupState::Miss,
+        crate::core::cache_manager::CacheState::NegativeHit => CacheLookupState::NegativeHit,
+    }
+}
+
+fn get_cache_message(response: crate::core::cache_manager::CacheResponse) -> GetCacheResponse {
+    let source = response.source.map(|source| FastStr::from(source.as_str()));
+    let state = cache_state(response.state);
+    if response.found {
+        GetCacheResponse {
+            key: FastStr::from(response.key),
+            value: FastStr::from(serde_json::to_string(&response.value).unwrap_or_default()),
+            expires_at: response
+                .expires_at
+                .map(|expires_at| FastStr::from(expires_at.to_rfc3339())),
+            tags: vec![],
+            success: true,
+            message: FastStr::from("Cache retrieved successfully"),
+            source,
+            state,
+        }
+    } else {
+        GetCacheResponse {
+            key: FastStr::from(response.key),
+            value: FastStr::from(""),
+            expires_at: None,
+            tags: vec![],
+            success: false,
+            message: FastStr::from("Cache not found"),
+            source,
+            state,
+        }
+    }
+}
+
+fn set_cache_message(response: crate::core::cache_manager::CacheResponse) -> SetCacheResponse {
+    SetCacheResponse {
+        key: FastStr::from(response.key),
+        value: FastStr::from(serde_json::to_string(&response.value).unwrap_or_default()),
+        expires_at: response
+            .expires_at
+            .map(|expires_at| FastStr::from(expires_at.to_rfc3339())),
+        tags: vec![],
+        success: true,
+        message: FastStr::from("Cache set successfully"),
+        state: cache_state(response.state),
+    }
+}
+
+/// Message of a stored event, with its data and metadata as JSON strings,
+/// sensitive metadata values redacted by `policy` and its timestamp in
+/// Unix seconds.
+fn event_message(mut event: crate::core::event_store::Event, policy: &MetadataPolicy) -> Event {
+    policy.redact(&mut event.metadata);
+    Event {
+        event_id: FastStr::from(event.id),
+        stream_id: FastStr::from(event.stream_id),
+        event_type: FastStr::from(event.event_type),
+        data: FastStr::from(event.data.to_string()),
+        version: event.version.max(0) as u64,
+        timestamp: event.timestamp.timestamp().max(0) as u64,
+        metadata: event
+            .metadata
+            .into_iter()
+            .map(|(key, value)| (FastStr::from(key), FastStr::from(value)))
+            .collect(),
+    }
+}
+
 #[async_trait::async_trait]
 impl SyrosService for SyrosGrpcService {
     /// Acquires a distributed lock.
@@ -186,29 +456,59 @@ impl SyrosService for SyrosGrpcService {
     ///
     /// # Returns
     ///
-    /// Returns a gRPC response with lock information or an error status.
+    /// Returns a gRPC response with lock information, `ALREADY_EXISTS` with
+    /// the holder's expiry and the seconds to retry after if the lock is held,
+    /// `RESOURCE_EXHAUSTED` if the key's namespace holds as many locks as
+    /// allowed, or another error status.
     async fn acquire_lock(
         &self,
         request: Request<LockRequest>,
     ) -> Result<Response<LockResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let created_by = within(deadline, self.caller(&request)).await?;
         let req = request.into_inner();
+        self.check_writable(&req.key)?;
+        if let Some(metadata) = &req.metadata {
+            self.metadata_policy
+                .check_text(metadata)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        }
+        self.lock_limits
+            .check_ttl(req.ttl_seconds)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        self.lock_limits
+            .check_wait_timeout(req.wait_timeout_seconds)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
 
         let lock_request = crate::core::lock_manager::LockRequest {
             key: req.key.to_string(),
             owner: req.owner.to_string(),
             ttl: std::time::Duration::from_secs(req.ttl_seconds),
             metadata: req.metadata.map(|m| m.to_string()),
-            wait_timeout: req
-                .wait_timeout_seconds
-                .map(|s| std::time::Duration::from_secs(s)),
+            wait_timeout: req.wait_timeout_seconds.map(std::time::Duration::from_secs),
+            priority: match req.priority {
+                LockPriority::Normal => crate::core::lock_queue::LockPriority::Normal,
+                LockPriority::Low => crate::core::lock_queue::LockPriority::Low,
+                LockPriority::High => crate::core::lock_queue::LockPriority::High,
+            },
+            created_by,
+            reentrant: false,
+            session_id: None,
         };
 
-        match self.lock_manager.acquire_lock(lock_request).await {
+        match within(deadline, self.lock_manager.acquire_lock(lock_request)).await? {
+            Ok(response) if !response.success => Err(Status::already_exists(lock_held_message(
+                &req.key, &response,
+            ))),
             Ok(response) => Ok(Response::new(LockResponse {
                 lock_id: FastStr::from(response.lock_id),
                 success: response.success,
                 message: FastStr::from(response.message),
+                fence_token: response.fence_token,
             })),
+            Err(crate::SyrosError::NamespaceLimitExceeded(message)) => {
+                Err(Status::resource_exhausted(message))
+            }
             Err(e) => Err(Status::internal(format!("Error acquiring lock: {}", e))),
         }
     }
@@ -217,7 +517,9 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<ReleaseLockRequest>,
     ) -> Result<Response<ReleaseLockResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
+        self.check_writable(&req.key)?;
 
         let release_request = crate::core::lock_manager::ReleaseLockRequest {
             key: req.key.to_string(),
@@ -225,7 +527,7 @@ impl SyrosService for SyrosGrpcService {
             owner: req.owner.to_string(),
         };
 
-        match self.lock_manager.release_lock(release_request).await {
+        match within(deadline, self.lock_manager.release_lock(release_request)).await? {
             Ok(response) => Ok(Response::new(ReleaseLockResponse {
                 success: response.success,
                 message: FastStr::from(response.message),
@@ -238,34 +540,71 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<ExtendLockRequest>,
     ) -> Result<Response<ExtendLockResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
+        self.check_writable(&req.key)?;
+        self.lock_limits
+            .check_ttl(req.ttl_seconds)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
 
-        Ok(Response::new(ExtendLockResponse {
-            success: true,
-            message: FastStr::from(format!(
-                "Lock {} extended by {} seconds",
-                req.lock_id, req.ttl_seconds
-            )),
-        }))
+        let extend_request = crate::core::lock_manager::ExtendLockRequest {
+            key: req.key.to_string(),
+            lock_id: req.lock_id.to_string(),
+            owner: req.owner.to_string(),
+            ttl: std::time::Duration::from_secs(req.ttl_seconds),
+        };
+
+        match within(deadline, self.lock_manager.extend_lock(extend_request)).await? {
+            Ok(response) => Ok(Response::new(ExtendLockResponse {
+                success: response.success,
+                message: FastStr::from(response.message),
+            })),
+            Err(crate::SyrosError::LockError(message)) => Err(Status::invalid_argument(message)),
+            Err(e) => Err(Status::internal(format!("Error extending lock: {}", e))),
+        }
     }
 
     async fn list_locks(
         &self,
         request: Request<ListLocksRequest>,
     ) -> Result<Response<ListLocksResponse>, Status> {
-        let _req = request.into_inner();
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
 
-        Ok(Response::new(ListLocksResponse {
-            locks: vec![],
-            success: true,
-            message: FastStr::from("Lock list retrieved successfully"),
-        }))
+        let filter = crate::core::lock_manager::LockFilter {
+            owner: req.owner.map(|owner| owner.to_string()),
+            pattern: req.pattern.map(|pattern| pattern.to_string()),
+            ..Default::default()
+        };
+
+        match within(deadline, self.lock_manager.list_locks(&filter)).await? {
+            Ok(locks) => Ok(Response::new(ListLocksResponse {
+                locks: locks
+                    .into_iter()
+                    .map(|lock| LockInfo {
+                        key: FastStr::from(lock.key),
+                        lock_id: FastStr::from(lock.id),
+                        owner: FastStr::from(lock.owner),
+                        expires_at: lock.expires_at.timestamp().max(0) as u64,
+                        metadata: lock.metadata.map(|metadata| {
+                            FastStr::from(self.metadata_policy.redact_text(metadata))
+                        }),
+                        fence_token: lock.fence_token,
+                    })
+                    .collect(),
+                success: true,
+                message: FastStr::from("Lock list retrieved successfully"),
+            })),
+            Err(e) => Err(Status::internal(format!("Error listing locks: {}", e))),
+        }
     }
 
     async fn start_saga(
         &self,
         request: Request<SagaRequest>,
     ) -> Result<Response<SagaResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let created_by = within(deadline, self.caller(&request)).await?;
         let req = request.into_inner();
 
         let steps: Result<Vec<crate::core::saga_orchestrator::SagaStep>, String> = req
@@ -279,44 +618,118 @@ impl SyrosService for SyrosGrpcService {
                     compensation: step.compensation.to_string(),
                     timeout: step
                         .timeout_seconds
-                        .map(|s| std::time::Duration::from_secs(s))
+                        .map(std::time::Duration::from_secs)
                         .unwrap_or(std::time::Duration::from_secs(30)),
-                    retry_policy: step.retry_policy.map(|rp| {
-                        crate::core::saga_orchestrator::RetryPolicy {
-                            max_retries: rp.max_retries,
-                            backoff_strategy: match rp.backoff_strategy.as_str() {
-                                "exponential" => {
-                                    crate::core::saga_orchestrator::BackoffStrategy::Exponential
-                                }
-                                "linear" => crate::core::saga_orchestrator::BackoffStrategy::Linear,
-                                _ => crate::core::saga_orchestrator::BackoffStrategy::Fixed,
-                            },
-                            initial_delay: std::time::Duration::from_secs(
-                                rp.initial_delay_seconds.unwrap_or(1),
-                            ),
-                        }
-                    }),
+                    retry_policy: step
+                        .retry_policy
+                        .map(|rp| {
+                            use crate::core::saga_orchestrator::RetryPolicy as StepRetryPolicy;
+                            let policy = StepRetryPolicy {
+                                max_retries: rp.max_retries,
+                                backoff_strategy: rp
+                                    .backoff_strategy
+                                    .parse()
+                                    .map_err(|e| format!("step {}: {}", step.name, e))?,
+                                initial_delay: std::time::Duration::from_secs(
+                                    rp.initial_delay_seconds.unwrap_or(1),
+                                ),
+                            };
+                            if policy.max_retries > StepRetryPolicy::MAX_RETRIES
+                                || policy.initial_delay > StepRetryPolicy::MAX_INITIAL_DELAY
+                            {
+                                return Err(format!(
+                                    "step {}: at most {} retries and {}s of initial delay are allowed",
+                                    step.name,
+                                    StepRetryPolicy::MAX_RETRIES,
+                                    StepRetryPolicy::MAX_INITIAL_DELAY.as_secs()
+                                ));
+                            }
+                            Ok::<_, String>(policy)
+                        })
+                        .transpose()?,
+                    payload: step
+                        .payload
+                        .map(|payload| {
+                            serde_json::from_str(&payload).map_err(|e| {
+                                format!("step {}: payload is not JSON: {}", step.name, e)
+                            })
+                        })
+                        .transpose()?,
+                    acquired_locks: step
+                        .acquired_locks
+                        .into_iter()
+                        .map(|lock| crate::core::saga_orchestrator::StepLock {
+                            key: lock.key.to_string(),
+                            owner: lock.owner.map(|owner| owner.to_string()),
+                        })
+                        .collect(),
+                    cache_keys: step.cache_keys.iter().map(|key| key.to_string()).collect(),
+                    group: step.group.map(|group| group.to_string()),
+                    executor: step.executor.map(|executor| executor.to_string()),
                 })
             })
             .collect();
 
+        let mut metadata: std::collections::HashMap<String, String> = req
+            .metadata
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        self.metadata_policy
+            .check(&metadata)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        metadata.remove(OWNER_METADATA_KEY);
+        if let Some(created_by) = created_by {
+            metadata.insert(OWNER_METADATA_KEY.to_string(), created_by);
+        }
+
+        let start_at = match req.start_at {
+            Some(secs) => Some(
+                i64::try_from(secs)
+                    .ok()
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .ok_or_else(|| Status::invalid_argument("start_at is out of range"))?,
+            ),
+            None => None,
+        };
+
         let saga_request = crate::core::saga_orchestrator::SagaRequest {
             name: req.name.to_string(),
             steps: steps.map_err(|e| Status::invalid_argument(format!("Error in steps: {}", e)))?,
-            metadata: Some(
-                req.metadata
-                    .into_iter()
-                    .map(|(k, v)| (k.to_string(), v.to_string()))
-                    .collect(),
-            ),
+            metadata: Some(metadata),
+            max_duration: req.max_duration_seconds.map(std::time::Duration::from_secs),
+            start_at,
         };
 
-        match self.saga_orchestrator.start_saga(saga_request).await {
-            Ok(response) => Ok(Response::new(SagaResponse {
-                saga_id: FastStr::from(response.saga_id),
-                status: FastStr::from("Started"),
-                message: FastStr::from(response.message),
-            })),
+        let started = match req.idempotency_key {
+            Some(key) => {
+                crate::core::saga_idempotency::check_key(&key)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+                within(
+                    deadline,
+                    self.saga_orchestrator
+                        .start_saga_idempotent(&key, saga_request),
+                )
+                .await?
+            }
+            None => within(deadline, self.saga_orchestrator.start_saga(saga_request))
+                .await?
+                .map(IdempotentStart::Started),
+        };
+        match started {
+            Ok(started) => {
+                let status = match started {
+                    IdempotentStart::Started(_) => "Started",
+                    IdempotentStart::Replayed(_) => "Replayed",
+                };
+                let response = started.into_saga_response();
+                Ok(Response::new(SagaResponse {
+                    saga_id: FastStr::from(response.saga_id),
+                    status: FastStr::from(status),
+                    message: FastStr::from(response.message),
+                }))
+            }
+            Err(crate::SyrosError::Conflict(message)) => Err(Status::already_exists(message)),
             Err(e) => Err(Status::internal(format!("Error starting saga: {}", e))),
         }
     }
@@ -325,38 +738,128 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<GetSagaStatusRequest>,
     ) -> Result<Response<GetSagaStatusResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
 
-        Ok(Response::new(GetSagaStatusResponse {
-            saga_id: req.saga_id,
-            status: FastStr::from("Running"),
-            current_step: 1,
-            step_results: vec![],
-            success: true,
-            message: FastStr::from("Saga status retrieved successfully"),
-        }))
+        match within(
+            deadline,
+            self.saga_orchestrator.get_saga_status(&req.saga_id),
+        )
+        .await?
+        {
+            Ok(Some(saga)) => Ok(Response::new(GetSagaStatusResponse {
+                saga_id: req.saga_id,
+                status: FastStr::from(saga.status),
+                current_step: saga.current_step.unwrap_or(0).max(0) as u32,
+                step_results: saga
+                    .step_results
+                    .into_iter()
+                    .map(|execution| StepResult {
+                        step_name: FastStr::from(execution.step),
+                        status: FastStr::from(execution.status.to_string()),
+                        error: execution.error.map(FastStr::from),
+                        started_at: execution
+                            .started_at
+                            .map_or(0, |at| at.timestamp().max(0) as u64),
+                        completed_at: execution
+                            .completed_at
+                            .map(|at| at.timestamp().max(0) as u64),
+                        compensation_attempts: execution.compensation_attempts,
+                        compensated_at: execution
+                            .compensated_at
+                            .map(|at| at.timestamp().max(0) as u64),
+                    })
+                    .collect(),
+                success: true,
+                message: FastStr::from("Saga status retrieved successfully"),
+            })),
+            Ok(None) => Err(Status::not_found(format!("Saga {} not found", req.saga_id))),
+            Err(e) => Err(Status::internal(format!(
+                "Error getting saga status: {}",
+                e
+            ))),
+        }
     }
 
     async fn cancel_saga(
         &self,
         request: Request<CancelSagaRequest>,
     ) -> Result<Response<CancelSagaResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
+        let reason = if req.reason.is_empty() {
+            "cancelled"
+        } else {
+            req.reason.as_str()
+        };
 
-        Ok(Response::new(CancelSagaResponse {
-            success: true,
-            message: FastStr::from(format!("Saga {} cancelled: {}", req.saga_id, req.reason)),
-        }))
+        match within(
+            deadline,
+            self.saga_orchestrator.cancel_saga(&req.saga_id, reason),
+        )
+        .await?
+        {
+            Ok(()) => Ok(Response::new(CancelSagaResponse {
+                success: true,
+                message: FastStr::from(format!("Saga {} cancelled: {}", req.saga_id, reason)),
+            })),
+            Err(crate::SyrosError::NotFound(message)) => Err(Status::not_found(message)),
+            Err(crate::SyrosError::Conflict(message)) => Err(Status::failed_precondition(message)),
+            Err(e) => Err(Status::internal(format!("Error cancelling saga: {}", e))),
+        }
     }
 
     async fn list_sagas(
         &self,
         request: Request<ListSagasRequest>,
     ) -> Result<Response<ListSagasResponse>, Status> {
-        let _req = request.into_inner();
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
+        let status = match req.status {
+            Some(status) => Some(SagaStatus::from_name(&status).ok_or_else(|| {
+                Status::invalid_argument(format!("Unknown saga status: {}", status))
+            })?),
+            None => None,
+        };
+        let created_after = match req.created_after {
+            Some(seconds) => Some(
+                i64::try_from(seconds)
+                    .ok()
+                    .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+                    .ok_or_else(|| {
+                        Status::invalid_argument(format!("Invalid created_after: {}", seconds))
+                    })?,
+            ),
+            None => None,
+        };
+        let filter = SagaFilter {
+            status,
+            active_only: false,
+            name_prefix: req.name_prefix.map(|prefix| prefix.to_string()),
+            created_after,
+            service: None,
+            owner: req.owner.map(|owner| owner.to_string()),
+            limit: req.limit.map(|limit| limit as usize),
+            offset: req.offset.unwrap_or(0) as usize,
+        };
 
+        let sagas = within(deadline, self.saga_orchestrator.list_sagas(&filter))
+            .await?
+            .map_err(|e| Status::internal(format!("Error listing sagas: {}", e)))?;
         Ok(Response::new(ListSagasResponse {
-            sagas: vec![],
+            sagas: sagas
+                .into_iter()
+                .map(|saga| SagaInfo {
+                    saga_id: FastStr::from(saga.saga_id),
+                    name: FastStr::from(saga.name),
+                    status: FastStr::from(saga.status),
+                    current_step: saga.current_step.unwrap_or(0) as u32,
+                    created_at: saga.created_at.timestamp().max(0) as u64,
+                    completed_at: saga
+                        .completed_at
+                        .map(|completed_at| completed_at.timestamp().max(0) as u64),
+                })
+                .collect(),
             success: true,
             message: FastStr::from("Saga list retrieved successfully"),
         }))
@@ -366,30 +869,57 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<EventRequest>,
     ) -> Result<Response<EventResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let created_by = within(deadline, self.caller(&request)).await?;
+        let correlation_id = request
+            .metadata()
+            .get("x-correlation-id")
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
         let req = request.into_inner();
+        self.check_writable(&req.stream_id)?;
 
         let data: serde_json::Value = serde_json::from_str(&req.data)
             .map_err(|e| Status::invalid_argument(format!("Invalid JSON: {}", e)))?;
+        let metadata = req
+            .metadata
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        self.metadata_policy
+            .check(&metadata)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
 
         let event_request = crate::core::event_store::EventRequest {
             stream_id: req.stream_id.to_string(),
             event_type: req.event_type.to_string(),
             data,
-            metadata: Some(
-                req.metadata
-                    .into_iter()
-                    .map(|(k, v)| (k.to_string(), v.to_string()))
-                    .collect(),
-            ),
+            metadata: Some(metadata),
+            created_by,
+            expected_version: req.expected_version,
+            event_id: req.event_id.map(|event_id| event_id.to_string()),
+            correlation_id,
+            causation_id: req
+                .causation_id
+                .map(|causation_id| causation_id.to_string()),
         };
 
-        match self.event_store.append_event(event_request).await {
+        match within(deadline, self.event_store.append_event(event_request)).await? {
             Ok(response) => Ok(Response::new(EventResponse {
                 event_id: FastStr::from(response.event_id),
-                version: 1,
+                version: response.version.max(0) as u64,
                 success: response.success,
                 message: FastStr::from(response.message),
             })),
+            Err(crate::SyrosError::ApiError(message)) => Err(Status::invalid_argument(message)),
+            Err(crate::SyrosError::Conflict(message)) => Err(Status::failed_precondition(message)),
+            Err(e @ crate::SyrosError::VersionConflict { .. }) => {
+                Err(Status::aborted(e.to_string()))
+            }
+            Err(crate::SyrosError::StreamDeleted(message)) => {
+                Err(Status::failed_precondition(message))
+            }
             Err(e) => Err(Status::internal(format!("Error adding event: {}", e))),
         }
     }
@@ -398,62 +928,94 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<GetEventsRequest>,
     ) -> Result<Response<GetEventsResponse>, Status> {
-        let _req = request.into_inner();
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
+        if let Some(key) = req
+            .metadata
+            .keys()
+            .find(|key| self.metadata_policy.is_redacted(key))
+        {
+            return Err(Status::invalid_argument(format!(
+                "Metadata key {} is redacted and cannot be filtered on",
+                key
+            )));
+        }
+        let events_request = crate::core::event_store::GetEventsRequest {
+            stream_id: req.stream_id.to_string(),
+            from_version: req.from_version.map(|version| version as i64),
+            from_snapshot: false,
+            to_version: req.to_version.map(|version| version as i64),
+            filter: crate::core::event_store::EventFilter {
+                event_types: (!req.event_types.is_empty()).then(|| {
+                    req.event_types
+                        .iter()
+                        .map(|event_type| event_type.to_string())
+                        .collect()
+                }),
+                metadata: req
+                    .metadata
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+            },
+            direction: match req.direction {
+                ReadDirection::Forward => crate::core::event_store::ReadDirection::Forward,
+                ReadDirection::Backward => crate::core::event_store::ReadDirection::Backward,
+            },
+            limit: req.limit.map(i64::from),
+        };
 
-        Ok(Response::new(GetEventsResponse {
-            events: vec![],
-            success: true,
-            message: FastStr::from("Events retrieved successfully"),
-        }))
+        match within(deadline, self.event_store.get_events(events_request)).await? {
+            Ok(response) => Ok(Response::new(GetEventsResponse {
+                events: response
+                    .events
+                    .into_iter()
+                    .map(|event| event_message(event, &self.metadata_policy))
+                    .collect(),
+                success: response.success,
+                message: FastStr::from(response.message),
+            })),
+            Err(e) => Err(Status::internal(format!("Error getting events: {}", e))),
+        }
     }
 
     async fn get_stream_info(
         &self,
         request: Request<GetStreamInfoRequest>,
     ) -> Result<Response<GetStreamInfoResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
 
-        Ok(Response::new(GetStreamInfoResponse {
-            stream_id: req.stream_id,
-            version: 1,
-            event_count: 0,
-            created_at: chrono::Utc::now().timestamp() as u64,
-            last_updated: chrono::Utc::now().timestamp() as u64,
-            success: true,
-            message: FastStr::from("Stream information retrieved successfully"),
-        }))
+        match within(deadline, self.event_store.get_stream_info(&req.stream_id)).await? {
+            Ok(Some(info)) => Ok(Response::new(GetStreamInfoResponse {
+                stream_id: req.stream_id,
+                version: info.version.max(0) as u64,
+                event_count: info.event_count as u64,
+                created_at: info.created_at.timestamp().max(0) as u64,
+                last_updated: info.updated_at.timestamp().max(0) as u64,
+                success: true,
+                message: FastStr::from("Stream information retrieved successfully"),
+            })),
+            Ok(None) => Err(Status::not_found(format!(
+                "Stream {} not found",
+                req.stream_id
+            ))),
+            Err(e) => Err(Status::internal(format!(
+                "Error getting stream information: {}",
+                e
+            ))),
+        }
     }
 
     async fn get_cache(
         &self,
         request: Request<GetCacheRequest>,
     ) -> Result<Response<GetCacheResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
 
-        match self.cache_manager.get(&req.key).await {
-            Ok(response) => {
-                if response.found {
-                    Ok(Response::new(GetCacheResponse {
-                        key: req.key,
-                        value: FastStr::from(
-                            serde_json::to_string(&response.value).unwrap_or_default(),
-                        ),
-                        expires_at: None,
-                        tags: vec![],
-                        success: true,
-                        message: FastStr::from("Cache retrieved successfully"),
-                    }))
-                } else {
-                    Ok(Response::new(GetCacheResponse {
-                        key: req.key,
-                        value: FastStr::from(""),
-                        expires_at: None,
-                        tags: vec![],
-                        success: false,
-                        message: FastStr::from("Cache not found"),
-                    }))
-                }
-            }
+        match within(deadline, self.cache_manager.get(&req.key)).await? {
+            Ok(response) => Ok(Response::new(get_cache_message(response))),
             Err(e) => Err(Status::internal(format!("Error getting cache: {}", e))),
         }
     }
@@ -462,27 +1024,19 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<SetCacheRequest>,
     ) -> Result<Response<SetCacheResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let created_by = within(deadline, self.caller(&request)).await?;
         let req = request.into_inner();
+        self.check_writable(&req.key)?;
 
-        let value: serde_json::Value = serde_json::from_str(&req.value)
-            .map_err(|e| Status::invalid_argument(format!("Invalid JSON: {}", e)))?;
+        let cache_request = cache_request(&req, created_by)?;
 
-        let cache_request = crate::core::cache_manager::CacheRequest {
-            key: req.key.to_string(),
-            value,
-            ttl: req.ttl_seconds.map(|s| std::time::Duration::from_secs(s)),
-            tags: req.tags.into_iter().map(|t| t.to_string()).collect(),
-        };
-
-        match self.cache_manager.set(cache_request).await {
-            Ok(response) => Ok(Response::new(SetCacheResponse {
-                key: req.key,
-                value: FastStr::from(serde_json::to_string(&response.value).unwrap_or_default()),
-                expires_at: None,
-                tags: vec![],
-                success: true,
-                message: FastStr::from("Cache set successfully"),
-            })),
+        match within(deadline, self.cache_manager.set(cache_request)).await? {
+            Ok(response) => Ok(Response::new(set_cache_message(response))),
+            Err(crate::SyrosError::Conflict(message)) => Err(Status::already_exists(message)),
+            Err(crate::SyrosError::NotFound(message)) => Err(Status::not_found(message)),
+            Err(crate::SyrosError::ValueTooLarge(message))
+            | Err(crate::SyrosError::ApiError(message)) => Err(Status::invalid_argument(message)),
             Err(e) => Err(Status::internal(format!("Error setting cache: {}", e))),
         }
     }
@@ -491,24 +1045,211 @@ impl SyrosService for SyrosGrpcService {
         &self,
         request: Request<DeleteCacheRequest>,
     ) -> Result<Response<DeleteCacheResponse>, Status> {
+        let deadline = self.deadline(&request);
         let req = request.into_inner();
+        self.check_writable(&req.key)?;
 
-        Ok(Response::new(DeleteCacheResponse {
-            success: true,
-            message: FastStr::from(format!("Cache {} deleted successfully", req.key)),
-        }))
+        let delete_request = crate::core::cache_manager::DeleteCacheRequest {
+            key: req.key.to_string(),
+        };
+
+        match within(deadline, self.cache_manager.delete(delete_request)).await? {
+            Ok(response) => Ok(Response::new(DeleteCacheResponse {
+                success: response.success,
+                message: FastStr::from(response.message),
+            })),
+            Err(e) => Err(Status::internal(format!("Error deleting cache: {}", e))),
+        }
     }
 
     async fn list_cache(
         &self,
         request: Request<ListCacheRequest>,
     ) -> Result<Response<ListCacheResponse>, Status> {
-        let _req = request.into_inner();
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
+        let tags: Vec<String> = req.tags.iter().map(|tag| tag.to_string()).collect();
+
+        let entries = within(
+            deadline,
+            self.cache_manager.list(
+                req.pattern.as_deref(),
+                &tags,
+                req.limit.map(|limit| limit as usize),
+            ),
+        )
+        .await?
+        .map_err(|e| Status::internal(format!("Error listing cache: {}", e)))?;
 
         Ok(Response::new(ListCacheResponse {
-            items: vec![],
+            items: entries
+                .into_iter()
+                .map(|entry| CacheItem {
+                    key: FastStr::from(entry.key),
+                    value: FastStr::from(serde_json::to_string(&entry.value).unwrap_or_default()),
+                    expires_at: entry
+                        .expires_at
+                        .map(|expires_at| FastStr::from(expires_at.to_rfc3339())),
+                    tags: entry.tags.into_iter().map(FastStr::from).collect(),
+                })
+                .collect(),
             success: true,
             message: FastStr::from("Cache list retrieved successfully"),
         }))
     }
+
+    async fn increment_cache(
+        &self,
+        request: Request<IncrementCacheRequest>,
+    ) -> Result<Response<IncrementCacheResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
+        self.check_writable(&req.key)?;
+
+        let ttl_if_new = req.ttl_seconds.map(std::time::Duration::from_secs);
+        match within(
+            deadline,
+            self.cache_manager
+                .increment(&req.key, req.delta, ttl_if_new),
+        )
+        .await?
+        {
+            Ok(value) => Ok(Response::new(IncrementCacheResponse {
+                key: req.key,
+                value,
+                success: true,
+                message: FastStr::from("Cache incremented successfully"),
+            })),
+            Err(crate::SyrosError::Conflict(message)) => Err(Status::failed_precondition(message)),
+            Err(e) => Err(Status::internal(format!("Error incrementing cache: {}", e))),
+        }
+    }
+
+    async fn get_cache_batch(
+        &self,
+        request: Request<GetCacheBatchRequest>,
+    ) -> Result<Response<GetCacheBatchResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
+        let keys: Vec<String> = req.keys.iter().map(|key| key.to_string()).collect();
+
+        let responses = within(deadline, self.cache_manager.get_many(&keys))
+            .await?
+            .map_err(|e| Status::internal(format!("Error getting cache batch: {}", e)))?;
+
+        Ok(Response::new(GetCacheBatchResponse {
+            items: responses.into_iter().map(get_cache_message).collect(),
+            success: true,
+            message: FastStr::from("Cache batch retrieved successfully"),
+        }))
+    }
+
+    async fn set_cache_batch(
+        &self,
+        request: Request<SetCacheBatchRequest>,
+    ) -> Result<Response<SetCacheBatchResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let created_by = within(deadline, self.caller(&request)).await?;
+        let req = request.into_inner();
+        let mut cache_requests = Vec::with_capacity(req.items.len());
+        for item in &req.items {
+            self.check_writable(&item.key)?;
+            cache_requests.push(cache_request(item, created_by.clone())?);
+        }
+
+        let outcomes = within(deadline, self.cache_manager.set_many(cache_requests))
+            .await?
+            .map_err(|e| Status::internal(format!("Error setting cache batch: {}", e)))?;
+
+        let mut items = Vec::with_capacity(outcomes.len());
+        for (item, outcome) in req.items.into_iter().zip(outcomes) {
+            items.push(match outcome {
+                Ok(response) => set_cache_message(response),
+                Err(crate::SyrosError::Conflict(message))
+                | Err(crate::SyrosError::NotFound(message))
+                | Err(crate::SyrosError::ValueTooLarge(message))
+                | Err(crate::SyrosError::ApiError(message)) => SetCacheResponse {
+                    key: item.key,
+                    value: FastStr::from(""),
+                    expires_at: None,
+                    tags: vec![],
+                    success: false,
+                    message: FastStr::from(message),
+                    state: CacheLookupState::Miss,
+                },
+                Err(e) => {
+                    return Err(Status::internal(format!(
+                        "Error setting cache batch: {}",
+                        e
+                    )))
+                }
+            });
+        }
+        Ok(Response::new(SetCacheBatchResponse {
+            items,
+            success: true,
+            message: FastStr::from("Cache batch set"),
+        }))
+    }
+
+    async fn delete_cache_batch(
+        &self,
+        request: Request<DeleteCacheBatchRequest>,
+    ) -> Result<Response<DeleteCacheBatchResponse>, Status> {
+        let deadline = self.deadline(&request);
+        let req = request.into_inner();
+        for key in &req.keys {
+            self.check_writable(key)?;
+        }
+        let keys: Vec<String> = req.keys.iter().map(|key| key.to_string()).collect();
+
+        let responses = within(deadline, self.cache_manager.delete_many(&keys))
+            .await?
+            .map_err(|e| Status::internal(format!("Error deleting cache batch: {}", e)))?;
+
+        Ok(Response::new(DeleteCacheBatchResponse {
+            items: req
+                .keys
+                .into_iter()
+                .zip(responses)
+                .map(|(key, response)| DeleteCacheBatchItem {
+                    key,
+                    success: response.success,
+                    message: FastStr::from(response.message),
+                })
+                .collect(),
+            success: true,
+            message: FastStr::from("Cache batch deleted"),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grpc_timeout() {
+        assert_eq!(parse_grpc_timeout("250m"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_grpc_timeout("5S"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_grpc_timeout("1H"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_grpc_timeout("100"), None);
+        assert_eq!(parse_grpc_timeout("1234567890m"), None);
+        assert_eq!(parse_grpc_timeout("m"), None);
+    }
+
+    #[tokio::test]
+    async fn test_within_cancels_slow_calls() {
+        let slow = tokio::time::sleep(Duration::from_secs(10));
+        let deadline = Instant::now() + Duration::from_millis(20);
+        let status = within(deadline, slow).await.unwrap_err();
+        assert_eq!(status.code(), volo_grpc::Code::DeadlineExceeded);
+        // Later steps of a call only get what is left of its deadline.
+        let next_step = tokio::time::sleep(Duration::from_millis(5));
+        let status = within(deadline, next_step).await.unwrap_err();
+        assert_eq!(status.code(), volo_grpc::Code::DeadlineExceeded);
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        assert_eq!(within(deadline, async { 7 }).await.unwrap(), 7);
+    }
 }
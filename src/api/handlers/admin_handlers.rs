@@ -0,0 +1,119 @@
+//! Admin status handler for the Syros API.
+//!
+//! This module provides the admin HTTP handlers describing this process:
+//! its version, environment and service discovery identity, and pausing the
+//! sagas that call a service during an incident.
+
+use crate::api::rest::ApiState;
+use crate::config::Environment;
+use crate::core::memory::MemoryUsage;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Response structure for the admin status endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminStatusResponse {
+    /// Syros version
+    pub version: String,
+    /// Deployment environment from the configuration
+    pub environment: Environment,
+    /// Service name this process registers under
+    pub service_name: String,
+    /// Instance ID this process registers under, suffixed when
+    /// `service_discovery.auto_instance_id` is enabled
+    pub instance_id: String,
+    /// Whether the process registers with service discovery
+    pub service_discovery_enabled: bool,
+    /// Number of active sagas with a step calling each service
+    pub active_sagas_by_service: BTreeMap<String, u64>,
+    /// Estimated bytes held in memory by each manager
+    pub memory_bytes: MemoryUsage,
+}
+
+/// Response of the endpoints pausing and resuming the sagas of a service.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceSagasResponse {
+    pub service: String,
+    /// Sagas that were paused or resumed
+    pub saga_ids: Vec<String>,
+}
+
+/// Describes this process.
+pub async fn get_status(State(state): State<ApiState>) -> impl IntoResponse {
+    let active_sagas_by_service = match state
+        .saga_orchestrator
+        .active_saga_counts_by_service()
+        .await
+    {
+        Ok(counts) => counts,
+        Err(e) => {
+            eprintln!("Error counting active sagas: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let memory_bytes = MemoryUsage::estimate(
+        &state.lock_manager,
+        &state.cache_manager,
+        &state.event_store,
+        &state.saga_orchestrator,
+    )
+    .await;
+
+    Json(AdminStatusResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        environment: state.config.environment,
+        service_name: state.config.service_discovery.service_name.clone(),
+        instance_id: state.instance_id.clone(),
+        service_discovery_enabled: state.config.service_discovery.enabled,
+        active_sagas_by_service,
+        memory_bytes,
+    })
+    .into_response()
+}
+
+/// Pauses every active saga whose next step calls the service `name`.
+pub async fn pause_service_sagas(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.saga_orchestrator.pause_sagas_for_service(&name).await {
+        Ok(saga_ids) => Json(ServiceSagasResponse {
+            service: name,
+            saga_ids,
+        })
+        .into_response(),
+        Err(e) => {
+            eprintln!("Error pausing sagas: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Resumes the paused sagas whose next step calls the service `name`.
+pub async fn resume_service_sagas(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state
+        .saga_orchestrator
+        .resume_sagas_for_service(&name)
+        .await
+    {
+        Ok(saga_ids) => Json(ServiceSagasResponse {
+            service: name,
+            saga_ids,
+        })
+        .into_response(),
+        Err(e) => {
+            eprintln!("Error resuming sagas: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::api::rest::ApiState;
-use crate::auth::api_keys::{ApiKeyResponse, ApiKeyStats, CreateApiKeyRequest};
+use crate::auth::api_keys::CreateApiKeyRequest;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 
@@ -1,29 +1,167 @@
 //! Cache handlers for the Syros API.
 //!
 //! This module provides HTTP handlers for distributed caching operations,
-//! including setting, getting, deleting cache entries and managing cache by tags.
+//! including setting, getting, deleting and listing cache entries and managing
+//! cache by tags.
 
+use crate::api::handlers::namespace_handlers::reject_if_frozen;
+use crate::api::rest::{Caller, KeyPath};
+use crate::core::cache_fills::{DEFAULT_FILL_LEASE, DEFAULT_FILL_WAIT};
 use crate::core::cache_manager::{
-    CacheManager, CacheRequest, CacheResponse, DeleteCacheRequest, DeleteCacheResponse,
-    InvalidateByTagRequest, InvalidateByTagResponse,
+    CacheEntry, CacheFetch, CacheManager, CacheRequest, CacheSetMode, DeleteCacheRequest,
+    DeleteCacheResponse, GetOrSetRequest, InvalidateByTagRequest,
 };
+use crate::core::NamespaceFreezes;
+use crate::SyrosError;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Response header naming the layer that served a cache read.
+pub const CACHE_SOURCE_HEADER: &str = "X-Cache-Source";
 
 /// Request structure for setting a cache entry.
 #[derive(Debug, Deserialize)]
 pub struct SetCacheRequest {
-    /// Value to cache (JSON)
+    /// Value to cache (JSON); ignored by negative writes
+    #[serde(default)]
+    pub value: serde_json::Value,
+    /// Time-to-live in seconds (optional)
+    pub ttl_seconds: Option<u64>,
+    /// Tags for cache invalidation (optional)
+    pub tags: Option<Vec<String>>,
+    /// "upsert" (default), "create_only" or "update_only"
+    pub mode: Option<CacheSetMode>,
+    /// Records the key as known to be absent (default: false)
+    #[serde(default)]
+    pub cache_negative: bool,
+}
+
+/// Request structure for a compare-and-swap of a cache entry.
+#[derive(Debug, Deserialize)]
+pub struct SwapCacheRequest {
+    /// New value (JSON)
+    pub value: serde_json::Value,
+    /// Time-to-live in seconds of the new value (optional)
+    pub ttl_seconds: Option<u64>,
+}
+
+/// Request structure for fetching a cache entry, computing it if missing.
+#[derive(Debug, Default, Deserialize)]
+pub struct FetchCacheRequest {
+    /// Seconds the caller told to compute the value has to store it
+    /// (default: 10)
+    pub lease_seconds: Option<u64>,
+    /// Milliseconds to wait for a value another caller is computing
+    /// (default: 5000)
+    pub wait_timeout_ms: Option<u64>,
+}
+
+/// Request structure for incrementing a numeric cache entry.
+#[derive(Debug, Deserialize)]
+pub struct IncrementCacheRequest {
+    /// Amount to add, negative to decrement (default: 1)
+    pub delta: Option<i64>,
+    /// Time-to-live in seconds of an entry the increment creates (optional)
+    pub ttl_seconds: Option<u64>,
+}
+
+/// Response structure for an increment.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncrementCacheResponse {
+    pub key: String,
+    /// Value after the increment
+    pub value: i64,
+}
+
+/// Request structure for touching a cache entry.
+#[derive(Debug, Default, Deserialize)]
+pub struct TouchCacheRequest {
+    /// New time-to-live in seconds; the entry never expires when omitted
+    pub ttl_seconds: Option<u64>,
+}
+
+/// Response structure for the time left on a cache entry.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheTtlResponse {
+    pub key: String,
+    /// Milliseconds left before the entry expires; absent if it never does
+    pub ttl_ms: Option<u64>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Request structure for adding or removing tags of a cache entry.
+#[derive(Debug, Deserialize)]
+pub struct CacheTagsRequest {
+    /// Tags to add or remove
+    pub tags: Vec<String>,
+}
+
+/// Response structure for the tags of a cache entry.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheTagsResponse {
+    pub key: String,
+    pub tags: Vec<String>,
+}
+
+/// Response structure for the keys carrying a tag.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagKeysResponse {
+    pub tag: String,
+    /// Keys of the live entries carrying the tag, ordered
+    pub keys: Vec<String>,
+}
+
+/// Request structure for reading or deleting several cache entries.
+#[derive(Debug, Deserialize)]
+pub struct CacheKeysRequest {
+    /// Keys to read or delete
+    pub keys: Vec<String>,
+}
+
+/// One entry of a multi-set.
+#[derive(Debug, Deserialize)]
+pub struct SetCacheBatchEntry {
+    /// Cache key to set
+    pub key: String,
+    /// Value to cache (JSON); ignored by negative writes
+    #[serde(default)]
     pub value: serde_json::Value,
     /// Time-to-live in seconds (optional)
     pub ttl_seconds: Option<u64>,
     /// Tags for cache invalidation (optional)
     pub tags: Option<Vec<String>>,
+    /// "upsert" (default), "create_only" or "update_only"
+    pub mode: Option<CacheSetMode>,
+    /// Records the key as known to be absent (default: false)
+    #[serde(default)]
+    pub cache_negative: bool,
+}
+
+/// Request structure for setting several cache entries.
+#[derive(Debug, Deserialize)]
+pub struct SetCacheBatchRequest {
+    pub entries: Vec<SetCacheBatchEntry>,
+}
+
+/// Outcome of one write of a multi-set.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetCacheBatchResult {
+    pub success: bool,
+    pub message: String,
+    /// Version of the entry written; absent if the write failed
+    pub version: Option<u64>,
+}
+
+/// Response structure of a bulk cache operation, keyed by cache key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheBatchResponse<T> {
+    pub results: BTreeMap<String, T>,
 }
 
 /// Request structure for invalidating cache by tag.
@@ -33,6 +171,17 @@ pub struct InvalidateByTagRequestPayload {
     pub tag: String,
 }
 
+/// Query parameters for listing cache entries.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListCacheQuery {
+    /// Only keys matching this pattern, where `*` matches any characters
+    pub pattern: Option<String>,
+    /// Only entries carrying all of these comma-separated tags
+    pub tag: Option<String>,
+    /// Most entries to return
+    pub limit: Option<usize>,
+}
+
 /// Response structure for cache statistics.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CacheStatsResponse {
@@ -42,12 +191,18 @@ pub struct CacheStatsResponse {
     pub expired_entries: usize,
     /// Number of active entries
     pub active_entries: usize,
+    /// Number of active entries carrying each tag
+    pub entries_by_tag: BTreeMap<String, usize>,
+    /// Approximate bytes of the active values, as serialized JSON
+    pub value_bytes: u64,
 }
 
 /// Retrieves a cache entry by its key.
 ///
 /// This handler fetches a cached value using the provided key.
 /// Returns the cached value if found and not expired, otherwise returns not found.
+/// The `X-Cache-Source` header tells whether the value came from memory, a
+/// backend layer, a remembered absence or a stale entry.
 ///
 /// # Arguments
 ///
@@ -59,10 +214,15 @@ pub struct CacheStatsResponse {
 /// Returns a JSON response with the cached value or an error status.
 pub async fn get_cache(
     State(cache_manager): State<CacheManager>,
-    Path(key): Path<String>,
+    KeyPath(key): KeyPath,
 ) -> impl IntoResponse {
     match cache_manager.get(&key).await {
-        Ok(response) => Json(response).into_response(),
+        Ok(response) => match response.source {
+            Some(source) => {
+                ([(CACHE_SOURCE_HEADER, source.as_str())], Json(response)).into_response()
+            }
+            None => Json(response).into_response(),
+        },
         Err(e) => {
             eprintln!("Error getting cache: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -75,29 +235,78 @@ pub async fn get_cache(
 /// This handler stores a value in the cache with optional TTL and tags.
 /// The value will be automatically expired if TTL is specified.
 ///
+/// Conditional writes are selected with the `mode` field or with the
+/// `If-None-Match: *` (create only) and `If-Match` (update only) headers.
+/// A failed `mode` condition returns `409 Conflict` or `404 Not Found`;
+/// a failed header precondition returns `412 Precondition Failed`.
+///
 /// # Arguments
 ///
 /// * `cache_manager` - Cache manager instance
 /// * `key` - Cache key to set
+/// * `headers` - Request headers carrying optional preconditions
 /// * `request` - Cache value and configuration
 ///
 /// # Returns
 ///
-/// Returns a JSON response indicating success or failure.
+/// Returns a JSON response indicating success or failure, or `413 Payload
+/// Too Large` if the value exceeds `cache.max_value_bytes`.
 pub async fn set_cache(
     State(cache_manager): State<CacheManager>,
-    Path(key): Path<String>,
+    State(freezes): State<NamespaceFreezes>,
+    Caller(created_by): Caller,
+    KeyPath(key): KeyPath,
+    headers: HeaderMap,
     Json(request): Json<SetCacheRequest>,
 ) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
+    let header_mode = if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|v| v.as_bytes() == b"*")
+    {
+        Some(CacheSetMode::CreateOnly)
+    } else if headers.contains_key(header::IF_MATCH) {
+        Some(CacheSetMode::UpdateOnly)
+    } else {
+        None
+    };
+
+    let mode = match (header_mode, request.mode) {
+        (Some(header_mode), Some(body_mode)) if header_mode != body_mode => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Conditional headers contradict the requested mode",
+            )
+                .into_response();
+        }
+        (header_mode, body_mode) => header_mode.or(body_mode).unwrap_or_default(),
+    };
+
     let cache_request = CacheRequest {
         key,
         value: request.value,
         ttl: request.ttl_seconds.map(std::time::Duration::from_secs),
         tags: request.tags.unwrap_or_default(),
+        mode,
+        created_by,
+        cache_negative: request.cache_negative,
     };
 
     match cache_manager.set(cache_request).await {
         Ok(response) => Json(response).into_response(),
+        Err(SyrosError::Conflict(message)) | Err(SyrosError::NotFound(message))
+            if header_mode.is_some() =>
+        {
+            (StatusCode::PRECONDITION_FAILED, message).into_response()
+        }
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(SyrosError::ValueTooLarge(message)) => {
+            (StatusCode::PAYLOAD_TOO_LARGE, message).into_response()
+        }
+        Err(SyrosError::ApiError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
         Err(e) => {
             eprintln!("Error setting cache: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -105,6 +314,169 @@ pub async fn set_cache(
     }
 }
 
+/// Replaces a cache entry only if it is still at the version the caller read.
+///
+/// The `If-Match` header carries the version, as returned by reads, bare or
+/// quoted. The entry keeps its tags.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to replace
+/// * `headers` - Request headers carrying the expected version
+/// * `request` - New value and TTL
+///
+/// # Returns
+///
+/// Returns the entry with its new version, `428 Precondition Required`
+/// without an `If-Match` version, or `412 Precondition Failed` if the entry
+/// is missing or at another version.
+pub async fn swap_cache(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    KeyPath(key): KeyPath,
+    headers: HeaderMap,
+    Json(request): Json<SwapCacheRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
+    let Some(if_match) = headers.get(header::IF_MATCH) else {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            "If-Match must carry the expected version",
+        )
+            .into_response();
+    };
+    let Some(expected_version) = if_match
+        .to_str()
+        .ok()
+        .and_then(|version| version.trim().trim_matches('"').parse::<u64>().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "If-Match must be a version number").into_response();
+    };
+    let ttl = request.ttl_seconds.map(std::time::Duration::from_secs);
+
+    match cache_manager
+        .compare_and_swap(&key, expected_version, request.value, ttl)
+        .await
+    {
+        Ok(response) => Json(response).into_response(),
+        Err(SyrosError::Conflict(message)) => {
+            (StatusCode::PRECONDITION_FAILED, message).into_response()
+        }
+        Err(SyrosError::ValueTooLarge(message)) => {
+            (StatusCode::PAYLOAD_TOO_LARGE, message).into_response()
+        }
+        Err(SyrosError::ApiError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error swapping cache: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Fetches a cache entry, electing a single caller to compute it if missing.
+///
+/// The first caller to miss is answered with `"status": "leader"` and should
+/// compute the value and store it with a regular write before
+/// `lease_expires_at`. Callers missing meanwhile wait up to the wait timeout
+/// for that value, which they receive as `"status": "hit"`.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to fetch
+/// * `request` - Lease duration and wait timeout
+///
+/// # Returns
+///
+/// Returns the cached value or the leader's lease, `202 Accepted` with a
+/// `Retry-After` header if another caller is still computing the value, or
+/// `400` for a lease over an hour or a wait over a minute.
+pub async fn fetch_cache(
+    State(cache_manager): State<CacheManager>,
+    Path(key): Path<String>,
+    request: Option<Json<FetchCacheRequest>>,
+) -> impl IntoResponse {
+    let Json(request) = request.unwrap_or_default();
+    let fetch_request = GetOrSetRequest {
+        key,
+        lease: request
+            .lease_seconds
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(DEFAULT_FILL_LEASE),
+        wait_timeout: request
+            .wait_timeout_ms
+            .map(std::time::Duration::from_millis)
+            .unwrap_or(DEFAULT_FILL_WAIT),
+    };
+
+    match cache_manager.get_or_set(fetch_request).await {
+        Ok(fetch) => {
+            let CacheFetch::Pending {
+                lease_expires_at, ..
+            } = &fetch
+            else {
+                return Json(fetch).into_response();
+            };
+            let retry_after = (*lease_expires_at - chrono::Utc::now())
+                .num_seconds()
+                .max(1);
+            let mut pending = (StatusCode::ACCEPTED, Json(fetch)).into_response();
+            pending
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            pending
+        }
+        Err(SyrosError::ApiError(msg)) => (StatusCode::BAD_REQUEST, msg).into_response(),
+        Err(e) => {
+            eprintln!("Error fetching cache: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Atomically adds to the integer stored under a key.
+///
+/// A missing key starts from zero and expires after `ttl_seconds`; an
+/// existing entry keeps its expiry.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to increment
+/// * `request` - Amount to add and TTL of a new entry
+///
+/// # Returns
+///
+/// Returns the value after the increment, or `409 Conflict` if the entry
+/// does not hold an integer or the sum overflows.
+pub async fn increment_cache(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(key): Path<String>,
+    Json(request): Json<IncrementCacheRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
+    let ttl_if_new = request.ttl_seconds.map(std::time::Duration::from_secs);
+
+    match cache_manager
+        .increment(&key, request.delta.unwrap_or(1), ttl_if_new)
+        .await
+    {
+        Ok(value) => Json(IncrementCacheResponse { key, value }).into_response(),
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(SyrosError::ApiError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error incrementing cache: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 /// Deletes a cache entry by its key.
 ///
 /// This handler removes a cached value using the provided key.
@@ -119,8 +491,12 @@ pub async fn set_cache(
 /// Returns a JSON response indicating success or failure.
 pub async fn delete_cache(
     State(cache_manager): State<CacheManager>,
-    Path(key): Path<String>,
+    State(freezes): State<NamespaceFreezes>,
+    KeyPath(key): KeyPath,
 ) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
     let delete_request = DeleteCacheRequest { key };
 
     match cache_manager.delete(delete_request).await {
@@ -132,22 +508,287 @@ pub async fn delete_cache(
     }
 }
 
+/// Reports how long a cache entry has left.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to inspect
+///
+/// # Returns
+///
+/// Returns the milliseconds left and the expiry, both absent for an entry
+/// that never expires, or `404 Not Found` if the key is missing or expired.
+pub async fn get_cache_ttl(
+    State(cache_manager): State<CacheManager>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    match cache_manager.ttl(&key).await {
+        Ok(ttl) => Json(CacheTtlResponse {
+            expires_at: ttl.map(|ttl| {
+                chrono::Utc::now() + chrono::Duration::from_std(ttl).unwrap_or_default()
+            }),
+            ttl_ms: ttl.map(|ttl| ttl.as_millis() as u64),
+            key,
+        })
+        .into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(e) => {
+            eprintln!("Error getting cache TTL: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Resets the expiry of a cache entry without rewriting its value.
+///
+/// The entry expires `ttl_seconds` from now, or never if it is omitted, and
+/// keeps its value, tags and version.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to touch
+/// * `request` - New TTL (optional body)
+///
+/// # Returns
+///
+/// Returns the entry with its new expiry, or `404 Not Found` if the key is
+/// missing or expired.
+pub async fn touch_cache(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(key): Path<String>,
+    request: Option<Json<TouchCacheRequest>>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
+    let Json(request) = request.unwrap_or_default();
+    let ttl = request.ttl_seconds.map(std::time::Duration::from_secs);
+
+    match cache_manager.touch(&key, ttl).await {
+        Ok(response) => Json(response).into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(SyrosError::ApiError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error touching cache: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Retrieves several cache entries at once.
+///
+/// Keys the cache does not hold fall through the backend chain as they do
+/// for a single read.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `request` - Keys to retrieve
+///
+/// # Returns
+///
+/// Returns a JSON map from each key to its cache response.
+pub async fn get_cache_batch(
+    State(cache_manager): State<CacheManager>,
+    Json(request): Json<CacheKeysRequest>,
+) -> impl IntoResponse {
+    match cache_manager.get_many(&request.keys).await {
+        Ok(responses) => Json(CacheBatchResponse {
+            results: responses
+                .into_iter()
+                .map(|response| (response.key.clone(), response))
+                .collect(),
+        })
+        .into_response(),
+        Err(e) => {
+            eprintln!("Error getting cache batch: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Sets several cache entries at once.
+///
+/// Each entry is written on its own terms: one failing its `mode` or the
+/// size limit does not keep the others from being written.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `request` - Entries to set
+///
+/// # Returns
+///
+/// Returns a JSON map from each key to the outcome of its write, or `503`
+/// if any key's namespace is frozen, in which case nothing is written.
+pub async fn set_cache_batch(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    Caller(created_by): Caller,
+    Json(request): Json<SetCacheBatchRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = request
+        .entries
+        .iter()
+        .find_map(|entry| reject_if_frozen(&freezes, &entry.key))
+    {
+        return frozen;
+    }
+    let keys: Vec<String> = request
+        .entries
+        .iter()
+        .map(|entry| entry.key.clone())
+        .collect();
+    let cache_requests = request
+        .entries
+        .into_iter()
+        .map(|entry| CacheRequest {
+            key: entry.key,
+            value: entry.value,
+            ttl: entry.ttl_seconds.map(std::time::Duration::from_secs),
+            tags: entry.tags.unwrap_or_default(),
+            mode: entry.mode.unwrap_or_default(),
+            created_by: created_by.clone(),
+            cache_negative: entry.cache_negative,
+        })
+        .collect();
+
+    let outcomes = match cache_manager.set_many(cache_requests).await {
+        Ok(outcomes) => outcomes,
+        Err(e) => {
+            eprintln!("Error setting cache batch: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let mut results = BTreeMap::new();
+    for (key, outcome) in keys.into_iter().zip(outcomes) {
+        let result = match outcome {
+            Ok(response) => SetCacheBatchResult {
+                success: true,
+                message: response.message,
+                version: response.version,
+            },
+            Err(SyrosError::Conflict(message))
+            | Err(SyrosError::NotFound(message))
+            | Err(SyrosError::ValueTooLarge(message))
+            | Err(SyrosError::ApiError(message)) => SetCacheBatchResult {
+                success: false,
+                message,
+                version: None,
+            },
+            Err(e) => {
+                eprintln!("Error setting cache batch: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        results.insert(key, result);
+    }
+    Json(CacheBatchResponse { results }).into_response()
+}
+
+/// Deletes several cache entries at once.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `request` - Keys to delete
+///
+/// # Returns
+///
+/// Returns a JSON map from each key to whether it was deleted, or `503` if
+/// any key's namespace is frozen, in which case nothing is deleted.
+pub async fn delete_cache_batch(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    Json(request): Json<CacheKeysRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = request
+        .keys
+        .iter()
+        .find_map(|key| reject_if_frozen(&freezes, key))
+    {
+        return frozen;
+    }
+    match cache_manager.delete_many(&request.keys).await {
+        Ok(responses) => Json(CacheBatchResponse {
+            results: request
+                .keys
+                .into_iter()
+                .zip(responses)
+                .collect::<BTreeMap<String, DeleteCacheResponse>>(),
+        })
+        .into_response(),
+        Err(e) => {
+            eprintln!("Error deleting cache batch: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists live cache entries, ordered by key.
+///
+/// # Returns
+///
+/// Returns a JSON array of the entries whose key matches `pattern` and that
+/// carry every tag in `tag`.
+pub async fn list_cache(
+    State(cache_manager): State<CacheManager>,
+    Query(query): Query<ListCacheQuery>,
+) -> impl IntoResponse {
+    let tags: Vec<String> = query
+        .tag
+        .as_deref()
+        .into_iter()
+        .flat_map(|tags| tags.split(','))
+        .map(str::to_string)
+        .collect();
+    match cache_manager
+        .list(query.pattern.as_deref(), &tags, query.limit)
+        .await
+    {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => {
+            eprintln!("Error listing cache: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 /// Invalidates all cache entries with the specified tag.
 ///
 /// This handler removes all cached values that have the specified tag.
+/// Nothing is invalidated while any of them is in a frozen namespace.
 ///
 /// # Arguments
 ///
 /// * `cache_manager` - Cache manager instance
+/// * `freezes` - Namespace freezes the entries' keys are checked against
 /// * `tag` - Tag to invalidate
 ///
 /// # Returns
 ///
-/// Returns a JSON response with the number of invalidated entries.
+/// Returns a JSON response with the number of invalidated entries, or `503`
+/// with the freeze of the first frozen key.
 pub async fn invalidate_by_tag(
     State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
     Path(tag): Path<String>,
 ) -> impl IntoResponse {
+    if !freezes.list().is_empty() {
+        let keys = match cache_manager.keys_by_tag(&tag).await {
+            Ok(keys) => keys,
+            Err(e) => {
+                eprintln!("Error listing cache keys by tag: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        if let Some(frozen) = keys.iter().find_map(|key| reject_if_frozen(&freezes, key)) {
+            return frozen;
+        }
+    }
     let invalidate_request = InvalidateByTagRequest { tag };
 
     match cache_manager.invalidate_by_tag(invalidate_request).await {
@@ -159,10 +800,106 @@ pub async fn invalidate_by_tag(
     }
 }
 
+/// Lists the keys of the live entries carrying a tag.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `tag` - Tag to look up
+///
+/// # Returns
+///
+/// Returns the tag and its keys, ordered; none if no entry carries it.
+pub async fn list_tag_keys(
+    State(cache_manager): State<CacheManager>,
+    Path(tag): Path<String>,
+) -> impl IntoResponse {
+    match cache_manager.keys_by_tag(&tag).await {
+        Ok(keys) => Json(TagKeysResponse { tag, keys }).into_response(),
+        Err(e) => {
+            eprintln!("Error listing cache keys by tag: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Adds tags to a cache entry without rewriting its value.
+///
+/// Tags the entry already carries are left as they are; its value, expiry
+/// and version are kept.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to tag
+/// * `request` - Tags to add
+///
+/// # Returns
+///
+/// Returns the entry's tags, or `404 Not Found` if the key is missing or
+/// expired.
+pub async fn add_cache_tags(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(key): Path<String>,
+    Json(request): Json<CacheTagsRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
+
+    tags_response(cache_manager.add_tags(&key, &request.tags).await)
+}
+
+/// Removes tags from a cache entry without rewriting its value.
+///
+/// Tags the entry does not carry are ignored; its value, expiry and version
+/// are kept.
+///
+/// # Arguments
+///
+/// * `cache_manager` - Cache manager instance
+/// * `key` - Cache key to untag
+/// * `request` - Tags to remove
+///
+/// # Returns
+///
+/// Returns the entry's remaining tags, or `404 Not Found` if the key is
+/// missing or expired.
+pub async fn remove_cache_tags(
+    State(cache_manager): State<CacheManager>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(key): Path<String>,
+    Json(request): Json<CacheTagsRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &key) {
+        return frozen;
+    }
+
+    tags_response(cache_manager.remove_tags(&key, &request.tags).await)
+}
+
+/// Response to an update of an entry's tags.
+fn tags_response(retagged: crate::Result<CacheEntry>) -> Response {
+    match retagged {
+        Ok(entry) => Json(CacheTagsResponse {
+            key: entry.key,
+            tags: entry.tags,
+        })
+        .into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(e) => {
+            eprintln!("Error updating cache tags: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 /// Retrieves cache statistics and metrics.
 ///
 /// This handler returns information about the cache including total entries,
-/// expired entries, and active entries.
+/// expired entries, active entries, the active entries per tag and the
+/// approximate bytes of their values.
 ///
 /// # Arguments
 ///
@@ -171,14 +908,14 @@ pub async fn invalidate_by_tag(
 /// # Returns
 ///
 /// Returns a JSON response with cache statistics.
-pub async fn get_cache_stats(
-    State(cache_manager): State<CacheManager>,
-) -> impl IntoResponse {
+pub async fn get_cache_stats(State(cache_manager): State<CacheManager>) -> impl IntoResponse {
     match cache_manager.get_stats().await {
         Ok(stats) => Json(CacheStatsResponse {
             total_entries: stats.total_entries,
             expired_entries: stats.expired_entries,
             active_entries: stats.active_entries,
+            entries_by_tag: stats.entries_by_tag,
+            value_bytes: stats.value_bytes,
         })
         .into_response(),
         Err(e) => {
@@ -0,0 +1,94 @@
+//! Capability handlers for the Syros API.
+//!
+//! This module provides the HTTP handler describing what this server
+//! supports, so clients can adapt to its version, APIs, storage and limits.
+
+use crate::api::rest::ApiState;
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+/// API versions served under `/api`.
+pub const API_VERSIONS: &[&str] = &["v1"];
+
+/// What this server supports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesResponse {
+    pub version: String,
+    pub api_versions: Vec<String>,
+    /// APIs and optional subsystems that are both compiled in and enabled
+    pub features: Vec<String>,
+    pub backends: BackendCapabilities,
+    pub limits: CapabilityLimits,
+}
+
+/// Storage behind each manager.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendCapabilities {
+    pub locks: String,
+    pub sagas: String,
+    pub events: String,
+    pub cache: String,
+}
+
+/// Configured limits clients should stay within.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityLimits {
+    pub max_lock_ttl_seconds: u64,
+    /// Largest page of the GraphQL `locks` query, if GraphQL is served
+    pub max_locks_page_size: Option<usize>,
+    pub max_metadata_keys: usize,
+    pub max_metadata_key_bytes: usize,
+    pub max_metadata_value_bytes: usize,
+    pub max_step_result_bytes: usize,
+    /// Largest WebSocket message, if WebSocket is served
+    pub max_websocket_message_bytes: Option<usize>,
+}
+
+/// Describes the version, APIs, storage backends and limits of this server.
+pub async fn get_capabilities(State(state): State<ApiState>) -> impl IntoResponse {
+    Json(capabilities(&state))
+}
+
+fn capabilities(state: &ApiState) -> CapabilitiesResponse {
+    let config = &state.config;
+    #[cfg(feature = "graphql")]
+    let max_locks_page_size = config
+        .apis
+        .graphql
+        .then_some(crate::api::graphql::queries::MAX_LOCKS_PAGE_SIZE);
+    #[cfg(not(feature = "graphql"))]
+    let max_locks_page_size = None;
+    let websocket = cfg!(feature = "websocket") && config.apis.websocket;
+    let features = [
+        ("rest", true),
+        ("grpc", cfg!(feature = "grpc") && config.apis.grpc),
+        ("graphql", max_locks_page_size.is_some()),
+        ("websocket", websocket),
+        ("metrics", cfg!(feature = "metrics")),
+    ];
+
+    CapabilitiesResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        api_versions: API_VERSIONS.iter().map(|v| v.to_string()).collect(),
+        features: features
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        backends: BackendCapabilities {
+            locks: state.lock_manager.backend_name().to_string(),
+            sagas: state.saga_orchestrator.backend_name().to_string(),
+            events: state.event_store.backend_name().to_string(),
+            cache: state.cache_manager.backend_name().to_string(),
+        },
+        limits: CapabilityLimits {
+            max_lock_ttl_seconds: config.locks.max_ttl_seconds,
+            max_locks_page_size,
+            max_metadata_keys: config.metadata.max_keys,
+            max_metadata_key_bytes: config.metadata.max_key_bytes,
+            max_metadata_value_bytes: config.metadata.max_value_bytes,
+            max_step_result_bytes: config.sagas.max_step_result_bytes,
+            max_websocket_message_bytes: websocket.then_some(config.websocket.max_message_bytes),
+        },
+    }
+}
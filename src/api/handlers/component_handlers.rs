@@ -0,0 +1,20 @@
+//! Component handlers for the Syros API.
+//!
+//! This module provides the admin HTTP handlers listing the background
+//! components of this process with their schedules and activity, and the
+//! tasks it has spawned.
+
+use crate::api::rest::ApiState;
+use crate::core::task_tracker::TaskInventory;
+use crate::core::TaskTracker;
+use axum::{extract::State, response::IntoResponse, Json};
+
+/// Lists the registered components, ordered by name.
+pub async fn list_components(State(state): State<ApiState>) -> impl IntoResponse {
+    Json(state.components.list())
+}
+
+/// Lists the live and spawned tasks of this process by name.
+pub async fn list_tasks(State(tasks): State<TaskTracker>) -> Json<TaskInventory> {
+    Json(tasks.inventory())
+}
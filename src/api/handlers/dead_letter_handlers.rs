@@ -0,0 +1,107 @@
+//! Dead-letter handlers for the Syros API.
+//!
+//! This module provides the HTTP handlers for sagas escalated after they
+//! failed or their compensation failed: listing unresolved entries,
+//! requeueing their sagas and acknowledging them.
+
+use crate::api::handlers::saga_handlers::{self, RetrySagaRequest};
+use crate::api::rest::{ApiState, Caller};
+use crate::core::saga_dead_letter::DeadLetterEntry;
+use crate::core::MetadataPolicy;
+use crate::SyrosError;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+
+/// Request structure for resolving a dead-letter entry.
+#[derive(Debug, Default, Deserialize)]
+pub struct ResolveDeadLetterRequest {
+    /// Operator acknowledging the entry
+    pub resolved_by: Option<String>,
+    /// Free-form note on how the saga was handled
+    pub note: Option<String>,
+}
+
+/// Lists dead-lettered sagas that have not been resolved yet.
+pub async fn list_dead_letters(State(state): State<ApiState>) -> impl IntoResponse {
+    let mut entries = state.dead_letters.list_unresolved().await;
+    for entry in &mut entries {
+        redact_saga_metadata(&state.metadata_policy, entry);
+    }
+    Json(entries)
+}
+
+/// Acknowledges a dead-lettered saga without executing it again.
+///
+/// # Arguments
+///
+/// * `state` - API state containing the dead-letter queue
+/// * `caller` - Recorded as the operator unless the request names one
+/// * `saga_id` - Saga whose entry is resolved
+/// * `request` - Optional operator and resolution note
+///
+/// # Returns
+///
+/// Returns the resolved entry, `404` for an unknown saga, or `409` if the
+/// entry was already resolved.
+pub async fn resolve_dead_letter(
+    State(state): State<ApiState>,
+    Caller(caller): Caller,
+    Path(saga_id): Path<String>,
+    request: Option<Json<ResolveDeadLetterRequest>>,
+) -> impl IntoResponse {
+    let Json(request) = request.unwrap_or_default();
+
+    match state
+        .dead_letters
+        .resolve(&saga_id, request.resolved_by.or(caller), request.note)
+        .await
+    {
+        Ok(mut entry) => {
+            redact_saga_metadata(&state.metadata_policy, &mut entry);
+            Json(entry).into_response()
+        }
+        Err(SyrosError::NotFound(msg)) => (StatusCode::NOT_FOUND, msg).into_response(),
+        Err(SyrosError::Conflict(msg)) => (StatusCode::CONFLICT, msg).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Executes a dead-lettered saga again through
+/// [`retry_saga`](saga_handlers::retry_saga), which resolves its entry, and
+/// returns the saga's state.
+///
+/// Answers `404 Not Found` if the saga has no entry, `409 Conflict` if the
+/// entry was already resolved, and otherwise as `retry_saga` does.
+pub async fn requeue_dead_letter(
+    State(state): State<ApiState>,
+    Path(saga_id): Path<String>,
+    body: Option<Json<RetrySagaRequest>>,
+) -> axum::response::Response {
+    match state.dead_letters.get(&saga_id).await {
+        None => (
+            StatusCode::NOT_FOUND,
+            format!("No dead-letter entry for saga {}", saga_id),
+        )
+            .into_response(),
+        Some(entry) if entry.resolved_at.is_some() => (
+            StatusCode::CONFLICT,
+            format!("Dead-letter entry for saga {} is already resolved", saga_id),
+        )
+            .into_response(),
+        Some(_) => saga_handlers::retry_saga(State(state), Path(saga_id), body)
+            .await
+            .into_response(),
+    }
+}
+
+/// Redacts the metadata of the saga snapshot in `entry`.
+fn redact_saga_metadata(policy: &MetadataPolicy, entry: &mut DeadLetterEntry) {
+    if let Some(metadata) = entry.saga.get_mut("metadata") {
+        policy.redact_value(metadata);
+    }
+}
@@ -0,0 +1,249 @@
+//! Service discovery handlers for the Syros API.
+//!
+//! This module provides the HTTP handlers registering, deregistering and
+//! discovering service instances, and reporting their health as observed by
+//! the checks this process runs. All of them answer `503` while service
+//! discovery is disabled; registering, deregistering, listing and
+//! discovering instances requires the `AdminSystem` permission. Instance
+//! `meta` is checked against the metadata policy and redacted in responses.
+
+use crate::api::rest::ApiState;
+use crate::auth::{Permission, Principal};
+use crate::core::{HealthStatus, ServiceInfo, ServiceRegistration};
+use crate::SyrosError;
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Response structure for the services with registered instances.
+#[derive(Debug, Serialize)]
+pub struct ListServicesResponse {
+    /// Names of the services, sorted
+    pub services: Vec<String>,
+}
+
+/// Response structure for the instances of a service.
+#[derive(Debug, Serialize)]
+pub struct ServiceInstancesResponse {
+    /// Name of the service
+    pub service: String,
+    /// Instances that are not critical
+    pub instances: Vec<ServiceInfo>,
+}
+
+/// Response structure for the health of a service.
+#[derive(Debug, Serialize)]
+pub struct ServiceHealthResponse {
+    /// Name of the service
+    pub service: String,
+    /// Health of each known instance, by instance ID
+    pub instances: HashMap<String, HealthStatus>,
+}
+
+/// Reports the health of every known instance of service `name`.
+///
+/// # Arguments
+///
+/// * `state` - API state holding the service registry
+/// * `name` - Name of the service
+///
+/// # Returns
+///
+/// Returns the health map, critical instances included; `404` if no
+/// instance of the service is known, or `503` if service discovery is
+/// disabled.
+pub async fn get_service_health(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let Some(discovery) = &state.service_discovery else {
+        return discovery_disabled();
+    };
+
+    match discovery.get_health_map(&name).await {
+        Ok(instances) if instances.is_empty() => (
+            StatusCode::NOT_FOUND,
+            format!("No instance of service {} is known", name),
+        )
+            .into_response(),
+        Ok(instances) => Json(ServiceHealthResponse {
+            service: name,
+            instances,
+        })
+        .into_response(),
+        Err(SyrosError::ServiceDiscoveryError(msg)) => {
+            (StatusCode::BAD_GATEWAY, msg).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error reading health of service {}: {}", name, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Registers a service instance.
+///
+/// # Arguments
+///
+/// * `state` - API state holding the service registry
+/// * `headers` - Request headers carrying the caller's credentials
+/// * `request` - Instance to register, with its optional health check
+///
+/// # Returns
+///
+/// Returns `201 Created` with the registration, `400` for an empty ID or
+/// name, an invalid check duration or a check targeting another host,
+/// `401`/`403` unless the caller is an administrator, `409` if the ID is
+/// already registered, or `422` for `meta` the metadata policy refuses.
+pub async fn register_service(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<ServiceRegistration>,
+) -> impl IntoResponse {
+    let Some(discovery) = &state.service_discovery else {
+        return discovery_disabled();
+    };
+    if let Some(rejection) = reject_unless_admin(&state, &headers).await {
+        return rejection;
+    }
+    if let Err(e) = state.metadata_policy.check(&request.meta) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response();
+    }
+
+    let mut registration = request.clone();
+    state.metadata_policy.redact(&mut registration.meta);
+    match discovery.register_service(request).await {
+        Ok(()) => (StatusCode::CREATED, Json(registration)).into_response(),
+        Err(SyrosError::ApiError(msg)) => (StatusCode::BAD_REQUEST, msg).into_response(),
+        Err(SyrosError::Conflict(msg)) => (StatusCode::CONFLICT, msg).into_response(),
+        Err(SyrosError::ServiceDiscoveryError(msg)) => {
+            (StatusCode::BAD_GATEWAY, msg).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error registering service {}: {}", registration.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Deregisters the service instance `id`.
+///
+/// # Returns
+///
+/// Returns `204 No Content`, `401`/`403` unless the caller is an
+/// administrator, or `404` if the instance was not registered through this
+/// process.
+pub async fn deregister_service(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(discovery) = &state.service_discovery else {
+        return discovery_disabled();
+    };
+    if let Some(rejection) = reject_unless_admin(&state, &headers).await {
+        return rejection;
+    }
+
+    match discovery.deregister_service(&id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(SyrosError::NotFound(msg)) => (StatusCode::NOT_FOUND, msg).into_response(),
+        Err(SyrosError::ServiceDiscoveryError(msg)) => {
+            (StatusCode::BAD_GATEWAY, msg).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error deregistering service {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists the names of the services with instances registered through this
+/// process.
+///
+/// # Returns
+///
+/// Returns the sorted names, or `401`/`403` unless the caller is an
+/// administrator.
+pub async fn list_services(State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+    let Some(discovery) = &state.service_discovery else {
+        return discovery_disabled();
+    };
+    if let Some(rejection) = reject_unless_admin(&state, &headers).await {
+        return rejection;
+    }
+
+    match discovery.list_all_services().await {
+        Ok(services) => Json(ListServicesResponse { services }).into_response(),
+        Err(e) => {
+            eprintln!("Error listing services: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Discovers the instances of service `name`.
+///
+/// # Returns
+///
+/// Returns the instances the registry lists, except critical ones, with
+/// their `meta` redacted; the list is empty for an unknown service. Answers
+/// `401`/`403` unless the caller is an administrator.
+pub async fn discover_service(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let Some(discovery) = &state.service_discovery else {
+        return discovery_disabled();
+    };
+    if let Some(rejection) = reject_unless_admin(&state, &headers).await {
+        return rejection;
+    }
+
+    match discovery.discover_services(&name).await {
+        Ok(mut instances) => {
+            for instance in &mut instances {
+                state.metadata_policy.redact(&mut instance.meta);
+            }
+            Json(ServiceInstancesResponse {
+                service: name,
+                instances,
+            })
+            .into_response()
+        }
+        Err(SyrosError::ServiceDiscoveryError(msg)) => {
+            (StatusCode::BAD_GATEWAY, msg).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error discovering service {}: {}", name, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// The `401` answered to anonymous callers, or the `403` answered to
+/// callers without the `AdminSystem` permission.
+async fn reject_unless_admin(state: &ApiState, headers: &HeaderMap) -> Option<Response> {
+    match Principal::from_headers(state, headers).await {
+        None => Some(StatusCode::UNAUTHORIZED.into_response()),
+        Some(principal) if !principal.has_permission(&Permission::AdminSystem) => {
+            Some(StatusCode::FORBIDDEN.into_response())
+        }
+        Some(_) => None,
+    }
+}
+
+/// The `503` answered while service discovery is disabled.
+fn discovery_disabled() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Service discovery is disabled",
+    )
+        .into_response()
+}
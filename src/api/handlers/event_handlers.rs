@@ -1,18 +1,31 @@
 //! Event handlers for the Syros API.
 //!
 //! This module provides HTTP handlers for event sourcing operations,
-//! including appending events to streams and retrieving event history.
+//! including appending events to streams, retrieving event history,
+//! stream snapshots, deletion and retention, and NDJSON stream export and
+//! import.
 
+use crate::api::handlers::namespace_handlers::reject_if_frozen;
+use crate::api::rest::Caller;
 use crate::core::event_store::{
-    EventRequest, EventResponse, EventStore, GetEventsRequest, GetEventsResponse,
+    EventFilter, EventRequest, EventStore, GetEventsRequest, ReadDirection, RetentionPolicy,
+    CORRELATION_ID_HEADER, CORRELATION_ID_METADATA_KEY,
 };
+use crate::core::event_transfer::{
+    export_pages, to_ndjson, EventImporter, ImportOptions, NDJSON_CONTENT_TYPE,
+};
+use crate::core::{MetadataPolicy, NamespaceFreezes};
+use crate::SyrosError;
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
+use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Request structure for appending an event to a stream.
 #[derive(Debug, Deserialize)]
@@ -23,6 +36,12 @@ pub struct AppendEventRequest {
     pub data: serde_json::Value,
     /// Optional metadata for the event
     pub metadata: Option<std::collections::HashMap<String, String>>,
+    /// Version the stream must be at for the event to be appended (optional)
+    pub expected_version: Option<u64>,
+    /// Client-chosen event ID (UUID) that makes retries idempotent (optional)
+    pub event_id: Option<String>,
+    /// ID of the event that caused this one (optional)
+    pub causation_id: Option<String>,
 }
 
 /// Query parameters for retrieving events from a stream.
@@ -30,10 +49,128 @@ pub struct AppendEventRequest {
 pub struct GetEventsQuery {
     /// Start from this version (optional)
     pub from_version: Option<i64>,
-    /// Maximum number of events to return (optional)
+    /// Return the latest snapshot and only the events after it
+    #[serde(default)]
+    pub from_snapshot: bool,
+    /// Stop at this version, inclusive (optional)
+    pub to_version: Option<i64>,
+    /// Only events of these comma-separated types (optional)
+    pub event_types: Option<String>,
+    /// Only events whose metadata holds these comma-separated `key:value`
+    /// pairs (optional)
+    pub metadata: Option<String>,
+    /// Only events of this business transaction (optional)
+    pub correlation_id: Option<String>,
+    /// `forward` for oldest first, the default, or `backward` for newest first
+    #[serde(default)]
+    pub direction: ReadDirection,
+    /// Maximum number of matching events to return (optional)
     pub limit: Option<i64>,
 }
 
+impl GetEventsQuery {
+    /// The event filter the query describes, or why it is invalid.
+    pub fn to_filter(&self) -> Result<EventFilter, String> {
+        let event_types = self
+            .event_types
+            .as_deref()
+            .map(|types| types.split(',').map(str::to_string).collect());
+        let mut metadata: std::collections::HashMap<_, _> = self
+            .metadata
+            .as_deref()
+            .into_iter()
+            .flat_map(|pairs| pairs.split(','))
+            .map(|pair| {
+                pair.split_once(':')
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .ok_or_else(|| format!("Invalid metadata filter {}, expected key:value", pair))
+            })
+            .collect::<Result<_, _>>()?;
+        if let Some(correlation_id) = &self.correlation_id {
+            metadata.insert(
+                CORRELATION_ID_METADATA_KEY.to_string(),
+                correlation_id.clone(),
+            );
+        }
+        Ok(EventFilter {
+            event_types,
+            metadata,
+        })
+    }
+}
+
+/// Query parameters for listing streams.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListStreamsQuery {
+    /// Only streams whose ID starts with this prefix
+    pub prefix: Option<String>,
+    /// Most streams to return
+    pub limit: Option<usize>,
+    /// Matching streams to skip
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// Query parameters for deleting a stream.
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteStreamQuery {
+    /// Version the stream must be at to be deleted (optional)
+    pub expected_version: Option<u64>,
+    /// Remove the stream with its events and version, instead of only
+    /// rejecting further appends
+    #[serde(default)]
+    pub hard: bool,
+}
+
+/// Request structure for saving a stream snapshot.
+#[derive(Debug, Deserialize)]
+pub struct SaveSnapshotRequest {
+    /// Version of the latest event folded into the state
+    pub version: i64,
+    /// State of the stream's aggregate as of `version` (JSON)
+    pub state: serde_json::Value,
+}
+
+/// Events returned per page of the feed when `limit` is unset.
+pub const DEFAULT_FEED_PAGE_SIZE: usize = 100;
+/// Most events returned per page of the feed.
+pub const MAX_FEED_PAGE_SIZE: usize = 1000;
+
+/// Query parameters for reading the feed of all streams.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadAllQuery {
+    /// Read from this position on; from the start if unset
+    #[serde(default)]
+    pub from_position: i64,
+    /// Events to return, at most [`MAX_FEED_PAGE_SIZE`]
+    pub limit: Option<usize>,
+}
+
+/// Longest a poll waits for new events, in seconds.
+pub const MAX_POLL_TIMEOUT_SECONDS: u64 = 60;
+
+/// Query parameters for long-polling a stream.
+#[derive(Debug, Deserialize)]
+pub struct PollEventsQuery {
+    /// Wait for events from this version on; the next version if unset
+    pub from_version: Option<i64>,
+    /// Seconds to wait for new events, at most [`MAX_POLL_TIMEOUT_SECONDS`]
+    #[serde(default = "default_poll_timeout")]
+    pub timeout: u64,
+}
+
+fn default_poll_timeout() -> u64 {
+    30
+}
+
+/// Query parameters for importing events.
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportEventsQuery {
+    /// Renumber events whose version does not follow their stream instead of failing
+    #[serde(default)]
+    pub force: bool,
+}
+
 /// Response structure for event data.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EventResponseData {
@@ -66,21 +203,55 @@ pub struct EventResponseData {
 ///
 /// # Returns
 ///
-/// Returns a JSON response with event information or an error status.
+/// Returns a JSON response with event information and its version, `422` if
+/// the metadata exceeds the configured limits, `400` if the event ID is not a
+/// UUID, `409` if the stream is archived or not at the expected version, or
+/// an error status. Retrying with the same `event_id` returns the event
+/// already appended under it. The event's metadata records the
+/// `X-Correlation-Id` header, or a generated correlation ID without one.
 pub async fn append_event(
     State(event_store): State<EventStore>,
+    State(metadata_policy): State<MetadataPolicy>,
+    State(freezes): State<NamespaceFreezes>,
+    Caller(created_by): Caller,
+    headers: HeaderMap,
     Path(stream_id): Path<String>,
     Json(request): Json<AppendEventRequest>,
 ) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &stream_id) {
+        return frozen;
+    }
+    if let Some(metadata) = &request.metadata {
+        if let Err(e) = metadata_policy.check(metadata) {
+            return (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response();
+        }
+    }
+    let correlation_id = headers
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+
     let event_request = EventRequest {
         stream_id,
         event_type: request.event_type,
         data: request.data,
         metadata: request.metadata,
+        created_by,
+        expected_version: request.expected_version,
+        event_id: request.event_id,
+        correlation_id,
+        causation_id: request.causation_id,
     };
 
     match event_store.append_event(event_request).await {
         Ok(response) => Json(response).into_response(),
+        Err(SyrosError::ApiError(msg)) => (StatusCode::BAD_REQUEST, msg).into_response(),
+        Err(SyrosError::Conflict(msg)) => (StatusCode::CONFLICT, msg).into_response(),
+        Err(e @ SyrosError::VersionConflict { .. }) => {
+            (StatusCode::CONFLICT, e.to_string()).into_response()
+        }
+        Err(SyrosError::StreamDeleted(msg)) => (StatusCode::GONE, msg).into_response(),
         Err(e) => {
             eprintln!("Error appending event: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -101,23 +272,408 @@ pub async fn append_event(
 ///
 /// # Returns
 ///
-/// Returns a JSON response with the list of events or an error status.
+/// Returns a JSON response with the list of events or an error status. If
+/// versions from `from_version` on were removed by cleanup or archival, the
+/// response is marked `truncated` and starts at `first_available_version`.
+/// With `from_snapshot=true`, the response carries the stream's latest
+/// `snapshot`, if any, and only the events after it.
+///
+/// Event type and metadata filters are applied before `limit`; a malformed
+/// filter, or one on a redacted metadata key, answers `400`.
 pub async fn get_events(
     State(event_store): State<EventStore>,
+    State(metadata_policy): State<MetadataPolicy>,
     Path(stream_id): Path<String>,
     Query(params): Query<GetEventsQuery>,
 ) -> impl IntoResponse {
+    let filter = match params.to_filter() {
+        Ok(filter) => filter,
+        Err(message) => return (StatusCode::BAD_REQUEST, message).into_response(),
+    };
+    if let Some(key) = filter
+        .metadata
+        .keys()
+        .find(|key| metadata_policy.is_redacted(key))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Metadata key {} is redacted and cannot be filtered on", key),
+        )
+            .into_response();
+    }
     let get_events_request = GetEventsRequest {
         stream_id,
         from_version: params.from_version,
+        from_snapshot: params.from_snapshot,
+        to_version: params.to_version,
+        filter,
+        direction: params.direction,
         limit: params.limit,
     };
 
     match event_store.get_events(get_events_request).await {
-        Ok(response) => Json(response).into_response(),
+        Ok(mut response) => {
+            for event in &mut response.events {
+                metadata_policy.redact(&mut event.metadata);
+            }
+            Json(response).into_response()
+        }
         Err(e) => {
             eprintln!("Error getting events: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
+
+/// Reads the events of all streams in the order they were committed.
+///
+/// # Returns
+///
+/// Returns the events from `from_position` on and the `next_position` to
+/// read the following page from, or an error status. Positions increase
+/// strictly but may have gaps.
+pub async fn read_all(
+    State(event_store): State<EventStore>,
+    State(metadata_policy): State<MetadataPolicy>,
+    Query(query): Query<ReadAllQuery>,
+) -> impl IntoResponse {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_FEED_PAGE_SIZE)
+        .min(MAX_FEED_PAGE_SIZE);
+    match event_store.read_all(query.from_position, limit).await {
+        Ok(mut response) => {
+            for event in &mut response.events {
+                metadata_policy.redact(&mut event.metadata);
+            }
+            Json(response).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error reading the event feed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Waits for events of the specified stream from a version on.
+///
+/// Returns the events from `from_version` on as soon as there are any,
+/// waiting up to `timeout` seconds for one to be appended. Without
+/// `from_version`, waits for the event after the stream's current version.
+///
+/// # Returns
+///
+/// Returns a JSON response with the list of events, empty if none arrived
+/// in time, or an error status.
+pub async fn poll_events(
+    State(event_store): State<EventStore>,
+    State(metadata_policy): State<MetadataPolicy>,
+    Path(stream_id): Path<String>,
+    Query(params): Query<PollEventsQuery>,
+) -> impl IntoResponse {
+    // Subscribe before reading, so an event appended in between is not missed.
+    let mut subscription = event_store.subscribe(&stream_id);
+    let from_version = match params.from_version {
+        Some(from_version) => from_version,
+        None => match event_store.get_stream_version(&stream_id).await {
+            Ok(version) => version + 1,
+            Err(e) => {
+                eprintln!("Error getting stream version: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+    };
+    let request = GetEventsRequest {
+        stream_id,
+        from_version: Some(from_version),
+        from_snapshot: false,
+        to_version: None,
+        filter: EventFilter::default(),
+        direction: ReadDirection::Forward,
+        limit: None,
+    };
+
+    let timeout = Duration::from_secs(params.timeout.min(MAX_POLL_TIMEOUT_SECONDS));
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut timed_out = false;
+    let mut response = loop {
+        match event_store.get_events(request.clone()).await {
+            Ok(response) if !response.events.is_empty() || timed_out => break response,
+            Ok(_) => {
+                let appended = async {
+                    while let Some(event) = subscription.recv().await {
+                        if event.version >= from_version {
+                            return;
+                        }
+                    }
+                    std::future::pending().await
+                };
+                // Read once more on timeout, so the response is current.
+                timed_out = tokio::time::timeout_at(deadline, appended).await.is_err();
+            }
+            Err(e) => {
+                eprintln!("Error polling events: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    };
+
+    for event in &mut response.events {
+        metadata_policy.redact(&mut event.metadata);
+    }
+    Json(response).into_response()
+}
+
+/// Returns the version, size and creator of a stream.
+pub async fn get_stream_info(
+    State(event_store): State<EventStore>,
+    Path(stream_id): Path<String>,
+) -> impl IntoResponse {
+    match event_store.get_stream_info(&stream_id).await {
+        Ok(Some(info)) => Json(info).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("Error getting stream info: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists streams, archived or not, in the order they were created.
+///
+/// Appends do not reorder the list, so paging with `offset` neither skips
+/// nor repeats streams while they are written to.
+pub async fn list_streams(
+    State(event_store): State<EventStore>,
+    Query(query): Query<ListStreamsQuery>,
+) -> impl IntoResponse {
+    match event_store
+        .list_streams(query.prefix.as_deref(), query.limit, query.offset)
+        .await
+    {
+        Ok(streams) => Json(streams).into_response(),
+        Err(e) => {
+            eprintln!("Error listing streams: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Deletes a stream, optionally only if it is at `expected_version`.
+///
+/// A soft delete, the default, keeps the stream's events readable but
+/// rejects further appends with `410`. With `hard=true`, the stream is
+/// removed with its events and version.
+///
+/// # Returns
+///
+/// Returns `204`, `404` if the stream does not exist, or `409` if it is at
+/// another version.
+pub async fn delete_stream(
+    State(event_store): State<EventStore>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(stream_id): Path<String>,
+    Query(query): Query<DeleteStreamQuery>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &stream_id) {
+        return frozen;
+    }
+    match event_store
+        .delete_stream(&stream_id, query.expected_version, query.hard)
+        .await
+    {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e @ SyrosError::VersionConflict { .. }) => {
+            (StatusCode::CONFLICT, e.to_string()).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error deleting stream: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Sets which events of a stream are kept; a policy without limits clears
+/// it. Events outside the policy are removed by a background task.
+///
+/// # Returns
+///
+/// Returns the policy, or `400` if `max_events` is 0.
+pub async fn set_retention(
+    State(event_store): State<EventStore>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(stream_id): Path<String>,
+    Json(policy): Json<RetentionPolicy>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &stream_id) {
+        return frozen;
+    }
+    match event_store.set_retention(&stream_id, policy).await {
+        Ok(()) => Json(policy).into_response(),
+        Err(SyrosError::ApiError(msg)) => (StatusCode::BAD_REQUEST, msg).into_response(),
+        Err(e) => {
+            eprintln!("Error setting stream retention: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Archives a stream, keeping only its summary and rejecting new events.
+///
+/// # Returns
+///
+/// Returns the archived stream's summary, or `404` if the stream does not
+/// exist.
+pub async fn archive_stream(
+    State(event_store): State<EventStore>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(stream_id): Path<String>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &stream_id) {
+        return frozen;
+    }
+    match event_store.archive_stream(&stream_id).await {
+        Ok(Some(info)) => Json(info).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("Error archiving stream: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Saves the state of a stream's aggregate as of a version, replacing the
+/// stream's latest snapshot.
+///
+/// # Returns
+///
+/// Returns the saved snapshot, `404` if the stream does not exist, `422` if
+/// the stream has no such version, `409` if the stream is archived or
+/// already has a newer snapshot, or `410` if it was deleted.
+pub async fn save_snapshot(
+    State(event_store): State<EventStore>,
+    State(freezes): State<NamespaceFreezes>,
+    Path(stream_id): Path<String>,
+    Json(request): Json<SaveSnapshotRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&freezes, &stream_id) {
+        return frozen;
+    }
+    match event_store
+        .save_snapshot(&stream_id, request.version, request.state)
+        .await
+    {
+        Ok(snapshot) => Json(snapshot).into_response(),
+        Err(SyrosError::NotFound(msg)) => (StatusCode::NOT_FOUND, msg).into_response(),
+        Err(SyrosError::EventStoreError(msg)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, msg).into_response()
+        }
+        Err(SyrosError::Conflict(msg)) => (StatusCode::CONFLICT, msg).into_response(),
+        Err(SyrosError::StreamDeleted(msg)) => (StatusCode::GONE, msg).into_response(),
+        Err(e) => {
+            eprintln!("Error saving snapshot: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Returns the latest snapshot of a stream, or `404` if none was saved.
+pub async fn get_snapshot(
+    State(event_store): State<EventStore>,
+    Path(stream_id): Path<String>,
+) -> impl IntoResponse {
+    match event_store.get_latest_snapshot(&stream_id).await {
+        Ok(Some(snapshot)) => Json(snapshot).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("Error getting snapshot: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Exports a stream as NDJSON, one event per line, oldest first.
+///
+/// The stream is read and sent a page at a time, so large streams are never
+/// held in memory. Redacted metadata values are exported as `***`.
+pub async fn export_stream(
+    State(event_store): State<EventStore>,
+    State(metadata_policy): State<MetadataPolicy>,
+    Path(stream_id): Path<String>,
+) -> impl IntoResponse {
+    let pages = export_pages(event_store, stream_id).and_then(move |mut events| {
+        for event in &mut events {
+            metadata_policy.redact(&mut event.metadata);
+        }
+        async move { to_ndjson(&events) }
+    });
+
+    (
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(pages),
+    )
+}
+
+/// Imports events from an NDJSON multipart upload.
+///
+/// Every part of the upload is read as NDJSON and imported as it arrives.
+/// Event IDs, versions, timestamps and metadata are kept; versions must
+/// continue their stream unless `force=true` renumbers them.
+///
+/// # Returns
+///
+/// Returns the import summary, `400` for malformed input or metadata the
+/// policy refuses, `409` if a version does not follow its stream, `410` for
+/// an event of a deleted stream, `413` for a line over 1 MiB, or `503` for
+/// an event in a frozen namespace. Events before the failing line stay
+/// imported.
+pub async fn import_events(
+    State(event_store): State<EventStore>,
+    State(freezes): State<NamespaceFreezes>,
+    State(metadata_policy): State<MetadataPolicy>,
+    Query(query): Query<ImportEventsQuery>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut importer = EventImporter::new(
+        event_store,
+        ImportOptions {
+            force_renumber: query.force,
+        },
+    )
+    .with_namespace_freezes(freezes)
+    .with_metadata_policy(metadata_policy);
+
+    let result = async {
+        while let Some(mut field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| SyrosError::ApiError(e.to_string()))?
+        {
+            while let Some(chunk) = field
+                .chunk()
+                .await
+                .map_err(|e| SyrosError::ApiError(e.to_string()))?
+            {
+                importer.import_bytes(&chunk).await?;
+            }
+        }
+        importer.finish().await
+    }
+    .await;
+
+    match result {
+        Ok(summary) => Json(summary).into_response(),
+        Err(SyrosError::ApiError(msg)) | Err(SyrosError::EventStoreError(msg)) => {
+            (StatusCode::BAD_REQUEST, msg).into_response()
+        }
+        Err(SyrosError::Conflict(msg)) => (StatusCode::CONFLICT, msg).into_response(),
+        Err(SyrosError::StreamDeleted(msg)) => (StatusCode::GONE, msg).into_response(),
+        Err(SyrosError::ValueTooLarge(msg)) => (StatusCode::PAYLOAD_TOO_LARGE, msg).into_response(),
+        Err(SyrosError::Unavailable(msg)) => (StatusCode::SERVICE_UNAVAILABLE, msg).into_response(),
+        Err(e) => {
+            eprintln!("Error importing events: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
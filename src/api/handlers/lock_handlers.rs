@@ -1,11 +1,15 @@
-use crate::api::rest::ApiState;
+use crate::api::handlers::namespace_handlers::reject_if_frozen;
+use crate::api::rest::{ApiState, Caller, KeyPath};
 use crate::core::lock_manager::{
-    LockRequest, LockResponse, ReleaseLockRequest, ReleaseLockResponse,
+    ExtendLockRequest, LockFilter, LockRequest, LockResponse, ReleaseLockRequest,
 };
+use crate::core::lock_queue::LockPriority;
+use crate::core::namespace_freeze::NAMESPACE_SEPARATOR;
+use crate::SyrosError;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -17,6 +21,14 @@ pub struct AcquireLockRequest {
     pub metadata: Option<String>,
     pub owner: String,
     pub wait_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub priority: LockPriority,
+    /// Succeed with the held lock if `owner` already holds the key
+    #[serde(default)]
+    pub reentrant: bool,
+    /// WebSocket session whose disconnect releases the lock
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,6 +37,22 @@ pub struct ReleaseLockRequestPayload {
     pub owner: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ExtendLockRequestPayload {
+    pub lock_id: String,
+    pub owner: String,
+    pub ttl_seconds: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListLocksQuery {
+    pub owner: Option<String>,
+    /// Glob pattern the lock keys must match, e.g. `orders:*`
+    pub pattern: Option<String>,
+    /// Namespace the lock keys must be in, e.g. `team-a` for `team-a/orders:1`
+    pub namespace: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LockStatusResponse {
     pub key: String,
@@ -33,14 +61,40 @@ pub struct LockStatusResponse {
     pub acquired_at: Option<String>,
     pub expires_at: Option<String>,
     pub metadata: Option<String>,
+    pub created_by: Option<String>,
+    /// Fencing token of the current lock
+    pub fence_token: Option<u64>,
+    /// Acquisitions of the current lock not yet released
+    pub hold_count: Option<u32>,
     pub is_locked: bool,
 }
 
-pub async fn acquire_lock(
-    State(state): State<ApiState>,
-    Json(request): Json<AcquireLockRequest>,
-) -> impl IntoResponse {
-    let lock_request = LockRequest {
+/// Checks an acquisition against the namespace freezes, the metadata policy
+/// and the TTL and wait limits, returning the response refusing it if any fails.
+fn check_acquisition(state: &ApiState, request: &AcquireLockRequest) -> Option<Response> {
+    if let Some(frozen) = reject_if_frozen(&state.namespace_freezes, &request.key) {
+        return Some(frozen);
+    }
+    if let Some(metadata) = &request.metadata {
+        if let Err(e) = state.metadata_policy.check_text(metadata) {
+            return Some((StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response());
+        }
+    }
+    if let Err(e) = state.config.locks.check_ttl(request.ttl_seconds) {
+        return Some((StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response());
+    }
+    if let Err(e) = state
+        .config
+        .locks
+        .check_wait_timeout(request.wait_timeout_seconds)
+    {
+        return Some((StatusCode::BAD_REQUEST, e.to_string()).into_response());
+    }
+    None
+}
+
+fn lock_request(request: AcquireLockRequest, created_by: Option<String>) -> LockRequest {
+    LockRequest {
         key: request.key,
         ttl: std::time::Duration::from_secs(request.ttl_seconds),
         metadata: request.metadata,
@@ -48,12 +102,35 @@ pub async fn acquire_lock(
         wait_timeout: request
             .wait_timeout_seconds
             .map(std::time::Duration::from_secs),
-    };
+        priority: request.priority,
+        created_by,
+        reentrant: request.reentrant,
+        session_id: request.session_id,
+    }
+}
 
+pub async fn acquire_lock(
+    State(state): State<ApiState>,
+    Caller(created_by): Caller,
+    Json(request): Json<AcquireLockRequest>,
+) -> impl IntoResponse {
+    if let Some(refused) = check_acquisition(&state, &request) {
+        return refused;
+    }
+    let lock_request = lock_request(request, created_by);
+
+    #[cfg(feature = "metrics")]
     state.metrics.increment_locks_acquired();
 
     match state.lock_manager.acquire_lock(lock_request).await {
+        Ok(response) if !response.success => lock_held(response),
         Ok(response) => Json(response).into_response(),
+        Err(SyrosError::LockError(message)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+        }
+        Err(SyrosError::NamespaceLimitExceeded(message)) => {
+            (StatusCode::TOO_MANY_REQUESTS, message).into_response()
+        }
         Err(e) => {
             eprintln!("Error acquiring lock: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -61,17 +138,76 @@ pub async fn acquire_lock(
     }
 }
 
+/// Refuses an acquisition of a lock held by another owner.
+///
+/// The `409` response carries the failed attempt with the holder's
+/// `held_until`, and a `Retry-After` header with the seconds left until then.
+fn lock_held(response: LockResponse) -> Response {
+    let retry_after = response.retry_after(chrono::Utc::now());
+    let mut refused = (StatusCode::CONFLICT, Json(response)).into_response();
+    if let Some(retry_after) = retry_after {
+        refused.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(retry_after.as_secs().max(1)),
+        );
+    }
+    refused
+}
+
+/// Acquires every lock in the batch or none of them.
+///
+/// # Returns
+///
+/// Returns the per-key results with a combined `success` flag, `400` for a
+/// wait over the limit, `422` for an invalid lock or a key listed twice, `429`
+/// if a key's namespace holds as many locks as allowed, or `503` if a key's
+/// namespace is frozen.
+pub async fn acquire_locks_batch(
+    State(state): State<ApiState>,
+    Caller(created_by): Caller,
+    Json(requests): Json<Vec<AcquireLockRequest>>,
+) -> impl IntoResponse {
+    if let Some(refused) = requests
+        .iter()
+        .find_map(|request| check_acquisition(&state, request))
+    {
+        return refused;
+    }
+    let lock_requests = requests
+        .into_iter()
+        .map(|request| lock_request(request, created_by.clone()))
+        .collect();
+
+    match state.lock_manager.acquire_multi(lock_requests).await {
+        Ok(response) => Json(response).into_response(),
+        Err(SyrosError::LockError(message)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+        }
+        Err(SyrosError::NamespaceLimitExceeded(message)) => {
+            (StatusCode::TOO_MANY_REQUESTS, message).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error acquiring locks: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 pub async fn release_lock(
     State(state): State<ApiState>,
-    Path(key): Path<String>,
+    KeyPath(key): KeyPath,
     Json(request): Json<ReleaseLockRequestPayload>,
 ) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&state.namespace_freezes, &key) {
+        return frozen;
+    }
     let release_request = ReleaseLockRequest {
         key,
         lock_id: request.lock_id,
         owner: request.owner,
     };
 
+    #[cfg(feature = "metrics")]
     state.metrics.increment_locks_released();
 
     match state.lock_manager.release_lock(release_request).await {
@@ -83,6 +219,66 @@ pub async fn release_lock(
     }
 }
 
+pub async fn extend_lock(
+    State(state): State<ApiState>,
+    Path(key): Path<String>,
+    Json(request): Json<ExtendLockRequestPayload>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&state.namespace_freezes, &key) {
+        return frozen;
+    }
+    if let Err(e) = state.config.locks.check_ttl(request.ttl_seconds) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response();
+    }
+    let extend_request = ExtendLockRequest {
+        key,
+        lock_id: request.lock_id,
+        owner: request.owner,
+        ttl: std::time::Duration::from_secs(request.ttl_seconds),
+    };
+
+    match state.lock_manager.extend_lock(extend_request).await {
+        Ok(response) => Json(response).into_response(),
+        Err(SyrosError::LockError(message)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error extending lock: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists the live locks, oldest acquisition first, optionally filtered by
+/// owner, key pattern and namespace.
+pub async fn list_locks(
+    State(state): State<ApiState>,
+    Query(query): Query<ListLocksQuery>,
+) -> impl IntoResponse {
+    let filter = LockFilter {
+        owner: query.owner,
+        pattern: query.pattern,
+        namespace: query.namespace,
+        ..Default::default()
+    };
+
+    match state.lock_manager.list_locks(&filter).await {
+        Ok(mut locks) => {
+            for lock in &mut locks {
+                lock.metadata = lock
+                    .metadata
+                    .take()
+                    .map(|metadata| state.metadata_policy.redact_text(metadata));
+            }
+            Json(locks).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error listing locks: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 pub async fn get_lock_status(
     State(state): State<ApiState>,
     Path(key): Path<String>,
@@ -94,7 +290,12 @@ pub async fn get_lock_status(
             owner: Some(lock_state.owner),
             acquired_at: Some(lock_state.acquired_at.to_rfc3339()),
             expires_at: Some(lock_state.expires_at.to_rfc3339()),
-            metadata: lock_state.metadata,
+            metadata: lock_state
+                .metadata
+                .map(|metadata| state.metadata_policy.redact_text(metadata)),
+            created_by: lock_state.created_by,
+            fence_token: Some(lock_state.fence_token),
+            hold_count: Some(lock_state.hold_count),
             is_locked: true,
         })
         .into_response(),
@@ -105,6 +306,9 @@ pub async fn get_lock_status(
             acquired_at: None,
             expires_at: None,
             metadata: None,
+            created_by: None,
+            fence_token: None,
+            hold_count: None,
             is_locked: false,
         })
         .into_response(),
@@ -114,3 +318,95 @@ pub async fn get_lock_status(
         }
     }
 }
+
+/// Shows the wait queue of a lock and the audit of its recent queued
+/// acquisitions in this instance.
+pub async fn get_lock_queue(
+    State(state): State<ApiState>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    Json(state.lock_manager.lock_queue(&key))
+}
+
+/// Shows the contention of the keys locked in this instance, most contended
+/// first, and the locks held in each namespace against its limit.
+pub async fn get_lock_stats(State(state): State<ApiState>) -> impl IntoResponse {
+    Json(state.lock_manager.get_lock_stats())
+}
+
+/// Key of the lock `key` in the namespace `ns`, unless the namespace is
+/// empty or contains the separator.
+fn namespaced_key(ns: &str, key: &str) -> Option<String> {
+    if ns.is_empty() || ns.contains(NAMESPACE_SEPARATOR) {
+        return None;
+    }
+    Some(format!("{}{}{}", ns, NAMESPACE_SEPARATOR, key))
+}
+
+fn invalid_namespace(ns: &str) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        format!("Invalid namespace {:?}", ns),
+    )
+        .into_response()
+}
+
+/// Lists the live locks in the namespace `ns`, as [`list_locks`] does.
+pub async fn list_namespace_locks(
+    state: State<ApiState>,
+    Path(ns): Path<String>,
+    Query(mut query): Query<ListLocksQuery>,
+) -> Response {
+    query.namespace = Some(ns);
+    list_locks(state, Query(query)).await.into_response()
+}
+
+/// Acquires a lock on the request's key within the namespace `ns`.
+pub async fn acquire_namespace_lock(
+    state: State<ApiState>,
+    caller: Caller,
+    Path(ns): Path<String>,
+    Json(mut request): Json<AcquireLockRequest>,
+) -> Response {
+    let Some(key) = namespaced_key(&ns, &request.key) else {
+        return invalid_namespace(&ns);
+    };
+    request.key = key;
+    acquire_lock(state, caller, Json(request))
+        .await
+        .into_response()
+}
+
+pub async fn release_namespace_lock(
+    state: State<ApiState>,
+    Path((ns, key)): Path<(String, String)>,
+    request: Json<ReleaseLockRequestPayload>,
+) -> Response {
+    match namespaced_key(&ns, &key) {
+        Some(key) => release_lock(state, KeyPath(key), request)
+            .await
+            .into_response(),
+        None => invalid_namespace(&ns),
+    }
+}
+
+pub async fn extend_namespace_lock(
+    state: State<ApiState>,
+    Path((ns, key)): Path<(String, String)>,
+    request: Json<ExtendLockRequestPayload>,
+) -> Response {
+    match namespaced_key(&ns, &key) {
+        Some(key) => extend_lock(state, Path(key), request).await.into_response(),
+        None => invalid_namespace(&ns),
+    }
+}
+
+pub async fn get_namespace_lock_status(
+    state: State<ApiState>,
+    Path((ns, key)): Path<(String, String)>,
+) -> Response {
+    match namespaced_key(&ns, &key) {
+        Some(key) => get_lock_status(state, Path(key)).await.into_response(),
+        None => invalid_namespace(&ns),
+    }
+}
@@ -1,8 +1,18 @@
+pub mod admin_handlers;
 pub mod auth_handlers;
 pub mod cache_handlers;
+pub mod capabilities_handlers;
+pub mod component_handlers;
+pub mod dead_letter_handlers;
+pub mod discovery_handlers;
 pub mod event_handlers;
 pub mod health_handlers;
 pub mod lock_handlers;
+#[cfg(feature = "metrics")]
 pub mod metrics_handlers;
+pub mod namespace_handlers;
+pub mod projection_handlers;
 pub mod rbac_handlers;
+pub mod saga_definition_handlers;
 pub mod saga_handlers;
+pub mod saga_worker_handlers;
@@ -0,0 +1,103 @@
+//! Namespace freeze handlers for the Syros API.
+//!
+//! This module provides the admin HTTP handlers freezing and thawing the
+//! writes to a namespace, and the check the write handlers run before
+//! changing a key.
+
+use crate::api::rest::{ApiState, Caller};
+use crate::core::namespace_freeze::NamespaceFreeze;
+use crate::core::NamespaceFreezes;
+use crate::SyrosError;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Request structure for freezing a namespace.
+#[derive(Debug, Deserialize)]
+pub struct FreezeNamespaceRequest {
+    /// How long writes to the namespace are refused
+    pub duration_seconds: u64,
+    /// Free-form note shown with the freeze, e.g. the backup it is for
+    pub reason: Option<String>,
+}
+
+/// Refuses a write to `key` while its namespace is frozen.
+///
+/// The `503` response carries the freeze and a `Retry-After` header with the
+/// seconds left in it.
+pub fn reject_if_frozen(freezes: &NamespaceFreezes, key: &str) -> Option<Response> {
+    let freeze = freezes.check(key)?;
+    let retry_after = freeze.retry_after(chrono::Utc::now()).as_secs().max(1);
+    Some(
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(freeze),
+        )
+            .into_response(),
+    )
+}
+
+/// Lists the namespaces whose writes are frozen.
+pub async fn list_namespaces(
+    State(freezes): State<NamespaceFreezes>,
+) -> Json<Vec<NamespaceFreeze>> {
+    Json(freezes.list())
+}
+
+/// Freezes the writes to namespace `ns`.
+///
+/// # Returns
+///
+/// Returns the freeze, replacing any earlier one on the namespace, or `422`
+/// for a zero duration or one ending out of range.
+pub async fn freeze_namespace(
+    State(state): State<ApiState>,
+    Caller(frozen_by): Caller,
+    Path(ns): Path<String>,
+    Json(request): Json<FreezeNamespaceRequest>,
+) -> impl IntoResponse {
+    if request.duration_seconds == 0 {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "duration_seconds must be positive",
+        )
+            .into_response();
+    }
+
+    match state.namespace_freezes.freeze(
+        &ns,
+        Duration::from_secs(request.duration_seconds),
+        frozen_by,
+        request.reason,
+    ) {
+        Ok(freeze) => Json(freeze).into_response(),
+        Err(SyrosError::ApiError(message)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+        }
+        Err(e) => {
+            eprintln!("Error freezing namespace {}: {}", ns, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lifts the freeze on namespace `ns`.
+///
+/// # Returns
+///
+/// Returns the lifted freeze, or `404` if the namespace was not frozen.
+pub async fn thaw_namespace(
+    State(freezes): State<NamespaceFreezes>,
+    Path(ns): Path<String>,
+) -> impl IntoResponse {
+    match freezes.thaw(&ns) {
+        Some(freeze) => Json(freeze).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
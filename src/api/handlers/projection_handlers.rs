@@ -0,0 +1,75 @@
+//! Projection handlers for the Syros API.
+//!
+//! This module provides HTTP handlers for projections of the event feed:
+//! registering one, reading its state and rebuilding it from scratch.
+
+use crate::api::rest::ApiState;
+use crate::core::projections::ProjectionDefinition;
+use crate::SyrosError;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+/// Registers the projection `name` and returns it with the state folded
+/// from the feed so far.
+///
+/// Registering the same definition again keeps the projection's state; a
+/// different one replaces it and folds the feed from the start. Answers
+/// `400 Bad Request` for an invalid name.
+pub async fn register_projection(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Json(definition): Json<ProjectionDefinition>,
+) -> impl IntoResponse {
+    match state.projections.register(&name, definition).await {
+        Ok(projection) => Json(projection).into_response(),
+        Err(SyrosError::ApiError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error registering projection: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Fetches the projection `name` caught up with the feed, including its
+/// state and checkpoint.
+///
+/// Answers `404 Not Found` if no projection has that name.
+pub async fn get_projection_state(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.projections.get(&name).await {
+        Ok(Some(projection)) => Json(projection).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            format!("Projection {} not found", name),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("Error getting projection: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Drops the state of the projection `name` and folds the retained events
+/// again from the start of the feed.
+///
+/// Answers `404 Not Found` if no projection has that name.
+pub async fn rebuild_projection(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.projections.rebuild(&name).await {
+        Ok(projection) => Json(projection).into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(e) => {
+            eprintln!("Error rebuilding projection: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -10,15 +10,14 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Json},
 };
-use serde_json::{json, Value};
+use serde_json::json;
 
 pub async fn create_user(
     State(state): State<ApiState>,
     Json(payload): Json<CreateUserRequest>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac
+    match state
+        .rbac_manager
         .create_user(payload.username, payload.email, payload.roles)
         .await
     {
@@ -38,9 +37,7 @@ pub async fn get_user(
     State(state): State<ApiState>,
     Path(user_id): Path<String>,
 ) -> impl IntoResponse {
-    let rbac = state.rbac_manager.lock().await;
-
-    match rbac.get_user(&user_id).await {
+    match state.rbac_manager.get_user(&user_id).await {
         Ok(Some(user)) => Json(json!({
             "success": true,
             "data": user
@@ -58,9 +55,7 @@ pub async fn get_user_by_username(
     State(state): State<ApiState>,
     Path(username): Path<String>,
 ) -> impl IntoResponse {
-    let rbac = state.rbac_manager.lock().await;
-
-    match rbac.get_user_by_username(&username).await {
+    match state.rbac_manager.get_user_by_username(&username).await {
         Ok(Some(user)) => Json(json!({
             "success": true,
             "data": user
@@ -79,9 +74,11 @@ pub async fn update_user_roles(
     Path(user_id): Path<String>,
     Json(payload): Json<UpdateUserRolesRequest>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac.update_user_roles(&user_id, payload.roles).await {
+    match state
+        .rbac_manager
+        .update_user_roles(&user_id, payload.roles)
+        .await
+    {
         Ok(_) => Json(json!({
             "success": true,
             "message": "User roles updated successfully"
@@ -99,9 +96,11 @@ pub async fn add_user_permission(
     Path(user_id): Path<String>,
     Json(payload): Json<AddPermissionRequest>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac.add_user_permission(&user_id, payload.permission).await {
+    match state
+        .rbac_manager
+        .add_user_permission(&user_id, payload.permission)
+        .await
+    {
         Ok(_) => Json(json!({
             "success": true,
             "message": "Permission added successfully"
@@ -119,9 +118,8 @@ pub async fn remove_user_permission(
     Path(user_id): Path<String>,
     Json(payload): Json<RemovePermissionRequest>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac
+    match state
+        .rbac_manager
         .remove_user_permission(&user_id, payload.permission)
         .await
     {
@@ -142,9 +140,11 @@ pub async fn check_permission(
     Path(user_id): Path<String>,
     Json(payload): Json<CheckPermissionRequest>,
 ) -> impl IntoResponse {
-    let rbac = state.rbac_manager.lock().await;
-
-    match rbac.check_permission(&user_id, &payload.permission).await {
+    match state
+        .rbac_manager
+        .check_permission(&user_id, &payload.permission)
+        .await
+    {
         Ok(has_permission) => Json(json!({
             "success": true,
             "has_permission": has_permission
@@ -162,9 +162,8 @@ pub async fn check_resource_permission(
     Path((user_id, resource_id)): Path<(String, String)>,
     Json(payload): Json<CheckResourcePermissionRequest>,
 ) -> impl IntoResponse {
-    let rbac = state.rbac_manager.lock().await;
-
-    match rbac
+    match state
+        .rbac_manager
         .check_resource_permission(&user_id, &resource_id, &payload.permission)
         .await
     {
@@ -184,9 +183,8 @@ pub async fn create_custom_role(
     State(state): State<ApiState>,
     Json(payload): Json<CreateCustomRoleRequest>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac
+    match state
+        .rbac_manager
         .create_custom_role(payload.name, payload.description, payload.permissions)
         .await
     {
@@ -203,9 +201,7 @@ pub async fn create_custom_role(
 }
 
 pub async fn get_all_users(State(state): State<ApiState>) -> impl IntoResponse {
-    let rbac = state.rbac_manager.lock().await;
-
-    match rbac.get_all_users().await {
+    match state.rbac_manager.get_all_users().await {
         Ok(users) => Json(json!({
             "success": true,
             "data": users
@@ -219,9 +215,7 @@ pub async fn get_all_users(State(state): State<ApiState>) -> impl IntoResponse {
 }
 
 pub async fn get_all_roles(State(state): State<ApiState>) -> impl IntoResponse {
-    let rbac = state.rbac_manager.lock().await;
-
-    match rbac.get_all_roles().await {
+    match state.rbac_manager.get_all_roles().await {
         Ok(roles) => Json(json!({
             "success": true,
             "data": roles
@@ -238,9 +232,7 @@ pub async fn deactivate_user(
     State(state): State<ApiState>,
     Path(user_id): Path<String>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac.deactivate_user(&user_id).await {
+    match state.rbac_manager.deactivate_user(&user_id).await {
         Ok(_) => Json(json!({
             "success": true,
             "message": "User deactivated successfully"
@@ -257,9 +249,7 @@ pub async fn activate_user(
     State(state): State<ApiState>,
     Path(user_id): Path<String>,
 ) -> impl IntoResponse {
-    let mut rbac = state.rbac_manager.lock().await;
-
-    match rbac.activate_user(&user_id).await {
+    match state.rbac_manager.activate_user(&user_id).await {
         Ok(_) => Json(json!({
             "success": true,
             "message": "User activated successfully"
@@ -0,0 +1,162 @@
+//! Saga definition handlers for the Syros API.
+//!
+//! This module provides HTTP handlers for the registry of named saga
+//! definitions: registering new versions, listing them and fetching one.
+//! Sagas are started from a definition through the saga start endpoint.
+
+use crate::api::handlers::saga_handlers::{validation_failed, SagaStepRequest, StartSagaRequest};
+use crate::api::rest::{ApiState, Caller};
+use crate::core::saga_definitions::SagaDefinition;
+use crate::core::saga_orchestrator::REQUEST_ID_METADATA_KEY;
+use crate::core::saga_plan::SagaValidationError;
+use crate::SyrosError;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Request body of [`register_definition`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaDefinitionRequest {
+    /// Steps of every saga started from the definition
+    pub steps: Vec<SagaStepRequest>,
+    /// Metadata of every saga started from the definition, which the saga
+    /// start may add to
+    pub metadata: Option<serde_json::Value>,
+    /// Global budget of every saga started from the definition in seconds
+    pub max_duration_seconds: Option<u64>,
+}
+
+/// Hides the metadata values the metadata policy redacts.
+fn redacted(state: &ApiState, mut definition: SagaDefinition) -> SagaDefinition {
+    if let Some(metadata) = definition.definition.metadata.as_mut() {
+        state.metadata_policy.redact(metadata);
+    }
+    definition
+}
+
+/// Registers the next version of the saga definition `name` and returns
+/// it.
+///
+/// The definition is validated like a saga start; invalid definitions are
+/// rejected with `422 Unprocessable Entity`. Answers `409 Conflict` if
+/// another version was registered at the same time.
+pub async fn register_definition(
+    State(state): State<ApiState>,
+    Caller(created_by): Caller,
+    Path(name): Path<String>,
+    Json(request): Json<SagaDefinitionRequest>,
+) -> impl IntoResponse {
+    if name.contains('@') {
+        return validation_failed(
+            vec![SagaValidationError::new(None, "name", "must not contain @")],
+            Vec::new(),
+        );
+    }
+    let request = StartSagaRequest {
+        name: name.clone(),
+        steps: request.steps,
+        definition: None,
+        payloads: HashMap::new(),
+        metadata: request.metadata,
+        max_duration_seconds: request.max_duration_seconds,
+        client_id: None,
+        start_at: None,
+        delay_seconds: None,
+        dry_run: false,
+    };
+    let mut definition =
+        match request.into_saga_request(String::new(), None, &state.metadata_policy, None) {
+            Ok(definition) => definition,
+            Err(errors) => return validation_failed(errors, Vec::new()),
+        };
+    // Every saga started from the definition gets its own request ID.
+    if let Some(metadata) = definition.metadata.as_mut() {
+        metadata.remove(REQUEST_ID_METADATA_KEY);
+    }
+    let plan = state.saga_orchestrator.plan_saga(&definition).await;
+    if !plan.is_valid() {
+        return validation_failed(plan.errors, plan.warnings);
+    }
+
+    match state
+        .saga_definitions
+        .register(&name, definition, created_by)
+        .await
+    {
+        Ok(definition) => (StatusCode::CREATED, Json(redacted(&state, definition))).into_response(),
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(e) => {
+            eprintln!("Error registering saga definition: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists the latest version of every saga definition, by name.
+pub async fn list_definitions(State(state): State<ApiState>) -> impl IntoResponse {
+    match state.saga_definitions.list().await {
+        Ok(definitions) => Json(
+            definitions
+                .into_iter()
+                .map(|definition| redacted(&state, definition))
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => {
+            eprintln!("Error listing saga definitions: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Fetches the saga definition `reference` points to, e.g. `checkout@2`,
+/// or the latest version of `checkout`.
+///
+/// Answers `404 Not Found` for an unknown definition and `400 Bad Request`
+/// for a malformed reference.
+pub async fn get_definition(
+    State(state): State<ApiState>,
+    Path(reference): Path<String>,
+) -> impl IntoResponse {
+    match state.saga_definitions.resolve(&reference).await {
+        Ok(definition) => Json(redacted(&state, definition)).into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(SyrosError::SagaError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error getting saga definition: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists every version of the saga definition `name`, oldest first.
+///
+/// Answers `404 Not Found` if no definition has that name.
+pub async fn list_definition_versions(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.saga_definitions.versions(&name).await {
+        Ok(versions) if versions.is_empty() => (
+            StatusCode::NOT_FOUND,
+            format!("Saga definition {} not found", name),
+        )
+            .into_response(),
+        Ok(versions) => Json(
+            versions
+                .into_iter()
+                .map(|definition| redacted(&state, definition))
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => {
+            eprintln!("Error listing saga definition versions: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
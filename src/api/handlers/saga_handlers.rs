@@ -3,31 +3,236 @@
 //! This module provides HTTP handlers for saga orchestration operations,
 //! including starting sagas, checking status, and managing saga execution.
 
-use crate::api::rest::ApiState;
+use crate::api::handlers::namespace_handlers::reject_if_frozen;
+use crate::api::rest::{ApiState, Caller};
+use crate::core::lock_manager::LockState;
+use crate::core::saga_definitions::SagaDefinition;
+use crate::core::saga_executors::StepOutcome;
+use crate::core::saga_idempotency::{self, IDEMPOTENCY_KEY_HEADER};
 use crate::core::saga_orchestrator::{
-    BackoffStrategy, RetryPolicy, SagaRequest, SagaResponse, SagaStep,
+    IdempotentStart, LockedSagaStart, RetryPolicy, Saga, SagaFilter, SagaLock, SagaRequest,
+    SagaStatus, SagaStep, StepExecution, StepLock, CLIENT_ID_METADATA_KEY, DEFINITION_METADATA_KEY,
+    LOCK_ID_METADATA_KEY, LOCK_KEY_METADATA_KEY, LOCK_OWNER_METADATA_KEY, OWNER_METADATA_KEY,
+    REQUEST_ID_HEADER, REQUEST_ID_METADATA_KEY, RETRY_COUNT_METADATA_KEY,
 };
+use crate::core::saga_plan::SagaValidationError;
+use crate::core::saga_results::StepResult;
+use crate::core::MetadataPolicy;
+use crate::SyrosError;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Request structure for starting a new saga.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartSagaRequest {
-    /// Name of the saga
+    /// Name of the saga; defaults to the definition's when starting from one
+    #[serde(default)]
     pub name: String,
-    /// List of steps to execute in the saga
+    /// List of steps to execute in the saga; empty when starting from a
+    /// definition
+    #[serde(default)]
     pub steps: Vec<SagaStepRequest>,
-    /// Optional metadata for the saga
+    /// Saga definition to take the steps from, e.g. `checkout@2`, or
+    /// `checkout` for its latest version
+    pub definition: Option<String>,
+    /// Payloads replacing those of the steps, by step name
+    #[serde(default)]
+    pub payloads: HashMap<String, serde_json::Value>,
+    /// Optional metadata for the saga; added to the definition's
     pub metadata: Option<serde_json::Value>,
+    /// Optional global budget for the whole saga in seconds; defaults to the
+    /// definition's
+    pub max_duration_seconds: Option<u64>,
+    /// Client to deliver this saga's status notifications to, exclusively
+    pub client_id: Option<String>,
+    /// Time to begin executing the saga at; it is `Scheduled` until then
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+    /// Seconds to wait before executing the saga, instead of `start_at`
+    #[serde(default)]
+    pub delay_seconds: Option<u64>,
+    /// Validate and return the execution plan without starting the saga
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl StartSagaRequest {
+    /// Converts the request into a [`SagaRequest`] tagged with `request_id`
+    /// and started by `created_by`.
+    ///
+    /// Starting from `definition`, the resolved definition of the request,
+    /// takes its steps, and its metadata and budget unless the request
+    /// overrides them; its reference is recorded under the definition
+    /// metadata key.
+    ///
+    /// The owner, client ID, lock, definition and retry count metadata keys are reserved: any values
+    /// the caller put there are dropped, and `created_by` and `client_id` are
+    /// recorded instead.
+    ///
+    /// Fails with an error per step whose retry policy names an unknown
+    /// backoff strategy, per payload naming no step, with an error if steps
+    /// are listed along with a definition or a delay along with a start
+    /// time, and with an error if the metadata exceeds the limits of
+    /// `metadata_policy`.
+    pub fn into_saga_request(
+        self,
+        request_id: String,
+        created_by: Option<String>,
+        metadata_policy: &MetadataPolicy,
+        definition: Option<&SagaDefinition>,
+    ) -> Result<SagaRequest, Vec<SagaValidationError>> {
+        let mut errors = Vec::new();
+        let mut steps = Vec::with_capacity(self.steps.len());
+        if definition.is_some() && !self.steps.is_empty() {
+            errors.push(SagaValidationError::new(
+                None,
+                "steps",
+                "must be empty when starting from a definition",
+            ));
+        }
+        for step in self.steps {
+            let retry_policy = match step.retry_policy {
+                Some(rp) => match rp.backoff_strategy.parse() {
+                    Ok(backoff_strategy) => Some(RetryPolicy {
+                        max_retries: rp.max_retries,
+                        backoff_strategy,
+                        initial_delay: std::time::Duration::from_millis(rp.initial_delay_ms),
+                    }),
+                    Err(e) => {
+                        errors.push(SagaValidationError::new(
+                            Some(&step.name),
+                            "retry_policy.backoff_strategy",
+                            e.to_string(),
+                        ));
+                        None
+                    }
+                },
+                None => None,
+            };
+            steps.push(SagaStep {
+                name: step.name,
+                service: step.service,
+                action: step.action,
+                compensation: step.compensation,
+                timeout: std::time::Duration::from_secs(step.timeout_seconds),
+                retry_policy,
+                payload: step.payload,
+                acquired_locks: step.acquired_locks,
+                cache_keys: step.cache_keys,
+                group: step.group,
+                executor: step.executor,
+            });
+        }
+        if let Some(definition) = definition {
+            steps = definition.definition.steps.clone();
+        }
+        for (name, payload) in self.payloads {
+            match steps.iter_mut().find(|step| step.name == name) {
+                Some(step) => step.payload = Some(payload),
+                None => errors.push(SagaValidationError::new(
+                    Some(&name),
+                    "payloads",
+                    "no step has this name",
+                )),
+            }
+        }
+        if let Some(metadata) = &self.metadata {
+            if let Err(e) = metadata_policy.check_value(metadata) {
+                errors.push(SagaValidationError::new(None, &e.field, e.message));
+            }
+        }
+        let start_at = match (self.start_at, self.delay_seconds) {
+            (Some(_), Some(_)) => {
+                errors.push(SagaValidationError::new(
+                    None,
+                    "delay_seconds",
+                    "must not be set along with start_at",
+                ));
+                None
+            }
+            (Some(start_at), None) => Some(start_at),
+            (None, Some(delay)) => {
+                let start_at = i64::try_from(delay)
+                    .ok()
+                    .and_then(chrono::Duration::try_seconds)
+                    .and_then(|delay| Utc::now().checked_add_signed(delay));
+                if start_at.is_none() {
+                    errors.push(SagaValidationError::new(
+                        None,
+                        "delay_seconds",
+                        "puts the start out of range",
+                    ));
+                }
+                start_at
+            }
+            (None, None) => None,
+        };
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut metadata = definition
+            .and_then(|definition| definition.definition.metadata.clone())
+            .unwrap_or_default();
+        metadata.extend(
+            self.metadata
+                .and_then(|m| serde_json::from_value::<HashMap<String, String>>(m).ok())
+                .unwrap_or_default(),
+        );
+        for key in [
+            OWNER_METADATA_KEY,
+            CLIENT_ID_METADATA_KEY,
+            LOCK_KEY_METADATA_KEY,
+            LOCK_ID_METADATA_KEY,
+            LOCK_OWNER_METADATA_KEY,
+            DEFINITION_METADATA_KEY,
+            RETRY_COUNT_METADATA_KEY,
+        ] {
+            metadata.remove(key);
+        }
+        if let Some(definition) = definition {
+            metadata.insert(DEFINITION_METADATA_KEY.to_string(), definition.reference());
+        }
+        if let Some(created_by) = created_by {
+            metadata.insert(OWNER_METADATA_KEY.to_string(), created_by);
+        }
+        if let Some(client_id) = self.client_id {
+            metadata.insert(CLIENT_ID_METADATA_KEY.to_string(), client_id);
+        }
+        metadata
+            .entry(REQUEST_ID_METADATA_KEY.to_string())
+            .or_insert(request_id);
+
+        let (name, max_duration) = match definition {
+            Some(definition) if self.name.is_empty() => (
+                definition.definition.name.clone(),
+                definition.definition.max_duration,
+            ),
+            Some(definition) => (self.name, definition.definition.max_duration),
+            None => (self.name, None),
+        };
+        Ok(SagaRequest {
+            name,
+            steps,
+            metadata: Some(metadata),
+            max_duration: self
+                .max_duration_seconds
+                .map(std::time::Duration::from_secs)
+                .or(max_duration),
+            start_at,
+        })
+    }
 }
 
 /// Request structure for defining a saga step.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SagaStepRequest {
     /// Name of the step
     pub name: String,
@@ -41,10 +246,25 @@ pub struct SagaStepRequest {
     pub timeout_seconds: u64,
     /// Optional retry policy for this step
     pub retry_policy: Option<RetryPolicyRequest>,
+    /// Optional body sent to the service, with `{{...}}` references
+    pub payload: Option<serde_json::Value>,
+    /// Locks the step acquires, released when it is compensated
+    #[serde(default)]
+    pub acquired_locks: Vec<StepLock>,
+    /// Cache keys the step writes, deleted when it is compensated
+    #[serde(default)]
+    pub cache_keys: Vec<String>,
+    /// Parallel group of the step; consecutive steps of the same group run
+    /// concurrently
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Executor running the step, e.g. `manual`; `http` when unset
+    #[serde(default)]
+    pub executor: Option<String>,
 }
 
 /// Request structure for defining retry policy.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetryPolicyRequest {
     /// Maximum number of retries
     pub max_retries: u32,
@@ -54,6 +274,57 @@ pub struct RetryPolicyRequest {
     pub initial_delay_ms: u64,
 }
 
+/// Lock held for the lifetime of a saga started with
+/// [`start_saga_with_lock`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaLockRequest {
+    pub key: String,
+    pub ttl_seconds: u64,
+    pub owner: String,
+    /// How long to wait for a held lock before giving up
+    pub wait_timeout_seconds: Option<u64>,
+}
+
+/// Request body of [`start_saga_with_lock`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartSagaWithLockRequest {
+    pub lock: SagaLockRequest,
+    pub saga: StartSagaRequest,
+}
+
+/// Response of a saga started while holding a lock.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartSagaWithLockResponse {
+    pub saga_id: String,
+    pub success: bool,
+    pub message: String,
+    /// ID of the lock held until the saga ends
+    pub lock_id: String,
+}
+
+/// Response body of a saga not started because its lock is held.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SagaLockConflictResponse {
+    pub message: String,
+    /// Current holder of the lock, if it is still held
+    pub holder: Option<LockState>,
+}
+
+/// Query parameters accepted when starting a saga.
+#[derive(Debug, Default, Deserialize)]
+pub struct StartSagaQuery {
+    /// Validate and return the execution plan without starting the saga
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Response body of a saga definition that failed validation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SagaValidationResponse {
+    pub errors: Vec<SagaValidationError>,
+    pub warnings: Vec<String>,
+}
+
 /// Response structure for saga status information.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SagaStatusResponse {
@@ -71,6 +342,123 @@ pub struct SagaStatusResponse {
     pub updated_at: String,
     /// Optional metadata associated with the saga
     pub metadata: Option<serde_json::Value>,
+    /// Time the saga was scheduled to start at, if it was scheduled
+    pub start_at: Option<String>,
+    /// Deadline of the saga's global budget, if it has one
+    pub deadline_at: Option<String>,
+    /// Milliseconds left before the saga is cancelled, if it has a deadline
+    pub remaining_budget_ms: Option<u64>,
+    /// Why the saga stopped before completing
+    pub failure_reason: Option<String>,
+    /// Authenticated principal that started the saga
+    pub created_by: Option<String>,
+    /// Manual retries of the saga so far
+    #[serde(default)]
+    pub retry_count: u32,
+    /// Status, error, timestamps and attempts of every step, in step order
+    #[serde(default)]
+    pub step_results: Vec<StepExecution>,
+    /// Stored result of every step whose service answered, by step name;
+    /// `truncated` or `offloaded_to` marks a body that is not kept in full
+    #[serde(default)]
+    pub results: HashMap<String, StepResult>,
+}
+
+impl SagaStatusResponse {
+    fn from_saga(saga: Saga, metadata_policy: &MetadataPolicy) -> Self {
+        let remaining_budget = saga.remaining_budget(chrono::Utc::now());
+        let created_by = saga.created_by();
+        let retry_count = saga.retry_count();
+        let results = saga
+            .step_results
+            .iter()
+            .filter_map(|execution| {
+                let result = saga.step_result(&execution.step)?;
+                Some((execution.step.clone(), result))
+            })
+            .collect();
+
+        let metadata = if saga.metadata.is_null() {
+            None
+        } else {
+            let mut metadata = saga.metadata;
+            metadata_policy.redact_value(&mut metadata);
+            Some(metadata)
+        };
+
+        Self {
+            saga_id: saga.id,
+            name: saga.name,
+            status: saga.status,
+            current_step_index: saga.current_step.map(|s| s as usize),
+            created_at: saga.created_at.to_rfc3339(),
+            updated_at: saga.updated_at.to_rfc3339(),
+            metadata,
+            start_at: saga.start_at.map(|s| s.to_rfc3339()),
+            deadline_at: saga.deadline_at.map(|d| d.to_rfc3339()),
+            remaining_budget_ms: remaining_budget.map(|r| r.as_millis() as u64),
+            failure_reason: saga.failure_reason,
+            created_by,
+            retry_count,
+            step_results: saga.step_results,
+            results,
+        }
+    }
+}
+
+/// Query parameters of [`list_sagas`].
+#[derive(Debug, Default, Deserialize)]
+pub struct ListSagasQuery {
+    /// Only sagas in this status, ignoring case, e.g. `running`
+    pub status: Option<String>,
+    /// Only sagas that have not finished; the default with `service`
+    pub active: Option<bool>,
+    /// Only sagas whose name starts with this prefix
+    pub name_prefix: Option<String>,
+    /// Only sagas created after this RFC 3339 time
+    pub created_after: Option<String>,
+    /// Only sagas with a step calling this service
+    pub service: Option<String>,
+    /// Only sagas started by this principal
+    pub owner: Option<String>,
+    /// Most sagas to return
+    pub limit: Option<usize>,
+    /// Matching sagas to skip
+    #[serde(default)]
+    pub offset: usize,
+}
+
+impl ListSagasQuery {
+    /// The saga filter the query describes, or why it is invalid.
+    pub fn to_filter(&self) -> Result<SagaFilter, String> {
+        let status = self
+            .status
+            .as_deref()
+            .map(|status| {
+                SagaStatus::from_name(status)
+                    .ok_or_else(|| format!("Unknown saga status: {}", status))
+            })
+            .transpose()?;
+        let created_after = self
+            .created_after
+            .as_deref()
+            .map(|created_after| {
+                DateTime::parse_from_rfc3339(created_after)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|e| format!("Invalid created_after {}: {}", created_after, e))
+            })
+            .transpose()?;
+        Ok(SagaFilter {
+            status,
+            active_only: self.active.unwrap_or(self.service.is_some()),
+            name_prefix: self.name_prefix.clone(),
+            created_after,
+            service: self.service.clone(),
+            owner: self.owner.clone(),
+            limit: self.limit,
+            offset: self.offset,
+        })
+    }
 }
 
 /// Starts a new saga with the provided steps and configuration.
@@ -80,58 +468,242 @@ pub struct SagaStatusResponse {
 ///
 /// # Arguments
 ///
+/// With `dry_run` set in the body or the query, the definition is only
+/// validated and its execution plan returned; nothing is persisted or run.
+/// Invalid definitions are rejected with `422 Unprocessable Entity` and the
+/// errors of each step.
+///
+/// With an `Idempotency-Key` header, replaying the same request under the
+/// key returns the saga it started instead of starting another one, and a
+/// different request under the key is rejected with `409 Conflict`.
+///
+/// # Arguments
+///
 /// * `state` - API state containing the saga orchestrator
+/// * `headers` - Request headers; `X-Request-Id` is propagated to every step
+///   call and `Idempotency-Key` deduplicates retried starts
+/// * `query` - Query parameters, e.g. `dry_run`
 /// * `request` - Saga configuration including steps and metadata
 ///
 /// # Returns
 ///
-/// Returns a JSON response with saga information or an error status.
+/// Returns a JSON response with saga information, the execution plan of a
+/// dry run, or an error status.
 pub async fn start_saga(
     State(state): State<ApiState>,
+    Caller(created_by): Caller,
+    headers: HeaderMap,
+    Query(query): Query<StartSagaQuery>,
     Json(request): Json<StartSagaRequest>,
 ) -> impl IntoResponse {
-    let steps: Vec<SagaStep> = request
-        .steps
-        .into_iter()
-        .map(|step| SagaStep {
-            name: step.name,
-            service: step.service,
-            action: step.action,
-            compensation: step.compensation,
-            timeout: std::time::Duration::from_secs(step.timeout_seconds),
-            retry_policy: step.retry_policy.map(|rp| RetryPolicy {
-                max_retries: rp.max_retries,
-                backoff_strategy: match rp.backoff_strategy.as_str() {
-                    "exponential" => BackoffStrategy::Exponential,
-                    "linear" => BackoffStrategy::Linear,
-                    _ => BackoffStrategy::Fixed,
-                },
-                initial_delay: std::time::Duration::from_millis(rp.initial_delay_ms),
-            }),
-        })
-        .collect();
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let dry_run = query.dry_run || request.dry_run;
+    let idempotency_key = headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|v| v.to_str().unwrap_or_default().to_string());
+    if let Some(key) = &idempotency_key {
+        if let Err(e) = saga_idempotency::check_key(key) {
+            return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+        }
+    }
+    let definition = match resolve_definition(&state, &request).await {
+        Ok(definition) => definition,
+        Err(e) => return definition_failed(e),
+    };
+    let saga_request = match request.into_saga_request(
+        request_id,
+        created_by,
+        &state.metadata_policy,
+        definition.as_ref(),
+    ) {
+        Ok(saga_request) => saga_request,
+        Err(errors) => return validation_failed(errors, Vec::new()),
+    };
 
-    let metadata = request
-        .metadata
-        .and_then(|m| serde_json::from_value::<std::collections::HashMap<String, String>>(m).ok());
+    let mut plan = state.saga_orchestrator.plan_saga(&saga_request).await;
+    if !plan.is_valid() {
+        return validation_failed(plan.errors, plan.warnings);
+    }
+    if dry_run {
+        state.metadata_policy.redact(&mut plan.metadata);
+        return Json(plan).into_response();
+    }
 
-    let saga_request = SagaRequest {
-        name: request.name,
-        steps,
-        metadata,
+    let started = match idempotency_key {
+        Some(key) => {
+            state
+                .saga_orchestrator
+                .start_saga_idempotent(&key, saga_request)
+                .await
+        }
+        None => state
+            .saga_orchestrator
+            .start_saga(saga_request)
+            .await
+            .map(IdempotentStart::Started),
+    };
+    match started {
+        Ok(IdempotentStart::Started(response)) | Ok(IdempotentStart::Replayed(response)) => {
+            Json(response).into_response()
+        }
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(e) => {
+            eprintln!("Error starting saga: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Acquires a lock, then starts a saga that holds it until it ends.
+///
+/// The lock is recorded in the saga metadata, extended while the saga runs
+/// and released on any terminal state. If the lock cannot be acquired within
+/// `wait_timeout_seconds`, no saga is created and `409 Conflict` is returned
+/// with the current holder. Dry runs only validate the saga.
+pub async fn start_saga_with_lock(
+    State(state): State<ApiState>,
+    Caller(created_by): Caller,
+    headers: HeaderMap,
+    Json(request): Json<StartSagaWithLockRequest>,
+) -> impl IntoResponse {
+    if let Some(frozen) = reject_if_frozen(&state.namespace_freezes, &request.lock.key) {
+        return frozen;
+    }
+    if let Err(e) = state.config.locks.check_ttl(request.lock.ttl_seconds) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response();
+    }
+    if let Err(e) = state
+        .config
+        .locks
+        .check_wait_timeout(request.lock.wait_timeout_seconds)
+    {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let dry_run = request.saga.dry_run;
+    let definition = match resolve_definition(&state, &request.saga).await {
+        Ok(definition) => definition,
+        Err(e) => return definition_failed(e),
     };
+    let saga_request = match request.saga.into_saga_request(
+        request_id,
+        created_by.clone(),
+        &state.metadata_policy,
+        definition.as_ref(),
+    ) {
+        Ok(saga_request) => saga_request,
+        Err(errors) => return validation_failed(errors, Vec::new()),
+    };
+    if saga_request.start_at.is_some() {
+        // The lock would be held, and extended, until the saga starts.
+        return validation_failed(
+            vec![SagaValidationError::new(
+                None,
+                "start_at",
+                "sagas started under a lock cannot be scheduled",
+            )],
+            Vec::new(),
+        );
+    }
 
-    state.metrics.increment_sagas_started();
+    let mut plan = state.saga_orchestrator.plan_saga(&saga_request).await;
+    if !plan.is_valid() {
+        return validation_failed(plan.errors, plan.warnings);
+    }
+    if dry_run {
+        state.metadata_policy.redact(&mut plan.metadata);
+        return Json(plan).into_response();
+    }
 
-    match state.saga_orchestrator.start_saga(saga_request).await {
-        Ok(response) => Json(response).into_response(),
+    let lock = SagaLock {
+        key: request.lock.key,
+        ttl: std::time::Duration::from_secs(request.lock.ttl_seconds),
+        owner: request.lock.owner,
+        wait_timeout: request
+            .lock
+            .wait_timeout_seconds
+            .map(std::time::Duration::from_secs),
+        created_by,
+    };
+    match state
+        .saga_orchestrator
+        .start_saga_with_lock(lock, saga_request)
+        .await
+    {
+        Ok(LockedSagaStart::Started { saga, lock_id }) => Json(StartSagaWithLockResponse {
+            saga_id: saga.saga_id,
+            success: saga.success,
+            message: saga.message,
+            lock_id,
+        })
+        .into_response(),
+        Ok(LockedSagaStart::Conflict { message, holder }) => {
+            let holder = holder.map(|mut holder| {
+                holder.metadata = holder
+                    .metadata
+                    .map(|metadata| state.metadata_policy.redact_text(metadata));
+                holder
+            });
+            (
+                StatusCode::CONFLICT,
+                Json(SagaLockConflictResponse { message, holder }),
+            )
+                .into_response()
+        }
+        Err(crate::SyrosError::NamespaceLimitExceeded(message)) => {
+            (StatusCode::TOO_MANY_REQUESTS, message).into_response()
+        }
         Err(e) => {
-            eprintln!("Error starting saga: {:?}", e);
+            eprintln!("Error starting saga with lock: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Resolves the definition `request` starts from, if any.
+async fn resolve_definition(
+    state: &ApiState,
+    request: &StartSagaRequest,
+) -> Result<Option<SagaDefinition>, SyrosError> {
+    match &request.definition {
+        Some(reference) => state.saga_definitions.resolve(reference).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Unknown definitions and malformed references fail validation.
+fn definition_failed(error: SyrosError) -> axum::response::Response {
+    match error {
+        SyrosError::NotFound(message) | SyrosError::SagaError(message) => validation_failed(
+            vec![SagaValidationError::new(None, "definition", message)],
+            Vec::new(),
+        ),
+        e => {
+            eprintln!("Error resolving saga definition: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
+pub(crate) fn validation_failed(
+    errors: Vec<SagaValidationError>,
+    warnings: Vec<String>,
+) -> axum::response::Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(SagaValidationResponse { errors, warnings }),
+    )
+        .into_response()
+}
+
 /// Retrieves the current status of a saga by its ID.
 ///
 /// This handler returns detailed information about a saga's current state,
@@ -151,24 +723,7 @@ pub async fn get_saga_status(
 ) -> impl IntoResponse {
     match state.saga_orchestrator.get_saga_status(&saga_id).await {
         Ok(Some(saga)) => {
-            let status = saga.status.clone();
-
-            let metadata = if saga.metadata.is_null() {
-                None
-            } else {
-                Some(saga.metadata)
-            };
-
-            Json(SagaStatusResponse {
-                saga_id: saga.id,
-                name: saga.name,
-                status,
-                current_step_index: saga.current_step.map(|s| s as usize),
-                created_at: saga.created_at.to_rfc3339(),
-                updated_at: saga.updated_at.to_rfc3339(),
-                metadata,
-            })
-            .into_response()
+            Json(SagaStatusResponse::from_saga(saga, &state.metadata_policy)).into_response()
         }
         Ok(None) => StatusCode::NOT_FOUND.into_response(),
         Err(e) => {
@@ -177,3 +732,146 @@ pub async fn get_saga_status(
         }
     }
 }
+
+/// Request body of [`cancel_saga`].
+#[derive(Debug, Default, Deserialize)]
+pub struct CancelSagaRequest {
+    /// Why the saga is cancelled; defaults to `cancelled`
+    pub reason: Option<String>,
+}
+
+/// Cancels a pending, running or paused saga, compensating the steps that
+/// started, and returns its final state.
+///
+/// Answers `404 Not Found` for an unknown saga and `409 Conflict` for a saga
+/// that already finished.
+pub async fn cancel_saga(
+    State(state): State<ApiState>,
+    Path(saga_id): Path<String>,
+    body: Option<Json<CancelSagaRequest>>,
+) -> impl IntoResponse {
+    let reason = body
+        .and_then(|Json(body)| body.reason)
+        .unwrap_or_else(|| "cancelled".to_string());
+
+    match state.saga_orchestrator.cancel_saga(&saga_id, &reason).await {
+        Ok(()) => get_saga_status(State(state), Path(saga_id))
+            .await
+            .into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(e) => {
+            eprintln!("Error cancelling saga: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Request body of [`retry_saga`].
+#[derive(Debug, Default, Deserialize)]
+pub struct RetrySagaRequest {
+    /// Index of the step to execute again from; defaults to the first step
+    /// that did not complete
+    pub from_step: Option<usize>,
+}
+
+/// Executes a `Failed` or `CompensationFailed` saga again, keeping the
+/// results of the steps before the one it resumes from, and returns its
+/// state.
+///
+/// Answers `404 Not Found` for an unknown saga, `409 Conflict` for a saga in
+/// any other state, `400 Bad Request` for a `from_step` past a step that
+/// did not complete and `500 Internal Server Error` if the saga cannot be
+/// read or updated.
+pub async fn retry_saga(
+    State(state): State<ApiState>,
+    Path(saga_id): Path<String>,
+    body: Option<Json<RetrySagaRequest>>,
+) -> impl IntoResponse {
+    let from_step = body.and_then(|Json(body)| body.from_step);
+
+    match state
+        .saga_orchestrator
+        .retry_saga(&saga_id, from_step)
+        .await
+    {
+        Ok(()) => get_saga_status(State(state), Path(saga_id))
+            .await
+            .into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(SyrosError::SagaError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error retrying saga: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Request body of [`complete_step`].
+#[derive(Debug, Default, Deserialize)]
+pub struct CompleteStepRequest {
+    /// Recorded as the step's result
+    pub output: Option<serde_json::Value>,
+    /// Fails the step's attempt with this error instead of completing it
+    pub error: Option<String>,
+}
+
+/// Confirms a step parked by the `manual` executor, completing it with
+/// `output` or failing its attempt with `error`, and returns the saga's
+/// state.
+///
+/// Answers `404 Not Found` for an unknown saga or step, `400 Bad Request`
+/// for a step run by another executor and `409 Conflict` for a step that
+/// is not waiting for confirmation on this instance.
+pub async fn complete_step(
+    State(state): State<ApiState>,
+    Path((saga_id, step)): Path<(String, String)>,
+    body: Option<Json<CompleteStepRequest>>,
+) -> impl IntoResponse {
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let outcome = match body.error {
+        Some(error) => Err(SyrosError::SagaError(error)),
+        None => Ok(StepOutcome {
+            output: body.output.map(|output| output.to_string().into_bytes()),
+        }),
+    };
+
+    match state
+        .saga_orchestrator
+        .complete_step(&saga_id, &step, outcome)
+        .await
+    {
+        Ok(()) => get_saga_status(State(state), Path(saga_id))
+            .await
+            .into_response(),
+        Err(SyrosError::NotFound(message)) => (StatusCode::NOT_FOUND, message).into_response(),
+        Err(SyrosError::Conflict(message)) => (StatusCode::CONFLICT, message).into_response(),
+        Err(SyrosError::SagaError(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            eprintln!("Error completing saga step: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists summaries of the sagas matching the query, oldest first.
+///
+/// An unknown status or a malformed `created_after` answers
+/// `400 Bad Request`.
+pub async fn list_sagas(
+    State(state): State<ApiState>,
+    Query(query): Query<ListSagasQuery>,
+) -> impl IntoResponse {
+    let filter = match query.to_filter() {
+        Ok(filter) => filter,
+        Err(message) => return (StatusCode::BAD_REQUEST, message).into_response(),
+    };
+    match state.saga_orchestrator.list_sagas(&filter).await {
+        Ok(sagas) => Json(sagas).into_response(),
+        Err(e) => {
+            eprintln!("Error listing sagas: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Saga worker handlers for the Syros API.
+//!
+//! This module provides HTTP handlers for pull-mode saga workers:
+//! registration, heartbeats, claiming and completing steps, and the admin
+//! listing of workers with their active claims.
+
+use crate::api::rest::ApiState;
+use crate::core::saga_executors::StepOutcome;
+use crate::core::saga_workers::{ClaimedAttempt, WorkerRegistration};
+use crate::SyrosError;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+
+/// Request structure for registering a saga worker.
+#[derive(Debug, Deserialize)]
+pub struct RegisterWorkerRequest {
+    /// Service whose steps the worker executes
+    pub service: String,
+    /// Maximum number of steps the worker holds at once
+    pub capacity: u32,
+}
+
+/// Request structure for completing a claimed step.
+#[derive(Debug, Deserialize)]
+pub struct CompleteClaimRequest {
+    /// Saga, step, attempt and direction of the claim, as claimed
+    #[serde(flatten)]
+    pub claim: ClaimedAttempt,
+    /// Answer recorded as the step's result when it succeeded
+    #[serde(default)]
+    pub output: Option<serde_json::Value>,
+    /// Error the step failed with; it succeeded when absent
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Registers a pull-mode saga worker.
+///
+/// # Arguments
+///
+/// * `state` - API state containing the saga worker registry
+/// * `request` - Service name and capacity of the worker
+///
+/// # Returns
+///
+/// Returns the registered worker, including the ID used for heartbeats.
+pub async fn register_worker(
+    State(state): State<ApiState>,
+    Json(request): Json<RegisterWorkerRequest>,
+) -> impl IntoResponse {
+    let registration = WorkerRegistration {
+        service: request.service,
+        capacity: request.capacity,
+    };
+
+    match state.saga_workers.register(registration).await {
+        Ok(worker) => (StatusCode::CREATED, Json(worker)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Records a heartbeat for a worker and renews its claim leases.
+///
+/// Workers removed after missing heartbeats get `404` and must register again.
+pub async fn heartbeat(
+    State(state): State<ApiState>,
+    Path(worker_id): Path<String>,
+) -> impl IntoResponse {
+    match state.saga_workers.heartbeat(&worker_id).await {
+        Ok(worker) => Json(worker).into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Leases the next pending step for the worker's service.
+///
+/// Returns `204 No Content` when there is nothing to claim or the worker is
+/// at capacity.
+pub async fn claim_step(
+    State(state): State<ApiState>,
+    Path(worker_id): Path<String>,
+) -> impl IntoResponse {
+    match state.saga_workers.claim_next(&worker_id).await {
+        Ok(Some(claim)) => Json(claim).into_response(),
+        Ok(None) => StatusCode::NO_CONTENT.into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Releases a step the worker has finished, ending the attempt it was claimed
+/// for with its outcome.
+pub async fn complete_claim(
+    State(state): State<ApiState>,
+    Path(worker_id): Path<String>,
+    Json(request): Json<CompleteClaimRequest>,
+) -> impl IntoResponse {
+    let outcome = match request.error {
+        Some(error) => Err(SyrosError::SagaError(error)),
+        None => Ok(StepOutcome {
+            output: request.output.map(|output| output.to_string().into_bytes()),
+        }),
+    };
+
+    match state
+        .saga_workers
+        .complete_claim(&worker_id, &request.claim, outcome)
+        .await
+    {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::CONFLICT.into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Lists registered workers with their active claims.
+pub async fn list_workers(State(state): State<ApiState>) -> impl IntoResponse {
+    Json(state.saga_workers.list_workers().await)
+}
@@ -243,12 +243,10 @@ pub async fn get_events(
 }
 
 pub async fn set_cache(
-    State(state): State<ApiState>,
+    State(_state): State<ApiState>,
     Path(key): Path<String>,
     Json(_request): Json<SetCacheRequest>,
 ) -> Result<Json<SetCacheResponse>, StatusCode> {
-    state.metrics.set_cache_size(1.0);
-    
     Ok(Json(SetCacheResponse {
         key,
         success: true,
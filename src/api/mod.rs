@@ -1,10 +1,21 @@
+#[cfg(feature = "graphql")]
 pub mod graphql;
+#[cfg(feature = "grpc")]
 pub mod grpc;
+#[cfg(feature = "rest")]
 pub mod handlers;
+#[cfg(feature = "rest")]
 pub mod rest;
+#[cfg(feature = "rest")]
+pub mod timeout;
+#[cfg(feature = "websocket")]
 pub mod websocket;
 
+#[cfg(feature = "graphql")]
 pub use graphql::{create_schema, graphql_handler, graphql_playground};
+#[cfg(feature = "grpc")]
 pub use grpc::SyrosGrpcService;
+#[cfg(feature = "rest")]
 pub use rest::create_rest_router;
+#[cfg(feature = "websocket")]
 pub use websocket::WebSocketService;
@@ -4,22 +4,47 @@
 //! for the Syros. It provides endpoints for distributed locks,
 //! saga orchestration, event sourcing, caching, authentication, and RBAC.
 
+#[cfg(feature = "graphql")]
 use crate::api::graphql::{graphql_handler, graphql_playground};
+#[cfg(feature = "metrics")]
+use crate::api::handlers::metrics_handlers;
 use crate::api::handlers::{
-    auth_handlers, cache_handlers, event_handlers, health_handlers, lock_handlers, metrics_handlers,
-    rbac_handlers, saga_handlers,
+    admin_handlers, auth_handlers, cache_handlers, capabilities_handlers, component_handlers,
+    dead_letter_handlers, discovery_handlers, event_handlers, health_handlers, lock_handlers,
+    namespace_handlers, projection_handlers, rbac_handlers, saga_definition_handlers,
+    saga_handlers, saga_worker_handlers,
 };
-use crate::api::websocket::WebSocketService;
-use crate::auth::{AuthMiddleware, RBACManager};
+use crate::api::timeout::enforce_timeout;
+#[cfg(feature = "websocket")]
+use crate::api::websocket::{ConnectionIdentity, WebSocketService};
+#[cfg(feature = "websocket")]
+use crate::auth::Permission;
+use crate::auth::{AuthMiddleware, Principal, RBACManager};
 use crate::config::Config;
-use crate::core::{CacheManager, EventStore, LockManager, SagaOrchestrator};
+use crate::core::{
+    CacheManager, ComponentRegistry, DeadLetterQueue, EventStore, LockManager, MetadataPolicy,
+    NamespaceFreezes, Projections, SagaDefinitions, SagaOrchestrator, SagaWorkerRegistry,
+    ServiceDiscovery, TaskTracker,
+};
+#[cfg(feature = "metrics")]
 use crate::metrics::Metrics;
 use axum::{
-    extract::WebSocketUpgrade,
-    response::Response,
-    routing::{delete, get, post},
+    extract::{
+        rejection::RawPathParamsRejection, DefaultBodyLimit, FromRequestParts, RawPathParams,
+    },
+    handler::Handler,
+    http::request::Parts,
+    routing::{delete, get, post, put},
     Router,
 };
+#[cfg(feature = "websocket")]
+use axum::{
+    extract::{Query, WebSocketUpgrade},
+    http::HeaderMap,
+    response::Response,
+};
+#[cfg(feature = "websocket")]
+use serde::Deserialize;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 
@@ -32,22 +57,44 @@ use tower_http::cors::CorsLayer;
 pub struct ApiState {
     /// Application configuration
     pub config: Config,
+    /// ID this process registers under in service discovery
+    pub instance_id: String,
     /// Distributed lock manager
     pub lock_manager: LockManager,
     /// Saga orchestration service
     pub saga_orchestrator: SagaOrchestrator,
+    /// Registry of pull-mode saga workers
+    pub saga_workers: SagaWorkerRegistry,
+    /// Named saga definitions
+    pub saga_definitions: SagaDefinitions,
+    /// Sagas escalated after a failed compensation
+    pub dead_letters: DeadLetterQueue,
     /// Event store for event sourcing
     pub event_store: EventStore,
+    /// Projections of the event feed
+    pub projections: Projections,
     /// Cache manager for distributed caching
     pub cache_manager: CacheManager,
     /// WebSocket service for real-time communication
+    #[cfg(feature = "websocket")]
     pub websocket_service: Arc<WebSocketService>,
     /// Metrics collection service
+    #[cfg(feature = "metrics")]
     pub metrics: Arc<Metrics>,
     /// Authentication middleware
     pub auth_middleware: AuthMiddleware,
     /// Role-based access control manager
-    pub rbac_manager: Arc<tokio::sync::Mutex<RBACManager>>,
+    pub rbac_manager: Arc<RBACManager>,
+    /// Background components of this process
+    pub components: ComponentRegistry,
+    /// Limits and redaction applied to caller-supplied metadata
+    pub metadata_policy: MetadataPolicy,
+    /// Namespaces whose writes are frozen
+    pub namespace_freezes: NamespaceFreezes,
+    /// Service registry, when discovery is enabled
+    pub service_discovery: Option<Arc<ServiceDiscovery>>,
+    /// Tasks spawned by this process
+    pub tasks: TaskTracker,
 }
 
 impl axum::extract::FromRef<ApiState> for Config {
@@ -80,36 +127,128 @@ impl axum::extract::FromRef<ApiState> for CacheManager {
     }
 }
 
+#[cfg(feature = "metrics")]
 impl axum::extract::FromRef<ApiState> for Arc<Metrics> {
     fn from_ref(state: &ApiState) -> Self {
         state.metrics.clone()
     }
 }
 
+impl axum::extract::FromRef<ApiState> for MetadataPolicy {
+    fn from_ref(state: &ApiState) -> Self {
+        state.metadata_policy.clone()
+    }
+}
+
+impl axum::extract::FromRef<ApiState> for NamespaceFreezes {
+    fn from_ref(state: &ApiState) -> Self {
+        state.namespace_freezes.clone()
+    }
+}
+
+impl axum::extract::FromRef<ApiState> for TaskTracker {
+    fn from_ref(state: &ApiState) -> Self {
+        state.tasks.clone()
+    }
+}
+
 impl axum::extract::FromRef<ApiState> for AuthMiddleware {
     fn from_ref(state: &ApiState) -> Self {
         state.auth_middleware.clone()
     }
 }
 
+/// Subject of the authenticated caller of a request, resolved from the
+/// `Authorization` or `X-API-Key` header; `None` for anonymous requests.
+///
+/// Handlers record it as the `created_by` of the resources they create.
+#[derive(Debug, Clone, Default)]
+pub struct Caller(pub Option<String>);
+
+#[axum::async_trait]
+impl FromRequestParts<ApiState> for Caller {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ApiState,
+    ) -> Result<Self, Self::Rejection> {
+        let principal = Principal::from_headers(state, &parts.headers).await;
+        Ok(Caller(principal.map(|p| p.subject)))
+    }
+}
+
+/// The `:key` segment of a request path.
+///
+/// A static route with the path of a key, such as `/api/v1/locks/batch`,
+/// also serves the methods of the key's route it shadows; there the key is
+/// the route's last segment.
+#[derive(Debug, Clone)]
+pub struct KeyPath(pub String);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for KeyPath {
+    type Rejection = RawPathParamsRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let params = RawPathParams::from_request_parts(parts, state).await?;
+        let key = match params.iter().find(|(name, _)| *name == "key") {
+            Some((_, key)) => key.to_string(),
+            None => parts
+                .uri
+                .path()
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        Ok(KeyPath(key))
+    }
+}
+
+/// Query parameters accepted when opening a WebSocket connection.
+#[cfg(feature = "websocket")]
+#[derive(Debug, Default, Deserialize)]
+pub struct WebSocketParams {
+    /// Client whose private saga notifications the connection receives
+    pub client_id: Option<String>,
+}
+
 /// WebSocket connection handler.
 ///
 /// This function handles WebSocket upgrade requests and delegates
-/// the connection to the WebSocket service.
+/// the connection to the WebSocket service. The caller is identified from
+/// the `Authorization` or `X-API-Key` header, which decides the private
+/// notifications the connection receives.
 ///
 /// # Arguments
 ///
 /// * `ws` - WebSocket upgrade request
 /// * `state` - API state containing the WebSocket service
+/// * `headers` - Request headers carrying the caller's credentials
+/// * `params` - Optional `client_id` of the connection
 ///
 /// # Returns
 ///
 /// Returns a WebSocket response for the connection.
+#[cfg(feature = "websocket")]
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     axum::extract::State(state): axum::extract::State<ApiState>,
+    headers: HeaderMap,
+    Query(params): Query<WebSocketParams>,
 ) -> Response {
-    WebSocketService::handle_websocket(ws, axum::extract::State(state.websocket_service)).await
+    let principal = Principal::from_headers(&state, &headers).await;
+    let identity = ConnectionIdentity {
+        is_admin: principal
+            .as_ref()
+            .is_some_and(|p| p.has_permission(&Permission::AdminSystem)),
+        principal: principal.map(|p| p.subject),
+        client_id: params.client_id,
+    };
+
+    WebSocketService::handle_websocket(ws, axum::extract::State(state.websocket_service), identity)
+        .await
 }
 
 /// Creates the main REST API router with all endpoints.
@@ -127,28 +266,302 @@ async fn websocket_handler(
 /// Returns an Axum router configured with all API endpoints and middleware.
 pub fn create_rest_router(state: ApiState) -> Router {
     let cors_layer = CorsLayer::permissive();
+    let timeout_layer = axum::middleware::from_fn_with_state(
+        Arc::new(state.config.timeouts.clone()),
+        enforce_timeout,
+    );
 
-    Router::new()
+    let router = Router::new()
         .route("/health", get(health_handlers::health_check))
         .route("/ready", get(health_handlers::readiness_check))
         .route("/live", get(health_handlers::liveness_check))
-        .route("/metrics", get(metrics_handlers::metrics_handler))
-        .route("/api/v1/locks", post(lock_handlers::acquire_lock))
+        .route(
+            "/api/v1/capabilities",
+            get(capabilities_handlers::get_capabilities),
+        )
+        .route(
+            "/api/v1/locks",
+            get(lock_handlers::list_locks).post(lock_handlers::acquire_lock),
+        )
+        // Static routes shadowing a key's also serve the key's other methods
+        .route(
+            "/api/v1/locks/batch",
+            post(lock_handlers::acquire_locks_batch).delete(lock_handlers::release_lock),
+        )
+        .route(
+            "/api/v1/locks/stats",
+            get(lock_handlers::get_lock_stats).delete(lock_handlers::release_lock),
+        )
         .route("/api/v1/locks/:key", delete(lock_handlers::release_lock))
+        .route("/api/v1/locks/:key/extend", put(lock_handlers::extend_lock))
         .route(
             "/api/v1/locks/:key/status",
             get(lock_handlers::get_lock_status),
         )
-        .route("/api/v1/sagas", post(saga_handlers::start_saga))
+        .route(
+            "/api/v1/namespaces/:ns/locks",
+            get(lock_handlers::list_namespace_locks).post(lock_handlers::acquire_namespace_lock),
+        )
+        .route(
+            "/api/v1/namespaces/:ns/locks/:key",
+            delete(lock_handlers::release_namespace_lock),
+        )
+        .route(
+            "/api/v1/namespaces/:ns/locks/:key/extend",
+            put(lock_handlers::extend_namespace_lock),
+        )
+        .route(
+            "/api/v1/namespaces/:ns/locks/:key/status",
+            get(lock_handlers::get_namespace_lock_status),
+        )
+        .route(
+            "/api/v1/sagas",
+            get(saga_handlers::list_sagas).post(saga_handlers::start_saga),
+        )
+        .route(
+            "/api/v1/sagas/with-lock",
+            post(saga_handlers::start_saga_with_lock),
+        )
+        .route(
+            "/api/v1/sagas/dead-letter",
+            get(dead_letter_handlers::list_dead_letters),
+        )
+        .route(
+            "/api/v1/sagas/dead-letter/:saga_id",
+            delete(dead_letter_handlers::resolve_dead_letter),
+        )
+        .route(
+            "/api/v1/sagas/dead-letter/:saga_id/requeue",
+            post(dead_letter_handlers::requeue_dead_letter),
+        )
         .route(
             "/api/v1/sagas/:saga_id/status",
             get(saga_handlers::get_saga_status),
         )
-        .route("/api/v1/events", post(event_handlers::append_event))
+        .route(
+            "/api/v1/sagas/:saga_id/cancel",
+            post(saga_handlers::cancel_saga),
+        )
+        .route(
+            "/api/v1/sagas/:saga_id/retry",
+            post(saga_handlers::retry_saga),
+        )
+        .route(
+            "/api/v1/sagas/:saga_id/steps/:step/complete",
+            post(saga_handlers::complete_step),
+        )
+        .route(
+            "/api/v1/saga-definitions",
+            get(saga_definition_handlers::list_definitions),
+        )
+        .route(
+            "/api/v1/saga-definitions/:name",
+            get(saga_definition_handlers::get_definition)
+                .put(saga_definition_handlers::register_definition),
+        )
+        .route(
+            "/api/v1/saga-definitions/:name/versions",
+            get(saga_definition_handlers::list_definition_versions),
+        )
+        .route(
+            "/api/v1/saga-workers/register",
+            post(saga_worker_handlers::register_worker),
+        )
+        .route(
+            "/api/v1/saga-workers/:worker_id/heartbeat",
+            post(saga_worker_handlers::heartbeat),
+        )
+        .route(
+            "/api/v1/saga-workers/:worker_id/claim",
+            post(saga_worker_handlers::claim_step),
+        )
+        .route(
+            "/api/v1/saga-workers/:worker_id/complete",
+            post(saga_worker_handlers::complete_claim),
+        )
+        .route(
+            "/api/v1/admin/saga-workers",
+            get(saga_worker_handlers::list_workers),
+        )
+        .route(
+            "/api/v1/admin/locks/:key/queue",
+            get(lock_handlers::get_lock_queue),
+        )
+        .route("/api/v1/admin/status", get(admin_handlers::get_status))
+        .route(
+            "/api/v1/admin/services/:name/pause-sagas",
+            post(admin_handlers::pause_service_sagas),
+        )
+        .route(
+            "/api/v1/admin/services/:name/resume-sagas",
+            post(admin_handlers::resume_service_sagas),
+        )
+        .route(
+            "/api/v1/admin/namespaces",
+            get(namespace_handlers::list_namespaces),
+        )
+        .route(
+            "/api/v1/admin/namespaces/:ns/freeze",
+            post(namespace_handlers::freeze_namespace).delete(namespace_handlers::thaw_namespace),
+        )
+        .route(
+            "/api/v1/admin/components",
+            get(component_handlers::list_components),
+        )
+        .route("/api/v1/admin/tasks", get(component_handlers::list_tasks))
+        // The dead-letter routes under /api/v1/sagas, for operators
+        .route(
+            "/api/v1/admin/dead-letter",
+            get(dead_letter_handlers::list_dead_letters),
+        )
+        .route(
+            "/api/v1/admin/dead-letter/:saga_id/resolve",
+            post(dead_letter_handlers::resolve_dead_letter),
+        )
+        .route(
+            "/api/v1/admin/dead-letter/:saga_id/requeue",
+            post(dead_letter_handlers::requeue_dead_letter),
+        )
+        .route("/api/v1/events", get(event_handlers::read_all))
+        .route(
+            "/api/v1/events/:stream_id",
+            post(event_handlers::append_event),
+        )
         .route("/api/v1/events/:stream_id", get(event_handlers::get_events))
+        .route(
+            "/api/v1/events/:stream_id",
+            delete(event_handlers::delete_stream),
+        )
+        .route(
+            "/api/v1/events/:stream_id/archive",
+            post(event_handlers::archive_stream),
+        )
+        .route(
+            "/api/v1/events/:stream_id/info",
+            get(event_handlers::get_stream_info),
+        )
+        .route(
+            "/api/v1/events/:stream_id/poll",
+            get(event_handlers::poll_events),
+        )
+        // Bulk imports are streamed, so they are exempt from the default body limit
+        .route(
+            "/api/v1/streams",
+            get(event_handlers::list_streams)
+                .post(event_handlers::import_events.layer(DefaultBodyLimit::disable())),
+        )
+        .route(
+            "/api/v1/streams/:stream_id",
+            delete(event_handlers::delete_stream),
+        )
+        .route(
+            "/api/v1/streams/:stream_id/retention",
+            put(event_handlers::set_retention),
+        )
+        .route(
+            "/api/v1/streams/:stream_id/export",
+            get(event_handlers::export_stream),
+        )
+        .route(
+            "/api/v1/streams/:stream_id/snapshot",
+            put(event_handlers::save_snapshot).get(event_handlers::get_snapshot),
+        )
+        .route(
+            "/api/v1/projections/:name",
+            put(projection_handlers::register_projection),
+        )
+        .route(
+            "/api/v1/projections/:name/state",
+            get(projection_handlers::get_projection_state),
+        )
+        .route(
+            "/api/v1/projections/:name/rebuild",
+            post(projection_handlers::rebuild_projection),
+        )
+        .route("/api/v1/cache", get(cache_handlers::list_cache))
+        // Static routes shadowing a key's also serve the key's other methods
+        .route(
+            "/api/v1/cache/stats",
+            get(cache_handlers::get_cache_stats)
+                .post(cache_handlers::set_cache)
+                .put(cache_handlers::swap_cache)
+                .delete(cache_handlers::delete_cache),
+        )
+        .route(
+            "/api/v1/cache/tags/:tag",
+            delete(cache_handlers::invalidate_by_tag),
+        )
+        .route(
+            "/api/v1/cache/tags/:tag/keys",
+            get(cache_handlers::list_tag_keys),
+        )
+        .route(
+            "/api/v1/cache/mget",
+            post(cache_handlers::get_cache_batch)
+                .get(cache_handlers::get_cache)
+                .put(cache_handlers::swap_cache)
+                .delete(cache_handlers::delete_cache),
+        )
+        .route(
+            "/api/v1/cache/mset",
+            post(cache_handlers::set_cache_batch)
+                .get(cache_handlers::get_cache)
+                .put(cache_handlers::swap_cache)
+                .delete(cache_handlers::delete_cache),
+        )
+        .route(
+            "/api/v1/cache/mdelete",
+            post(cache_handlers::delete_cache_batch)
+                .get(cache_handlers::get_cache)
+                .put(cache_handlers::swap_cache)
+                .delete(cache_handlers::delete_cache),
+        )
         .route("/api/v1/cache/:key", post(cache_handlers::set_cache))
         .route("/api/v1/cache/:key", get(cache_handlers::get_cache))
         .route("/api/v1/cache/:key", delete(cache_handlers::delete_cache))
+        .route("/api/v1/cache/:key", put(cache_handlers::swap_cache))
+        .route(
+            "/api/v1/cache/:key/fetch",
+            post(cache_handlers::fetch_cache),
+        )
+        .route(
+            "/api/v1/cache/:key/incr",
+            post(cache_handlers::increment_cache),
+        )
+        .route("/api/v1/cache/:key/ttl", get(cache_handlers::get_cache_ttl))
+        .route(
+            "/api/v1/cache/:key/touch",
+            post(cache_handlers::touch_cache),
+        )
+        .route(
+            "/api/v1/cache/:key/tags",
+            post(cache_handlers::add_cache_tags),
+        )
+        .route(
+            "/api/v1/cache/:key/tags",
+            delete(cache_handlers::remove_cache_tags),
+        )
+        .route(
+            "/api/v1/discovery/services",
+            get(discovery_handlers::list_services),
+        )
+        .route(
+            "/api/v1/discovery/services",
+            post(discovery_handlers::register_service),
+        )
+        // The segment is a service name to GET and an instance ID to DELETE
+        .route(
+            "/api/v1/discovery/services/:name",
+            get(discovery_handlers::discover_service),
+        )
+        .route(
+            "/api/v1/discovery/services/:name",
+            delete(discovery_handlers::deregister_service),
+        )
+        .route(
+            "/api/v1/discovery/services/:name/health",
+            get(discovery_handlers::get_service_health),
+        )
         .route("/api/v1/auth/login", post(auth_handlers::login))
         .route("/api/v1/auth/token", post(auth_handlers::create_token))
         .route("/api/v1/auth/api-keys", post(auth_handlers::create_api_key))
@@ -197,10 +610,26 @@ pub fn create_rest_router(state: ApiState) -> Router {
         .route(
             "/api/v1/rbac/permissions/check/:user_id/:resource_id",
             post(rbac_handlers::check_resource_permission),
-        )
-        .route("/graphql", post(graphql_handler))
-        .route("/graphql-playground", get(graphql_playground))
-        .route("/ws", get(websocket_handler))
+        );
+    #[cfg(feature = "metrics")]
+    let router = router.route("/metrics", get(metrics_handlers::metrics_handler));
+    #[cfg(feature = "graphql")]
+    let router = if state.config.apis.graphql {
+        router
+            .route("/graphql", post(graphql_handler))
+            .route("/graphql-playground", get(graphql_playground))
+    } else {
+        router
+    };
+    #[cfg(feature = "websocket")]
+    let router = if state.config.apis.websocket {
+        router.route("/ws", get(websocket_handler))
+    } else {
+        router
+    };
+
+    router
+        .layer(timeout_layer)
         .layer(cors_layer)
         .with_state(state)
 }
@@ -0,0 +1,155 @@
+//! Request time budgets for the REST API.
+//!
+//! Every request runs under the budget configured for its route in
+//! [`TimeoutConfig`]. When the budget runs out the handler future is dropped,
+//! cancelling any manager call still in flight, and the client gets a `504`.
+
+use crate::config::TimeoutConfig;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Error code returned when a request exceeds its budget.
+pub const TIMEOUT_ERROR_CODE: &str = "request_timeout";
+
+/// Body of a `504 Gateway Timeout` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeoutErrorResponse {
+    /// Always [`TIMEOUT_ERROR_CODE`]
+    pub error: String,
+    /// Human-readable description
+    pub message: String,
+    /// Route pattern whose budget was exceeded
+    pub route: String,
+    /// Budget that was exceeded, in milliseconds
+    pub timeout_ms: u64,
+}
+
+/// Middleware enforcing the per-route time budget.
+pub async fn enforce_timeout(
+    State(timeouts): State<Arc<TimeoutConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let budget = timeouts.for_route(&route);
+
+    match tokio::time::timeout(budget, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Request to {} timed out after {:?}", route, budget);
+            let timeout_ms = budget.as_millis() as u64;
+            let body = TimeoutErrorResponse {
+                error: TIMEOUT_ERROR_CODE.to_string(),
+                message: format!("Request did not complete within {} ms", timeout_ms),
+                route,
+                timeout_ms,
+            };
+            (StatusCode::GATEWAY_TIMEOUT, Json(body)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+
+    /// Sets its flag when dropped, i.e. when the handler future is cancelled.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    async fn serve(timeouts: TimeoutConfig, cancelled: Arc<AtomicBool>) -> String {
+        let slow = move || {
+            let cancelled = cancelled.clone();
+            async move {
+                let _flag = DropFlag(cancelled);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                "done"
+            }
+        };
+        let app = Router::new()
+            .route("/slow", get(slow.clone()))
+            .route("/slow/:id/long", get(slow))
+            .route(
+                "/fast",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    "fast"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(timeouts),
+                enforce_timeout,
+            ));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_slow_route_times_out_with_504() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let base = serve(
+            TimeoutConfig {
+                default_ms: 100,
+                routes: HashMap::from([("/slow/:id/long".to_string(), 300)]),
+                grpc_max_ms: 100,
+            },
+            cancelled.clone(),
+        )
+        .await;
+        let client = reqwest::Client::new();
+
+        let started = Instant::now();
+        let response = client.get(format!("{}/slow", base)).send().await.unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(response.status(), reqwest::StatusCode::GATEWAY_TIMEOUT);
+        assert!(elapsed >= Duration::from_millis(100));
+        assert!(elapsed < Duration::from_secs(1), "took {:?}", elapsed);
+
+        let body: TimeoutErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.error, TIMEOUT_ERROR_CODE);
+        assert_eq!(body.route, "/slow");
+        assert_eq!(body.timeout_ms, 100);
+        assert!(
+            cancelled.load(Ordering::SeqCst),
+            "handler was not cancelled"
+        );
+
+        // The override applies to the route pattern, not the concrete path.
+        let started = Instant::now();
+        let response = client
+            .get(format!("{}/slow/42/long", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        let body: TimeoutErrorResponse = response.json().await.unwrap();
+        assert_eq!(body.timeout_ms, 300);
+
+        let response = client.get(format!("{}/fast", base)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
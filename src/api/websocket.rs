@@ -2,20 +2,75 @@
 //!
 //! This module provides WebSocket functionality for real-time updates
 //! and communication with the Syros distributed coordination service.
+//!
+//! Saga status notifications are private: a saga only notifies connections
+//! authenticated as the principal that started it, narrowed to those opened
+//! with the same `client_id` when it was started with one. Sagas started
+//! anonymously notify authenticated connections only. Admins can join the
+//! [`ADMIN_CHANNEL`] to see every notification.
+//!
+//! Events are appended with `event.append` commands numbered by the client.
+//! Each connection opens an append session whose resume token is sent in the
+//! welcome message; after a reconnect, `resume` rejoins the session, and
+//! replayed commands are re-acked instead of appended twice.
+//!
+//! `subscribe_stream` with a stream ID, or a prefix followed by `*`, pushes
+//! every event appended to the matching streams as an `event.appended`
+//! message carrying the event, with its `correlation_id` and `causation_id`
+//! lifted from the metadata for routing.
+//!
+//! The welcome message also carries the connection's lock `session_id`.
+//! Locks acquired with it, over any API, are released when the connection
+//! closes.
 
-use crate::core::{CacheManager, EventStore, LockManager, SagaOrchestrator};
+use crate::api::handlers::saga_handlers::StartSagaRequest;
+use crate::config::WebSocketConfig;
+use crate::core::event_store::{
+    Event, EventRequest, CAUSATION_ID_METADATA_KEY, CORRELATION_ID_METADATA_KEY,
+};
+use crate::core::event_subscriptions::{stream_matches, EventSubscription};
+use crate::core::saga_dead_letter::SystemNotification;
+use crate::core::saga_orchestrator::SagaStatusUpdate;
+use crate::core::{
+    CacheManager, EventStore, LockManager, MetadataPolicy, NamespaceFreezes, SagaOrchestrator,
+    TaskTracker,
+};
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
         State,
     },
     response::Response,
 };
-use futures::{SinkExt, StreamExt};
+use futures::{Sink, SinkExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 
+/// Close code sent when a connection keeps violating its limits (RFC 6455 policy violation).
+pub const POLICY_VIOLATION_CLOSE_CODE: u16 = 1008;
+
+/// Messages up to this multiple of `max_message_bytes` are read and rejected
+/// by the connection limiter with a `message_too_large` reply; the transport
+/// refuses larger ones before buffering them, which drops the connection.
+const TRANSPORT_LIMIT_FACTOR: usize = 4;
+
+/// Violations older than this no longer count towards closing the connection.
+const VIOLATION_WINDOW: Duration = Duration::from_secs(60);
+
+/// Channel admins subscribe to in order to receive every private notification.
+pub const ADMIN_CHANNEL: &str = "admin";
+
+/// Acks remembered per append session for re-acking replayed commands.
+const APPEND_ACK_HISTORY: usize = 256;
+
+/// Append sessions idle for longer than this can no longer be resumed.
+const APPEND_SESSION_IDLE: Duration = Duration::from_secs(15 * 60);
+
 /// WebSocket message structure for real-time communication.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketMessage {
@@ -27,16 +82,114 @@ pub struct WebSocketMessage {
     pub timestamp: String,
 }
 
+/// Connections a message is delivered to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Audience {
+    /// Every connection
+    All,
+    /// Authenticated connections, plus the admin channel
+    Authenticated,
+    /// Connections of the given principal, narrowed to the given client if
+    /// the connection names one, plus the admin channel
+    Owner {
+        principal: Option<String>,
+        client_id: Option<String>,
+    },
+    /// Connections subscribed to a pattern matching the stream ID
+    Stream(String),
+}
+
+/// A message together with the connections it is addressed to.
+#[derive(Debug, Clone)]
+pub struct Dispatch {
+    pub audience: Audience,
+    pub message: WebSocketMessage,
+}
+
+impl Dispatch {
+    /// Addresses `message` to every connection.
+    pub fn to_all(message: WebSocketMessage) -> Self {
+        Self {
+            audience: Audience::All,
+            message,
+        }
+    }
+}
+
+/// Who is on the other end of a connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionIdentity {
+    /// Authenticated principal: the JWT subject or API key ID
+    pub principal: Option<String>,
+    /// Client ID given when the connection was opened; it only tells apart
+    /// the connections of one principal
+    pub client_id: Option<String>,
+    /// Whether the principal may join the [`ADMIN_CHANNEL`]
+    pub is_admin: bool,
+}
+
+impl ConnectionIdentity {
+    /// Whether a message for `audience` should be delivered to this connection.
+    pub fn receives(&self, audience: &Audience, admin_channel: bool) -> bool {
+        match audience {
+            Audience::All => true,
+            Audience::Authenticated => admin_channel || self.principal.is_some(),
+            Audience::Owner {
+                principal,
+                client_id,
+            } => {
+                let same_client = match (client_id, &self.client_id) {
+                    (Some(wanted), Some(own)) => wanted == own,
+                    _ => true,
+                };
+                admin_channel
+                    || (principal.is_some() && *principal == self.principal && same_client)
+            }
+            // Stream subscriptions belong to the connection, not its identity.
+            Audience::Stream(_) => false,
+        }
+    }
+}
+
+impl From<SagaStatusUpdate> for Dispatch {
+    /// Sagas started anonymously notify every authenticated connection.
+    fn from(update: SagaStatusUpdate) -> Self {
+        let audience = if update.owner.is_none() {
+            Audience::Authenticated
+        } else {
+            Audience::Owner {
+                principal: update.owner.clone(),
+                client_id: update.client_id.clone(),
+            }
+        };
+        Self {
+            audience,
+            message: WebSocketMessage {
+                r#type: "saga.status".to_string(),
+                data: serde_json::to_value(&update).unwrap_or_default(),
+                timestamp: update.timestamp.to_rfc3339(),
+            },
+        }
+    }
+}
+
 /// WebSocket service for handling real-time connections.
 ///
 /// This service manages WebSocket connections and provides real-time
 /// updates for distributed coordination operations.
 pub struct WebSocketService {
-    _lock_manager: Arc<LockManager>,
-    _saga_orchestrator: Arc<SagaOrchestrator>,
-    _event_store: Arc<EventStore>,
+    lock_manager: Arc<LockManager>,
+    saga_orchestrator: Arc<SagaOrchestrator>,
+    event_store: Arc<EventStore>,
+    append_sessions: AppendSessions,
     _cache_manager: Arc<CacheManager>,
-    event_sender: broadcast::Sender<WebSocketMessage>,
+    event_sender: broadcast::Sender<Dispatch>,
+    limits: WebSocketConfig,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
+    metadata_policy: MetadataPolicy,
+    namespace_freezes: NamespaceFreezes,
+    tasks: TaskTracker,
 }
 
 impl WebSocketService {
@@ -61,23 +214,64 @@ impl WebSocketService {
         let (event_sender, _) = broadcast::channel(1000);
 
         Self {
-            _lock_manager: Arc::new(lock_manager),
-            _saga_orchestrator: Arc::new(saga_orchestrator),
-            _event_store: Arc::new(event_store),
+            lock_manager: Arc::new(lock_manager),
+            saga_orchestrator: Arc::new(saga_orchestrator),
+            event_store: Arc::new(event_store),
+            append_sessions: AppendSessions::default(),
             _cache_manager: Arc::new(cache_manager),
             event_sender,
+            limits: WebSocketConfig::default(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            metadata_policy: MetadataPolicy::default(),
+            namespace_freezes: NamespaceFreezes::new(),
+            tasks: TaskTracker::new(),
         }
     }
 
+    /// Sets the per-connection command rate and message size limits.
+    pub fn with_limits(mut self, limits: WebSocketConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Attaches metrics used to count commands rejected by the limits.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sets the limits that metadata in commands is checked against.
+    pub fn with_metadata_policy(mut self, metadata_policy: MetadataPolicy) -> Self {
+        self.metadata_policy = metadata_policy;
+        self
+    }
+
+    /// Refuses appends to streams in the namespaces frozen in `namespace_freezes`.
+    pub fn with_namespace_freezes(mut self, namespace_freezes: NamespaceFreezes) -> Self {
+        self.namespace_freezes = namespace_freezes;
+        self
+    }
+
+    /// Counts connections and spawns the notification relays through `tasks`.
+    pub fn with_task_tracker(mut self, tasks: TaskTracker) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
     /// Handles WebSocket upgrade requests.
     ///
     /// This method upgrades HTTP connections to WebSocket and starts
-    /// the WebSocket handler for real-time communication.
+    /// the WebSocket handler for real-time communication. Frames and
+    /// messages far over `max_message_bytes` are refused before they are
+    /// buffered, which closes the connection.
     ///
     /// # Arguments
     ///
     /// * `ws` - WebSocket upgrade request
     /// * `state` - WebSocket service state
+    /// * `identity` - Principal and client ID resolved from the upgrade request
     ///
     /// # Returns
     ///
@@ -85,89 +279,1081 @@ impl WebSocketService {
     pub async fn handle_websocket(
         ws: WebSocketUpgrade,
         State(state): State<Arc<Self>>,
+        identity: ConnectionIdentity,
     ) -> Response {
-        ws.on_upgrade(|socket| handle_socket(socket, state))
+        let tasks = state.tasks.clone();
+        let max_bytes = state
+            .limits
+            .max_message_bytes
+            .saturating_mul(TRANSPORT_LIMIT_FACTOR);
+        ws.max_message_size(max_bytes)
+            .max_frame_size(max_bytes)
+            .on_upgrade(move |socket| {
+                tasks.track(
+                    "websocket_connection",
+                    handle_socket(socket, state, identity),
+                )
+            })
+    }
+
+    /// Relays operator notifications to every connected client.
+    ///
+    /// Each notification is broadcast as a `system.notification` message.
+    pub fn forward_notifications(
+        &self,
+        notifications: broadcast::Receiver<SystemNotification>,
+    ) -> tokio::task::JoinHandle<()> {
+        relay(
+            &self.tasks,
+            notifications,
+            self.event_sender.clone(),
+            |notification| {
+                Dispatch::to_all(WebSocketMessage {
+                    r#type: "system.notification".to_string(),
+                    data: serde_json::to_value(&notification).unwrap_or_default(),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                })
+            },
+        )
+    }
+
+    /// Relays saga status transitions as `saga.status` messages.
+    ///
+    /// Each transition goes only to the saga's owner, its client and the
+    /// admin channel; see [`Dispatch::from`].
+    pub fn forward_saga_updates(
+        &self,
+        updates: broadcast::Receiver<SagaStatusUpdate>,
+    ) -> tokio::task::JoinHandle<()> {
+        relay(
+            &self.tasks,
+            updates,
+            self.event_sender.clone(),
+            Dispatch::from,
+        )
+    }
+
+    /// Relays appended events as `event.appended` messages to the
+    /// connections subscribed to their stream, with their metadata redacted
+    /// as in REST responses.
+    pub fn forward_appended_events(
+        &self,
+        mut subscription: EventSubscription,
+    ) -> tokio::task::JoinHandle<()> {
+        let event_sender = self.event_sender.clone();
+        let metadata_policy = self.metadata_policy.clone();
+        self.tasks.spawn_background("event_relay", async move {
+            while let Some(event) = subscription.recv().await {
+                let _ = event_sender.send(Dispatch {
+                    audience: Audience::Stream(event.stream_id.clone()),
+                    message: WebSocketMessage {
+                        r#type: "event.appended".to_string(),
+                        data: appended_event_data(&event, &metadata_policy),
+                        timestamp: event.timestamp.to_rfc3339(),
+                    },
+                });
+            }
+        })
     }
 
     /// Gets the event sender for broadcasting messages.
     ///
     /// This method returns a clone of the event sender that can be used
-    /// to broadcast messages to all connected WebSocket clients.
+    /// to send messages to the connected WebSocket clients in their audience.
     ///
     /// # Returns
     ///
-    /// Returns a broadcast sender for WebSocket messages.
-    pub fn get_event_sender(&self) -> broadcast::Sender<WebSocketMessage> {
+    /// Returns a broadcast sender for addressed WebSocket messages.
+    pub fn get_event_sender(&self) -> broadcast::Sender<Dispatch> {
         self.event_sender.clone()
     }
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<WebSocketService>) {
-    let mut rx = state.event_sender.subscribe();
+/// Data of an `event.appended` message: the event, with its metadata
+/// redacted by `policy` and its correlation and causation IDs copied from
+/// the metadata to the top level.
+fn appended_event_data(event: &Event, policy: &MetadataPolicy) -> serde_json::Value {
+    let mut event = event.clone();
+    policy.redact(&mut event.metadata);
+    let mut data = serde_json::to_value(&event).unwrap_or_default();
+    if let Some(fields) = data.as_object_mut() {
+        for key in [CORRELATION_ID_METADATA_KEY, CAUSATION_ID_METADATA_KEY] {
+            if let Some(value) = event.metadata.get(key) {
+                fields.insert(key.to_string(), serde_json::Value::from(value.clone()));
+            }
+        }
+    }
+    data
+}
+
+/// Token bucket limiting the command rate of a single connection.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket holding `burst` tokens, refilled at `rate` per second.
+    pub fn new(rate: u32, burst: u32, now: Instant) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_second: f64::from(rate),
+            last_refill: now,
+        }
+    }
+
+    /// Takes one token if available.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A limit broken by an inbound message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitViolation {
+    /// The connection sent commands faster than its token bucket allows
+    RateLimited,
+    /// The message exceeded the configured maximum size
+    MessageTooLarge { size: usize, max: usize },
+}
+
+impl LimitViolation {
+    /// Stable error code reported to clients and used as the metrics label.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitViolation::RateLimited => "rate_limited",
+            LimitViolation::MessageTooLarge { .. } => "message_too_large",
+        }
+    }
+
+    fn to_message(&self) -> WebSocketMessage {
+        let detail = match self {
+            LimitViolation::RateLimited => "Command rate limit exceeded".to_string(),
+            LimitViolation::MessageTooLarge { size, max } => {
+                format!("Message of {} bytes exceeds the {} byte limit", size, max)
+            }
+        };
+
+        error_message(self.code(), &detail)
+    }
+}
+
+/// Decision for an inbound message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Process the command
+    Accept,
+    /// Reply with an error and keep the connection open
+    Reject(LimitViolation),
+    /// Reply with an error and close the connection
+    Close(LimitViolation),
+}
+
+/// Enforces the command rate and message size limits of one connection.
+#[derive(Debug, Clone)]
+pub struct ConnectionLimiter {
+    limits: WebSocketConfig,
+    bucket: TokenBucket,
+    violations: u32,
+    last_violation: Option<Instant>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(limits: WebSocketConfig, now: Instant) -> Self {
+        let bucket = TokenBucket::new(limits.commands_per_second, limits.burst, now);
+        Self {
+            limits,
+            bucket,
+            violations: 0,
+            last_violation: None,
+        }
+    }
+
+    /// Checks a message of `size` bytes received at `now`.
+    ///
+    /// Oversized messages are rejected without spending a token. Once more than
+    /// `max_violations` rejections happen without a quiet minute in between,
+    /// the connection is closed.
+    pub fn admit(&mut self, size: usize, now: Instant) -> Admission {
+        let violation = if size > self.limits.max_message_bytes {
+            LimitViolation::MessageTooLarge {
+                size,
+                max: self.limits.max_message_bytes,
+            }
+        } else if self.bucket.try_acquire(now) {
+            return Admission::Accept;
+        } else {
+            LimitViolation::RateLimited
+        };
+
+        if self
+            .last_violation
+            .is_some_and(|last| now.saturating_duration_since(last) > VIOLATION_WINDOW)
+        {
+            self.violations = 0;
+        }
+        self.violations += 1;
+        self.last_violation = Some(now);
+
+        if self.violations > self.limits.max_violations {
+            Admission::Close(violation)
+        } else {
+            Admission::Reject(violation)
+        }
+    }
+}
+
+/// Converts everything received on `source` and sends it to the connections.
+fn relay<T: Clone + Send + 'static>(
+    tasks: &TaskTracker,
+    mut source: broadcast::Receiver<T>,
+    event_sender: broadcast::Sender<Dispatch>,
+    to_dispatch: fn(T) -> Dispatch,
+) -> tokio::task::JoinHandle<()> {
+    tasks.spawn_background("notification_relay", async move {
+        loop {
+            match source.recv().await {
+                Ok(item) => {
+                    let _ = event_sender.send(to_dispatch(item));
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+/// Ack for an `event.append` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendAck {
+    /// Sequence number the client gave the command
+    pub sequence: u64,
+    pub stream_id: String,
+    pub event_id: String,
+    /// Version the event was assigned in its stream
+    pub version: i64,
+    /// Whether the command was a replay of one already applied
+    #[serde(default)]
+    pub duplicate: bool,
+}
+
+/// Sequence bookkeeping of one append session.
+struct AppendSession {
+    last_sequence: u64,
+    acks: VecDeque<AppendAck>,
+    last_used: Instant,
+}
+
+impl AppendSession {
+    fn new() -> Self {
+        Self {
+            last_sequence: 0,
+            acks: VecDeque::new(),
+            last_used: Instant::now(),
+        }
+    }
+
+    /// The ack to repeat for a replayed `sequence`, if it was applied.
+    fn replayed(&self, sequence: u64) -> Option<AppendAck> {
+        self.acks
+            .iter()
+            .find(|ack| ack.sequence == sequence)
+            .map(|ack| AppendAck {
+                duplicate: true,
+                ..ack.clone()
+            })
+    }
+
+    fn record(&mut self, ack: AppendAck) {
+        self.last_sequence = ack.sequence;
+        if self.acks.len() == APPEND_ACK_HISTORY {
+            self.acks.pop_front();
+        }
+        self.acks.push_back(ack);
+    }
+}
+
+type AppendSessionKey = (Option<String>, String);
+
+/// Append sessions of every connection, keyed by principal and resume token,
+/// so they outlive the connection that opened them.
+#[derive(Clone, Default)]
+struct AppendSessions {
+    sessions: Arc<Mutex<HashMap<AppendSessionKey, SharedAppendSession>>>,
+}
+
+type SharedAppendSession = Arc<tokio::sync::Mutex<AppendSession>>;
+
+impl AppendSessions {
+    /// The session for `key`, opened on first use.
+    fn open(&self, key: &AppendSessionKey) -> SharedAppendSession {
+        self.pruned()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(AppendSession::new())))
+            .clone()
+    }
+
+    /// The session for `key`, if it is still open.
+    fn find(&self, key: &AppendSessionKey) -> Option<SharedAppendSession> {
+        self.pruned().get(key).cloned()
+    }
+
+    /// The sessions, without those idle for longer than
+    /// [`APPEND_SESSION_IDLE`]. Sessions locked by a command are kept.
+    fn pruned(&self) -> std::sync::MutexGuard<'_, HashMap<AppendSessionKey, SharedAppendSession>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| {
+            session.try_lock().map_or(true, |session| {
+                session.last_used.elapsed() < APPEND_SESSION_IDLE
+            })
+        });
+        sessions
+    }
+}
+
+/// Body of an `event.append` command.
+#[derive(Debug, Deserialize)]
+struct AppendCommand {
+    sequence: u64,
+    stream_id: String,
+    event_type: String,
+    data: serde_json::Value,
+    metadata: Option<HashMap<String, String>>,
+    correlation_id: Option<String>,
+    causation_id: Option<String>,
+}
+
+/// Per-connection state for handling commands and filtering deliveries.
+struct Session {
+    identity: ConnectionIdentity,
+    admin_channel: bool,
+    /// Stream IDs and prefix patterns of the `subscribe_stream` commands
+    stream_subscriptions: Vec<String>,
+    saga_orchestrator: Option<Arc<SagaOrchestrator>>,
+    metadata_policy: MetadataPolicy,
+    event_store: Option<Arc<EventStore>>,
+    namespace_freezes: NamespaceFreezes,
+    append_sessions: AppendSessions,
+    resume_token: String,
+    /// Lock session of the connection, closed when it disconnects
+    session_id: String,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl Session {
+    fn new(
+        identity: ConnectionIdentity,
+        saga_orchestrator: Option<Arc<SagaOrchestrator>>,
+        metadata_policy: MetadataPolicy,
+    ) -> Self {
+        Self {
+            identity,
+            admin_channel: false,
+            stream_subscriptions: Vec::new(),
+            saga_orchestrator,
+            metadata_policy,
+            event_store: None,
+            namespace_freezes: NamespaceFreezes::new(),
+            append_sessions: AppendSessions::default(),
+            resume_token: uuid::Uuid::new_v4().to_string(),
+            session_id: uuid::Uuid::new_v4().to_string(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Counts commands rejected by the connection limits.
+    #[cfg(feature = "metrics")]
+    fn with_metrics(mut self, metrics: Option<Arc<Metrics>>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn count_rejection(&self, violation: &LimitViolation) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_websocket_commands_rejected(violation.code());
+        }
+    }
+
+    /// Accepts `event.append` commands, keeping their sequence numbers in
+    /// `append_sessions`.
+    fn with_event_store(
+        mut self,
+        event_store: Arc<EventStore>,
+        append_sessions: AppendSessions,
+    ) -> Self {
+        self.event_store = Some(event_store);
+        self.append_sessions = append_sessions;
+        self
+    }
+
+    /// Refuses appends to streams in the namespaces frozen in `namespace_freezes`.
+    fn with_namespace_freezes(mut self, namespace_freezes: NamespaceFreezes) -> Self {
+        self.namespace_freezes = namespace_freezes;
+        self
+    }
+
+    fn append_session_key(&self) -> AppendSessionKey {
+        (self.identity.principal.clone(), self.resume_token.clone())
+    }
+
+    fn receives(&self, dispatch: &Dispatch) -> bool {
+        match &dispatch.audience {
+            Audience::Stream(stream_id) => self
+                .stream_subscriptions
+                .iter()
+                .any(|pattern| stream_matches(pattern, stream_id)),
+            audience => self.identity.receives(audience, self.admin_channel),
+        }
+    }
+
+    async fn handle_command(&mut self, text: &str) -> Option<WebSocketMessage> {
+        let parsed = serde_json::from_str::<serde_json::Value>(text).ok()?;
+        match parsed.get("type").and_then(|v| v.as_str())? {
+            "subscribe"
+                if parsed.get("channel").and_then(|v| v.as_str()) == Some(ADMIN_CHANNEL) =>
+            {
+                if !self.identity.is_admin {
+                    return Some(error_message(
+                        "forbidden",
+                        "The admin channel requires admin privileges",
+                    ));
+                }
+                self.admin_channel = true;
+                Some(WebSocketMessage {
+                    r#type: "subscribed".to_string(),
+                    data: serde_json::json!({ "channel": ADMIN_CHANNEL }),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                })
+            }
+            "subscribe_stream" => Some(self.subscribe_stream(parsed.get("stream_id")?.as_str()?)),
+            "start_saga" => Some(self.start_saga(parsed.get("data")?.clone()).await),
+            "resume" => Some(self.resume(parsed.get("resume_token")?.as_str()?).await),
+            "event.append" => Some(self.append_event(parsed.get("data")?.clone()).await),
+            _ => handle_command(text),
+        }
+    }
+
+    /// Pushes the events appended to the streams matching `pattern` to this
+    /// connection.
+    fn subscribe_stream(&mut self, pattern: &str) -> WebSocketMessage {
+        if pattern.is_empty() {
+            return error_message("invalid_request", "stream_id must not be empty");
+        }
+        if !self.stream_subscriptions.iter().any(|p| p == pattern) {
+            self.stream_subscriptions.push(pattern.to_string());
+        }
+        WebSocketMessage {
+            r#type: "subscribed".to_string(),
+            data: serde_json::json!({ "stream_id": pattern }),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Rejoins the append session of an earlier connection of the same
+    /// principal.
+    async fn resume(&mut self, resume_token: &str) -> WebSocketMessage {
+        let key = (self.identity.principal.clone(), resume_token.to_string());
+        let Some(session) = self.append_sessions.find(&key) else {
+            return error_message(
+                "unknown_session",
+                "No append session to resume for this resume token",
+            );
+        };
+        let last_sequence = session.lock().await.last_sequence;
+        self.resume_token = key.1;
+
+        WebSocketMessage {
+            r#type: "resumed".to_string(),
+            data: serde_json::json!({
+                "resume_token": self.resume_token,
+                "last_sequence": last_sequence,
+            }),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Appends an event unless its sequence number was already applied in
+    /// this append session, in which case the original ack is repeated.
+    async fn append_event(&self, data: serde_json::Value) -> WebSocketMessage {
+        let Some(event_store) = &self.event_store else {
+            return error_message(
+                "unavailable",
+                "Events cannot be appended on this connection",
+            );
+        };
+        let command: AppendCommand = match serde_json::from_value(data) {
+            Ok(command) => command,
+            Err(e) => return error_message("invalid_request", &e.to_string()),
+        };
+        if command.sequence == 0 {
+            return error_message("invalid_request", "Sequence numbers start at 1");
+        }
+        if let Some(metadata) = &command.metadata {
+            if let Err(e) = self.metadata_policy.check(metadata) {
+                return error_message("invalid_request", &e.to_string());
+            }
+        }
+        if let Some(freeze) = self.namespace_freezes.check(&command.stream_id) {
+            return error_message(
+                "namespace_frozen",
+                &format!(
+                    "Namespace {} is frozen until {}",
+                    freeze.namespace,
+                    freeze.frozen_until.to_rfc3339()
+                ),
+            );
+        }
+
+        let session = self.append_sessions.open(&self.append_session_key());
+        let mut session = session.lock().await;
+        session.last_used = Instant::now();
+
+        let ack = if command.sequence <= session.last_sequence {
+            match session.replayed(command.sequence) {
+                Some(ack) => ack,
+                None => {
+                    return error_message(
+                        "stale_sequence",
+                        &format!(
+                            "Sequence {} is not above the last applied sequence {} and has no recorded ack",
+                            command.sequence, session.last_sequence
+                        ),
+                    )
+                }
+            }
+        } else {
+            let request = EventRequest {
+                stream_id: command.stream_id.clone(),
+                event_type: command.event_type,
+                data: command.data,
+                metadata: command.metadata,
+                created_by: self.identity.principal.clone(),
+                expected_version: None,
+                event_id: None,
+                correlation_id: command.correlation_id,
+                causation_id: command.causation_id,
+            };
+            let response = match event_store.append_event(request).await {
+                Ok(response) => response,
+                Err(e) => return error_message("append_failed", &e.to_string()),
+            };
+            let ack = AppendAck {
+                sequence: command.sequence,
+                stream_id: command.stream_id,
+                event_id: response.event_id,
+                version: response.version,
+                duplicate: false,
+            };
+            session.record(ack.clone());
+            ack
+        };
+
+        WebSocketMessage {
+            r#type: "event.appended".to_string(),
+            data: serde_json::to_value(&ack).unwrap_or_default(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Starts a saga whose notifications are private to this connection's
+    /// principal and client.
+    async fn start_saga(&self, data: serde_json::Value) -> WebSocketMessage {
+        let Some(orchestrator) = &self.saga_orchestrator else {
+            return error_message("unavailable", "Sagas cannot be started on this connection");
+        };
+        let request: StartSagaRequest = match serde_json::from_value(data) {
+            Ok(request) => request,
+            Err(e) => return error_message("invalid_request", &e.to_string()),
+        };
+        if request.definition.is_some() {
+            return error_message(
+                "invalid_request",
+                "Sagas are started from definitions over REST only",
+            );
+        }
+
+        let request = match (StartSagaRequest {
+            client_id: self.identity.client_id.clone(),
+            ..request
+        })
+        .into_saga_request(
+            uuid::Uuid::new_v4().to_string(),
+            self.identity.principal.clone(),
+            &self.metadata_policy,
+            None,
+        ) {
+            Ok(request) => request,
+            Err(errors) => {
+                let message = errors
+                    .iter()
+                    .map(|e| format!("{}: {}", e.field, e.message))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                return error_message("invalid_request", &message);
+            }
+        };
+
+        match orchestrator.start_saga(request).await {
+            Ok(response) => WebSocketMessage {
+                r#type: "saga.started".to_string(),
+                data: serde_json::to_value(&response).unwrap_or_default(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            },
+            Err(e) => error_message("saga_start_failed", &e.to_string()),
+        }
+    }
+}
+
+async fn handle_socket(
+    socket: WebSocket,
+    state: Arc<WebSocketService>,
+    identity: ConnectionIdentity,
+) {
+    let (sender, receiver) = socket.split();
+    let limiter = ConnectionLimiter::new(state.limits.clone(), Instant::now());
+    let session = Session::new(
+        identity,
+        Some(state.saga_orchestrator.clone()),
+        state.metadata_policy.clone(),
+    )
+    .with_event_store(state.event_store.clone(), state.append_sessions.clone())
+    .with_namespace_freezes(state.namespace_freezes.clone());
+    #[cfg(feature = "metrics")]
+    let session = session.with_metrics(state.metrics.clone());
+
+    let session_id = session.session_id.clone();
+    state.lock_manager.open_session(&session_id);
+    run_connection(
+        sender,
+        receiver,
+        state.event_sender.subscribe(),
+        limiter,
+        session,
+    )
+    .await;
+
+    match state.lock_manager.release_by_session(&session_id).await {
+        Ok(0) => {}
+        Ok(released) => tracing::info!(
+            session_id = %session_id,
+            "Released {} locks of closed WebSocket session",
+            released
+        ),
+        Err(e) => tracing::error!(
+            session_id = %session_id,
+            "Failed to release locks of closed WebSocket session: {}",
+            e
+        ),
+    }
+}
 
-    let (mut sender, mut receiver) = socket.split();
+async fn send_message<S>(sender: &mut S, message: &WebSocketMessage)
+where
+    S: Sink<Message> + Unpin,
+{
+    if let Ok(text) = serde_json::to_string(message) {
+        let _ = sender.send(Message::Text(text)).await;
+    }
+}
 
+async fn run_connection<S, R>(
+    mut sender: S,
+    mut receiver: R,
+    mut rx: broadcast::Receiver<Dispatch>,
+    mut limiter: ConnectionLimiter,
+    mut session: Session,
+) where
+    S: Sink<Message> + Unpin,
+    R: Stream<Item = Result<Message, axum::Error>> + Unpin,
+{
     let welcome_msg = WebSocketMessage {
         r#type: "welcome".to_string(),
         data: serde_json::json!({
             "message": "Connected to Syros WebSocket",
-            "version": env!("CARGO_PKG_VERSION")
+            "version": env!("CARGO_PKG_VERSION"),
+            "resume_token": session.resume_token,
+            "session_id": session.session_id,
         }),
         timestamp: chrono::Utc::now().to_rfc3339(),
     };
-
-    if let Ok(msg) = serde_json::to_string(&welcome_msg) {
-        let _ = sender.send(Message::Text(msg)).await;
-    }
+    send_message(&mut sender, &welcome_msg).await;
 
     loop {
         tokio::select! {
             msg = receiver.next() => {
-                if let Some(Ok(msg)) = msg {
-                    match msg {
-                        Message::Text(text) => {
-                            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&text) {
-                                if let Some(msg_type) = parsed.get("type").and_then(|v| v.as_str()) {
-                                    match msg_type {
-                                        "ping" => {
-                                            let pong = WebSocketMessage {
-                                                r#type: "pong".to_string(),
-                                                data: serde_json::json!({"timestamp": chrono::Utc::now().to_rfc3339()}),
-                                                timestamp: chrono::Utc::now().to_rfc3339(),
-                                            };
-                                            if let Ok(pong_msg) = serde_json::to_string(&pong) {
-                                                let _ = sender.send(Message::Text(pong_msg)).await;
-                                            }
-                                        }
-                                        "subscribe" => {
-                                            let response = WebSocketMessage {
-                                                r#type: "subscribed".to_string(),
-                                                data: serde_json::json!({"message": "Inscrito para receber eventos"}),
-                                                timestamp: chrono::Utc::now().to_rfc3339(),
-                                            };
-                                            if let Ok(response_msg) = serde_json::to_string(&response) {
-                                                let _ = sender.send(Message::Text(response_msg)).await;
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
+                let Some(Ok(msg)) = msg else {
+                    break;
+                };
+
+                let size = match &msg {
+                    Message::Text(text) => text.len(),
+                    Message::Binary(data) => data.len(),
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+
+                match limiter.admit(size, Instant::now()) {
+                    Admission::Accept => {
+                        if let Message::Text(text) = msg {
+                            if let Some(reply) = session.handle_command(&text).await {
+                                send_message(&mut sender, &reply).await;
                             }
                         }
-                        Message::Close(_) => break,
-                        _ => {}
                     }
-                } else {
-                    break;
+                    Admission::Reject(violation) => {
+                        session.count_rejection(&violation);
+                        send_message(&mut sender, &violation.to_message()).await;
+                    }
+                    Admission::Close(violation) => {
+                        session.count_rejection(&violation);
+                        send_message(&mut sender, &violation.to_message()).await;
+                        let _ = sender
+                            .send(Message::Close(Some(CloseFrame {
+                                code: POLICY_VIOLATION_CLOSE_CODE,
+                                reason: "Too many limit violations".into(),
+                            })))
+                            .await;
+                        break;
+                    }
                 }
             }
             event_msg = rx.recv() => {
-                if let Ok(msg) = event_msg {
-                    if let Ok(msg_str) = serde_json::to_string(&msg) {
-                        let _ = sender.send(Message::Text(msg_str)).await;
+                if let Ok(dispatch) = event_msg {
+                    if session.receives(&dispatch) {
+                        send_message(&mut sender, &dispatch.message).await;
                     }
                 }
             }
         }
     }
 }
+
+fn error_message(code: &str, message: &str) -> WebSocketMessage {
+    WebSocketMessage {
+        r#type: "error".to_string(),
+        data: serde_json::json!({
+            "code": code,
+            "message": message,
+        }),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+fn handle_command(text: &str) -> Option<WebSocketMessage> {
+    let parsed = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    match parsed.get("type").and_then(|v| v.as_str())? {
+        "ping" => Some(WebSocketMessage {
+            r#type: "pong".to_string(),
+            data: serde_json::json!({"timestamp": chrono::Utc::now().to_rfc3339()}),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }),
+        "subscribe" => Some(WebSocketMessage {
+            r#type: "subscribed".to_string(),
+            data: serde_json::json!({"message": "Inscrito para receber eventos"}),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+
+    struct TestConnection {
+        input: mpsc::UnboundedSender<Result<Message, axum::Error>>,
+        output: mpsc::UnboundedReceiver<Message>,
+        task: tokio::task::JoinHandle<()>,
+        _events: broadcast::Sender<Dispatch>,
+    }
+
+    fn connect(limits: WebSocketConfig) -> TestConnection {
+        let (events, _) = broadcast::channel(16);
+        connect_as(&events, ConnectionIdentity::default(), limits)
+    }
+
+    fn connect_as(
+        events: &broadcast::Sender<Dispatch>,
+        identity: ConnectionIdentity,
+        limits: WebSocketConfig,
+    ) -> TestConnection {
+        spawn_session(
+            events,
+            Session::new(identity, None, MetadataPolicy::default()),
+            limits,
+        )
+    }
+
+    fn spawn_session(
+        events: &broadcast::Sender<Dispatch>,
+        session: Session,
+        limits: WebSocketConfig,
+    ) -> TestConnection {
+        let (input, receiver) = mpsc::unbounded();
+        let (sender, output) = mpsc::unbounded();
+        let limiter = ConnectionLimiter::new(limits, Instant::now());
+        let task = tokio::spawn(run_connection(
+            sender,
+            receiver,
+            events.subscribe(),
+            limiter,
+            session,
+        ));
+
+        TestConnection {
+            input,
+            output,
+            task,
+            _events: events.clone(),
+        }
+    }
+
+    impl TestConnection {
+        fn send(&self, text: &str) {
+            self.input
+                .unbounded_send(Ok(Message::Text(text.to_string())))
+                .unwrap();
+        }
+
+        async fn recv(&mut self) -> Message {
+            self.output.next().await.expect("connection output ended")
+        }
+
+        async fn recv_json(&mut self) -> WebSocketMessage {
+            match self.recv().await {
+                Message::Text(text) => serde_json::from_str(&text).unwrap(),
+                other => panic!("expected text message, got {:?}", other),
+            }
+        }
+    }
+
+    fn limits(rate: u32, burst: u32, max_bytes: usize, max_violations: u32) -> WebSocketConfig {
+        WebSocketConfig {
+            commands_per_second: rate,
+            burst,
+            max_message_bytes: max_bytes,
+            max_violations,
+        }
+    }
+
+    #[test]
+    fn test_token_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, 3, start);
+
+        assert!((0..3).all(|_| bucket.try_acquire(start)));
+        assert!(!bucket.try_acquire(start));
+
+        let later = start + Duration::from_millis(500);
+        assert!(bucket.try_acquire(later));
+        assert!(!bucket.try_acquire(later));
+
+        // Refill never exceeds the burst capacity.
+        let much_later = later + Duration::from_secs(60);
+        assert_eq!(
+            (0..10).filter(|_| bucket.try_acquire(much_later)).count(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_replies_then_disconnects() {
+        let (events, _) = broadcast::channel(16);
+        let session = Session::new(
+            ConnectionIdentity::default(),
+            None,
+            MetadataPolicy::default(),
+        );
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(Metrics::new().unwrap());
+        #[cfg(feature = "metrics")]
+        let session = session.with_metrics(Some(metrics.clone()));
+        let mut conn = spawn_session(&events, session, limits(1, 2, 1024, 3));
+        assert_eq!(conn.recv_json().await.r#type, "welcome");
+
+        for _ in 0..10 {
+            conn.send(r#"{"type":"ping"}"#);
+        }
+
+        for _ in 0..2 {
+            assert_eq!(conn.recv_json().await.r#type, "pong");
+        }
+        for _ in 0..4 {
+            let error = conn.recv_json().await;
+            assert_eq!(error.r#type, "error");
+            assert_eq!(error.data["code"], "rate_limited");
+        }
+        match conn.recv().await {
+            Message::Close(Some(frame)) => assert_eq!(frame.code, POLICY_VIOLATION_CLOSE_CODE),
+            other => panic!("expected close frame, got {:?}", other),
+        }
+
+        conn.task.await.unwrap();
+        assert!(conn.output.next().await.is_none());
+        #[cfg(feature = "metrics")]
+        assert_eq!(
+            metrics
+                .websocket_commands_rejected_total
+                .with_label_values(&["rate_limited"])
+                .get(),
+            4.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oversized_messages_rejected_then_disconnect() {
+        let mut conn = connect(limits(100, 100, 64, 1));
+        assert_eq!(conn.recv_json().await.r#type, "welcome");
+
+        let oversized = format!(r#"{{"type":"ping","padding":"{}"}}"#, "x".repeat(64));
+        conn.send(&oversized);
+        let error = conn.recv_json().await;
+        assert_eq!(error.r#type, "error");
+        assert_eq!(error.data["code"], "message_too_large");
+
+        // The connection stays usable after a single violation.
+        conn.send(r#"{"type":"ping"}"#);
+        assert_eq!(conn.recv_json().await.r#type, "pong");
+
+        conn.send(&oversized);
+        assert_eq!(conn.recv_json().await.data["code"], "message_too_large");
+        match conn.recv().await {
+            Message::Close(Some(frame)) => assert_eq!(frame.code, POLICY_VIOLATION_CLOSE_CODE),
+            other => panic!("expected close frame, got {:?}", other),
+        }
+        conn.task.await.unwrap();
+    }
+
+    fn principal(name: &str, is_admin: bool) -> ConnectionIdentity {
+        ConnectionIdentity {
+            principal: Some(name.to_string()),
+            client_id: None,
+            is_admin,
+        }
+    }
+
+    fn saga_update(
+        saga_id: &str,
+        owner: Option<&str>,
+        client_id: Option<&str>,
+    ) -> SagaStatusUpdate {
+        SagaStatusUpdate {
+            saga_id: saga_id.to_string(),
+            name: "checkout".to_string(),
+            status: "Running".to_string(),
+            owner: owner.map(|o| o.to_string()),
+            client_id: client_id.map(|c| c.to_string()),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_saga_notifications_are_private_to_their_owner() {
+        let (events, _) = broadcast::channel(16);
+        let limits = limits(100, 100, 1024, 3);
+        let mut alice = connect_as(&events, principal("alice", false), limits.clone());
+        let mut bob = connect_as(&events, principal("bob", false), limits.clone());
+        let mut admin = connect_as(&events, principal("root", true), limits.clone());
+        let mut kiosk = connect_as(
+            &events,
+            ConnectionIdentity {
+                principal: Some("alice".to_string()),
+                client_id: Some("kiosk-7".to_string()),
+                is_admin: false,
+            },
+            limits.clone(),
+        );
+        // Naming someone else's client ID does not reveal their sagas.
+        let mut anonymous = connect_as(
+            &events,
+            ConnectionIdentity {
+                client_id: Some("kiosk-7".to_string()),
+                ..Default::default()
+            },
+            limits,
+        );
+        for conn in [&mut alice, &mut bob, &mut admin, &mut kiosk, &mut anonymous] {
+            assert_eq!(conn.recv_json().await.r#type, "welcome");
+        }
+
+        // Only admins may join the admin channel.
+        bob.send(r#"{"type":"subscribe","channel":"admin"}"#);
+        assert_eq!(bob.recv_json().await.data["code"], "forbidden");
+        admin.send(r#"{"type":"subscribe","channel":"admin"}"#);
+        assert_eq!(admin.recv_json().await.data["channel"], ADMIN_CHANNEL);
+
+        let (updates, rx) = broadcast::channel(16);
+        relay(&TaskTracker::new(), rx, events.clone(), Dispatch::from);
+        updates
+            .send(saga_update("saga-a", Some("alice"), None))
+            .unwrap();
+        updates
+            .send(saga_update("saga-k", Some("alice"), Some("kiosk-7")))
+            .unwrap();
+        updates
+            .send(saga_update("saga-b", Some("bob"), Some("kiosk-7")))
+            .unwrap();
+        updates.send(saga_update("saga-p", None, None)).unwrap();
+
+        let saga_ids = |messages: Vec<WebSocketMessage>| -> Vec<String> {
+            messages
+                .into_iter()
+                .map(|m| {
+                    assert_eq!(m.r#type, "saga.status");
+                    m.data["saga_id"].as_str().unwrap().to_string()
+                })
+                .collect()
+        };
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(alice.recv_json().await);
+        }
+        assert_eq!(saga_ids(received), vec!["saga-a", "saga-k", "saga-p"]);
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(kiosk.recv_json().await);
+        }
+        assert_eq!(saga_ids(received), vec!["saga-a", "saga-k", "saga-p"]);
+
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            received.push(bob.recv_json().await);
+        }
+        assert_eq!(saga_ids(received), vec!["saga-b", "saga-p"]);
+
+        // Anonymous connections get no saga notifications: the next message is the pong.
+        anonymous.send(r#"{"type":"ping"}"#);
+        assert_eq!(anonymous.recv_json().await.r#type, "pong");
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            received.push(admin.recv_json().await);
+        }
+        assert_eq!(
+            saga_ids(received),
+            vec!["saga-a", "saga-k", "saga-b", "saga-p"]
+        );
+    }
+}
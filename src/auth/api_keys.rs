@@ -1,15 +1,30 @@
-use crate::Result;
+use crate::{Result, SyrosError};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+/// Marker at the start of every API key.
+const KEY_MARKER: &str = "sk_";
+/// Length of the public prefix that identifies a key.
+pub const KEY_PREFIX_LEN: usize = 8;
+
+/// An API key as stored: the public prefix and a salted hash of the secret.
+///
+/// Keys have the form `sk_<prefix>_<secret>`; the full key is only returned
+/// once, when it is created.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKey {
     pub id: String,
-    pub key: String,
+    /// Public part of the key, used for lookups and display
+    pub prefix: String,
+    /// Random salt mixed into `secret_hash`
+    pub salt: [u8; 16],
+    /// SHA-256 of the salt followed by the secret
+    pub secret_hash: [u8; 32],
     pub name: String,
     pub description: Option<String>,
     pub permissions: Vec<String>,
@@ -43,79 +58,100 @@ pub struct ApiKeyResponse {
 #[derive(Clone)]
 pub struct ApiKeyManager {
     keys: Arc<RwLock<HashMap<String, ApiKey>>>,
-    key_to_id: Arc<RwLock<HashMap<String, String>>>, // Maps API key to ID
+    prefix_to_id: Arc<RwLock<HashMap<String, String>>>, // Maps key prefix to ID
 }
 
 impl ApiKeyManager {
     pub fn new() -> Self {
         Self {
             keys: Arc::new(RwLock::new(HashMap::new())),
-            key_to_id: Arc::new(RwLock::new(HashMap::new())),
+            prefix_to_id: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     pub async fn create_api_key(&self, request: CreateApiKeyRequest) -> Result<ApiKeyResponse> {
-        let id = Uuid::new_v4().to_string();
-        let key = format!("sk_{}", Uuid::new_v4().to_string().replace('-', ""));
-        let now = Utc::now();
+        let secret = Uuid::new_v4().simple().to_string();
 
-        let expires_at = request
-            .expires_in_days
-            .map(|days| now + chrono::Duration::days(days as i64));
-
-        let api_key = ApiKey {
-            id: id.clone(),
-            key: key.clone(),
-            name: request.name.clone(),
-            description: request.description.clone(),
-            permissions: request.permissions.clone(),
-            created_at: now,
-            expires_at,
-            is_active: true,
-            last_used_at: None,
-            usage_count: 0,
+        let mut keys = self.keys.write().await;
+        let mut prefix_to_id = self.prefix_to_id.write().await;
+        let prefix = loop {
+            let candidate = Uuid::new_v4().simple().to_string()[..KEY_PREFIX_LEN].to_string();
+            if !prefix_to_id.contains_key(&candidate) {
+                break candidate;
+            }
         };
 
-        {
-            let mut keys = self.keys.write().await;
-            let mut key_to_id = self.key_to_id.write().await;
-            keys.insert(id.clone(), api_key.clone());
-            key_to_id.insert(key.clone(), id.clone());
+        let api_key = new_api_key(&prefix, &secret, request);
+        keys.insert(api_key.id.clone(), api_key.clone());
+        prefix_to_id.insert(prefix.clone(), api_key.id.clone());
+
+        Ok(key_response(
+            api_key,
+            format!("{}{}_{}", KEY_MARKER, prefix, secret),
+        ))
+    }
+
+    /// Stores a key with a value chosen by the caller, such as a fixed
+    /// development key, instead of generating one.
+    ///
+    /// `key` must have the `sk_<prefix>_<secret>` form. Returns `None`
+    /// without changing anything if a key with the same prefix exists.
+    pub async fn import_api_key(
+        &self,
+        key: &str,
+        request: CreateApiKeyRequest,
+    ) -> Result<Option<ApiKeyResponse>> {
+        let (prefix, secret) = split_api_key(key).ok_or_else(|| {
+            SyrosError::ConfigError(format!(
+                "API key {} must have the form {}<{} letters or digits>_<secret>",
+                request.name, KEY_MARKER, KEY_PREFIX_LEN
+            ))
+        })?;
+
+        let mut keys = self.keys.write().await;
+        let mut prefix_to_id = self.prefix_to_id.write().await;
+        if prefix_to_id.contains_key(prefix) {
+            return Ok(None);
         }
 
-        Ok(ApiKeyResponse {
-            id: api_key.id,
-            key: api_key.key,
-            name: api_key.name,
-            description: api_key.description,
-            permissions: api_key.permissions,
-            created_at: api_key.created_at.to_rfc3339(),
-            expires_at: api_key.expires_at.map(|dt| dt.to_rfc3339()),
-            is_active: api_key.is_active,
-        })
+        let api_key = new_api_key(prefix, secret, request);
+        keys.insert(api_key.id.clone(), api_key.clone());
+        prefix_to_id.insert(prefix.to_string(), api_key.id.clone());
+
+        Ok(Some(key_response(api_key, key.to_string())))
     }
 
+    /// Returns the key's record if `key` is a valid, active, unexpired API key.
+    ///
+    /// The key is found by its public prefix and its secret is checked against
+    /// the stored salted hash in constant time. Malformed input is rejected.
     pub async fn validate_api_key(&self, key: &str) -> Result<Option<ApiKey>> {
-        let key_to_id = self.key_to_id.read().await;
-        let keys = self.keys.read().await;
+        let Some((prefix, secret)) = split_api_key(key) else {
+            return Ok(None);
+        };
+        let id = match self.prefix_to_id.read().await.get(prefix) {
+            Some(id) => id.clone(),
+            None => return Ok(None),
+        };
 
-        if let Some(id) = key_to_id.get(key) {
-            if let Some(api_key) = keys.get(id) {
-                if api_key.is_active {
-                    if let Some(expires_at) = api_key.expires_at {
-                        if Utc::now() > expires_at {
-                            return Ok(None); // Expired
-                        }
+        // Take the write lock directly; upgrading from a held read guard deadlocks.
+        let mut keys = self.keys.write().await;
+        if let Some(api_key) = keys.get_mut(&id) {
+            let secret_hash = hash_secret(&api_key.salt, secret);
+            if !constant_time_eq(&secret_hash, &api_key.secret_hash) {
+                return Ok(None);
+            }
+            if api_key.is_active {
+                if let Some(expires_at) = api_key.expires_at {
+                    if Utc::now() > expires_at {
+                        return Ok(None); // Expired
                     }
+                }
 
-                    let mut keys = self.keys.write().await;
-                    if let Some(api_key) = keys.get_mut(id) {
-                        api_key.last_used_at = Some(Utc::now());
-                        api_key.usage_count += 1;
-                    }
+                api_key.last_used_at = Some(Utc::now());
+                api_key.usage_count += 1;
 
-                    return Ok(Some(api_key.clone()));
-                }
+                return Ok(Some(api_key.clone()));
             }
         }
 
@@ -129,11 +165,7 @@ impl ApiKeyManager {
         for api_key in keys.values() {
             result.push(ApiKeyResponse {
                 id: api_key.id.clone(),
-                key: format!(
-                    "{}...{}",
-                    &api_key.key[..8],
-                    &api_key.key[api_key.key.len() - 4..]
-                ), // Masked key
+                key: masked_key(&api_key.prefix),
                 name: api_key.name.clone(),
                 description: api_key.description.clone(),
                 permissions: api_key.permissions.clone(),
@@ -201,3 +233,183 @@ impl Default for ApiKeyManager {
         Self::new()
     }
 }
+
+/// A new active key record for `prefix` and `secret`.
+fn new_api_key(prefix: &str, secret: &str, request: CreateApiKeyRequest) -> ApiKey {
+    let salt = Uuid::new_v4().into_bytes();
+    let now = Utc::now();
+
+    ApiKey {
+        id: Uuid::new_v4().to_string(),
+        prefix: prefix.to_string(),
+        salt,
+        secret_hash: hash_secret(&salt, secret),
+        name: request.name,
+        description: request.description,
+        permissions: request.permissions,
+        created_at: now,
+        expires_at: request
+            .expires_in_days
+            .map(|days| now + chrono::Duration::days(days as i64)),
+        is_active: true,
+        last_used_at: None,
+        usage_count: 0,
+    }
+}
+
+fn key_response(api_key: ApiKey, key: String) -> ApiKeyResponse {
+    ApiKeyResponse {
+        id: api_key.id,
+        key,
+        name: api_key.name,
+        description: api_key.description,
+        permissions: api_key.permissions,
+        created_at: api_key.created_at.to_rfc3339(),
+        expires_at: api_key.expires_at.map(|dt| dt.to_rfc3339()),
+        is_active: api_key.is_active,
+    }
+}
+
+/// Splits `sk_<prefix>_<secret>` into its prefix and secret.
+fn split_api_key(key: &str) -> Option<(&str, &str)> {
+    let (prefix, secret) = key.strip_prefix(KEY_MARKER)?.split_once('_')?;
+    if prefix.len() != KEY_PREFIX_LEN
+        || !prefix.bytes().all(|b| b.is_ascii_alphanumeric())
+        || secret.is_empty()
+    {
+        return None;
+    }
+    Some((prefix, secret))
+}
+
+/// Display form of a key: its public prefix with the secret elided.
+fn masked_key(prefix: &str) -> String {
+    format!("{}{}_...", KEY_MARKER, prefix)
+}
+
+fn hash_secret(salt: &[u8], secret: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(secret.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Compares two byte strings in time that depends only on their length.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: &str) -> CreateApiKeyRequest {
+        CreateApiKeyRequest {
+            name: name.to_string(),
+            description: None,
+            permissions: vec!["read".to_string()],
+            expires_in_days: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_created_key_validates_and_wrong_secret_does_not() {
+        let manager = ApiKeyManager::new();
+        let created = manager.create_api_key(request("ci")).await.unwrap();
+
+        let validated = manager.validate_api_key(&created.key).await.unwrap();
+        assert_eq!(validated.unwrap().id, created.id);
+
+        let (prefix, secret) = split_api_key(&created.key).unwrap();
+        let mut tampered = secret.to_string().into_bytes();
+        tampered[0] = if tampered[0] == b'0' { b'1' } else { b'0' };
+        let tampered = format!(
+            "{}{}_{}",
+            KEY_MARKER,
+            prefix,
+            String::from_utf8(tampered).unwrap()
+        );
+        assert!(manager.validate_api_key(&tampered).await.unwrap().is_none());
+        assert!(manager
+            .validate_api_key(&format!("{}{}_", KEY_MARKER, prefix))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_short_and_garbage_keys_are_rejected() {
+        let manager = ApiKeyManager::new();
+        let created = manager.create_api_key(request("ci")).await.unwrap();
+
+        for key in [
+            "",
+            "s",
+            "sk_",
+            "sk_abc",
+            "sk_abc_def",
+            "sk_________",
+            "xx_12345678_secret",
+            "sk_1234567é_secret",
+            "sk_ééééé",
+            &created.key[..created.key.len() - 1],
+            &created.key[KEY_MARKER.len()..],
+        ] {
+            assert!(
+                manager.validate_api_key(key).await.unwrap().is_none(),
+                "accepted {:?}",
+                key
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listing_masks_without_slicing_the_key() {
+        // Listing used to slice the raw key with `[..8]`, panicking on short keys.
+        let manager = ApiKeyManager::new();
+        let created = manager.create_api_key(request("ci")).await.unwrap();
+        let mut short = manager.keys.read().await[&created.id].clone();
+        short.id = "short".to_string();
+        short.prefix = "ab".to_string();
+        manager.keys.write().await.insert(short.id.clone(), short);
+
+        let listed = manager.list_api_keys().await.unwrap();
+        assert_eq!(listed.len(), 2);
+        let masked: Vec<_> = listed.iter().map(|key| key.key.as_str()).collect();
+        assert!(masked.contains(&"sk_ab_..."));
+        assert!(listed.iter().all(|key| key.key != created.key));
+    }
+
+    #[tokio::test]
+    async fn test_imported_key_validates_once_per_prefix() {
+        let manager = ApiKeyManager::new();
+        let key = "sk_dev00001_local-secret";
+
+        let imported = manager.import_api_key(key, request("dev")).await.unwrap();
+        assert_eq!(imported.unwrap().key, key);
+        assert!(manager.validate_api_key(key).await.unwrap().is_some());
+
+        // The same prefix is not imported twice, whatever the secret.
+        assert!(manager
+            .import_api_key("sk_dev00001_other", request("dev"))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(manager.list_api_keys().await.unwrap().len(), 1);
+        assert!(manager
+            .import_api_key("sk_short_secret", request("dev"))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -1,5 +1,7 @@
+#[cfg(feature = "rest")]
 use crate::api::rest::ApiState;
 use crate::auth::{ApiKeyManager, JwtAuth};
+#[cfg(feature = "rest")]
 use axum::{
     extract::{Request, State},
     http::{HeaderMap, StatusCode},
@@ -20,7 +22,10 @@ impl AuthMiddleware {
             api_key_manager: ApiKeyManager::new(),
         }
     }
+}
 
+#[cfg(feature = "rest")]
+impl AuthMiddleware {
     pub async fn authenticate_request(
         State(state): State<ApiState>,
         headers: HeaderMap,
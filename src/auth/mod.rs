@@ -1,9 +1,11 @@
 pub mod api_keys;
 pub mod jwt;
 pub mod middleware;
+pub mod principal;
 pub mod rbac;
 
 pub use api_keys::ApiKeyManager;
 pub use jwt::JwtAuth;
 pub use middleware::AuthMiddleware;
+pub use principal::Principal;
 pub use rbac::{Permission, RBACManager, Resource, ResourceType, Role, RoleDefinition, User};
@@ -0,0 +1,81 @@
+//! Resolution of the authenticated caller of a request.
+//!
+//! Every API resolves its caller from the same credentials, an API key or
+//! an `Authorization` header, into a [`Principal`].
+
+use crate::auth::{AuthMiddleware, JwtAuth, Permission, Role};
+
+/// Authenticated caller of a request.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// User ID from the JWT subject, or the API key ID
+    pub subject: String,
+    /// Permissions granted to the caller
+    pub permissions: Vec<Permission>,
+}
+
+impl Principal {
+    /// Resolves the caller from request headers.
+    ///
+    /// Returns `None` for anonymous requests or invalid credentials.
+    #[cfg(feature = "rest")]
+    pub async fn from_headers(
+        state: &crate::api::rest::ApiState,
+        headers: &axum::http::HeaderMap,
+    ) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        Self::from_credentials(
+            &state.auth_middleware,
+            header("x-api-key"),
+            header("authorization"),
+        )
+        .await
+    }
+
+    /// Resolves the caller from an API key or an `Authorization` header
+    /// value, e.g. taken from gRPC metadata.
+    pub async fn from_credentials(
+        auth: &AuthMiddleware,
+        api_key: Option<&str>,
+        authorization: Option<&str>,
+    ) -> Option<Self> {
+        if let Some(api_key) = api_key {
+            if let Ok(Some(api_key)) = auth.api_key_manager.validate_api_key(api_key).await {
+                let permissions = all_permissions()
+                    .into_iter()
+                    .filter(|p| api_key.permissions.contains(&format!("{:?}", p)))
+                    .collect();
+                return Some(Self {
+                    subject: api_key.id,
+                    permissions,
+                });
+            }
+        }
+
+        let token = authorization.and_then(JwtAuth::extract_token_from_header)?;
+        let claims = auth.jwt_auth.validate_token(&token).ok()?;
+
+        Some(Self {
+            subject: claims.sub,
+            permissions: role_from_claim(&claims.role).get_permissions(),
+        })
+    }
+
+    pub fn has_permission(&self, permission: &Permission) -> bool {
+        self.permissions.contains(permission)
+    }
+}
+
+fn role_from_claim(role: &str) -> Role {
+    match role.to_ascii_lowercase().as_str() {
+        "admin" => Role::Admin,
+        "manager" => Role::Manager,
+        "developer" => Role::Developer,
+        "viewer" => Role::Viewer,
+        _ => Role::Custom(role.to_string()),
+    }
+}
+
+fn all_permissions() -> Vec<Permission> {
+    Role::Admin.get_permissions()
+}
@@ -1,6 +1,10 @@
 use crate::{Result, SyrosError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Permission {
@@ -41,7 +45,34 @@ pub enum Permission {
     ApiGraphQL,
 }
 
+/// Prefix of a custom role's name in its string form, e.g. `custom:billing`.
+const CUSTOM_ROLE_PREFIX: &str = "custom:";
+
+/// Permissions are written by variant name, e.g. `LockCreate`, matching their
+/// serde representation.
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Permission {
+    type Err = SyrosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // Admins hold every permission
+        Role::Admin
+            .get_permissions()
+            .into_iter()
+            .find(|permission| permission.to_string() == s)
+            .ok_or_else(|| SyrosError::ApiError(format!("Unknown permission: {}", s)))
+    }
+}
+
+/// Roles are written `Admin`, `Manager`, `Developer`, `Viewer` or
+/// `custom:<name>`, in REST payloads as well as in GraphQL.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(into = "String", try_from = "String")]
 pub enum Role {
     Admin,
     Manager,
@@ -50,6 +81,49 @@ pub enum Role {
     Custom(String),
 }
 
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Admin => f.write_str("Admin"),
+            Role::Manager => f.write_str("Manager"),
+            Role::Developer => f.write_str("Developer"),
+            Role::Viewer => f.write_str("Viewer"),
+            Role::Custom(name) => write!(f, "{}{}", CUSTOM_ROLE_PREFIX, name),
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = SyrosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Admin" => Ok(Role::Admin),
+            "Manager" => Ok(Role::Manager),
+            "Developer" => Ok(Role::Developer),
+            "Viewer" => Ok(Role::Viewer),
+            _ => match s.strip_prefix(CUSTOM_ROLE_PREFIX) {
+                Some(name) if !name.is_empty() => Ok(Role::Custom(name.to_string())),
+                _ => Err(SyrosError::ApiError(format!("Unknown role: {}", s))),
+            },
+        }
+    }
+}
+
+impl From<Role> for String {
+    fn from(role: Role) -> Self {
+        role.to_string()
+    }
+}
+
+impl TryFrom<String> for Role {
+    type Error = SyrosError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
 impl Role {
     pub fn get_permissions(&self) -> Vec<Permission> {
         match self {
@@ -186,59 +260,34 @@ pub enum ResourceType {
     System,
 }
 
+/// Users, roles and resources, shared by every request.
+///
+/// Each map sits behind its own `RwLock`, so permission checks only take
+/// read locks and run concurrently; user and role updates hold a map's write
+/// lock for the whole change, so a check never sees a user half updated.
+#[derive(Clone)]
 pub struct RBACManager {
-    users: HashMap<String, User>,
-    roles: HashMap<Role, RoleDefinition>,
-    resources: HashMap<String, Resource>,
+    users: Arc<RwLock<HashMap<String, User>>>,
+    roles: Arc<RwLock<HashMap<Role, RoleDefinition>>>,
+    resources: Arc<RwLock<HashMap<String, Resource>>>,
 }
 
 impl RBACManager {
     pub fn new() -> Self {
-        let mut rbac = Self {
-            users: HashMap::new(),
-            roles: HashMap::new(),
-            resources: HashMap::new(),
-        };
-
-        rbac.initialize_default_roles();
-        rbac
-    }
-
-    fn initialize_default_roles(&mut self) {
-        let default_roles = vec![
-            RoleDefinition {
-                name: Role::Admin,
-                description: "Full system access".to_string(),
-                permissions: Role::Admin.get_permissions(),
-                is_system: true,
-            },
-            RoleDefinition {
-                name: Role::Manager,
-                description: "Management access to all resources".to_string(),
-                permissions: Role::Manager.get_permissions(),
-                is_system: true,
-            },
-            RoleDefinition {
-                name: Role::Developer,
-                description: "Developer access to create and use resources".to_string(),
-                permissions: Role::Developer.get_permissions(),
-                is_system: true,
-            },
-            RoleDefinition {
-                name: Role::Viewer,
-                description: "Read-only access to resources".to_string(),
-                permissions: Role::Viewer.get_permissions(),
-                is_system: true,
-            },
-        ];
-
-        for role_def in default_roles {
-            self.roles.insert(role_def.name.clone(), role_def);
+        let roles = default_roles()
+            .into_iter()
+            .map(|role_def| (role_def.name.clone(), role_def))
+            .collect();
+
+        Self {
+            users: Arc::new(RwLock::new(HashMap::new())),
+            roles: Arc::new(RwLock::new(roles)),
+            resources: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     pub async fn create_user(
-        &mut self,
+        &self,
         username: String,
         email: String,
         roles: Vec<Role>,
@@ -246,101 +295,71 @@ impl RBACManager {
         let user_id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now();
 
-        let mut permissions = Vec::new();
-        for role in &roles {
-            permissions.extend(role.get_permissions());
-        }
-
         let user = User {
             id: user_id.clone(),
-            username: username.clone(),
-            email: email.clone(),
-            roles: roles.clone(),
-            permissions,
+            username,
+            email,
+            permissions: role_permissions(&roles),
+            roles,
             is_active: true,
             created_at: now,
             updated_at: now,
         };
 
-        self.users.insert(user_id.clone(), user.clone());
+        self.users.write().await.insert(user_id, user.clone());
         Ok(user)
     }
 
-    pub async fn get_user(&self, user_id: &str) -> Result<Option<&User>> {
-        Ok(self.users.get(user_id))
+    pub async fn get_user(&self, user_id: &str) -> Result<Option<User>> {
+        Ok(self.users.read().await.get(user_id).cloned())
     }
 
-    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<&User>> {
-        Ok(self.users.values().find(|u| u.username == username))
+    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        Ok(self
+            .users
+            .read()
+            .await
+            .values()
+            .find(|u| u.username == username)
+            .cloned())
     }
 
-    pub async fn update_user_roles(&mut self, user_id: &str, roles: Vec<Role>) -> Result<()> {
-        if let Some(user) = self.users.get_mut(user_id) {
-            user.roles = roles.clone();
-            user.updated_at = chrono::Utc::now();
-
-            let mut permissions = Vec::new();
-            for role in &roles {
-                permissions.extend(role.get_permissions());
-            }
-            user.permissions = permissions;
-
-            Ok(())
-        } else {
-            Err(SyrosError::ApiError(format!("User {} not found", user_id)))
-        }
+    pub async fn update_user_roles(&self, user_id: &str, roles: Vec<Role>) -> Result<()> {
+        self.update_user(user_id, |user| {
+            user.permissions = role_permissions(&roles);
+            user.roles = roles;
+        })
+        .await
     }
 
-    pub async fn add_user_permission(
-        &mut self,
-        user_id: &str,
-        permission: Permission,
-    ) -> Result<()> {
-        if let Some(user) = self.users.get_mut(user_id) {
-            if !user.permissions.contains(&permission) {
-                user.permissions.push(permission);
-                user.updated_at = chrono::Utc::now();
-            }
-            Ok(())
-        } else {
-            Err(SyrosError::ApiError(format!("User {} not found", user_id)))
+    pub async fn add_user_permission(&self, user_id: &str, permission: Permission) -> Result<()> {
+        let mut users = self.users.write().await;
+        let user = users
+            .get_mut(user_id)
+            .ok_or_else(|| user_not_found(user_id))?;
+        if !user.permissions.contains(&permission) {
+            user.permissions.push(permission);
+            user.updated_at = chrono::Utc::now();
         }
+        Ok(())
     }
 
     pub async fn remove_user_permission(
-        &mut self,
+        &self,
         user_id: &str,
         permission: Permission,
     ) -> Result<()> {
-        if let Some(user) = self.users.get_mut(user_id) {
-            user.permissions.retain(|p| p != &permission);
-            user.updated_at = chrono::Utc::now();
-            Ok(())
-        } else {
-            Err(SyrosError::ApiError(format!("User {} not found", user_id)))
-        }
+        self.update_user(user_id, |user| {
+            user.permissions.retain(|p| p != &permission)
+        })
+        .await
     }
 
     pub async fn check_permission(&self, user_id: &str, permission: &Permission) -> Result<bool> {
-        if let Some(user) = self.users.get(user_id) {
-            if !user.is_active {
-                return Ok(false);
-            }
-
-            if user.permissions.contains(permission) {
-                return Ok(true);
-            }
-
-            for role in &user.roles {
-                if role.get_permissions().contains(permission) {
-                    return Ok(true);
-                }
-            }
-
-            Ok(false)
-        } else {
-            Ok(false)
-        }
+        let users = self.users.read().await;
+        Ok(users
+            .get(user_id)
+            .is_some_and(|user| user_has_permission(user, permission)))
     }
 
     pub async fn check_resource_permission(
@@ -349,20 +368,22 @@ impl RBACManager {
         resource_id: &str,
         permission: &Permission,
     ) -> Result<bool> {
-        if !self.check_permission(user_id, permission).await? {
+        // Read the user once so the role and ownership checks agree
+        let Some(user) = self.get_user(user_id).await? else {
+            return Ok(false);
+        };
+        if !user_has_permission(&user, permission) {
             return Ok(false);
         }
 
-        if let Some(resource) = self.resources.get(resource_id) {
+        if let Some(resource) = self.resources.read().await.get(resource_id) {
             if resource.owner_id == user_id {
                 return Ok(true);
             }
 
-            if let Some(user) = self.users.get(user_id) {
-                for role in &user.roles {
-                    if role.get_permissions().contains(permission) {
-                        return Ok(true);
-                    }
+            for role in &user.roles {
+                if role.get_permissions().contains(permission) {
+                    return Ok(true);
                 }
             }
         }
@@ -371,12 +392,12 @@ impl RBACManager {
     }
 
     pub async fn create_custom_role(
-        &mut self,
+        &self,
         name: String,
         description: String,
         permissions: Vec<Permission>,
     ) -> Result<()> {
-        let role = Role::Custom(name.clone());
+        let role = Role::Custom(name);
         let role_def = RoleDefinition {
             name: role.clone(),
             description,
@@ -384,37 +405,85 @@ impl RBACManager {
             is_system: false,
         };
 
-        self.roles.insert(role, role_def);
+        self.roles.write().await.insert(role, role_def);
         Ok(())
     }
 
-    pub async fn get_all_users(&self) -> Result<Vec<&User>> {
-        Ok(self.users.values().collect())
+    pub async fn get_all_users(&self) -> Result<Vec<User>> {
+        Ok(self.users.read().await.values().cloned().collect())
     }
 
-    pub async fn get_all_roles(&self) -> Result<Vec<&RoleDefinition>> {
-        Ok(self.roles.values().collect())
+    pub async fn get_all_roles(&self) -> Result<Vec<RoleDefinition>> {
+        Ok(self.roles.read().await.values().cloned().collect())
     }
 
-    pub async fn deactivate_user(&mut self, user_id: &str) -> Result<()> {
-        if let Some(user) = self.users.get_mut(user_id) {
-            user.is_active = false;
-            user.updated_at = chrono::Utc::now();
-            Ok(())
-        } else {
-            Err(SyrosError::ApiError(format!("User {} not found", user_id)))
-        }
+    pub async fn deactivate_user(&self, user_id: &str) -> Result<()> {
+        self.update_user(user_id, |user| user.is_active = false)
+            .await
     }
 
-    pub async fn activate_user(&mut self, user_id: &str) -> Result<()> {
-        if let Some(user) = self.users.get_mut(user_id) {
-            user.is_active = true;
-            user.updated_at = chrono::Utc::now();
-            Ok(())
-        } else {
-            Err(SyrosError::ApiError(format!("User {} not found", user_id)))
-        }
+    pub async fn activate_user(&self, user_id: &str) -> Result<()> {
+        self.update_user(user_id, |user| user.is_active = true)
+            .await
     }
+
+    /// Applies `change` to the user under the write lock and bumps its
+    /// `updated_at`.
+    async fn update_user(&self, user_id: &str, change: impl FnOnce(&mut User)) -> Result<()> {
+        let mut users = self.users.write().await;
+        let user = users
+            .get_mut(user_id)
+            .ok_or_else(|| user_not_found(user_id))?;
+        change(user);
+        user.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+}
+
+fn default_roles() -> Vec<RoleDefinition> {
+    vec![
+        RoleDefinition {
+            name: Role::Admin,
+            description: "Full system access".to_string(),
+            permissions: Role::Admin.get_permissions(),
+            is_system: true,
+        },
+        RoleDefinition {
+            name: Role::Manager,
+            description: "Management access to all resources".to_string(),
+            permissions: Role::Manager.get_permissions(),
+            is_system: true,
+        },
+        RoleDefinition {
+            name: Role::Developer,
+            description: "Developer access to create and use resources".to_string(),
+            permissions: Role::Developer.get_permissions(),
+            is_system: true,
+        },
+        RoleDefinition {
+            name: Role::Viewer,
+            description: "Read-only access to resources".to_string(),
+            permissions: Role::Viewer.get_permissions(),
+            is_system: true,
+        },
+    ]
+}
+
+fn role_permissions(roles: &[Role]) -> Vec<Permission> {
+    roles.iter().flat_map(Role::get_permissions).collect()
+}
+
+fn user_has_permission(user: &User, permission: &Permission) -> bool {
+    user.is_active
+        && (user.permissions.contains(permission)
+            || user
+                .roles
+                .iter()
+                .any(|role| role.get_permissions().contains(permission)))
+}
+
+fn user_not_found(user_id: &str) -> SyrosError {
+    SyrosError::ApiError(format!("User {} not found", user_id))
 }
 
 impl Default for RBACManager {
@@ -430,12 +499,12 @@ mod tests {
     #[tokio::test]
     async fn test_rbac_manager_creation() {
         let rbac = RBACManager::new();
-        assert!(!rbac.roles.is_empty());
+        assert!(!rbac.get_all_roles().await.unwrap().is_empty());
     }
 
     #[tokio::test]
     async fn test_user_creation() {
-        let mut rbac = RBACManager::new();
+        let rbac = RBACManager::new();
         let user = rbac
             .create_user(
                 "testuser".to_string(),
@@ -452,7 +521,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_permission_check() {
-        let mut rbac = RBACManager::new();
+        let rbac = RBACManager::new();
         let user = rbac
             .create_user(
                 "testuser".to_string(),
@@ -472,6 +541,61 @@ mod tests {
             .unwrap());
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_permission_checks_run_while_roles_change() {
+        let rbac = RBACManager::new();
+        let user = rbac
+            .create_user(
+                "testuser".to_string(),
+                "test@example.com".to_string(),
+                vec![Role::Viewer],
+            )
+            .await
+            .unwrap();
+
+        let writer = {
+            let rbac = rbac.clone();
+            let user_id = user.id.clone();
+            tokio::spawn(async move {
+                for i in 0..500 {
+                    let role = if i % 2 == 0 {
+                        Role::Developer
+                    } else {
+                        Role::Viewer
+                    };
+                    rbac.update_user_roles(&user_id, vec![role]).await.unwrap();
+                }
+            })
+        };
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let rbac = rbac.clone();
+                let user_id = user.id.clone();
+                tokio::spawn(async move {
+                    for _ in 0..500 {
+                        // Both roles grant LockRead
+                        assert!(rbac
+                            .check_permission(&user_id, &Permission::LockRead)
+                            .await
+                            .unwrap());
+                        // Permissions always match the roles they were derived from
+                        let user = rbac.get_user(&user_id).await.unwrap().unwrap();
+                        assert_eq!(user.permissions, role_permissions(&user.roles));
+                    }
+                })
+            })
+            .collect();
+
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            writer.await.unwrap();
+            for reader in readers {
+                reader.await.unwrap();
+            }
+        })
+        .await
+        .expect("permission checks deadlocked");
+    }
+
     #[tokio::test]
     async fn test_role_permissions() {
         let admin_permissions = Role::Admin.get_permissions();
@@ -481,4 +605,50 @@ mod tests {
         assert!(!developer_permissions.contains(&Permission::AdminUsers));
         assert!(developer_permissions.contains(&Permission::LockCreate));
     }
+
+    #[test]
+    fn test_role_strings_round_trip() {
+        for role in [
+            Role::Admin,
+            Role::Manager,
+            Role::Developer,
+            Role::Viewer,
+            Role::Custom("billing".to_string()),
+        ] {
+            let text = role.to_string();
+            assert_eq!(text.parse::<Role>().unwrap(), role);
+            assert_eq!(
+                serde_json::to_value(&role).unwrap(),
+                serde_json::Value::String(text)
+            );
+        }
+        assert_eq!(
+            Role::Custom("billing".to_string()).to_string(),
+            "custom:billing"
+        );
+        assert_eq!(
+            serde_json::from_str::<Role>("\"custom:billing\"").unwrap(),
+            Role::Custom("billing".to_string())
+        );
+
+        for invalid in ["admin", "Superuser", "custom:", ""] {
+            assert!(invalid.parse::<Role>().is_err(), "{invalid}");
+            assert!(serde_json::from_value::<Role>(invalid.into()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_permission_strings_round_trip() {
+        for permission in Role::Admin.get_permissions() {
+            let text = permission.to_string();
+            assert_eq!(text.parse::<Permission>().unwrap(), permission);
+            assert_eq!(
+                serde_json::to_value(&permission).unwrap(),
+                serde_json::Value::String(text)
+            );
+        }
+        assert_eq!(Permission::LockCreate.to_string(), "LockCreate");
+        assert!("lock_create".parse::<Permission>().is_err());
+        assert!("".parse::<Permission>().is_err());
+    }
 }
@@ -72,6 +72,38 @@ pub enum Commands {
     },
     /// Display system information
     Info,
+    /// Import and export event streams as NDJSON
+    Events {
+        #[command(subcommand)]
+        command: EventsCommand,
+    },
+}
+
+/// Event stream transfer commands.
+///
+/// Both commands use the Postgres event store from the configuration file.
+#[derive(Subcommand)]
+pub enum EventsCommand {
+    /// Export a stream to an NDJSON file
+    Export {
+        /// Stream to export
+        #[arg(long)]
+        stream: String,
+
+        /// File to write
+        #[arg(long)]
+        out: String,
+    },
+    /// Import events from an NDJSON file
+    Import {
+        /// File to read
+        #[arg(long = "in", value_name = "FILE")]
+        input: String,
+
+        /// Renumber events whose version does not follow their stream instead of failing
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 /// Types of servers that can be started by the Syros.
@@ -83,8 +115,10 @@ pub enum ServerType {
     /// REST API server
     Rest,
     /// gRPC server
+    #[cfg(feature = "grpc")]
     Grpc,
     /// WebSocket server
+    #[cfg(feature = "websocket")]
     Websocket,
     /// All servers
     All,
@@ -3,15 +3,76 @@
 //! This module handles loading and managing configuration settings
 //! from TOML files and environment variables.
 
+use crate::core::event_store::DEFAULT_DEDUP_WINDOW;
+use crate::core::saga_idempotency::DEFAULT_IDEMPOTENCY_TTL;
+use crate::core::saga_results::{OversizedResultPolicy, DEFAULT_MAX_STEP_RESULT_BYTES};
+#[cfg(feature = "rest")]
+use crate::seed::SeedData;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
+    /// Deployment environment this instance runs in
+    #[serde(default)]
+    pub environment: Environment,
     pub server: ServerConfig,
     pub storage: StorageConfig,
     pub security: SecurityConfig,
     pub logging: LoggingConfig,
     pub service_discovery: ServiceDiscoveryConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub sagas: SagaConfig,
+    #[serde(default)]
+    pub events: EventConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    #[serde(default)]
+    pub background_tasks: BackgroundTasksConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub metadata: MetadataConfig,
+    #[serde(default)]
+    pub locks: LockConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    /// Base URL of each service saga steps call, by service name; a saga's
+    /// `service_url.<service>` metadata overrides it
+    #[serde(default)]
+    pub services: HashMap<String, String>,
+    #[serde(default)]
+    pub apis: ApisConfig,
+    #[serde(default)]
+    pub dev: DevConfig,
+}
+
+/// Deployment environment; development conveniences refuse to run in production.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Environment {
+    #[default]
+    Development,
+    Staging,
+    Production,
+}
+
+/// Development-only settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DevConfig {
+    /// Applies the seed data at startup; refused in production
+    pub seed_enabled: bool,
+    /// TOML file with more seed data, applied after `seed`
+    pub seed_file: Option<String>,
+    /// Users, API keys, saga templates, cache entries and event files to create
+    #[cfg(feature = "rest")]
+    pub seed: SeedData,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -20,14 +81,84 @@ pub struct ServerConfig {
     pub grpc_port: u16,
     pub websocket_port: u16,
     pub host: String,
+    /// How long shutdown waits for in-flight requests and running tasks,
+    /// such as sagas, to finish
+    #[serde(default = "default_drain_timeout_seconds")]
+    pub drain_timeout_seconds: u64,
+}
+
+impl ServerConfig {
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_seconds)
+    }
+}
+
+fn default_drain_timeout_seconds() -> u64 {
+    30
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct StorageConfig {
+    /// Where locks are kept
+    #[serde(default)]
+    pub locks: LockStorage,
+    /// Where sagas are kept
+    #[serde(default)]
+    pub sagas: SagaStorage,
+    /// Where event streams are kept
+    #[serde(default)]
+    pub events: EventStorage,
+    /// Where cache entries are kept
+    #[serde(default)]
+    pub cache: CacheStorage,
     pub redis: RedisConfig,
     pub database: DatabaseConfig,
 }
 
+/// Storage of the lock manager.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockStorage {
+    /// Shared by every instance pointed at `storage.redis`
+    #[default]
+    Redis,
+    /// Local to this process and lost on restart
+    Memory,
+}
+
+/// Storage of the saga orchestrator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStorage {
+    /// In the Postgres at `storage.database`, surviving restarts
+    #[default]
+    Postgres,
+    /// Local to this process and lost on restart
+    Memory,
+}
+
+/// Storage of the event store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStorage {
+    /// In the Postgres at `storage.database`, surviving restarts
+    #[default]
+    Postgres,
+    /// Local to this process and lost on restart
+    Memory,
+}
+
+/// Storage of the cache manager.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStorage {
+    /// Local to this process, surviving restarts only with `cache.persistence`
+    #[default]
+    Memory,
+    /// Shared by every instance pointed at `storage.redis`
+    Redis,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RedisConfig {
     pub url: String,
@@ -64,6 +195,430 @@ pub struct ServiceDiscoveryConfig {
     pub service_id: String,
     pub health_check_interval: u64,
     pub tags: Vec<String>,
+    /// Suffixes `service_id` per process, so replicas sharing a config file
+    /// register distinct instances
+    #[serde(default)]
+    pub auto_instance_id: bool,
+    /// Consecutive failed health checks after which an instance is reported
+    /// as warning; 1 when unset
+    #[serde(default)]
+    pub warning_after_failures: Option<u32>,
+    /// Consecutive failed health checks after which an instance is reported
+    /// as critical and no longer discovered; 3 when unset
+    #[serde(default)]
+    pub critical_after_failures: Option<u32>,
+}
+
+impl ServiceDiscoveryConfig {
+    /// ID this process registers under.
+    ///
+    /// With `auto_instance_id`, `service_id` gets the host name and a random
+    /// suffix, e.g. `syros-1-web-01-3f9a2c1e`; each call picks a new suffix.
+    pub fn generate_instance_id(&self) -> String {
+        if !self.auto_instance_id {
+            return self.service_id.clone();
+        }
+        let random = uuid::Uuid::new_v4().simple().to_string();
+        match host_name() {
+            Some(host) => format!("{}-{}-{}", self.service_id, host, &random[..8]),
+            None => format!("{}-{}", self.service_id, &random[..8]),
+        }
+    }
+}
+
+fn host_name() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CacheConfig {
+    /// Write-behind journal for the in-memory cache; disabled when absent
+    pub persistence: Option<CachePersistenceConfig>,
+    /// Most entries kept in memory, beyond which the least recently used
+    /// are evicted; unlimited when unset
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+    /// Largest value accepted, in bytes of JSON; unlimited when unset
+    #[serde(default)]
+    pub max_value_bytes: Option<usize>,
+    /// Longest a key written as known to be absent is remembered, in
+    /// seconds; 60 when unset
+    #[serde(default)]
+    pub negative_ttl_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachePersistenceConfig {
+    pub path: String,
+    #[serde(default = "default_cache_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    #[serde(default)]
+    pub fsync: FsyncPolicy,
+    #[serde(default = "default_cache_compaction_interval_seconds")]
+    pub compaction_interval_seconds: u64,
+}
+
+/// When the cache journal is synced to stable storage.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FsyncPolicy {
+    /// fsync after every batched flush
+    #[default]
+    EveryFlush,
+    /// Leave syncing to the operating system
+    Never,
+}
+
+fn default_cache_flush_interval_ms() -> u64 {
+    100
+}
+
+fn default_cache_compaction_interval_seconds() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SagaConfig {
+    /// Webhook called with a summary when a saga is dead-lettered
+    pub escalation_webhook_url: Option<String>,
+    /// Largest step result stored verbatim, in bytes
+    pub max_step_result_bytes: usize,
+    /// What happens to step results above `max_step_result_bytes`
+    pub oversized_step_results: OversizedResultPolicy,
+    /// Resume the sagas left active by a previous run at startup; off by
+    /// default, as it would also resume the sagas other live instances run
+    pub recover_on_startup: bool,
+    /// How long the idempotency key of a saga start is remembered
+    pub idempotency_ttl_seconds: u64,
+}
+
+impl SagaConfig {
+    pub fn idempotency_ttl(&self) -> Duration {
+        Duration::from_secs(self.idempotency_ttl_seconds)
+    }
+}
+
+impl Default for SagaConfig {
+    fn default() -> Self {
+        Self {
+            escalation_webhook_url: None,
+            max_step_result_bytes: DEFAULT_MAX_STEP_RESULT_BYTES,
+            oversized_step_results: OversizedResultPolicy::default(),
+            recover_on_startup: false,
+            idempotency_ttl_seconds: DEFAULT_IDEMPOTENCY_TTL.as_secs(),
+        }
+    }
+}
+
+/// Settings of the event store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventConfig {
+    /// Latest events of each stream whose client-chosen IDs appends are
+    /// deduplicated against
+    pub dedup_window: usize,
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MetricsConfig {
+    /// Export process (CPU, memory, file descriptors) and Tokio runtime metrics
+    #[serde(default)]
+    pub runtime_metrics: bool,
+}
+
+/// Limits and redaction of metadata on locks, sagas and events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataConfig {
+    /// Most keys accepted in one metadata map
+    pub max_keys: usize,
+    /// Longest key accepted, in bytes
+    pub max_key_bytes: usize,
+    /// Longest value accepted, in bytes
+    pub max_value_bytes: usize,
+    /// Keys whose values are shown as `***` in responses, matched case-insensitively
+    pub redacted_keys: Vec<String>,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            max_keys: 32,
+            max_key_bytes: 128,
+            max_value_bytes: 4 * 1024,
+            redacted_keys: ["password", "secret", "token", "api_key", "authorization"]
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Limits on lock requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockConfig {
+    /// Longest TTL a single acquisition may ask for, in seconds
+    pub max_ttl_seconds: u64,
+    /// Seconds a key's fencing counter, wait-queue audit and contention
+    /// counters are kept after its last use
+    pub idle_ttl_seconds: u64,
+    /// Longest a single acquisition may wait for a held lock, in seconds
+    pub max_wait_timeout_seconds: u64,
+}
+
+impl LockConfig {
+    /// Rejects acquisitions asking for more than `max_ttl_seconds`.
+    pub fn check_ttl(&self, ttl_seconds: u64) -> Result<(), crate::errors::SyrosError> {
+        if ttl_seconds > self.max_ttl_seconds {
+            return Err(crate::errors::SyrosError::LockError(format!(
+                "TTL of {}s exceeds the maximum of {}s",
+                ttl_seconds, self.max_ttl_seconds
+            )));
+        }
+        Ok(())
+    }
+
+    /// Rejects acquisitions waiting longer than `max_wait_timeout_seconds`.
+    pub fn check_wait_timeout(
+        &self,
+        wait_timeout_seconds: Option<u64>,
+    ) -> Result<(), crate::errors::SyrosError> {
+        match wait_timeout_seconds {
+            Some(seconds) if seconds > self.max_wait_timeout_seconds => {
+                Err(crate::errors::SyrosError::ApiError(format!(
+                    "Wait timeout of {}s exceeds the maximum of {}s",
+                    seconds, self.max_wait_timeout_seconds
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// How long the state kept per lock key outlives its last use.
+    pub fn idle_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.idle_ttl_seconds)
+    }
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        Self {
+            max_ttl_seconds: 24 * 60 * 60,
+            idle_ttl_seconds: crate::core::lock_table::DEFAULT_IDLE_KEY_TTL.as_secs(),
+            max_wait_timeout_seconds: 60 * 60,
+        }
+    }
+}
+
+/// Caps on what a single namespace may hold, so one tenant sharing the
+/// instance cannot exhaust it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Most locks a namespace may hold at once through one instance;
+    /// unlimited when unset
+    pub max_locks_per_namespace: Option<usize>,
+}
+
+/// APIs served on top of REST, among those this build was compiled with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApisConfig {
+    /// Starts the gRPC server
+    pub grpc: bool,
+    /// Serves `/graphql` and the GraphQL playground
+    pub graphql: bool,
+    /// Serves `/ws`
+    pub websocket: bool,
+}
+
+impl Default for ApisConfig {
+    fn default() -> Self {
+        Self {
+            grpc: true,
+            graphql: true,
+            websocket: true,
+        }
+    }
+}
+
+/// Per-connection limits for inbound WebSocket commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketConfig {
+    /// Sustained commands per second allowed on one connection
+    pub commands_per_second: u32,
+    /// Commands a connection may send in a burst above the sustained rate
+    pub burst: u32,
+    /// Largest inbound message accepted, in bytes
+    pub max_message_bytes: usize,
+    /// Rejected commands tolerated before the connection is closed
+    pub max_violations: u32,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            commands_per_second: 100,
+            burst: 200,
+            max_message_bytes: 64 * 1024,
+            max_violations: 20,
+        }
+    }
+}
+
+/// Time budgets for handling API requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Budget for REST requests without a route override, in milliseconds
+    pub default_ms: u64,
+    /// Per-route budgets in milliseconds, keyed by route pattern, e.g. `/api/v1/events/:stream_id`
+    pub routes: HashMap<String, u64>,
+    /// Server-side cap for gRPC calls in milliseconds; client deadlines can only shorten it
+    pub grpc_max_ms: u64,
+}
+
+impl TimeoutConfig {
+    /// Budget for requests matched by `route`.
+    pub fn for_route(&self, route: &str) -> Duration {
+        Duration::from_millis(self.routes.get(route).copied().unwrap_or(self.default_ms))
+    }
+
+    /// Server-side cap for gRPC calls.
+    pub fn grpc_max(&self) -> Duration {
+        Duration::from_millis(self.grpc_max_ms)
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            default_ms: 30_000,
+            routes: HashMap::new(),
+            grpc_max_ms: 30_000,
+        }
+    }
+}
+
+/// Shortest interval accepted for a background task.
+pub const MIN_TASK_INTERVAL_MS: u64 = 100;
+
+/// Schedule of a single background task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskSchedule {
+    /// Whether the task runs at all
+    #[serde(default = "default_task_enabled")]
+    pub enabled: bool,
+    /// Time between two runs, in milliseconds
+    pub interval_ms: u64,
+}
+
+impl TaskSchedule {
+    pub const fn every_ms(interval_ms: u64) -> Self {
+        Self {
+            enabled: true,
+            interval_ms,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+fn default_task_enabled() -> bool {
+    true
+}
+
+/// Intervals of the periodic background tasks, by task name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundTasksConfig {
+    /// Removes expired locks and reclaims idle per-key lock state
+    pub locks_sweep: TaskSchedule,
+    /// Evicts expired cache entries
+    pub cache_sweep: TaskSchedule,
+    /// Cancels sagas that exceeded their max duration and starts scheduled
+    /// sagas once their time comes
+    pub saga_scheduler: TaskSchedule,
+    /// Refreshes gauges that are not updated inline
+    pub metrics_sync: TaskSchedule,
+    /// Runs the health checks of registered services that are due
+    pub discovery_health: TaskSchedule,
+    /// Polls for messages to relay to external systems
+    pub relay_poll: TaskSchedule,
+    /// Removes saga workers that missed their heartbeat and requeues their
+    /// claims and those whose lease ran out
+    pub saga_worker_liveness: TaskSchedule,
+    /// Removes the events outside their stream's retention policy
+    pub event_retention: TaskSchedule,
+}
+
+impl Default for BackgroundTasksConfig {
+    fn default() -> Self {
+        Self {
+            locks_sweep: TaskSchedule::every_ms(1_000),
+            cache_sweep: TaskSchedule::every_ms(5_000),
+            saga_scheduler: TaskSchedule::every_ms(1_000),
+            metrics_sync: TaskSchedule::every_ms(10_000),
+            discovery_health: TaskSchedule::every_ms(1_000),
+            relay_poll: TaskSchedule::every_ms(1_000),
+            saga_worker_liveness: TaskSchedule::every_ms(5_000),
+            event_retention: TaskSchedule::every_ms(60_000),
+        }
+    }
+}
+
+impl BackgroundTasksConfig {
+    /// Every task with its name, in a stable order.
+    pub fn tasks(&self) -> [(&'static str, TaskSchedule); 8] {
+        [
+            ("locks_sweep", self.locks_sweep),
+            ("cache_sweep", self.cache_sweep),
+            ("saga_scheduler", self.saga_scheduler),
+            ("metrics_sync", self.metrics_sync),
+            ("discovery_health", self.discovery_health),
+            ("relay_poll", self.relay_poll),
+            ("saga_worker_liveness", self.saga_worker_liveness),
+            ("event_retention", self.event_retention),
+        ]
+    }
+
+    /// Schedule of the task called `name`.
+    pub fn get(&self, name: &str) -> Option<TaskSchedule> {
+        self.tasks()
+            .into_iter()
+            .find(|(task, _)| *task == name)
+            .map(|(_, schedule)| schedule)
+    }
+
+    /// Rejects enabled tasks scheduled more often than [`MIN_TASK_INTERVAL_MS`].
+    pub fn validate(&self) -> Result<(), crate::errors::SyrosError> {
+        for (name, schedule) in self.tasks() {
+            if schedule.enabled && schedule.interval_ms < MIN_TASK_INTERVAL_MS {
+                return Err(crate::errors::SyrosError::ConfigError(format!(
+                    "background_tasks.{}.interval_ms must be at least {} ms, got {}",
+                    name, MIN_TASK_INTERVAL_MS, schedule.interval_ms
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Config {
@@ -83,6 +638,7 @@ impl Config {
                 config_file_path, e
             ))
         })?;
+        config.background_tasks.validate()?;
 
         Ok(config)
     }
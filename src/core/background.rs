@@ -0,0 +1,379 @@
+//! Periodic background tasks and the registry reporting on them.
+//!
+//! Every periodic task is registered by name with a [`TaskSpawner`], which
+//! runs it on the schedule configured under `[background_tasks]`. Applying a
+//! new configuration restarts only the tasks whose schedule changed. The
+//! [`ComponentRegistry`] records each task's schedule and activity for the
+//! admin components endpoint.
+
+use crate::config::{BackgroundTasksConfig, TaskSchedule};
+use crate::core::saga_workers::SagaWorkerRegistry;
+use crate::core::task_tracker::TaskTracker;
+use crate::core::{CacheManager, EventStore, LockManager, SagaOrchestrator, ServiceDiscovery};
+use crate::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use tokio::task::AbortHandle;
+
+/// Component kind reported for background tasks.
+pub const BACKGROUND_TASK_KIND: &str = "background_task";
+
+/// State of a component as shown by the admin components endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub kind: String,
+    /// Whether the configuration enables the component
+    pub enabled: bool,
+    /// Configured interval between runs, in milliseconds
+    pub interval_ms: u64,
+    /// Whether this build has an implementation registered for the component
+    pub registered: bool,
+    /// Whether the component is currently scheduled
+    pub running: bool,
+    /// Completed runs since the process started
+    pub runs: u64,
+    /// Times the component was restarted after a configuration change
+    pub restarts: u64,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+/// Registry of the components running in this process.
+#[derive(Clone, Default)]
+pub struct ComponentRegistry {
+    components: Arc<RwLock<BTreeMap<String, ComponentStatus>>>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All components, ordered by name.
+    pub fn list(&self) -> Vec<ComponentStatus> {
+        self.components.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<ComponentStatus> {
+        self.components.read().unwrap().get(name).cloned()
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut ComponentStatus)) {
+        let mut components = self.components.write().unwrap();
+        let status = components
+            .entry(name.to_string())
+            .or_insert_with(|| ComponentStatus {
+                name: name.to_string(),
+                kind: BACKGROUND_TASK_KIND.to_string(),
+                enabled: false,
+                interval_ms: 0,
+                registered: false,
+                running: false,
+                runs: 0,
+                restarts: 0,
+                last_run_at: None,
+            });
+        f(status);
+    }
+}
+
+type TaskJob = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Runs the registered background tasks on their configured schedules.
+///
+/// Tasks stop when the spawner is dropped.
+pub struct TaskSpawner {
+    registry: ComponentRegistry,
+    jobs: HashMap<String, TaskJob>,
+    running: HashMap<String, (TaskSchedule, AbortHandle)>,
+    tasks: TaskTracker,
+}
+
+impl TaskSpawner {
+    pub fn new(registry: ComponentRegistry) -> Self {
+        Self {
+            registry,
+            jobs: HashMap::new(),
+            running: HashMap::new(),
+            tasks: TaskTracker::new(),
+        }
+    }
+
+    /// Spawns the scheduled tasks through `tasks`, under their registered
+    /// names.
+    pub fn with_task_tracker(mut self, tasks: TaskTracker) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Registers the body of the task called `name`; it is scheduled by the
+    /// next [`apply`](Self::apply).
+    pub fn register<F, Fut>(&mut self, name: &str, job: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.jobs
+            .insert(name.to_string(), Arc::new(move || Box::pin(job())));
+        self.registry
+            .update(name, |status| status.registered = true);
+    }
+
+    /// Registers the sweepers of the core managers: `locks_sweep` removes
+    /// expired locks, `cache_sweep` evicts expired cache entries and
+    /// `saga_scheduler` cancels sagas that exceeded their max duration and
+    /// starts scheduled sagas once their time comes.
+    pub fn register_sweepers(
+        &mut self,
+        locks: &LockManager,
+        cache: &CacheManager,
+        sagas: &SagaOrchestrator,
+    ) {
+        let locks = locks.clone();
+        self.register("locks_sweep", move || {
+            let locks = locks.clone();
+            async move {
+                if let Err(e) = locks.cleanup_expired_locks().await {
+                    tracing::error!("Lock sweep failed: {}", e);
+                }
+            }
+        });
+
+        let cache = cache.clone();
+        self.register("cache_sweep", move || {
+            let cache = cache.clone();
+            async move {
+                if let Err(e) = cache.cleanup_expired().await {
+                    tracing::error!("Cache sweep failed: {}", e);
+                }
+            }
+        });
+
+        let sagas = sagas.clone();
+        self.register("saga_scheduler", move || {
+            let sagas = sagas.clone();
+            async move {
+                if let Err(e) = sagas.cancel_expired_sagas().await {
+                    tracing::error!("Saga timeout watchdog failed: {}", e);
+                }
+                if let Err(e) = sagas.start_due_sagas().await {
+                    tracing::error!("Saga scheduler failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Registers `event_retention`, which removes the events outside their
+    /// stream's retention policy.
+    pub fn register_event_retention(&mut self, events: &EventStore) {
+        let events = events.clone();
+        self.register("event_retention", move || {
+            let events = events.clone();
+            async move {
+                match events.enforce_retention().await {
+                    Ok(0) => {}
+                    Ok(removed) => tracing::debug!("Event retention removed {} events", removed),
+                    Err(e) => tracing::error!("Event retention failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Registers `discovery_health`, which runs the health checks of the
+    /// instances registered with `discovery` once their interval elapsed.
+    pub fn register_discovery_health(&mut self, discovery: &Arc<ServiceDiscovery>) {
+        let discovery = discovery.clone();
+        self.register("discovery_health", move || {
+            let discovery = discovery.clone();
+            async move {
+                discovery.run_due_checks().await;
+            }
+        });
+    }
+
+    /// Registers `saga_worker_liveness`, which removes the workers that
+    /// missed their heartbeat and requeues their claims and those whose
+    /// lease ran out.
+    pub fn register_saga_worker_liveness(&mut self, workers: &SagaWorkerRegistry) {
+        let workers = workers.clone();
+        self.register("saga_worker_liveness", move || {
+            let workers = workers.clone();
+            async move {
+                let requeued = workers.reap(Utc::now()).await;
+                if requeued > 0 {
+                    tracing::debug!("Requeued {} saga worker claims", requeued);
+                }
+            }
+        });
+    }
+
+    /// Schedules the registered tasks according to `config`.
+    ///
+    /// Tasks whose schedule is unchanged keep running untouched; changed ones
+    /// are restarted and disabled ones stopped. An invalid configuration is
+    /// rejected as a whole, leaving every task as it was.
+    pub fn apply(&mut self, config: &BackgroundTasksConfig) -> Result<()> {
+        config.validate()?;
+
+        for (name, schedule) in config.tasks() {
+            self.registry.update(name, |status| {
+                status.enabled = schedule.enabled;
+                status.interval_ms = schedule.interval_ms;
+            });
+
+            let current = self.running.get(name).map(|(current, _)| *current);
+            if current == Some(schedule) {
+                continue;
+            }
+            if let Some((_, task)) = self.running.remove(name) {
+                task.abort();
+                self.registry.update(name, |status| {
+                    status.running = false;
+                    status.restarts += u64::from(schedule.enabled);
+                });
+            }
+
+            let Some(job) = self.jobs.get(name) else {
+                continue;
+            };
+            if !schedule.enabled {
+                continue;
+            }
+            let task = spawn_task(
+                &self.tasks,
+                name,
+                schedule,
+                job.clone(),
+                self.registry.clone(),
+            );
+            self.running.insert(name.to_string(), (schedule, task));
+            self.registry.update(name, |status| status.running = true);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for TaskSpawner {
+    fn drop(&mut self) {
+        for (_, task) in self.running.values() {
+            task.abort();
+        }
+    }
+}
+
+fn spawn_task(
+    tasks: &TaskTracker,
+    name: &str,
+    schedule: TaskSchedule,
+    job: TaskJob,
+    registry: ComponentRegistry,
+) -> AbortHandle {
+    let task_name = name.to_string();
+    tasks
+        .spawn_background(name, async move {
+            let mut ticker = tokio::time::interval(schedule.interval());
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                job().await;
+                registry.update(&task_name, |status| {
+                    status.runs += 1;
+                    status.last_run_at = Some(Utc::now());
+                });
+            }
+        })
+        .abort_handle()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    fn counting_task(spawner: &mut TaskSpawner, name: &str) -> Arc<AtomicU64> {
+        let count = Arc::new(AtomicU64::new(0));
+        let counter = count.clone();
+        spawner.register(name, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        count
+    }
+
+    fn config(locks_sweep: TaskSchedule, cache_sweep: TaskSchedule) -> BackgroundTasksConfig {
+        BackgroundTasksConfig {
+            locks_sweep,
+            cache_sweep,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tasks_do_not_run() {
+        let registry = ComponentRegistry::new();
+        let mut spawner = TaskSpawner::new(registry.clone());
+        let locks = counting_task(&mut spawner, "locks_sweep");
+        let cache = counting_task(&mut spawner, "cache_sweep");
+
+        let disabled = TaskSchedule {
+            enabled: false,
+            interval_ms: 100,
+        };
+        spawner
+            .apply(&config(TaskSchedule::every_ms(100), disabled))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(350)).await;
+
+        assert!(locks.load(Ordering::SeqCst) >= 3);
+        assert_eq!(cache.load(Ordering::SeqCst), 0);
+
+        let cache_status = registry.get("cache_sweep").unwrap();
+        assert!(cache_status.registered && !cache_status.enabled && !cache_status.running);
+        // Configured but without an implementation in this process.
+        let relay = registry.get("relay_poll").unwrap();
+        assert!(relay.enabled && !relay.registered && !relay.running);
+        assert_eq!(registry.list().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_reload_restarts_only_changed_tasks() {
+        let registry = ComponentRegistry::new();
+        let mut spawner = TaskSpawner::new(registry.clone());
+        let locks = counting_task(&mut spawner, "locks_sweep");
+        let cache = counting_task(&mut spawner, "cache_sweep");
+
+        let slow = TaskSchedule::every_ms(60_000);
+        spawner.apply(&config(slow, slow)).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // Both ran once, on their first tick.
+        assert_eq!(locks.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.load(Ordering::SeqCst), 1);
+
+        spawner
+            .apply(&config(TaskSchedule::every_ms(100), slow))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(350)).await;
+
+        assert!(locks.load(Ordering::SeqCst) >= 4);
+        // An unchanged task is not restarted, so it does not tick again.
+        assert_eq!(cache.load(Ordering::SeqCst), 1);
+        assert_eq!(registry.get("locks_sweep").unwrap().restarts, 1);
+        assert_eq!(registry.get("locks_sweep").unwrap().interval_ms, 100);
+        assert_eq!(registry.get("cache_sweep").unwrap().restarts, 0);
+
+        // Invalid intervals are rejected without touching the running tasks.
+        assert!(spawner
+            .apply(&config(TaskSchedule::every_ms(10), slow))
+            .is_err());
+        assert_eq!(registry.get("locks_sweep").unwrap().interval_ms, 100);
+        assert!(registry.get("locks_sweep").unwrap().running);
+    }
+}
@@ -0,0 +1,396 @@
+//! Storage of cache entries, and layers consulted when a key is not in it.
+//!
+//! A [`CacheBackend`] holds the entries a
+//! [`CacheManager`](crate::core::CacheManager) writes: in process memory by
+//! default, or in Redis, where every instance pointed at the same server
+//! shares them.
+//!
+//! A [`CacheBackendChain`] lists [`CacheLayer`]s in lookup order, e.g. a
+//! shared Redis cache followed by the origin the data comes from. The
+//! [`CacheManager`](crate::core::CacheManager) serves live entries from
+//! memory and falls through the chain otherwise: the first layer holding the
+//! key wins, and its value is copied into memory. Every response records
+//! which of them served it as a [`CacheSource`].
+//!
+//! The chain also decides what happens when the layers come up empty or
+//! fail: with a negative TTL, keys no layer holds are remembered as absent,
+//! and with a stale TTL, an expired entry is served for that long after its
+//! expiry when every layer fails.
+
+use crate::core::cache_manager::{CacheEntry, CacheSetMode, CacheStats};
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Where a cache read was served from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheSource {
+    /// A live entry in the local cache
+    Memory,
+    /// A shared Redis cache layer
+    Redis,
+    /// The origin behind the cache, read through on a miss
+    Origin,
+    /// A remembered absence: no layer held the key on a recent lookup
+    Negative,
+    /// An expired entry, served because every layer failed
+    Stale,
+}
+
+impl CacheSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheSource::Memory => "memory",
+            CacheSource::Redis => "redis",
+            CacheSource::Origin => "origin",
+            CacheSource::Negative => "negative",
+            CacheSource::Stale => "stale",
+        }
+    }
+}
+
+impl fmt::Display for CacheSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where a [`CacheManager`](crate::core::CacheManager) keeps its entries.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// The live entry under `key`, including remembered absences.
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>>;
+
+    /// Stores `entry` as `mode` allows and returns it as stored, numbered
+    /// one version past the live entry it replaces.
+    ///
+    /// Fails with `Conflict` for `CreateOnly` if a live entry exists, and
+    /// with `NotFound` for `UpdateOnly` if none does.
+    async fn set(&self, entry: CacheEntry, mode: CacheSetMode) -> Result<CacheEntry>;
+
+    /// Replaces the live entry under `entry.key` with `entry` if it is at
+    /// `expected_version`, keeping its tags; returns it as stored.
+    ///
+    /// Fails with `Conflict` if there is no live entry at that version.
+    async fn compare_and_swap(
+        &self,
+        entry: CacheEntry,
+        expected_version: u64,
+    ) -> Result<CacheEntry>;
+
+    /// Adds `delta` to the integer under `key` and returns the sum, keeping
+    /// the entry's expiry; a missing key starts from zero and expires after
+    /// `ttl_if_new`.
+    ///
+    /// Fails with `Conflict` if the live entry is not an integer or the sum
+    /// overflows.
+    async fn increment(&self, key: &str, delta: i64, ttl_if_new: Option<Duration>) -> Result<i64>;
+
+    /// Makes the live entry under `key` expire at `expires_at`, or never if
+    /// `None`, keeping its value and version; returns it as stored.
+    ///
+    /// Fails with `NotFound` if there is no live entry.
+    async fn touch(&self, key: &str, expires_at: Option<DateTime<Utc>>) -> Result<CacheEntry>;
+
+    /// Adds `add` to the tags of the live entry under `key`, then removes
+    /// `remove`, keeping its value, expiry and version; returns it as stored.
+    ///
+    /// Fails with `NotFound` if there is no live entry.
+    async fn retag(&self, key: &str, add: &[String], remove: &[String]) -> Result<CacheEntry>;
+
+    /// Removes the entry under `key`; returns whether there was one.
+    async fn delete(&self, key: &str) -> Result<bool>;
+
+    /// Removes every entry carrying `tag`; returns how many.
+    async fn invalidate_by_tag(&self, tag: &str) -> Result<u64>;
+
+    /// Up to `limit` live entries whose key matches the glob `pattern` and
+    /// that carry all of `tags`, ordered by key; absences are left out.
+    async fn list(
+        &self,
+        pattern: Option<&str>,
+        tags: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<CacheEntry>>;
+
+    async fn stats(&self) -> Result<CacheStats>;
+
+    /// The live entries under `keys`, in the same order.
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<CacheEntry>>> {
+        let mut found = Vec::with_capacity(keys.len());
+        for key in keys {
+            found.push(self.get(key).await?);
+        }
+        Ok(found)
+    }
+
+    /// Stores each entry as its mode allows, in order; returns the outcome
+    /// of each write.
+    async fn set_many(
+        &self,
+        entries: Vec<(CacheEntry, CacheSetMode)>,
+    ) -> Result<Vec<Result<CacheEntry>>> {
+        let mut stored = Vec::with_capacity(entries.len());
+        for (entry, mode) in entries {
+            stored.push(self.set(entry, mode).await);
+        }
+        Ok(stored)
+    }
+
+    /// Removes the entries under `keys`; returns whether each was there.
+    async fn delete_many(&self, keys: &[String]) -> Result<Vec<bool>> {
+        let mut deleted = Vec::with_capacity(keys.len());
+        for key in keys {
+            deleted.push(self.delete(key).await?);
+        }
+        Ok(deleted)
+    }
+}
+
+/// Error of a compare-and-swap of a key not at the expected version.
+pub(crate) fn version_mismatch(key: &str, expected_version: u64) -> SyrosError {
+    SyrosError::Conflict(format!(
+        "Cache key {} is not at version {}",
+        key, expected_version
+    ))
+}
+
+/// Error of a touch or retag of a key with no live entry.
+pub(crate) fn key_not_found(key: &str) -> SyrosError {
+    SyrosError::NotFound(format!("Cache key {} not found", key))
+}
+
+/// Error of an increment of a key that does not hold an integer.
+pub(crate) fn not_an_integer(key: &str) -> SyrosError {
+    SyrosError::Conflict(format!("Cache key {} does not hold an integer", key))
+}
+
+/// Error of an increment past the range of a 64-bit integer.
+pub(crate) fn increment_overflow(key: &str) -> SyrosError {
+    SyrosError::Conflict(format!("Incrementing cache key {} would overflow", key))
+}
+
+/// Longest TTL a cache entry may be written with, about 100 years.
+pub const MAX_CACHE_TTL: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// Fails with `ApiError` if `ttl` is longer than [`MAX_CACHE_TTL`].
+pub fn check_ttl(ttl: Option<Duration>) -> Result<()> {
+    match ttl {
+        Some(ttl) if ttl > MAX_CACHE_TTL => Err(SyrosError::ApiError(format!(
+            "TTL of {}s exceeds the maximum of {}s",
+            ttl.as_secs(),
+            MAX_CACHE_TTL.as_secs()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Key no entry may be written under: the REST routes of tags under
+/// `/api/v1/cache/tags/` shadow the routes of an entry of that name.
+pub const RESERVED_CACHE_KEY: &str = "tags";
+
+/// Fails with `ApiError` for [`RESERVED_CACHE_KEY`].
+pub fn check_key(key: &str) -> Result<()> {
+    if key == RESERVED_CACHE_KEY {
+        return Err(SyrosError::ApiError(format!(
+            "Cache key {} is reserved for tag routes",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// When an entry written at `now` with `ttl` expires; a TTL longer than
+/// [`MAX_CACHE_TTL`] is cut to it.
+pub(crate) fn expiry_after(now: DateTime<Utc>, ttl: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(ttl.min(MAX_CACHE_TTL))
+        .ok()
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// `tags` with those of `add` it lacks appended, then those of `remove`
+/// left out.
+pub(crate) fn retagged(tags: &[String], add: &[String], remove: &[String]) -> Vec<String> {
+    let mut retagged = tags.to_vec();
+    for tag in add {
+        if !retagged.contains(tag) {
+            retagged.push(tag.clone());
+        }
+    }
+    retagged.retain(|tag| !remove.contains(tag));
+    retagged
+}
+
+/// A read-only cache layer below the in-memory cache.
+#[async_trait]
+pub trait CacheLayer: Send + Sync {
+    /// Source reported for values this layer serves.
+    fn source(&self) -> CacheSource;
+
+    /// The value stored under `key`, or `None` if the layer does not hold it.
+    async fn get(&self, key: &str) -> Result<Option<serde_json::Value>>;
+}
+
+/// Outcome of a lookup through a [`CacheBackendChain`].
+#[derive(Debug)]
+pub enum ChainLookup {
+    /// A layer held the key
+    Found {
+        value: serde_json::Value,
+        source: CacheSource,
+    },
+    /// Every layer answered and none held the key
+    Absent,
+    /// No layer held the key and at least one failed
+    Failed(crate::SyrosError),
+}
+
+/// Ordered layers behind the in-memory cache; empty by default.
+#[derive(Clone, Default)]
+pub struct CacheBackendChain {
+    layers: Vec<Arc<dyn CacheLayer>>,
+    fill_ttl: Option<Duration>,
+    negative_ttl: Option<Duration>,
+    stale_ttl: Option<Duration>,
+}
+
+impl CacheBackendChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a layer, consulted after the ones already in the chain.
+    pub fn with_layer(mut self, layer: Arc<dyn CacheLayer>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// How long values found in a layer are kept in memory; forever if unset.
+    pub fn with_fill_ttl(mut self, ttl: Duration) -> Self {
+        self.fill_ttl = Some(ttl);
+        self
+    }
+
+    /// Remembers keys no layer holds for `ttl`, answering them from memory.
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(ttl);
+        self
+    }
+
+    /// Serves expired entries for up to `ttl` past their expiry when every
+    /// layer fails.
+    pub fn with_stale_ttl(mut self, ttl: Duration) -> Self {
+        self.stale_ttl = Some(ttl);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub fn fill_ttl(&self) -> Option<Duration> {
+        self.fill_ttl
+    }
+
+    pub fn negative_ttl(&self) -> Option<Duration> {
+        self.negative_ttl
+    }
+
+    /// How long expired entries are kept for stale reads; zero without layers.
+    pub fn stale_ttl(&self) -> Duration {
+        match self.stale_ttl {
+            Some(ttl) if !self.is_empty() => ttl,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Asks each layer in turn for `key`.
+    ///
+    /// A failing layer is logged and skipped, so a later layer can still
+    /// serve the key.
+    pub async fn lookup(&self, key: &str) -> ChainLookup {
+        let mut failure = None;
+        for layer in &self.layers {
+            match layer.get(key).await {
+                Ok(Some(value)) => {
+                    return ChainLookup::Found {
+                        value,
+                        source: layer.source(),
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "Cache layer {} failed to look up {}: {}",
+                        layer.source(),
+                        key,
+                        e
+                    );
+                    failure = Some(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) => ChainLookup::Failed(e),
+            None => ChainLookup::Absent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyrosError;
+
+    struct Stub(CacheSource, Option<Result<Option<serde_json::Value>>>);
+
+    #[async_trait]
+    impl CacheLayer for Stub {
+        fn source(&self) -> CacheSource {
+            self.0
+        }
+
+        async fn get(&self, _key: &str) -> Result<Option<serde_json::Value>> {
+            match &self.1 {
+                Some(Ok(value)) => Ok(value.clone()),
+                Some(Err(e)) => Err(SyrosError::StorageError(e.to_string())),
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_falls_through_failing_and_empty_layers() {
+        let failing = Stub(
+            CacheSource::Redis,
+            Some(Err(SyrosError::StorageError("down".to_string()))),
+        );
+        let origin = Stub(CacheSource::Origin, Some(Ok(Some(serde_json::json!(7)))));
+        let chain = CacheBackendChain::new()
+            .with_layer(Arc::new(failing))
+            .with_layer(Arc::new(origin));
+
+        match chain.lookup("k").await {
+            ChainLookup::Found { value, source } => {
+                assert_eq!(value, serde_json::json!(7));
+                assert_eq!(source, CacheSource::Origin);
+            }
+            other => panic!("unexpected lookup: {:?}", other),
+        }
+
+        let empty = CacheBackendChain::new().with_layer(Arc::new(Stub(CacheSource::Redis, None)));
+        assert!(matches!(empty.lookup("k").await, ChainLookup::Absent));
+
+        let failing = CacheBackendChain::new().with_layer(Arc::new(Stub(
+            CacheSource::Origin,
+            Some(Err(SyrosError::StorageError("down".to_string()))),
+        )));
+        assert!(matches!(failing.lookup("k").await, ChainLookup::Failed(_)));
+    }
+}
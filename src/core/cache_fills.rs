@@ -0,0 +1,141 @@
+//! Leases on missing cache keys, so that one caller recomputes a value while
+//! the others wait for it instead of recomputing it too.
+//!
+//! The caller holding a key's lease is expected to store the value before
+//! the lease expires; storing it releases the lease and wakes the waiters.
+//! A lease left to expire passes to the next caller. Leases are local to
+//! this process, so instances sharing a Redis cache each elect a leader.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// How long a fetch caller may take to compute and store a missing value
+/// by default.
+pub const DEFAULT_FILL_LEASE: Duration = Duration::from_secs(10);
+
+/// How long a fetch waits by default for a value another caller computes.
+pub const DEFAULT_FILL_WAIT: Duration = Duration::from_secs(5);
+
+/// Longest lease a fetch caller may ask for.
+pub const MAX_FILL_LEASE: Duration = Duration::from_secs(3600);
+
+/// Longest a fetch may wait for a value another caller computes.
+pub const MAX_FILL_WAIT: Duration = Duration::from_secs(60);
+
+struct FillLease {
+    expires_at: Instant,
+    filled: Arc<Notify>,
+}
+
+/// Outcome of [`FillLeases::try_acquire`].
+pub enum FillLeaseAttempt {
+    /// The caller holds the lease until `expires_at`
+    Acquired { expires_at: Instant },
+    /// Another caller holds the lease until `expires_at`; `filled` is
+    /// notified when it stores the value
+    Held {
+        expires_at: Instant,
+        filled: Arc<Notify>,
+    },
+}
+
+/// Leases on the cache keys being recomputed.
+#[derive(Clone, Default)]
+pub struct FillLeases {
+    leases: Arc<Mutex<HashMap<String, FillLease>>>,
+}
+
+impl FillLeases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the lease on `key` for `duration`, at most [`MAX_FILL_LEASE`],
+    /// unless another caller holds a lease on it that has not expired.
+    pub fn try_acquire(&self, key: &str, duration: Duration) -> FillLeaseAttempt {
+        let now = Instant::now();
+        // Computed before locking: a panic under the lock would poison it.
+        let expires_at = now + duration.min(MAX_FILL_LEASE);
+        let mut leases = self.leases.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(lease) = leases.get(key).filter(|lease| lease.expires_at > now) {
+            return FillLeaseAttempt::Held {
+                expires_at: lease.expires_at,
+                filled: lease.filled.clone(),
+            };
+        }
+        leases.insert(
+            key.to_string(),
+            FillLease {
+                expires_at,
+                filled: Arc::new(Notify::new()),
+            },
+        );
+        FillLeaseAttempt::Acquired { expires_at }
+    }
+
+    /// Drops the lease on `key`, waking the callers waiting for its value.
+    pub fn release(&self, key: &str) {
+        if let Some(lease) = self
+            .leases
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key)
+        {
+            lease.filled.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lease_passes_on_after_expiry_or_release() {
+        let leases = FillLeases::new();
+        assert!(matches!(
+            leases.try_acquire("k", Duration::from_millis(20)),
+            FillLeaseAttempt::Acquired { .. }
+        ));
+        assert!(matches!(
+            leases.try_acquire("k", Duration::from_secs(10)),
+            FillLeaseAttempt::Held { .. }
+        ));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(matches!(
+            leases.try_acquire("k", Duration::from_secs(10)),
+            FillLeaseAttempt::Acquired { .. }
+        ));
+
+        let FillLeaseAttempt::Held { filled, .. } = leases.try_acquire("k", Duration::ZERO) else {
+            panic!("lease should still be held");
+        };
+        let woken = filled.notified();
+        leases.release("k");
+        woken.await;
+        assert!(matches!(
+            leases.try_acquire("k", Duration::from_secs(10)),
+            FillLeaseAttempt::Acquired { .. }
+        ));
+    }
+
+    #[test]
+    fn test_huge_lease_is_capped_without_poisoning() {
+        let leases = FillLeases::new();
+        let FillLeaseAttempt::Acquired { expires_at } =
+            leases.try_acquire("k", Duration::from_secs(u64::MAX))
+        else {
+            panic!("lease should be acquired");
+        };
+        assert!(expires_at <= Instant::now() + MAX_FILL_LEASE);
+        leases.release("k");
+        assert!(matches!(
+            leases.try_acquire("k", Duration::from_secs(1)),
+            FillLeaseAttempt::Acquired { .. }
+        ));
+    }
+}
@@ -0,0 +1,541 @@
+//! Write-behind journal for the in-memory cache.
+//!
+//! Cache mutations are appended to a local newline-delimited JSON file by a
+//! background writer task, replayed on startup to rebuild the cache, and
+//! periodically compacted into a snapshot of the live entries.
+
+use crate::config::{CachePersistenceConfig, FsyncPolicy};
+use crate::core::cache_manager::CacheEntry;
+use crate::core::task_tracker::TaskTracker;
+use crate::{Result, SyrosError};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot, RwLock};
+
+/// Number of buffered records that triggers an early flush.
+const MAX_BATCH_SIZE: usize = 512;
+
+/// A single cache mutation as stored in the journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalRecord {
+    /// An entry was inserted or overwritten
+    Set { entry: CacheEntry },
+    /// A key was deleted
+    Delete { key: String },
+    /// Every entry carrying the tag was removed
+    InvalidateTag { tag: String },
+}
+
+enum JournalCommand {
+    Record(JournalRecord),
+    Flush(oneshot::Sender<Result<()>>),
+    Compact(oneshot::Sender<Result<()>>),
+}
+
+/// Handle used by the cache manager to queue mutations for the writer task.
+#[derive(Clone)]
+pub struct CacheJournal {
+    sender: mpsc::UnboundedSender<JournalCommand>,
+}
+
+impl CacheJournal {
+    /// Replays the journal at `path`, returning the live (non-expired) entries.
+    ///
+    /// A missing file yields an empty cache. Records that cannot be parsed,
+    /// such as a torn write at the tail of the file, are skipped with a warning.
+    pub fn replay(path: &Path) -> Result<HashMap<String, CacheEntry>> {
+        let mut entries = HashMap::new();
+
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => {
+                return Err(SyrosError::StorageError(format!(
+                    "Failed to open cache journal {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        for (line_number, line) in BufReader::new(file).lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    tracing::warn!(
+                        "Stopping cache journal replay at line {} of {}: {}",
+                        line_number + 1,
+                        path.display(),
+                        e
+                    );
+                    break;
+                }
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<JournalRecord>(&line) {
+                Ok(record) => apply_record(&mut entries, record),
+                Err(e) => tracing::warn!(
+                    "Skipping corrupt cache journal record at line {} of {}: {}",
+                    line_number + 1,
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        let now = Utc::now();
+        entries.retain(|_, entry| entry.expires_at.is_none_or(|expires_at| expires_at > now));
+
+        Ok(entries)
+    }
+
+    /// Opens the journal for appending and spawns the background writer task
+    /// through `tasks`.
+    ///
+    /// The writer holds a handle to `cache` so compaction can snapshot the
+    /// live entries. It exits after a final flush once every journal handle
+    /// has been dropped, or when `tasks` drains.
+    pub async fn spawn(
+        config: &CachePersistenceConfig,
+        cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
+        tasks: &TaskTracker,
+    ) -> Result<Self> {
+        let path = PathBuf::from(&config.path);
+        let file = open_append(&path).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let writer = JournalWriter {
+            path,
+            file,
+            fsync: config.fsync.clone(),
+            cache,
+            pending: Vec::new(),
+        };
+
+        tasks.spawn_background(
+            "cache_journal",
+            writer.run(
+                receiver,
+                Duration::from_millis(config.flush_interval_ms.max(1)),
+                Duration::from_secs(config.compaction_interval_seconds.max(1)),
+            ),
+        );
+
+        Ok(Self { sender })
+    }
+
+    /// Queues a mutation for the next flush.
+    ///
+    /// Callers should invoke this while still holding the cache write lock so
+    /// records reach the writer in the same order the mutations were applied.
+    pub fn record(&self, record: JournalRecord) {
+        if self.sender.send(JournalCommand::Record(record)).is_err() {
+            tracing::warn!("Cache journal writer is gone; mutation was not persisted");
+        }
+    }
+
+    /// Writes every queued record to disk and waits for completion.
+    pub async fn flush(&self) -> Result<()> {
+        self.request(JournalCommand::Flush).await
+    }
+
+    /// Rewrites the journal as a snapshot of the live entries.
+    pub async fn compact(&self) -> Result<()> {
+        self.request(JournalCommand::Compact).await
+    }
+
+    async fn request(
+        &self,
+        command: impl FnOnce(oneshot::Sender<Result<()>>) -> JournalCommand,
+    ) -> Result<()> {
+        let (ack, done) = oneshot::channel();
+        self.sender.send(command(ack)).map_err(|_| {
+            SyrosError::StorageError("Cache journal writer is not running".to_string())
+        })?;
+        done.await.map_err(|_| {
+            SyrosError::StorageError("Cache journal writer stopped unexpectedly".to_string())
+        })?
+    }
+}
+
+fn apply_record(entries: &mut HashMap<String, CacheEntry>, record: JournalRecord) {
+    match record {
+        JournalRecord::Set { entry } => {
+            entries.insert(entry.key.clone(), entry);
+        }
+        JournalRecord::Delete { key } => {
+            entries.remove(&key);
+        }
+        JournalRecord::InvalidateTag { tag } => {
+            entries.retain(|_, entry| !entry.tags.contains(&tag));
+        }
+    }
+}
+
+async fn open_append(path: &Path) -> Result<tokio::fs::File> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                SyrosError::StorageError(format!(
+                    "Failed to create cache journal directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
+        }
+    }
+
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| {
+            SyrosError::StorageError(format!(
+                "Failed to open cache journal {}: {}",
+                path.display(),
+                e
+            ))
+        })
+}
+
+fn encode_line(buffer: &mut Vec<u8>, record: &JournalRecord) {
+    match serde_json::to_vec(record) {
+        Ok(bytes) => {
+            buffer.extend_from_slice(&bytes);
+            buffer.push(b'\n');
+        }
+        Err(e) => tracing::warn!("Failed to encode cache journal record: {}", e),
+    }
+}
+
+struct JournalWriter {
+    path: PathBuf,
+    file: tokio::fs::File,
+    fsync: FsyncPolicy,
+    cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    pending: Vec<JournalRecord>,
+}
+
+impl JournalWriter {
+    async fn run(
+        mut self,
+        mut receiver: mpsc::UnboundedReceiver<JournalCommand>,
+        flush_interval: Duration,
+        compaction_interval: Duration,
+    ) {
+        let mut flush_tick = tokio::time::interval(flush_interval);
+        let mut compact_tick = tokio::time::interval_at(
+            tokio::time::Instant::now() + compaction_interval,
+            compaction_interval,
+        );
+
+        loop {
+            tokio::select! {
+                command = receiver.recv() => match command {
+                    Some(JournalCommand::Record(record)) => {
+                        self.pending.push(record);
+                        if self.pending.len() >= MAX_BATCH_SIZE {
+                            self.flush_logged().await;
+                        }
+                    }
+                    Some(JournalCommand::Flush(ack)) => {
+                        let _ = ack.send(self.flush().await);
+                    }
+                    Some(JournalCommand::Compact(ack)) => {
+                        let _ = ack.send(self.compact().await);
+                    }
+                    None => {
+                        self.flush_logged().await;
+                        break;
+                    }
+                },
+                _ = flush_tick.tick() => self.flush_logged().await,
+                _ = compact_tick.tick() => {
+                    if let Err(e) = self.compact().await {
+                        tracing::warn!("Cache journal compaction failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn flush_logged(&mut self) {
+        if let Err(e) = self.flush().await {
+            tracing::warn!("Cache journal flush failed: {}", e);
+        }
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffer = Vec::new();
+        for record in &self.pending {
+            encode_line(&mut buffer, record);
+        }
+
+        self.write(&buffer).await?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    async fn write(&mut self, buffer: &[u8]) -> Result<()> {
+        self.file
+            .write_all(buffer)
+            .await
+            .map_err(|e| SyrosError::StorageError(format!("Cache journal write failed: {}", e)))?;
+        self.file
+            .flush()
+            .await
+            .map_err(|e| SyrosError::StorageError(format!("Cache journal write failed: {}", e)))?;
+
+        if self.fsync == FsyncPolicy::EveryFlush {
+            self.file.sync_data().await.map_err(|e| {
+                SyrosError::StorageError(format!("Cache journal fsync failed: {}", e))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the journal with one `set` record per live entry.
+    ///
+    /// Records still queued when the snapshot is taken are appended afterwards;
+    /// every record overwrites the full state of its key, so replaying them on
+    /// top of a snapshot that already reflects them yields the same cache.
+    async fn compact(&mut self) -> Result<()> {
+        let now = Utc::now();
+        let mut buffer = Vec::new();
+        {
+            let cache = self.cache.read().await;
+            for entry in cache.values() {
+                if entry.expires_at.is_none_or(|expires_at| expires_at > now) {
+                    encode_line(
+                        &mut buffer,
+                        &JournalRecord::Set {
+                            entry: entry.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        let mut snapshot_path = self.path.clone().into_os_string();
+        snapshot_path.push(".compact");
+        let snapshot_path = PathBuf::from(snapshot_path);
+
+        let map_err = |e: std::io::Error| {
+            SyrosError::StorageError(format!("Cache journal compaction failed: {}", e))
+        };
+
+        let mut snapshot = tokio::fs::File::create(&snapshot_path)
+            .await
+            .map_err(map_err)?;
+        snapshot.write_all(&buffer).await.map_err(map_err)?;
+        snapshot.sync_all().await.map_err(map_err)?;
+        drop(snapshot);
+
+        tokio::fs::rename(&snapshot_path, &self.path)
+            .await
+            .map_err(map_err)?;
+        self.file = open_append(&self.path).await?;
+
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache_manager::{
+        CacheManager, CacheRequest, CacheSetMode, DeleteCacheRequest,
+    };
+    use std::io::Write;
+
+    fn journal_config(path: &Path) -> CachePersistenceConfig {
+        CachePersistenceConfig {
+            path: path.to_string_lossy().to_string(),
+            flush_interval_ms: 10,
+            fsync: FsyncPolicy::EveryFlush,
+            compaction_interval_seconds: 3600,
+        }
+    }
+
+    fn temp_journal_path() -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "syros-cache-journal-{}.ndjson",
+            uuid::Uuid::new_v4()
+        ))
+    }
+
+    fn cache_request(key: &str, value: serde_json::Value, ttl: Option<Duration>) -> CacheRequest {
+        CacheRequest {
+            key: key.to_string(),
+            value,
+            ttl,
+            tags: vec!["journal".to_string()],
+            mode: CacheSetMode::Upsert,
+            created_by: None,
+            cache_negative: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_rebuilt_from_journal() {
+        let path = temp_journal_path();
+        let config = journal_config(&path);
+
+        let cache = CacheManager::with_persistence(&config, &TaskTracker::new())
+            .await
+            .unwrap();
+        cache
+            .set(cache_request(
+                "user:1",
+                serde_json::json!({"name": "ana"}),
+                Some(Duration::from_secs(3600)),
+            ))
+            .await
+            .unwrap();
+        cache
+            .set(cache_request("user:2", serde_json::json!(42), None))
+            .await
+            .unwrap();
+        cache
+            .set(cache_request("user:3", serde_json::json!("gone"), None))
+            .await
+            .unwrap();
+        cache
+            .delete(DeleteCacheRequest {
+                key: "user:3".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let original = cache.get_entry("user:1").await.unwrap().unwrap();
+        cache.flush().await.unwrap();
+        drop(cache);
+
+        let rebuilt = CacheManager::with_persistence(&config, &TaskTracker::new())
+            .await
+            .unwrap();
+
+        let restored = rebuilt.get_entry("user:1").await.unwrap().unwrap();
+        assert_eq!(restored.value, serde_json::json!({"name": "ana"}));
+        assert_eq!(restored.expires_at, original.expires_at);
+        assert_eq!(restored.tags, vec!["journal".to_string()]);
+
+        let no_ttl = rebuilt.get("user:2").await.unwrap();
+        assert!(no_ttl.found);
+        assert_eq!(no_ttl.value, Some(serde_json::json!(42)));
+        assert!(rebuilt
+            .get_entry("user:2")
+            .await
+            .unwrap()
+            .unwrap()
+            .expires_at
+            .is_none());
+
+        assert!(!rebuilt.get("user:3").await.unwrap().found);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_replay_skips_corrupt_tail_and_expired_entries() {
+        let path = temp_journal_path();
+        let now = Utc::now();
+
+        let live = CacheEntry {
+            key: "live".to_string(),
+            value: serde_json::json!("ok"),
+            expires_at: Some(now + chrono::Duration::hours(1)),
+            tags: vec![],
+            created_at: now,
+            created_by: None,
+            negative: false,
+            version: 1,
+        };
+        let expired = CacheEntry {
+            key: "expired".to_string(),
+            value: serde_json::json!("old"),
+            expires_at: Some(now - chrono::Duration::seconds(1)),
+            tags: vec![],
+            created_at: now,
+            created_by: None,
+            negative: false,
+            version: 1,
+        };
+
+        let mut file = std::fs::File::create(&path).unwrap();
+        for entry in [live, expired] {
+            let line = serde_json::to_string(&JournalRecord::Set { entry }).unwrap();
+            writeln!(file, "{}", line).unwrap();
+        }
+        write!(file, "{{\"op\":\"set\",\"entry\":{{\"key\":\"torn").unwrap();
+        drop(file);
+
+        let entries = CacheJournal::replay(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries.contains_key("live"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_compaction_keeps_only_live_entries() {
+        let path = temp_journal_path();
+        let config = journal_config(&path);
+
+        let cache = CacheManager::with_persistence(&config, &TaskTracker::new())
+            .await
+            .unwrap();
+        for i in 0..20 {
+            cache
+                .set(cache_request(
+                    &format!("key:{}", i),
+                    serde_json::json!(i),
+                    None,
+                ))
+                .await
+                .unwrap();
+        }
+        for i in 0..15 {
+            cache
+                .delete(DeleteCacheRequest {
+                    key: format!("key:{}", i),
+                })
+                .await
+                .unwrap();
+        }
+
+        cache.flush().await.unwrap();
+        cache.compact().await.unwrap();
+
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, 5);
+
+        drop(cache);
+        let rebuilt = CacheManager::with_persistence(&config, &TaskTracker::new())
+            .await
+            .unwrap();
+        for i in 15..20 {
+            assert!(rebuilt.get(&format!("key:{}", i)).await.unwrap().found);
+        }
+        assert!(!rebuilt.get("key:0").await.unwrap().found);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
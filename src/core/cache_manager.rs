@@ -2,14 +2,34 @@
 //!
 //! This module provides a cache manager that implements distributed caching
 //! with TTL support and tagging capabilities.
+//!
+//! Entries are kept by a [`CacheBackend`]: a [`MemoryCache`] local to the
+//! process, or a [`RedisCache`] shared by every instance pointed at the same
+//! server.
 
-use crate::Result;
+use crate::config::{CacheConfig, CachePersistenceConfig};
+use crate::core::cache_backend::{
+    check_key, check_ttl, expiry_after, key_not_found, CacheBackend, CacheBackendChain,
+    CacheSource, ChainLookup,
+};
+use crate::core::cache_fills::{FillLeaseAttempt, FillLeases, MAX_FILL_LEASE, MAX_FILL_WAIT};
+use crate::core::cache_memory::{MemoryCache, MemoryLookup};
+use crate::core::cache_redis::RedisCache;
+use crate::core::memory::serialized_size;
+use crate::core::task_tracker::TaskTracker;
+#[cfg(feature = "metrics")]
+use crate::metrics::{Metrics, MetricsHandle};
+use crate::storage::redis::RedisManager;
+use crate::{Result, SyrosError};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+#[cfg(feature = "metrics")]
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+
+/// How long negative entries are kept unless `negative_ttl_seconds` is set.
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheEntry {
@@ -18,6 +38,59 @@ pub struct CacheEntry {
     pub expires_at: Option<DateTime<Utc>>,
     pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
+    /// Authenticated principal that wrote the entry
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// Marks a key the backend chain found absent; the value is null
+    #[serde(default)]
+    pub negative: bool,
+    /// Writes of the key since it was last created, starting at 1
+    #[serde(default = "first_version")]
+    pub version: u64,
+}
+
+fn first_version() -> u64 {
+    1
+}
+
+impl CacheEntry {
+    pub(crate) fn is_live(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Whether `key` matches `pattern`, in which `*` stands for any run of
+/// characters, e.g. `user:*` or `*:session:*`.
+pub fn key_matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// How `set` treats an existing entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheSetMode {
+    /// Insert or overwrite the entry
+    #[default]
+    Upsert,
+    /// Fail with a conflict if a live entry already exists
+    CreateOnly,
+    /// Fail with not found unless a live entry already exists
+    UpdateOnly,
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +99,24 @@ pub struct CacheRequest {
     pub value: serde_json::Value,
     pub ttl: Option<Duration>,
     pub tags: Vec<String>,
+    pub mode: CacheSetMode,
+    /// Authenticated principal writing the entry
+    pub created_by: Option<String>,
+    /// Records the key as known to be absent instead of storing `value`
+    pub cache_negative: bool,
+}
+
+/// What a read found, or a write left, under a key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheState {
+    /// A live value
+    Hit,
+    /// Nothing
+    #[default]
+    Miss,
+    /// A sentinel recording the key as known to be absent
+    NegativeHit,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +125,50 @@ pub struct CacheResponse {
     pub value: Option<serde_json::Value>,
     pub found: bool,
     pub message: String,
+    /// Principal that wrote the entry, when found
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// Layer that served a read; absent for writes and plain misses
+    #[serde(default)]
+    pub source: Option<CacheSource>,
+    /// Version of the entry written or read; absent for misses
+    #[serde(default)]
+    pub version: Option<u64>,
+    /// When the entry written or read expires; absent if it never does
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether the key holds a value, nothing, or a negative sentinel
+    #[serde(default)]
+    pub state: CacheState,
+}
+
+#[derive(Debug, Clone)]
+pub struct GetOrSetRequest {
+    pub key: String,
+    /// How long the caller told to compute the value may take to store it
+    pub lease: Duration,
+    /// How long to wait for a value another caller is computing
+    pub wait_timeout: Duration,
+}
+
+/// Outcome of [`CacheManager::get_or_set`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CacheFetch {
+    /// The value is cached
+    Hit(CacheResponse),
+    /// The value is missing and the caller should compute and store it
+    /// before `lease_expires_at`
+    Leader {
+        key: String,
+        lease_expires_at: DateTime<Utc>,
+    },
+    /// Another caller is computing the value and did not store it within
+    /// the wait timeout; its lease runs until `lease_expires_at`
+    Pending {
+        key: String,
+        lease_expires_at: DateTime<Utc>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -59,102 +194,648 @@ pub struct InvalidateByTagResponse {
     pub message: String,
 }
 
+/// Where a [`CacheManager`] keeps its entries.
+#[derive(Clone)]
+enum CacheStore {
+    Memory(MemoryCache),
+    Redis(RedisCache),
+}
+
+impl CacheStore {
+    fn backend(&self) -> &dyn CacheBackend {
+        match self {
+            CacheStore::Memory(memory) => memory,
+            CacheStore::Redis(redis) => redis,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CacheManager {
-    cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    store: CacheStore,
+    max_value_bytes: Option<usize>,
+    negative_ttl: Duration,
+    chain: CacheBackendChain,
+    fills: FillLeases,
+    tasks: TaskTracker,
+    #[cfg(feature = "metrics")]
+    metrics: MetricsHandle,
 }
 
 impl CacheManager {
     pub fn new() -> Self {
+        Self::with_store(CacheStore::Memory(MemoryCache::new()))
+    }
+
+    fn with_store(store: CacheStore) -> Self {
         Self {
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            store,
+            max_value_bytes: None,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+            chain: CacheBackendChain::new(),
+            fills: FillLeases::new(),
+            tasks: TaskTracker::new(),
+            #[cfg(feature = "metrics")]
+            metrics: MetricsHandle::default(),
+        }
+    }
+
+    /// Creates a cache whose entries are kept in Redis, shared with every
+    /// instance pointed at the same server.
+    pub fn with_redis(redis: RedisManager) -> Self {
+        Self::with_store(CacheStore::Redis(RedisCache::new(redis)))
+    }
+
+    /// Caps the entries kept and the size of each value as `config` sets.
+    ///
+    /// Beyond `max_entries`, the least recently written or read entries are
+    /// evicted from memory; Redis applies its own eviction policy instead.
+    /// Values over `max_value_bytes` of JSON are rejected, and negative
+    /// entries expire after `negative_ttl_seconds` at most.
+    pub fn with_limits(mut self, config: &CacheConfig) -> Self {
+        if let CacheStore::Memory(memory) = self.store {
+            self.store = CacheStore::Memory(memory.with_max_entries(config.max_entries));
+        }
+        self.max_value_bytes = config.max_value_bytes;
+        if let Some(seconds) = config.negative_ttl_seconds {
+            self.negative_ttl = Duration::from_secs(seconds);
+        }
+        self
+    }
+
+    /// Falls through `chain` on reads of keys that are not in the cache.
+    pub fn with_backend_chain(mut self, chain: CacheBackendChain) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Spawns the sweeper through `tasks`.
+    pub fn with_task_tracker(mut self, tasks: TaskTracker) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Reports hits and misses to `cache_hits_total` and
+    /// `cache_misses_total`, reads by source to `cache_hits_by_source_total`,
+    /// operation durations to `cache_operation_duration_seconds`, evictions
+    /// to `cache_evictions_total` and, after each sweep, the live entries to
+    /// `cache_size` and `cache_value_bytes`.
+    ///
+    /// Applies to every clone of this cache, including those taken before.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        if let CacheStore::Memory(memory) = self.store {
+            self.store = CacheStore::Memory(memory.with_metrics(metrics.clone()));
+        }
+        self.metrics.set(metrics);
+        self
+    }
+
+    /// Creates a cache backed by a write-behind journal, whose writer task is
+    /// spawned through `tasks`.
+    ///
+    /// Entries recorded in the journal are replayed before the manager is
+    /// returned, so keys and their original expiry survive a restart.
+    pub async fn with_persistence(
+        config: &CachePersistenceConfig,
+        tasks: &TaskTracker,
+    ) -> Result<Self> {
+        let memory = MemoryCache::with_persistence(config, tasks).await?;
+        Ok(Self::with_store(CacheStore::Memory(memory)))
+    }
+
+    /// Name of the storage the entries are kept in.
+    pub fn backend_name(&self) -> &'static str {
+        match &self.store {
+            CacheStore::Memory(memory) if memory.is_journaled() => "memory+journal",
+            CacheStore::Memory(_) => "memory",
+            CacheStore::Redis(_) => "redis",
+        }
+    }
+
+    /// Waits until every mutation so far has been written to the journal.
+    pub async fn flush(&self) -> Result<()> {
+        match &self.store {
+            CacheStore::Memory(memory) => memory.flush().await,
+            CacheStore::Redis(_) => Ok(()),
+        }
+    }
+
+    /// Rewrites the journal as a snapshot of the live entries.
+    pub async fn compact(&self) -> Result<()> {
+        match &self.store {
+            CacheStore::Memory(memory) => memory.compact().await,
+            CacheStore::Redis(_) => Ok(()),
         }
     }
 
+    /// Fails with `ValueTooLarge` if `value` is over `max_value_bytes`.
+    fn check_value_size(&self, key: &str, value: &serde_json::Value) -> Result<()> {
+        let Some(max_value_bytes) = self.max_value_bytes else {
+            return Ok(());
+        };
+        let size = serialized_size(value);
+        if size > max_value_bytes {
+            return Err(SyrosError::ValueTooLarge(format!(
+                "Value of cache key {} is {} bytes, over the limit of {} bytes",
+                key, size, max_value_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Writes an entry; fails with `ValueTooLarge` if the value is over the
+    /// configured limit, and with `ApiError` for
+    /// [`RESERVED_CACHE_KEY`](crate::core::cache_backend::RESERVED_CACHE_KEY).
+    ///
+    /// Storing a key releases its fill lease, if any, and wakes the
+    /// [`get_or_set`](Self::get_or_set) callers waiting for it.
     pub async fn set(&self, request: CacheRequest) -> Result<CacheResponse> {
+        let _timer = self.time_operation("set");
+        let mode = request.mode;
+        check_key(&request.key)?;
+        check_ttl(request.ttl)?;
+        let entry = new_entry(request, Utc::now(), self.negative_ttl);
+        self.check_value_size(&entry.key, &entry.value)?;
+        let entry = self.store.backend().set(entry, mode).await?;
+        self.fills.release(&entry.key);
+        Ok(stored(entry))
+    }
+
+    /// Writes several entries with a single backend call; returns the
+    /// outcome of each write, in order.
+    ///
+    /// Each entry is checked and stored on its own, so one failing does not
+    /// keep the others from being stored.
+    pub async fn set_many(
+        &self,
+        requests: Vec<CacheRequest>,
+    ) -> Result<Vec<Result<CacheResponse>>> {
+        let _timer = self.time_operation("set_many");
         let now = Utc::now();
-        let expires_at = request
-            .ttl
-            .map(|ttl| now + chrono::Duration::from_std(ttl).unwrap());
+        let mut outcomes = Vec::with_capacity(requests.len());
+        let mut batch = Vec::with_capacity(requests.len());
+        for request in requests {
+            let mode = request.mode;
+            if let Err(e) = check_key(&request.key).and_then(|()| check_ttl(request.ttl)) {
+                outcomes.push(Some(Err(e)));
+                continue;
+            }
+            let entry = new_entry(request, now, self.negative_ttl);
+            match self.check_value_size(&entry.key, &entry.value) {
+                Ok(()) => {
+                    batch.push((entry, mode));
+                    outcomes.push(None);
+                }
+                Err(e) => outcomes.push(Some(Err(e))),
+            }
+        }
 
+        let mut written = self.store.backend().set_many(batch).await?.into_iter();
+        Ok(outcomes
+            .into_iter()
+            .map(|outcome| match outcome {
+                Some(rejected) => rejected,
+                None => {
+                    let entry = written.next().expect("one outcome per stored entry")?;
+                    self.fills.release(&entry.key);
+                    Ok(stored(entry))
+                }
+            })
+            .collect())
+    }
+
+    /// Replaces the value under `key` only if the live entry is at
+    /// `expected_version`, keeping its tags; `ttl` sets the new expiry.
+    ///
+    /// Fails with `Conflict` if the entry is missing or was written since
+    /// that version was read, and with `ValueTooLarge` if the value is over
+    /// the configured limit.
+    pub async fn compare_and_swap(
+        &self,
+        key: &str,
+        expected_version: u64,
+        new_value: serde_json::Value,
+        ttl: Option<Duration>,
+    ) -> Result<CacheResponse> {
+        let _timer = self.time_operation("compare_and_swap");
+        check_ttl(ttl)?;
+        self.check_value_size(key, &new_value)?;
+        let now = Utc::now();
         let entry = CacheEntry {
-            key: request.key.clone(),
-            value: request.value.clone(),
-            expires_at,
-            tags: request.tags,
+            key: key.to_string(),
+            value: new_value,
+            expires_at: ttl.map(|ttl| expiry_after(now, ttl)),
+            tags: vec![],
             created_at: now,
+            created_by: None,
+            negative: false,
+            version: 1,
         };
-
-        let mut cache = self.cache.write().await;
-        cache.insert(request.key.clone(), entry);
+        let entry = self
+            .store
+            .backend()
+            .compare_and_swap(entry, expected_version)
+            .await?;
+        self.fills.release(key);
 
         Ok(CacheResponse {
-            key: request.key,
-            value: Some(request.value),
+            key: entry.key,
+            value: Some(entry.value),
             found: true,
-            message: "Cache set successfully".to_string(),
+            message: "Cache swapped successfully".to_string(),
+            created_by: None,
+            source: None,
+            version: Some(entry.version),
+            expires_at: entry.expires_at,
+            state: CacheState::Hit,
         })
     }
 
+    /// Atomically adds `delta` to the integer under `key` and returns the
+    /// sum; a missing key starts from zero and expires after `ttl_if_new`.
+    ///
+    /// An existing entry keeps its expiry and tags. Fails with `Conflict` if
+    /// it does not hold an integer or the sum overflows.
+    pub async fn increment(
+        &self,
+        key: &str,
+        delta: i64,
+        ttl_if_new: Option<Duration>,
+    ) -> Result<i64> {
+        let _timer = self.time_operation("increment");
+        check_key(key)?;
+        check_ttl(ttl_if_new)?;
+        let value = self
+            .store
+            .backend()
+            .increment(key, delta, ttl_if_new)
+            .await?;
+        self.fills.release(key);
+        Ok(value)
+    }
+
+    /// Reads `key` from the cache, falling through the backend chain when
+    /// it is missing or expired.
+    ///
+    /// The response's `source` tells which layer served it. When every
+    /// layer fails, an expired entry still within the chain's stale TTL is
+    /// served instead; without one, the layer's error is returned. Redis
+    /// drops entries as they expire, so it never has one to serve.
     pub async fn get(&self, key: &str) -> Result<CacheResponse> {
-        let mut cache = self.cache.write().await;
+        let _timer = self.time_operation("get");
+        let response = self.read(key).await?;
+        self.record_lookup(&response);
+        Ok(response)
+    }
+
+    async fn read(&self, key: &str) -> Result<CacheResponse> {
         let now = Utc::now();
+        let mut stale = None;
+        let mut expired = false;
+
+        let live = match &self.store {
+            CacheStore::Memory(memory) => {
+                let lookup = memory
+                    .lookup(key, now, |entry| self.within_stale_ttl(entry, now))
+                    .await;
+                match lookup {
+                    MemoryLookup::Live(entry) => Some((entry, CacheSource::Memory)),
+                    MemoryLookup::Expired(entry) => {
+                        expired = true;
+                        stale = entry;
+                        None
+                    }
+                    MemoryLookup::Missing => None,
+                }
+            }
+            CacheStore::Redis(redis) => redis
+                .get(key)
+                .await?
+                .map(|entry| (entry, CacheSource::Redis)),
+        };
+        if let Some((entry, source)) = live {
+            let source = if entry.negative {
+                CacheSource::Negative
+            } else {
+                source
+            };
+            self.record_source(source);
+            return Ok(served(&entry, source));
+        }
+
+        if self.chain.is_empty() {
+            return Ok(not_found(key, expired));
+        }
+
+        match self.chain.lookup(key).await {
+            ChainLookup::Found { value, source } => {
+                let entry = self.fill(key, value, self.chain.fill_ttl(), false).await;
+                self.record_source(source);
+                Ok(served(&entry, source))
+            }
+            ChainLookup::Absent => {
+                if let Some(ttl) = self.chain.negative_ttl() {
+                    self.fill(key, serde_json::Value::Null, Some(ttl), true)
+                        .await;
+                }
+                Ok(not_found(key, expired))
+            }
+            ChainLookup::Failed(e) => match stale {
+                Some(entry) => {
+                    self.record_source(CacheSource::Stale);
+                    Ok(served(&entry, CacheSource::Stale))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Reads several keys with a single backend call; returns a response
+    /// per key, in order.
+    ///
+    /// Keys the cache does not hold live fall through the backend chain one
+    /// at a time, as [`get`](Self::get) does.
+    pub async fn get_many(&self, keys: &[String]) -> Result<Vec<CacheResponse>> {
+        let _timer = self.time_operation("get_many");
+        let found = self.store.backend().get_many(keys).await?;
+        let source = match &self.store {
+            CacheStore::Memory(_) => CacheSource::Memory,
+            CacheStore::Redis(_) => CacheSource::Redis,
+        };
+
+        let mut responses = Vec::with_capacity(keys.len());
+        for (key, entry) in keys.iter().zip(found) {
+            let response = match entry {
+                Some(entry) => {
+                    let source = if entry.negative {
+                        CacheSource::Negative
+                    } else {
+                        source
+                    };
+                    self.record_source(source);
+                    served(&entry, source)
+                }
+                None if self.chain.is_empty() => not_found(key, false),
+                None => self.read(key).await?,
+            };
+            self.record_lookup(&response);
+            responses.push(response);
+        }
+        Ok(responses)
+    }
 
-        if let Some(entry) = cache.get(key) {
-            if let Some(expires_at) = entry.expires_at {
-                if expires_at <= now {
-                    cache.remove(key);
-                    return Ok(CacheResponse {
-                        key: key.to_string(),
-                        value: None,
-                        found: false,
-                        message: "Cache expired".to_string(),
+    /// Reads `key`, electing a single caller to compute it when it is
+    /// missing.
+    ///
+    /// The first caller to miss takes the key's fill lease and is told to
+    /// compute and store the value. Callers missing while the lease is held
+    /// wait up to `wait_timeout` for it to be stored, and are told to retry
+    /// later if it is not; a lease that expires passes to the next caller.
+    /// A key recorded as absent is a hit, so it is not computed again until
+    /// its negative entry expires.
+    ///
+    /// Fails with [`SyrosError::ApiError`] for a lease longer than
+    /// [`MAX_FILL_LEASE`] or a wait longer than [`MAX_FILL_WAIT`].
+    pub async fn get_or_set(&self, request: GetOrSetRequest) -> Result<CacheFetch> {
+        validate_fill_bounds(request.lease, request.wait_timeout)?;
+        let deadline = tokio::time::Instant::now() + request.wait_timeout;
+        let mut waited = false;
+
+        loop {
+            let response = self.get(&request.key).await?;
+            if response.state != CacheState::Miss {
+                if waited {
+                    self.record_stampede_prevented();
+                }
+                return Ok(CacheFetch::Hit(response));
+            }
+
+            let (expires_at, filled) = match self.fills.try_acquire(&request.key, request.lease) {
+                FillLeaseAttempt::Acquired { expires_at } => {
+                    return Ok(CacheFetch::Leader {
+                        key: request.key,
+                        lease_expires_at: lease_expiry(expires_at),
                     });
                 }
+                FillLeaseAttempt::Held { expires_at, filled } => (expires_at, filled),
+            };
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(CacheFetch::Pending {
+                    key: request.key,
+                    lease_expires_at: lease_expiry(expires_at),
+                });
             }
 
-            Ok(CacheResponse {
-                key: key.to_string(),
-                value: Some(entry.value.clone()),
-                found: true,
-                message: "Cache retrieved successfully".to_string(),
-            })
-        } else {
-            Ok(CacheResponse {
-                key: key.to_string(),
-                value: None,
-                found: false,
-                message: "Cache key not found".to_string(),
-            })
+            // Register before reading again, so a store in between wakes us.
+            let notified = filled.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let response = self.get(&request.key).await?;
+            if response.state != CacheState::Miss {
+                self.record_stampede_prevented();
+                return Ok(CacheFetch::Hit(response));
+            }
+            let _ = tokio::time::timeout_at(deadline.min(expires_at), notified).await;
+            waited = true;
         }
     }
 
-    pub async fn delete(&self, request: DeleteCacheRequest) -> Result<DeleteCacheResponse> {
-        let mut cache = self.cache.write().await;
+    fn record_stampede_prevented(&self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            metrics.increment_cache_stampedes_prevented();
+        }
+    }
 
-        if cache.remove(&request.key).is_some() {
-            Ok(DeleteCacheResponse {
-                success: true,
-                message: "Cache deleted successfully".to_string(),
-            })
-        } else {
-            Ok(DeleteCacheResponse {
-                success: false,
-                message: "Cache key not found".to_string(),
-            })
+    /// Stores a value found through the backend chain, unless the key was
+    /// written meanwhile, and returns the entry now cached. A value above the
+    /// size limit, or one Redis fails to store, is returned without being
+    /// cached.
+    async fn fill(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        ttl: Option<Duration>,
+        negative: bool,
+    ) -> CacheEntry {
+        let now = Utc::now();
+        let oversized = self.check_value_size(key, &value).is_err();
+        let entry = CacheEntry {
+            key: key.to_string(),
+            value,
+            expires_at: ttl.map(|ttl| expiry_after(now, ttl)),
+            tags: vec![],
+            created_at: now,
+            created_by: None,
+            negative,
+            version: 1,
+        };
+        if oversized {
+            return entry;
+        }
+
+        match &self.store {
+            CacheStore::Memory(memory) => memory.fill(entry).await,
+            CacheStore::Redis(redis) => {
+                match redis.set(entry.clone(), CacheSetMode::CreateOnly).await {
+                    Ok(stored) => stored,
+                    Err(SyrosError::Conflict(_)) => match redis.get(key).await {
+                        Ok(Some(current)) => current,
+                        _ => entry,
+                    },
+                    Err(e) => {
+                        tracing::warn!("Failed to fill cache key {} in Redis: {}", key, e);
+                        entry
+                    }
+                }
+            }
+        }
+    }
+
+    fn within_stale_ttl(&self, entry: &CacheEntry, now: DateTime<Utc>) -> bool {
+        let stale_ttl = self.chain.stale_ttl();
+        match entry.expires_at {
+            Some(expires_at) if !stale_ttl.is_zero() => expiry_after(expires_at, stale_ttl) > now,
+            _ => false,
+        }
+    }
+
+    fn time_operation(&self, operation: &'static str) -> OperationTimer {
+        OperationTimer {
+            #[cfg(feature = "metrics")]
+            metrics: self.metrics.get(),
+            operation,
+            started: std::time::Instant::now(),
+        }
+    }
+
+    /// Counts a read in `cache_hits_total` or `cache_misses_total`.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn record_lookup(&self, response: &CacheResponse) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            if response.found {
+                metrics.increment_cache_hits();
+            } else {
+                metrics.increment_cache_misses();
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn record_source(&self, source: CacheSource) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            metrics.increment_cache_source_hits(source.as_str());
+        }
+    }
+
+    /// Time left before `key` expires, or `None` if it never does.
+    ///
+    /// Fails with `NotFound` if the key is missing or expired.
+    pub async fn ttl(&self, key: &str) -> Result<Option<Duration>> {
+        let entry = self
+            .get_entry(key)
+            .await?
+            .ok_or_else(|| key_not_found(key))?;
+        Ok(entry
+            .expires_at
+            .map(|expires_at| (expires_at - Utc::now()).to_std().unwrap_or_default()))
+    }
+
+    /// Makes `key` expire `ttl` from now, or never if `None`, without
+    /// changing its value or version.
+    ///
+    /// Fails with `NotFound` if the key is missing or expired.
+    pub async fn touch(&self, key: &str, ttl: Option<Duration>) -> Result<CacheResponse> {
+        let _timer = self.time_operation("touch");
+        check_ttl(ttl)?;
+        let expires_at = ttl.map(|ttl| expiry_after(Utc::now(), ttl));
+        let entry = self.store.backend().touch(key, expires_at).await?;
+        Ok(CacheResponse {
+            message: "Cache touched successfully".to_string(),
+            ..stored(entry)
+        })
+    }
+
+    /// Adds `tags` to the live entry under `key`, keeping its value, expiry
+    /// and version; returns the entry with its tags.
+    ///
+    /// Fails with `NotFound` if the key is missing or expired.
+    pub async fn add_tags(&self, key: &str, tags: &[String]) -> Result<CacheEntry> {
+        let _timer = self.time_operation("add_tags");
+        self.store.backend().retag(key, tags, &[]).await
+    }
+
+    /// Removes `tags` from the live entry under `key`, keeping its value,
+    /// expiry and version; returns the entry with its remaining tags.
+    ///
+    /// Fails with `NotFound` if the key is missing or expired.
+    pub async fn remove_tags(&self, key: &str, tags: &[String]) -> Result<CacheEntry> {
+        let _timer = self.time_operation("remove_tags");
+        self.store.backend().retag(key, &[], tags).await
+    }
+
+    /// Keys of the live entries carrying `tag`, ordered.
+    pub async fn keys_by_tag(&self, tag: &str) -> Result<Vec<String>> {
+        let entries = self.list(None, &[tag.to_string()], None).await?;
+        Ok(entries.into_iter().map(|entry| entry.key).collect())
+    }
+
+    /// Returns the full entry for `key` if it exists and has not expired.
+    pub async fn get_entry(&self, key: &str) -> Result<Option<CacheEntry>> {
+        match &self.store {
+            CacheStore::Memory(memory) => Ok(memory.get_entry(key).await),
+            CacheStore::Redis(redis) => Ok(redis.get(key).await?.filter(|entry| !entry.negative)),
         }
     }
 
+    /// Live entries whose key starts with `prefix` and that carry `tag`,
+    /// ordered by key.
+    pub async fn list_entries(
+        &self,
+        prefix: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<Vec<CacheEntry>> {
+        let pattern = prefix.map(|prefix| format!("{}*", prefix));
+        let tags: Vec<String> = tag.into_iter().map(str::to_string).collect();
+        let mut entries = self.list(pattern.as_deref(), &tags, None).await?;
+        // `*` in the prefix itself is matched literally.
+        entries.retain(|entry| prefix.is_none_or(|prefix| entry.key.starts_with(prefix)));
+        Ok(entries)
+    }
+
+    /// Up to `limit` live entries whose key matches the glob `pattern` and
+    /// that carry all of `tags`, ordered by key.
+    pub async fn list(
+        &self,
+        pattern: Option<&str>,
+        tags: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<CacheEntry>> {
+        self.store.backend().list(pattern, tags, limit).await
+    }
+
+    pub async fn delete(&self, request: DeleteCacheRequest) -> Result<DeleteCacheResponse> {
+        let _timer = self.time_operation("delete");
+        let deleted = self.store.backend().delete(&request.key).await?;
+        Ok(deleted_response(deleted))
+    }
+
+    /// Removes several keys with a single backend call; returns a response
+    /// per key, in order.
+    pub async fn delete_many(&self, keys: &[String]) -> Result<Vec<DeleteCacheResponse>> {
+        let _timer = self.time_operation("delete_many");
+        let deleted = self.store.backend().delete_many(keys).await?;
+        Ok(deleted.into_iter().map(deleted_response).collect())
+    }
+
     pub async fn invalidate_by_tag(
         &self,
         request: InvalidateByTagRequest,
     ) -> Result<InvalidateByTagResponse> {
-        let mut cache = self.cache.write().await;
-        let initial_count = cache.len();
-
-        cache.retain(|_, entry| !entry.tags.contains(&request.tag));
-
-        let invalidated_count = (initial_count - cache.len()) as u64;
+        let invalidated_count = self.store.backend().invalidate_by_tag(&request.tag).await?;
 
         Ok(InvalidateByTagResponse {
             invalidated_count,
@@ -163,49 +844,1358 @@ impl CacheManager {
         })
     }
 
+    /// Removes expired entries, keeping those the backend chain may still
+    /// serve stale, then reports the entries left to `cache_size` and their
+    /// values' bytes to `cache_value_bytes`. Both are tracked as entries are
+    /// written and removed, so reporting them takes no scan.
+    ///
+    /// Redis expires entries itself, so there only the tag sets are pruned
+    /// of them, and the gauges are left to the `metrics_sync` task.
     pub async fn cleanup_expired(&self) -> Result<u64> {
-        let mut cache = self.cache.write().await;
-        let now = Utc::now();
-        let initial_count = cache.len();
+        let removed = match &self.store {
+            CacheStore::Memory(memory) => {
+                let now = Utc::now();
+                memory
+                    .cleanup_expired(|entry| self.within_stale_ttl(entry, now))
+                    .await
+            }
+            CacheStore::Redis(redis) => {
+                redis.prune_tags().await?;
+                0
+            }
+        };
+        #[cfg(feature = "metrics")]
+        if let (Some(metrics), CacheStore::Memory(memory)) = (self.metrics.get(), &self.store) {
+            let (entries, value_bytes) = memory.stored_totals();
+            metrics.set_cache_size(entries as f64);
+            metrics.set_cache_value_bytes(value_bytes);
+        }
+        Ok(removed)
+    }
 
-        cache.retain(|_, entry| {
-            if let Some(expires_at) = entry.expires_at {
-                expires_at > now
-            } else {
-                true // No expiration
+    /// Spawns a sweeper that runs [`cleanup_expired`](Self::cleanup_expired)
+    /// every `interval`, until the returned handle is aborted, so expired
+    /// entries are dropped without waiting for a read.
+    ///
+    /// The server schedules the same sweep as the `cache_sweep` background
+    /// task instead; this is for embedding the manager without one.
+    pub fn start_sweeper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let cache = self.clone();
+        self.tasks.spawn_background("cache_sweeper", async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = cache.cleanup_expired().await {
+                    tracing::error!("Cache sweeper failed: {}", e);
+                }
             }
-        });
+        })
+    }
 
-        Ok((initial_count - cache.len()) as u64)
+    /// Estimated bytes held by the entries in memory, stale ones included;
+    /// zero when they are kept in Redis.
+    pub async fn estimated_bytes(&self) -> u64 {
+        match &self.store {
+            CacheStore::Memory(memory) => memory.estimated_bytes().await,
+            CacheStore::Redis(_) => 0,
+        }
     }
 
     pub async fn get_stats(&self) -> Result<CacheStats> {
-        let cache = self.cache.read().await;
-        let now = Utc::now();
+        self.store.backend().stats().await
+    }
+}
 
-        let total_entries = cache.len();
-        let expired_entries = cache
-            .values()
-            .filter(|entry| {
-                if let Some(expires_at) = entry.expires_at {
-                    expires_at <= now
-                } else {
-                    false
-                }
-            })
-            .count();
+/// Records the time until it is dropped as the duration of a cache
+/// operation, in `cache_operation_duration_seconds`.
+#[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+struct OperationTimer {
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
+    operation: &'static str,
+    started: std::time::Instant,
+}
 
-        Ok(CacheStats {
-            total_entries,
-            expired_entries,
-            active_entries: total_entries - expired_entries,
-        })
+impl Drop for OperationTimer {
+    fn drop(&mut self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cache_operation(self.operation, self.started.elapsed().as_secs_f64());
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+fn served(entry: &CacheEntry, source: CacheSource) -> CacheResponse {
+    let message = match source {
+        CacheSource::Negative => "Cache key cached as absent",
+        CacheSource::Stale => "Stale cache entry served after backend failure",
+        _ => "Cache retrieved successfully",
+    };
+    CacheResponse {
+        key: entry.key.clone(),
+        value: (!entry.negative).then(|| entry.value.clone()),
+        found: !entry.negative,
+        message: message.to_string(),
+        created_by: entry.created_by.clone(),
+        source: Some(source),
+        version: (!entry.negative).then_some(entry.version),
+        expires_at: entry.expires_at.filter(|_| !entry.negative),
+        state: if entry.negative {
+            CacheState::NegativeHit
+        } else {
+            CacheState::Hit
+        },
+    }
+}
+
+/// The entry `request` writes at `now`.
+///
+/// A negative entry holds null and expires after `negative_ttl`, or
+/// sooner if the request asks for a shorter TTL.
+fn new_entry(request: CacheRequest, now: DateTime<Utc>, negative_ttl: Duration) -> CacheEntry {
+    let (value, ttl) = if request.cache_negative {
+        let ttl = request
+            .ttl
+            .map_or(negative_ttl, |ttl| ttl.min(negative_ttl));
+        (serde_json::Value::Null, Some(ttl))
+    } else {
+        (request.value, request.ttl)
+    };
+    CacheEntry {
+        key: request.key,
+        value,
+        expires_at: ttl.map(|ttl| expiry_after(now, ttl)),
+        tags: request.tags,
+        created_at: now,
+        created_by: request.created_by,
+        negative: request.cache_negative,
+        version: 1,
+    }
+}
+
+fn stored(entry: CacheEntry) -> CacheResponse {
+    let (message, state) = if entry.negative {
+        ("Cache key recorded as absent", CacheState::NegativeHit)
+    } else {
+        ("Cache set successfully", CacheState::Hit)
+    };
+    CacheResponse {
+        key: entry.key,
+        value: (!entry.negative).then_some(entry.value),
+        found: !entry.negative,
+        message: message.to_string(),
+        created_by: entry.created_by,
+        source: None,
+        version: Some(entry.version),
+        expires_at: entry.expires_at,
+        state,
+    }
+}
+
+fn deleted_response(deleted: bool) -> DeleteCacheResponse {
+    if deleted {
+        DeleteCacheResponse {
+            success: true,
+            message: "Cache deleted successfully".to_string(),
+        }
+    } else {
+        DeleteCacheResponse {
+            success: false,
+            message: "Cache key not found".to_string(),
+        }
+    }
+}
+
+/// Checks a fetch's lease and wait against [`MAX_FILL_LEASE`] and
+/// [`MAX_FILL_WAIT`].
+pub fn validate_fill_bounds(lease: Duration, wait_timeout: Duration) -> Result<()> {
+    if lease > MAX_FILL_LEASE {
+        return Err(SyrosError::ApiError(format!(
+            "lease_seconds must be at most {}",
+            MAX_FILL_LEASE.as_secs()
+        )));
+    }
+    if wait_timeout > MAX_FILL_WAIT {
+        return Err(SyrosError::ApiError(format!(
+            "wait_timeout_ms must be at most {}",
+            MAX_FILL_WAIT.as_millis()
+        )));
+    }
+    Ok(())
+}
+
+/// Wall-clock time of a lease expiry.
+fn lease_expiry(expires_at: tokio::time::Instant) -> DateTime<Utc> {
+    let remaining = expires_at.saturating_duration_since(tokio::time::Instant::now());
+    Utc::now() + chrono::Duration::from_std(remaining).unwrap_or_default()
+}
+
+fn not_found(key: &str, expired: bool) -> CacheResponse {
+    let message = if expired {
+        "Cache expired"
+    } else {
+        "Cache key not found"
+    };
+    CacheResponse {
+        key: key.to_string(),
+        value: None,
+        found: false,
+        message: message.to_string(),
+        created_by: None,
+        source: None,
+        version: None,
+        expires_at: None,
+        state: CacheState::Miss,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct CacheStats {
     pub total_entries: usize,
     pub expired_entries: usize,
     pub active_entries: usize,
+    /// Live entries carrying each tag
+    pub entries_by_tag: BTreeMap<String, usize>,
+    /// Approximate bytes of the live values, as serialized JSON
+    pub value_bytes: u64,
+}
+
+impl CacheStats {
+    /// Counts the tags and value bytes of a live entry; absences have none.
+    pub(crate) fn add_live(&mut self, entry: &CacheEntry) {
+        if entry.negative {
+            return;
+        }
+        for tag in &entry.tags {
+            *self.entries_by_tag.entry(tag.clone()).or_default() += 1;
+        }
+        self.value_bytes += serialized_size(&entry.value) as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache_backend::{CacheLayer, MAX_CACHE_TTL, RESERVED_CACHE_KEY};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    /// Redis used by tests of the Redis backend, which are skipped when unset.
+    const TEST_REDIS_URL_ENV: &str = "SYROS_TEST_REDIS_URL";
+
+    fn request(key: &str, value: i64, mode: CacheSetMode) -> CacheRequest {
+        CacheRequest {
+            key: key.to_string(),
+            value: serde_json::json!(value),
+            ttl: None,
+            tags: vec![],
+            mode,
+            created_by: None,
+            cache_negative: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_modes() {
+        let cache = CacheManager::new();
+
+        let missing = cache
+            .set(request("config", 1, CacheSetMode::UpdateOnly))
+            .await;
+        assert!(matches!(missing, Err(SyrosError::NotFound(_))));
+
+        cache
+            .set(request("config", 1, CacheSetMode::CreateOnly))
+            .await
+            .unwrap();
+
+        let duplicate = cache
+            .set(request("config", 2, CacheSetMode::CreateOnly))
+            .await;
+        assert!(matches!(duplicate, Err(SyrosError::Conflict(_))));
+        assert_eq!(
+            cache.get("config").await.unwrap().value,
+            Some(serde_json::json!(1))
+        );
+
+        cache
+            .set(request("config", 3, CacheSetMode::UpdateOnly))
+            .await
+            .unwrap();
+        cache
+            .set(request("config", 4, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.get("config").await.unwrap().value,
+            Some(serde_json::json!(4))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_versions_count_writes_and_listing_filters() {
+        let cache = CacheManager::new();
+        for (key, value) in [("user:2", 1), ("user:1", 1), ("user:1", 2), ("order:1", 1)] {
+            cache
+                .set(request(key, value, CacheSetMode::Upsert))
+                .await
+                .unwrap();
+        }
+        cache
+            .set(CacheRequest {
+                tags: vec!["vip".to_string()],
+                ..request("user:3", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+
+        let users = cache.list_entries(Some("user:"), None).await.unwrap();
+        let keys: Vec<_> = users.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["user:1", "user:2", "user:3"]);
+        assert_eq!(users[0].version, 2);
+        assert_eq!(users[1].version, 1);
+
+        let vip = cache.list_entries(None, Some("vip")).await.unwrap();
+        assert_eq!(vip.len(), 1);
+        assert_eq!(vip[0].key, "user:3");
+
+        cache
+            .delete(DeleteCacheRequest {
+                key: "user:1".to_string(),
+            })
+            .await
+            .unwrap();
+        cache
+            .set(request("user:1", 3, CacheSetMode::CreateOnly))
+            .await
+            .unwrap();
+        assert_eq!(cache.get_entry("user:1").await.unwrap().unwrap().version, 1);
+    }
+
+    #[test]
+    fn test_key_matches() {
+        assert!(key_matches("user:*", "user:1"));
+        assert!(key_matches("user:*", "user:"));
+        assert!(!key_matches("user:*", "order:1"));
+        assert!(key_matches("*:session:*", "user:session:42"));
+        assert!(!key_matches("*:session:*", "user:sessions"));
+        assert!(key_matches("user:*:name", "user:1:name"));
+        assert!(!key_matches("user:*:name", "user:1:names"));
+        assert!(key_matches("user:1", "user:1"));
+        assert!(!key_matches("user:1", "user:10"));
+        assert!(key_matches("*", "anything"));
+    }
+
+    #[tokio::test]
+    async fn test_list_filters_by_pattern_tags_and_limit() {
+        let cache = CacheManager::new();
+        for (key, tags) in [
+            ("user:1", vec!["session", "vip"]),
+            ("user:2", vec!["session"]),
+            ("user:3", vec![]),
+            ("order:1", vec!["session"]),
+        ] {
+            cache
+                .set(CacheRequest {
+                    tags: tags.into_iter().map(str::to_string).collect(),
+                    ..request(key, 1, CacheSetMode::Upsert)
+                })
+                .await
+                .unwrap();
+        }
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(10)),
+                tags: vec!["session".to_string()],
+                ..request("user:0", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let keys = |entries: Vec<CacheEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.key).collect()
+        };
+        let session = ["session".to_string()];
+        assert_eq!(
+            keys(cache.list(Some("user:*"), &session, None).await.unwrap()),
+            ["user:1", "user:2"]
+        );
+        assert_eq!(
+            keys(
+                cache
+                    .list(None, &["session".to_string(), "vip".to_string()], None)
+                    .await
+                    .unwrap()
+            ),
+            ["user:1"]
+        );
+        assert_eq!(
+            keys(cache.list(None, &[], Some(2)).await.unwrap()),
+            ["order:1", "user:1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_entries_are_evicted() {
+        let cache = CacheManager::new().with_limits(&CacheConfig {
+            max_entries: Some(3),
+            ..CacheConfig::default()
+        });
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(Metrics::new().unwrap());
+        #[cfg(feature = "metrics")]
+        let cache = cache.with_metrics(metrics.clone());
+        for key in ["a", "b", "c"] {
+            cache
+                .set(request(key, 1, CacheSetMode::Upsert))
+                .await
+                .unwrap();
+        }
+        // Reading `a` makes `b` the least recently used entry.
+        assert!(cache.get("a").await.unwrap().found);
+
+        cache
+            .set(request("d", 1, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        assert!(!cache.get("b").await.unwrap().found);
+        for key in ["a", "c", "d"] {
+            assert!(cache.get(key).await.unwrap().found, "{} was evicted", key);
+        }
+
+        // Rewriting `c` counts as a use too.
+        cache
+            .set(request("c", 2, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        cache
+            .set(request("e", 1, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        assert!(!cache.get("a").await.unwrap().found);
+        assert_eq!(cache.get_stats().await.unwrap().total_entries, 3);
+        #[cfg(feature = "metrics")]
+        {
+            assert_eq!(metrics.cache_evictions_total.get(), 2.0);
+            assert_eq!(metrics.cache_size.get(), 3.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_drops_expired_entries_without_reads() {
+        let cache = CacheManager::new();
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(Metrics::new().unwrap());
+        #[cfg(feature = "metrics")]
+        let cache = cache.with_metrics(metrics.clone());
+        for i in 0..600 {
+            cache
+                .set(CacheRequest {
+                    ttl: Some(Duration::from_millis(30)),
+                    ..request(&format!("short:{}", i), i, CacheSetMode::Upsert)
+                })
+                .await
+                .unwrap();
+        }
+        cache
+            .set(request("long", 1, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+
+        let sweeper = cache.start_sweeper(Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        sweeper.abort();
+
+        let stats = cache.get_stats().await.unwrap();
+        assert_eq!(stats.total_entries, 1);
+        assert_eq!(stats.expired_entries, 0);
+        #[cfg(feature = "metrics")]
+        assert_eq!(metrics.cache_size.get(), 1.0);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_sweep_reports_totals_kept_as_entries_change() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let cache = CacheManager::new().with_metrics(metrics.clone());
+        for key in ["a", "b", "c"] {
+            cache
+                .set(CacheRequest {
+                    tags: vec![key.to_string()],
+                    ..request(key, 100, CacheSetMode::Upsert)
+                })
+                .await
+                .unwrap();
+        }
+        cache
+            .set(request("a", 1_000_000, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        cache
+            .delete(DeleteCacheRequest {
+                key: "b".to_string(),
+            })
+            .await
+            .unwrap();
+        cache
+            .invalidate_by_tag(InvalidateByTagRequest {
+                tag: "c".to_string(),
+            })
+            .await
+            .unwrap();
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(10)),
+                ..request("gone", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(cache.cleanup_expired().await.unwrap(), 1);
+        assert_eq!(metrics.cache_size.get(), 1.0);
+        // Only `a` is left, holding `1000000`.
+        assert_eq!(metrics.cache_value_bytes.get(), 7.0);
+    }
+
+    #[tokio::test]
+    async fn test_values_over_the_size_limit_are_rejected() {
+        let cache = CacheManager::new().with_limits(&CacheConfig {
+            max_value_bytes: Some(16),
+            ..CacheConfig::default()
+        });
+        let oversized = CacheRequest {
+            value: serde_json::json!("x".repeat(32)),
+            ..request("big", 1, CacheSetMode::Upsert)
+        };
+        assert!(matches!(
+            cache.set(oversized).await,
+            Err(SyrosError::ValueTooLarge(_))
+        ));
+        assert!(!cache.get("big").await.unwrap().found);
+        cache
+            .set(request("small", 1, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_only_over_expired_entry() {
+        let cache = CacheManager::new();
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(10)),
+                ..request("session", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        cache
+            .set(request("session", 2, CacheSetMode::CreateOnly))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_create_only_has_single_winner() {
+        let cache = CacheManager::new();
+
+        let writers: Vec<_> = (0..32)
+            .map(|i| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    cache
+                        .set(request("leader", i, CacheSetMode::CreateOnly))
+                        .await
+                })
+            })
+            .collect();
+
+        let mut winners = 0;
+        let mut conflicts = 0;
+        for writer in writers {
+            match writer.await.unwrap() {
+                Ok(_) => winners += 1,
+                Err(SyrosError::Conflict(_)) => conflicts += 1,
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+
+        assert_eq!(winners, 1);
+        assert_eq!(conflicts, 31);
+    }
+
+    /// Origin holding `product:1`, failing while `down` is set.
+    #[derive(Default)]
+    struct StubOrigin {
+        down: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl CacheLayer for StubOrigin {
+        fn source(&self) -> CacheSource {
+            CacheSource::Origin
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<serde_json::Value>> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(SyrosError::StorageError("origin unavailable".to_string()));
+            }
+            Ok((key == "product:1").then(|| serde_json::json!({ "price": 10 })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_report_their_source() {
+        let origin = Arc::new(StubOrigin::default());
+        let cache = CacheManager::new().with_backend_chain(
+            CacheBackendChain::new()
+                .with_layer(origin.clone())
+                .with_fill_ttl(Duration::from_millis(50))
+                .with_negative_ttl(Duration::from_secs(60))
+                .with_stale_ttl(Duration::from_secs(60)),
+        );
+
+        let first = cache.get("product:1").await.unwrap();
+        assert_eq!(first.source, Some(CacheSource::Origin));
+        assert_eq!(first.value, Some(serde_json::json!({ "price": 10 })));
+        let second = cache.get("product:1").await.unwrap();
+        assert_eq!(second.source, Some(CacheSource::Memory));
+
+        let missing = cache.get("product:2").await.unwrap();
+        assert!(!missing.found);
+        assert_eq!(missing.source, None);
+        let remembered = cache.get("product:2").await.unwrap();
+        assert!(!remembered.found);
+        assert_eq!(remembered.source, Some(CacheSource::Negative));
+        assert!(cache.get_entry("product:2").await.unwrap().is_none());
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(cache.cleanup_expired().await.unwrap(), 0);
+        origin.down.store(true, Ordering::SeqCst);
+        let stale = cache.get("product:1").await.unwrap();
+        assert_eq!(stale.source, Some(CacheSource::Stale));
+        assert_eq!(stale.value, Some(serde_json::json!({ "price": 10 })));
+
+        assert!(cache.get("product:3").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_elect_a_single_leader() {
+        let cache = CacheManager::new();
+        let fetch = |cache: CacheManager| async move {
+            let fetched = cache
+                .get_or_set(GetOrSetRequest {
+                    key: "report".to_string(),
+                    lease: Duration::from_secs(10),
+                    wait_timeout: Duration::from_secs(5),
+                })
+                .await
+                .unwrap();
+            if matches!(fetched, CacheFetch::Leader { .. }) {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                cache
+                    .set(request("report", 42, CacheSetMode::Upsert))
+                    .await
+                    .unwrap();
+            }
+            fetched
+        };
+
+        let fetchers: Vec<_> = (0..20)
+            .map(|_| tokio::spawn(fetch(cache.clone())))
+            .collect();
+        let mut leaders = 0;
+        for fetcher in fetchers {
+            match fetcher.await.unwrap() {
+                CacheFetch::Leader { .. } => leaders += 1,
+                CacheFetch::Hit(response) => {
+                    assert_eq!(response.value, Some(serde_json::json!(42)))
+                }
+                CacheFetch::Pending { .. } => panic!("fetch should not time out"),
+            }
+        }
+        assert_eq!(leaders, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_lease_passes_on_when_the_leader_gives_up() {
+        let cache = CacheManager::new();
+        let fetch = |wait_timeout| {
+            cache.get_or_set(GetOrSetRequest {
+                key: "report".to_string(),
+                lease: Duration::from_millis(50),
+                wait_timeout,
+            })
+        };
+
+        assert!(matches!(
+            fetch(Duration::ZERO).await.unwrap(),
+            CacheFetch::Leader { .. }
+        ));
+        assert!(matches!(
+            fetch(Duration::ZERO).await.unwrap(),
+            CacheFetch::Pending { .. }
+        ));
+        // The leader never stores the value, so a waiter takes over.
+        assert!(matches!(
+            fetch(Duration::from_secs(5)).await.unwrap(),
+            CacheFetch::Leader { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_racing_compare_and_swaps_have_single_winner() {
+        let cache = CacheManager::new();
+        cache
+            .set(CacheRequest {
+                tags: vec!["doc".to_string()],
+                ..request("doc", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        let version = cache.get("doc").await.unwrap().version.unwrap();
+
+        let writers: Vec<_> = [2, 3]
+            .into_iter()
+            .map(|value| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    cache
+                        .compare_and_swap("doc", version, serde_json::json!(value), None)
+                        .await
+                })
+            })
+            .collect();
+        let mut swapped = Vec::new();
+        for writer in writers {
+            match writer.await.unwrap() {
+                Ok(response) => swapped.push(response),
+                Err(SyrosError::Conflict(_)) => {}
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+        assert_eq!(swapped.len(), 1);
+        assert_eq!(swapped[0].version, Some(version + 1));
+
+        let current = cache.get_entry("doc").await.unwrap().unwrap();
+        assert_eq!(Some(current.value), swapped[0].value);
+        assert_eq!(current.tags, ["doc"]);
+        assert!(matches!(
+            cache
+                .compare_and_swap("missing", 1, serde_json::json!(1), None)
+                .await,
+            Err(SyrosError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_increments_are_atomic() {
+        let cache = CacheManager::new();
+        let increments: Vec<_> = (0..100)
+            .map(|i| {
+                let cache = cache.clone();
+                let delta = if i % 4 == 0 { -1 } else { 2 };
+                tokio::spawn(async move { cache.increment("hits", delta, None).await })
+            })
+            .collect();
+        for increment in increments {
+            increment.await.unwrap().unwrap();
+        }
+
+        let hits = cache.get_entry("hits").await.unwrap().unwrap();
+        assert_eq!(hits.value, serde_json::json!(125));
+        assert_eq!(hits.version, 100);
+    }
+
+    #[tokio::test]
+    async fn test_increment_creates_counters_and_rejects_other_values() {
+        let cache = CacheManager::new();
+        let ttl = Some(Duration::from_secs(60));
+        assert_eq!(cache.increment("rate", 5, ttl).await.unwrap(), 5);
+        let created = cache.get_entry("rate").await.unwrap().unwrap();
+        assert!(created.expires_at.is_some());
+        assert_eq!(cache.increment("rate", -2, None).await.unwrap(), 3);
+        let incremented = cache.get_entry("rate").await.unwrap().unwrap();
+        assert_eq!(incremented.expires_at, created.expires_at);
+
+        cache
+            .set(CacheRequest {
+                value: serde_json::json!("five"),
+                ..request("name", 0, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            cache.increment("name", 1, None).await,
+            Err(SyrosError::Conflict(_))
+        ));
+        cache
+            .set(request("max", i64::MAX, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        assert!(matches!(
+            cache.increment("max", 1, None).await,
+            Err(SyrosError::Conflict(_))
+        ));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_reads_count_hits_and_misses() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let cache = CacheManager::new().with_metrics(metrics.clone());
+        cache
+            .set(request("present", 1, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+
+        cache.get("present").await.unwrap();
+        cache.get("absent").await.unwrap();
+        assert_eq!(metrics.cache_hits_total.get(), 1.0);
+        assert_eq!(metrics.cache_misses_total.get(), 1.0);
+
+        let keys = ["present", "absent", "present"].map(str::to_string);
+        cache.get_many(&keys).await.unwrap();
+        assert_eq!(metrics.cache_hits_total.get(), 3.0);
+        assert_eq!(metrics.cache_misses_total.get(), 2.0);
+
+        let durations = metrics.cache_operation_duration.with_label_values(&["get"]);
+        assert_eq!(durations.get_sample_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_entries_by_tag_and_value_bytes() {
+        let cache = CacheManager::new();
+        for (key, tags) in [("a", vec!["session"]), ("b", vec!["session", "vip"])] {
+            cache
+                .set(CacheRequest {
+                    tags: tags.into_iter().map(str::to_string).collect(),
+                    ..request(key, 100, CacheSetMode::Upsert)
+                })
+                .await
+                .unwrap();
+        }
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(10)),
+                tags: vec!["vip".to_string()],
+                ..request("gone", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let stats = cache.get_stats().await.unwrap();
+        assert_eq!(stats.active_entries, 2);
+        assert_eq!(stats.expired_entries, 1);
+        assert_eq!(
+            stats.entries_by_tag,
+            BTreeMap::from([("session".to_string(), 2), ("vip".to_string(), 1)])
+        );
+        // Each value is `100`, three bytes of JSON.
+        assert_eq!(stats.value_bytes, 6);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_and_touch() {
+        let cache = CacheManager::new();
+        assert!(matches!(
+            cache.ttl("missing").await,
+            Err(SyrosError::NotFound(_))
+        ));
+        assert!(matches!(
+            cache.touch("missing", Some(Duration::from_secs(60))).await,
+            Err(SyrosError::NotFound(_))
+        ));
+
+        cache
+            .set(request("forever", 1, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        assert_eq!(cache.ttl("forever").await.unwrap(), None);
+        let touched = cache
+            .touch("forever", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert_eq!(touched.value, Some(serde_json::json!(1)));
+        assert_eq!(touched.version, Some(1));
+        assert!(touched.expires_at.is_some());
+        let ttl = cache.ttl("forever").await.unwrap().unwrap();
+        assert!(ttl > Duration::from_secs(59) && ttl <= Duration::from_secs(60));
+        assert_eq!(
+            cache.get("forever").await.unwrap().expires_at,
+            touched.expires_at
+        );
+
+        // Touching without a TTL makes the entry permanent again.
+        cache.touch("forever", None).await.unwrap();
+        assert_eq!(cache.ttl("forever").await.unwrap(), None);
+
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(20)),
+                ..request("brief", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        cache
+            .touch("brief", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(cache.get("brief").await.unwrap().found);
+
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(10)),
+                ..request("gone", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(matches!(
+            cache.ttl("gone").await,
+            Err(SyrosError::NotFound(_))
+        ));
+        assert!(matches!(
+            cache.touch("gone", None).await,
+            Err(SyrosError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ttls_past_the_maximum_are_rejected() {
+        let cache = CacheManager::new();
+        let too_long = Some(MAX_CACHE_TTL + Duration::from_secs(1));
+        fn rejected<T>(outcome: Result<T>) -> bool {
+            matches!(outcome, Err(SyrosError::ApiError(_)))
+        }
+
+        assert!(rejected(
+            cache
+                .set(CacheRequest {
+                    ttl: Some(Duration::MAX),
+                    ..request("k", 1, CacheSetMode::Upsert)
+                })
+                .await
+        ));
+        assert!(rejected(cache.increment("n", 1, too_long).await));
+        let batch = cache
+            .set_many(vec![
+                CacheRequest {
+                    ttl: too_long,
+                    ..request("a", 1, CacheSetMode::Upsert)
+                },
+                request("b", 2, CacheSetMode::Upsert),
+            ])
+            .await
+            .unwrap();
+        assert!(matches!(batch[0], Err(SyrosError::ApiError(_))));
+        assert!(batch[1].is_ok());
+        assert!(rejected(cache.touch("b", too_long).await));
+        assert!(rejected(
+            cache
+                .compare_and_swap("b", 1, serde_json::json!(3), too_long)
+                .await
+        ));
+        assert_eq!(cache.get("b").await.unwrap().expires_at, None);
+
+        let longest = cache.touch("b", Some(MAX_CACHE_TTL)).await.unwrap();
+        assert!(longest.expires_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_the_reserved_key_is_not_written() {
+        let cache = CacheManager::new();
+        let reserved = || request(RESERVED_CACHE_KEY, 1, CacheSetMode::Upsert);
+
+        assert!(matches!(
+            cache.set(reserved()).await,
+            Err(SyrosError::ApiError(_))
+        ));
+        assert!(matches!(
+            cache.increment(RESERVED_CACHE_KEY, 1, None).await,
+            Err(SyrosError::ApiError(_))
+        ));
+        let batch = cache
+            .set_many(vec![reserved(), request("b", 2, CacheSetMode::Upsert)])
+            .await
+            .unwrap();
+        assert!(matches!(batch[0], Err(SyrosError::ApiError(_))));
+        assert!(batch[1].is_ok());
+        assert!(!cache.get(RESERVED_CACHE_KEY).await.unwrap().found);
+    }
+
+    #[tokio::test]
+    async fn test_negative_entries_are_reported_apart_from_misses() {
+        let config = CacheConfig {
+            negative_ttl_seconds: Some(60),
+            ..CacheConfig::default()
+        };
+        let cache = CacheManager::new().with_limits(&config);
+        assert_eq!(cache.get("absent").await.unwrap().state, CacheState::Miss);
+
+        // A longer TTL than the negative one is capped.
+        let written = cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_secs(3600)),
+                cache_negative: true,
+                ..request("absent", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        assert_eq!(written.state, CacheState::NegativeHit);
+        assert_eq!(written.value, None);
+        let ttl = cache.ttl("absent").await;
+        assert!(matches!(ttl, Err(SyrosError::NotFound(_))));
+        let expires_in = written.expires_at.unwrap() - Utc::now();
+        assert!(expires_in <= chrono::Duration::seconds(60));
+
+        let read = cache.get("absent").await.unwrap();
+        assert_eq!(read.state, CacheState::NegativeHit);
+        assert!(!read.found);
+        assert_eq!(read.value, None);
+        assert_eq!(read.source, Some(CacheSource::Negative));
+        let keys = vec!["absent".to_string(), "other".to_string()];
+        let states: Vec<_> = cache
+            .get_many(&keys)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|response| response.state)
+            .collect();
+        assert_eq!(states, vec![CacheState::NegativeHit, CacheState::Miss]);
+
+        // Callers fetching the key are not elected to compute it.
+        let fetched = cache
+            .get_or_set(GetOrSetRequest {
+                key: "absent".to_string(),
+                lease: Duration::from_secs(10),
+                wait_timeout: Duration::from_millis(10),
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            fetched,
+            CacheFetch::Hit(CacheResponse {
+                state: CacheState::NegativeHit,
+                ..
+            })
+        ));
+
+        // A shorter TTL is kept, and a real value replaces the sentinel.
+        cache
+            .set(CacheRequest {
+                ttl: Some(Duration::from_millis(10)),
+                cache_negative: true,
+                ..request("brief", 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(cache.get("brief").await.unwrap().state, CacheState::Miss);
+        let stored = cache
+            .set(request("absent", 7, CacheSetMode::CreateOnly))
+            .await
+            .unwrap();
+        assert_eq!(stored.state, CacheState::Hit);
+        assert_eq!(cache.get("absent").await.unwrap().state, CacheState::Hit);
+    }
+
+    #[tokio::test]
+    async fn test_tag_membership_follows_overwrites_and_retags() {
+        let cache = CacheManager::new();
+        let tags = |tags: &[&str]| -> Vec<String> { tags.iter().map(|t| t.to_string()).collect() };
+        for key in ["user:1", "user:2"] {
+            cache
+                .set(CacheRequest {
+                    tags: tags(&["users", "team:a"]),
+                    ..request(key, 1, CacheSetMode::Upsert)
+                })
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            cache.keys_by_tag("team:a").await.unwrap(),
+            ["user:1", "user:2"]
+        );
+
+        // Overwriting with other tags moves the key out of the old ones.
+        cache
+            .set(CacheRequest {
+                tags: tags(&["users", "team:b"]),
+                ..request("user:2", 2, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        assert_eq!(cache.keys_by_tag("team:a").await.unwrap(), ["user:1"]);
+        assert_eq!(cache.keys_by_tag("team:b").await.unwrap(), ["user:2"]);
+        assert_eq!(
+            cache.keys_by_tag("users").await.unwrap(),
+            ["user:1", "user:2"]
+        );
+
+        let added = cache
+            .add_tags("user:1", &tags(&["team:b", "users"]))
+            .await
+            .unwrap();
+        assert_eq!(added.tags, tags(&["users", "team:a", "team:b"]));
+        assert_eq!(added.value, serde_json::json!(1));
+        assert_eq!(added.version, 1);
+        let removed = cache
+            .remove_tags("user:1", &tags(&["team:a", "unknown"]))
+            .await
+            .unwrap();
+        assert_eq!(removed.tags, tags(&["users", "team:b"]));
+        assert!(cache.keys_by_tag("team:a").await.unwrap().is_empty());
+        assert_eq!(
+            cache.keys_by_tag("team:b").await.unwrap(),
+            ["user:1", "user:2"]
+        );
+
+        let invalidated = cache
+            .invalidate_by_tag(InvalidateByTagRequest {
+                tag: "team:b".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(invalidated.invalidated_count, 2);
+        assert!(matches!(
+            cache.add_tags("user:1", &tags(&["users"])).await,
+            Err(SyrosError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_operations_keep_key_order_and_per_key_outcomes() {
+        let cache = CacheManager::new().with_limits(&CacheConfig {
+            max_value_bytes: Some(64),
+            ..CacheConfig::default()
+        });
+        cache
+            .set(request("b", 2, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+
+        let outcomes = cache
+            .set_many(vec![
+                request("a", 1, CacheSetMode::Upsert),
+                request("b", 3, CacheSetMode::CreateOnly),
+                CacheRequest {
+                    value: serde_json::json!("x".repeat(100)),
+                    ..request("c", 0, CacheSetMode::Upsert)
+                },
+                request("d", 4, CacheSetMode::UpdateOnly),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(outcomes[0].as_ref().unwrap().version, Some(1));
+        assert!(matches!(outcomes[1], Err(SyrosError::Conflict(_))));
+        assert!(matches!(outcomes[2], Err(SyrosError::ValueTooLarge(_))));
+        assert!(matches!(outcomes[3], Err(SyrosError::NotFound(_))));
+
+        let keys: Vec<String> = ["b", "missing", "a"].map(str::to_string).to_vec();
+        let read = cache.get_many(&keys).await.unwrap();
+        assert_eq!(
+            read.iter().map(|r| r.value.clone()).collect::<Vec<_>>(),
+            vec![Some(serde_json::json!(2)), None, Some(serde_json::json!(1))]
+        );
+        assert_eq!(read[0].source, Some(CacheSource::Memory));
+
+        let deleted = cache.delete_many(&keys).await.unwrap();
+        assert_eq!(
+            deleted.iter().map(|r| r.success).collect::<Vec<_>>(),
+            vec![true, false, true]
+        );
+        assert_eq!(cache.get_stats().await.unwrap().total_entries, 0);
+    }
+
+    #[test]
+    fn test_backend_name_reports_the_store() {
+        assert_eq!(CacheManager::new().backend_name(), "memory");
+        // Creating the Redis backend does not connect yet.
+        let redis = RedisManager::new("redis://127.0.0.1:6379").unwrap();
+        assert_eq!(CacheManager::with_redis(redis).backend_name(), "redis");
+    }
+
+    #[tokio::test]
+    async fn test_redis_cache_is_shared_across_instances() {
+        let Ok(url) = std::env::var(TEST_REDIS_URL_ENV) else {
+            eprintln!("Skipping: set {} to run against Redis", TEST_REDIS_URL_ENV);
+            return;
+        };
+        // Two managers sharing nothing but Redis, as two processes would.
+        let first = CacheManager::with_redis(RedisManager::new(&url).unwrap());
+        let second = CacheManager::with_redis(RedisManager::new(&url).unwrap());
+        let prefix = format!("shared-{}:", Uuid::new_v4());
+        let tag = format!("{}tag", prefix);
+        let key = |name: &str| format!("{}{}", prefix, name);
+
+        first
+            .set(CacheRequest {
+                tags: vec![tag.clone()],
+                ..request(&key("1"), 1, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+        let read = second.get(&key("1")).await.unwrap();
+        assert!(read.found);
+        assert_eq!(read.value, Some(serde_json::json!(1)));
+        assert_eq!(read.source, Some(CacheSource::Redis));
+
+        let rewritten = second
+            .set(CacheRequest {
+                tags: vec![tag.clone()],
+                ..request(&key("1"), 2, CacheSetMode::UpdateOnly)
+            })
+            .await
+            .unwrap();
+        assert_eq!(rewritten.version, Some(2));
+        assert!(matches!(
+            first
+                .set(request(&key("1"), 3, CacheSetMode::CreateOnly))
+                .await,
+            Err(SyrosError::Conflict(_))
+        ));
+        let swapped = first
+            .compare_and_swap(&key("1"), 2, serde_json::json!(3), None)
+            .await
+            .unwrap();
+        assert_eq!(swapped.version, Some(3));
+        assert!(matches!(
+            second
+                .compare_and_swap(&key("1"), 2, serde_json::json!(4), None)
+                .await,
+            Err(SyrosError::Conflict(_))
+        ));
+        second
+            .set(CacheRequest {
+                ttl: Some(Duration::from_secs(60)),
+                tags: vec![tag.clone()],
+                ..request(&key("2"), 2, CacheSetMode::Upsert)
+            })
+            .await
+            .unwrap();
+
+        let pattern = format!("{}*", prefix);
+        let listed = first.list(Some(&pattern), &[], None).await.unwrap();
+        let keys: Vec<_> = listed.iter().map(|entry| entry.key.clone()).collect();
+        assert_eq!(keys, [key("1"), key("2")]);
+        assert_eq!(listed[0].version, 3);
+        assert!(listed[1].expires_at.is_some());
+        assert!(first.ttl(&key("2")).await.unwrap().is_some());
+        let touched = first.touch(&key("2"), None).await.unwrap();
+        assert_eq!(touched.version, Some(1));
+        assert_eq!(second.ttl(&key("2")).await.unwrap(), None);
+        assert!(matches!(
+            second.touch(&key("missing"), None).await,
+            Err(SyrosError::NotFound(_))
+        ));
+        let other = format!("{}other", prefix);
+        first
+            .add_tags(&key("2"), std::slice::from_ref(&other))
+            .await
+            .unwrap();
+        assert_eq!(second.keys_by_tag(&other).await.unwrap(), [key("2")]);
+        second
+            .remove_tags(&key("2"), std::slice::from_ref(&other))
+            .await
+            .unwrap();
+        assert!(first.keys_by_tag(&other).await.unwrap().is_empty());
+        assert_eq!(
+            first
+                .list(None, std::slice::from_ref(&tag), None)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let invalidated = first
+            .invalidate_by_tag(InvalidateByTagRequest { tag: tag.clone() })
+            .await
+            .unwrap();
+        assert_eq!(invalidated.invalidated_count, 2);
+        assert!(!second.get(&key("2")).await.unwrap().found);
+
+        second
+            .set(request(&key("3"), 3, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        let deleted = first
+            .delete(DeleteCacheRequest { key: key("3") })
+            .await
+            .unwrap();
+        assert!(deleted.success);
+        assert!(second.get_entry(&key("3")).await.unwrap().is_none());
+
+        // Bulk writes and deletes go through one pipeline each.
+        let written = first
+            .set_many(vec![
+                CacheRequest {
+                    tags: vec![tag.clone()],
+                    ..request(&key("4"), 4, CacheSetMode::Upsert)
+                },
+                request(&key("5"), 5, CacheSetMode::Upsert),
+                request(&key("4"), 6, CacheSetMode::CreateOnly),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(written[0].as_ref().unwrap().version, Some(1));
+        assert!(written[1].is_ok());
+        assert!(matches!(written[2], Err(SyrosError::Conflict(_))));
+        let read = second
+            .get_many(&[key("4"), key("5"), key("6")])
+            .await
+            .unwrap();
+        let found: Vec<bool> = read.iter().map(|response| response.found).collect();
+        assert_eq!(found, [true, true, false]);
+        let deleted = second
+            .delete_many(&[key("4"), key("6"), key("5")])
+            .await
+            .unwrap();
+        let deleted: Vec<bool> = deleted.iter().map(|response| response.success).collect();
+        assert_eq!(deleted, [true, false, true]);
+        assert!(first.keys_by_tag(&tag).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redis_increments_are_atomic_across_instances() {
+        let Ok(url) = std::env::var(TEST_REDIS_URL_ENV) else {
+            eprintln!("Skipping: set {} to run against Redis", TEST_REDIS_URL_ENV);
+            return;
+        };
+        let first = CacheManager::with_redis(RedisManager::new(&url).unwrap());
+        let second = CacheManager::with_redis(RedisManager::new(&url).unwrap());
+        let key = format!("counter-{}", Uuid::new_v4());
+
+        let increments: Vec<_> = (0..100)
+            .map(|i| {
+                let cache = if i % 2 == 0 { &first } else { &second }.clone();
+                let key = key.clone();
+                tokio::spawn(async move {
+                    cache
+                        .increment(&key, 1, Some(Duration::from_secs(60)))
+                        .await
+                })
+            })
+            .collect();
+        for increment in increments {
+            increment.await.unwrap().unwrap();
+        }
+
+        let counted = first.get(&key).await.unwrap();
+        assert_eq!(counted.value, Some(serde_json::json!(100)));
+        assert_eq!(counted.version, Some(100));
+        first
+            .set(request(&key, 7, CacheSetMode::Upsert))
+            .await
+            .unwrap();
+        assert_eq!(second.increment(&key, 1, None).await.unwrap(), 8);
+    }
 }
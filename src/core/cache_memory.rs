@@ -0,0 +1,612 @@
+//! Cache entries kept in process memory.
+//!
+//! The entries live in a map shared with the optional write-behind journal,
+//! and only the clones of a [`MemoryCache`] see them. Their number can be
+//! capped, in which case the least recently written or read entries are
+//! evicted to make room for new ones.
+
+use crate::config::CachePersistenceConfig;
+use crate::core::cache_backend::{
+    expiry_after, increment_overflow, key_not_found, not_an_integer, retagged, version_mismatch,
+    CacheBackend,
+};
+use crate::core::cache_journal::{CacheJournal, JournalRecord};
+use crate::core::cache_manager::{key_matches, CacheEntry, CacheSetMode, CacheStats};
+use crate::core::memory::{entry_size, serialized_size};
+use crate::core::task_tracker::TaskTracker;
+#[cfg(feature = "metrics")]
+use crate::metrics::{Metrics, MetricsHandle};
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Most expired entries removed under one hold of the write lock.
+const SWEEP_BATCH: usize = 256;
+
+/// Order in which cache keys were last written or read, oldest first.
+#[derive(Default)]
+struct AccessOrder {
+    tick: u64,
+    ticks: HashMap<String, u64>,
+    keys: BTreeMap<u64, String>,
+}
+
+impl AccessOrder {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some(previous) = self.ticks.insert(key.to_string(), self.tick) {
+            self.keys.remove(&previous);
+        }
+        self.keys.insert(self.tick, key.to_string());
+    }
+
+    fn forget(&mut self, key: &str) {
+        if let Some(tick) = self.ticks.remove(key) {
+            self.keys.remove(&tick);
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<String> {
+        let (_, key) = self.keys.pop_first()?;
+        self.ticks.remove(&key);
+        Some(key)
+    }
+}
+
+/// Entries held and the bytes of their values, kept up to date as entries
+/// are stored and removed so the sweep can report them without a scan.
+/// Remembered absences are not counted.
+#[derive(Default)]
+struct StoredTotals {
+    entries: AtomicU64,
+    value_bytes: AtomicU64,
+}
+
+impl StoredTotals {
+    fn add(&self, entry: &CacheEntry) {
+        if !entry.negative {
+            self.entries.fetch_add(1, Ordering::Relaxed);
+            self.value_bytes
+                .fetch_add(serialized_size(&entry.value) as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn subtract(&self, entry: &CacheEntry) {
+        if !entry.negative {
+            self.entries.fetch_sub(1, Ordering::Relaxed);
+            self.value_bytes
+                .fetch_sub(serialized_size(&entry.value) as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// What memory holds for a key read through [`MemoryCache::lookup`].
+pub(crate) enum MemoryLookup {
+    /// A live entry, possibly a remembered absence
+    Live(CacheEntry),
+    /// An expired entry, kept for stale reads if still wanted
+    Expired(Option<CacheEntry>),
+    Missing,
+}
+
+/// Cache entries in process memory, optionally journaled to disk.
+#[derive(Clone)]
+pub struct MemoryCache {
+    entries: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    /// Access order of the keys in `entries`, updated under its write lock
+    access: Arc<Mutex<AccessOrder>>,
+    /// Totals of `entries`, updated under its write lock
+    totals: Arc<StoredTotals>,
+    max_entries: Option<usize>,
+    journal: Option<CacheJournal>,
+    #[cfg(feature = "metrics")]
+    metrics: MetricsHandle,
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::default(),
+            access: Arc::default(),
+            totals: Arc::default(),
+            max_entries: None,
+            journal: None,
+            #[cfg(feature = "metrics")]
+            metrics: MetricsHandle::default(),
+        }
+    }
+
+    /// Creates a cache backed by a write-behind journal, whose writer task is
+    /// spawned through `tasks`.
+    ///
+    /// Entries recorded in the journal are replayed first, oldest first in
+    /// the access order.
+    pub async fn with_persistence(
+        config: &CachePersistenceConfig,
+        tasks: &TaskTracker,
+    ) -> Result<Self> {
+        let entries = CacheJournal::replay(std::path::Path::new(&config.path))?;
+        let mut access = AccessOrder::default();
+        let totals = StoredTotals::default();
+        let mut replayed: Vec<&CacheEntry> = entries.values().collect();
+        replayed.sort_by_key(|entry| entry.created_at);
+        for entry in replayed {
+            access.touch(&entry.key);
+            totals.add(entry);
+        }
+        let entries = Arc::new(RwLock::new(entries));
+        let journal = CacheJournal::spawn(config, entries.clone(), tasks).await?;
+
+        Ok(Self {
+            entries,
+            access: Arc::new(Mutex::new(access)),
+            totals: Arc::new(totals),
+            journal: Some(journal),
+            ..Self::new()
+        })
+    }
+
+    /// Evicts the least recently used entries beyond `max_entries`.
+    pub fn with_max_entries(mut self, max_entries: Option<usize>) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> Self {
+        self.metrics.set(metrics);
+        self
+    }
+
+    pub fn is_journaled(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Waits until every mutation so far has been written to the journal.
+    pub async fn flush(&self) -> Result<()> {
+        match &self.journal {
+            Some(journal) => journal.flush().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Rewrites the journal as a snapshot of the live entries.
+    pub async fn compact(&self) -> Result<()> {
+        match &self.journal {
+            Some(journal) => journal.compact().await,
+            None => Ok(()),
+        }
+    }
+
+    fn journal(&self, record: JournalRecord) {
+        if let Some(journal) = &self.journal {
+            journal.record(record);
+        }
+    }
+
+    /// Stores `entry` as the most recently used, evicting the least recently
+    /// used entries beyond `max_entries`. Called under the write lock.
+    fn insert(&self, entries: &mut HashMap<String, CacheEntry>, entry: CacheEntry) {
+        let mut access = self.access.lock().unwrap();
+        access.touch(&entry.key);
+        self.totals.add(&entry);
+        if let Some(replaced) = entries.insert(entry.key.clone(), entry) {
+            self.totals.subtract(&replaced);
+        }
+
+        let Some(max_entries) = self.max_entries else {
+            return;
+        };
+        let mut evicted = 0u64;
+        while entries.len() > max_entries {
+            let Some(key) = access.pop_oldest() else {
+                break;
+            };
+            if let Some(removed) = entries.remove(&key) {
+                self.totals.subtract(&removed);
+                self.journal(JournalRecord::Delete { key });
+                evicted += 1;
+            }
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            if evicted > 0 {
+                metrics.increment_cache_evictions(evicted);
+            }
+            metrics.set_cache_size(self.totals.entries.load(Ordering::Relaxed) as f64);
+        }
+        if evicted > 0 {
+            tracing::debug!("Evicted {} least recently used cache entries", evicted);
+        }
+    }
+
+    /// Reads `key`, marking a live entry as used.
+    ///
+    /// An expired entry is kept for stale reads if `keep_stale` wants it,
+    /// and removed otherwise.
+    pub(crate) async fn lookup(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        keep_stale: impl Fn(&CacheEntry) -> bool,
+    ) -> MemoryLookup {
+        let mut entries = self.entries.write().await;
+        let Some(entry) = entries.get(key) else {
+            return MemoryLookup::Missing;
+        };
+        if entry.is_live(now) {
+            self.access.lock().unwrap().touch(key);
+            return MemoryLookup::Live(entry.clone());
+        }
+        if !entry.negative && keep_stale(entry) {
+            return MemoryLookup::Expired(Some(entry.clone()));
+        }
+        if let Some(removed) = entries.remove(key) {
+            self.totals.subtract(&removed);
+        }
+        self.access.lock().unwrap().forget(key);
+        MemoryLookup::Expired(None)
+    }
+
+    /// Stores a value found below the cache, unless a live entry was
+    /// written meanwhile; returns the entry now cached.
+    pub(crate) async fn fill(&self, entry: CacheEntry) -> CacheEntry {
+        let mut entries = self.entries.write().await;
+        if let Some(current) = entries
+            .get(&entry.key)
+            .filter(|current| !current.negative && current.is_live(entry.created_at))
+        {
+            return current.clone();
+        }
+        self.journal(JournalRecord::Set {
+            entry: entry.clone(),
+        });
+        self.insert(&mut entries, entry.clone());
+        entry
+    }
+
+    /// The live entry under `key`, without marking it as used.
+    pub async fn get_entry(&self, key: &str) -> Option<CacheEntry> {
+        let entries = self.entries.read().await;
+        entries
+            .get(key)
+            .filter(|entry| !entry.negative && entry.is_live(Utc::now()))
+            .cloned()
+    }
+
+    /// Removes expired entries, except those `keep_stale` wants kept for
+    /// stale reads; returns how many were removed.
+    ///
+    /// The entries are scanned under the read lock, and the expired ones
+    /// removed [`SWEEP_BATCH`] at a time, so writers are only held up for
+    /// one batch at once.
+    pub async fn cleanup_expired(&self, keep_stale: impl Fn(&CacheEntry) -> bool) -> u64 {
+        let now = Utc::now();
+        let expired = |entry: &CacheEntry| !entry.is_live(now) && !keep_stale(entry);
+        let candidates: Vec<String> = {
+            let entries = self.entries.read().await;
+            entries
+                .iter()
+                .filter(|(_, entry)| expired(entry))
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+
+        let mut removed = 0;
+        for batch in candidates.chunks(SWEEP_BATCH) {
+            {
+                let mut entries = self.entries.write().await;
+                let mut access = self.access.lock().unwrap();
+                for key in batch {
+                    // The key may have been rewritten since the scan.
+                    if matches!(entries.get(key), Some(entry) if expired(entry)) {
+                        if let Some(entry) = entries.remove(key) {
+                            self.totals.subtract(&entry);
+                        }
+                        access.forget(key);
+                        removed += 1;
+                    }
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+        removed
+    }
+
+    /// Entries held, stale ones included, and the approximate bytes of their
+    /// values as serialized JSON; remembered absences are left out.
+    pub fn stored_totals(&self) -> (u64, u64) {
+        (
+            self.totals.entries.load(Ordering::Relaxed),
+            self.totals.value_bytes.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Estimated bytes held by the entries, stale ones included.
+    pub async fn estimated_bytes(&self) -> u64 {
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .map(|(key, entry)| entry_size(key, entry))
+            .sum::<usize>() as u64
+    }
+
+    /// Stores `entry` as `mode` allows. Called under the write lock.
+    fn store(
+        &self,
+        entries: &mut HashMap<String, CacheEntry>,
+        mut entry: CacheEntry,
+        mode: CacheSetMode,
+    ) -> Result<CacheEntry> {
+        let current = entries
+            .get(&entry.key)
+            .filter(|current| !current.negative && current.is_live(entry.created_at));
+        let exists = current.is_some();
+        if let Some(current) = current {
+            entry.version = current.version + 1;
+        }
+        match mode {
+            CacheSetMode::CreateOnly if exists => {
+                return Err(SyrosError::Conflict(format!(
+                    "Cache key {} already exists",
+                    entry.key
+                )));
+            }
+            CacheSetMode::UpdateOnly if !exists => {
+                return Err(SyrosError::NotFound(format!(
+                    "Cache key {} not found",
+                    entry.key
+                )));
+            }
+            _ => {}
+        }
+
+        self.journal(JournalRecord::Set {
+            entry: entry.clone(),
+        });
+        self.insert(entries, entry.clone());
+        Ok(entry)
+    }
+
+    /// Removes the entry under `key`. Called under the write lock.
+    fn remove(&self, entries: &mut HashMap<String, CacheEntry>, key: &str) -> bool {
+        let Some(removed) = entries.remove(key) else {
+            return false;
+        };
+        self.totals.subtract(&removed);
+        self.access.lock().unwrap().forget(key);
+        self.journal(JournalRecord::Delete {
+            key: key.to_string(),
+        });
+        true
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>> {
+        Ok(match self.lookup(key, Utc::now(), |_| true).await {
+            MemoryLookup::Live(entry) => Some(entry),
+            MemoryLookup::Expired(_) | MemoryLookup::Missing => None,
+        })
+    }
+
+    async fn set(&self, entry: CacheEntry, mode: CacheSetMode) -> Result<CacheEntry> {
+        let mut entries = self.entries.write().await;
+        self.store(&mut entries, entry, mode)
+    }
+
+    async fn compare_and_swap(
+        &self,
+        mut entry: CacheEntry,
+        expected_version: u64,
+    ) -> Result<CacheEntry> {
+        let mut entries = self.entries.write().await;
+
+        let current = entries
+            .get(&entry.key)
+            .filter(|current| !current.negative && current.is_live(entry.created_at))
+            .filter(|current| current.version == expected_version)
+            .ok_or_else(|| version_mismatch(&entry.key, expected_version))?;
+        entry.tags = current.tags.clone();
+        entry.version = current.version + 1;
+
+        self.journal(JournalRecord::Set {
+            entry: entry.clone(),
+        });
+        self.insert(&mut entries, entry.clone());
+        Ok(entry)
+    }
+
+    async fn increment(&self, key: &str, delta: i64, ttl_if_new: Option<Duration>) -> Result<i64> {
+        let mut entries = self.entries.write().await;
+        let now = Utc::now();
+
+        let (value, entry) = match entries
+            .get(key)
+            .filter(|current| !current.negative && current.is_live(now))
+        {
+            Some(current) => {
+                let value = current
+                    .value
+                    .as_i64()
+                    .ok_or_else(|| not_an_integer(key))?
+                    .checked_add(delta)
+                    .ok_or_else(|| increment_overflow(key))?;
+                let entry = CacheEntry {
+                    value: value.into(),
+                    version: current.version + 1,
+                    ..current.clone()
+                };
+                (value, entry)
+            }
+            None => {
+                let entry = CacheEntry {
+                    key: key.to_string(),
+                    value: delta.into(),
+                    expires_at: ttl_if_new.map(|ttl| expiry_after(now, ttl)),
+                    tags: vec![],
+                    created_at: now,
+                    created_by: None,
+                    negative: false,
+                    version: 1,
+                };
+                (delta, entry)
+            }
+        };
+
+        self.journal(JournalRecord::Set {
+            entry: entry.clone(),
+        });
+        self.insert(&mut entries, entry);
+        Ok(value)
+    }
+
+    async fn touch(&self, key: &str, expires_at: Option<DateTime<Utc>>) -> Result<CacheEntry> {
+        let mut entries = self.entries.write().await;
+        let now = Utc::now();
+
+        let entry = entries
+            .get_mut(key)
+            .filter(|current| !current.negative && current.is_live(now))
+            .ok_or_else(|| key_not_found(key))?;
+        entry.expires_at = expires_at;
+        let entry = entry.clone();
+        self.access.lock().unwrap().touch(key);
+
+        self.journal(JournalRecord::Set {
+            entry: entry.clone(),
+        });
+        Ok(entry)
+    }
+
+    async fn retag(&self, key: &str, add: &[String], remove: &[String]) -> Result<CacheEntry> {
+        let mut entries = self.entries.write().await;
+        let now = Utc::now();
+
+        let entry = entries
+            .get_mut(key)
+            .filter(|current| !current.negative && current.is_live(now))
+            .ok_or_else(|| key_not_found(key))?;
+        entry.tags = retagged(&entry.tags, add, remove);
+        let entry = entry.clone();
+        self.access.lock().unwrap().touch(key);
+
+        self.journal(JournalRecord::Set {
+            entry: entry.clone(),
+        });
+        Ok(entry)
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        let mut entries = self.entries.write().await;
+        Ok(self.remove(&mut entries, key))
+    }
+
+    async fn invalidate_by_tag(&self, tag: &str) -> Result<u64> {
+        let mut entries = self.entries.write().await;
+        let initial_count = entries.len();
+
+        let mut access = self.access.lock().unwrap();
+        entries.retain(|key, entry| {
+            let keep = !entry.tags.iter().any(|t| t == tag);
+            if !keep {
+                access.forget(key);
+                self.totals.subtract(entry);
+            }
+            keep
+        });
+        drop(access);
+
+        let invalidated_count = (initial_count - entries.len()) as u64;
+        if invalidated_count > 0 {
+            self.journal(JournalRecord::InvalidateTag {
+                tag: tag.to_string(),
+            });
+        }
+        Ok(invalidated_count)
+    }
+
+    async fn list(
+        &self,
+        pattern: Option<&str>,
+        tags: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<CacheEntry>> {
+        let now = Utc::now();
+        let entries = self.entries.read().await;
+        let mut listed: Vec<CacheEntry> = entries
+            .values()
+            .filter(|entry| !entry.negative && entry.is_live(now))
+            .filter(|entry| pattern.is_none_or(|pattern| key_matches(pattern, &entry.key)))
+            .filter(|entry| tags.iter().all(|tag| entry.tags.contains(tag)))
+            .cloned()
+            .collect();
+        listed.sort_by(|a, b| a.key.cmp(&b.key));
+        listed.truncate(limit.unwrap_or(usize::MAX));
+        Ok(listed)
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<CacheEntry>>> {
+        let entries = self.entries.write().await;
+        let now = Utc::now();
+        let mut access = self.access.lock().unwrap();
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let entry = entries.get(key).filter(|entry| entry.is_live(now))?;
+                access.touch(key);
+                Some(entry.clone())
+            })
+            .collect())
+    }
+
+    async fn set_many(
+        &self,
+        batch: Vec<(CacheEntry, CacheSetMode)>,
+    ) -> Result<Vec<Result<CacheEntry>>> {
+        let mut entries = self.entries.write().await;
+        Ok(batch
+            .into_iter()
+            .map(|(entry, mode)| self.store(&mut entries, entry, mode))
+            .collect())
+    }
+
+    async fn delete_many(&self, keys: &[String]) -> Result<Vec<bool>> {
+        let mut entries = self.entries.write().await;
+        Ok(keys
+            .iter()
+            .map(|key| self.remove(&mut entries, key))
+            .collect())
+    }
+
+    async fn stats(&self) -> Result<CacheStats> {
+        let entries = self.entries.read().await;
+        let now = Utc::now();
+
+        let mut stats = CacheStats {
+            total_entries: entries.len(),
+            ..CacheStats::default()
+        };
+        for entry in entries.values() {
+            if entry.is_live(now) {
+                stats.active_entries += 1;
+                stats.add_live(entry);
+            } else {
+                stats.expired_entries += 1;
+            }
+        }
+        Ok(stats)
+    }
+}
@@ -0,0 +1,639 @@
+//! Cache entries kept in Redis, shared by every instance pointed at the same
+//! server.
+//!
+//! Each entry is a hash under `syros:cache:{key}` holding its JSON, version
+//! and whether it marks an absence, and expires with the entry's TTL. An
+//! integer value is also held as a `counter` field, which increments update
+//! in place with `HINCRBY` and which takes precedence over the JSON. Every
+//! tag is a set of the keys written with it under `syros:cache_tag:{tag}`.
+//! Sets are not updated when an entry expires or is rewritten without the
+//! tag, so their members are checked against the entry before use and
+//! pruned by [`RedisCache::prune_tags`].
+
+use crate::core::cache_backend::{
+    expiry_after, increment_overflow, key_not_found, not_an_integer, retagged, version_mismatch,
+    CacheBackend,
+};
+use crate::core::cache_manager::{key_matches, CacheEntry, CacheSetMode, CacheStats};
+use crate::core::lock_manager::{escape_glob, glob_prefix};
+use crate::storage::redis::RedisManager;
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const ENTRY_PREFIX: &str = "syros:cache:";
+const TAG_PREFIX: &str = "syros:cache_tag:";
+
+/// Most entries fetched per round trip when gathering stats.
+const STATS_BATCH: usize = 500;
+
+fn entry_key(key: &str) -> String {
+    format!("{}{}", ENTRY_PREFIX, key)
+}
+
+fn tag_key(tag: &str) -> String {
+    format!("{}{}", TAG_PREFIX, tag)
+}
+
+fn storage_error(e: redis::RedisError) -> SyrosError {
+    SyrosError::StorageError(e.to_string())
+}
+
+/// Parses the fields of an entry hash; `None` if the entry is gone.
+fn parse_entry(fields: HashMap<String, String>) -> Result<Option<CacheEntry>> {
+    let Some(json) = fields.get("entry") else {
+        return Ok(None);
+    };
+    let mut entry: CacheEntry =
+        serde_json::from_str(json).map_err(|e| SyrosError::StorageError(e.to_string()))?;
+    if let Some(version) = fields.get("version").and_then(|v| v.parse().ok()) {
+        entry.version = version;
+    }
+    if let Some(counter) = fields.get("counter").and_then(|v| v.parse::<i64>().ok()) {
+        entry.value = counter.into();
+    }
+    Ok(Some(entry))
+}
+
+/// Checks the mode or expected version against the live entry, then
+/// replaces it with the new one, numbered one version past it, and indexes
+/// it by its tags. Returns the new version, or -1, -2 or -3 if the entry
+/// exists, is missing or is at another version.
+const STORE_SCRIPT: &str = r"
+    local exists = redis.call('hget', KEYS[1], 'negative') == '0'
+    if ARGV[2] == 'create_only' and exists then
+        return -1
+    end
+    if ARGV[2] == 'update_only' and not exists then
+        return -2
+    end
+    if ARGV[2] == 'if_version'
+        and (not exists or redis.call('hget', KEYS[1], 'version') ~= ARGV[7]) then
+        return -3
+    end
+    local version = 1
+    if exists then
+        version = tonumber(redis.call('hget', KEYS[1], 'version')) + 1
+    end
+    redis.call('del', KEYS[1])
+    redis.call('hset', KEYS[1], 'entry', ARGV[1], 'version', version, 'negative', ARGV[3])
+    if ARGV[6] ~= '' then
+        redis.call('hset', KEYS[1], 'counter', ARGV[6])
+    end
+    if tonumber(ARGV[4]) > 0 then
+        redis.call('pexpire', KEYS[1], ARGV[4])
+    end
+    for i = 2, #KEYS do
+        redis.call('sadd', KEYS[i], ARGV[5])
+    end
+    return version
+";
+
+/// Keys and arguments of a [`STORE_SCRIPT`] call storing `entry`.
+fn store_call(
+    entry: &CacheEntry,
+    mode: CacheSetMode,
+    expected_version: Option<u64>,
+) -> Result<(Vec<String>, Vec<String>)> {
+    let json = serde_json::to_string(entry).map_err(|e| SyrosError::StorageError(e.to_string()))?;
+    let ttl_ms = entry
+        .expires_at
+        .map(|expires_at| (expires_at - Utc::now()).num_milliseconds().max(1))
+        .unwrap_or(0);
+    let mode_name = match (mode, expected_version) {
+        (_, Some(_)) => "if_version",
+        (CacheSetMode::Upsert, None) => "upsert",
+        (CacheSetMode::CreateOnly, None) => "create_only",
+        (CacheSetMode::UpdateOnly, None) => "update_only",
+    };
+
+    let mut keys = vec![entry_key(&entry.key)];
+    keys.extend(entry.tags.iter().map(|tag| tag_key(tag)));
+    let counter = entry
+        .value
+        .as_i64()
+        .filter(|_| !entry.negative)
+        .map(|counter| counter.to_string())
+        .unwrap_or_default();
+    let args = vec![
+        json,
+        mode_name.to_string(),
+        if entry.negative { "1" } else { "0" }.to_string(),
+        ttl_ms.to_string(),
+        entry.key.clone(),
+        counter,
+        expected_version.unwrap_or_default().to_string(),
+    ];
+    Ok((keys, args))
+}
+
+/// `entry` as stored under the `version` a [`STORE_SCRIPT`] call returned,
+/// or the reason the call refused to store it.
+fn stored(
+    mut entry: CacheEntry,
+    version: i64,
+    expected_version: Option<u64>,
+) -> Result<CacheEntry> {
+    match version {
+        -1 => Err(SyrosError::Conflict(format!(
+            "Cache key {} already exists",
+            entry.key
+        ))),
+        -2 => Err(SyrosError::NotFound(format!(
+            "Cache key {} not found",
+            entry.key
+        ))),
+        -3 => Err(version_mismatch(
+            &entry.key,
+            expected_version.unwrap_or_default(),
+        )),
+        version => {
+            entry.version = version as u64;
+            Ok(entry)
+        }
+    }
+}
+
+/// Cache entries in Redis.
+#[derive(Clone)]
+pub struct RedisCache {
+    redis: RedisManager,
+}
+
+impl RedisCache {
+    pub fn new(redis: RedisManager) -> Self {
+        Self { redis }
+    }
+
+    /// Removes the keys of expired or deleted entries from the tag sets;
+    /// returns how many were removed.
+    pub async fn prune_tags(&self) -> Result<u64> {
+        let mut conn = self.redis.get_connection().await?;
+        let mut tag_keys: Vec<String> = Vec::new();
+        {
+            let mut iter: redis::AsyncIter<String> = conn
+                .scan_match(format!("{}*", escape_glob(TAG_PREFIX)))
+                .await
+                .map_err(storage_error)?;
+            while let Some(tag_key) = iter.next_item().await {
+                tag_keys.push(tag_key);
+            }
+        }
+
+        let mut pruned = 0;
+        for tag_key in tag_keys {
+            let members: Vec<String> = conn.smembers(&tag_key).await.map_err(storage_error)?;
+            for member in members {
+                let exists: bool = conn
+                    .exists(entry_key(&member))
+                    .await
+                    .map_err(storage_error)?;
+                if !exists {
+                    let removed: u64 = conn.srem(&tag_key, &member).await.map_err(storage_error)?;
+                    pruned += removed;
+                }
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Stores `entry` as `mode` allows, or only over the live entry at
+    /// `expected_version` if set.
+    async fn store(
+        &self,
+        entry: CacheEntry,
+        mode: CacheSetMode,
+        expected_version: Option<u64>,
+    ) -> Result<CacheEntry> {
+        let mut conn = self.redis.get_connection().await?;
+        let (keys, args) = store_call(&entry, mode, expected_version)?;
+        let script = redis::Script::new(STORE_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        for key in &keys {
+            invocation.key(key);
+        }
+        for arg in &args {
+            invocation.arg(arg);
+        }
+        let version: i64 = invocation
+            .invoke_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+        stored(entry, version, expected_version)
+    }
+
+    /// Entries of `keys` in one round trip, `None` for those that are gone.
+    async fn fetch(
+        &self,
+        conn: &mut redis::aio::Connection,
+        keys: &[String],
+    ) -> Result<Vec<Option<CacheEntry>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.cmd("HGETALL").arg(entry_key(key));
+        }
+        let rows: Vec<HashMap<String, String>> =
+            pipe.query_async(conn).await.map_err(storage_error)?;
+
+        rows.into_iter().map(parse_entry).collect()
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>> {
+        let mut conn = self.redis.get_connection().await?;
+        let fields: HashMap<String, String> =
+            conn.hgetall(entry_key(key)).await.map_err(storage_error)?;
+        parse_entry(fields)
+    }
+
+    async fn set(&self, entry: CacheEntry, mode: CacheSetMode) -> Result<CacheEntry> {
+        self.store(entry, mode, None).await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        mut entry: CacheEntry,
+        expected_version: u64,
+    ) -> Result<CacheEntry> {
+        // The tags are carried over from the entry read here; the script
+        // refuses the write if it changed since.
+        let current = self
+            .get(&entry.key)
+            .await?
+            .filter(|current| !current.negative && current.version == expected_version)
+            .ok_or_else(|| version_mismatch(&entry.key, expected_version))?;
+        entry.tags = current.tags;
+        self.store(entry, CacheSetMode::UpdateOnly, Some(expected_version))
+            .await
+    }
+
+    async fn increment(&self, key: &str, delta: i64, ttl_if_new: Option<Duration>) -> Result<i64> {
+        let mut conn = self.redis.get_connection().await?;
+        let now = Utc::now();
+        let created = CacheEntry {
+            key: key.to_string(),
+            value: delta.into(),
+            expires_at: ttl_if_new.map(|ttl| expiry_after(now, ttl)),
+            tags: vec![],
+            created_at: now,
+            created_by: None,
+            negative: false,
+            version: 1,
+        };
+        let json =
+            serde_json::to_string(&created).map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        let ttl_ms = ttl_if_new.map_or(0, |ttl| ttl.as_millis() as u64);
+
+        // Add to the counter of a live entry, or create the entry with the
+        // delta when there is none; an entry without a counter holds a
+        // value that is not an integer.
+        let script = redis::Script::new(
+            r"
+            if redis.call('hget', KEYS[1], 'negative') == '0' then
+                if redis.call('hexists', KEYS[1], 'counter') == 0 then
+                    return {0, ''}
+                end
+                redis.call('hincrby', KEYS[1], 'version', 1)
+                return {1, tostring(redis.call('hincrby', KEYS[1], 'counter', ARGV[1]))}
+            end
+            redis.call('del', KEYS[1])
+            redis.call('hset', KEYS[1], 'entry', ARGV[2], 'version', 1, 'negative', '0', 'counter', ARGV[1])
+            if tonumber(ARGV[3]) > 0 then
+                redis.call('pexpire', KEYS[1], ARGV[3])
+            end
+            return {1, ARGV[1]}
+            ",
+        );
+
+        let (incremented, value): (i64, String) = script
+            .key(entry_key(key))
+            .arg(delta)
+            .arg(json)
+            .arg(ttl_ms)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("overflow") {
+                    increment_overflow(key)
+                } else {
+                    storage_error(e)
+                }
+            })?;
+        if incremented == 0 {
+            return Err(not_an_integer(key));
+        }
+        value
+            .parse()
+            .map_err(|_| SyrosError::StorageError(format!("Invalid counter {}", value)))
+    }
+
+    async fn touch(&self, key: &str, expires_at: Option<DateTime<Utc>>) -> Result<CacheEntry> {
+        let mut conn = self.redis.get_connection().await?;
+
+        // Rewrite the entry's JSON with the new expiry and reset the hash's
+        // TTL, unless it was written since it was read here; retry then.
+        let script = redis::Script::new(
+            r"
+            if redis.call('hget', KEYS[1], 'negative') ~= '0' then
+                return -2
+            end
+            if redis.call('hget', KEYS[1], 'version') ~= ARGV[2] then
+                return -3
+            end
+            redis.call('hset', KEYS[1], 'entry', ARGV[1])
+            if tonumber(ARGV[3]) > 0 then
+                redis.call('pexpire', KEYS[1], ARGV[3])
+            else
+                redis.call('persist', KEYS[1])
+            end
+            return 1
+            ",
+        );
+        loop {
+            let fields: HashMap<String, String> =
+                conn.hgetall(entry_key(key)).await.map_err(storage_error)?;
+            let mut entry = parse_entry(fields)?
+                .filter(|entry| !entry.negative)
+                .ok_or_else(|| key_not_found(key))?;
+            entry.expires_at = expires_at;
+            let json = serde_json::to_string(&entry)
+                .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+            let ttl_ms = expires_at
+                .map(|expires_at| (expires_at - Utc::now()).num_milliseconds().max(1))
+                .unwrap_or(0);
+
+            let outcome: i64 = script
+                .key(entry_key(key))
+                .arg(json)
+                .arg(entry.version)
+                .arg(ttl_ms)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(storage_error)?;
+            match outcome {
+                -2 => return Err(key_not_found(key)),
+                -3 => continue,
+                _ => return Ok(entry),
+            }
+        }
+    }
+
+    async fn retag(&self, key: &str, add: &[String], remove: &[String]) -> Result<CacheEntry> {
+        let mut conn = self.redis.get_connection().await?;
+
+        // Rewrite the entry's JSON with its new tags and move the key between
+        // the tag sets, unless it was written since it was read here; retry
+        // then. The hash keeps its TTL.
+        let script = redis::Script::new(
+            r"
+            if redis.call('hget', KEYS[1], 'negative') ~= '0' then
+                return -2
+            end
+            if redis.call('hget', KEYS[1], 'version') ~= ARGV[2] then
+                return -3
+            end
+            redis.call('hset', KEYS[1], 'entry', ARGV[1])
+            for i = 2, #KEYS do
+                if i - 1 <= tonumber(ARGV[4]) then
+                    redis.call('sadd', KEYS[i], ARGV[3])
+                else
+                    redis.call('srem', KEYS[i], ARGV[3])
+                end
+            end
+            return 1
+            ",
+        );
+        let added: Vec<&String> = add.iter().filter(|tag| !remove.contains(tag)).collect();
+        loop {
+            let fields: HashMap<String, String> =
+                conn.hgetall(entry_key(key)).await.map_err(storage_error)?;
+            let mut entry = parse_entry(fields)?
+                .filter(|entry| !entry.negative)
+                .ok_or_else(|| key_not_found(key))?;
+            entry.tags = retagged(&entry.tags, add, remove);
+            let json = serde_json::to_string(&entry)
+                .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+
+            let mut invocation = script.key(entry_key(key));
+            for tag in added.iter().copied().chain(remove) {
+                invocation.key(tag_key(tag));
+            }
+            let outcome: i64 = invocation
+                .arg(json)
+                .arg(entry.version)
+                .arg(key)
+                .arg(added.len())
+                .invoke_async(&mut conn)
+                .await
+                .map_err(storage_error)?;
+            match outcome {
+                -2 => return Err(key_not_found(key)),
+                -3 => continue,
+                _ => return Ok(entry),
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        let mut conn = self.redis.get_connection().await?;
+        let json: Option<String> = conn
+            .hget(entry_key(key), "entry")
+            .await
+            .map_err(storage_error)?;
+        let deleted: u64 = conn.del(entry_key(key)).await.map_err(storage_error)?;
+
+        if let Some(entry) = json.and_then(|json| serde_json::from_str::<CacheEntry>(&json).ok()) {
+            for tag in &entry.tags {
+                let _: u64 = conn.srem(tag_key(tag), key).await.map_err(storage_error)?;
+            }
+        }
+        Ok(deleted > 0)
+    }
+
+    async fn invalidate_by_tag(&self, tag: &str) -> Result<u64> {
+        let mut conn = self.redis.get_connection().await?;
+
+        // Delete the members that still carry the tag, and drop every member
+        // read from the set; keys tagged meanwhile stay in it.
+        let script = redis::Script::new(
+            r"
+            local count = 0
+            for _, key in ipairs(redis.call('smembers', KEYS[1])) do
+                local json = redis.call('hget', ARGV[2] .. key, 'entry')
+                if json then
+                    for _, tag in ipairs(cjson.decode(json)['tags']) do
+                        if tag == ARGV[1] then
+                            count = count + redis.call('del', ARGV[2] .. key)
+                            break
+                        end
+                    end
+                end
+                redis.call('srem', KEYS[1], key)
+            end
+            return count
+            ",
+        );
+
+        script
+            .key(tag_key(tag))
+            .arg(tag)
+            .arg(ENTRY_PREFIX)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn list(
+        &self,
+        pattern: Option<&str>,
+        tags: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<CacheEntry>> {
+        let mut conn = self.redis.get_connection().await?;
+
+        // Narrow the candidates by the tag sets, or else scan by the
+        // pattern's literal prefix; the filter checks the rest.
+        let keys: Vec<String> = if tags.is_empty() {
+            let prefix = pattern.map(glob_prefix).unwrap_or("");
+            let mut keys = Vec::new();
+            let mut iter: redis::AsyncIter<String> = conn
+                .scan_match(format!("{}*", entry_key(&escape_glob(prefix))))
+                .await
+                .map_err(storage_error)?;
+            while let Some(redis_key) = iter.next_item().await {
+                if let Some(key) = redis_key.strip_prefix(ENTRY_PREFIX) {
+                    keys.push(key.to_string());
+                }
+            }
+            keys
+        } else {
+            let tag_keys: Vec<String> = tags.iter().map(|tag| tag_key(tag)).collect();
+            redis::cmd("SINTER")
+                .arg(&tag_keys)
+                .query_async(&mut conn)
+                .await
+                .map_err(storage_error)?
+        };
+
+        let mut entries: Vec<CacheEntry> = self
+            .fetch(&mut conn, &keys)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter(|entry| !entry.negative)
+            .filter(|entry| pattern.is_none_or(|pattern| key_matches(pattern, &entry.key)))
+            .filter(|entry| tags.iter().all(|tag| entry.tags.contains(tag)))
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries.truncate(limit.unwrap_or(usize::MAX));
+        Ok(entries)
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<Option<CacheEntry>>> {
+        let mut conn = self.redis.get_connection().await?;
+        self.fetch(&mut conn, keys).await
+    }
+
+    /// Stores the entries in one round trip, in order.
+    async fn set_many(
+        &self,
+        entries: Vec<(CacheEntry, CacheSetMode)>,
+    ) -> Result<Vec<Result<CacheEntry>>> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.redis.get_connection().await?;
+        let script = redis::Script::new(STORE_SCRIPT);
+        let mut pipe = redis::pipe();
+        // Loaded first, so the calls below find it by its hash.
+        pipe.cmd("SCRIPT").arg("LOAD").arg(STORE_SCRIPT).ignore();
+        for (entry, mode) in &entries {
+            let (keys, args) = store_call(entry, *mode, None)?;
+            pipe.cmd("EVALSHA")
+                .arg(script.get_hash())
+                .arg(keys.len())
+                .arg(keys)
+                .arg(args);
+        }
+        let versions: Vec<i64> = pipe.query_async(&mut conn).await.map_err(storage_error)?;
+
+        Ok(entries
+            .into_iter()
+            .zip(versions)
+            .map(|((entry, _), version)| stored(entry, version, None))
+            .collect())
+    }
+
+    /// Removes the entries in one round trip, then their keys from their
+    /// tag sets in another.
+    async fn delete_many(&self, keys: &[String]) -> Result<Vec<bool>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.redis.get_connection().await?;
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.hget(entry_key(key), "entry").del(entry_key(key));
+        }
+        let removed: Vec<(Option<String>, u64)> =
+            pipe.query_async(&mut conn).await.map_err(storage_error)?;
+
+        let mut untag = redis::pipe();
+        let mut tagged = false;
+        for (key, (json, _)) in keys.iter().zip(&removed) {
+            let Some(entry) = json
+                .as_deref()
+                .and_then(|json| serde_json::from_str::<CacheEntry>(json).ok())
+            else {
+                continue;
+            };
+            for tag in &entry.tags {
+                untag.srem(tag_key(tag), key).ignore();
+                tagged = true;
+            }
+        }
+        if tagged {
+            let () = untag.query_async(&mut conn).await.map_err(storage_error)?;
+        }
+        Ok(removed
+            .into_iter()
+            .map(|(_, deleted)| deleted > 0)
+            .collect())
+    }
+
+    /// Entries Redis holds; it drops expired ones itself, so none are
+    /// counted as expired.
+    async fn stats(&self) -> Result<CacheStats> {
+        let mut conn = self.redis.get_connection().await?;
+        let mut keys = Vec::new();
+        {
+            let mut iter: redis::AsyncIter<String> = conn
+                .scan_match(format!("{}*", escape_glob(ENTRY_PREFIX)))
+                .await
+                .map_err(storage_error)?;
+            while let Some(redis_key) = iter.next_item().await {
+                if let Some(key) = redis_key.strip_prefix(ENTRY_PREFIX) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+
+        let mut stats = CacheStats::default();
+        for chunk in keys.chunks(STATS_BATCH) {
+            for entry in self.fetch(&mut conn, chunk).await?.into_iter().flatten() {
+                stats.total_entries += 1;
+                stats.active_entries += 1;
+                stats.add_live(&entry);
+            }
+        }
+        Ok(stats)
+    }
+}
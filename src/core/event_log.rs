@@ -0,0 +1,1249 @@
+//! In-memory, version-indexed event log.
+//!
+//! Each stream keeps its events in version order as individually shared
+//! `Arc<Event>`s, so range reads locate their start with a binary search and
+//! only copy the events they return.
+//!
+//! A stream's version survives the removal of its oldest events, so reads
+//! can tell which versions are no longer retained. Archived streams keep
+//! only a compact [`StreamInfo`] record; soft-deleted streams keep their
+//! events but reject appends, and hard-deleted streams leave nothing
+//! behind. A stream's latest [`Snapshot`] is kept with its events and goes
+//! when they do.
+//!
+//! Streams with a [`RetentionPolicy`] lose the events outside it each time
+//! retention is enforced.
+//!
+//! Every stored event also takes the next position of the global feed of
+//! all streams, indexed by position so the feed can be read from a cursor.
+//!
+//! Streams remember the client-chosen IDs of their latest events, up to a
+//! dedup window, so retried appends return the event already stored. Event
+//! IDs are unique across the retained events of all streams.
+
+use crate::core::event_persistence::Appended;
+use crate::core::event_store::{
+    check_expected_version, check_snapshot, Event, EventFilter, ReadDirection, RetentionPolicy,
+    Snapshot, StreamInfo, CREATED_BY_METADATA_KEY, DEFAULT_DEDUP_WINDOW,
+};
+use crate::core::memory::{entry_size, serialized_size, ENTRY_OVERHEAD_BYTES};
+use crate::{Result, SyrosError};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A stream's retained events and the bookkeeping that outlives them.
+struct Stream {
+    events: Vec<Arc<Event>>,
+    /// Version of the latest event, kept when older events are removed
+    version: i64,
+    created_by: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    snapshot: Option<Arc<Snapshot>>,
+    recent_ids: RecentIds,
+    /// When the stream was soft-deleted
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Client-chosen IDs of a stream's latest events and where each was stored.
+#[derive(Default)]
+struct RecentIds {
+    /// Versions and IDs, oldest first
+    order: VecDeque<(i64, String)>,
+    stored: HashMap<String, Appended>,
+}
+
+impl RecentIds {
+    /// Where the event appended under `id` was stored, if it is one of the
+    /// `window` events up to `version`.
+    fn get(&self, id: &str, version: i64, window: usize) -> Option<Appended> {
+        self.stored
+            .get(id)
+            .filter(|appended| version - appended.version < window as i64)
+            .copied()
+    }
+
+    /// Remembers `id` for a newly stored event, forgetting the IDs that fell
+    /// out of the `window` events up to it.
+    fn remember(&mut self, id: String, appended: Appended, window: usize) {
+        while let Some((version, _)) = self.order.front() {
+            if appended.version - version < window as i64 {
+                break;
+            }
+            if let Some((version, id)) = self.order.pop_front() {
+                // The ID may have been reused since, outside the window.
+                if self.stored.get(&id).is_some_and(|a| a.version == version) {
+                    self.stored.remove(&id);
+                }
+            }
+        }
+        if window > 0 {
+            self.order.push_back((appended.version, id.clone()));
+            self.stored.insert(id, appended);
+        }
+    }
+
+    fn estimated_bytes(&self) -> usize {
+        self.order
+            .iter()
+            .map(|(_, id)| 2 * (id.len() + ENTRY_OVERHEAD_BYTES))
+            .sum()
+    }
+}
+
+impl Stream {
+    fn new(first: &Event) -> Self {
+        Self {
+            events: Vec::new(),
+            version: 0,
+            created_by: first.metadata.get(CREATED_BY_METADATA_KEY).cloned(),
+            created_at: first.timestamp,
+            updated_at: first.timestamp,
+            snapshot: None,
+            recent_ids: RecentIds::default(),
+            deleted_at: None,
+        }
+    }
+
+    fn push(&mut self, event: Event) -> Arc<Event> {
+        self.version = event.version;
+        self.updated_at = event.timestamp;
+        let event = Arc::new(event);
+        self.events.push(event.clone());
+        event
+    }
+
+    /// Oldest version still held; one past `version` when none are.
+    fn retained_from(&self) -> i64 {
+        self.version - self.events.len() as i64 + 1
+    }
+
+    fn info(&self, stream_id: &str) -> StreamInfo {
+        StreamInfo {
+            stream_id: stream_id.to_string(),
+            version: self.version,
+            event_count: self.events.len(),
+            created_by: self.created_by.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            archived_at: None,
+            deleted_at: self.deleted_at,
+        }
+    }
+
+    /// Number of the oldest events outside `policy` at `now`; never the
+    /// latest event, nor one newer than the latest snapshot.
+    fn expired(&self, policy: &RetentionPolicy, now: DateTime<Utc>) -> usize {
+        let over_count = policy
+            .max_events
+            .map_or(0, |max| self.events.len().saturating_sub(max));
+        let too_old = policy.cutoff(now).map_or(0, |cutoff| {
+            self.events
+                .partition_point(|event| event.timestamp < cutoff)
+        });
+        let covered = self.snapshot.as_ref().map_or(usize::MAX, |snapshot| {
+            (snapshot.version - self.retained_from() + 1).max(0) as usize
+        });
+        over_count
+            .max(too_old)
+            .min(covered)
+            .min(self.events.len().saturating_sub(1))
+    }
+}
+
+#[derive(Default)]
+struct Directory {
+    streams: HashMap<String, Stream>,
+    /// Compact records of archived streams, whose events were dropped
+    archived: HashMap<String, StreamInfo>,
+    /// Retained events of all streams by global position
+    feed: BTreeMap<i64, Arc<Event>>,
+    /// IDs of the retained events of all streams
+    ids: HashSet<String>,
+    /// Retention policies by stream ID, including streams without events yet
+    retention: HashMap<String, RetentionPolicy>,
+}
+
+impl Directory {
+    /// Fails with `StreamDeleted` if `stream_id` was soft-deleted.
+    fn reject_deleted(&self, stream_id: &str) -> Result<()> {
+        let deleted = match self.streams.get(stream_id) {
+            Some(stream) => stream.deleted_at.is_some(),
+            None => self
+                .archived
+                .get(stream_id)
+                .is_some_and(|info| info.deleted_at.is_some()),
+        };
+        if deleted {
+            return Err(SyrosError::StreamDeleted(format!(
+                "Stream {} was deleted",
+                stream_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// The stream an event for `stream_id` is stored in, created if needed.
+    fn writable(&mut self, event: &Event) -> Result<&mut Stream> {
+        self.reject_deleted(&event.stream_id)?;
+        if self.archived.contains_key(&event.stream_id) {
+            return Err(SyrosError::Conflict(format!(
+                "Stream {} is archived",
+                event.stream_id
+            )));
+        }
+        Ok(self
+            .streams
+            .entry(event.stream_id.clone())
+            .or_insert_with(|| Stream::new(event)))
+    }
+
+    /// Stores an event at the end of its stream and of the feed, under the
+    /// next position taken from `positions`.
+    ///
+    /// Fails with `Conflict` if a retained event of any stream has its ID.
+    fn store(&mut self, mut event: Event, positions: &AtomicU64) -> Result<Arc<Event>> {
+        if self.ids.contains(&event.id) {
+            return Err(SyrosError::Conflict(format!(
+                "Event ID {} is already used by an older or another stream's event",
+                event.id
+            )));
+        }
+        let stream = self.writable(&event)?;
+        event.position = positions.fetch_add(1, Ordering::Relaxed) as i64 + 1;
+        let event = stream.push(event);
+        self.feed.insert(event.position, event.clone());
+        self.ids.insert(event.id.clone());
+        Ok(event)
+    }
+
+    /// Appends an event under the next version of its stream, if the stream
+    /// is at the `expected` version.
+    fn append(
+        &mut self,
+        mut event: Event,
+        expected: Option<u64>,
+        positions: &AtomicU64,
+    ) -> Result<Arc<Event>> {
+        // A closed stream fails below instead.
+        self.reject_deleted(&event.stream_id)?;
+        if !self.archived.contains_key(&event.stream_id) {
+            let current = self
+                .streams
+                .get(&event.stream_id)
+                .map_or(0, |stream| stream.version);
+            check_expected_version(expected, current)?;
+        }
+        event.version = self.writable(&event)?.version + 1;
+        self.store(event, positions)
+    }
+
+    /// Drops removed events from the feed and frees their IDs.
+    fn unfeed<'a>(&mut self, events: impl IntoIterator<Item = &'a Arc<Event>>) {
+        for event in events {
+            self.feed.remove(&event.position);
+            self.ids.remove(&event.id);
+        }
+    }
+
+    /// Removes everything kept for a stream; returns whether it existed.
+    fn remove(&mut self, stream_id: &str) -> bool {
+        self.retention.remove(stream_id);
+        let removed = self.streams.remove(stream_id);
+        if let Some(stream) = &removed {
+            self.unfeed(&stream.events);
+        }
+        self.archived.remove(stream_id).is_some() || removed.is_some()
+    }
+}
+
+/// Number of streams in a [`MemoryEventLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectorySize {
+    pub active: usize,
+    pub archived: usize,
+}
+
+/// Event streams held in memory.
+#[derive(Clone)]
+pub struct MemoryEventLog {
+    directory: Arc<RwLock<Directory>>,
+    /// Last global position taken; only advanced under the directory's
+    /// write lock, so positions follow the order events are stored in
+    positions: Arc<AtomicU64>,
+    /// Latest events of a stream whose client-chosen IDs are remembered
+    dedup_window: usize,
+}
+
+impl Default for MemoryEventLog {
+    fn default() -> Self {
+        Self {
+            directory: Arc::default(),
+            positions: Arc::default(),
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+        }
+    }
+}
+
+impl MemoryEventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deduplicates appends against the stream's latest `window` events.
+    pub fn with_dedup_window(mut self, window: usize) -> Self {
+        self.dedup_window = window;
+        self
+    }
+
+    /// Appends an event, assigning it the next version of its stream.
+    ///
+    /// The version on `event` is ignored; the stored event is returned.
+    /// Fails with `Conflict` if the stream is archived and with
+    /// `StreamDeleted` if it was deleted.
+    pub async fn append(&self, event: Event) -> Result<Arc<Event>> {
+        self.append_expecting(event, None).await
+    }
+
+    /// Appends an event as [`append`](Self::append) does, if its stream is
+    /// at the `expected` version.
+    ///
+    /// Fails with `VersionConflict` if the stream is at another version.
+    pub async fn append_expecting(
+        &self,
+        event: Event,
+        expected: Option<u64>,
+    ) -> Result<Arc<Event>> {
+        let mut directory = self.directory.write().await;
+        directory.append(event, expected, &self.positions)
+    }
+
+    /// Appends an event under its client-chosen ID as
+    /// [`append_expecting`](Self::append_expecting) does, unless one of the
+    /// stream's latest events within the dedup window has the same ID.
+    ///
+    /// That event's version and position are then returned as a duplicate,
+    /// whatever the `expected` version, and nothing is stored. Fails with
+    /// `Conflict` if another retained event, of this or another stream,
+    /// has the ID.
+    pub async fn append_once(&self, event: Event, expected: Option<u64>) -> Result<Appended> {
+        let mut directory = self.directory.write().await;
+        if let Some(stream) = directory.streams.get(&event.stream_id) {
+            if let Some(original) =
+                stream
+                    .recent_ids
+                    .get(&event.id, stream.version, self.dedup_window)
+            {
+                return Ok(Appended {
+                    duplicate: true,
+                    ..original
+                });
+            }
+        }
+
+        let id = event.id.clone();
+        let stored = directory.append(event, expected, &self.positions)?;
+        let appended = Appended {
+            version: stored.version,
+            position: stored.position,
+            duplicate: false,
+        };
+        if let Some(stream) = directory.streams.get_mut(&stored.stream_id) {
+            stream.recent_ids.remember(id, appended, self.dedup_window);
+        }
+        Ok(appended)
+    }
+
+    /// Stores an event under its own version.
+    ///
+    /// Fails with `Conflict` unless the version directly follows the stream's
+    /// current version and the stream is not archived.
+    pub async fn insert(&self, event: Event) -> Result<Arc<Event>> {
+        let mut directory = self.directory.write().await;
+        let current = match directory.streams.get(&event.stream_id) {
+            Some(stream) => stream.version,
+            None => directory
+                .archived
+                .get(&event.stream_id)
+                .map_or(0, |info| info.version),
+        };
+        if event.version != current + 1 {
+            return Err(SyrosError::Conflict(format!(
+                "Event {} does not follow the current version of stream {}",
+                event.version, event.stream_id
+            )));
+        }
+
+        directory.store(event, &self.positions)
+    }
+
+    /// Reads events with `from <= version <= to`, oldest first, up to `limit`.
+    ///
+    /// Only the returned events are cloned out of the log, as shared pointers.
+    pub async fn read(
+        &self,
+        stream_id: &str,
+        from_version: Option<i64>,
+        to_version: Option<i64>,
+        limit: Option<usize>,
+    ) -> Vec<Arc<Event>> {
+        self.read_filtered(
+            stream_id,
+            from_version,
+            to_version,
+            &EventFilter::default(),
+            ReadDirection::Forward,
+            limit,
+        )
+        .await
+    }
+
+    /// Reads the events with `from <= version <= to` that match `filter`, in
+    /// `direction`, up to `limit` matching events.
+    pub async fn read_filtered(
+        &self,
+        stream_id: &str,
+        from_version: Option<i64>,
+        to_version: Option<i64>,
+        filter: &EventFilter,
+        direction: ReadDirection,
+        limit: Option<usize>,
+    ) -> Vec<Arc<Event>> {
+        let directory = self.directory.read().await;
+        let Some(stream) = directory.streams.get(stream_id) else {
+            return Vec::new();
+        };
+        let events = &stream.events;
+
+        let start = from_version.map_or(0, |from| events.partition_point(|e| e.version < from));
+        let end = to_version.map_or(events.len(), |to| {
+            events.partition_point(|e| e.version <= to)
+        });
+        if start >= end {
+            return Vec::new();
+        }
+
+        let range = events[start..end].iter();
+        let matching = |event: &&Arc<Event>| filter.matches(event);
+        let limit = limit.unwrap_or(usize::MAX);
+        match direction {
+            ReadDirection::Forward => range.filter(matching).take(limit).cloned().collect(),
+            ReadDirection::Backward => range.rev().filter(matching).take(limit).cloned().collect(),
+        }
+    }
+
+    /// Current version of a stream, or 0 if it has no events.
+    ///
+    /// Archived streams and streams whose events were all removed keep the
+    /// version of their latest event.
+    pub async fn version(&self, stream_id: &str) -> i64 {
+        let directory = self.directory.read().await;
+        match directory.streams.get(stream_id) {
+            Some(stream) => stream.version,
+            None => directory
+                .archived
+                .get(stream_id)
+                .map_or(0, |info| info.version),
+        }
+    }
+
+    /// Number of events retained for a stream.
+    pub async fn len(&self, stream_id: &str) -> usize {
+        self.directory
+            .read()
+            .await
+            .streams
+            .get(stream_id)
+            .map_or(0, |stream| stream.events.len())
+    }
+
+    /// Oldest version of a stream that can still be read, or `None` for an
+    /// unknown stream.
+    ///
+    /// Versions below it were removed by [`truncate_front`](Self::truncate_front)
+    /// or archival; it is one past the stream's version when no events are
+    /// retained.
+    pub async fn retained_from(&self, stream_id: &str) -> Option<i64> {
+        let directory = self.directory.read().await;
+        match directory.streams.get(stream_id) {
+            Some(stream) => Some(stream.retained_from()),
+            None => directory
+                .archived
+                .get(stream_id)
+                .map(|info| info.version + 1),
+        }
+    }
+
+    /// Version, size and creator of a stream, archived or not.
+    pub async fn info(&self, stream_id: &str) -> Option<StreamInfo> {
+        let directory = self.directory.read().await;
+        match directory.streams.get(stream_id) {
+            Some(stream) => Some(stream.info(stream_id)),
+            None => directory.archived.get(stream_id).cloned(),
+        }
+    }
+
+    /// Replaces the latest snapshot of a stream.
+    ///
+    /// Fails with `NotFound` for unknown streams, with `Conflict` for
+    /// archived ones, with `StreamDeleted` for deleted ones, and as
+    /// [`check_snapshot`] does for versions the snapshot cannot be taken at.
+    pub async fn save_snapshot(&self, snapshot: Snapshot) -> Result<()> {
+        let mut directory = self.directory.write().await;
+        directory.reject_deleted(&snapshot.stream_id)?;
+        if directory.archived.contains_key(&snapshot.stream_id) {
+            return Err(SyrosError::Conflict(format!(
+                "Stream {} is archived",
+                snapshot.stream_id
+            )));
+        }
+        let Some(stream) = directory.streams.get_mut(&snapshot.stream_id) else {
+            return Err(SyrosError::NotFound(format!(
+                "Stream {} not found",
+                snapshot.stream_id
+            )));
+        };
+        check_snapshot(
+            &snapshot,
+            stream.version,
+            stream.snapshot.as_ref().map(|latest| latest.version),
+        )?;
+        stream.snapshot = Some(Arc::new(snapshot));
+        Ok(())
+    }
+
+    /// Latest snapshot of a stream, if one was saved.
+    pub async fn snapshot(&self, stream_id: &str) -> Option<Arc<Snapshot>> {
+        self.directory
+            .read()
+            .await
+            .streams
+            .get(stream_id)
+            .and_then(|stream| stream.snapshot.clone())
+    }
+
+    /// Streams, archived or not, whose ID starts with `prefix`, in the order
+    /// they were created, skipping `offset` and returning at most `limit`.
+    pub async fn list(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Vec<StreamInfo> {
+        let directory = self.directory.read().await;
+        let selected = |stream_id: &String| prefix.is_none_or(|p| stream_id.starts_with(p));
+        let mut streams: Vec<StreamInfo> = directory
+            .streams
+            .iter()
+            .filter(|(stream_id, _)| selected(stream_id))
+            .map(|(stream_id, stream)| stream.info(stream_id))
+            .chain(
+                directory
+                    .archived
+                    .iter()
+                    .filter(|(stream_id, _)| selected(stream_id))
+                    .map(|(_, info)| info.clone()),
+            )
+            .collect();
+        drop(directory);
+
+        streams.sort_by(|a, b| (a.created_at, &a.stream_id).cmp(&(b.created_at, &b.stream_id)));
+        streams
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    /// Drops all but the newest `keep_last` events of a stream.
+    ///
+    /// Versions are not renumbered and the stream keeps its version, so later
+    /// appends continue after it. Returns the number of events removed.
+    pub async fn truncate_front(&self, stream_id: &str, keep_last: usize) -> u64 {
+        let mut directory = self.directory.write().await;
+        let Some(stream) = directory.streams.get_mut(stream_id) else {
+            return 0;
+        };
+
+        let removed = stream.events.len().saturating_sub(keep_last);
+        let dropped: Vec<Arc<Event>> = stream.events.drain(..removed).collect();
+        directory.unfeed(&dropped);
+        removed as u64
+    }
+
+    /// Drops a stream's events and replaces them with a compact record of
+    /// the stream, marked as archived at `now`.
+    ///
+    /// Archived streams reject appends. Returns the record, or `None` for an
+    /// unknown stream; archiving twice returns the existing record.
+    pub async fn archive(&self, stream_id: &str, now: DateTime<Utc>) -> Option<StreamInfo> {
+        let mut directory = self.directory.write().await;
+        let Some(stream) = directory.streams.remove(stream_id) else {
+            return directory.archived.get(stream_id).cloned();
+        };
+        directory.unfeed(&stream.events);
+
+        let mut info = stream.info(stream_id);
+        info.archived_at = Some(now);
+        directory
+            .archived
+            .insert(stream_id.to_string(), info.clone());
+        Some(info)
+    }
+
+    /// Removes a stream, archived or not, including its version and
+    /// retention policy; a stream later appended under the same ID starts
+    /// again at version 1.
+    ///
+    /// Returns whether the stream existed.
+    pub async fn remove(&self, stream_id: &str) -> bool {
+        self.directory.write().await.remove(stream_id)
+    }
+
+    /// Soft-deletes a stream at `now`, or removes it as
+    /// [`remove`](Self::remove) does with `hard`, if it is at the `expected`
+    /// version.
+    ///
+    /// A soft-deleted stream keeps its events and snapshot, and deleting it
+    /// again keeps its first deletion time. Returns whether the stream
+    /// existed; fails with `VersionConflict` if it is at another version.
+    pub async fn delete(
+        &self,
+        stream_id: &str,
+        expected: Option<u64>,
+        hard: bool,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let mut directory = self.directory.write().await;
+        let directory = &mut *directory;
+        let (current, deleted_at) = match directory.streams.get_mut(stream_id) {
+            Some(stream) => (stream.version, &mut stream.deleted_at),
+            None => match directory.archived.get_mut(stream_id) {
+                Some(info) => (info.version, &mut info.deleted_at),
+                None => return Ok(false),
+            },
+        };
+        check_expected_version(expected, current)?;
+        if hard {
+            directory.remove(stream_id);
+        } else {
+            deleted_at.get_or_insert(now);
+        }
+        Ok(true)
+    }
+
+    /// Replaces the retention policy of a stream, whether or not it has
+    /// events yet; an unbounded policy clears it.
+    pub async fn set_retention(&self, stream_id: &str, policy: RetentionPolicy) {
+        let mut directory = self.directory.write().await;
+        if policy.is_unbounded() {
+            directory.retention.remove(stream_id);
+        } else {
+            directory.retention.insert(stream_id.to_string(), policy);
+        }
+    }
+
+    /// Drops the oldest events outside their stream's retention policy at
+    /// `now`, always keeping the latest one and those newer than the
+    /// stream's latest snapshot, and returns how many.
+    ///
+    /// Versions are not renumbered, as with [`truncate_front`](Self::truncate_front).
+    pub async fn enforce_retention(&self, now: DateTime<Utc>) -> u64 {
+        let mut directory = self.directory.write().await;
+        let directory = &mut *directory;
+        let mut dropped = Vec::new();
+        for (stream_id, policy) in &directory.retention {
+            if let Some(stream) = directory.streams.get_mut(stream_id) {
+                let expired = stream.expired(policy, now);
+                dropped.extend(stream.events.drain(..expired));
+            }
+        }
+        directory.unfeed(&dropped);
+        dropped.len() as u64
+    }
+
+    /// Retained events of all streams from global position `from` on, in
+    /// the order they were stored, up to `limit`.
+    pub async fn read_all(&self, from: i64, limit: usize) -> Vec<Arc<Event>> {
+        self.directory
+            .read()
+            .await
+            .feed
+            .range(from..)
+            .take(limit)
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    /// Estimated bytes held by the retained events, their snapshots, the
+    /// remembered event IDs and the archived stream records.
+    pub async fn estimated_bytes(&self) -> u64 {
+        let directory = self.directory.read().await;
+        let streams: usize = directory
+            .streams
+            .iter()
+            .map(|(stream_id, stream)| {
+                let events: usize = stream
+                    .events
+                    .iter()
+                    .map(|event| serialized_size(event.as_ref()) + ENTRY_OVERHEAD_BYTES)
+                    .sum();
+                let snapshot = stream
+                    .snapshot
+                    .as_ref()
+                    .map_or(0, |snapshot| serialized_size(snapshot.as_ref()));
+                stream_id.len()
+                    + ENTRY_OVERHEAD_BYTES
+                    + events
+                    + snapshot
+                    + stream.recent_ids.estimated_bytes()
+            })
+            .sum();
+        let archived: usize = directory
+            .archived
+            .iter()
+            .map(|(stream_id, info)| entry_size(stream_id, info))
+            .sum();
+        let ids: usize = directory
+            .ids
+            .iter()
+            .map(|id| id.len() + ENTRY_OVERHEAD_BYTES)
+            .sum();
+        (streams + archived + ids) as u64
+    }
+
+    /// Number of active and archived streams.
+    pub async fn directory_size(&self) -> DirectorySize {
+        let directory = self.directory.read().await;
+        DirectorySize {
+            active: directory.streams.len(),
+            archived: directory.archived.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    /// Fixed so a failing random range can be replayed.
+    const RANGE_SEED: u64 = 42;
+
+    fn event(stream_id: &str, n: usize) -> Event {
+        Event {
+            id: format!("{}-{}", stream_id, n),
+            stream_id: stream_id.to_string(),
+            event_type: ["even", "odd"][n % 2].to_string(),
+            data: serde_json::json!({ "n": n }),
+            metadata: HashMap::new(),
+            timestamp: Utc::now(),
+            version: 0,
+            position: 0,
+        }
+    }
+
+    /// Reference implementation: scan the whole stream, cloning the matches.
+    fn read_by_clone(
+        stream: &[Event],
+        from_version: Option<i64>,
+        to_version: Option<i64>,
+        limit: Option<usize>,
+    ) -> Vec<Event> {
+        let mut events: Vec<Event> = stream
+            .iter()
+            .filter(|e| from_version.is_none_or(|from| e.version >= from))
+            .filter(|e| to_version.is_none_or(|to| e.version <= to))
+            .cloned()
+            .collect();
+        if let Some(limit) = limit {
+            events.truncate(limit);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn test_append_assigns_versions() {
+        let log = MemoryEventLog::new();
+        for n in 0..3 {
+            log.append(event("orders", n)).await.unwrap();
+        }
+        log.append(event("payments", 0)).await.unwrap();
+
+        assert_eq!(log.version("orders").await, 3);
+        assert_eq!(log.version("payments").await, 1);
+        assert_eq!(log.version("missing").await, 0);
+
+        let tail = log.read("orders", Some(3), None, None).await;
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].data["n"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_only_one_append_at_the_expected_version_wins() {
+        let log = MemoryEventLog::new();
+        let (first, second) = tokio::join!(
+            log.append_expecting(event("orders", 0), Some(0)),
+            log.append_expecting(event("orders", 1), Some(0)),
+        );
+        let (won, lost) = if first.is_ok() {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        assert_eq!(won.unwrap().version, 1);
+        assert!(matches!(
+            lost,
+            Err(SyrosError::VersionConflict {
+                expected: 0,
+                actual: 1
+            })
+        ));
+
+        let next = log
+            .append_expecting(event("orders", 2), Some(1))
+            .await
+            .unwrap();
+        assert_eq!(next.version, 2);
+        assert_eq!(log.version("orders").await, 2);
+
+        log.archive("orders", Utc::now()).await.unwrap();
+        assert!(matches!(
+            log.append_expecting(event("orders", 3), Some(2)).await,
+            Err(SyrosError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_random_ranges_match_clone_and_filter() {
+        let log = MemoryEventLog::new();
+        let mut stored = Vec::new();
+        for n in 0..500 {
+            stored.push((*log.append(event("stream", n)).await.unwrap()).clone());
+        }
+        // Drain the front so versions no longer start at 1.
+        assert_eq!(log.truncate_front("stream", 400).await, 100);
+        stored.drain(..100);
+
+        let mut rng = fastrand::Rng::with_seed(RANGE_SEED);
+        let mut bound = || match rng.u8(0..4) {
+            0 => None,
+            _ => Some(rng.i64(-10..520)),
+        };
+        for _ in 0..2_000 {
+            let from = bound();
+            let to = bound();
+            let limit = bound().map(|l| l.unsigned_abs() as usize);
+
+            let expected = read_by_clone(&stored, from, to, limit);
+            let actual: Vec<Event> = log
+                .read("stream", from, to, limit)
+                .await
+                .iter()
+                .map(|e| (**e).clone())
+                .collect();
+
+            let versions = |events: &[Event]| events.iter().map(|e| e.version).collect::<Vec<_>>();
+            assert_eq!(
+                versions(&actual),
+                versions(&expected),
+                "from={:?} to={:?} limit={:?}",
+                from,
+                to,
+                limit
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filters_apply_before_the_limit() {
+        let log = MemoryEventLog::new();
+        for n in 0..10 {
+            let mut event = event("orders", n);
+            event
+                .metadata
+                .insert("region".to_string(), ["eu", "us"][n / 5].to_string());
+            log.append(event).await.unwrap();
+        }
+        let versions = |events: Vec<Arc<Event>>| -> Vec<i64> {
+            events.iter().map(|event| event.version).collect()
+        };
+        let odd_in_eu = EventFilter {
+            event_types: Some(vec!["odd".to_string()]),
+            metadata: HashMap::from([("region".to_string(), "eu".to_string())]),
+        };
+
+        // Version n + 1 holds n; odd n below 5 are at versions 2 and 4
+        let forward = log
+            .read_filtered(
+                "orders",
+                None,
+                None,
+                &odd_in_eu,
+                ReadDirection::Forward,
+                Some(1),
+            )
+            .await;
+        assert_eq!(versions(forward), [2]);
+        let backward = log
+            .read_filtered(
+                "orders",
+                None,
+                None,
+                &odd_in_eu,
+                ReadDirection::Backward,
+                None,
+            )
+            .await;
+        assert_eq!(versions(backward), [4, 2]);
+        let backward = log
+            .read_filtered(
+                "orders",
+                Some(3),
+                Some(8),
+                &EventFilter::default(),
+                ReadDirection::Backward,
+                Some(2),
+            )
+            .await;
+        assert_eq!(versions(backward), [8, 7]);
+        let none = EventFilter {
+            event_types: Some(vec![]),
+            ..EventFilter::default()
+        };
+        assert!(log
+            .read_filtered("orders", None, None, &none, ReadDirection::Forward, None)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_ids_are_remembered_within_the_window() {
+        let log = MemoryEventLog::new().with_dedup_window(2);
+        let first = log.append_once(event("orders", 0), Some(0)).await.unwrap();
+        assert!(!first.duplicate);
+        // A retry is answered before its stale expected version is checked.
+        let retried = log.append_once(event("orders", 0), Some(0)).await.unwrap();
+        assert!(retried.duplicate);
+        assert_eq!((retried.version, retried.position), (1, first.position));
+        assert_eq!(log.len("orders").await, 1);
+
+        log.append(event("orders", 1)).await.unwrap();
+        assert!(
+            log.append_once(event("orders", 0), None)
+                .await
+                .unwrap()
+                .duplicate
+        );
+        // Two later events push the first one out of the window, but its
+        // ID stays taken while the event is retained, in any stream.
+        log.append(event("orders", 2)).await.unwrap();
+        assert!(matches!(
+            log.append_once(event("orders", 0), None).await,
+            Err(SyrosError::Conflict(_))
+        ));
+        let reused = Event {
+            stream_id: "payments".to_string(),
+            ..event("orders", 2)
+        };
+        assert!(matches!(
+            log.append_once(reused, None).await,
+            Err(SyrosError::Conflict(_))
+        ));
+
+        // Once the event is gone, its ID can be used again.
+        log.truncate_front("orders", 2).await;
+        let appended = log.append_once(event("orders", 0), None).await.unwrap();
+        assert!(!appended.duplicate);
+        assert_eq!(appended.version, 4);
+    }
+
+    #[tokio::test]
+    async fn test_feed_follows_store_order_across_streams() {
+        let log = MemoryEventLog::new();
+        let appends = (0..20).map(|n| {
+            let log = log.clone();
+            let stream_id = ["orders", "payments", "refunds"][n % 3];
+            tokio::spawn(async move { log.append(event(stream_id, n)).await.unwrap() })
+        });
+        let mut stored = futures::future::join_all(appends)
+            .await
+            .into_iter()
+            .map(|appended| appended.unwrap())
+            .collect::<Vec<_>>();
+        stored.sort_by_key(|event| event.position);
+
+        let feed = log.read_all(1, usize::MAX).await;
+        let ids = |events: &[Arc<Event>]| -> Vec<String> {
+            events.iter().map(|event| event.id.clone()).collect()
+        };
+        assert_eq!(ids(&feed), ids(&stored));
+        assert!(feed
+            .windows(2)
+            .all(|pair| pair[0].position < pair[1].position));
+        // Within a stream, feed order is version order
+        for stream_id in ["orders", "payments", "refunds"] {
+            let versions: Vec<i64> = feed
+                .iter()
+                .filter(|event| event.stream_id == stream_id)
+                .map(|event| event.version)
+                .collect();
+            assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        log.truncate_front("orders", 1).await;
+        log.remove("payments").await;
+        let feed = log.read_all(1, usize::MAX).await;
+        assert_eq!(feed.len(), 1 + 6);
+        let page = log.read_all(feed[3].position, 2).await;
+        assert_eq!(ids(&page), ids(&feed[3..5]));
+    }
+
+    #[tokio::test]
+    async fn test_reads_share_stored_events() {
+        let log = MemoryEventLog::new();
+        let stored = log.append(event("orders", 0)).await.unwrap();
+
+        let read = log.read("orders", None, None, None).await;
+        assert!(Arc::ptr_eq(&stored, &read[0]));
+    }
+
+    #[tokio::test]
+    async fn test_versions_survive_truncation() {
+        let log = MemoryEventLog::new();
+        for n in 0..5 {
+            log.append(event("orders", n)).await.unwrap();
+        }
+        assert_eq!(log.retained_from("orders").await, Some(1));
+
+        assert_eq!(log.truncate_front("orders", 2).await, 3);
+        assert_eq!(log.retained_from("orders").await, Some(4));
+        assert_eq!(log.truncate_front("orders", 0).await, 2);
+        assert_eq!(log.version("orders").await, 5);
+        assert_eq!(log.retained_from("orders").await, Some(6));
+        assert!(log.read("orders", Some(1), None, None).await.is_empty());
+
+        assert_eq!(log.append(event("orders", 5)).await.unwrap().version, 6);
+        assert!(log.insert(event("orders", 6)).await.is_err());
+        assert_eq!(log.retained_from("missing").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_replace_older_ones_only() {
+        let log = MemoryEventLog::new();
+        for n in 0..3 {
+            log.append(event("orders", n)).await.unwrap();
+        }
+        let snapshot = |stream_id: &str, version| Snapshot {
+            stream_id: stream_id.to_string(),
+            version,
+            state: serde_json::json!({ "version": version }),
+            created_at: Utc::now(),
+        };
+
+        log.save_snapshot(snapshot("orders", 2)).await.unwrap();
+        assert!(matches!(
+            log.save_snapshot(snapshot("orders", 1)).await,
+            Err(SyrosError::Conflict(_))
+        ));
+        for version in [0, 4] {
+            assert!(matches!(
+                log.save_snapshot(snapshot("orders", version)).await,
+                Err(SyrosError::EventStoreError(_))
+            ));
+        }
+        assert!(matches!(
+            log.save_snapshot(snapshot("missing", 1)).await,
+            Err(SyrosError::NotFound(_))
+        ));
+        log.save_snapshot(snapshot("orders", 3)).await.unwrap();
+        assert_eq!(log.snapshot("orders").await.unwrap().version, 3);
+
+        log.archive("orders", Utc::now()).await;
+        assert!(log.snapshot("orders").await.is_none());
+        assert!(matches!(
+            log.save_snapshot(snapshot("orders", 3)).await,
+            Err(SyrosError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_remove_shrink_the_directory() {
+        let log = MemoryEventLog::new();
+        for n in 0..3 {
+            log.append(event("orders", n)).await.unwrap();
+            log.append(event("payments", n)).await.unwrap();
+        }
+
+        let archived_at = Utc::now();
+        let info = log.archive("orders", archived_at).await.unwrap();
+        assert_eq!((info.version, info.event_count), (3, 3));
+        assert_eq!(info.archived_at, Some(archived_at));
+        assert_eq!(log.archive("orders", Utc::now()).await, Some(info));
+        assert_eq!(log.len("orders").await, 0);
+        assert_eq!(log.version("orders").await, 3);
+        assert_eq!(log.retained_from("orders").await, Some(4));
+        assert!(matches!(
+            log.append(event("orders", 3)).await,
+            Err(SyrosError::Conflict(_))
+        ));
+        assert_eq!(
+            log.directory_size().await,
+            DirectorySize {
+                active: 1,
+                archived: 1
+            }
+        );
+
+        assert!(log.remove("orders").await);
+        assert!(log.remove("payments").await);
+        assert!(!log.remove("payments").await);
+        assert_eq!(
+            log.directory_size().await,
+            DirectorySize {
+                active: 0,
+                archived: 0
+            }
+        );
+        assert_eq!(log.append(event("orders", 0)).await.unwrap().version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_streams_keep_their_events_but_reject_appends() {
+        let log = MemoryEventLog::new();
+        for n in 0..3 {
+            log.append(event("orders", n)).await.unwrap();
+        }
+
+        assert!(matches!(
+            log.delete("orders", Some(2), false, Utc::now()).await,
+            Err(SyrosError::VersionConflict {
+                expected: 2,
+                actual: 3
+            })
+        ));
+        let deleted_at = Utc::now();
+        assert!(log
+            .delete("orders", Some(3), false, deleted_at)
+            .await
+            .unwrap());
+        assert!(log.delete("orders", None, false, Utc::now()).await.unwrap());
+        assert!(!log
+            .delete("missing", None, false, Utc::now())
+            .await
+            .unwrap());
+
+        assert_eq!(log.read("orders", None, None, None).await.len(), 3);
+        assert_eq!(
+            log.info("orders").await.unwrap().deleted_at,
+            Some(deleted_at)
+        );
+        assert!(matches!(
+            log.append(event("orders", 3)).await,
+            Err(SyrosError::StreamDeleted(_))
+        ));
+        // An archived record keeps the deletion.
+        log.archive("orders", Utc::now()).await;
+        assert!(matches!(
+            log.append(event("orders", 3)).await,
+            Err(SyrosError::StreamDeleted(_))
+        ));
+
+        assert!(log.delete("orders", None, true, Utc::now()).await.unwrap());
+        assert!(log.info("orders").await.is_none());
+        assert_eq!(log.append(event("orders", 0)).await.unwrap().version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retention_drops_events_outside_the_policy() {
+        let log = MemoryEventLog::new();
+        log.set_retention(
+            "orders",
+            RetentionPolicy {
+                max_events: Some(2),
+                max_age_seconds: None,
+            },
+        )
+        .await;
+        log.set_retention(
+            "payments",
+            RetentionPolicy {
+                max_events: None,
+                max_age_seconds: Some(60),
+            },
+        )
+        .await;
+        for n in 0..5 {
+            log.append(event("orders", n)).await.unwrap();
+            log.append(event("payments", n)).await.unwrap();
+            log.append(event("refunds", n)).await.unwrap();
+        }
+
+        assert_eq!(log.enforce_retention(Utc::now()).await, 3);
+        assert_eq!(log.retained_from("orders").await, Some(4));
+        assert_eq!(log.len("payments").await, 5);
+        // Every event is too old an hour later, but the latest one is kept.
+        let later = Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(log.enforce_retention(later).await, 4);
+        assert_eq!(log.retained_from("payments").await, Some(5));
+        assert_eq!(log.version("payments").await, 5);
+        assert_eq!(log.len("refunds").await, 5);
+        assert_eq!(log.read_all(0, usize::MAX).await.len(), 8);
+
+        log.set_retention("orders", RetentionPolicy::default())
+            .await;
+        log.append(event("orders", 5)).await.unwrap();
+        assert_eq!(log.enforce_retention(later).await, 0);
+        assert_eq!(log.len("orders").await, 3);
+
+        // Events after the latest snapshot are needed to rehydrate the stream.
+        log.save_snapshot(Snapshot {
+            stream_id: "refunds".to_string(),
+            version: 2,
+            state: serde_json::json!({}),
+            created_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+        log.set_retention(
+            "refunds",
+            RetentionPolicy {
+                max_events: Some(1),
+                max_age_seconds: None,
+            },
+        )
+        .await;
+        assert_eq!(log.enforce_retention(later).await, 2);
+        assert_eq!(log.retained_from("refunds").await, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_streams_are_listed_in_creation_order() {
+        let log = MemoryEventLog::new();
+        for stream_id in ["orders-b", "payments-a", "orders-a", "orders-c"] {
+            log.append(event(stream_id, 0)).await.unwrap();
+        }
+        log.archive("orders-c", Utc::now()).await;
+        let ids = |streams: Vec<StreamInfo>| -> Vec<String> {
+            streams.into_iter().map(|info| info.stream_id).collect()
+        };
+
+        let first_page = log.list(Some("orders-"), Some(2), 0).await;
+        // Appends to a listed stream leave later pages where they were
+        log.append(event("orders-b", 1)).await.unwrap();
+        log.append(event("orders-0", 0)).await.unwrap();
+        let second_page = log.list(Some("orders-"), Some(2), 2).await;
+
+        assert_eq!(ids(first_page), ["orders-b", "orders-a"]);
+        assert_eq!(second_page[0].stream_id, "orders-c");
+        assert!(second_page[0].archived_at.is_some());
+        assert_eq!(second_page[1].stream_id, "orders-0");
+        assert_eq!(log.list(None, None, 0).await.len(), 5);
+        let orders_b = &log.list(Some("orders-b"), None, 0).await[0];
+        assert_eq!((orders_b.version, orders_b.event_count), (2, 2));
+        assert!(orders_b.updated_at > orders_b.created_at);
+    }
+}
@@ -0,0 +1,1159 @@
+//! Storage of event streams behind the [`EventStore`](crate::core::EventStore).
+//!
+//! An [`EventPersistence`] appends events under the next version of their
+//! stream and the next position of the feed of all streams, and reads them
+//! back. [`MemoryEventLog`] keeps streams in this
+//! process, so they are lost on restart; [`PostgresEventLog`] keeps them in
+//! the `events` table, where the unique `(stream_id, version)` constraint
+//! guarantees that no two events share a version, their latest snapshots in
+//! the `snapshots` table, when they were created and last appended to in the
+//! `streams` table, and the streams' deletion flags and retention policies in
+//! the `deleted_streams` and `stream_retention` tables.
+
+use crate::core::event_log::MemoryEventLog;
+use crate::core::event_store::{
+    check_expected_version, check_snapshot, reject_closed, Event, EventFilter, ReadDirection,
+    RetentionPolicy, Snapshot, StreamInfo, CREATED_BY_METADATA_KEY, DEFAULT_DEDUP_WINDOW,
+};
+use crate::storage::postgres::PostgresManager;
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Where an appended event was stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Appended {
+    /// Version of the event in its stream
+    pub version: i64,
+    /// Position of the event in the feed of all streams
+    pub position: i64,
+    /// Whether an event was already appended under the same ID, whose
+    /// version and position these are; nothing new was stored
+    pub duplicate: bool,
+}
+
+/// Storage the events of an [`EventStore`](crate::core::EventStore) are
+/// appended to and read from.
+#[async_trait]
+pub trait EventPersistence: Send + Sync {
+    /// Stores `event` under the next version of its stream and the next
+    /// position of the feed, and returns both; the version and position on
+    /// `event` are ignored.
+    ///
+    /// With an `expected_version`, fails with `VersionConflict` unless the
+    /// stream is at that version when the event is stored. Fails with
+    /// `Conflict` if the stream is archived and with `StreamDeleted` if it
+    /// was deleted.
+    ///
+    /// With `deduplicate`, the event's ID was chosen by the client: if one
+    /// of the stream's latest events within the dedup window has the same
+    /// ID, that event's version and position are returned as a duplicate,
+    /// whatever the expected version.
+    async fn append(
+        &self,
+        event: Event,
+        expected_version: Option<u64>,
+        deduplicate: bool,
+    ) -> Result<Appended>;
+
+    /// Retained events of a stream from `from_version` up to `to_version`
+    /// that match `filter`, in `direction`; `limit` counts only matching
+    /// events.
+    async fn read(
+        &self,
+        stream_id: &str,
+        from_version: Option<i64>,
+        to_version: Option<i64>,
+        filter: &EventFilter,
+        direction: ReadDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Event>>;
+
+    /// Oldest version of a stream that can still be read; `None` for
+    /// unknown streams.
+    async fn first_available_version(&self, stream_id: &str) -> Result<Option<i64>>;
+
+    /// Version of the latest event of a stream; 0 for unknown streams.
+    async fn version(&self, stream_id: &str) -> Result<i64>;
+
+    /// Stores `event` exactly as given, keeping its ID, version, timestamp
+    /// and metadata.
+    ///
+    /// Fails with `Conflict` unless the event's version directly follows the
+    /// stream's current version, or if the stream is archived, and with
+    /// `StreamDeleted` if it was deleted.
+    async fn import(&self, event: Event) -> Result<()>;
+
+    /// Events of a stream still stored.
+    async fn event_count(&self, stream_id: &str) -> Result<usize>;
+
+    /// Summary of a stream, or its archived record; `None` if it has
+    /// neither events nor a record.
+    ///
+    /// When the stream was created and last appended to, and by whom it was
+    /// created, are recorded as events are appended, so removing old events
+    /// does not change them.
+    async fn info(&self, stream_id: &str) -> Result<Option<StreamInfo>>;
+
+    /// Summaries of the streams, archived or not, whose ID starts with
+    /// `prefix`, ordered by when they were created and then by ID, skipping
+    /// `offset` and returning at most `limit`.
+    async fn list(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Result<Vec<StreamInfo>>;
+
+    /// Retained events of all streams from feed position `from_position`
+    /// on, in commit order, up to `limit`.
+    async fn read_all(&self, from_position: i64, limit: usize) -> Result<Vec<Event>>;
+
+    /// Replaces the latest snapshot of its stream with `snapshot`.
+    ///
+    /// Fails with `NotFound` for unknown streams, with `Conflict` for
+    /// archived ones or if the stream has a newer snapshot, with
+    /// `StreamDeleted` for deleted ones, and with
+    /// `EventStoreError` if the stream has no event at the snapshot's
+    /// version.
+    async fn save_snapshot(&self, snapshot: Snapshot) -> Result<()>;
+
+    /// Latest snapshot of a stream, if one was saved.
+    async fn latest_snapshot(&self, stream_id: &str) -> Result<Option<Snapshot>>;
+
+    /// Flags a stream as deleted, or with `hard` removes everything kept
+    /// for it, if it is at `expected_version` when one is given; returns
+    /// whether it existed.
+    async fn delete(
+        &self,
+        stream_id: &str,
+        expected_version: Option<u64>,
+        hard: bool,
+    ) -> Result<bool>;
+
+    /// Replaces the retention policy of a stream; an unbounded policy
+    /// clears it.
+    async fn set_retention(&self, stream_id: &str, policy: RetentionPolicy) -> Result<()>;
+
+    /// Removes the events outside their stream's retention policy as of
+    /// `now`, except each stream's latest event; returns how many.
+    async fn enforce_retention(&self, now: DateTime<Utc>) -> Result<u64>;
+
+    /// Replaces a stream's events and snapshot with its summary, archived at
+    /// `now`; returns the record, or `None` for an unknown stream.
+    /// Archiving an archived stream returns its existing record.
+    async fn archive(&self, stream_id: &str, now: DateTime<Utc>) -> Result<Option<StreamInfo>>;
+}
+
+#[async_trait]
+impl EventPersistence for MemoryEventLog {
+    async fn append(
+        &self,
+        event: Event,
+        expected_version: Option<u64>,
+        deduplicate: bool,
+    ) -> Result<Appended> {
+        if deduplicate {
+            return self.append_once(event, expected_version).await;
+        }
+        let event = self.append_expecting(event, expected_version).await?;
+        Ok(Appended {
+            version: event.version,
+            position: event.position,
+            duplicate: false,
+        })
+    }
+
+    async fn read(
+        &self,
+        stream_id: &str,
+        from_version: Option<i64>,
+        to_version: Option<i64>,
+        filter: &EventFilter,
+        direction: ReadDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Event>> {
+        Ok(self
+            .read_filtered(
+                stream_id,
+                from_version,
+                to_version,
+                filter,
+                direction,
+                limit,
+            )
+            .await
+            .iter()
+            .map(|event| (**event).clone())
+            .collect())
+    }
+
+    async fn first_available_version(&self, stream_id: &str) -> Result<Option<i64>> {
+        Ok(self.retained_from(stream_id).await)
+    }
+
+    async fn version(&self, stream_id: &str) -> Result<i64> {
+        Ok(MemoryEventLog::version(self, stream_id).await)
+    }
+
+    async fn import(&self, event: Event) -> Result<()> {
+        self.insert(event).await?;
+        Ok(())
+    }
+
+    async fn event_count(&self, stream_id: &str) -> Result<usize> {
+        Ok(self.len(stream_id).await)
+    }
+
+    async fn info(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        Ok(MemoryEventLog::info(self, stream_id).await)
+    }
+
+    async fn list(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Result<Vec<StreamInfo>> {
+        Ok(MemoryEventLog::list(self, prefix, limit, offset).await)
+    }
+
+    async fn read_all(&self, from_position: i64, limit: usize) -> Result<Vec<Event>> {
+        Ok(MemoryEventLog::read_all(self, from_position, limit)
+            .await
+            .iter()
+            .map(|event| (**event).clone())
+            .collect())
+    }
+
+    async fn save_snapshot(&self, snapshot: Snapshot) -> Result<()> {
+        MemoryEventLog::save_snapshot(self, snapshot).await
+    }
+
+    async fn latest_snapshot(&self, stream_id: &str) -> Result<Option<Snapshot>> {
+        Ok(self
+            .snapshot(stream_id)
+            .await
+            .map(|snapshot| (*snapshot).clone()))
+    }
+
+    async fn delete(
+        &self,
+        stream_id: &str,
+        expected_version: Option<u64>,
+        hard: bool,
+    ) -> Result<bool> {
+        MemoryEventLog::delete(self, stream_id, expected_version, hard, Utc::now()).await
+    }
+
+    async fn set_retention(&self, stream_id: &str, policy: RetentionPolicy) -> Result<()> {
+        MemoryEventLog::set_retention(self, stream_id, policy).await;
+        Ok(())
+    }
+
+    async fn enforce_retention(&self, now: DateTime<Utc>) -> Result<u64> {
+        Ok(MemoryEventLog::enforce_retention(self, now).await)
+    }
+
+    async fn archive(&self, stream_id: &str, now: DateTime<Utc>) -> Result<Option<StreamInfo>> {
+        Ok(MemoryEventLog::archive(self, stream_id, now).await)
+    }
+}
+
+/// Summaries of the active streams, from their `streams` record and the
+/// events still stored, and the records of the archived ones, in the shape of
+/// a [`StreamRow`].
+const STREAM_SUMMARIES: &str = "SELECT streams.stream_id, counts.version, counts.event_count,
+            streams.created_by, streams.created_at, streams.updated_at,
+            NULL::timestamptz AS archived_at
+     FROM streams, LATERAL (
+         SELECT COALESCE(MAX(version), 0)::bigint AS version, COUNT(*)::bigint AS event_count
+         FROM events WHERE events.stream_id = streams.stream_id
+     ) counts
+     UNION ALL
+     SELECT stream_id, version, event_count, created_by, created_at, updated_at, archived_at
+     FROM archived_streams";
+
+/// Row of [`STREAM_SUMMARIES`], with when the stream was deleted.
+#[derive(sqlx::FromRow)]
+struct StreamRow {
+    stream_id: String,
+    version: i64,
+    event_count: i64,
+    created_by: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    archived_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<StreamRow> for StreamInfo {
+    fn from(row: StreamRow) -> Self {
+        StreamInfo {
+            stream_id: row.stream_id,
+            version: row.version,
+            event_count: row.event_count as usize,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            archived_at: row.archived_at,
+            deleted_at: row.deleted_at,
+        }
+    }
+}
+
+/// Records `event` as appended to its stream in the `streams` table: the
+/// stream's first event sets when it was created and by whom, and every
+/// event when it was last appended to.
+async fn record_append(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: &Event,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO streams (stream_id, created_by, created_at, updated_at)
+         VALUES ($1, $2, $3, $3)
+         ON CONFLICT (stream_id) DO UPDATE
+         SET updated_at = GREATEST(streams.updated_at, EXCLUDED.updated_at)",
+    )
+    .bind(&event.stream_id)
+    .bind(event.metadata.get(CREATED_BY_METADATA_KEY))
+    .bind(event.timestamp)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+    Ok(())
+}
+
+/// Event streams kept in Postgres, surviving restarts.
+#[derive(Clone)]
+pub struct PostgresEventLog {
+    pg: PostgresManager,
+    dedup_window: usize,
+}
+
+impl PostgresEventLog {
+    pub fn new(pg: PostgresManager) -> Self {
+        Self {
+            pg,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+        }
+    }
+
+    /// Deduplicates appends against the stream's latest `window` events.
+    ///
+    /// Event IDs are unique across the table, so reusing the ID of an older
+    /// event fails with `Conflict` instead.
+    pub fn with_dedup_window(mut self, window: usize) -> Self {
+        self.dedup_window = window;
+        self
+    }
+
+    pub fn pool(&self) -> &sqlx::PgPool {
+        self.pg.get_pool()
+    }
+}
+
+#[async_trait]
+impl EventPersistence for PostgresEventLog {
+    async fn append(
+        &self,
+        event: Event,
+        expected_version: Option<u64>,
+        deduplicate: bool,
+    ) -> Result<Appended> {
+        let id = Uuid::parse_str(&event.id).map_err(|e| {
+            SyrosError::EventStoreError(format!("Invalid event ID {}: {}", event.id, e))
+        })?;
+        let mut tx = self
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        // Appends to the same stream wait for each other until commit, so
+        // the version read below cannot change before the insert.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&event.stream_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        reject_closed(&mut tx, &event.stream_id).await?;
+
+        let current: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(version), 0)::bigint FROM events WHERE stream_id = $1",
+        )
+        .bind(&event.stream_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        if deduplicate {
+            let original: Option<(i64, i64)> = sqlx::query_as(
+                "SELECT version::bigint, position FROM events
+                 WHERE id = $1 AND stream_id = $2 AND version > $3",
+            )
+            .bind(id)
+            .bind(&event.stream_id)
+            .bind(current - self.dedup_window.min(i64::MAX as usize) as i64)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+            if let Some((version, position)) = original {
+                return Ok(Appended {
+                    version,
+                    position,
+                    duplicate: true,
+                });
+            }
+        }
+        check_expected_version(expected_version, current)?;
+        let version = current + 1;
+
+        let inserted: sqlx::Result<i64> = sqlx::query_scalar(
+            "INSERT INTO events (id, stream_id, event_type, data, metadata, version, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING position",
+        )
+        .bind(id)
+        .bind(&event.stream_id)
+        .bind(&event.event_type)
+        .bind(sqlx::types::Json(&event.data))
+        .bind(sqlx::types::Json(&event.metadata))
+        .bind(version)
+        .bind(event.timestamp)
+        .fetch_one(&mut *tx)
+        .await;
+        let position = match inserted {
+            Ok(position) => position,
+            Err(e) => {
+                // Writers that bypass the lock, such as imports, can still
+                // take the version first.
+                let unique = e.as_database_error().filter(|db| db.is_unique_violation());
+                if unique.is_some_and(|db| db.constraint() == Some("events_pkey")) {
+                    return Err(SyrosError::Conflict(format!(
+                        "Event ID {} is already used by an older or another stream's event",
+                        event.id
+                    )));
+                }
+                if unique.is_none() {
+                    return Err(SyrosError::StorageError(e.to_string()));
+                }
+                // The failed insert aborted the transaction, so the stream's
+                // version is read outside it.
+                drop(tx);
+                let actual = self.version(&event.stream_id).await?.max(version);
+                return Err(SyrosError::VersionConflict {
+                    expected: expected_version.unwrap_or(current as u64),
+                    actual: actual as u64,
+                });
+            }
+        };
+        record_append(&mut tx, &event).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(Appended {
+            version,
+            position,
+            duplicate: false,
+        })
+    }
+
+    async fn read(
+        &self,
+        stream_id: &str,
+        from_version: Option<i64>,
+        to_version: Option<i64>,
+        filter: &EventFilter,
+        direction: ReadDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Event>> {
+        let order = match direction {
+            ReadDirection::Forward => "ASC",
+            ReadDirection::Backward => "DESC",
+        };
+        sqlx::query_as(&format!(
+            "SELECT id::text, stream_id, event_type, data, metadata, created_at as timestamp, version::bigint, position
+             FROM events
+             WHERE stream_id = $1
+               AND ($2::bigint IS NULL OR version >= $2)
+               AND ($3::bigint IS NULL OR version <= $3)
+               AND ($4::text[] IS NULL OR event_type = ANY($4))
+               AND COALESCE(metadata, '{{}}') @> $5
+             ORDER BY version {}
+             LIMIT $6",
+            order
+        ))
+        .bind(stream_id)
+        .bind(from_version)
+        .bind(to_version)
+        .bind(filter.event_types.as_deref())
+        .bind(sqlx::types::Json(&filter.metadata))
+        .bind(limit.map(|limit| limit as i64))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn first_available_version(&self, stream_id: &str) -> Result<Option<i64>> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(
+                 (SELECT MIN(version)::bigint FROM events WHERE stream_id = $1),
+                 (SELECT version + 1 FROM archived_streams WHERE stream_id = $1)
+             )",
+        )
+        .bind(stream_id)
+        .fetch_one(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn version(&self, stream_id: &str) -> Result<i64> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(
+                 (SELECT MAX(version)::bigint FROM events WHERE stream_id = $1),
+                 (SELECT version FROM archived_streams WHERE stream_id = $1),
+                 0
+             )",
+        )
+        .bind(stream_id)
+        .fetch_one(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn import(&self, event: Event) -> Result<()> {
+        let conflict = |version: i64, stream_id: &str| {
+            SyrosError::Conflict(format!(
+                "Event {} does not follow the current version of stream {}",
+                version, stream_id
+            ))
+        };
+        let id = Uuid::parse_str(&event.id).map_err(|e| {
+            SyrosError::EventStoreError(format!("Invalid event ID {}: {}", event.id, e))
+        })?;
+
+        let mut tx = self
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        reject_closed(&mut tx, &event.stream_id).await?;
+
+        let current: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(version), 0)::bigint FROM events WHERE stream_id = $1",
+        )
+        .bind(&event.stream_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        if event.version != current + 1 {
+            return Err(conflict(event.version, &event.stream_id));
+        }
+
+        sqlx::query(
+            "INSERT INTO events (id, stream_id, event_type, data, metadata, version, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(id)
+        .bind(&event.stream_id)
+        .bind(&event.event_type)
+        .bind(sqlx::types::Json(&event.data))
+        .bind(sqlx::types::Json(&event.metadata))
+        .bind(event.version)
+        .bind(event.timestamp)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if e.as_database_error()
+                .is_some_and(|db| db.is_unique_violation())
+            {
+                conflict(event.version, &event.stream_id)
+            } else {
+                SyrosError::StorageError(e.to_string())
+            }
+        })?;
+        record_append(&mut tx, &event).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn event_count(&self, stream_id: &str) -> Result<usize> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE stream_id = $1")
+            .bind(stream_id)
+            .fetch_one(self.pool())
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(count as usize)
+    }
+
+    async fn info(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let row: Option<StreamRow> = sqlx::query_as(&format!(
+            "SELECT summaries.*, deleted_streams.deleted_at
+             FROM ({}) summaries LEFT JOIN deleted_streams USING (stream_id)
+             WHERE stream_id = $1",
+            STREAM_SUMMARIES
+        ))
+        .bind(stream_id)
+        .fetch_optional(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(row.map(StreamInfo::from))
+    }
+
+    async fn list(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Result<Vec<StreamInfo>> {
+        let rows: Vec<StreamRow> = sqlx::query_as(&format!(
+            "SELECT summaries.*, deleted_streams.deleted_at
+             FROM ({}) summaries LEFT JOIN deleted_streams USING (stream_id)
+             WHERE $1::text IS NULL OR starts_with(stream_id, $1)
+             ORDER BY created_at, stream_id
+             LIMIT $2 OFFSET $3",
+            STREAM_SUMMARIES
+        ))
+        .bind(prefix)
+        .bind(limit.map(|limit| limit.min(i64::MAX as usize) as i64))
+        .bind(offset.min(i64::MAX as usize) as i64)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(rows.into_iter().map(StreamInfo::from).collect())
+    }
+
+    async fn read_all(&self, from_position: i64, limit: usize) -> Result<Vec<Event>> {
+        // Positions are taken when a transaction inserts, not when it
+        // commits. Events are only read once every transaction that started
+        // before theirs has ended, so a position still being committed is
+        // never skipped by a reader that already moved past it.
+        sqlx::query_as(
+            "SELECT id::text, stream_id, event_type, data, metadata, created_at as timestamp, version::bigint, position
+             FROM events
+             WHERE position >= $1
+               AND transaction_id < pg_snapshot_xmin(pg_current_snapshot())
+             ORDER BY position ASC
+             LIMIT $2",
+        )
+        .bind(from_position)
+        .bind(limit.min(i64::MAX as usize) as i64)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn save_snapshot(&self, snapshot: Snapshot) -> Result<()> {
+        let mut tx = self
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        // Shares the lock of appends, so the stream cannot be archived or
+        // snapshotted by someone else between the checks and the upsert.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&snapshot.stream_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        reject_closed(&mut tx, &snapshot.stream_id).await?;
+
+        let current: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(version), 0)::bigint FROM events WHERE stream_id = $1",
+        )
+        .bind(&snapshot.stream_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        if current == 0 {
+            return Err(SyrosError::NotFound(format!(
+                "Stream {} not found",
+                snapshot.stream_id
+            )));
+        }
+        let latest: Option<i64> =
+            sqlx::query_scalar("SELECT version FROM snapshots WHERE stream_id = $1")
+                .bind(&snapshot.stream_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        check_snapshot(&snapshot, current, latest)?;
+
+        sqlx::query(
+            "INSERT INTO snapshots (stream_id, version, state, created_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (stream_id) DO UPDATE
+             SET version = EXCLUDED.version, state = EXCLUDED.state, created_at = EXCLUDED.created_at",
+        )
+        .bind(&snapshot.stream_id)
+        .bind(snapshot.version)
+        .bind(sqlx::types::Json(&snapshot.state))
+        .bind(snapshot.created_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn latest_snapshot(&self, stream_id: &str) -> Result<Option<Snapshot>> {
+        sqlx::query_as(
+            "SELECT stream_id, version, state, created_at FROM snapshots WHERE stream_id = $1",
+        )
+        .bind(stream_id)
+        .fetch_optional(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))
+    }
+
+    async fn delete(
+        &self,
+        stream_id: &str,
+        expected_version: Option<u64>,
+        hard: bool,
+    ) -> Result<bool> {
+        let mut tx = self
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        // Shares the lock of appends, so the version checked below is the
+        // one the stream is deleted at.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(stream_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        let current: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(
+                 (SELECT MAX(version)::bigint FROM events WHERE stream_id = $1),
+                 (SELECT version FROM archived_streams WHERE stream_id = $1),
+                 0
+             )",
+        )
+        .bind(stream_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        if current == 0 {
+            return Ok(false);
+        }
+        check_expected_version(expected_version, current)?;
+
+        let queries: &[&str] = if hard {
+            &[
+                "DELETE FROM events WHERE stream_id = $1",
+                "DELETE FROM streams WHERE stream_id = $1",
+                "DELETE FROM archived_streams WHERE stream_id = $1",
+                "DELETE FROM snapshots WHERE stream_id = $1",
+                "DELETE FROM deleted_streams WHERE stream_id = $1",
+                "DELETE FROM stream_retention WHERE stream_id = $1",
+            ]
+        } else {
+            &["INSERT INTO deleted_streams (stream_id) VALUES ($1) ON CONFLICT DO NOTHING"]
+        };
+        for query in queries {
+            sqlx::query(query)
+                .bind(stream_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(true)
+    }
+
+    async fn set_retention(&self, stream_id: &str, policy: RetentionPolicy) -> Result<()> {
+        let query = if policy.is_unbounded() {
+            sqlx::query("DELETE FROM stream_retention WHERE stream_id = $1").bind(stream_id)
+        } else {
+            sqlx::query(
+                "INSERT INTO stream_retention (stream_id, max_events, max_age_seconds)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (stream_id) DO UPDATE
+                 SET max_events = EXCLUDED.max_events, max_age_seconds = EXCLUDED.max_age_seconds",
+            )
+            .bind(stream_id)
+            .bind(
+                policy
+                    .max_events
+                    .map(|max| max.min(i64::MAX as usize) as i64),
+            )
+            .bind(
+                policy
+                    .max_age_seconds
+                    .map(|max| max.min(i64::MAX as u64) as i64),
+            )
+        };
+        query
+            .execute(self.pool())
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn enforce_retention(&self, now: DateTime<Utc>) -> Result<u64> {
+        // The latest event of each stream is kept, so its version, read as
+        // the highest version stored, does not change. So are the events
+        // after the latest snapshot, which rehydrating the stream replays.
+        let removed = sqlx::query(
+            "DELETE FROM events e
+             USING stream_retention r,
+                   (SELECT stream_id, MAX(version) AS version FROM events
+                    WHERE stream_id IN (SELECT stream_id FROM stream_retention)
+                    GROUP BY stream_id) latest
+             WHERE e.stream_id = r.stream_id
+               AND e.stream_id = latest.stream_id
+               AND e.version < latest.version
+               AND NOT EXISTS (SELECT 1 FROM snapshots s
+                               WHERE s.stream_id = e.stream_id AND s.version < e.version)
+               AND (e.version <= latest.version - r.max_events
+                    OR e.created_at < $1::timestamptz - r.max_age_seconds * INTERVAL '1 second')",
+        )
+        .bind(now)
+        .execute(self.pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?
+        .rows_affected();
+        Ok(removed)
+    }
+
+    async fn archive(&self, stream_id: &str, now: DateTime<Utc>) -> Result<Option<StreamInfo>> {
+        let Some(mut info) = self.info(stream_id).await? else {
+            return Ok(None);
+        };
+        if info.archived_at.is_some() {
+            return Ok(Some(info));
+        }
+        info.archived_at = Some(now);
+
+        let mut tx = self
+            .pool()
+            .begin()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        sqlx::query(
+            "INSERT INTO archived_streams (stream_id, version, event_count, created_by, created_at, updated_at, archived_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&info.stream_id)
+        .bind(info.version)
+        .bind(info.event_count as i64)
+        .bind(&info.created_by)
+        .bind(info.created_at)
+        .bind(info.updated_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        sqlx::query("DELETE FROM events WHERE stream_id = $1 AND version <= $2")
+            .bind(stream_id)
+            .bind(info.version)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        for query in [
+            "DELETE FROM streams WHERE stream_id = $1",
+            "DELETE FROM snapshots WHERE stream_id = $1",
+        ] {
+            sqlx::query(query)
+                .bind(stream_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+
+        Ok(Some(info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event_store::{EventRequest, EventStore, GetEventsRequest};
+    use std::collections::HashMap;
+
+    /// Postgres used by tests of the Postgres backend, migrated with the
+    /// files in `migrations/`; the tests are skipped when unset.
+    const TEST_DATABASE_URL_ENV: &str = "SYROS_TEST_DATABASE_URL";
+
+    fn request(stream_id: &str, expected_version: Option<u64>) -> EventRequest {
+        EventRequest {
+            stream_id: stream_id.to_string(),
+            event_type: "order.created".to_string(),
+            data: serde_json::json!({ "total": 42 }),
+            metadata: None,
+            created_by: Some("alice".to_string()),
+            expected_version,
+            event_id: None,
+            correlation_id: None,
+            causation_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_postgres_events_survive_a_restart() {
+        let Ok(url) = std::env::var(TEST_DATABASE_URL_ENV) else {
+            eprintln!(
+                "Skipping: set {} to run against Postgres",
+                TEST_DATABASE_URL_ENV
+            );
+            return;
+        };
+        let stream_id = format!("orders-{}", Uuid::new_v4());
+        {
+            let store = EventStore::new(PostgresManager::new(&url, 2).await.unwrap());
+            for expected_version in [0, 1] {
+                store
+                    .append_event(request(&stream_id, Some(expected_version)))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // A new pool, as a restarted process would open.
+        let store = EventStore::new(PostgresManager::new(&url, 2).await.unwrap());
+        assert_eq!(store.get_stream_version(&stream_id).await.unwrap(), 2);
+        let read = store
+            .get_events(GetEventsRequest {
+                stream_id: stream_id.clone(),
+                from_version: Some(2),
+                from_snapshot: false,
+                to_version: None,
+                filter: EventFilter::default(),
+                direction: ReadDirection::Forward,
+                limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(read.events.len(), 1);
+        assert_eq!(read.events[0].version, 2);
+        assert_eq!(read.events[0].data["total"], 42);
+        assert_eq!(read.events[0].metadata["created_by"], "alice");
+        let info = store.get_stream_info(&stream_id).await.unwrap().unwrap();
+        assert_eq!(info.event_count, 2);
+        assert!(info.updated_at >= info.created_at);
+        let listed = store.list_streams(Some(&stream_id), None, 0).await.unwrap();
+        assert_eq!(listed, vec![info]);
+        let newest = store
+            .get_events(GetEventsRequest {
+                stream_id: stream_id.clone(),
+                from_version: None,
+                from_snapshot: false,
+                to_version: None,
+                filter: EventFilter {
+                    event_types: Some(vec!["order.created".to_string()]),
+                    metadata: HashMap::from([("created_by".to_string(), "alice".to_string())]),
+                },
+                direction: ReadDirection::Backward,
+                limit: Some(1),
+            })
+            .await
+            .unwrap();
+        assert_eq!(newest.events.len(), 1);
+        assert_eq!(newest.events[0].version, 2);
+
+        let other_id = format!("payments-{}", Uuid::new_v4());
+        let other = store.append_event(request(&other_id, None)).await.unwrap();
+        assert!(other.position > read.events[0].position);
+        let feed = store
+            .read_all(read.events[0].position, usize::MAX)
+            .await
+            .unwrap();
+        let ours: Vec<(&str, i64)> = feed
+            .events
+            .iter()
+            .filter(|event| event.stream_id == stream_id || event.stream_id == other_id)
+            .map(|event| (event.stream_id.as_str(), event.position))
+            .collect();
+        assert_eq!(
+            ours,
+            [
+                (stream_id.as_str(), read.events[0].position),
+                (other_id.as_str(), other.position),
+            ]
+        );
+        assert!(store.delete_stream(&other_id, None, true).await.unwrap());
+
+        let event_id = Uuid::new_v4().to_string();
+        let retry = || EventRequest {
+            event_id: Some(event_id.clone()),
+            ..request(&other_id, Some(0))
+        };
+        let original = store.append_event(retry()).await.unwrap();
+        let retried = store.append_event(retry()).await.unwrap();
+        assert_eq!(
+            (retried.version, retried.position),
+            (original.version, original.position)
+        );
+        assert_eq!(store.get_stream_version(&other_id).await.unwrap(), 1);
+        // Event IDs are unique across streams.
+        assert!(matches!(
+            store
+                .append_event(EventRequest {
+                    event_id: Some(event_id.clone()),
+                    ..request(&stream_id, None)
+                })
+                .await,
+            Err(SyrosError::Conflict(_))
+        ));
+        assert!(store.delete_stream(&other_id, None, true).await.unwrap());
+
+        let (first, second) = tokio::join!(
+            store.append_event(request(&stream_id, Some(2))),
+            store.append_event(request(&stream_id, Some(2))),
+        );
+        assert_eq!(
+            [&first, &second]
+                .iter()
+                .filter(|appended| appended.is_ok())
+                .count(),
+            1
+        );
+        assert!(matches!(
+            first.and(second),
+            Err(SyrosError::VersionConflict {
+                expected: 2,
+                actual: 3
+            })
+        ));
+
+        store
+            .save_snapshot(&stream_id, 2, serde_json::json!({ "total": 84 }))
+            .await
+            .unwrap();
+        let read = store
+            .get_events(GetEventsRequest {
+                stream_id: stream_id.clone(),
+                from_version: None,
+                from_snapshot: true,
+                to_version: None,
+                filter: EventFilter::default(),
+                direction: ReadDirection::Forward,
+                limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(read.snapshot.unwrap().state["total"], 84);
+        assert_eq!(read.events.len(), 1);
+        assert_eq!(read.events[0].version, 3);
+        assert!(matches!(
+            store
+                .save_snapshot(&stream_id, 1, serde_json::json!({}))
+                .await,
+            Err(SyrosError::Conflict(_))
+        ));
+        assert!(store.delete_stream(&stream_id, None, true).await.unwrap());
+        assert!(store
+            .get_latest_snapshot(&stream_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_postgres_streams_are_deleted_and_retained() {
+        let Ok(url) = std::env::var(TEST_DATABASE_URL_ENV) else {
+            eprintln!(
+                "Skipping: set {} to run against Postgres",
+                TEST_DATABASE_URL_ENV
+            );
+            return;
+        };
+        let store = EventStore::new(PostgresManager::new(&url, 2).await.unwrap());
+        let stream_id = format!("orders-{}", Uuid::new_v4());
+        store
+            .set_retention(
+                &stream_id,
+                RetentionPolicy {
+                    max_events: Some(2),
+                    max_age_seconds: None,
+                },
+            )
+            .await
+            .unwrap();
+        for expected_version in 0..4 {
+            store
+                .append_event(request(&stream_id, Some(expected_version)))
+                .await
+                .unwrap();
+        }
+
+        let created = store.get_stream_info(&stream_id).await.unwrap().unwrap();
+        assert!(store.enforce_retention().await.unwrap() >= 2);
+        assert_eq!(store.get_stream_events_count(&stream_id).await.unwrap(), 2);
+        assert_eq!(store.get_stream_version(&stream_id).await.unwrap(), 4);
+        // The stream keeps when it was created as its first events go.
+        let retained = store.get_stream_info(&stream_id).await.unwrap().unwrap();
+        assert_eq!(retained.created_at, created.created_at);
+        assert_eq!(retained.created_by, created.created_by);
+        store
+            .set_retention(
+                &stream_id,
+                RetentionPolicy {
+                    max_events: None,
+                    max_age_seconds: Some(0),
+                },
+            )
+            .await
+            .unwrap();
+        // The events after the snapshot outlive the policy.
+        store
+            .save_snapshot(&stream_id, 2, serde_json::json!({ "total": 84 }))
+            .await
+            .unwrap();
+        store.enforce_retention().await.unwrap();
+        assert_eq!(store.get_stream_events_count(&stream_id).await.unwrap(), 2);
+        store
+            .save_snapshot(&stream_id, 3, serde_json::json!({ "total": 126 }))
+            .await
+            .unwrap();
+        store.enforce_retention().await.unwrap();
+        assert_eq!(store.get_stream_events_count(&stream_id).await.unwrap(), 1);
+        assert_eq!(store.get_stream_version(&stream_id).await.unwrap(), 4);
+
+        assert!(matches!(
+            store.delete_stream(&stream_id, Some(3), false).await,
+            Err(SyrosError::VersionConflict {
+                expected: 3,
+                actual: 4
+            })
+        ));
+        assert!(store
+            .delete_stream(&stream_id, Some(4), false)
+            .await
+            .unwrap());
+        assert!(matches!(
+            store.append_event(request(&stream_id, None)).await,
+            Err(SyrosError::StreamDeleted(_))
+        ));
+        let info = store.get_stream_info(&stream_id).await.unwrap().unwrap();
+        assert!(info.deleted_at.is_some());
+        assert_eq!(info.event_count, 1);
+        let listed = store.list_streams(Some(&stream_id), None, 0).await.unwrap();
+        assert_eq!(listed[0].deleted_at, info.deleted_at);
+
+        assert!(store.delete_stream(&stream_id, None, true).await.unwrap());
+        assert!(!store.delete_stream(&stream_id, None, true).await.unwrap());
+        // The retention policy went with the stream.
+        for expected_version in [0, 1] {
+            store
+                .append_event(request(&stream_id, Some(expected_version)))
+                .await
+                .unwrap();
+        }
+        store.enforce_retention().await.unwrap();
+        assert_eq!(store.get_stream_events_count(&stream_id).await.unwrap(), 2);
+        assert!(store.delete_stream(&stream_id, None, true).await.unwrap());
+    }
+}
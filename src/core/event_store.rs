@@ -3,14 +3,38 @@
 //! This module provides an event store that implements the event sourcing pattern,
 //! allowing applications to store and replay events for state reconstruction.
 
+use crate::core::event_log::MemoryEventLog;
+use crate::core::event_persistence::{EventPersistence, PostgresEventLog};
+use crate::core::event_subscriptions::{EventSubscription, SUBSCRIPTION_CAPACITY};
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
 use crate::storage::postgres::PostgresManager;
 use crate::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+/// Event metadata key holding the principal that appended the event.
+///
+/// Reserved: values supplied by callers are replaced on append.
+pub const CREATED_BY_METADATA_KEY: &str = "created_by";
+
+/// Header carrying the correlation ID of the business transaction an
+/// appended event belongs to.
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+/// Event metadata key holding the correlation ID of the business
+/// transaction the event belongs to; generated on append if not given.
+pub const CORRELATION_ID_METADATA_KEY: &str = "correlation_id";
+/// Event metadata key holding the ID of the event that caused the event.
+pub const CAUSATION_ID_METADATA_KEY: &str = "causation_id";
+
+/// Client-chosen event IDs each stream remembers unless configured
+/// otherwise.
+pub const DEFAULT_DEDUP_WINDOW: usize = 1000;
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Event {
     pub id: String,
@@ -22,6 +46,11 @@ pub struct Event {
     pub metadata: HashMap<String, String>,
     pub timestamp: DateTime<Utc>,
     pub version: i64,
+    /// Position in the feed of all streams, assigned when the event is
+    /// stored; strictly increasing in commit order, but with gaps
+    #[serde(default)]
+    #[sqlx(default)]
+    pub position: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +59,49 @@ pub struct EventRequest {
     pub event_type: String,
     pub data: serde_json::Value,
     pub metadata: Option<HashMap<String, String>>,
+    /// Authenticated principal appending the event, recorded in its metadata
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// Version the stream must be at for the event to be appended; 0 for a
+    /// stream without events
+    #[serde(default)]
+    pub expected_version: Option<u64>,
+    /// Client-chosen ID of the event, a UUID; appending it to the stream
+    /// again returns the event already appended under it
+    #[serde(default)]
+    pub event_id: Option<String>,
+    /// Correlation ID of the business transaction, recorded in metadata
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    /// ID of the event that caused this one, recorded in metadata
+    #[serde(default)]
+    pub causation_id: Option<String>,
+}
+
+impl EventRequest {
+    /// Metadata to store: the caller's, with the reserved creator key set
+    /// from `created_by`, and the correlation and causation IDs set from
+    /// the request's fields when given.
+    ///
+    /// Without a correlation ID in either, a new one is generated, so every
+    /// appended event carries one.
+    fn stored_metadata(&mut self) -> HashMap<String, String> {
+        let mut metadata = self.metadata.take().unwrap_or_default();
+        metadata.remove(CREATED_BY_METADATA_KEY);
+        if let Some(created_by) = self.created_by.take() {
+            metadata.insert(CREATED_BY_METADATA_KEY.to_string(), created_by);
+        }
+        if let Some(correlation_id) = self.correlation_id.take() {
+            metadata.insert(CORRELATION_ID_METADATA_KEY.to_string(), correlation_id);
+        }
+        metadata
+            .entry(CORRELATION_ID_METADATA_KEY.to_string())
+            .or_insert_with(|| Uuid::new_v4().to_string());
+        if let Some(causation_id) = self.causation_id.take() {
+            metadata.insert(CAUSATION_ID_METADATA_KEY.to_string(), causation_id);
+        }
+        metadata
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,12 +109,72 @@ pub struct EventResponse {
     pub event_id: String,
     pub success: bool,
     pub message: String,
+    /// Version assigned to the event in its stream
+    #[serde(default)]
+    pub version: i64,
+    /// Position assigned to the event in the feed of all streams
+    #[serde(default)]
+    pub position: i64,
+}
+
+/// A page of the feed of all streams.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadAllResponse {
+    pub events: Vec<Event>,
+    /// Position to read the next page from
+    pub next_position: i64,
+}
+
+/// Order in which a read returns the events of a stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadDirection {
+    /// Oldest first
+    #[default]
+    Forward,
+    /// Newest first, so a `limit` keeps the newest events
+    Backward,
+}
+
+/// Events a read returns out of its version range.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventFilter {
+    /// Only events of these types; any type if unset
+    #[serde(default)]
+    pub event_types: Option<Vec<String>>,
+    /// Only events whose metadata holds each of these key-value pairs
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl EventFilter {
+    /// Checks whether an event satisfies the filter.
+    pub fn matches(&self, event: &Event) -> bool {
+        self.event_types
+            .as_ref()
+            .is_none_or(|types| types.contains(&event.event_type))
+            && self
+                .metadata
+                .iter()
+                .all(|(key, value)| event.metadata.get(key) == Some(value))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetEventsRequest {
     pub stream_id: String,
     pub from_version: Option<i64>,
+    /// Start with the latest snapshot and read only the events after it
+    #[serde(default)]
+    pub from_snapshot: bool,
+    /// Last version to read, inclusive; up to the latest if unset
+    #[serde(default)]
+    pub to_version: Option<i64>,
+    /// Applied before `limit`, so a page holds up to `limit` matching events
+    #[serde(default, flatten)]
+    pub filter: EventFilter,
+    #[serde(default)]
+    pub direction: ReadDirection,
     pub limit: Option<i64>,
 }
 
@@ -52,127 +184,591 @@ pub struct GetEventsResponse {
     pub events: Vec<Event>,
     pub success: bool,
     pub message: String,
+    /// Whether versions from the requested one on are no longer retained,
+    /// so `events` starts later than requested
+    #[serde(default)]
+    pub truncated: bool,
+    /// Oldest version that can still be read; `None` for unknown streams
+    #[serde(default)]
+    pub first_available_version: Option<i64>,
+    /// Latest snapshot `events` continue from, when reading from a snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<Snapshot>,
+}
+
+/// Summary of an event stream.
+///
+/// Archived streams are reduced to this record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub stream_id: String,
+    /// Version of the latest event
+    pub version: i64,
+    /// Events retained, or held when the stream was archived
+    pub event_count: usize,
+    /// Principal that appended the stream's first event
+    pub created_by: Option<String>,
+    /// When the stream's first event was appended
+    pub created_at: DateTime<Utc>,
+    /// When the stream's latest event was appended
+    pub updated_at: DateTime<Utc>,
+    /// When the stream was archived; `None` for active streams
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// When the stream was deleted, keeping its events but rejecting
+    /// appends; `None` for streams not deleted
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Which events of a stream are kept when retention is enforced.
+///
+/// The latest event is always kept, so the stream keeps its version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Newest events kept
+    #[serde(default)]
+    pub max_events: Option<usize>,
+    /// Events appended longer ago than this are removed
+    #[serde(default)]
+    pub max_age_seconds: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Whether the policy keeps every event, clearing the stream's retention.
+    pub fn is_unbounded(&self) -> bool {
+        self.max_events.is_none() && self.max_age_seconds.is_none()
+    }
+
+    /// Oldest time an event kept by `max_age_seconds` was appended at, as
+    /// of `now`.
+    pub(crate) fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.max_age_seconds.map(|seconds| {
+            let age = chrono::Duration::try_seconds(seconds.min(i64::MAX as u64) as i64)
+                .unwrap_or(chrono::TimeDelta::MAX);
+            now.checked_sub_signed(age)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC)
+        })
+    }
+}
+
+/// State of a stream's aggregate as of a version, saved so it can be
+/// rehydrated without replaying the events up to that version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Snapshot {
+    pub stream_id: String,
+    /// Version of the latest event folded into `state`
+    pub version: i64,
+    #[sqlx(json)]
+    pub state: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Fails unless `snapshot` can replace `latest` on a stream at version
+/// `current`: its version must be an existing version of the stream, not
+/// older than the latest snapshot.
+pub(crate) fn check_snapshot(snapshot: &Snapshot, current: i64, latest: Option<i64>) -> Result<()> {
+    if snapshot.version < 1 || snapshot.version > current {
+        return Err(crate::SyrosError::EventStoreError(format!(
+            "Snapshot version {} is not a version of stream {}, which is at version {}",
+            snapshot.version, snapshot.stream_id, current
+        )));
+    }
+    match latest {
+        Some(latest) if latest > snapshot.version => Err(crate::SyrosError::Conflict(format!(
+            "Stream {} already has a snapshot at version {}",
+            snapshot.stream_id, latest
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Storage behind an [`EventStore`].
+#[derive(Clone)]
+enum EventBackend {
+    Postgres(PostgresEventLog),
+    Memory(MemoryEventLog),
+}
+
+impl EventBackend {
+    fn persistence(&self) -> &dyn EventPersistence {
+        match self {
+            EventBackend::Postgres(log) => log,
+            EventBackend::Memory(log) => log,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct EventStore {
-    pg: PostgresManager,
+    backend: EventBackend,
+    appended: broadcast::Sender<Arc<Event>>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl EventStore {
     pub fn new(pg: PostgresManager) -> Self {
-        Self { pg }
+        Self::with_backend(EventBackend::Postgres(PostgresEventLog::new(pg)))
     }
 
-    pub async fn append_event(&self, request: EventRequest) -> Result<EventResponse> {
-        let event_id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-        let pool = self.pg.get_pool();
+    /// Creates an event store that keeps its streams in memory.
+    pub fn in_memory() -> Self {
+        Self::with_backend(EventBackend::Memory(MemoryEventLog::new()))
+    }
 
-        // Transaction to ensure version consistency
-        let mut tx = pool
-            .begin()
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
-
-        // Get expected version (optimistic concurrency can be added here)
-        let version: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(version), 0) + 1 FROM events WHERE stream_id = $1",
-        )
-        .bind(&request.stream_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
-
-        sqlx::query(
-            "INSERT INTO events (id, stream_id, event_type, data, metadata, version, created_at) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7)",
-        )
-        .bind(Uuid::parse_str(&event_id).unwrap_or_default())
-        .bind(&request.stream_id)
-        .bind(&request.event_type)
-        .bind(sqlx::types::Json(request.data))
-        .bind(sqlx::types::Json(request.metadata))
-        .bind(version)
-        .bind(now)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
-
-        tx.commit()
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    fn with_backend(backend: EventBackend) -> Self {
+        let (appended, _) = broadcast::channel(SUBSCRIPTION_CAPACITY);
+        Self {
+            backend,
+            appended,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Subscribes to the events appended from now on to the streams
+    /// selected by `pattern`, a stream ID or a prefix followed by `*`.
+    pub fn subscribe(&self, pattern: &str) -> EventSubscription {
+        EventSubscription::new(pattern, self.appended.subscribe())
+    }
+
+    /// Name of the storage the streams are kept in.
+    pub fn backend_name(&self) -> &'static str {
+        match &self.backend {
+            EventBackend::Postgres(_) => "postgres",
+            EventBackend::Memory(_) => "memory",
+        }
+    }
+
+    /// Remembers the client-chosen IDs of each stream's latest `window`
+    /// events, which appends under the same ID are deduplicated against.
+    pub fn with_dedup_window(mut self, window: usize) -> Self {
+        self.backend = match self.backend {
+            EventBackend::Postgres(log) => EventBackend::Postgres(log.with_dedup_window(window)),
+            EventBackend::Memory(log) => EventBackend::Memory(log.with_dedup_window(window)),
+        };
+        self
+    }
+
+    /// Reports appended events and the size of the in-memory stream
+    /// directory to `metrics`.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Estimated bytes held by the streams kept in memory; 0 over Postgres.
+    pub async fn estimated_bytes(&self) -> u64 {
+        match &self.backend {
+            EventBackend::Postgres(_) => 0,
+            EventBackend::Memory(log) => log.estimated_bytes().await,
+        }
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    async fn record_directory_size(&self, log: &MemoryEventLog) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            let size = log.directory_size().await;
+            metrics.set_event_streams(size.active, size.archived);
+        }
+    }
+
+    /// Appends an event to its stream under the stream's next version.
+    ///
+    /// With an `expected_version`, the event is only appended if the stream
+    /// is still at that version, checked atomically with the append, and
+    /// fails with `VersionConflict` otherwise. Fails with `Conflict` if the
+    /// stream is archived and with `StreamDeleted` if it was deleted. The
+    /// stored event is published to subscribers and counted by the
+    /// `events_appended_total` metric; rejected and deduplicated appends are
+    /// not.
+    ///
+    /// With an `event_id`, a retried append is not stored twice: if one of
+    /// the stream's latest events, up to the dedup window, was appended under
+    /// the same ID, its version and position are returned instead, before
+    /// the expected version is checked. Fails with `ApiError` if the ID is
+    /// not a UUID.
+    ///
+    /// The event's metadata records the request's correlation and causation
+    /// IDs; without a correlation ID in the request or its metadata, a new
+    /// one is generated.
+    pub async fn append_event(&self, mut request: EventRequest) -> Result<EventResponse> {
+        let deduplicate = request.event_id.is_some();
+        let event_id = match &request.event_id {
+            Some(event_id) => Uuid::parse_str(event_id)
+                .map_err(|e| {
+                    crate::SyrosError::ApiError(format!("Invalid event ID {}: {}", event_id, e))
+                })?
+                .to_string(),
+            None => Uuid::new_v4().to_string(),
+        };
+        let metadata = request.stored_metadata();
+        let event = Event {
+            id: event_id.clone(),
+            stream_id: request.stream_id,
+            event_type: request.event_type,
+            data: request.data,
+            metadata,
+            timestamp: Utc::now(),
+            version: 0,
+            position: 0,
+        };
+        let published = (self.appended.receiver_count() > 0).then(|| event.clone());
+        let appended = self
+            .backend
+            .persistence()
+            .append(event, request.expected_version, deduplicate)
+            .await?;
+        if appended.duplicate {
+            return Ok(EventResponse {
+                event_id,
+                success: true,
+                message: "Event was already appended".to_string(),
+                version: appended.version,
+                position: appended.position,
+            });
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_events_appended();
+        }
+        if let EventBackend::Memory(log) = &self.backend {
+            self.record_directory_size(log).await;
+        }
+        if let Some(mut event) = published {
+            event.version = appended.version;
+            event.position = appended.position;
+            let _ = self.appended.send(Arc::new(event));
+        }
 
         Ok(EventResponse {
             event_id,
             success: true,
             message: "Event appended successfully".to_string(),
+            version: appended.version,
+            position: appended.position,
         })
     }
 
-    pub async fn get_events(&self, request: GetEventsRequest) -> Result<GetEventsResponse> {
-        let pool = self.pg.get_pool();
-        let mut query = "SELECT id::text, stream_id, event_type, data, metadata, created_at as timestamp, version::bigint FROM events WHERE stream_id = $1".to_string();
+    /// Reads the feed of all streams from `from_position` on, in the order
+    /// events were committed, up to `limit` events.
+    ///
+    /// Positions are strictly increasing but not contiguous: failed appends
+    /// and removed events leave gaps. Resume from the response's
+    /// `next_position` to read on without skipping or repeating events.
+    pub async fn read_all(&self, from_position: i64, limit: usize) -> Result<ReadAllResponse> {
+        let events = self
+            .backend
+            .persistence()
+            .read_all(from_position, limit)
+            .await?;
+        let next_position = events
+            .last()
+            .map_or(from_position, |event| event.position + 1);
+        Ok(ReadAllResponse {
+            events,
+            next_position,
+        })
+    }
 
-        if let Some(from_version) = request.from_version {
-            query.push_str(&format!(" AND version >= {}", from_version));
+    /// Stores an event exactly as given, keeping its ID, version, timestamp
+    /// and metadata.
+    ///
+    /// Fails with `Conflict` unless the event's version directly follows the
+    /// stream's current version, or if the stream is archived, and with
+    /// `StreamDeleted` if it was deleted.
+    pub async fn import_event(&self, event: Event) -> Result<()> {
+        self.backend.persistence().import(event).await?;
+        if let EventBackend::Memory(log) = &self.backend {
+            self.record_directory_size(log).await;
         }
+        Ok(())
+    }
 
-        query.push_str(" ORDER BY version ASC");
+    /// Reads a stream from `from_version` on, oldest first.
+    ///
+    /// When the requested versions were removed by cleanup or archival, the
+    /// response starts at the oldest retained event and is marked
+    /// `truncated`, rather than silently skipping the missing versions.
+    ///
+    /// With `from_snapshot`, the response carries the stream's latest
+    /// snapshot and reads only the events after it. Backward reads return
+    /// the newest events of the range first.
+    pub async fn get_events(&self, request: GetEventsRequest) -> Result<GetEventsResponse> {
+        let persistence = self.backend.persistence();
+        let snapshot = match request.from_snapshot {
+            true => persistence.latest_snapshot(&request.stream_id).await?,
+            false => None,
+        };
+        let from_version = match &snapshot {
+            Some(snapshot) => Some(request.from_version.unwrap_or(1).max(snapshot.version + 1)),
+            None => request.from_version,
+        };
+        let limit = request.limit.map(|limit| limit.max(0) as usize);
+        let events = persistence
+            .read(
+                &request.stream_id,
+                from_version,
+                request.to_version,
+                &request.filter,
+                request.direction,
+                limit,
+            )
+            .await?;
+        let first_available = persistence
+            .first_available_version(&request.stream_id)
+            .await?;
 
-        if let Some(limit) = request.limit {
-            query.push_str(&format!(" LIMIT {}", limit));
-        }
+        let mut response =
+            events_response(request.stream_id, events, from_version, first_available);
+        response.snapshot = snapshot;
+        Ok(response)
+    }
 
-        let events: Vec<Event> = sqlx::query_as(&query)
-            .bind(&request.stream_id)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    /// Saves the state of a stream's aggregate as of `version`, replacing
+    /// the stream's latest snapshot.
+    ///
+    /// Fails with `NotFound` for unknown streams, with `EventStoreError` if
+    /// the stream has no such version, with `Conflict` if the stream is
+    /// archived or already has a newer snapshot, and with `StreamDeleted` if
+    /// it was deleted.
+    pub async fn save_snapshot(
+        &self,
+        stream_id: &str,
+        version: i64,
+        state: serde_json::Value,
+    ) -> Result<Snapshot> {
+        let snapshot = Snapshot {
+            stream_id: stream_id.to_string(),
+            version,
+            state,
+            created_at: Utc::now(),
+        };
+        self.backend
+            .persistence()
+            .save_snapshot(snapshot.clone())
+            .await?;
+        Ok(snapshot)
+    }
 
-        if !events.is_empty() {
-            Ok(GetEventsResponse {
-                stream_id: request.stream_id,
-                events,
-                success: true,
-                message: "Events retrieved successfully".to_string(),
-            })
-        } else {
-            Ok(GetEventsResponse {
-                stream_id: request.stream_id,
-                events: Vec::new(),
-                success: true,
-                message: "No events found".to_string(),
-            })
-        }
+    /// The latest snapshot of a stream, if one was saved.
+    pub async fn get_latest_snapshot(&self, stream_id: &str) -> Result<Option<Snapshot>> {
+        self.backend.persistence().latest_snapshot(stream_id).await
     }
 
     pub async fn get_stream_version(&self, stream_id: &str) -> Result<i64> {
-        let pool = self.pg.get_pool();
-        let version: i64 =
-            sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM events WHERE stream_id = $1")
-                .bind(stream_id)
-                .fetch_one(pool)
-                .await
-                .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
-
-        Ok(version)
+        self.backend.persistence().version(stream_id).await
     }
 
     pub async fn get_stream_events_count(&self, stream_id: &str) -> Result<usize> {
-        let pool = self.pg.get_pool();
-        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE stream_id = $1")
-            .bind(stream_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        self.backend.persistence().event_count(stream_id).await
+    }
 
-        Ok(count as usize)
+    /// Version, size and creator of `stream_id`, or its archived record;
+    /// `None` if it has neither events nor a record.
+    pub async fn get_stream_info(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        self.backend.persistence().info(stream_id).await
     }
 
-    pub async fn cleanup_old_events(&self, _stream_id: &str, _keep_last: usize) -> Result<u64> {
+    /// Lists streams, archived or not, whose ID starts with `prefix`.
+    ///
+    /// Streams are listed in the order they were created, by the time of
+    /// their first event and then by ID. That time is recorded on the first
+    /// append and kept when old events are removed, so appends and retention
+    /// do not reorder streams, and streams created while paging with
+    /// `offset` land after the pages already read.
+    pub async fn list_streams(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> Result<Vec<StreamInfo>> {
+        self.backend.persistence().list(prefix, limit, offset).await
+    }
+
+    /// Drops all but the newest `keep_last` events of an in-memory stream.
+    ///
+    /// The stream keeps its version: reads from a removed version are
+    /// reported as `truncated` by [`get_events`](Self::get_events). Unless
+    /// `force` is set, fails with `Conflict` rather than drop events newer
+    /// than the stream's latest snapshot, which are needed to rehydrate it.
+    pub async fn cleanup_old_events(
+        &self,
+        stream_id: &str,
+        keep_last: usize,
+        force: bool,
+    ) -> Result<u64> {
+        if !force {
+            if let Some(snapshot) = self.get_latest_snapshot(stream_id).await? {
+                let last_dropped = self.get_stream_version(stream_id).await? - keep_last as i64;
+                if last_dropped > snapshot.version {
+                    return Err(crate::SyrosError::Conflict(format!(
+                        "Keeping {} events of stream {} would drop events after its snapshot at version {}",
+                        keep_last, stream_id, snapshot.version
+                    )));
+                }
+            }
+        }
+        if let EventBackend::Memory(log) = &self.backend {
+            return Ok(log.truncate_front(stream_id, keep_last).await);
+        }
+
         // This is complex in SQL without subqueries or window functions, but doable.
         // Simplified approach for now (no-op):
         Ok(0)
     }
+
+    /// Deletes a stream, archived or not, if it is at `expected_version`
+    /// when one is given.
+    ///
+    /// A soft delete keeps the stream's events readable, and its record
+    /// shows when it was deleted, but further appends fail with
+    /// `StreamDeleted`. A hard delete removes the stream with its events,
+    /// snapshot and retention policy, including its version: a stream later
+    /// appended under the same ID starts again at version 1.
+    ///
+    /// Returns whether the stream existed. Fails with `VersionConflict` if
+    /// the stream is at another version.
+    pub async fn delete_stream(
+        &self,
+        stream_id: &str,
+        expected_version: Option<u64>,
+        hard: bool,
+    ) -> Result<bool> {
+        let deleted = self
+            .backend
+            .persistence()
+            .delete(stream_id, expected_version, hard)
+            .await?;
+        if let EventBackend::Memory(log) = &self.backend {
+            self.record_directory_size(log).await;
+        }
+        Ok(deleted)
+    }
+
+    /// Sets which events of a stream are kept, replacing its previous
+    /// policy; an unbounded policy clears it.
+    ///
+    /// The policy can be set before the stream's first event. Events outside
+    /// it are removed by [`enforce_retention`](Self::enforce_retention),
+    /// which the `event_retention` background task runs. Fails with
+    /// `ApiError` if `max_events` is 0.
+    pub async fn set_retention(&self, stream_id: &str, policy: RetentionPolicy) -> Result<()> {
+        if policy.max_events == Some(0) {
+            return Err(crate::SyrosError::ApiError(
+                "max_events must be at least 1".to_string(),
+            ));
+        }
+        self.backend
+            .persistence()
+            .set_retention(stream_id, policy)
+            .await
+    }
+
+    /// Removes the events outside the retention policy of their stream, and
+    /// returns how many were removed.
+    ///
+    /// As with cleanup, each stream keeps its version, and reads from a
+    /// removed version are reported as `truncated`. Events newer than a
+    /// stream's latest snapshot are kept whatever the policy says, as
+    /// cleanup keeps them.
+    pub async fn enforce_retention(&self) -> Result<u64> {
+        let removed = self
+            .backend
+            .persistence()
+            .enforce_retention(Utc::now())
+            .await?;
+        if let EventBackend::Memory(log) = &self.backend {
+            self.record_directory_size(log).await;
+        }
+        Ok(removed)
+    }
+
+    /// Replaces a stream's events with a compact [`StreamInfo`] record.
+    ///
+    /// The stream keeps its version but rejects further appends. Returns the
+    /// record, or `None` for an unknown stream; archiving an archived stream
+    /// returns its existing record.
+    pub async fn archive_stream(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let info = self
+            .backend
+            .persistence()
+            .archive(stream_id, Utc::now())
+            .await?;
+        if let EventBackend::Memory(log) = &self.backend {
+            self.record_directory_size(log).await;
+        }
+        Ok(info)
+    }
+}
+
+/// Fails with `VersionConflict` unless a stream at version `current` is at
+/// the `expected` version, if one is given.
+pub(crate) fn check_expected_version(expected: Option<u64>, current: i64) -> Result<()> {
+    let actual = current.max(0) as u64;
+    match expected {
+        Some(expected) if expected != actual => {
+            Err(crate::SyrosError::VersionConflict { expected, actual })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Fails with `StreamDeleted` if `stream_id` was deleted in Postgres, and
+/// with `Conflict` if it is archived.
+pub(crate) async fn reject_closed(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    stream_id: &str,
+) -> Result<()> {
+    let (archived, deleted): (bool, bool) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM archived_streams WHERE stream_id = $1),
+                EXISTS (SELECT 1 FROM deleted_streams WHERE stream_id = $1)",
+    )
+    .bind(stream_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    if deleted {
+        return Err(crate::SyrosError::StreamDeleted(format!(
+            "Stream {} was deleted",
+            stream_id
+        )));
+    }
+    if archived {
+        return Err(crate::SyrosError::Conflict(format!(
+            "Stream {} is archived",
+            stream_id
+        )));
+    }
+    Ok(())
+}
+
+fn events_response(
+    stream_id: String,
+    events: Vec<Event>,
+    from_version: Option<i64>,
+    first_available_version: Option<i64>,
+) -> GetEventsResponse {
+    let truncated =
+        first_available_version.is_some_and(|first| first > 1 && from_version.unwrap_or(1) < first);
+    let message = match first_available_version {
+        Some(first) if truncated => {
+            format!("Events before version {} are no longer retained", first)
+        }
+        _ if events.is_empty() => "No events found".to_string(),
+        _ => "Events retrieved successfully".to_string(),
+    };
+
+    GetEventsResponse {
+        stream_id,
+        events,
+        success: true,
+        message,
+        truncated,
+        first_available_version,
+        snapshot: None,
+    }
 }
@@ -0,0 +1,90 @@
+//! Subscriptions to the events appended to an
+//! [`EventStore`](crate::core::EventStore).
+//!
+//! Appended events are published on a bounded broadcast channel, so
+//! appending never waits for subscribers: a subscriber that falls more than
+//! [`SUBSCRIPTION_CAPACITY`] events behind skips the ones it missed, and
+//! should re-read the stream to catch up. Only events appended through this
+//! instance are published.
+
+use crate::core::event_store::Event;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Appended events buffered for subscribers that have not received them yet.
+pub const SUBSCRIPTION_CAPACITY: usize = 1024;
+
+/// Whether `stream_id` is selected by `pattern`: the stream ID itself, or a
+/// prefix followed by `*`, e.g. `orders-*`. `*` alone selects every stream.
+pub fn stream_matches(pattern: &str, stream_id: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => stream_id.starts_with(prefix),
+        None => pattern == stream_id,
+    }
+}
+
+/// Events appended to the streams selected by a pattern, from the moment of
+/// subscribing on.
+pub struct EventSubscription {
+    pattern: String,
+    receiver: broadcast::Receiver<Arc<Event>>,
+}
+
+impl EventSubscription {
+    pub(crate) fn new(pattern: &str, receiver: broadcast::Receiver<Arc<Event>>) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            receiver,
+        }
+    }
+
+    /// Stream ID or prefix pattern the subscription selects.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The next event appended to a selected stream; `None` once the event
+    /// store is dropped.
+    pub async fn recv(&mut self) -> Option<Arc<Event>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if stream_matches(&self.pattern, &event.stream_id) => return Some(event),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        pattern = %self.pattern,
+                        "Event subscription fell behind and skipped {} events",
+                        skipped
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// The next event already appended to a selected stream, without
+    /// waiting; `None` if there is none yet.
+    pub fn try_recv(&mut self) -> Option<Arc<Event>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) if stream_matches(&self.pattern, &event.stream_id) => return Some(event),
+                Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_matches() {
+        assert!(stream_matches("orders-1", "orders-1"));
+        assert!(!stream_matches("orders-1", "orders-10"));
+        assert!(stream_matches("orders-*", "orders-10"));
+        assert!(!stream_matches("orders-*", "payments-1"));
+        assert!(stream_matches("*", "payments-1"));
+    }
+}
@@ -0,0 +1,440 @@
+//! NDJSON import and export of event streams.
+//!
+//! Exports write one JSON-encoded [`Event`] per line, oldest first, reading
+//! the stream a page at a time. Imports read the same format incrementally
+//! and store each event unchanged, so IDs, versions, timestamps and metadata
+//! survive a round trip.
+
+use crate::core::event_store::{Event, EventFilter, EventStore, GetEventsRequest, ReadDirection};
+use crate::core::{MetadataPolicy, NamespaceFreezes};
+use crate::{Result, SyrosError};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Events read from the store per page during an export.
+pub const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Content type of exported streams.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Longest line, newline included, an import accepts.
+pub const MAX_IMPORT_LINE_BYTES: usize = 1024 * 1024;
+
+/// Reads a stream page by page, oldest events first.
+pub fn export_pages(
+    store: EventStore,
+    stream_id: String,
+) -> impl Stream<Item = Result<Vec<Event>>> + Send + 'static {
+    futures::stream::try_unfold(Some(0), move |from_version| {
+        let store = store.clone();
+        let stream_id = stream_id.clone();
+        async move {
+            let Some(from_version) = from_version else {
+                return Ok(None);
+            };
+            let events = store
+                .get_events(GetEventsRequest {
+                    stream_id,
+                    from_version: Some(from_version),
+                    from_snapshot: false,
+                    to_version: None,
+                    filter: EventFilter::default(),
+                    direction: ReadDirection::Forward,
+                    limit: Some(EXPORT_PAGE_SIZE),
+                })
+                .await?
+                .events;
+            if events.is_empty() {
+                return Ok(None);
+            }
+
+            let next = match events.last() {
+                Some(last) if events.len() as i64 == EXPORT_PAGE_SIZE => Some(last.version + 1),
+                _ => None,
+            };
+            Ok(Some((events, next)))
+        }
+    })
+}
+
+/// Encodes events as NDJSON, one line per event.
+pub fn to_ndjson(events: &[Event]) -> Result<String> {
+    let mut ndjson = String::new();
+    for event in events {
+        let line = serde_json::to_string(event)
+            .map_err(|e| SyrosError::EventStoreError(format!("Failed to encode event: {}", e)))?;
+        ndjson.push_str(&line);
+        ndjson.push('\n');
+    }
+    Ok(ndjson)
+}
+
+/// Writes a stream to `writer` as NDJSON and returns the number of events written.
+pub async fn export_to<W: AsyncWrite + Unpin>(
+    store: &EventStore,
+    stream_id: &str,
+    writer: &mut W,
+) -> Result<u64> {
+    use futures::TryStreamExt;
+
+    let mut pages = std::pin::pin!(export_pages(store.clone(), stream_id.to_string()));
+    let mut written = 0;
+    while let Some(events) = pages.try_next().await? {
+        writer
+            .write_all(to_ndjson(&events)?.as_bytes())
+            .await
+            .map_err(|e| SyrosError::EventStoreError(format!("Failed to write export: {}", e)))?;
+        written += events.len() as u64;
+    }
+    Ok(written)
+}
+
+/// Options for an import.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    /// Renumber events whose version does not follow their stream instead of failing
+    pub force_renumber: bool,
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Events stored
+    pub imported: u64,
+    /// Events stored under a different version than the one in the file
+    pub renumbered: u64,
+    /// Streams that received events, in order of first appearance
+    pub streams: Vec<String>,
+}
+
+/// Incremental NDJSON importer.
+///
+/// Input can be fed in arbitrary chunks; complete lines are imported as soon
+/// as they arrive. Each event's version must directly follow the previous
+/// version of its stream, unless [`ImportOptions::force_renumber`] is set.
+/// A line longer than [`MAX_IMPORT_LINE_BYTES`] fails the import with
+/// `ValueTooLarge`. Events imported before a failing line are kept.
+pub struct EventImporter {
+    store: EventStore,
+    options: ImportOptions,
+    freezes: Option<NamespaceFreezes>,
+    metadata_policy: Option<MetadataPolicy>,
+    next_versions: HashMap<String, i64>,
+    pending: Vec<u8>,
+    line: u64,
+    summary: ImportSummary,
+}
+
+impl EventImporter {
+    pub fn new(store: EventStore, options: ImportOptions) -> Self {
+        Self {
+            store,
+            options,
+            freezes: None,
+            metadata_policy: None,
+            next_versions: HashMap::new(),
+            pending: Vec::new(),
+            line: 0,
+            summary: ImportSummary::default(),
+        }
+    }
+
+    /// Fails lines whose stream is in a namespace frozen in `freezes`.
+    pub fn with_namespace_freezes(mut self, freezes: NamespaceFreezes) -> Self {
+        self.freezes = Some(freezes);
+        self
+    }
+
+    /// Fails lines whose event metadata `policy` refuses.
+    pub fn with_metadata_policy(mut self, policy: MetadataPolicy) -> Self {
+        self.metadata_policy = Some(policy);
+        self
+    }
+
+    /// Imports every complete line in `bytes`, buffering any trailing partial line.
+    pub async fn import_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(bytes);
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.import_line(&line).await?;
+        }
+        if self.pending.len() > MAX_IMPORT_LINE_BYTES {
+            return Err(line_too_long(self.line + 1));
+        }
+        Ok(())
+    }
+
+    /// Imports the final line, if it had no trailing newline, and returns the summary.
+    pub async fn finish(mut self) -> Result<ImportSummary> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.import_line(&line).await?;
+        }
+        Ok(self.summary)
+    }
+
+    /// Imports everything readable from `reader`.
+    pub async fn import_from<R: AsyncRead + Unpin>(
+        mut self,
+        mut reader: R,
+    ) -> Result<ImportSummary> {
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = reader.read(&mut buffer).await.map_err(|e| {
+                SyrosError::EventStoreError(format!("Failed to read import: {}", e))
+            })?;
+            if read == 0 {
+                return self.finish().await;
+            }
+            self.import_bytes(&buffer[..read]).await?;
+        }
+    }
+
+    async fn import_line(&mut self, line: &[u8]) -> Result<()> {
+        self.line += 1;
+        let line_number = self.line;
+        if line.len() > MAX_IMPORT_LINE_BYTES {
+            return Err(line_too_long(line_number));
+        }
+        let line = std::str::from_utf8(line).map_err(|e| {
+            SyrosError::EventStoreError(format!("line {}: invalid UTF-8: {}", line_number, e))
+        })?;
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+
+        let mut event: Event = serde_json::from_str(line).map_err(|e| {
+            SyrosError::EventStoreError(format!("line {}: invalid event: {}", line_number, e))
+        })?;
+        if let Some(policy) = &self.metadata_policy {
+            policy
+                .check(&event.metadata)
+                .map_err(|e| SyrosError::EventStoreError(format!("line {}: {}", line_number, e)))?;
+        }
+        if let Some(freeze) = self
+            .freezes
+            .as_ref()
+            .and_then(|freezes| freezes.check(&event.stream_id))
+        {
+            return Err(SyrosError::Unavailable(format!(
+                "line {}: namespace {} is frozen until {}",
+                line_number,
+                freeze.namespace,
+                freeze.frozen_until.to_rfc3339()
+            )));
+        }
+        let expected = match self.next_versions.get(&event.stream_id) {
+            Some(version) => *version,
+            None => self.store.get_stream_version(&event.stream_id).await? + 1,
+        };
+        if event.version != expected {
+            if !self.options.force_renumber {
+                return Err(SyrosError::Conflict(format!(
+                    "line {}: stream {} expects version {} but the event has version {}",
+                    line_number, event.stream_id, expected, event.version
+                )));
+            }
+            event.version = expected;
+            self.summary.renumbered += 1;
+        }
+
+        let stream_id = event.stream_id.clone();
+        self.store
+            .import_event(event)
+            .await
+            .map_err(|e| at_line(line_number, e))?;
+
+        self.next_versions.insert(stream_id.clone(), expected + 1);
+        if !self.summary.streams.contains(&stream_id) {
+            self.summary.streams.push(stream_id);
+        }
+        self.summary.imported += 1;
+        Ok(())
+    }
+}
+
+/// The error failing an import at a line over [`MAX_IMPORT_LINE_BYTES`].
+fn line_too_long(line: u64) -> SyrosError {
+    SyrosError::ValueTooLarge(format!(
+        "line {}: longer than {} bytes",
+        line, MAX_IMPORT_LINE_BYTES
+    ))
+}
+
+/// Prefixes an import error with the line it occurred on.
+fn at_line(line: u64, error: SyrosError) -> SyrosError {
+    match error {
+        SyrosError::Conflict(msg) => SyrosError::Conflict(format!("line {}: {}", line, msg)),
+        SyrosError::StreamDeleted(msg) => {
+            SyrosError::StreamDeleted(format!("line {}: {}", line, msg))
+        }
+        SyrosError::EventStoreError(msg) => {
+            SyrosError::EventStoreError(format!("line {}: {}", line, msg))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event_store::EventRequest;
+
+    async fn seed(store: &EventStore, stream_id: &str, count: usize) {
+        for n in 0..count {
+            store
+                .append_event(EventRequest {
+                    stream_id: stream_id.to_string(),
+                    event_type: "order.updated".to_string(),
+                    data: serde_json::json!({ "n": n }),
+                    metadata: Some(HashMap::from([("source".to_string(), "test".to_string())])),
+                    created_by: None,
+                    expected_version: None,
+                    event_id: None,
+                    correlation_id: None,
+                    causation_id: None,
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn all_events(store: &EventStore, stream_id: &str) -> Vec<serde_json::Value> {
+        store
+            .get_events(GetEventsRequest {
+                stream_id: stream_id.to_string(),
+                from_version: None,
+                from_snapshot: false,
+                to_version: None,
+                filter: EventFilter::default(),
+                direction: ReadDirection::Forward,
+                limit: None,
+            })
+            .await
+            .unwrap()
+            .events
+            .iter()
+            .map(|event| serde_json::to_value(event).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source = EventStore::in_memory();
+        seed(&source, "orders", 1_234).await;
+        seed(&source, "payments", 3).await;
+
+        let mut ndjson = Vec::new();
+        assert_eq!(
+            export_to(&source, "orders", &mut ndjson).await.unwrap(),
+            1_234
+        );
+        assert_eq!(
+            export_to(&source, "payments", &mut ndjson).await.unwrap(),
+            3
+        );
+
+        // Feed the file in small chunks so lines straddle chunk boundaries.
+        let target = EventStore::in_memory();
+        let mut importer = EventImporter::new(target.clone(), ImportOptions::default());
+        for chunk in ndjson.chunks(7) {
+            importer.import_bytes(chunk).await.unwrap();
+        }
+        let summary = importer.finish().await.unwrap();
+        assert_eq!(summary.imported, 1_237);
+        assert_eq!(summary.renumbered, 0);
+        assert_eq!(summary.streams, vec!["orders", "payments"]);
+
+        for stream_id in ["orders", "payments"] {
+            assert_eq!(
+                all_events(&target, stream_id).await,
+                all_events(&source, stream_id).await
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_version_gaps_unless_forced() {
+        let source = EventStore::in_memory();
+        seed(&source, "orders", 5).await;
+        let mut ndjson = Vec::new();
+        export_to(&source, "orders", &mut ndjson).await.unwrap();
+        // Drop the first two events so the file starts at version 3.
+        let tail: Vec<&[u8]> = ndjson.split(|b| *b == b'\n').skip(2).collect();
+        let tail = tail.join(&b'\n');
+
+        let target = EventStore::in_memory();
+        let error = EventImporter::new(target.clone(), ImportOptions::default())
+            .import_from(tail.as_slice())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SyrosError::Conflict(msg) if msg.starts_with("line 1:")));
+        assert_eq!(target.get_stream_version("orders").await.unwrap(), 0);
+
+        let summary = EventImporter::new(
+            target.clone(),
+            ImportOptions {
+                force_renumber: true,
+            },
+        )
+        .import_from(tail.as_slice())
+        .await
+        .unwrap();
+        assert_eq!(summary.imported, 3);
+        assert_eq!(summary.renumbered, 3);
+
+        let imported = all_events(&target, "orders").await;
+        let versions: Vec<_> = imported.iter().map(|e| e["version"].clone()).collect();
+        assert_eq!(versions, vec![1, 2, 3]);
+        assert_eq!(imported[0]["data"], serde_json::json!({ "n": 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_invalid_lines() {
+        let target = EventStore::in_memory();
+        let error = EventImporter::new(target, ImportOptions::default())
+            .import_from(&b"\n{not json}\n"[..])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SyrosError::EventStoreError(msg) if msg.starts_with("line 2:")));
+    }
+
+    #[tokio::test]
+    async fn test_import_bounds_lines_and_checks_metadata() {
+        let mut importer = EventImporter::new(EventStore::in_memory(), ImportOptions::default());
+        importer.import_bytes(b"\n").await.unwrap();
+        let unterminated = vec![b' '; MAX_IMPORT_LINE_BYTES + 1];
+        let error = importer.import_bytes(&unterminated).await.unwrap_err();
+        assert!(matches!(error, SyrosError::ValueTooLarge(msg) if msg.starts_with("line 2:")));
+
+        let source = EventStore::in_memory();
+        source
+            .append_event(EventRequest {
+                stream_id: "orders-1".to_string(),
+                event_type: "order.updated".to_string(),
+                data: serde_json::json!({}),
+                metadata: Some(HashMap::from([("k".repeat(200), "v".to_string())])),
+                created_by: None,
+                expected_version: None,
+                event_id: None,
+                correlation_id: None,
+                causation_id: None,
+            })
+            .await
+            .unwrap();
+        let mut export = Vec::new();
+        export_to(&source, "orders-1", &mut export).await.unwrap();
+        let error = EventImporter::new(EventStore::in_memory(), ImportOptions::default())
+            .with_metadata_policy(MetadataPolicy::default())
+            .import_from(&export[..])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&error, SyrosError::EventStoreError(msg) if msg.starts_with("line 1: metadata.")),
+            "{:?}",
+            error
+        );
+    }
+}
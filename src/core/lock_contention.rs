@@ -0,0 +1,204 @@
+//! Per-key contention counters of the lock manager.
+//!
+//! Every acquisition attempt and release updates its key's counters, so
+//! operators can tell which keys are hot: how often they are acquired, how
+//! often acquiring them fails, and how long they are held. The counters live
+//! behind their own mutex, apart from the lock table and the wait queues, and
+//! a stats read only copies them out before computing averages.
+//!
+//! Hold times are measured in this process, from the acquisition to the
+//! release of the last hold; locks that expire or are released by another
+//! process do not count towards them.
+
+use crate::core::memory::ENTRY_OVERHEAD_BYTES;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Contention of a key, as shown by the lock stats endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyContention {
+    pub key: String,
+    /// Requests currently queued for the key
+    pub waiters: usize,
+    /// Successful acquisitions, reentrant ones included
+    pub acquisitions: u64,
+    /// Acquisition attempts that failed, including queued ones that timed out
+    pub failed_attempts: u64,
+    /// Mean time the key was held until released; `None` before any release
+    pub average_hold_seconds: Option<f64>,
+}
+
+/// Contention of the keys with recent lock activity, most contended first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockContentionStats {
+    pub keys: Vec<KeyContention>,
+}
+
+#[derive(Clone)]
+struct KeyCounters {
+    acquisitions: u64,
+    failed_attempts: u64,
+    releases: u64,
+    total_hold: Duration,
+    /// Lock ID and acquisition time of the lock currently held
+    held: Option<(String, Instant)>,
+    last_used: DateTime<Utc>,
+}
+
+impl KeyCounters {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            acquisitions: 0,
+            failed_attempts: 0,
+            releases: 0,
+            total_hold: Duration::ZERO,
+            held: None,
+            last_used: now,
+        }
+    }
+}
+
+/// Contention counters of every key with recent lock activity.
+#[derive(Clone, Default)]
+pub struct LockContention {
+    keys: Arc<Mutex<HashMap<String, KeyCounters>>>,
+}
+
+impl LockContention {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a successful acquisition of `key` under `lock_id`.
+    ///
+    /// Re-entering the lock already held under `lock_id` keeps the hold
+    /// timer running from the first acquisition.
+    pub fn acquired(&self, key: &str, lock_id: &str, now: DateTime<Utc>) {
+        let mut keys = self.keys.lock().unwrap();
+        let counters = keys
+            .entry(key.to_string())
+            .or_insert_with(|| KeyCounters::new(now));
+        counters.acquisitions += 1;
+        counters.last_used = now;
+        if !matches!(&counters.held, Some((held_id, _)) if held_id == lock_id) {
+            counters.held = Some((lock_id.to_string(), Instant::now()));
+        }
+    }
+
+    /// Counts a failed attempt at acquiring `key`.
+    pub fn failed(&self, key: &str, now: DateTime<Utc>) {
+        let mut keys = self.keys.lock().unwrap();
+        let counters = keys
+            .entry(key.to_string())
+            .or_insert_with(|| KeyCounters::new(now));
+        counters.failed_attempts += 1;
+        counters.last_used = now;
+    }
+
+    /// Ends the hold of `key` under `lock_id` and returns how long it lasted,
+    /// if it was acquired in this process.
+    pub fn released(&self, key: &str, lock_id: &str, now: DateTime<Utc>) -> Option<Duration> {
+        let mut keys = self.keys.lock().unwrap();
+        let counters = keys.get_mut(key)?;
+        counters.last_used = now;
+        let (_, acquired) = counters.held.take_if(|(held_id, _)| held_id == lock_id)?;
+        let hold = acquired.elapsed();
+        counters.releases += 1;
+        counters.total_hold += hold;
+        Some(hold)
+    }
+
+    /// Contention of every key with counters or `waiters`, most contended
+    /// first: by waiters, then failed attempts, then acquisitions.
+    pub fn stats(&self, waiters: &HashMap<String, usize>) -> LockContentionStats {
+        let mut counters: HashMap<String, KeyCounters> = self.keys.lock().unwrap().clone();
+        for key in waiters.keys() {
+            counters
+                .entry(key.clone())
+                .or_insert_with(|| KeyCounters::new(Utc::now()));
+        }
+
+        let mut keys: Vec<KeyContention> = counters
+            .into_iter()
+            .map(|(key, counters)| KeyContention {
+                waiters: waiters.get(&key).copied().unwrap_or(0),
+                acquisitions: counters.acquisitions,
+                failed_attempts: counters.failed_attempts,
+                average_hold_seconds: (counters.releases > 0)
+                    .then(|| counters.total_hold.as_secs_f64() / counters.releases as f64),
+                key,
+            })
+            .collect();
+        keys.sort_by(|a, b| {
+            (b.waiters, b.failed_attempts, b.acquisitions)
+                .cmp(&(a.waiters, a.failed_attempts, a.acquisitions))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        LockContentionStats { keys }
+    }
+
+    /// Estimated bytes held by the counters.
+    pub fn estimated_bytes(&self) -> usize {
+        let keys = self.keys.lock().unwrap();
+        keys.iter()
+            .map(|(key, counters)| {
+                let held = counters
+                    .held
+                    .as_ref()
+                    .map_or(0, |(lock_id, _)| lock_id.len());
+                key.len() + std::mem::size_of::<KeyCounters>() + ENTRY_OVERHEAD_BYTES + held
+            })
+            .sum()
+    }
+
+    /// Drops the counters of keys not held and unused for `idle_ttl` and
+    /// returns how many were reclaimed.
+    pub fn remove_idle(&self, now: DateTime<Utc>, idle_ttl: Duration) -> u64 {
+        let idle_ttl = chrono::Duration::from_std(idle_ttl).unwrap_or(chrono::Duration::MAX);
+        let mut keys = self.keys.lock().unwrap();
+        let before = keys.len();
+        keys.retain(|_, counters| counters.held.is_some() || now - counters.last_used < idle_ttl);
+        (before - keys.len()) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_acquisitions_failures_and_holds() {
+        let contention = LockContention::new();
+        let now = Utc::now();
+
+        contention.acquired("orders", "lock-1", now);
+        contention.failed("orders", now);
+        contention.failed("orders", now);
+        // Re-entering keeps the first hold running.
+        contention.acquired("orders", "lock-1", now);
+        assert!(contention.released("orders", "lock-2", now).is_none());
+        assert!(contention.released("orders", "lock-1", now).is_some());
+        assert!(contention.released("orders", "lock-1", now).is_none());
+        contention.acquired("payments", "lock-3", now);
+
+        let waiters = HashMap::from([("refunds".to_string(), 2)]);
+        let stats = contention.stats(&waiters);
+        let keys: Vec<_> = stats.keys.iter().map(|k| k.key.as_str()).collect();
+        assert_eq!(keys, ["refunds", "orders", "payments"]);
+        assert_eq!(stats.keys[0].waiters, 2);
+        assert_eq!(stats.keys[1].acquisitions, 2);
+        assert_eq!(stats.keys[1].failed_attempts, 2);
+        assert!(stats.keys[1].average_hold_seconds.is_some());
+        assert_eq!(stats.keys[2].average_hold_seconds, None);
+
+        // Only the held key survives the idle sweep.
+        let later = now + chrono::Duration::seconds(61);
+        assert_eq!(contention.remove_idle(later, Duration::from_secs(60)), 1);
+        let stats = contention.stats(&HashMap::new());
+        assert_eq!(stats.keys.len(), 1);
+        assert_eq!(stats.keys[0].key, "payments");
+    }
+}
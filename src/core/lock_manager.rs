@@ -3,14 +3,35 @@
 //! This module provides a distributed lock manager that allows multiple processes
 //! to coordinate access to shared resources by acquiring and releasing locks.
 
+use crate::config::{LockStorage, StorageConfig};
+use crate::core::lock_contention::{LockContention, LockContentionStats};
+use crate::core::lock_namespaces::{LockNamespaces, NamespaceLockStats};
+use crate::core::lock_queue::{LockPriority, LockQueueSnapshot, LockWaitQueues};
+use crate::core::lock_redis::RedisLockStore;
+use crate::core::lock_sessions::{LockSessions, SessionLock};
+use crate::core::lock_store::LockStore;
+use crate::core::lock_table::{
+    lock_expiry, LockExtension, LockRelease, MemoryLockTable, DEFAULT_IDLE_KEY_TTL,
+};
+use crate::core::namespace_freeze::namespace_of;
+use crate::core::task_tracker::TaskTracker;
+#[cfg(feature = "metrics")]
+use crate::metrics::{Metrics, MetricsHandle};
 use crate::storage::redis::RedisManager;
 use crate::Result;
 use chrono::{DateTime, Utc};
-use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Duration;
 use uuid::Uuid;
 
+/// How often the head of a wait queue retries on its own, to notice locks
+/// that expired or were released by another process.
+const WAITER_RECHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Deadline offset used for waits too long to add to the current instant.
+const FAR_FUTURE: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
 /// Represents the state of a distributed lock.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LockState {
@@ -26,6 +47,76 @@ pub struct LockState {
     pub expires_at: DateTime<Utc>,
     /// Optional metadata associated with the lock
     pub metadata: Option<String>,
+    /// Monotonically increasing token issued per key on every acquisition
+    #[serde(default)]
+    pub fence_token: u64,
+    /// Authenticated principal that acquired the lock; `None` without auth
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// Acquisitions by the owner not yet released; above 1 only for locks
+    /// acquired reentrantly
+    #[serde(default = "single_hold")]
+    pub hold_count: u32,
+}
+
+impl LockState {
+    /// Whether the lock has expired at `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Criteria for listing locks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockFilter {
+    /// Only return locks held by this owner
+    pub owner: Option<String>,
+    /// Only return locks whose key starts with this prefix
+    pub key_prefix: Option<String>,
+    /// Only return locks whose key matches this glob pattern, e.g.
+    /// `orders:*`; `*` matches any run of characters and `?` exactly one
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Only return locks acquired by this principal
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// Only return locks whose key is in this namespace, e.g. `team-a` for
+    /// `team-a/orders:1`
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Also return locks that expired recently
+    pub include_expired: bool,
+}
+
+impl LockFilter {
+    /// Checks whether a lock satisfies the filter at `now`.
+    pub fn matches(&self, lock: &LockState, now: DateTime<Utc>) -> bool {
+        if !self.include_expired && lock.is_expired(now) {
+            return false;
+        }
+        if let Some(owner) = &self.owner {
+            if &lock.owner != owner {
+                return false;
+            }
+        }
+        if let Some(prefix) = &self.key_prefix {
+            if !lock.key.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !glob_matches(pattern, &lock.key) {
+                return false;
+            }
+        }
+        if self.created_by.is_some() && lock.created_by != self.created_by {
+            return false;
+        }
+        if self.namespace.is_some() && namespace_of(&lock.key) != self.namespace.as_deref() {
+            return false;
+        }
+        true
+    }
 }
 
 /// Request to acquire a distributed lock.
@@ -41,6 +132,20 @@ pub struct LockRequest {
     pub owner: String,
     /// Maximum time to wait for lock acquisition
     pub wait_timeout: Option<Duration>,
+    /// Priority class among requests waiting for the same key
+    #[serde(default)]
+    pub priority: LockPriority,
+    /// Authenticated principal making the request, recorded on the lock
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// If the owner already holds the key, succeed with the held lock and
+    /// count one more hold instead of failing
+    #[serde(default)]
+    pub reentrant: bool,
+    /// Open client session the lock is bound to; closing the session
+    /// releases the lock
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Response from a lock acquisition attempt.
@@ -52,6 +157,23 @@ pub struct LockResponse {
     pub success: bool,
     /// Status message
     pub message: String,
+    /// Fencing token of the acquired lock; 0 if it was not acquired
+    #[serde(default)]
+    pub fence_token: u64,
+    /// Expiry of the lock held by another owner, when acquiring failed
+    #[serde(default)]
+    pub held_until: Option<DateTime<Utc>>,
+}
+
+impl LockResponse {
+    /// Time left at `now` until the lock that made acquiring fail expires,
+    /// rounded up to whole seconds; `None` if it was acquired or the holder
+    /// is gone.
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let held_until = self.held_until?;
+        let millis = (held_until - now).num_milliseconds().max(0) as u64;
+        Some(Duration::from_secs(millis.div_ceil(1000)))
+    }
 }
 
 /// Request to release a distributed lock.
@@ -72,24 +194,171 @@ pub struct ReleaseLockResponse {
     pub success: bool,
     /// Status message
     pub message: String,
+    /// Holds the owner still has on a reentrant lock; 0 once it is released
+    #[serde(default)]
+    pub remaining_holds: u32,
+}
+
+/// Request to extend the lease of a held lock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtendLockRequest {
+    /// Lock key/name
+    pub key: String,
+    /// Lock identifier returned on acquisition
+    pub lock_id: String,
+    /// Owner identifier
+    pub owner: String,
+    /// Time added to the lock's current expiry
+    pub ttl: Duration,
+}
+
+/// Response from a lock extension attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtendLockResponse {
+    /// Whether the lock was extended
+    pub success: bool,
+    /// Status message
+    pub message: String,
+    /// New expiry of the lock, when extended
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of [`LockManager::acquire_multi`] for one key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiLockResult {
+    /// Lock key/name
+    pub key: String,
+    /// Outcome of the acquisition; unsuccessful for every key once the
+    /// batch failed, including the locks that were rolled back
+    #[serde(flatten)]
+    pub response: LockResponse,
+}
+
+/// Response from an all-or-nothing acquisition of several locks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiLockResponse {
+    /// Whether every lock was acquired
+    pub success: bool,
+    /// Per-key outcomes, in the order the keys were acquired in
+    pub results: Vec<MultiLockResult>,
+}
+
+/// Lock stats: the contention of the keys with recent activity and the
+/// locks held in each namespace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockStats {
+    #[serde(flatten)]
+    pub contention: LockContentionStats,
+    /// Namespaces with held locks or recent activity, most locks first
+    pub namespaces: Vec<NamespaceLockStats>,
 }
 
 /// Distributed lock manager for coordinating access to shared resources.
 #[derive(Clone)]
 pub struct LockManager {
-    redis: RedisManager,
+    store: Arc<dyn LockStore>,
+    queues: LockWaitQueues,
+    contention: LockContention,
+    sessions: LockSessions,
+    namespaces: LockNamespaces,
+    /// How long the state kept per key outlives its last use
+    idle_ttl: Arc<std::sync::RwLock<Duration>>,
+    tasks: TaskTracker,
+    #[cfg(feature = "metrics")]
+    metrics: MetricsHandle,
 }
 
 impl LockManager {
-    /// Creates a new lock manager instance.
+    /// Creates a lock manager that keeps its locks in Redis.
     pub fn new(redis: RedisManager) -> Self {
-        Self { redis }
+        Self::with_store(Arc::new(RedisLockStore::new(redis)))
+    }
+
+    /// Creates a lock manager that keeps its locks in `store`.
+    pub fn with_store(store: Arc<dyn LockStore>) -> Self {
+        Self {
+            store,
+            queues: LockWaitQueues::new(),
+            contention: LockContention::new(),
+            sessions: LockSessions::new(),
+            namespaces: LockNamespaces::new(),
+            idle_ttl: Arc::new(std::sync::RwLock::new(DEFAULT_IDLE_KEY_TTL)),
+            tasks: TaskTracker::new(),
+            #[cfg(feature = "metrics")]
+            metrics: MetricsHandle::default(),
+        }
+    }
+
+    /// Creates a lock manager over the lock storage selected in `config`.
+    pub fn from_config(config: &StorageConfig) -> Result<Self> {
+        match config.locks {
+            LockStorage::Redis => Ok(Self::new(RedisManager::new(&config.redis.url)?)),
+            LockStorage::Memory => Ok(Self::in_memory()),
+        }
+    }
+
+    /// Creates a lock manager that keeps its locks in process memory.
+    ///
+    /// Locks are only shared between clones of this manager, not across processes.
+    pub fn in_memory() -> Self {
+        Self::with_lock_table(MemoryLockTable::new())
+    }
+
+    /// Creates a lock manager backed by the given in-memory lock table.
+    pub fn with_lock_table(table: MemoryLockTable) -> Self {
+        Self::with_store(Arc::new(table))
+    }
+
+    /// Name of the storage the locks are kept in.
+    pub fn backend_name(&self) -> &'static str {
+        self.store.name()
+    }
+
+    /// Caps every namespace at `limit` locks held through this process at
+    /// once; acquisitions beyond it fail with `NamespaceLimitExceeded`.
+    ///
+    /// The clones of this manager share their namespace counts, so the cap
+    /// applies to all of them.
+    pub fn with_namespace_limit(self, limit: Option<usize>) -> Self {
+        self.namespaces.set_limit(limit);
+        self
+    }
+
+    /// Keeps the fencing counters, wait-queue audit and contention counters
+    /// of a key for `idle_ttl` after its last use, instead of
+    /// [`DEFAULT_IDLE_KEY_TTL`].
+    ///
+    /// Applies to every clone of this manager, including those taken before.
+    pub fn with_idle_ttl(self, idle_ttl: Duration) -> Self {
+        *self.idle_ttl.write().unwrap() = idle_ttl;
+        self
+    }
+
+    /// Spawns the reaper through `tasks`.
+    pub fn with_task_tracker(mut self, tasks: TaskTracker) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Reports expired locks to `locks_expired_total`, reclaimed per-key
+    /// state to `idle_state_reclaimed_total`, queued acquisitions to
+    /// `lock_wait_duration_seconds` and `lock_queue_jumps_total`, queue
+    /// lengths to `lock_waiters` and hold times to
+    /// `lock_hold_duration_seconds`.
+    ///
+    /// Applies to every clone of this manager, including those taken before.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> Self {
+        self.metrics.set(metrics);
+        self
     }
 
     /// Attempts to acquire a distributed lock.
     ///
     /// This method tries to acquire a lock with the specified key. If a lock
-    /// already exists and hasn't expired, the acquisition will fail.
+    /// already exists and hasn't expired, the acquisition fails, unless the
+    /// request sets a `wait_timeout`: it then joins the key's wait queue and
+    /// acquires once it is served, or fails when the timeout elapses.
     ///
     /// # Arguments
     ///
@@ -98,43 +367,296 @@ impl LockManager {
     /// # Returns
     ///
     /// Returns a `LockResponse` indicating success or failure of the acquisition.
+    /// Fails with a `LockError` if the request names a session that is not
+    /// open, with an `ApiError` if the TTL is longer than
+    /// [`MAX_LOCK_TTL`](crate::core::lock_table::MAX_LOCK_TTL), or
+    /// with `NamespaceLimitExceeded` if the key's namespace already holds as
+    /// many locks as allowed.
     pub async fn acquire_lock(&self, request: LockRequest) -> Result<LockResponse> {
-        let mut conn = self.redis.get_connection().await?;
-        let lock_key = format!("syros:locks:{}", request.key);
-        let lock_id = Uuid::new_v4().to_string();
-        let ttl_ms = request.ttl.as_millis() as u64;
-
-        // Try to acquire lock using SET NX PX
-        let result: Option<String> = redis::cmd("SET")
-            .arg(&lock_key)
-            .arg(&lock_id)
-            .arg("NX")
-            .arg("PX")
-            .arg(ttl_ms)
-            .query_async(&mut conn)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
-
-        if result.is_some() {
-            // Lock acquired
-            Ok(LockResponse {
-                lock_id,
+        if let Some(session_id) = &request.session_id {
+            if !self.sessions.is_open(session_id) {
+                return Err(unknown_session(session_id));
+            }
+        }
+
+        let now = Utc::now();
+        let reserved =
+            self.namespaces
+                .reserve(&request.key, lock_expiry(now, request.ttl)?, now)?;
+        let acquired = self.acquire(&request).await;
+        if reserved && !acquired.as_ref().is_ok_and(|response| response.success) {
+            self.namespaces.cancel(&request.key);
+        }
+        let mut response = acquired?;
+        if !response.success {
+            self.contention.failed(&request.key, Utc::now());
+            // Read after the attempt, so the holder may have changed or left since.
+            response.held_until = self
+                .get_lock_status(&request.key)
+                .await?
+                .map(|lock| lock.expires_at);
+            return Ok(response);
+        }
+        self.contention
+            .acquired(&request.key, &response.lock_id, Utc::now());
+        // The TTL was checked on entry; the lock may have waited in the queue since.
+        let expires_at = lock_expiry(Utc::now(), request.ttl).unwrap_or(DateTime::<Utc>::MAX_UTC);
+        self.namespaces.acquired(&request.key, expires_at);
+
+        if let Some(session_id) = &request.session_id {
+            let lock = SessionLock {
+                key: request.key.clone(),
+                lock_id: response.lock_id.clone(),
+                owner: request.owner.clone(),
+            };
+            // The session closed while the lock was being acquired.
+            if !self.sessions.bind(session_id, lock.clone()) {
+                self.release_all_holds(lock).await?;
+                return Err(unknown_session(session_id));
+            }
+        }
+        Ok(response)
+    }
+
+    async fn acquire(&self, request: &LockRequest) -> Result<LockResponse> {
+        let Some(wait_timeout) = request.wait_timeout.filter(|t| !t.is_zero()) else {
+            return self.try_acquire(request).await;
+        };
+
+        // Only skip the queue when nobody is in it, so newcomers cannot
+        // overtake existing waiters. A reentrant holder must not queue behind
+        // the waiters for its own lock.
+        if !self.queues.has_waiters(&request.key) || self.reenters(request).await? {
+            let response = self.try_acquire(request).await?;
+            if response.success {
+                return Ok(response);
+            }
+        }
+
+        // A wait too long to represent has no deadline in practice.
+        let now = tokio::time::Instant::now();
+        let deadline = now
+            .checked_add(wait_timeout)
+            .unwrap_or_else(|| now + FAR_FUTURE);
+        let mut waiter = QueueTicket {
+            queues: &self.queues,
+            key: &request.key,
+            ticket: self
+                .queues
+                .enqueue(&request.key, &request.owner, request.priority, Utc::now()),
+            served: false,
+        };
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            metrics.observe_lock_waiters(self.queues.waiters(&request.key));
+        }
+        let notify = self.queues.notifier(&request.key);
+        loop {
+            let notified = notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.queues.is_next(&request.key, waiter.ticket) {
+                let response = self.try_acquire(request).await?;
+                if response.success {
+                    waiter.served = true;
+                    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+                    let acquisition = self.queues.granted(&request.key, waiter.ticket, Utc::now());
+                    #[cfg(feature = "metrics")]
+                    if let (Some(metrics), Some(acquisition)) = (self.metrics.get(), acquisition) {
+                        metrics.record_lock_wait(
+                            acquisition.priority.as_str(),
+                            acquisition.wait().as_secs_f64(),
+                            acquisition.overtook,
+                        );
+                    }
+                    return Ok(response);
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                return Ok(acquire_response(String::new(), None));
+            }
+            let _ = tokio::time::timeout(remaining.min(WAITER_RECHECK_INTERVAL), notified).await;
+        }
+    }
+
+    /// Acquires several locks, all or nothing.
+    ///
+    /// The locks are acquired one by one in key order, so concurrent batches
+    /// over overlapping keys queue on their first shared key instead of each
+    /// holding a key the other waits for. If any lock cannot be acquired
+    /// within its `wait_timeout`, the ones already acquired are released and
+    /// the batch fails; the remaining keys are not attempted.
+    ///
+    /// Fails with a `LockError` if a key appears twice.
+    pub async fn acquire_multi(&self, mut requests: Vec<LockRequest>) -> Result<MultiLockResponse> {
+        requests.sort_by(|a, b| a.key.cmp(&b.key));
+        if let Some(pair) = requests.windows(2).find(|pair| pair[0].key == pair[1].key) {
+            return Err(crate::SyrosError::LockError(format!(
+                "Lock {} is requested more than once",
+                pair[0].key
+            )));
+        }
+
+        let mut acquired: Vec<(&LockRequest, LockResponse)> = Vec::new();
+        let mut failure = None;
+        for request in &requests {
+            match self.acquire_lock(request.clone()).await {
+                Ok(response) if response.success => acquired.push((request, response)),
+                Ok(response) => {
+                    failure = Some((request, response));
+                    break;
+                }
+                Err(e) => {
+                    self.roll_back(&acquired).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        let Some((failed, response)) = failure else {
+            return Ok(MultiLockResponse {
                 success: true,
-                message: "Lock acquired successfully".to_string(),
-            })
-        } else {
-            // Lock already exists
-            Ok(LockResponse {
-                lock_id: String::new(),
-                success: false,
-                message: "Lock already exists".to_string(),
+                results: acquired
+                    .into_iter()
+                    .map(|(request, response)| MultiLockResult {
+                        key: request.key.clone(),
+                        response,
+                    })
+                    .collect(),
+            });
+        };
+
+        self.roll_back(&acquired).await;
+        let results = requests
+            .iter()
+            .map(|request| {
+                let message = if request.key == failed.key {
+                    response.message.clone()
+                } else if acquired.iter().any(|(held, _)| held.key == request.key) {
+                    format!("Released because lock {} was not acquired", failed.key)
+                } else {
+                    format!("Not attempted because lock {} was not acquired", failed.key)
+                };
+                MultiLockResult {
+                    key: request.key.clone(),
+                    response: LockResponse {
+                        lock_id: String::new(),
+                        success: false,
+                        message,
+                        fence_token: 0,
+                        held_until: if request.key == failed.key {
+                            response.held_until
+                        } else {
+                            None
+                        },
+                    },
+                }
             })
+            .collect();
+        Ok(MultiLockResponse {
+            success: false,
+            results,
+        })
+    }
+
+    /// Releases the locks of a failed batch, most recently acquired first.
+    async fn roll_back(&self, acquired: &[(&LockRequest, LockResponse)]) {
+        for (request, response) in acquired.iter().rev() {
+            let release = ReleaseLockRequest {
+                key: request.key.clone(),
+                lock_id: response.lock_id.clone(),
+                owner: request.owner.clone(),
+            };
+            if let Err(e) = self.release_lock(release).await {
+                tracing::error!(key = %request.key, "Failed to roll back batch lock: {}", e);
+            }
         }
     }
 
+    /// The wait queue and acquisition audit of `key` in this process.
+    pub fn lock_queue(&self, key: &str) -> LockQueueSnapshot {
+        self.queues.snapshot(key)
+    }
+
+    /// Estimated bytes held in this process by the locks, when kept in
+    /// memory, and by the wait queues, contention counters and sessions.
+    pub async fn estimated_bytes(&self) -> u64 {
+        let table = self.store.estimated_bytes().await;
+        let local = self.queues.estimated_bytes()
+            + self.contention.estimated_bytes()
+            + self.sessions.estimated_bytes()
+            + self.namespaces.estimated_bytes();
+        table + local as u64
+    }
+
+    /// Contention of the keys locked in this process: their current
+    /// waiters, acquisitions, failed attempts and average hold time.
+    ///
+    /// Reads counters kept apart from the lock table, so it never blocks
+    /// acquisitions or releases for longer than it takes to copy them.
+    pub fn get_contention_stats(&self) -> LockContentionStats {
+        self.contention.stats(&self.queues.waiter_counts())
+    }
+
+    /// The contention stats together with the locks held through this
+    /// process in each namespace, their limit and the acquisitions refused
+    /// for reaching it.
+    pub fn get_lock_stats(&self) -> LockStats {
+        let contention = self.get_contention_stats();
+        let namespaces = self.namespaces.stats(&contention, Utc::now());
+        LockStats {
+            contention,
+            namespaces,
+        }
+    }
+
+    /// Locks held through this process in `namespace`.
+    pub fn namespace_lock_count(&self, namespace: &str) -> usize {
+        self.namespaces.count(namespace, Utc::now())
+    }
+
+    /// Whether `request` would re-enter a lock its owner already holds.
+    async fn reenters(&self, request: &LockRequest) -> Result<bool> {
+        if !request.reentrant {
+            return Ok(false);
+        }
+        Ok(self
+            .get_lock_status(&request.key)
+            .await?
+            .is_some_and(|lock| lock.owner == request.owner))
+    }
+
+    /// Makes a single attempt at acquiring the lock.
+    async fn try_acquire(&self, request: &LockRequest) -> Result<LockResponse> {
+        let now = Utc::now();
+        let state = LockState {
+            id: Uuid::new_v4().to_string(),
+            key: request.key.clone(),
+            owner: request.owner.clone(),
+            acquired_at: now,
+            expires_at: lock_expiry(now, request.ttl)?,
+            metadata: request.metadata.clone(),
+            fence_token: 0,
+            created_by: request.created_by.clone(),
+            hold_count: 1,
+        };
+
+        Ok(
+            match self.store.acquire(state, request.reentrant, now).await? {
+                Some(held) => reentrant_response(held.id, held.fence_token, held.hold_count),
+                None => acquire_response(String::new(), None),
+            },
+        )
+    }
+
     /// Releases a distributed lock.
     ///
     /// This method releases a lock if the requester is the owner of the lock.
+    /// A lock acquired reentrantly is only removed once every hold on it has
+    /// been released; each earlier release just drops one hold.
     ///
     /// # Arguments
     ///
@@ -144,38 +666,100 @@ impl LockManager {
     ///
     /// Returns a `ReleaseLockResponse` indicating success or failure of the release.
     pub async fn release_lock(&self, request: ReleaseLockRequest) -> Result<ReleaseLockResponse> {
-        let mut conn = self.redis.get_connection().await?;
-        let lock_key = format!("syros:locks:{}", request.key);
-
-        // Lua script to safely release lock only if ID matches
-        let script = redis::Script::new(
-            r"
-            if redis.call('get', KEYS[1]) == ARGV[1] then
-                return redis.call('del', KEYS[1])
-            else
-                return 0
-            end
-            ",
-        );
+        let release = self.release(&request).await?;
+        if release == LockRelease::Released {
+            self.queues.notify(&request.key);
+            self.sessions.unbind(&request.lock_id);
+            self.namespaces.released(&request.key);
+            #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+            let hold = self
+                .contention
+                .released(&request.key, &request.lock_id, Utc::now());
+            #[cfg(feature = "metrics")]
+            if let (Some(metrics), Some(hold)) = (self.metrics.get(), hold) {
+                metrics.observe_lock_hold(hold.as_secs_f64());
+            }
+        }
+        Ok(release_response(release))
+    }
 
-        let result: i32 = script
-            .key(&lock_key)
-            .arg(&request.lock_id)
-            .invoke_async(&mut conn)
+    /// Opens the client session `session_id`, e.g. for a new WebSocket
+    /// connection, so lock requests can be bound to it.
+    pub fn open_session(&self, session_id: &str) {
+        self.sessions.open(session_id);
+    }
+
+    /// Closes the client session `session_id` and releases every lock still
+    /// bound to it, with all of its holds.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of locks released; locks that already expired or
+    /// were taken over are not counted.
+    pub async fn release_by_session(&self, session_id: &str) -> Result<u64> {
+        let mut released = 0;
+        for lock in self.sessions.close(session_id) {
+            if self.release_all_holds(lock).await? {
+                released += 1;
+            }
+        }
+        Ok(released)
+    }
+
+    /// Releases `lock` until no hold on it is left, returning whether it was
+    /// still held.
+    async fn release_all_holds(&self, lock: SessionLock) -> Result<bool> {
+        let request = ReleaseLockRequest {
+            key: lock.key,
+            lock_id: lock.lock_id,
+            owner: lock.owner,
+        };
+        loop {
+            let response = self.release_lock(request.clone()).await?;
+            if !response.success || response.remaining_holds == 0 {
+                return Ok(response.success);
+            }
+        }
+    }
+
+    async fn release(&self, request: &ReleaseLockRequest) -> Result<LockRelease> {
+        self.store
+            .release(&request.key, &request.lock_id, Utc::now())
             .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    }
 
-        if result == 1 {
-            Ok(ReleaseLockResponse {
-                success: true,
-                message: "Lock released successfully".to_string(),
-            })
-        } else {
-            Ok(ReleaseLockResponse {
-                success: false,
-                message: "Lock not found or ID mismatch".to_string(),
-            })
+    /// Extends the lease of a held lock.
+    ///
+    /// The lock's expiry is pushed forward by the requested TTL, provided the
+    /// lock has not expired and the lock ID and owner match the holder's.
+    /// Otherwise the response is unsuccessful and says why. Fails with a
+    /// lock error if the new expiry is out of range.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Extension request containing key, lock ID, owner and TTL
+    ///
+    /// # Returns
+    ///
+    /// Returns an `ExtendLockResponse` with the new expiry on success.
+    pub async fn extend_lock(&self, request: ExtendLockRequest) -> Result<ExtendLockResponse> {
+        let extension = self.extend(&request).await?;
+        if let LockExtension::Extended(expires_at) = extension {
+            self.namespaces.extended(&request.key, expires_at);
         }
+        Ok(extend_response(&request.key, extension))
+    }
+
+    async fn extend(&self, request: &ExtendLockRequest) -> Result<LockExtension> {
+        self.store
+            .extend(
+                &request.key,
+                &request.lock_id,
+                &request.owner,
+                request.ttl,
+                Utc::now(),
+            )
+            .await
     }
 
     /// Gets the current status of a lock.
@@ -190,46 +774,1254 @@ impl LockManager {
     /// # Returns
     ///
     /// Returns `Some(LockState)` if the lock exists and is active, `None` otherwise.
-    /// Gets the current status of a lock.
     pub async fn get_lock_status(&self, key: &str) -> Result<Option<LockState>> {
-        let mut conn = self.redis.get_connection().await?;
-        let lock_key = format!("syros:locks:{}", key);
+        self.store.get(key, Utc::now()).await
+    }
+
+    /// Checks a fencing token presented by a writer to a resource guarded by
+    /// the lock on `key`.
+    ///
+    /// Returns `true` only while `key` is held under `token`. Tokens strictly
+    /// increase per key, so a writer whose lock expired or was taken over
+    /// presents an older token and should be rejected.
+    pub async fn verify_fence(&self, key: &str, token: u64) -> Result<bool> {
+        Ok(self
+            .get_lock_status(key)
+            .await?
+            .is_some_and(|lock| lock.fence_token == token))
+    }
+
+    /// Lists locks matching the filter, oldest acquisition first.
+    ///
+    /// Recently expired locks are only returned when `include_expired` is set;
+    /// their state is retained for a few minutes after expiry.
+    pub async fn list_locks(&self, filter: &LockFilter) -> Result<Vec<LockState>> {
+        let mut locks = self.store.list(filter, Utc::now()).await?;
+        locks.sort_by(|a, b| a.acquired_at.cmp(&b.acquired_at).then(a.key.cmp(&b.key)));
+        Ok(locks)
+    }
+
+    /// Cleans up expired locks from the registry.
+    ///
+    /// Redis handles expiration automatically, so this is a no-op there; the
+    /// in-memory table drops expired locks one shard at a time, then
+    /// reclaims the fencing counters of keys idle for longer than the idle
+    /// TTL. The waiters queued on the keys of expired locks are woken.
+    ///
+    /// The audit of wait queues and the contention counters left idle for as
+    /// long, and the namespace counts of expired locks, are dropped on every
+    /// backend.
+    pub async fn cleanup_expired_locks(&self) -> Result<u64> {
+        let idle_ttl = *self.idle_ttl.read().unwrap();
+        #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+        let queues = self.queues.remove_idle(Utc::now(), idle_ttl);
+        #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+        let contention = self.contention.remove_idle(Utc::now(), idle_ttl);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            metrics.increment_idle_state_reclaimed("lock_queue", queues);
+            metrics.increment_idle_state_reclaimed("lock_contention", contention);
+        }
+        self.namespaces.remove_expired(Utc::now());
+
+        let cleanup = self.store.cleanup(Utc::now(), idle_ttl).await?;
+        for key in &cleanup.expired {
+            self.queues.notify(key);
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.get() {
+            metrics.increment_locks_expired(cleanup.expired.len() as u64);
+            metrics.increment_idle_state_reclaimed("lock_fence", cleanup.reclaimed);
+        }
+        Ok(cleanup.expired.len() as u64)
+    }
+
+    /// Spawns a reaper that runs [`cleanup_expired_locks`](Self::cleanup_expired_locks)
+    /// every `interval`, until the returned handle is aborted.
+    ///
+    /// The server schedules the same sweep as the `locks_sweep` background
+    /// task instead; this is for embedding the manager without one.
+    pub fn start_reaper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let locks = self.clone();
+        self.tasks.spawn_background("lock_reaper", async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = locks.cleanup_expired_locks().await {
+                    tracing::error!("Lock reaper failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// A place in a key's wait queue, given up when dropped unless served.
+struct QueueTicket<'a> {
+    queues: &'a LockWaitQueues,
+    key: &'a str,
+    ticket: u64,
+    served: bool,
+}
+
+impl Drop for QueueTicket<'_> {
+    fn drop(&mut self) {
+        if !self.served {
+            self.queues.leave(self.key, self.ticket);
+        }
+    }
+}
+
+fn unknown_session(session_id: &str) -> crate::SyrosError {
+    crate::SyrosError::LockError(format!("Lock session {} is not open", session_id))
+}
+
+fn acquire_response(lock_id: String, fence_token: Option<u64>) -> LockResponse {
+    match fence_token {
+        Some(fence_token) => LockResponse {
+            lock_id,
+            success: true,
+            message: "Lock acquired successfully".to_string(),
+            fence_token,
+            held_until: None,
+        },
+        None => LockResponse {
+            lock_id: String::new(),
+            success: false,
+            message: "Lock already exists".to_string(),
+            fence_token: 0,
+            held_until: None,
+        },
+    }
+}
+
+fn reentrant_response(lock_id: String, fence_token: u64, hold_count: u32) -> LockResponse {
+    if hold_count <= 1 {
+        return acquire_response(lock_id, Some(fence_token));
+    }
+    LockResponse {
+        lock_id,
+        success: true,
+        message: format!(
+            "Lock re-entered by its owner, now held {} times",
+            hold_count
+        ),
+        fence_token,
+        held_until: None,
+    }
+}
+
+fn release_response(release: LockRelease) -> ReleaseLockResponse {
+    match release {
+        LockRelease::Released => ReleaseLockResponse {
+            success: true,
+            message: "Lock released successfully".to_string(),
+            remaining_holds: 0,
+        },
+        LockRelease::HoldReleased(remaining_holds) => ReleaseLockResponse {
+            success: true,
+            message: format!("Lock hold released, {} still held", remaining_holds),
+            remaining_holds,
+        },
+        LockRelease::NotHeld => ReleaseLockResponse {
+            success: false,
+            message: "Lock not found or ID mismatch".to_string(),
+            remaining_holds: 0,
+        },
+    }
+}
+
+fn single_hold() -> u32 {
+    1
+}
+
+fn extend_response(key: &str, extension: LockExtension) -> ExtendLockResponse {
+    let (expires_at, message) = match extension {
+        LockExtension::Extended(expires_at) => (
+            Some(expires_at),
+            format!("Lock {} extended until {}", key, expires_at.to_rfc3339()),
+        ),
+        LockExtension::NotHeld => (None, format!("Lock {} has expired or is not held", key)),
+        LockExtension::HeldByOther => (
+            None,
+            format!("Lock {} is held under another lock ID or owner", key),
+        ),
+    };
+    ExtendLockResponse {
+        success: expires_at.is_some(),
+        message,
+        expires_at,
+    }
+}
+
+/// Part of a glob pattern before its first wildcard.
+pub(crate) fn glob_prefix(pattern: &str) -> &str {
+    let end = pattern.find(['*', '?']).unwrap_or(pattern.len());
+    &pattern[..end]
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters
+/// and `?` matches exactly one.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Escapes Redis glob metacharacters so a prefix is matched literally.
+pub(crate) fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(key: &str, owner: &str, expires_in_secs: i64) -> LockState {
+        let now = Utc::now();
+        LockState {
+            id: Uuid::new_v4().to_string(),
+            key: key.to_string(),
+            owner: owner.to_string(),
+            acquired_at: now,
+            expires_at: now + chrono::Duration::seconds(expires_in_secs),
+            metadata: None,
+            fence_token: 1,
+            created_by: None,
+            hold_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_lock_filter() {
+        let now = Utc::now();
+        let active = lock("orders:1", "worker-a", 30);
+        let expired = lock("orders:2", "worker-b", -30);
+
+        assert!(LockFilter::default().matches(&active, now));
+        assert!(!LockFilter::default().matches(&expired, now));
+
+        let include_expired = LockFilter {
+            include_expired: true,
+            ..Default::default()
+        };
+        assert!(include_expired.matches(&expired, now));
+
+        let by_owner = LockFilter {
+            owner: Some("worker-b".to_string()),
+            include_expired: true,
+            ..Default::default()
+        };
+        assert!(!by_owner.matches(&active, now));
+        assert!(by_owner.matches(&expired, now));
+
+        let by_prefix = LockFilter {
+            key_prefix: Some("orders:".to_string()),
+            ..Default::default()
+        };
+        assert!(by_prefix.matches(&active, now));
+        assert!(!by_prefix.matches(&lock("payments:1", "worker-a", 30), now));
+
+        let by_creator = LockFilter {
+            created_by: Some("alice".to_string()),
+            ..Default::default()
+        };
+        assert!(!by_creator.matches(&active, now));
+        let created = LockState {
+            created_by: Some("alice".to_string()),
+            ..lock("orders:3", "worker-a", 30)
+        };
+        assert!(by_creator.matches(&created, now));
+    }
+
+    /// Redis used by tests of the Redis backend, which are skipped when unset.
+    const TEST_REDIS_URL_ENV: &str = "SYROS_TEST_REDIS_URL";
+
+    fn storage(locks: LockStorage, url: &str) -> StorageConfig {
+        let mut config = StorageConfig {
+            locks,
+            ..Default::default()
+        };
+        config.redis.url = url.to_string();
+        config
+    }
+
+    #[test]
+    fn test_from_config_selects_lock_storage() {
+        // Creating the Redis backend does not connect yet.
+        let redis = storage(LockStorage::Redis, "redis://127.0.0.1:6379");
+        assert_eq!(
+            LockManager::from_config(&redis).unwrap().backend_name(),
+            "redis"
+        );
+        let memory = storage(LockStorage::Memory, "");
+        assert_eq!(
+            LockManager::from_config(&memory).unwrap().backend_name(),
+            "memory"
+        );
+        assert!(LockManager::from_config(&storage(LockStorage::Redis, "not a url")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redis_locks_are_exclusive_across_instances() {
+        let Ok(url) = std::env::var(TEST_REDIS_URL_ENV) else {
+            eprintln!("Skipping: set {} to run against Redis", TEST_REDIS_URL_ENV);
+            return;
+        };
+        // Two managers sharing nothing but Redis, as two processes would.
+        let config = storage(LockStorage::Redis, &url);
+        let first = LockManager::from_config(&config).unwrap();
+        let second = LockManager::from_config(&config).unwrap();
+        let key = format!("instances-{}", Uuid::new_v4());
+
+        let held = first
+            .acquire_lock(request(&key, "first", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert!(held.success);
+        let contended = second
+            .acquire_lock(request(&key, "second", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert!(!contended.success);
+
+        // Releasing needs the holder's lock ID, whichever instance asks.
+        let wrong_id = second
+            .release_lock(ReleaseLockRequest {
+                key: key.clone(),
+                lock_id: Uuid::new_v4().to_string(),
+                owner: "first".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(!wrong_id.success);
+        release(&second, &key, "first", held.lock_id).await;
+
+        let acquired = second
+            .acquire_lock(request(&key, "second", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert!(acquired.success);
+        assert!(acquired.fence_token > held.fence_token);
+        release(&second, &key, "second", acquired.lock_id).await;
+    }
+
+    #[tokio::test]
+    async fn test_fence_tokens_increase_across_expiry_and_reacquisition() {
+        let lock_manager = LockManager::in_memory();
+        let acquire = |owner: &str, ttl_ms: u64| LockRequest {
+            key: "ledger".to_string(),
+            ttl: Duration::from_millis(ttl_ms),
+            metadata: None,
+            owner: owner.to_string(),
+            wait_timeout: None,
+            priority: LockPriority::Normal,
+            created_by: None,
+            reentrant: false,
+            session_id: None,
+        };
+
+        let first = lock_manager.acquire_lock(acquire("a", 50)).await.unwrap();
+        assert!(first.success);
+        assert!(lock_manager
+            .verify_fence("ledger", first.fence_token)
+            .await
+            .unwrap());
+        let refused = lock_manager.acquire_lock(acquire("b", 50)).await.unwrap();
+        assert!(!refused.success);
+        assert_eq!(refused.fence_token, 0);
 
-        // In this simple implementation, we just check existence and return a dummy state
-        // To do this properly, we should store the state as JSON, as per design.
-        // For now, we return minimal info if it exists.
-        let lock_id: Option<String> = conn
-            .get(&lock_key)
+        // Take over after expiry: the stale holder's token is now rejected.
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let second = lock_manager
+            .acquire_lock(acquire("b", 30_000))
+            .await
+            .unwrap();
+        assert!(second.fence_token > first.fence_token);
+        assert!(!lock_manager
+            .verify_fence("ledger", first.fence_token)
+            .await
+            .unwrap());
+        assert!(lock_manager
+            .verify_fence("ledger", second.fence_token)
+            .await
+            .unwrap());
+        let status = lock_manager
+            .get_lock_status("ledger")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.fence_token, second.fence_token);
+
+        // Release and reacquire.
+        lock_manager
+            .release_lock(ReleaseLockRequest {
+                key: "ledger".to_string(),
+                lock_id: second.lock_id,
+                owner: "b".to_string(),
+            })
             .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+            .unwrap();
+        assert!(!lock_manager
+            .verify_fence("ledger", second.fence_token)
+            .await
+            .unwrap());
+        let third = lock_manager
+            .acquire_lock(acquire("c", 30_000))
+            .await
+            .unwrap();
+        assert!(third.fence_token > second.fence_token);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_rejects_ttl_beyond_maximum() {
+        let lock_manager = LockManager::in_memory();
+        for ttl in [Duration::from_secs(10_000_000_000_000), Duration::MAX] {
+            let result = lock_manager
+                .acquire_lock(LockRequest {
+                    ttl,
+                    ..request("huge-ttl", "a", LockPriority::Normal, 0)
+                })
+                .await;
+            assert!(matches!(result, Err(crate::SyrosError::ApiError(_))));
+        }
+        assert!(lock_manager
+            .get_lock_status("huge-ttl")
+            .await
+            .unwrap()
+            .is_none());
+
+        let longest = lock_manager
+            .acquire_lock(LockRequest {
+                ttl: crate::core::lock_table::MAX_LOCK_TTL,
+                ..request("huge-ttl", "a", LockPriority::Normal, 0)
+            })
+            .await
+            .unwrap();
+        assert!(longest.success);
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("orders:*", "orders:1"));
+        assert!(glob_matches("orders:*", "orders:"));
+        assert!(!glob_matches("orders:*", "payments:1"));
+        assert!(glob_matches("*:eu-?", "orders:eu-1"));
+        assert!(!glob_matches("*:eu-?", "orders:eu-12"));
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(!glob_matches("a*b*c", "axxbyy"));
+        assert!(glob_matches("exact", "exact"));
+        assert_eq!(glob_prefix("orders:*:eu"), "orders:");
+        assert_eq!(glob_prefix("orders"), "orders");
+    }
 
-        if let Some(id) = lock_id {
-            // Calculate TTL remaining
-            let ttl: i64 = conn
-                .ttl(&lock_key)
+    #[tokio::test]
+    async fn test_list_locks_by_pattern_sorted_by_acquisition() {
+        let lock_manager = LockManager::in_memory();
+        for (key, owner) in [
+            ("orders:2", "billing"),
+            ("payments:1", "billing"),
+            ("orders:1", "shipping"),
+            ("orders:3", "billing"),
+        ] {
+            let response = lock_manager
+                .acquire_lock(LockRequest {
+                    key: key.to_string(),
+                    ttl: Duration::from_secs(30),
+                    metadata: None,
+                    owner: owner.to_string(),
+                    wait_timeout: None,
+                    priority: LockPriority::Normal,
+                    created_by: None,
+                    reentrant: false,
+                    session_id: None,
+                })
                 .await
-                .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+                .unwrap();
+            assert!(response.success);
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        let keys = |locks: Vec<LockState>| locks.into_iter().map(|l| l.key).collect::<Vec<_>>();
+        let orders = LockFilter {
+            pattern: Some("orders:*".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            keys(lock_manager.list_locks(&orders).await.unwrap()),
+            vec!["orders:2", "orders:1", "orders:3"]
+        );
 
-            let now = Utc::now();
-            let expires_at = now + chrono::Duration::seconds(ttl);
+        let billing_orders = LockFilter {
+            owner: Some("billing".to_string()),
+            ..orders
+        };
+        assert_eq!(
+            keys(lock_manager.list_locks(&billing_orders).await.unwrap()),
+            vec!["orders:2", "orders:3"]
+        );
+    }
+
+    #[test]
+    fn test_escape_glob() {
+        assert_eq!(escape_glob("orders:"), "orders:");
+        assert_eq!(escape_glob("a*b?[c]"), "a\\*b\\?\\[c\\]");
+    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_in_memory_locks_under_concurrency() {
+        use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+        use std::sync::Arc;
 
-            Ok(Some(LockState {
-                id,
+        const TASKS: usize = 32;
+        const KEYS: usize = 8;
+        const ROUNDS: usize = 200;
+
+        let lock_manager = LockManager::in_memory();
+        let held: Arc<Vec<AtomicBool>> =
+            Arc::new((0..KEYS).map(|_| AtomicBool::new(false)).collect());
+        let acquisitions: Arc<Vec<AtomicU64>> =
+            Arc::new((0..KEYS).map(|_| AtomicU64::new(0)).collect());
+
+        let tasks: Vec<_> = (0..TASKS)
+            .map(|task| {
+                let lock_manager = lock_manager.clone();
+                let held = held.clone();
+                let acquisitions = acquisitions.clone();
+                tokio::spawn(async move {
+                    let owner = format!("task-{}", task);
+                    for round in 0..ROUNDS {
+                        let slot = (task + round) % KEYS;
+                        let key = format!("stress:{}", slot);
+                        let response = lock_manager
+                            .acquire_lock(LockRequest {
+                                key: key.clone(),
+                                ttl: Duration::from_secs(60),
+                                metadata: None,
+                                owner: owner.clone(),
+                                wait_timeout: None,
+                                priority: LockPriority::Normal,
+                                created_by: None,
+                                reentrant: false,
+                                session_id: None,
+                            })
+                            .await
+                            .unwrap();
+                        if !response.success {
+                            continue;
+                        }
+
+                        assert!(
+                            !held[slot].swap(true, Ordering::SeqCst),
+                            "{} held twice",
+                            key
+                        );
+                        acquisitions[slot].fetch_add(1, Ordering::SeqCst);
+                        tokio::task::yield_now().await;
+                        held[slot].store(false, Ordering::SeqCst);
+
+                        let released = lock_manager
+                            .release_lock(ReleaseLockRequest {
+                                key,
+                                lock_id: response.lock_id,
+                                owner: owner.clone(),
+                            })
+                            .await
+                            .unwrap();
+                        assert!(released.success);
+                    }
+                })
+            })
+            .collect();
+        for task in futures::future::join_all(tasks).await {
+            task.unwrap();
+        }
+
+        // Every successful acquisition issued exactly one fencing token.
+        for (slot, count) in acquisitions.iter().enumerate() {
+            let key = format!("stress:{}", slot);
+            let response = lock_manager
+                .acquire_lock(LockRequest {
+                    key: key.clone(),
+                    ttl: Duration::from_secs(60),
+                    metadata: None,
+                    owner: "checker".to_string(),
+                    wait_timeout: None,
+                    priority: LockPriority::Normal,
+                    created_by: None,
+                    reentrant: false,
+                    session_id: None,
+                })
+                .await
+                .unwrap();
+            assert!(response.success);
+            let state = lock_manager.get_lock_status(&key).await.unwrap().unwrap();
+            assert_eq!(state.fence_token, count.load(Ordering::SeqCst) + 1);
+        }
+        let locks = lock_manager
+            .list_locks(&LockFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(locks.len(), KEYS);
+    }
+
+    fn request(key: &str, owner: &str, priority: LockPriority, wait_ms: u64) -> LockRequest {
+        LockRequest {
+            key: key.to_string(),
+            ttl: Duration::from_secs(60),
+            metadata: None,
+            owner: owner.to_string(),
+            wait_timeout: Some(Duration::from_millis(wait_ms)),
+            priority,
+            created_by: None,
+            reentrant: false,
+            session_id: None,
+        }
+    }
+
+    async fn release(lock_manager: &LockManager, key: &str, owner: &str, lock_id: String) {
+        let released = lock_manager
+            .release_lock(ReleaseLockRequest {
                 key: key.to_string(),
-                owner: "unknown".to_string(), // We didn't store owner in main key
-                acquired_at: now,             // Approximate
-                expires_at,
-                metadata: None,
-            }))
-        } else {
-            Ok(None)
+                lock_id,
+                owner: owner.to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(released.success);
+    }
+
+    #[tokio::test]
+    async fn test_reentrant_holds_need_matching_releases() {
+        let lock_manager = LockManager::in_memory();
+        let reenter = |owner: &str| LockRequest {
+            reentrant: true,
+            ..request("jobs", owner, LockPriority::Normal, 0)
+        };
+        let release_hold = |lock_id: &str| ReleaseLockRequest {
+            key: "jobs".to_string(),
+            lock_id: lock_id.to_string(),
+            owner: "worker".to_string(),
+        };
+        let holds = || async {
+            lock_manager
+                .get_lock_status("jobs")
+                .await
+                .unwrap()
+                .map(|lock| lock.hold_count)
+        };
+
+        let first = lock_manager.acquire_lock(reenter("worker")).await.unwrap();
+        assert!(first.success);
+        let second = lock_manager.acquire_lock(reenter("worker")).await.unwrap();
+        assert!(second.success, "{}", second.message);
+        assert_eq!(second.lock_id, first.lock_id);
+        assert_eq!(second.fence_token, first.fence_token);
+        assert_eq!(holds().await, Some(2));
+
+        // Without opting in, the holder is refused like anyone else.
+        let plain = LockRequest {
+            reentrant: false,
+            ..reenter("worker")
+        };
+        assert!(!lock_manager.acquire_lock(plain).await.unwrap().success);
+        assert!(
+            !lock_manager
+                .acquire_lock(reenter("other"))
+                .await
+                .unwrap()
+                .success
+        );
+
+        let released = lock_manager
+            .release_lock(release_hold(&first.lock_id))
+            .await
+            .unwrap();
+        assert!(released.success);
+        assert_eq!(released.remaining_holds, 1);
+        assert_eq!(holds().await, Some(1));
+
+        // A waiter queues behind the holder, who re-enters without queueing.
+        let waiter = tokio::spawn({
+            let lock_manager = lock_manager.clone();
+            async move {
+                lock_manager
+                    .acquire_lock(request("jobs", "other", LockPriority::Normal, 2000))
+                    .await
+                    .unwrap()
+            }
+        });
+        while !lock_manager.queues.has_waiters("jobs") {
+            tokio::task::yield_now().await;
+        }
+        let third = lock_manager
+            .acquire_lock(LockRequest {
+                wait_timeout: Some(Duration::from_millis(100)),
+                ..reenter("worker")
+            })
+            .await
+            .unwrap();
+        assert!(third.success, "{}", third.message);
+        assert_eq!(third.lock_id, first.lock_id);
+        assert_eq!(holds().await, Some(2));
+
+        for remaining in [1, 0] {
+            let released = lock_manager
+                .release_lock(release_hold(&first.lock_id))
+                .await
+                .unwrap();
+            assert!(released.success);
+            assert_eq!(released.remaining_holds, remaining);
         }
+        let taken = waiter.await.unwrap();
+        assert!(taken.success);
+        assert!(taken.fence_token > first.fence_token);
+        assert!(
+            !lock_manager
+                .release_lock(release_hold(&first.lock_id))
+                .await
+                .unwrap()
+                .success
+        );
     }
 
-    /// Cleans up expired locks from the registry.
-    ///
-    /// Redis handles expiration automatically, so this is a no-op.
-    pub async fn cleanup_expired_locks(&self) -> Result<u64> {
-        Ok(0)
+    #[tokio::test]
+    async fn test_closing_a_session_releases_its_locks() {
+        let lock_manager = LockManager::in_memory();
+        let bound = |key: &str| LockRequest {
+            reentrant: true,
+            session_id: Some("ws-1".to_string()),
+            ..request(key, "worker", LockPriority::Normal, 0)
+        };
+
+        // Sessions must be open before locks are bound to them.
+        assert!(matches!(
+            lock_manager.acquire_lock(bound("jobs")).await,
+            Err(crate::SyrosError::LockError(_))
+        ));
+        assert!(lock_manager
+            .get_lock_status("jobs")
+            .await
+            .unwrap()
+            .is_none());
+
+        lock_manager.open_session("ws-1");
+        let jobs = lock_manager.acquire_lock(bound("jobs")).await.unwrap();
+        assert!(
+            lock_manager
+                .acquire_lock(bound("jobs"))
+                .await
+                .unwrap()
+                .success
+        );
+        let reports = lock_manager.acquire_lock(bound("reports")).await.unwrap();
+        let unbound = lock_manager
+            .acquire_lock(request("other", "worker", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        release(&lock_manager, "reports", "worker", reports.lock_id).await;
+
+        assert_eq!(lock_manager.release_by_session("ws-1").await.unwrap(), 1);
+        assert!(lock_manager
+            .get_lock_status("jobs")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(lock_manager
+            .get_lock_status("other")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(
+            !lock_manager
+                .release_lock(ReleaseLockRequest {
+                    key: "jobs".to_string(),
+                    lock_id: jobs.lock_id,
+                    owner: "worker".to_string(),
+                })
+                .await
+                .unwrap()
+                .success
+        );
+        assert!(lock_manager.acquire_lock(bound("jobs")).await.is_err());
+        release(&lock_manager, "other", "worker", unbound.lock_id).await;
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_batches_never_deadlock_or_both_succeed() {
+        let lock_manager = LockManager::in_memory();
+        // Listed in opposite orders, which would deadlock without sorting.
+        let batch = |owner: &str, keys: &[&str]| -> Vec<LockRequest> {
+            keys.iter()
+                .map(|key| request(key, owner, LockPriority::Normal, 200))
+                .collect()
+        };
+
+        for _ in 0..10 {
+            let (first, second) = tokio::time::timeout(Duration::from_secs(2), async {
+                tokio::join!(
+                    lock_manager.acquire_multi(batch("first", &["a", "b", "c"])),
+                    lock_manager.acquire_multi(batch("second", &["d", "c", "b"])),
+                )
+            })
+            .await
+            .expect("overlapping batches deadlocked");
+            let (first, second) = (first.unwrap(), second.unwrap());
+            assert!(first.success != second.success);
+
+            let (winner, owner) = if first.success {
+                (first, "first")
+            } else {
+                (second, "second")
+            };
+            let held = lock_manager
+                .list_locks(&LockFilter::default())
+                .await
+                .unwrap();
+            assert_eq!(held.len(), 3);
+            assert!(held.iter().all(|lock| lock.owner == owner));
+            for result in winner.results {
+                release(&lock_manager, &result.key, owner, result.response.lock_id).await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_acquisition_reports_holder_expiry() {
+        let lock_manager = LockManager::in_memory();
+        let held = lock_manager
+            .acquire_lock(request("orders", "other", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert_eq!(held.held_until, None);
+        assert_eq!(held.retry_after(Utc::now()), None);
+
+        let refused = lock_manager
+            .acquire_lock(request("orders", "worker", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert!(!refused.success);
+        let status = lock_manager
+            .get_lock_status("orders")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(refused.held_until, Some(status.expires_at));
+
+        // Rounded up to whole seconds, and never negative once expired.
+        let now = status.expires_at - chrono::Duration::milliseconds(1500);
+        assert_eq!(refused.retry_after(now), Some(Duration::from_secs(2)));
+        let later = status.expires_at + chrono::Duration::seconds(1);
+        assert_eq!(refused.retry_after(later), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_namespace_limit_counts_held_locks() {
+        let lock_manager = LockManager::in_memory().with_namespace_limit(Some(2));
+        let acquire = |key: &'static str, owner: &'static str| {
+            let lock_manager = lock_manager.clone();
+            async move {
+                lock_manager
+                    .acquire_lock(request(key, owner, LockPriority::Normal, 0))
+                    .await
+            }
+        };
+
+        let first = acquire("team-a/orders", "worker").await.unwrap();
+        assert!(acquire("team-a/payments", "worker").await.unwrap().success);
+        // A refused attempt at a held key and keys outside the namespace
+        // take no slot.
+        assert!(!acquire("team-a/orders", "other").await.unwrap().success);
+        assert!(acquire("team-b/orders", "worker").await.unwrap().success);
+        assert!(acquire("orders", "worker").await.unwrap().success);
+        assert!(matches!(
+            acquire("team-a/invoices", "worker").await,
+            Err(crate::SyrosError::NamespaceLimitExceeded(_))
+        ));
+        assert_eq!(lock_manager.namespace_lock_count("team-a"), 2);
+
+        release(&lock_manager, "team-a/orders", "worker", first.lock_id).await;
+        assert!(acquire("team-a/invoices", "worker").await.unwrap().success);
+
+        let filter = LockFilter {
+            namespace: Some("team-a".to_string()),
+            ..Default::default()
+        };
+        let keys: Vec<_> = lock_manager
+            .list_locks(&filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|lock| lock.key)
+            .collect();
+        assert_eq!(keys, ["team-a/payments", "team-a/invoices"]);
+
+        let stats = lock_manager.get_lock_stats();
+        let team_a = &stats.namespaces[0];
+        assert_eq!(team_a.namespace, "team-a");
+        assert_eq!(
+            (team_a.locks, team_a.limit, team_a.rejected),
+            (2, Some(2), 1)
+        );
+        assert_eq!((team_a.acquisitions, team_a.failed_attempts), (3, 1));
+        assert_eq!(stats.namespaces[1].namespace, "team-b");
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_reports_every_key() {
+        let lock_manager = LockManager::in_memory();
+        lock_manager
+            .acquire_lock(request("b", "other", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+
+        let batch = ["c", "a", "b"]
+            .iter()
+            .map(|key| request(key, "worker", LockPriority::Normal, 0))
+            .collect();
+        let response = lock_manager.acquire_multi(batch).await.unwrap();
+        assert!(!response.success);
+        let keys: Vec<_> = response.results.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert!(response.results.iter().all(|r| !r.response.success));
+        assert!(response.results[0].response.message.starts_with("Released"));
+        assert_eq!(response.results[1].response.message, "Lock already exists");
+        assert!(response.results[1].response.held_until.is_some());
+        assert!(response.results[0].response.held_until.is_none());
+        assert!(response.results[2]
+            .response
+            .message
+            .starts_with("Not attempted"));
+        assert!(lock_manager.get_lock_status("a").await.unwrap().is_none());
+
+        let duplicate = vec![
+            request("a", "worker", LockPriority::Normal, 0),
+            request("a", "worker", LockPriority::Normal, 0),
+        ];
+        assert!(lock_manager.acquire_multi(duplicate).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extend_lock() {
+        let lock_manager = LockManager::in_memory();
+        let acquired = lock_manager
+            .acquire_lock(LockRequest {
+                ttl: Duration::from_millis(300),
+                ..request("jobs", "worker", LockPriority::Normal, 0)
+            })
+            .await
+            .unwrap();
+        let extend = |lock_id: &str, owner: &str| ExtendLockRequest {
+            key: "jobs".to_string(),
+            lock_id: lock_id.to_string(),
+            owner: owner.to_string(),
+            ttl: Duration::from_secs(10),
+        };
+
+        let before = lock_manager.get_lock_status("jobs").await.unwrap().unwrap();
+        let extended = lock_manager
+            .extend_lock(extend(&acquired.lock_id, "worker"))
+            .await
+            .unwrap();
+        assert!(extended.success, "{}", extended.message);
+        assert_eq!(
+            extended.expires_at,
+            Some(before.expires_at + chrono::Duration::seconds(10))
+        );
+
+        // The lease outlives its original TTL.
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let after = lock_manager.get_lock_status("jobs").await.unwrap().unwrap();
+        assert_eq!(after.expires_at, extended.expires_at.unwrap());
+
+        // An expiry out of range is refused and leaves the lock as it was.
+        let overflowing = lock_manager
+            .extend_lock(ExtendLockRequest {
+                ttl: Duration::from_secs(u64::MAX),
+                ..extend(&acquired.lock_id, "worker")
+            })
+            .await;
+        assert!(matches!(overflowing, Err(crate::SyrosError::LockError(_))));
+        let unchanged = lock_manager.get_lock_status("jobs").await.unwrap().unwrap();
+        assert_eq!(unchanged.expires_at, after.expires_at);
+
+        let stolen = lock_manager
+            .extend_lock(extend(&acquired.lock_id, "intruder"))
+            .await
+            .unwrap();
+        assert!(!stolen.success);
+        assert!(stolen.message.contains("another"), "{}", stolen.message);
+
+        release(&lock_manager, "jobs", "worker", acquired.lock_id.clone()).await;
+        let released = lock_manager
+            .extend_lock(extend(&acquired.lock_id, "worker"))
+            .await
+            .unwrap();
+        assert!(!released.success);
+        assert!(released.message.contains("expired"), "{}", released.message);
+    }
+
+    #[tokio::test]
+    async fn test_waiter_acquires_when_held_lock_expires() {
+        let lock_manager = LockManager::in_memory();
+        let holder = lock_manager
+            .acquire_lock(LockRequest {
+                ttl: Duration::from_millis(500),
+                ..request("reports", "holder", LockPriority::Normal, 0)
+            })
+            .await
+            .unwrap();
+        assert!(holder.success);
+
+        // Without a wait timeout a held lock fails fast.
+        let started = tokio::time::Instant::now();
+        let contended = lock_manager
+            .try_acquire(&request("reports", "impatient", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert!(!contended.success);
+        let no_wait = lock_manager
+            .acquire_lock(LockRequest {
+                wait_timeout: None,
+                ..request("reports", "impatient", LockPriority::Normal, 0)
+            })
+            .await
+            .unwrap();
+        assert!(!no_wait.success);
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        // The holder never releases; the waiter gets the lock once it expires.
+        let waiter = lock_manager
+            .acquire_lock(request("reports", "waiter", LockPriority::Normal, 10_000))
+            .await
+            .unwrap();
+        assert!(waiter.success);
+        let waited = started.elapsed();
+        assert!(
+            waited >= Duration::from_millis(450) && waited < Duration::from_secs(2),
+            "waited {:?}",
+            waited
+        );
+        let state = lock_manager
+            .get_lock_status("reports")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.owner, "waiter");
+    }
+
+    #[tokio::test]
+    async fn test_waiter_with_unrepresentable_timeout_still_waits() {
+        let lock_manager = LockManager::in_memory();
+        let holder = lock_manager
+            .acquire_lock(LockRequest {
+                ttl: Duration::from_millis(200),
+                ..request("exports", "holder", LockPriority::Normal, 0)
+            })
+            .await
+            .unwrap();
+        assert!(holder.success);
+
+        let waiter = lock_manager
+            .acquire_lock(LockRequest {
+                wait_timeout: Some(Duration::MAX),
+                ..request("exports", "waiter", LockPriority::Normal, 0)
+            })
+            .await
+            .unwrap();
+        assert!(waiter.success);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_reaper_removes_expired_locks_until_stopped() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let lock_manager = LockManager::in_memory().with_metrics(metrics.clone());
+        let include_expired = LockFilter {
+            include_expired: true,
+            ..Default::default()
+        };
+        let acquire_briefly = |key: &'static str| {
+            lock_manager.acquire_lock(LockRequest {
+                ttl: Duration::from_millis(50),
+                ..request(key, "crashed", LockPriority::Normal, 0)
+            })
+        };
+
+        let reaper = lock_manager.start_reaper(Duration::from_millis(20));
+        assert!(acquire_briefly("a").await.unwrap().success);
+        assert!(acquire_briefly("b").await.unwrap().success);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(lock_manager
+            .list_locks(&include_expired)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(metrics.locks_expired_total.get(), 2.0);
+
+        reaper.abort();
+        assert!(reaper.await.unwrap_err().is_cancelled());
+        assert!(acquire_briefly("c").await.unwrap().success);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            lock_manager
+                .list_locks(&include_expired)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(metrics.locks_expired_total.get(), 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_key_state_follows_the_configured_ttl() {
+        let kept = LockManager::in_memory();
+        // A clone taken before the TTL is configured follows it too.
+        let dropped = LockManager::in_memory();
+        let _ = dropped.clone().with_idle_ttl(Duration::ZERO);
+        for lock_manager in [&kept, &dropped] {
+            let response = lock_manager
+                .acquire_lock(request("idle", "worker", LockPriority::Normal, 0))
+                .await
+                .unwrap();
+            release(lock_manager, "idle", "worker", response.lock_id).await;
+            lock_manager.cleanup_expired_locks().await.unwrap();
+        }
+
+        assert_eq!(kept.get_contention_stats().keys.len(), 1);
+        assert!(dropped.get_contention_stats().keys.is_empty());
+        // Fencing tokens keep growing once the key's counter is dropped.
+        let response = dropped
+            .acquire_lock(request("idle", "worker", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert_eq!(response.fence_token, 2);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_waiters_acquire_in_audited_order() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let lock_manager = LockManager::in_memory().with_metrics(metrics.clone());
+        let holder = lock_manager
+            .acquire_lock(request("orders", "holder", LockPriority::Normal, 0))
+            .await
+            .unwrap();
+        assert!(holder.success);
+
+        let (acquired_tx, mut acquired_rx) = tokio::sync::mpsc::unbounded_channel();
+        for (owner, priority) in [
+            ("first", LockPriority::Normal),
+            ("second", LockPriority::Normal),
+            ("urgent", LockPriority::High),
+        ] {
+            let lock_manager = lock_manager.clone();
+            let acquired_tx = acquired_tx.clone();
+            tokio::spawn(async move {
+                let response = lock_manager
+                    .acquire_lock(request("orders", owner, priority, 5_000))
+                    .await
+                    .unwrap();
+                assert!(response.success);
+                acquired_tx.send((owner, response.lock_id)).unwrap();
+            });
+            // Give each waiter a distinct, known enqueue time.
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        // A request that stops waiting leaves the queue without acquiring.
+        let impatient = lock_manager
+            .acquire_lock(request("orders", "impatient", LockPriority::High, 30))
+            .await
+            .unwrap();
+        assert!(!impatient.success);
+
+        let queue = lock_manager.lock_queue("orders");
+        let queued: Vec<_> = queue.waiters.iter().map(|w| w.owner.as_str()).collect();
+        assert_eq!(queued, ["urgent", "first", "second"]);
+        let enqueued: Vec<_> = ["first", "second", "urgent"]
+            .iter()
+            .map(|owner| {
+                queue
+                    .waiters
+                    .iter()
+                    .find(|w| w.owner == *owner)
+                    .unwrap()
+                    .enqueued_at
+            })
+            .collect();
+        assert!(enqueued.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let mut order = Vec::new();
+        let (mut owner, mut lock_id) = ("holder", holder.lock_id);
+        for _ in 0..3 {
+            release(&lock_manager, "orders", owner, lock_id).await;
+            (owner, lock_id) = acquired_rx.recv().await.unwrap();
+            order.push(owner);
+        }
+        assert_eq!(order, ["urgent", "first", "second"]);
+
+        let audit = lock_manager.lock_queue("orders");
+        assert!(audit.waiters.is_empty());
+        let audited: Vec<_> = audit
+            .recent_acquisitions
+            .iter()
+            .map(|a| (a.owner.as_str(), a.overtook))
+            .collect();
+        assert_eq!(audited, [("urgent", 2), ("first", 0), ("second", 0)]);
+        for (acquisition, enqueued_at) in
+            audit
+                .recent_acquisitions
+                .iter()
+                .zip([enqueued[2], enqueued[0], enqueued[1]])
+        {
+            assert_eq!(acquisition.enqueued_at, enqueued_at);
+            assert!(acquisition.acquired_at > acquisition.enqueued_at);
+        }
+        assert_eq!(audit.queue_jumps, 2);
+
+        assert_eq!(
+            metrics
+                .lock_queue_jumps_total
+                .with_label_values(&["high"])
+                .get(),
+            2.0
+        );
+        assert_eq!(
+            metrics
+                .lock_wait_duration
+                .with_label_values(&["normal"])
+                .get_sample_count(),
+            2
+        );
     }
 }
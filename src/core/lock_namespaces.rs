@@ -0,0 +1,307 @@
+//! Per-namespace lock counts of the lock manager.
+//!
+//! Lock keys are addressed as `namespace/key`, with the namespace being the
+//! part before the first `/` as for namespace freezes; keys without a `/`
+//! are in no namespace and never limited. Every acquisition in a namespace
+//! is counted until the lock is released or expires, so one namespace can be
+//! capped at a maximum number of live locks without scanning the storage.
+//!
+//! The counts cover the locks acquired through this process. Other
+//! instances sharing the same Redis keep their own counts, so the cap holds
+//! per instance.
+
+use crate::core::lock_contention::LockContentionStats;
+use crate::core::memory::ENTRY_OVERHEAD_BYTES;
+use crate::core::namespace_freeze::namespace_of;
+use crate::SyrosError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Lock activity of a namespace, as shown by the lock stats endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamespaceLockStats {
+    pub namespace: String,
+    /// Locks currently held in the namespace
+    pub locks: usize,
+    /// Most locks the namespace may hold at once; `None` if unlimited
+    pub limit: Option<usize>,
+    /// Acquisitions refused because the namespace was at its limit
+    pub rejected: u64,
+    /// Successful acquisitions of keys in the namespace with recent activity
+    pub acquisitions: u64,
+    /// Failed attempts at keys in the namespace with recent activity
+    pub failed_attempts: u64,
+}
+
+#[derive(Default)]
+struct NamespaceLocks {
+    /// Expiry of every lock counted in the namespace, by key
+    locks: HashMap<String, DateTime<Utc>>,
+    rejected: u64,
+}
+
+impl NamespaceLocks {
+    fn live(&self, now: DateTime<Utc>) -> usize {
+        self.locks
+            .values()
+            .filter(|expires_at| **expires_at > now)
+            .count()
+    }
+}
+
+#[derive(Default)]
+struct Namespaces {
+    limit: Option<usize>,
+    namespaces: HashMap<String, NamespaceLocks>,
+}
+
+/// Live lock counts of every namespace, shared by the clones of a lock
+/// manager.
+#[derive(Clone, Default)]
+pub struct LockNamespaces {
+    inner: Arc<Mutex<Namespaces>>,
+}
+
+impl LockNamespaces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caps every namespace at `limit` live locks; `None` lifts the cap.
+    pub fn set_limit(&self, limit: Option<usize>) {
+        self.inner.lock().unwrap().limit = limit;
+    }
+
+    /// Counts a lock about to be acquired on `key` until `expires_at`.
+    ///
+    /// Returns whether a new lock was counted, in which case a failed
+    /// acquisition must be [`cancel`](Self::cancel)led. A key already counted
+    /// takes no new slot, since it is either held by another owner or
+    /// re-entered by its holder. Fails with `NamespaceLimitExceeded` if the
+    /// namespace holds as many live locks as the limit.
+    pub fn reserve(
+        &self,
+        key: &str,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool, SyrosError> {
+        let Some(namespace) = namespace_of(key) else {
+            return Ok(false);
+        };
+        let mut inner = self.inner.lock().unwrap();
+        let limit = inner.limit;
+        let counted = inner.namespaces.entry(namespace.to_string()).or_default();
+        if counted.locks.contains_key(key) {
+            return Ok(false);
+        }
+        if let Some(limit) = limit {
+            if counted.locks.len() >= limit {
+                counted.locks.retain(|_, expires_at| *expires_at > now);
+            }
+            if counted.locks.len() >= limit {
+                counted.rejected += 1;
+                return Err(SyrosError::NamespaceLimitExceeded(format!(
+                    "Namespace {} already holds its maximum of {} locks",
+                    namespace, limit
+                )));
+            }
+        }
+        counted.locks.insert(key.to_string(), expires_at);
+        Ok(true)
+    }
+
+    /// Stops counting a lock reserved on `key` that was not acquired.
+    pub fn cancel(&self, key: &str) {
+        self.released(key);
+    }
+
+    /// Records that the lock on `key` now lasts until at least `expires_at`.
+    pub fn acquired(&self, key: &str, expires_at: DateTime<Utc>) {
+        let Some(namespace) = namespace_of(key) else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        let counted = inner.namespaces.entry(namespace.to_string()).or_default();
+        let held_until = counted.locks.entry(key.to_string()).or_insert(expires_at);
+        *held_until = (*held_until).max(expires_at);
+    }
+
+    /// Records the new expiry of an extended lock on `key`.
+    pub fn extended(&self, key: &str, expires_at: DateTime<Utc>) {
+        let Some(namespace) = namespace_of(key) else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(held_until) = inner
+            .namespaces
+            .get_mut(namespace)
+            .and_then(|counted| counted.locks.get_mut(key))
+        {
+            *held_until = expires_at;
+        }
+    }
+
+    /// Stops counting the lock on `key`, once its last hold is released.
+    pub fn released(&self, key: &str) {
+        let Some(namespace) = namespace_of(key) else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(counted) = inner.namespaces.get_mut(namespace) {
+            counted.locks.remove(key);
+        }
+    }
+
+    /// Stops counting the locks that expired by `now` and drops namespaces
+    /// left without locks or rejections.
+    pub fn remove_expired(&self, now: DateTime<Utc>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.namespaces.retain(|_, counted| {
+            counted.locks.retain(|_, expires_at| *expires_at > now);
+            !counted.locks.is_empty() || counted.rejected > 0
+        });
+    }
+
+    /// Live locks in `namespace` at `now`.
+    pub fn count(&self, namespace: &str, now: DateTime<Utc>) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .namespaces
+            .get(namespace)
+            .map_or(0, |counted| counted.live(now))
+    }
+
+    /// Activity of every namespace with counted locks, rejections or
+    /// contention, most locks first.
+    pub fn stats(
+        &self,
+        contention: &LockContentionStats,
+        now: DateTime<Utc>,
+    ) -> Vec<NamespaceLockStats> {
+        let (limit, mut stats) = {
+            let inner = self.inner.lock().unwrap();
+            let stats: HashMap<String, NamespaceLockStats> = inner
+                .namespaces
+                .iter()
+                .map(|(namespace, counted)| {
+                    let stats = NamespaceLockStats {
+                        namespace: namespace.clone(),
+                        locks: counted.live(now),
+                        limit: inner.limit,
+                        rejected: counted.rejected,
+                        acquisitions: 0,
+                        failed_attempts: 0,
+                    };
+                    (namespace.clone(), stats)
+                })
+                .collect();
+            (inner.limit, stats)
+        };
+        for key in &contention.keys {
+            let Some(namespace) = namespace_of(&key.key) else {
+                continue;
+            };
+            let stats = stats
+                .entry(namespace.to_string())
+                .or_insert_with(|| NamespaceLockStats {
+                    namespace: namespace.to_string(),
+                    locks: 0,
+                    limit,
+                    rejected: 0,
+                    acquisitions: 0,
+                    failed_attempts: 0,
+                });
+            stats.acquisitions += key.acquisitions;
+            stats.failed_attempts += key.failed_attempts;
+        }
+
+        let mut stats: Vec<NamespaceLockStats> = stats.into_values().collect();
+        stats.sort_by(|a, b| {
+            b.locks
+                .cmp(&a.locks)
+                .then_with(|| a.namespace.cmp(&b.namespace))
+        });
+        stats
+    }
+
+    /// Estimated bytes held by the counts.
+    pub fn estimated_bytes(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .namespaces
+            .iter()
+            .map(|(namespace, counted)| {
+                namespace.len()
+                    + ENTRY_OVERHEAD_BYTES
+                    + counted
+                        .locks
+                        .keys()
+                        .map(|key| key.len() + ENTRY_OVERHEAD_BYTES)
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::lock_contention::KeyContention;
+    use chrono::Duration;
+
+    #[test]
+    fn test_limit_counts_live_locks_per_namespace() {
+        let namespaces = LockNamespaces::new();
+        namespaces.set_limit(Some(2));
+        let now = Utc::now();
+        let later = now + Duration::seconds(30);
+
+        assert!(namespaces.reserve("team-a/1", later, now).unwrap());
+        assert!(namespaces
+            .reserve("team-a/2", now + Duration::seconds(1), now)
+            .unwrap());
+        // Held keys take no new slot, other namespaces and plain keys are apart.
+        assert!(!namespaces.reserve("team-a/1", later, now).unwrap());
+        assert!(namespaces.reserve("team-b/1", later, now).unwrap());
+        assert!(!namespaces.reserve("no-namespace", later, now).unwrap());
+        assert!(matches!(
+            namespaces.reserve("team-a/3", later, now),
+            Err(SyrosError::NamespaceLimitExceeded(_))
+        ));
+
+        // Released and expired locks free their slots.
+        namespaces.released("team-a/1");
+        assert!(namespaces.reserve("team-a/3", later, now).unwrap());
+        let after_expiry = now + Duration::seconds(2);
+        assert!(namespaces.reserve("team-a/4", later, after_expiry).unwrap());
+        assert_eq!(namespaces.count("team-a", after_expiry), 2);
+
+        let contention = LockContentionStats {
+            keys: vec![KeyContention {
+                key: "team-c/1".to_string(),
+                waiters: 0,
+                acquisitions: 3,
+                failed_attempts: 1,
+                average_hold_seconds: None,
+            }],
+        };
+        let stats = namespaces.stats(&contention, after_expiry);
+        let names: Vec<_> = stats.iter().map(|s| s.namespace.as_str()).collect();
+        assert_eq!(names, ["team-a", "team-b", "team-c"]);
+        assert_eq!((stats[0].locks, stats[0].rejected), (2, 1));
+        assert_eq!(stats[0].limit, Some(2));
+        assert_eq!((stats[2].acquisitions, stats[2].failed_attempts), (3, 1));
+
+        namespaces.remove_expired(now + Duration::seconds(60));
+        assert_eq!(namespaces.count("team-a", now), 0);
+        assert_eq!(
+            namespaces
+                .stats(&LockContentionStats { keys: vec![] }, now)
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Per-key queues of lock requests waiting for a held lock.
+//!
+//! A request that is willing to wait takes a ticket in its key's queue and is
+//! only allowed to try the lock once it is at the head. Waiters are served by
+//! priority class first and arrival order second, so a later, higher-priority
+//! waiter may overtake earlier ones; every such overtake is a queue jump.
+//!
+//! Each key keeps an audit of its recent acquisitions through the queue
+//! (enqueue time, acquisition time and how many earlier waiters were
+//! overtaken) so the serving order can be checked after the fact.
+
+use crate::core::memory::{serialized_size, ENTRY_OVERHEAD_BYTES};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Number of acquisitions kept in each key's audit.
+pub const LOCK_QUEUE_AUDIT_LEN: usize = 100;
+
+/// Priority class of a lock request; higher classes are served first.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LockPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl LockPriority {
+    /// Label used for the priority class in metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockPriority::Low => "low",
+            LockPriority::Normal => "normal",
+            LockPriority::High => "high",
+        }
+    }
+}
+
+/// A request waiting in a key's queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedWaiter {
+    /// Arrival order across all keys
+    pub ticket: u64,
+    pub owner: String,
+    pub priority: LockPriority,
+    pub enqueued_at: DateTime<Utc>,
+}
+
+impl QueuedWaiter {
+    /// Whether this waiter arrived before `other`.
+    fn arrived_before(&self, other: &QueuedWaiter) -> bool {
+        (self.enqueued_at, self.ticket) < (other.enqueued_at, other.ticket)
+    }
+}
+
+/// Audit record of a waiter that acquired the lock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedAcquisition {
+    pub owner: String,
+    pub priority: LockPriority,
+    pub enqueued_at: DateTime<Utc>,
+    pub acquired_at: DateTime<Utc>,
+    /// Earlier waiters still queued when this one acquired
+    pub overtook: u64,
+}
+
+impl QueuedAcquisition {
+    /// Time spent in the queue.
+    pub fn wait(&self) -> Duration {
+        (self.acquired_at - self.enqueued_at)
+            .to_std()
+            .unwrap_or_default()
+    }
+}
+
+/// Current queue and audit of a key, as shown by the admin endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockQueueSnapshot {
+    pub key: String,
+    /// Waiters in the order they will be served
+    pub waiters: Vec<QueuedWaiter>,
+    /// Most recent acquisitions through the queue, oldest first
+    pub recent_acquisitions: Vec<QueuedAcquisition>,
+    /// Acquisitions through the queue since the key was first queued on
+    pub acquisitions: u64,
+    /// Times a waiter acquired before an earlier-arriving one
+    pub queue_jumps: u64,
+}
+
+struct KeyQueue {
+    /// Kept in serving order: priority class, then arrival
+    waiters: Vec<QueuedWaiter>,
+    recent: VecDeque<QueuedAcquisition>,
+    acquisitions: u64,
+    queue_jumps: u64,
+    notify: Arc<Notify>,
+    last_used: DateTime<Utc>,
+}
+
+impl KeyQueue {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            waiters: Vec::new(),
+            recent: VecDeque::new(),
+            acquisitions: 0,
+            queue_jumps: 0,
+            notify: Arc::new(Notify::new()),
+            last_used: now,
+        }
+    }
+}
+
+/// Wait queues of every key with waiters or recent queued acquisitions.
+#[derive(Clone, Default)]
+pub struct LockWaitQueues {
+    keys: Arc<Mutex<HashMap<String, KeyQueue>>>,
+    next_ticket: Arc<AtomicU64>,
+}
+
+impl LockWaitQueues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `owner` for `key` and returns its ticket.
+    pub fn enqueue(
+        &self,
+        key: &str,
+        owner: &str,
+        priority: LockPriority,
+        now: DateTime<Utc>,
+    ) -> u64 {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed) + 1;
+        let waiter = QueuedWaiter {
+            ticket,
+            owner: owner.to_string(),
+            priority,
+            enqueued_at: now,
+        };
+
+        let mut keys = self.keys.lock().unwrap();
+        let queue = keys
+            .entry(key.to_string())
+            .or_insert_with(|| KeyQueue::new(now));
+        let position = queue
+            .waiters
+            .iter()
+            .position(|queued| {
+                queued.priority < priority
+                    || (queued.priority == priority && waiter.arrived_before(queued))
+            })
+            .unwrap_or(queue.waiters.len());
+        queue.waiters.insert(position, waiter);
+        queue.last_used = now;
+        ticket
+    }
+
+    /// Whether anyone is waiting for `key`.
+    pub fn has_waiters(&self, key: &str) -> bool {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|queue| !queue.waiters.is_empty())
+    }
+
+    /// Number of requests waiting for `key`.
+    pub fn waiters(&self, key: &str) -> usize {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_or(0, |queue| queue.waiters.len())
+    }
+
+    /// Number of requests waiting for each key with waiters.
+    pub fn waiter_counts(&self) -> HashMap<String, usize> {
+        self.keys
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, queue)| !queue.waiters.is_empty())
+            .map(|(key, queue)| (key.clone(), queue.waiters.len()))
+            .collect()
+    }
+
+    /// Whether `ticket` is the next waiter to be served for `key`.
+    pub fn is_next(&self, key: &str, ticket: u64) -> bool {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key)
+            .and_then(|queue| queue.waiters.first())
+            .is_some_and(|head| head.ticket == ticket)
+    }
+
+    /// Handle notified whenever `key` may have become available to its queue.
+    pub fn notifier(&self, key: &str) -> Arc<Notify> {
+        let mut keys = self.keys.lock().unwrap();
+        keys.entry(key.to_string())
+            .or_insert_with(|| KeyQueue::new(Utc::now()))
+            .notify
+            .clone()
+    }
+
+    /// Wakes the waiters of `key`, e.g. after its lock was released.
+    pub fn notify(&self, key: &str) {
+        if let Some(queue) = self.keys.lock().unwrap().get(key) {
+            queue.notify.notify_waiters();
+        }
+    }
+
+    /// Removes `ticket` from the queue of `key` after it acquired the lock at
+    /// `now`, and records the acquisition in the key's audit.
+    pub fn granted(&self, key: &str, ticket: u64, now: DateTime<Utc>) -> Option<QueuedAcquisition> {
+        let mut keys = self.keys.lock().unwrap();
+        let queue = keys.get_mut(key)?;
+        let index = queue.waiters.iter().position(|w| w.ticket == ticket)?;
+        let waiter = queue.waiters.remove(index);
+
+        let overtook = queue
+            .waiters
+            .iter()
+            .filter(|other| other.arrived_before(&waiter))
+            .count() as u64;
+        let acquisition = QueuedAcquisition {
+            owner: waiter.owner,
+            priority: waiter.priority,
+            enqueued_at: waiter.enqueued_at,
+            acquired_at: now,
+            overtook,
+        };
+
+        queue.acquisitions += 1;
+        queue.queue_jumps += overtook;
+        queue.last_used = now;
+        if queue.recent.len() == LOCK_QUEUE_AUDIT_LEN {
+            queue.recent.pop_front();
+        }
+        queue.recent.push_back(acquisition.clone());
+        Some(acquisition)
+    }
+
+    /// Removes `ticket` from the queue of `key` without acquiring, e.g. after
+    /// its wait timed out, and lets the next waiter in.
+    pub fn leave(&self, key: &str, ticket: u64) {
+        let mut keys = self.keys.lock().unwrap();
+        if let Some(queue) = keys.get_mut(key) {
+            queue.waiters.retain(|waiter| waiter.ticket != ticket);
+            queue.notify.notify_waiters();
+        }
+    }
+
+    /// The current queue and audit of `key`.
+    pub fn snapshot(&self, key: &str) -> LockQueueSnapshot {
+        let keys = self.keys.lock().unwrap();
+        let queue = keys.get(key);
+        LockQueueSnapshot {
+            key: key.to_string(),
+            waiters: queue.map(|q| q.waiters.clone()).unwrap_or_default(),
+            recent_acquisitions: queue
+                .map(|q| q.recent.iter().cloned().collect())
+                .unwrap_or_default(),
+            acquisitions: queue.map_or(0, |q| q.acquisitions),
+            queue_jumps: queue.map_or(0, |q| q.queue_jumps),
+        }
+    }
+
+    /// Estimated bytes held by the queues and their audits.
+    pub fn estimated_bytes(&self) -> usize {
+        let keys = self.keys.lock().unwrap();
+        keys.iter()
+            .map(|(key, queue)| {
+                let waiters: usize = queue.waiters.iter().map(entry_size_of).sum();
+                let recent: usize = queue.recent.iter().map(entry_size_of).sum();
+                key.len()
+                    + std::mem::size_of::<KeyQueue>()
+                    + ENTRY_OVERHEAD_BYTES
+                    + waiters
+                    + recent
+            })
+            .sum()
+    }
+
+    /// Drops the audit of keys without waiters unused for `idle_ttl` and
+    /// returns how many were reclaimed.
+    pub fn remove_idle(&self, now: DateTime<Utc>, idle_ttl: Duration) -> u64 {
+        let idle_ttl = chrono::Duration::from_std(idle_ttl).unwrap_or(chrono::Duration::MAX);
+        let mut keys = self.keys.lock().unwrap();
+        let before = keys.len();
+        keys.retain(|_, queue| !queue.waiters.is_empty() || now - queue.last_used < idle_ttl);
+        (before - keys.len()) as u64
+    }
+}
+
+fn entry_size_of<T: Serialize>(value: &T) -> usize {
+    serialized_size(value) + ENTRY_OVERHEAD_BYTES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(base: DateTime<Utc>, ms: i64) -> DateTime<Utc> {
+        base + chrono::Duration::milliseconds(ms)
+    }
+
+    #[test]
+    fn test_waiters_are_served_by_priority_then_arrival() {
+        let queues = LockWaitQueues::new();
+        let base = Utc::now();
+
+        let a = queues.enqueue("orders", "a", LockPriority::Normal, at(base, 0));
+        let b = queues.enqueue("orders", "b", LockPriority::Normal, at(base, 10));
+        let c = queues.enqueue("orders", "c", LockPriority::High, at(base, 20));
+        let d = queues.enqueue("orders", "d", LockPriority::Low, at(base, 30));
+
+        let order: Vec<_> = queues
+            .snapshot("orders")
+            .waiters
+            .into_iter()
+            .map(|w| w.owner)
+            .collect();
+        assert_eq!(order, ["c", "a", "b", "d"]);
+        assert!(queues.is_next("orders", c));
+
+        for (ticket, acquired_ms) in [(c, 100), (a, 200), (b, 300), (d, 400)] {
+            assert!(queues.is_next("orders", ticket));
+            queues.granted("orders", ticket, at(base, acquired_ms));
+        }
+
+        let snapshot = queues.snapshot("orders");
+        assert!(snapshot.waiters.is_empty());
+        let audit: Vec<_> = snapshot
+            .recent_acquisitions
+            .iter()
+            .map(|r| (r.owner.as_str(), r.overtook, r.wait().as_millis()))
+            .collect();
+        assert_eq!(
+            audit,
+            [("c", 2, 80), ("a", 0, 200), ("b", 0, 290), ("d", 0, 370)]
+        );
+        assert_eq!(snapshot.acquisitions, 4);
+        assert_eq!(snapshot.queue_jumps, 2);
+    }
+
+    #[test]
+    fn test_leaving_waiters_are_not_overtaken_and_idle_keys_are_reclaimed() {
+        let queues = LockWaitQueues::new();
+        let base = Utc::now();
+
+        let a = queues.enqueue("orders", "a", LockPriority::Normal, at(base, 0));
+        let b = queues.enqueue("orders", "b", LockPriority::Normal, at(base, 10));
+        queues.leave("orders", a);
+        assert!(queues.is_next("orders", b));
+        assert_eq!(
+            queues.granted("orders", b, at(base, 50)).unwrap().overtook,
+            0
+        );
+        assert!(queues.granted("orders", a, at(base, 60)).is_none());
+
+        queues.enqueue("payments", "p", LockPriority::Low, at(base, 0));
+        let later = at(base, 61_000);
+        assert_eq!(queues.remove_idle(later, Duration::from_secs(60)), 1);
+        assert_eq!(queues.snapshot("orders").acquisitions, 0);
+        assert!(queues.has_waiters("payments"));
+    }
+}
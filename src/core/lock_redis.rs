@@ -0,0 +1,318 @@
+//! Locks kept in Redis, shared by every instance pointed at the same server.
+//!
+//! A lock is the lock ID under `syros:locks:{key}`, which expires with the
+//! lock, and its full state under `syros:lock_state:{key}`, kept a few
+//! minutes longer so recently expired locks can still be inspected. The
+//! fencing counter of a key lives under `syros:lock_fence:{key}` and is never
+//! reset, so tokens keep increasing across releases.
+
+use crate::core::lock_manager::{escape_glob, glob_prefix, LockFilter, LockState};
+use crate::core::lock_store::{LockCleanup, LockStore};
+use crate::core::lock_table::{extended_expiry, LockExtension, LockRelease};
+use crate::core::namespace_freeze::NAMESPACE_SEPARATOR;
+use crate::storage::redis::RedisManager;
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use std::time::Duration;
+
+/// How long a lock's state record outlives the lock itself, so recently
+/// expired locks can still be inspected.
+const LOCK_STATE_RETENTION_MS: u64 = 5 * 60 * 1000;
+
+fn lock_key(key: &str) -> String {
+    format!("syros:locks:{}", key)
+}
+
+fn lock_state_key(key: &str) -> String {
+    format!("syros:lock_state:{}", key)
+}
+
+fn lock_fence_key(key: &str) -> String {
+    format!("syros:lock_fence:{}", key)
+}
+
+fn storage_error(e: redis::RedisError) -> SyrosError {
+    SyrosError::StorageError(e.to_string())
+}
+
+/// Locks in Redis.
+#[derive(Clone)]
+pub struct RedisLockStore {
+    redis: RedisManager,
+}
+
+impl RedisLockStore {
+    pub fn new(redis: RedisManager) -> Self {
+        Self { redis }
+    }
+}
+
+#[async_trait]
+impl LockStore for RedisLockStore {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn acquire(
+        &self,
+        state: LockState,
+        reentrant: bool,
+        _now: DateTime<Utc>,
+    ) -> Result<Option<LockState>> {
+        let mut conn = self.redis.get_connection().await?;
+        let ttl_ms = (state.expires_at - state.acquired_at)
+            .num_milliseconds()
+            .max(1) as u64;
+        let state_json =
+            serde_json::to_string(&state).map_err(|e| SyrosError::LockError(e.to_string()))?;
+
+        // SET NX PX on the lock key; on success bump the per-key fencing
+        // counter and store the full lock state alongside it. A reentrant
+        // request from the holder instead counts one more hold on the held
+        // lock and pushes its expiry out if the new TTL ends later. Returns
+        // the state of the lock held, or nil.
+        let script = redis::Script::new(
+            r"
+            if redis.call('set', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+                local state = cjson.decode(ARGV[3])
+                state['fence_token'] = redis.call('incr', KEYS[3])
+                local encoded = cjson.encode(state)
+                redis.call('set', KEYS[2], encoded, 'PX', ARGV[4])
+                return encoded
+            end
+            if ARGV[5] ~= '1' then
+                return false
+            end
+            local held = redis.call('get', KEYS[2])
+            if not held then
+                return false
+            end
+            local state = cjson.decode(held)
+            if state['id'] ~= redis.call('get', KEYS[1]) or state['owner'] ~= ARGV[6] then
+                return false
+            end
+            state['hold_count'] = (state['hold_count'] or 1) + 1
+            if tonumber(ARGV[2]) > redis.call('pttl', KEYS[1]) then
+                redis.call('pexpire', KEYS[1], ARGV[2])
+                state['expires_at'] = ARGV[7]
+            end
+            local encoded = cjson.encode(state)
+            local state_ttl = redis.call('pttl', KEYS[1]) + tonumber(ARGV[8])
+            redis.call('set', KEYS[2], encoded, 'PX', state_ttl)
+            return encoded
+            ",
+        );
+
+        let held: Option<String> = script
+            .key(lock_key(&state.key))
+            .key(lock_state_key(&state.key))
+            .key(lock_fence_key(&state.key))
+            .arg(&state.id)
+            .arg(ttl_ms)
+            .arg(state_json)
+            .arg(ttl_ms + LOCK_STATE_RETENTION_MS)
+            .arg(if reentrant { "1" } else { "0" })
+            .arg(&state.owner)
+            .arg(state.expires_at.to_rfc3339())
+            .arg(LOCK_STATE_RETENTION_MS)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+
+        held.map(|json| {
+            serde_json::from_str(&json).map_err(|e| SyrosError::StorageError(e.to_string()))
+        })
+        .transpose()
+    }
+
+    async fn release(&self, key: &str, lock_id: &str, _now: DateTime<Utc>) -> Result<LockRelease> {
+        let mut conn = self.redis.get_connection().await?;
+
+        // Lua script to safely release lock only if ID matches; returns the
+        // holds left, or -1 if the lock is not held under that ID.
+        let script = redis::Script::new(
+            r"
+            if redis.call('get', KEYS[1]) ~= ARGV[1] then
+                return -1
+            end
+            local held = redis.call('get', KEYS[2])
+            if held then
+                local state = cjson.decode(held)
+                local holds = (state['hold_count'] or 1) - 1
+                if holds > 0 then
+                    state['hold_count'] = holds
+                    redis.call('set', KEYS[2], cjson.encode(state), 'PX', redis.call('pttl', KEYS[2]))
+                    return holds
+                end
+            end
+            redis.call('del', KEYS[2])
+            redis.call('del', KEYS[1])
+            return 0
+            ",
+        );
+
+        let result: i64 = script
+            .key(lock_key(key))
+            .key(lock_state_key(key))
+            .arg(lock_id)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(match result {
+            0 => LockRelease::Released,
+            holds if holds > 0 => LockRelease::HoldReleased(holds as u32),
+            _ => LockRelease::NotHeld,
+        })
+    }
+
+    async fn extend(
+        &self,
+        key: &str,
+        lock_id: &str,
+        owner: &str,
+        ttl: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<LockExtension> {
+        let mut conn = self.redis.get_connection().await?;
+
+        let state_json: Option<String> =
+            conn.get(lock_state_key(key)).await.map_err(storage_error)?;
+        let Some(mut state) =
+            state_json.and_then(|json| serde_json::from_str::<LockState>(&json).ok())
+        else {
+            return Ok(LockExtension::NotHeld);
+        };
+        if state.is_expired(now) {
+            return Ok(LockExtension::NotHeld);
+        }
+        if state.id != lock_id || state.owner != owner {
+            return Ok(LockExtension::HeldByOther);
+        }
+
+        state.expires_at = extended_expiry(state.expires_at, ttl)?;
+        let ttl_ms = (state.expires_at - now).num_milliseconds().max(1) as u64;
+        let state_json =
+            serde_json::to_string(&state).map_err(|e| SyrosError::LockError(e.to_string()))?;
+
+        // Only extend while the lock key still holds this lock ID.
+        let script = redis::Script::new(
+            r"
+            if redis.call('get', KEYS[1]) ~= ARGV[1] then
+                return 0
+            end
+            redis.call('pexpire', KEYS[1], ARGV[2])
+            redis.call('set', KEYS[2], ARGV[3], 'PX', ARGV[4])
+            return 1
+            ",
+        );
+
+        let result: i32 = script
+            .key(lock_key(key))
+            .key(lock_state_key(key))
+            .arg(lock_id)
+            .arg(ttl_ms)
+            .arg(state_json)
+            .arg(ttl_ms + LOCK_STATE_RETENTION_MS)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(if result == 1 {
+            LockExtension::Extended(state.expires_at)
+        } else {
+            LockExtension::NotHeld
+        })
+    }
+
+    async fn get(&self, key: &str, now: DateTime<Utc>) -> Result<Option<LockState>> {
+        let mut conn = self.redis.get_connection().await?;
+        let lock_key = lock_key(key);
+
+        let lock_id: Option<String> = conn.get(&lock_key).await.map_err(storage_error)?;
+
+        let Some(id) = lock_id else {
+            return Ok(None);
+        };
+
+        let state_json: Option<String> =
+            conn.get(lock_state_key(key)).await.map_err(storage_error)?;
+
+        if let Some(state) =
+            state_json.and_then(|json| serde_json::from_str::<LockState>(&json).ok())
+        {
+            if state.id == id {
+                return Ok(Some(state));
+            }
+        }
+
+        // Locks written before state records existed only carry their ID
+        let ttl: i64 = conn.ttl(&lock_key).await.map_err(storage_error)?;
+
+        let expires_at = now + chrono::Duration::seconds(ttl);
+
+        Ok(Some(LockState {
+            id,
+            key: key.to_string(),
+            owner: "unknown".to_string(),
+            acquired_at: now, // Approximate
+            expires_at,
+            metadata: None,
+            fence_token: 0,
+            created_by: None,
+            hold_count: 1,
+        }))
+    }
+
+    async fn list(&self, filter: &LockFilter, now: DateTime<Utc>) -> Result<Vec<LockState>> {
+        let mut conn = self.redis.get_connection().await?;
+        // Scan by the longest literal prefix; the filter checks the rest.
+        let namespace_prefix = filter
+            .namespace
+            .as_ref()
+            .map(|namespace| format!("{}{}", namespace, NAMESPACE_SEPARATOR));
+        let prefix = [
+            filter.key_prefix.as_deref(),
+            filter.pattern.as_deref().map(glob_prefix),
+            namespace_prefix.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .max_by_key(|prefix| prefix.len())
+        .unwrap_or("");
+        let pattern = format!("{}*", lock_state_key(&escape_glob(prefix)));
+
+        let mut state_keys: Vec<String> = Vec::new();
+        {
+            let mut iter: redis::AsyncIter<String> =
+                conn.scan_match(&pattern).await.map_err(storage_error)?;
+            while let Some(state_key) = iter.next_item().await {
+                state_keys.push(state_key);
+            }
+        }
+
+        if state_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&state_keys)
+            .query_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(values
+            .into_iter()
+            .flatten()
+            .filter_map(|json| serde_json::from_str::<LockState>(&json).ok())
+            .filter(|state| filter.matches(state, now))
+            .collect())
+    }
+
+    /// Redis expires locks and their state on its own.
+    async fn cleanup(&self, _now: DateTime<Utc>, _idle_ttl: Duration) -> Result<LockCleanup> {
+        Ok(LockCleanup::default())
+    }
+}
@@ -0,0 +1,148 @@
+//! Locks bound to the client session that acquired them.
+//!
+//! A lock request may name a `session_id`, e.g. the one a WebSocket
+//! connection is given when it opens. The lock is then released as soon as
+//! the session closes instead of lingering until its TTL expires. Sessions
+//! are opened and closed by the connection that owns them; a lock cannot be
+//! bound to a session that is not open, so none is left behind by a client
+//! that already disconnected.
+//!
+//! Sessions are local to the process holding the connection, like the wait
+//! queues.
+
+use crate::core::memory::ENTRY_OVERHEAD_BYTES;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A lock acquired on behalf of a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionLock {
+    pub key: String,
+    pub lock_id: String,
+    pub owner: String,
+}
+
+#[derive(Default)]
+struct SessionState {
+    /// Locks of each open session
+    sessions: HashMap<String, Vec<SessionLock>>,
+    /// Session each bound lock ID belongs to
+    bound: HashMap<String, String>,
+}
+
+/// Open sessions and the locks bound to them.
+#[derive(Clone, Default)]
+pub struct LockSessions {
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl LockSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens `session_id` so locks can be bound to it.
+    pub fn open(&self, session_id: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .sessions
+            .entry(session_id.to_string())
+            .or_default();
+    }
+
+    /// Whether `session_id` is open.
+    pub fn is_open(&self, session_id: &str) -> bool {
+        self.state.lock().unwrap().sessions.contains_key(session_id)
+    }
+
+    /// Binds `lock` to `session_id`, returning `false` if the session is not
+    /// open. Binding a lock already bound, e.g. when it is re-entered, is a
+    /// no-op.
+    pub fn bind(&self, session_id: &str, lock: SessionLock) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let SessionState { sessions, bound } = &mut *state;
+        let Some(locks) = sessions.get_mut(session_id) else {
+            return false;
+        };
+        if !bound.contains_key(&lock.lock_id) {
+            bound.insert(lock.lock_id.clone(), session_id.to_string());
+            locks.push(lock);
+        }
+        true
+    }
+
+    /// Forgets the lock `lock_id` once it was released.
+    pub fn unbind(&self, lock_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        let Some(session_id) = state.bound.remove(lock_id) else {
+            return;
+        };
+        if let Some(locks) = state.sessions.get_mut(&session_id) {
+            locks.retain(|lock| lock.lock_id != lock_id);
+        }
+    }
+
+    /// Estimated bytes held by the open sessions and their locks.
+    pub fn estimated_bytes(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .sessions
+            .iter()
+            .map(|(session_id, locks)| {
+                let bound: usize = locks
+                    .iter()
+                    .map(|lock| {
+                        // Each lock also has an entry in `bound`
+                        lock.key.len()
+                            + 2 * lock.lock_id.len()
+                            + lock.owner.len()
+                            + session_id.len()
+                            + 2 * ENTRY_OVERHEAD_BYTES
+                    })
+                    .sum();
+                session_id.len() + ENTRY_OVERHEAD_BYTES + bound
+            })
+            .sum()
+    }
+
+    /// Closes `session_id` and returns the locks still bound to it.
+    pub fn close(&self, session_id: &str) -> Vec<SessionLock> {
+        let mut state = self.state.lock().unwrap();
+        let locks = state.sessions.remove(session_id).unwrap_or_default();
+        for lock in &locks {
+            state.bound.remove(&lock.lock_id);
+        }
+        locks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(lock_id: &str) -> SessionLock {
+        SessionLock {
+            key: format!("key-{}", lock_id),
+            lock_id: lock_id.to_string(),
+            owner: "owner".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_binds_only_to_open_sessions() {
+        let sessions = LockSessions::new();
+        assert!(!sessions.bind("ws-1", lock("a")));
+
+        sessions.open("ws-1");
+        assert!(sessions.bind("ws-1", lock("a")));
+        assert!(sessions.bind("ws-1", lock("a")));
+        assert!(sessions.bind("ws-1", lock("b")));
+        sessions.unbind("b");
+
+        assert_eq!(sessions.close("ws-1"), vec![lock("a")]);
+        assert!(!sessions.is_open("ws-1"));
+        assert!(!sessions.bind("ws-1", lock("c")));
+        assert!(sessions.close("ws-1").is_empty());
+    }
+}
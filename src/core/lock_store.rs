@@ -0,0 +1,79 @@
+//! Storage of locks.
+//!
+//! A [`LockStore`] holds the locks a [`LockManager`](crate::core::LockManager)
+//! grants: in process memory by default, in a
+//! [`MemoryLockTable`](crate::core::lock_table::MemoryLockTable), or in Redis
+//! with a [`RedisLockStore`](crate::core::lock_redis::RedisLockStore), where
+//! every instance pointed at the same server shares them. Wait queues,
+//! sessions, contention and namespace counts are kept by the manager on top
+//! of either.
+
+use crate::core::lock_manager::{LockFilter, LockState};
+use crate::core::lock_table::{LockExtension, LockRelease};
+use crate::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Where a [`LockManager`](crate::core::LockManager) keeps its locks.
+#[async_trait]
+pub trait LockStore: Send + Sync {
+    /// Name of the storage, e.g. `memory` or `redis`.
+    fn name(&self) -> &'static str;
+
+    /// Stores `state` as the lock on its key under the key's next fencing
+    /// token, unless an unexpired lock holds the key; returns the lock as
+    /// held.
+    ///
+    /// With `reentrant`, a key held by `state.owner` instead counts one more
+    /// hold on the held lock and pushes its expiry out to `state.expires_at`
+    /// if that is later. Returns `None` if the key is held otherwise.
+    async fn acquire(
+        &self,
+        state: LockState,
+        reentrant: bool,
+        now: DateTime<Utc>,
+    ) -> Result<Option<LockState>>;
+
+    /// Releases one hold of the lock on `key` if it is still the unexpired
+    /// lock identified by `lock_id`, removing the lock with its last hold.
+    async fn release(&self, key: &str, lock_id: &str, now: DateTime<Utc>) -> Result<LockRelease>;
+
+    /// Pushes the expiry of the lock on `key` forward by `ttl`, if it is
+    /// still the unexpired lock identified by `lock_id` and held by `owner`.
+    ///
+    /// Fails, leaving the lock as it is, if the new expiry is out of range.
+    async fn extend(
+        &self,
+        key: &str,
+        lock_id: &str,
+        owner: &str,
+        ttl: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<LockExtension>;
+
+    /// The unexpired lock on `key`, if any.
+    async fn get(&self, key: &str, now: DateTime<Utc>) -> Result<Option<LockState>>;
+
+    /// Locks matching `filter`, in no particular order.
+    async fn list(&self, filter: &LockFilter, now: DateTime<Utc>) -> Result<Vec<LockState>>;
+
+    /// Drops the locks expired at `now` and the fencing counters of keys
+    /// unused for `idle_ttl`, for stores that do not expire them on their
+    /// own.
+    async fn cleanup(&self, now: DateTime<Utc>, idle_ttl: Duration) -> Result<LockCleanup>;
+
+    /// Estimated bytes the store holds in this process.
+    async fn estimated_bytes(&self) -> u64 {
+        0
+    }
+}
+
+/// What a [`LockStore::cleanup`] removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockCleanup {
+    /// Keys whose expired lock was dropped
+    pub expired: Vec<String>,
+    /// Fencing counters reclaimed
+    pub reclaimed: u64,
+}
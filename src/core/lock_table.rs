@@ -0,0 +1,604 @@
+//! In-memory, sharded lock table.
+//!
+//! Locks are spread over a fixed number of shards selected by key hash, each
+//! behind its own `RwLock`, so operations on unrelated keys do not contend.
+//! Every operation on a single key takes exactly one shard lock, which keeps
+//! per-key operations linearizable; scans visit the shards one at a time.
+//!
+//! The table is the in-memory [`LockStore`].
+//!
+//! Fencing counters outlive their locks so tokens keep increasing across
+//! releases. Counters of keys left unused for the idle TTL are reclaimed by
+//! [`MemoryLockTable::remove_idle`]; each shard remembers the highest token it
+//! reclaimed and starts new counters above it, so a key whose counter was
+//! dropped never sees a token go backwards.
+
+use crate::core::lock_manager::{LockFilter, LockState};
+use crate::core::lock_store::{LockCleanup, LockStore};
+use crate::core::memory::{entry_size, ENTRY_OVERHEAD_BYTES};
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Default number of shards in a [`MemoryLockTable`].
+pub const DEFAULT_LOCK_SHARDS: usize = 64;
+
+/// Default time a key's fencing counter is kept after its last use.
+pub const DEFAULT_IDLE_KEY_TTL: Duration = Duration::from_secs(300);
+
+/// Longest TTL a lock may be acquired with, about 100 years.
+pub const MAX_LOCK_TTL: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+struct Fence {
+    token: u64,
+    last_used: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct Shard {
+    locks: HashMap<String, LockState>,
+    /// Last fencing token issued per key; survives release so tokens keep increasing.
+    fences: HashMap<String, Fence>,
+    /// Highest token of any reclaimed fence; new fences start above it.
+    fence_floor: u64,
+}
+
+impl Shard {
+    /// Stores `state` as the lock on its key under the key's next fencing token.
+    fn insert(&mut self, mut state: LockState, now: DateTime<Utc>) -> &LockState {
+        let floor = self.fence_floor;
+        let fence = self.fences.entry(state.key.clone()).or_insert(Fence {
+            token: floor,
+            last_used: now,
+        });
+        fence.token += 1;
+        fence.last_used = now;
+        state.fence_token = fence.token;
+        let key = state.key.clone();
+        self.locks.insert(key.clone(), state);
+        &self.locks[&key]
+    }
+}
+
+/// Outcome of [`MemoryLockTable::extend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockExtension {
+    /// The lock now expires at the given time
+    Extended(DateTime<Utc>),
+    /// No unexpired lock is held on the key
+    NotHeld,
+    /// The key is locked under another lock ID or owner
+    HeldByOther,
+}
+
+/// Outcome of [`MemoryLockTable::release`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockRelease {
+    /// The lock was removed and the key is free
+    Released,
+    /// One nested hold was released; the owner still holds the given number
+    HoldReleased(u32),
+    /// No unexpired lock with the given ID is held on the key
+    NotHeld,
+}
+
+/// Locks held in memory, partitioned by key hash.
+#[derive(Clone)]
+pub struct MemoryLockTable {
+    shards: Arc<[RwLock<Shard>]>,
+    hasher: RandomState,
+}
+
+impl Default for MemoryLockTable {
+    fn default() -> Self {
+        Self::with_shards(DEFAULT_LOCK_SHARDS)
+    }
+}
+
+impl MemoryLockTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a table with `shards` partitions (at least one).
+    pub fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| RwLock::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Number of shards the keys are spread over.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard(&self, key: &str) -> &RwLock<Shard> {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        &self.shards[index]
+    }
+
+    /// Stores `state` unless its key is held by an unexpired lock.
+    ///
+    /// Returns the fencing token issued for the new lock, or `None` if the
+    /// key is already held.
+    pub async fn try_acquire(&self, state: LockState, now: DateTime<Utc>) -> Option<u64> {
+        let mut shard = self.shard(&state.key).write().await;
+        if shard
+            .locks
+            .get(&state.key)
+            .is_some_and(|held| !held.is_expired(now))
+        {
+            return None;
+        }
+        Some(shard.insert(state, now).fence_token)
+    }
+
+    /// Like [`try_acquire`](Self::try_acquire), but when `state.owner`
+    /// already holds the key, counts one more hold on the existing lock and
+    /// pushes its expiry out to `state.expires_at` if that is later.
+    ///
+    /// Returns the lock now held by the owner, or `None` if the key is held
+    /// by another owner.
+    pub async fn try_acquire_reentrant(
+        &self,
+        state: LockState,
+        now: DateTime<Utc>,
+    ) -> Option<LockState> {
+        let mut shard = self.shard(&state.key).write().await;
+        match shard.locks.get_mut(&state.key) {
+            Some(held) if held.is_expired(now) => {}
+            Some(held) if held.owner == state.owner => {
+                held.hold_count += 1;
+                held.expires_at = held.expires_at.max(state.expires_at);
+                return Some(held.clone());
+            }
+            Some(_) => return None,
+            None => {}
+        }
+        Some(shard.insert(state, now).clone())
+    }
+
+    /// Releases one hold of the lock on `key` if it is still the one
+    /// identified by `lock_id`, removing the lock with its last hold.
+    pub async fn release(&self, key: &str, lock_id: &str, now: DateTime<Utc>) -> LockRelease {
+        let mut shard = self.shard(key).write().await;
+        match shard.locks.get_mut(key) {
+            Some(held) if held.id == lock_id && !held.is_expired(now) => {
+                if held.hold_count > 1 {
+                    held.hold_count -= 1;
+                    return LockRelease::HoldReleased(held.hold_count);
+                }
+                shard.locks.remove(key);
+                if let Some(fence) = shard.fences.get_mut(key) {
+                    fence.last_used = now;
+                }
+                LockRelease::Released
+            }
+            _ => LockRelease::NotHeld,
+        }
+    }
+
+    /// Pushes the expiry of the lock on `key` forward by `ttl`, if it is
+    /// still the unexpired lock identified by `lock_id` and held by `owner`.
+    ///
+    /// Fails, leaving the lock as it is, if the new expiry is out of range.
+    pub async fn extend(
+        &self,
+        key: &str,
+        lock_id: &str,
+        owner: &str,
+        ttl: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<LockExtension> {
+        let mut shard = self.shard(key).write().await;
+        Ok(match shard.locks.get_mut(key) {
+            Some(held) if held.is_expired(now) => LockExtension::NotHeld,
+            Some(held) if held.id == lock_id && held.owner == owner => {
+                held.expires_at = extended_expiry(held.expires_at, ttl)?;
+                LockExtension::Extended(held.expires_at)
+            }
+            Some(_) => LockExtension::HeldByOther,
+            None => LockExtension::NotHeld,
+        })
+    }
+
+    /// The unexpired lock on `key`, if any.
+    pub async fn get(&self, key: &str, now: DateTime<Utc>) -> Option<LockState> {
+        self.shard(key)
+            .read()
+            .await
+            .locks
+            .get(key)
+            .filter(|held| !held.is_expired(now))
+            .cloned()
+    }
+
+    /// Locks matching `filter`, collected one shard at a time.
+    ///
+    /// Expired locks stay visible to `include_expired` until the next
+    /// [`remove_expired`](Self::remove_expired).
+    pub async fn list(&self, filter: &LockFilter, now: DateTime<Utc>) -> Vec<LockState> {
+        let mut locks = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.read().await;
+            locks.extend(
+                shard
+                    .locks
+                    .values()
+                    .filter(|lock| filter.matches(lock, now))
+                    .cloned(),
+            );
+        }
+        locks
+    }
+
+    /// Drops expired locks shard by shard and returns the keys they held.
+    pub async fn remove_expired(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut removed = Vec::new();
+        for shard in self.shards.iter() {
+            let mut shard = shard.write().await;
+            shard.locks.retain(|key, lock| {
+                if lock.is_expired(now) {
+                    removed.push(key.clone());
+                    return false;
+                }
+                true
+            });
+        }
+        removed
+    }
+
+    /// Estimated bytes held by the locks and fencing counters.
+    pub async fn estimated_bytes(&self) -> u64 {
+        let mut bytes = 0;
+        for shard in self.shards.iter() {
+            let shard = shard.read().await;
+            bytes += shard
+                .locks
+                .iter()
+                .map(|(key, lock)| entry_size(key, lock))
+                .sum::<usize>();
+            bytes += shard
+                .fences
+                .keys()
+                .map(|key| key.len() + std::mem::size_of::<Fence>() + ENTRY_OVERHEAD_BYTES)
+                .sum::<usize>();
+        }
+        bytes as u64
+    }
+
+    /// Drops the fencing counters of unlocked keys unused for `idle_ttl`,
+    /// shard by shard, and returns how many were reclaimed.
+    ///
+    /// Runs under each shard's write lock, so an acquire racing the sweep
+    /// either keeps its key's counter alive or starts above the shard floor.
+    pub async fn remove_idle(&self, now: DateTime<Utc>, idle_ttl: Duration) -> u64 {
+        let idle_ttl = chrono::Duration::from_std(idle_ttl).unwrap_or(chrono::Duration::MAX);
+        let mut reclaimed = 0;
+        for shard in self.shards.iter() {
+            let mut shard = shard.write().await;
+            let Shard {
+                locks,
+                fences,
+                fence_floor,
+            } = &mut *shard;
+            fences.retain(|key, fence| {
+                if locks.contains_key(key) || now - fence.last_used < idle_ttl {
+                    return true;
+                }
+                *fence_floor = (*fence_floor).max(fence.token);
+                reclaimed += 1;
+                false
+            });
+        }
+        reclaimed
+    }
+}
+
+#[async_trait]
+impl LockStore for MemoryLockTable {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn acquire(
+        &self,
+        state: LockState,
+        reentrant: bool,
+        now: DateTime<Utc>,
+    ) -> Result<Option<LockState>> {
+        if reentrant {
+            return Ok(self.try_acquire_reentrant(state, now).await);
+        }
+        let fence_token = self.try_acquire(state.clone(), now).await;
+        Ok(fence_token.map(|fence_token| LockState {
+            fence_token,
+            ..state
+        }))
+    }
+
+    async fn release(&self, key: &str, lock_id: &str, now: DateTime<Utc>) -> Result<LockRelease> {
+        Ok(MemoryLockTable::release(self, key, lock_id, now).await)
+    }
+
+    async fn extend(
+        &self,
+        key: &str,
+        lock_id: &str,
+        owner: &str,
+        ttl: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<LockExtension> {
+        MemoryLockTable::extend(self, key, lock_id, owner, ttl, now).await
+    }
+
+    async fn get(&self, key: &str, now: DateTime<Utc>) -> Result<Option<LockState>> {
+        Ok(MemoryLockTable::get(self, key, now).await)
+    }
+
+    async fn list(&self, filter: &LockFilter, now: DateTime<Utc>) -> Result<Vec<LockState>> {
+        Ok(MemoryLockTable::list(self, filter, now).await)
+    }
+
+    async fn cleanup(&self, now: DateTime<Utc>, idle_ttl: Duration) -> Result<LockCleanup> {
+        let expired = self.remove_expired(now).await;
+        let reclaimed = self.remove_idle(now, idle_ttl).await;
+        Ok(LockCleanup { expired, reclaimed })
+    }
+
+    async fn estimated_bytes(&self) -> u64 {
+        MemoryLockTable::estimated_bytes(self).await
+    }
+}
+
+/// When a lock acquired at `now` with `ttl` expires.
+///
+/// Fails with `ApiError` if `ttl` is longer than [`MAX_LOCK_TTL`].
+pub fn lock_expiry(now: DateTime<Utc>, ttl: Duration) -> Result<DateTime<Utc>> {
+    chrono::Duration::from_std(ttl)
+        .ok()
+        .filter(|_| ttl <= MAX_LOCK_TTL)
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .ok_or_else(|| {
+            SyrosError::ApiError(format!(
+                "Lock TTL of {}s exceeds the maximum of {}s",
+                ttl.as_secs(),
+                MAX_LOCK_TTL.as_secs()
+            ))
+        })
+}
+
+/// `expires_at` pushed forward by `ttl`, or a lock error if that is out of
+/// range.
+pub fn extended_expiry(expires_at: DateTime<Utc>, ttl: Duration) -> Result<DateTime<Utc>> {
+    chrono::Duration::from_std(ttl)
+        .ok()
+        .and_then(|ttl| expires_at.checked_add_signed(ttl))
+        .ok_or_else(|| {
+            SyrosError::LockError(format!(
+                "Extending the lock by {}s puts its expiry out of range",
+                ttl.as_secs()
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(key: &str, id: &str, expires_in_secs: i64) -> LockState {
+        let now = Utc::now();
+        LockState {
+            id: id.to_string(),
+            key: key.to_string(),
+            owner: "worker".to_string(),
+            acquired_at: now,
+            expires_at: now + chrono::Duration::seconds(expires_in_secs),
+            metadata: None,
+            fence_token: 0,
+            created_by: None,
+            hold_count: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acquire_release_and_fencing() {
+        let table = MemoryLockTable::new();
+        let now = Utc::now();
+
+        assert_eq!(
+            table.try_acquire(lock("orders:1", "a", 30), now).await,
+            Some(1)
+        );
+        assert_eq!(
+            table.try_acquire(lock("orders:1", "b", 30), now).await,
+            None
+        );
+        assert_eq!(
+            table.release("orders:1", "b", now).await,
+            LockRelease::NotHeld
+        );
+        assert_eq!(
+            table.release("orders:1", "a", now).await,
+            LockRelease::Released
+        );
+
+        // Tokens keep increasing across releases of the same key.
+        assert_eq!(
+            table.try_acquire(lock("orders:1", "c", 30), now).await,
+            Some(2)
+        );
+        assert_eq!(table.get("orders:1", now).await.unwrap().fence_token, 2);
+    }
+
+    #[tokio::test]
+    async fn test_store_returns_the_lock_held() {
+        let store: &dyn LockStore = &MemoryLockTable::new();
+        let now = Utc::now();
+
+        let held = store
+            .acquire(lock("orders:1", "a", 30), false, now)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((held.id.as_str(), held.fence_token), ("a", 1));
+        let other = LockState {
+            owner: "other".to_string(),
+            ..lock("orders:1", "b", 30)
+        };
+        assert!(store.acquire(other, true, now).await.unwrap().is_none());
+
+        // The owner re-enters the lock it holds under its first ID.
+        let reentered = store
+            .acquire(lock("orders:1", "c", 60), true, now)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((reentered.id.as_str(), reentered.hold_count), ("a", 2));
+        assert_eq!(reentered.fence_token, 1);
+
+        let later = now + chrono::Duration::seconds(120);
+        let cleanup = store.cleanup(later, Duration::ZERO).await.unwrap();
+        assert_eq!(cleanup.expired, vec!["orders:1".to_string()]);
+        assert_eq!(cleanup.reclaimed, 1);
+    }
+
+    async fn fence_count(table: &MemoryLockTable) -> usize {
+        let mut count = 0;
+        for shard in table.shards.iter() {
+            count += shard.read().await.fences.len();
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn test_idle_fences_are_reclaimed_without_reusing_tokens() {
+        let table = MemoryLockTable::with_shards(1);
+        let idle_ttl = Duration::from_secs(60);
+        let now = Utc::now();
+        let later = now + chrono::Duration::seconds(61);
+
+        assert_eq!(table.try_acquire(lock("idle", "a", 30), now).await, Some(1));
+        assert_eq!(table.release("idle", "a", now).await, LockRelease::Released);
+        // Held longer than the idle TTL, but still locked.
+        assert_eq!(
+            table.try_acquire(lock("held", "b", 600), now).await,
+            Some(1)
+        );
+
+        assert_eq!(table.remove_idle(now, idle_ttl).await, 0);
+        assert_eq!(table.remove_idle(later, idle_ttl).await, 1);
+        assert_eq!(fence_count(&table).await, 1);
+        assert_eq!(table.get("held", later).await.unwrap().fence_token, 1);
+
+        // A waiter arriving after the sweep starts above every reclaimed token.
+        assert_eq!(
+            table.try_acquire(lock("idle", "c", 30), later).await,
+            Some(2)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_tokens_keep_increasing_while_sweeping() {
+        let table = MemoryLockTable::with_shards(2);
+        let sweeping = Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        let sweeper = {
+            let table = table.clone();
+            let sweeping = sweeping.clone();
+            tokio::spawn(async move {
+                let mut reclaimed = 0;
+                while sweeping.load(std::sync::atomic::Ordering::Relaxed) {
+                    reclaimed += table.remove_idle(Utc::now(), Duration::ZERO).await;
+                    tokio::task::yield_now().await;
+                }
+                reclaimed
+            })
+        };
+
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let table = table.clone();
+                tokio::spawn(async move {
+                    let key = format!("key:{}", worker % 3);
+                    let mut tokens = Vec::new();
+                    for n in 0..200 {
+                        let id = format!("{}-{}", worker, n);
+                        let now = Utc::now();
+                        if let Some(token) = table.try_acquire(lock(&key, &id, 30), now).await {
+                            tokens.push(token);
+                            tokio::task::yield_now().await;
+                            assert_eq!(
+                                table.release(&key, &id, Utc::now()).await,
+                                LockRelease::Released
+                            );
+                        }
+                        tokio::task::yield_now().await;
+                    }
+                    (key, tokens)
+                })
+            })
+            .collect();
+
+        let mut issued: HashMap<String, Vec<u64>> = HashMap::new();
+        for worker in workers {
+            let (key, tokens) = worker.await.unwrap();
+            assert!(
+                tokens.windows(2).all(|pair| pair[0] < pair[1]),
+                "tokens went backwards: {:?}",
+                tokens
+            );
+            issued.entry(key).or_default().extend(tokens);
+        }
+        for tokens in issued.values_mut() {
+            let count = tokens.len();
+            tokens.sort_unstable();
+            tokens.dedup();
+            assert_eq!(tokens.len(), count, "a token was issued twice");
+        }
+
+        sweeping.store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(sweeper.await.unwrap() > 0);
+        let remaining = fence_count(&table).await as u64;
+        assert_eq!(
+            table.remove_idle(Utc::now(), Duration::ZERO).await,
+            remaining
+        );
+        assert_eq!(fence_count(&table).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_locks_are_replaced_and_removed() {
+        let table = MemoryLockTable::with_shards(4);
+        let now = Utc::now();
+
+        table.try_acquire(lock("stale", "a", -1), now).await;
+        assert!(table.get("stale", now).await.is_none());
+        assert_eq!(
+            table.try_acquire(lock("stale", "b", 30), now).await,
+            Some(2)
+        );
+
+        for i in 0..20 {
+            table
+                .try_acquire(lock(&format!("expired:{}", i), "x", -1), now)
+                .await;
+        }
+        let include_expired = LockFilter {
+            include_expired: true,
+            ..Default::default()
+        };
+        assert_eq!(table.list(&include_expired, now).await.len(), 21);
+        assert_eq!(table.list(&LockFilter::default(), now).await.len(), 1);
+
+        assert_eq!(table.remove_expired(now).await.len(), 20);
+        assert_eq!(table.list(&include_expired, now).await.len(), 1);
+    }
+}
@@ -0,0 +1,286 @@
+//! Size limits and redaction of caller-supplied metadata.
+//!
+//! Locks, sagas, events and service registrations accept free-form metadata.
+//! The API layer checks it against the configured limits before anything is
+//! stored, and redacts the values of sensitive keys (e.g. `password`) in
+//! everything it returns: status and list responses, exports and
+//! notifications. The stored metadata is never altered.
+//!
+//! Lock metadata is a single string: when it holds a JSON object, the
+//! object's keys are checked and redacted like any other metadata map;
+//! otherwise the whole string counts as one value.
+
+use crate::config::MetadataConfig;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// Value shown in place of a redacted metadata value.
+pub const REDACTED_VALUE: &str = "***";
+
+/// Metadata rejected by a [`MetadataPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataViolation {
+    /// Offending field: `metadata`, or `metadata.<key>` for a single entry
+    pub field: String,
+    pub message: String,
+}
+
+impl MetadataViolation {
+    fn new(key: Option<&str>, message: String) -> Self {
+        let field = match key {
+            Some(key) => format!("metadata.{}", key),
+            None => "metadata".to_string(),
+        };
+        Self { field, message }
+    }
+}
+
+impl fmt::Display for MetadataViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Limits and redaction list applied to metadata at the API boundary.
+#[derive(Debug, Clone)]
+pub struct MetadataPolicy {
+    max_keys: usize,
+    max_key_bytes: usize,
+    max_value_bytes: usize,
+    /// Lowercase names of the keys whose values are redacted
+    redacted_keys: Arc<HashSet<String>>,
+}
+
+impl Default for MetadataPolicy {
+    fn default() -> Self {
+        Self::from_config(&MetadataConfig::default())
+    }
+}
+
+impl MetadataPolicy {
+    /// Policy configured under `[metadata]`.
+    pub fn from_config(config: &MetadataConfig) -> Self {
+        Self {
+            max_keys: config.max_keys,
+            max_key_bytes: config.max_key_bytes,
+            max_value_bytes: config.max_value_bytes,
+            redacted_keys: Arc::new(
+                config
+                    .redacted_keys
+                    .iter()
+                    .map(|key| key.to_lowercase())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Whether values under `key` are redacted; names match case-insensitively.
+    pub fn is_redacted(&self, key: &str) -> bool {
+        self.redacted_keys.contains(&key.to_lowercase())
+    }
+
+    /// Checks a metadata map against the limits.
+    pub fn check(&self, metadata: &HashMap<String, String>) -> Result<(), MetadataViolation> {
+        self.check_entries(
+            metadata
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.len())),
+        )
+    }
+
+    /// Checks metadata given as JSON; only objects are accepted.
+    ///
+    /// Non-string values count with the length of their JSON text.
+    pub fn check_value(&self, metadata: &Value) -> Result<(), MetadataViolation> {
+        match metadata {
+            Value::Object(fields) => self.check_entries(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), json_len(value))),
+            ),
+            Value::Null => Ok(()),
+            _ => Err(MetadataViolation::new(
+                None,
+                "must be an object of key/value pairs".to_string(),
+            )),
+        }
+    }
+
+    /// Checks single-string metadata, such as a lock's.
+    pub fn check_text(&self, metadata: &str) -> Result<(), MetadataViolation> {
+        match serde_json::from_str::<Value>(metadata) {
+            Ok(value @ Value::Object(_)) => self.check_value(&value),
+            _ if metadata.len() > self.max_value_bytes => Err(MetadataViolation::new(
+                None,
+                format!(
+                    "is {} bytes, above the limit of {}",
+                    metadata.len(),
+                    self.max_value_bytes
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_entries<'a>(
+        &self,
+        entries: impl ExactSizeIterator<Item = (&'a str, usize)>,
+    ) -> Result<(), MetadataViolation> {
+        if entries.len() > self.max_keys {
+            return Err(MetadataViolation::new(
+                None,
+                format!(
+                    "has {} keys, above the limit of {}",
+                    entries.len(),
+                    self.max_keys
+                ),
+            ));
+        }
+        for (key, value_len) in entries {
+            if key.len() > self.max_key_bytes {
+                return Err(MetadataViolation::new(
+                    Some(key),
+                    format!(
+                        "key is {} bytes, above the limit of {}",
+                        key.len(),
+                        self.max_key_bytes
+                    ),
+                ));
+            }
+            if value_len > self.max_value_bytes {
+                return Err(MetadataViolation::new(
+                    Some(key),
+                    format!(
+                        "value is {} bytes, above the limit of {}",
+                        value_len, self.max_value_bytes
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the values of redacted keys in a metadata map.
+    pub fn redact(&self, metadata: &mut HashMap<String, String>) {
+        for (key, value) in metadata.iter_mut() {
+            if self.is_redacted(key) {
+                *value = REDACTED_VALUE.to_string();
+            }
+        }
+    }
+
+    /// Replaces the values of redacted keys in metadata given as a JSON object.
+    pub fn redact_value(&self, metadata: &mut Value) {
+        if let Value::Object(fields) = metadata {
+            for (key, value) in fields.iter_mut() {
+                if self.is_redacted(key) {
+                    *value = Value::String(REDACTED_VALUE.to_string());
+                }
+            }
+        }
+    }
+
+    /// Redacts single-string metadata holding a JSON object; other strings
+    /// are returned unchanged.
+    pub fn redact_text(&self, metadata: String) -> String {
+        match serde_json::from_str::<Value>(&metadata) {
+            Ok(mut value @ Value::Object(_)) => {
+                let redacted = value
+                    .as_object()
+                    .is_some_and(|fields| fields.keys().any(|key| self.is_redacted(key)));
+                if !redacted {
+                    return metadata;
+                }
+                self.redact_value(&mut value);
+                value.to_string()
+            }
+            _ => metadata,
+        }
+    }
+}
+
+fn json_len(value: &Value) -> usize {
+    match value {
+        Value::String(text) => text.len(),
+        other => other.to_string().len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> MetadataPolicy {
+        MetadataPolicy::from_config(&MetadataConfig {
+            max_keys: 3,
+            max_key_bytes: 8,
+            max_value_bytes: 16,
+            redacted_keys: vec!["Password".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_limits() {
+        let policy = policy();
+        let metadata = |entries: &[(&str, &str)]| -> HashMap<String, String> {
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert!(policy.check(&metadata(&[("a", "1"), ("b", "2")])).is_ok());
+        let error = policy
+            .check(&metadata(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4")]))
+            .unwrap_err();
+        assert_eq!(error.field, "metadata");
+        let error = policy
+            .check(&metadata(&[("much_too_long", "1")]))
+            .unwrap_err();
+        assert_eq!(error.field, "metadata.much_too_long");
+        let error = policy
+            .check(&metadata(&[("note", &"x".repeat(17))]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "metadata.note: value is 17 bytes, above the limit of 16"
+        );
+
+        assert!(policy.check_value(&json!({ "n": 12 })).is_ok());
+        assert!(policy
+            .check_value(&json!({ "n": (0..10).collect::<Vec<_>>() }))
+            .is_err());
+        assert!(policy.check_value(&json!("text")).is_err());
+        assert!(policy.check_text("short note").is_ok());
+        assert!(policy.check_text(&"x".repeat(17)).is_err());
+        assert!(policy
+            .check_text(r#"{"a":"1","b":"2","c":"3","d":"4"}"#)
+            .is_err());
+    }
+
+    #[test]
+    fn test_redaction_is_case_insensitive() {
+        let policy = policy();
+
+        let mut metadata = HashMap::from([
+            ("PASSWORD".to_string(), "hunter2".to_string()),
+            ("source".to_string(), "api".to_string()),
+        ]);
+        policy.redact(&mut metadata);
+        assert_eq!(metadata["PASSWORD"], REDACTED_VALUE);
+        assert_eq!(metadata["source"], "api");
+
+        let mut value = json!({ "password": { "nested": true }, "source": "api" });
+        policy.redact_value(&mut value);
+        assert_eq!(value, json!({ "password": "***", "source": "api" }));
+
+        assert_eq!(
+            policy.redact_text(r#"{"password":"hunter2"}"#.to_string()),
+            r#"{"password":"***"}"#
+        );
+        assert_eq!(policy.redact_text("password".to_string()), "password");
+    }
+}
@@ -1,13 +1,59 @@
+pub mod background;
+pub mod cache_backend;
+pub mod cache_fills;
+pub mod cache_journal;
 pub mod cache_manager;
+pub mod cache_memory;
+pub mod cache_redis;
+pub mod event_log;
+pub mod event_persistence;
 pub mod event_store;
+pub mod event_subscriptions;
+pub mod event_transfer;
+pub mod lock_contention;
 pub mod lock_manager;
+pub mod lock_namespaces;
+pub mod lock_queue;
+pub mod lock_redis;
+pub mod lock_sessions;
+pub mod lock_store;
+pub mod lock_table;
+pub mod memory;
+pub mod metadata_policy;
+pub mod namespace_freeze;
+pub mod projections;
+pub mod saga_dead_letter;
+pub mod saga_definitions;
+pub mod saga_executors;
+pub mod saga_http;
+pub mod saga_idempotency;
 pub mod saga_orchestrator;
+pub mod saga_plan;
+pub mod saga_results;
+pub mod saga_template;
+pub mod saga_workers;
+pub mod service_consul;
 pub mod service_discovery;
+pub mod task_tracker;
 
+pub use background::{ComponentRegistry, TaskSpawner};
+pub use cache_backend::{CacheBackend, CacheBackendChain, CacheLayer, CacheSource};
 pub use cache_manager::CacheManager;
+pub use event_persistence::EventPersistence;
 pub use event_store::EventStore;
 pub use lock_manager::LockManager;
+pub use lock_store::LockStore;
+pub use metadata_policy::MetadataPolicy;
+pub use namespace_freeze::NamespaceFreezes;
+pub use projections::Projections;
+pub use saga_dead_letter::DeadLetterQueue;
+pub use saga_definitions::SagaDefinitions;
+pub use saga_executors::{ManualStepExecutor, SagaStepExecutor, StepExecutors, StepOutcome};
 pub use saga_orchestrator::SagaOrchestrator;
+pub use saga_workers::SagaWorkerRegistry;
+pub use service_consul::ConsulBackend;
 pub use service_discovery::{
-    ServiceCheck, ServiceDiscovery, ServiceHealth, ServiceInfo, ServiceRegistration,
+    HealthStatus, HealthThresholds, MemoryDiscoveryBackend, ServiceCheck, ServiceDiscovery,
+    ServiceDiscoveryBackend, ServiceHealth, ServiceInfo, ServiceRegistration,
 };
+pub use task_tracker::TaskTracker;
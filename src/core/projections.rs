@@ -0,0 +1,655 @@
+//! Projections folding the event feed into queryable state.
+//!
+//! A projection selects events by stream prefix and, optionally, event type,
+//! and folds each selected event into a JSON document with one of the
+//! built-in [`Reducer`]s. Projections read the feed of all streams from the
+//! position after their checkpoint, so they see every event once, in commit
+//! order. They are kept up to date by following the store's appends and
+//! caught up again before their state is read, which also picks up events
+//! appended through other instances.
+//!
+//! Each projection's state is saved together with its checkpoint, so after a
+//! restart over Postgres it resumes where it stopped instead of replaying
+//! the whole feed. Rebuilding folds the retained events again from scratch.
+
+use crate::core::event_store::{Event, EventStore};
+use crate::core::event_subscriptions::{stream_matches, EventSubscription};
+use crate::core::task_tracker::TaskTracker;
+use crate::storage::postgres::PostgresManager;
+use crate::{Result, SyrosError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+/// Events read from the feed per page while catching up.
+pub const CATCH_UP_PAGE_SIZE: usize = 500;
+/// Longest projection name accepted, in bytes.
+pub const MAX_PROJECTION_NAME_BYTES: usize = 255;
+/// Most items an [`Reducer::Append`] projection keeps; older items are
+/// dropped as new ones are appended.
+pub const MAX_APPENDED_ITEMS: usize = 1_000;
+
+/// How a projection folds an event into its state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reducer {
+    /// Copies the fields of each event's data object into the state object,
+    /// replacing fields of the same name
+    Merge,
+    /// Appends each event's data to the state array, keeping the last
+    /// [`MAX_APPENDED_ITEMS`]
+    Append,
+    /// Counts the events
+    Counter,
+}
+
+impl Reducer {
+    /// State of a projection that has not folded any event yet.
+    pub fn initial_state(&self) -> Value {
+        match self {
+            Reducer::Merge => Value::Object(Default::default()),
+            Reducer::Append => Value::Array(Vec::new()),
+            Reducer::Counter => Value::from(0),
+        }
+    }
+
+    /// Folds `event` into `state`.
+    ///
+    /// Merging ignores events whose data is not an object.
+    pub fn apply(&self, state: &mut Value, event: &Event) {
+        match (self, state) {
+            (Reducer::Merge, Value::Object(state)) => {
+                if let Value::Object(fields) = &event.data {
+                    for (key, value) in fields {
+                        state.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            (Reducer::Append, Value::Array(state)) => {
+                if state.len() >= MAX_APPENDED_ITEMS {
+                    state.drain(..=state.len() - MAX_APPENDED_ITEMS);
+                }
+                state.push(event.data.clone());
+            }
+            (Reducer::Counter, state) => {
+                *state = Value::from(state.as_u64().unwrap_or(0) + 1);
+            }
+            // A state of another shape is replaced before folding.
+            (_, state) => {
+                *state = self.initial_state();
+                self.apply(state, event);
+            }
+        }
+    }
+}
+
+/// Events a projection folds and how.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectionDefinition {
+    /// Only events of streams whose ID starts with this prefix; every
+    /// stream if empty
+    #[serde(default)]
+    pub stream_prefix: String,
+    /// Only events of these types; any type if unset
+    #[serde(default)]
+    pub event_types: Option<Vec<String>>,
+    pub reducer: Reducer,
+}
+
+impl ProjectionDefinition {
+    /// Checks whether the projection folds `event`.
+    pub fn matches(&self, event: &Event) -> bool {
+        stream_matches(&format!("{}*", self.stream_prefix), &event.stream_id)
+            && self
+                .event_types
+                .as_ref()
+                .is_none_or(|types| types.contains(&event.event_type))
+    }
+}
+
+/// A registered projection and the state it has folded so far.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Projection {
+    pub name: String,
+    #[serde(flatten)]
+    pub definition: ProjectionDefinition,
+    pub state: Value,
+    /// Feed position of the last event processed; 0 before the first
+    pub checkpoint: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Projection {
+    fn new(name: &str, definition: ProjectionDefinition) -> Self {
+        Self {
+            name: name.to_string(),
+            state: definition.reducer.initial_state(),
+            definition,
+            checkpoint: 0,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Fails if `name` is empty or longer than [`MAX_PROJECTION_NAME_BYTES`].
+pub fn check_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(SyrosError::ApiError(
+            "Projection name must not be empty".to_string(),
+        ));
+    }
+    if name.len() > MAX_PROJECTION_NAME_BYTES {
+        return Err(SyrosError::ApiError(format!(
+            "Projection name must be at most {} bytes",
+            MAX_PROJECTION_NAME_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// Storage behind [`Projections`].
+#[derive(Clone)]
+enum ProjectionBackend {
+    Postgres(PostgresManager),
+    Memory(Arc<RwLock<HashMap<String, Projection>>>),
+}
+
+type ProjectionRow = (String, Value, Value, i64, DateTime<Utc>);
+
+fn from_row(
+    (name, definition, state, checkpoint, updated_at): ProjectionRow,
+) -> Result<Projection> {
+    let definition = serde_json::from_value(definition).map_err(|e| {
+        SyrosError::StorageError(format!("Invalid projection definition {}: {}", name, e))
+    })?;
+    Ok(Projection {
+        name,
+        definition,
+        state,
+        checkpoint,
+        updated_at,
+    })
+}
+
+/// Registry of projections over the events of an [`EventStore`].
+#[derive(Clone)]
+pub struct Projections {
+    events: EventStore,
+    backend: ProjectionBackend,
+    /// Held per projection while it catches up, so events are not folded
+    /// twice by this instance
+    catching_up: Arc<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    tasks: TaskTracker,
+}
+
+impl Projections {
+    /// Creates a registry that keeps its projections in memory.
+    pub fn new(events: EventStore) -> Self {
+        Self::with_backend(events, ProjectionBackend::Memory(Arc::default()))
+    }
+
+    /// Creates a registry that keeps its projections and their checkpoints
+    /// in Postgres.
+    pub fn with_postgres(events: EventStore, pg: PostgresManager) -> Self {
+        Self::with_backend(events, ProjectionBackend::Postgres(pg))
+    }
+
+    fn with_backend(events: EventStore, backend: ProjectionBackend) -> Self {
+        Self {
+            events,
+            backend,
+            catching_up: Arc::default(),
+            tasks: TaskTracker::new(),
+        }
+    }
+
+    /// Spawns the task following the event store through `tasks`.
+    pub fn with_task_tracker(mut self, tasks: TaskTracker) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Registers the projection `name`, folding the feed from its start,
+    /// and returns it caught up.
+    ///
+    /// Registering the same definition again keeps the projection as it is;
+    /// a different definition replaces it and starts over.
+    pub async fn register(
+        &self,
+        name: &str,
+        definition: ProjectionDefinition,
+    ) -> Result<Projection> {
+        check_name(name)?;
+        let catching_up = self.catch_up_lock(name);
+        let _catching_up = catching_up.lock().await;
+        let projection = match self.load(name).await? {
+            Some(existing) if existing.definition == definition => existing,
+            _ => {
+                let projection = Projection::new(name, definition);
+                self.store(&projection).await?;
+                projection
+            }
+        };
+        self.catch_up(projection).await
+    }
+
+    /// The projection `name`, caught up with the feed; `None` if no
+    /// projection has that name.
+    pub async fn get(&self, name: &str) -> Result<Option<Projection>> {
+        let catching_up = self.catch_up_lock(name);
+        let _catching_up = catching_up.lock().await;
+        match self.load(name).await? {
+            Some(projection) => self.catch_up(projection).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Drops the state of the projection `name` and folds the retained
+    /// events again from the start of the feed.
+    ///
+    /// Fails with `NotFound` if no projection has that name.
+    pub async fn rebuild(&self, name: &str) -> Result<Projection> {
+        let catching_up = self.catch_up_lock(name);
+        let _catching_up = catching_up.lock().await;
+        let Some(projection) = self.load(name).await? else {
+            return Err(SyrosError::NotFound(format!(
+                "Projection {} not found",
+                name
+            )));
+        };
+        let projection = Projection::new(name, projection.definition);
+        self.store(&projection).await?;
+        self.catch_up(projection).await
+    }
+
+    /// Names of the registered projections, sorted.
+    pub async fn names(&self) -> Result<Vec<String>> {
+        match &self.backend {
+            ProjectionBackend::Memory(projections) => {
+                let mut names: Vec<String> = projections.read().await.keys().cloned().collect();
+                names.sort();
+                Ok(names)
+            }
+            ProjectionBackend::Postgres(pg) => {
+                sqlx::query_scalar("SELECT name FROM projections ORDER BY name")
+                    .fetch_all(pg.get_pool())
+                    .await
+                    .map_err(|e| SyrosError::StorageError(e.to_string()))
+            }
+        }
+    }
+
+    /// Catches every projection up with the feed.
+    ///
+    /// Each projection is caught up under its own lock, so reading one
+    /// projection waits only for that projection.
+    pub async fn catch_up_all(&self) -> Result<()> {
+        for name in self.names().await? {
+            let catching_up = self.catch_up_lock(&name);
+            let _catching_up = catching_up.lock().await;
+            if let Some(projection) = self.load(&name).await? {
+                self.catch_up(projection).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Catches the projections up whenever events are appended through
+    /// `subscription`, until the event store is dropped.
+    ///
+    /// Events appended while a catch-up runs are folded by the next one
+    /// together, rather than triggering a catch-up each.
+    pub fn follow(&self, mut subscription: EventSubscription) -> tokio::task::JoinHandle<()> {
+        let projections = self.clone();
+        self.tasks.spawn_background("projections", async move {
+            loop {
+                if let Err(e) = projections.catch_up_all().await {
+                    tracing::error!("Projection catch-up failed: {}", e);
+                }
+                if subscription.recv().await.is_none() {
+                    break;
+                }
+                while subscription.try_recv().is_some() {}
+            }
+        })
+    }
+
+    fn catch_up_lock(&self, name: &str) -> Arc<Mutex<()>> {
+        self.catching_up
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Folds the feed after the projection's checkpoint into its state,
+    /// saving the state and checkpoint after every page.
+    ///
+    /// Callers hold the projection's `catching_up` lock. If another instance
+    /// saved the projection in the meantime, continues from what it saved.
+    async fn catch_up(&self, mut projection: Projection) -> Result<Projection> {
+        loop {
+            let page = self
+                .events
+                .read_all(projection.checkpoint + 1, CATCH_UP_PAGE_SIZE)
+                .await?;
+            if page.events.is_empty() {
+                return Ok(projection);
+            }
+
+            let mut state = projection.state.clone();
+            for event in &page.events {
+                if projection.definition.matches(event) {
+                    projection.definition.reducer.apply(&mut state, event);
+                }
+            }
+            let checkpoint = page.next_position - 1;
+            if self.advance(&projection, state.clone(), checkpoint).await? {
+                projection.state = state;
+                projection.checkpoint = checkpoint;
+                projection.updated_at = Utc::now();
+            } else {
+                match self.load(&projection.name).await? {
+                    Some(saved) => projection = saved,
+                    None => return Ok(projection),
+                }
+            }
+        }
+    }
+
+    async fn load(&self, name: &str) -> Result<Option<Projection>> {
+        match &self.backend {
+            ProjectionBackend::Memory(projections) => {
+                Ok(projections.read().await.get(name).cloned())
+            }
+            ProjectionBackend::Postgres(pg) => sqlx::query_as::<_, ProjectionRow>(
+                "SELECT name, definition, state, checkpoint, updated_at
+                 FROM projections WHERE name = $1",
+            )
+            .bind(name)
+            .fetch_optional(pg.get_pool())
+            .await
+            .map_err(|e| SyrosError::StorageError(e.to_string()))?
+            .map(from_row)
+            .transpose(),
+        }
+    }
+
+    /// Saves `projection` in full, replacing any projection of the same name.
+    async fn store(&self, projection: &Projection) -> Result<()> {
+        let pg = match &self.backend {
+            ProjectionBackend::Memory(projections) => {
+                projections
+                    .write()
+                    .await
+                    .insert(projection.name.clone(), projection.clone());
+                return Ok(());
+            }
+            ProjectionBackend::Postgres(pg) => pg,
+        };
+        let definition = serde_json::to_value(&projection.definition).map_err(|e| {
+            SyrosError::ApiError(format!(
+                "Invalid projection definition {}: {}",
+                projection.name, e
+            ))
+        })?;
+        sqlx::query(
+            "INSERT INTO projections (name, definition, state, checkpoint, updated_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (name) DO UPDATE
+             SET definition = EXCLUDED.definition, state = EXCLUDED.state,
+                 checkpoint = EXCLUDED.checkpoint, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&projection.name)
+        .bind(definition)
+        .bind(&projection.state)
+        .bind(projection.checkpoint)
+        .bind(projection.updated_at)
+        .execute(pg.get_pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Saves `state` as of `checkpoint` if the projection is still saved at
+    /// the checkpoint `projection` was read at; returns whether it was.
+    async fn advance(
+        &self,
+        projection: &Projection,
+        state: Value,
+        checkpoint: i64,
+    ) -> Result<bool> {
+        let pg = match &self.backend {
+            ProjectionBackend::Memory(projections) => {
+                let mut projections = projections.write().await;
+                let Some(saved) = projections
+                    .get_mut(&projection.name)
+                    .filter(|saved| saved.checkpoint == projection.checkpoint)
+                else {
+                    return Ok(false);
+                };
+                saved.state = state;
+                saved.checkpoint = checkpoint;
+                saved.updated_at = Utc::now();
+                return Ok(true);
+            }
+            ProjectionBackend::Postgres(pg) => pg,
+        };
+        let updated = sqlx::query(
+            "UPDATE projections SET state = $3, checkpoint = $4, updated_at = NOW()
+             WHERE name = $1 AND checkpoint = $2",
+        )
+        .bind(&projection.name)
+        .bind(projection.checkpoint)
+        .bind(state)
+        .bind(checkpoint)
+        .execute(pg.get_pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        Ok(updated.rows_affected() == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event_store::EventRequest;
+
+    async fn append(events: &EventStore, stream_id: &str, event_type: &str, data: Value) {
+        events
+            .append_event(EventRequest {
+                stream_id: stream_id.to_string(),
+                event_type: event_type.to_string(),
+                data,
+                metadata: None,
+                created_by: None,
+                expected_version: None,
+                event_id: None,
+                correlation_id: None,
+                causation_id: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    fn definition(stream_prefix: &str, reducer: Reducer) -> ProjectionDefinition {
+        ProjectionDefinition {
+            stream_prefix: stream_prefix.to_string(),
+            event_types: None,
+            reducer,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reducers_fold_the_selected_events() {
+        let events = EventStore::in_memory();
+        let projections = Projections::new(events.clone());
+        append(
+            &events,
+            "orders-1",
+            "order.created",
+            serde_json::json!({ "total": 10 }),
+        )
+        .await;
+        append(
+            &events,
+            "payments-1",
+            "payment.made",
+            serde_json::json!({ "paid": true }),
+        )
+        .await;
+        append(
+            &events,
+            "orders-2",
+            "order.shipped",
+            serde_json::json!({ "carrier": "ups" }),
+        )
+        .await;
+
+        let merged = projections
+            .register("orders", definition("orders-", Reducer::Merge))
+            .await
+            .unwrap();
+        assert_eq!(
+            merged.state,
+            serde_json::json!({ "total": 10, "carrier": "ups" })
+        );
+        let appended = projections
+            .register("everything", definition("", Reducer::Append))
+            .await
+            .unwrap();
+        assert_eq!(appended.state.as_array().unwrap().len(), 3);
+
+        let shipments = ProjectionDefinition {
+            event_types: Some(vec!["order.shipped".to_string()]),
+            ..definition("orders-", Reducer::Counter)
+        };
+        projections.register("shipments", shipments).await.unwrap();
+        append(&events, "orders-3", "order.shipped", Value::Null).await;
+        append(&events, "orders-3", "order.created", Value::Null).await;
+        let counted = projections.get("shipments").await.unwrap().unwrap();
+        assert_eq!(counted.state, 2);
+        assert_eq!(counted.checkpoint, 5);
+        assert!(projections.get("missing").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_append_keeps_the_latest_items() {
+        let mut state = Reducer::Append.initial_state();
+        for n in 0..MAX_APPENDED_ITEMS + 5 {
+            let event = Event {
+                id: n.to_string(),
+                stream_id: "orders-1".to_string(),
+                event_type: "order.created".to_string(),
+                data: Value::from(n),
+                metadata: HashMap::new(),
+                timestamp: Utc::now(),
+                version: n as i64 + 1,
+                position: n as i64 + 1,
+            };
+            Reducer::Append.apply(&mut state, &event);
+        }
+        let items = state.as_array().unwrap();
+        assert_eq!(items.len(), MAX_APPENDED_ITEMS);
+        assert_eq!(items[0], 5);
+        assert_eq!(items[MAX_APPENDED_ITEMS - 1], MAX_APPENDED_ITEMS + 4);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_folds_the_feed_again() {
+        let events = EventStore::in_memory();
+        let projections = Projections::new(events.clone());
+        append(&events, "orders-1", "order.created", Value::Null).await;
+        let counter = definition("orders-", Reducer::Counter);
+        projections
+            .register("orders", counter.clone())
+            .await
+            .unwrap();
+        // Registering the same definition again does not count twice.
+        let registered = projections.register("orders", counter).await.unwrap();
+        assert_eq!(registered.state, 1);
+
+        append(&events, "orders-2", "order.created", Value::Null).await;
+        events.delete_stream("orders-1", None, true).await.unwrap();
+        assert_eq!(projections.get("orders").await.unwrap().unwrap().state, 2);
+        let rebuilt = projections.rebuild("orders").await.unwrap();
+        assert_eq!(rebuilt.state, 1);
+        assert_eq!(rebuilt.checkpoint, 2);
+        assert!(matches!(
+            projections.rebuild("missing").await,
+            Err(SyrosError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_following_catches_up_on_appends() {
+        let events = EventStore::in_memory();
+        let projections = Projections::new(events.clone());
+        projections
+            .register("orders", definition("orders-", Reducer::Counter))
+            .await
+            .unwrap();
+        let follower = projections.follow(events.subscribe("*"));
+
+        append(&events, "orders-1", "order.created", Value::Null).await;
+        let mut checkpoint = 0;
+        for _ in 0..50 {
+            checkpoint = match &projections.backend {
+                ProjectionBackend::Memory(saved) => saved.read().await["orders"].checkpoint,
+                ProjectionBackend::Postgres(_) => unreachable!(),
+            };
+            if checkpoint == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(checkpoint, 1);
+        follower.abort();
+    }
+
+    /// Postgres used by tests of the Postgres backend, migrated with the
+    /// files in `migrations/`; the test is skipped when unset.
+    const TEST_DATABASE_URL_ENV: &str = "SYROS_TEST_DATABASE_URL";
+
+    #[tokio::test]
+    async fn test_postgres_projections_resume_from_their_checkpoint() {
+        let Ok(url) = std::env::var(TEST_DATABASE_URL_ENV) else {
+            eprintln!(
+                "Skipping: set {} to run against Postgres",
+                TEST_DATABASE_URL_ENV
+            );
+            return;
+        };
+        let stream_id = format!("orders-{}", uuid::Uuid::new_v4());
+        let name = format!("projection-{}", uuid::Uuid::new_v4());
+        let counter = definition(&stream_id, Reducer::Counter);
+        {
+            let pg = PostgresManager::new(&url, 2).await.unwrap();
+            let events = EventStore::new(pg.clone());
+            let projections = Projections::with_postgres(events.clone(), pg);
+            append(&events, &stream_id, "order.created", Value::Null).await;
+            let registered = projections.register(&name, counter.clone()).await.unwrap();
+            assert_eq!(registered.state, 1);
+        }
+
+        // A new pool, as a restarted process would open.
+        let pg = PostgresManager::new(&url, 2).await.unwrap();
+        let events = EventStore::new(pg.clone());
+        let projections = Projections::with_postgres(events.clone(), pg.clone());
+        append(&events, &stream_id, "order.created", Value::Null).await;
+        let resumed = projections.get(&name).await.unwrap().unwrap();
+        assert_eq!(resumed.state, 2);
+        assert_eq!(projections.rebuild(&name).await.unwrap().state, 2);
+
+        sqlx::query("DELETE FROM projections WHERE name = $1")
+            .bind(&name)
+            .execute(pg.get_pool())
+            .await
+            .unwrap();
+        events.delete_stream(&stream_id, None, true).await.unwrap();
+    }
+}
@@ -0,0 +1,537 @@
+//! Dead-letter queue for sagas that failed for good.
+//!
+//! When a compensation exhausts its retries, or a saga's execution fails
+//! without being rolled back, the saga is escalated: operators get a system
+//! notification, the full saga document is appended to the
+//! `saga-dead-letter` event stream, and an optional webhook receives a
+//! summary. Entries stay unresolved until an operator acknowledges them or
+//! requeues the saga.
+
+use crate::core::event_store::{
+    Event, EventFilter, EventRequest, EventStore, GetEventsRequest, ReadDirection,
+};
+use crate::core::saga_orchestrator::Saga;
+use crate::{Result, SyrosError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+
+/// Event stream holding every escalation and resolution.
+pub const DEAD_LETTER_STREAM: &str = "saga-dead-letter";
+/// Event type appended when a saga is escalated.
+pub const DEAD_LETTERED_EVENT: &str = "saga.dead_lettered";
+/// Event type appended when an operator resolves an entry.
+pub const DEAD_LETTER_RESOLVED_EVENT: &str = "saga.dead_letter_resolved";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A saga escalated after it failed or its compensation failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterEntry {
+    pub saga_id: String,
+    pub saga_name: String,
+    /// Step whose compensation could not be completed, or that the saga
+    /// was executing when it failed
+    pub failed_step: Option<String>,
+    /// Last error returned by the compensation, or why the saga failed
+    pub reason: String,
+    /// Full saga document at the time of escalation
+    pub saga: serde_json::Value,
+    pub escalated_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<String>,
+    pub resolution_note: Option<String>,
+}
+
+/// Summary posted to the escalation webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct EscalationSummary {
+    pub saga_id: String,
+    pub saga_name: String,
+    pub failed_step: Option<String>,
+    pub reason: String,
+    pub escalated_at: DateTime<Utc>,
+}
+
+/// Operator-facing notification, broadcast on escalation and when a namespace
+/// is frozen or thawed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemNotification {
+    /// Notification kind, e.g. `saga.compensation_failed`
+    pub kind: String,
+    pub severity: String,
+    pub message: String,
+    /// Saga the notification is about; empty for notifications about other resources
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub saga_id: String,
+    /// Namespace the notification is about, e.g. for `namespace.frozen`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ResolutionRecord {
+    saga_id: String,
+    resolved_at: DateTime<Utc>,
+    resolved_by: Option<String>,
+    note: Option<String>,
+}
+
+/// Escalates failed compensations and tracks their resolution.
+#[derive(Clone)]
+pub struct DeadLetterQueue {
+    entries: Arc<RwLock<HashMap<String, DeadLetterEntry>>>,
+    event_store: Option<EventStore>,
+    webhook_url: Option<String>,
+    http: reqwest::Client,
+    notifications: broadcast::Sender<SystemNotification>,
+}
+
+impl Default for DeadLetterQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeadLetterQueue {
+    pub fn new() -> Self {
+        let (notifications, _) = broadcast::channel(100);
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            event_store: None,
+            webhook_url: None,
+            http: reqwest::Client::new(),
+            notifications,
+        }
+    }
+
+    /// Records escalations and resolutions in the dead-letter event stream.
+    pub fn with_event_store(mut self, event_store: EventStore) -> Self {
+        self.event_store = Some(event_store);
+        self
+    }
+
+    /// Posts a summary of every escalation to `url`.
+    pub fn with_webhook(mut self, url: impl Into<String>) -> Self {
+        self.webhook_url = Some(url.into());
+        self
+    }
+
+    /// Subscribes to operator notifications.
+    pub fn subscribe(&self) -> broadcast::Receiver<SystemNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// Rebuilds the entries from the dead-letter stream after a restart.
+    pub async fn restore(&self) -> Result<usize> {
+        let Some(event_store) = &self.event_store else {
+            return Ok(0);
+        };
+
+        let response = event_store
+            .get_events(GetEventsRequest {
+                stream_id: DEAD_LETTER_STREAM.to_string(),
+                from_version: None,
+                from_snapshot: false,
+                to_version: None,
+                filter: EventFilter::default(),
+                direction: ReadDirection::Forward,
+                limit: None,
+            })
+            .await?;
+
+        let mut entries = self.entries.write().await;
+        entries.clear();
+        for event in &response.events {
+            apply_event(&mut entries, event);
+        }
+        Ok(entries.len())
+    }
+
+    /// Escalates a saga that failed or whose compensation failed.
+    ///
+    /// Every sink is attempted; a failing stream append or webhook is logged
+    /// and does not prevent the others.
+    pub async fn escalate(
+        &self,
+        saga: &Saga,
+        failed_step: Option<&str>,
+        reason: &str,
+    ) -> DeadLetterEntry {
+        let entry = DeadLetterEntry {
+            saga_id: saga.id.clone(),
+            saga_name: saga.name.clone(),
+            failed_step: failed_step.map(|s| s.to_string()),
+            reason: reason.to_string(),
+            saga: serde_json::to_value(saga).unwrap_or_default(),
+            escalated_at: Utc::now(),
+            resolved_at: None,
+            resolved_by: None,
+            resolution_note: None,
+        };
+
+        self.entries
+            .write()
+            .await
+            .insert(entry.saga_id.clone(), entry.clone());
+
+        let (kind, message) = match saga.status.as_str() {
+            "Failed" => (
+                "saga.failed",
+                format!(
+                    "Saga {} ({}) failed and needs operator attention: {}",
+                    entry.saga_name, entry.saga_id, entry.reason
+                ),
+            ),
+            _ => (
+                "saga.compensation_failed",
+                format!(
+                    "Compensation of saga {} ({}) failed and needs operator attention: {}",
+                    entry.saga_name, entry.saga_id, entry.reason
+                ),
+            ),
+        };
+        let _ = self.notifications.send(SystemNotification {
+            kind: kind.to_string(),
+            severity: "critical".to_string(),
+            message,
+            saga_id: entry.saga_id.clone(),
+            namespace: None,
+            timestamp: entry.escalated_at,
+        });
+
+        if let Err(e) = self
+            .append(
+                DEAD_LETTERED_EVENT,
+                serde_json::to_value(&entry).unwrap_or_default(),
+            )
+            .await
+        {
+            tracing::error!(saga_id = %entry.saga_id, "Failed to append dead-letter entry: {}", e);
+        }
+
+        if let Err(e) = self.call_webhook(&entry).await {
+            tracing::error!(saga_id = %entry.saga_id, "Escalation webhook failed: {}", e);
+        }
+
+        entry
+    }
+
+    /// Lists entries that have not been resolved yet, oldest first.
+    pub async fn list_unresolved(&self) -> Vec<DeadLetterEntry> {
+        let entries = self.entries.read().await;
+        let mut unresolved: Vec<DeadLetterEntry> = entries
+            .values()
+            .filter(|entry| entry.resolved_at.is_none())
+            .cloned()
+            .collect();
+        unresolved.sort_by_key(|entry| entry.escalated_at);
+        unresolved
+    }
+
+    /// Entry of `saga_id`, resolved or not.
+    pub async fn get(&self, saga_id: &str) -> Option<DeadLetterEntry> {
+        self.entries.read().await.get(saga_id).cloned()
+    }
+
+    /// Number of entries that have not been resolved yet.
+    pub async fn unresolved_count(&self) -> usize {
+        self.entries
+            .read()
+            .await
+            .values()
+            .filter(|entry| entry.resolved_at.is_none())
+            .count()
+    }
+
+    /// Acknowledges an entry so it no longer shows up as unresolved.
+    ///
+    /// The resolution is appended to the dead-letter stream first; if that
+    /// fails, the entry stays unresolved.
+    pub async fn resolve(
+        &self,
+        saga_id: &str,
+        resolved_by: Option<String>,
+        note: Option<String>,
+    ) -> Result<DeadLetterEntry> {
+        let record = ResolutionRecord {
+            saga_id: saga_id.to_string(),
+            resolved_at: Utc::now(),
+            resolved_by,
+            note,
+        };
+
+        // Held until the entry is updated, so a concurrent resolution cannot
+        // append a second event for it.
+        let mut entries = self.entries.write().await;
+        let entry = entries.get_mut(saga_id).ok_or_else(|| {
+            SyrosError::NotFound(format!("No dead-letter entry for saga {}", saga_id))
+        })?;
+        if entry.resolved_at.is_some() {
+            return Err(SyrosError::Conflict(format!(
+                "Dead-letter entry for saga {} is already resolved",
+                saga_id
+            )));
+        }
+
+        self.append(
+            DEAD_LETTER_RESOLVED_EVENT,
+            serde_json::to_value(&record).unwrap_or_default(),
+        )
+        .await?;
+        resolve_entry(entry, &record);
+
+        Ok(entry.clone())
+    }
+
+    async fn append(&self, event_type: &str, data: serde_json::Value) -> Result<()> {
+        let Some(event_store) = &self.event_store else {
+            return Ok(());
+        };
+
+        event_store
+            .append_event(EventRequest {
+                stream_id: DEAD_LETTER_STREAM.to_string(),
+                event_type: event_type.to_string(),
+                data,
+                metadata: Some(HashMap::new()),
+                created_by: None,
+                expected_version: None,
+                event_id: None,
+                correlation_id: None,
+                causation_id: None,
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn call_webhook(&self, entry: &DeadLetterEntry) -> Result<()> {
+        let Some(url) = &self.webhook_url else {
+            return Ok(());
+        };
+
+        let summary = EscalationSummary {
+            saga_id: entry.saga_id.clone(),
+            saga_name: entry.saga_name.clone(),
+            failed_step: entry.failed_step.clone(),
+            reason: entry.reason.clone(),
+            escalated_at: entry.escalated_at,
+        };
+
+        self.http
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&summary)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SyrosError::SagaError(format!("Webhook call failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+fn resolve_entry(entry: &mut DeadLetterEntry, record: &ResolutionRecord) {
+    entry.resolved_at = Some(record.resolved_at);
+    entry.resolved_by = record.resolved_by.clone();
+    entry.resolution_note = record.note.clone();
+}
+
+fn apply_event(entries: &mut HashMap<String, DeadLetterEntry>, event: &Event) {
+    match event.event_type.as_str() {
+        DEAD_LETTERED_EVENT => {
+            if let Ok(entry) = serde_json::from_value::<DeadLetterEntry>(event.data.clone()) {
+                entries.insert(entry.saga_id.clone(), entry);
+            }
+        }
+        DEAD_LETTER_RESOLVED_EVENT => {
+            if let Ok(record) = serde_json::from_value::<ResolutionRecord>(event.data.clone()) {
+                if let Some(entry) = entries.get_mut(&record.saga_id) {
+                    resolve_entry(entry, &record);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use tokio::sync::mpsc;
+
+    fn failed_saga(id: &str) -> Saga {
+        Saga {
+            id: id.to_string(),
+            name: "checkout".to_string(),
+            status: "CompensationFailed".to_string(),
+            steps: serde_json::json!([{"name": "reserve"}, {"name": "charge"}]),
+            current_step: Some(1),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            metadata: serde_json::json!({"request_id": "req-1"}),
+            deadline_at: None,
+            failure_reason: None,
+            step_results: vec![],
+            start_at: None,
+        }
+    }
+
+    fn stored_event(event_type: &str, data: serde_json::Value, version: i64) -> Event {
+        Event {
+            id: format!("event-{}", version),
+            stream_id: DEAD_LETTER_STREAM.to_string(),
+            event_type: event_type.to_string(),
+            data,
+            metadata: HashMap::new(),
+            timestamp: Utc::now(),
+            version,
+            position: version,
+        }
+    }
+
+    async fn webhook_server() -> (String, mpsc::UnboundedReceiver<serde_json::Value>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(body);
+                    StatusCode::OK
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_escalation_notifies_and_calls_webhook() {
+        let (url, mut webhook) = webhook_server().await;
+        let queue = DeadLetterQueue::new().with_webhook(url);
+        let mut notifications = queue.subscribe();
+
+        let entry = queue
+            .escalate(&failed_saga("saga-1"), Some("charge"), "gateway down")
+            .await;
+        assert_eq!(entry.saga["status"], "CompensationFailed");
+        assert_eq!(entry.saga["metadata"]["request_id"], "req-1");
+
+        let notification = notifications.recv().await.unwrap();
+        assert_eq!(notification.kind, "saga.compensation_failed");
+        assert_eq!(notification.saga_id, "saga-1");
+
+        let summary = webhook.recv().await.unwrap();
+        assert_eq!(summary["saga_id"], "saga-1");
+        assert_eq!(summary["failed_step"], "charge");
+        assert_eq!(summary["reason"], "gateway down");
+
+        let unresolved = queue.list_unresolved().await;
+        assert_eq!(unresolved, vec![entry]);
+    }
+
+    #[tokio::test]
+    async fn test_resolution_flow() {
+        let queue = DeadLetterQueue::new();
+        queue
+            .escalate(&failed_saga("saga-1"), Some("charge"), "gateway down")
+            .await;
+        queue
+            .escalate(&failed_saga("saga-2"), Some("ship"), "carrier down")
+            .await;
+
+        let resolved = queue
+            .resolve(
+                "saga-1",
+                Some("ops@example.com".to_string()),
+                Some("refunded manually".to_string()),
+            )
+            .await
+            .unwrap();
+        assert!(resolved.resolved_at.is_some());
+        assert_eq!(resolved.resolved_by.as_deref(), Some("ops@example.com"));
+
+        let unresolved = queue.list_unresolved().await;
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].saga_id, "saga-2");
+        assert_eq!(queue.unresolved_count().await, 1);
+        assert_eq!(queue.get("saga-1").await, Some(resolved.clone()));
+
+        assert!(matches!(
+            queue.resolve("saga-1", None, None).await,
+            Err(SyrosError::Conflict(_))
+        ));
+        assert!(matches!(
+            queue.resolve("saga-404", None, None).await,
+            Err(SyrosError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resolution_is_recorded_before_it_applies() {
+        let events = EventStore::in_memory();
+        let queue = DeadLetterQueue::new().with_event_store(events.clone());
+        queue
+            .escalate(&failed_saga("saga-1"), Some("charge"), "gateway down")
+            .await;
+
+        // An archived stream rejects the resolution event.
+        events.archive_stream(DEAD_LETTER_STREAM).await.unwrap();
+        assert!(queue.resolve("saga-1", None, None).await.is_err());
+        assert_eq!(queue.unresolved_count().await, 1);
+        assert!(queue.get("saga-1").await.unwrap().resolved_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_events_rebuild_entries() {
+        let queue = DeadLetterQueue::new();
+        let first = queue
+            .escalate(&failed_saga("saga-1"), Some("charge"), "gateway down")
+            .await;
+        let second = queue
+            .escalate(&failed_saga("saga-2"), None, "timeout")
+            .await;
+        let resolution = ResolutionRecord {
+            saga_id: "saga-1".to_string(),
+            resolved_at: Utc::now(),
+            resolved_by: Some("ops".to_string()),
+            note: None,
+        };
+
+        let events = vec![
+            stored_event(
+                DEAD_LETTERED_EVENT,
+                serde_json::to_value(&first).unwrap(),
+                1,
+            ),
+            stored_event(
+                DEAD_LETTERED_EVENT,
+                serde_json::to_value(&second).unwrap(),
+                2,
+            ),
+            stored_event(
+                DEAD_LETTER_RESOLVED_EVENT,
+                serde_json::to_value(&resolution).unwrap(),
+                3,
+            ),
+        ];
+
+        let mut entries = HashMap::new();
+        for event in &events {
+            apply_event(&mut entries, event);
+        }
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["saga-1"].resolved_by.as_deref(), Some("ops"));
+        assert_eq!(entries["saga-2"], second);
+        assert_eq!(entries["saga-2"].saga["name"], "checkout");
+    }
+}
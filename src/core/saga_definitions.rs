@@ -0,0 +1,373 @@
+//! Named saga definitions.
+//!
+//! A definition is a validated [`SagaRequest`] stored under a name, from
+//! which saga instances can later be started. Registering a name again adds
+//! a new version rather than replacing it, so running sagas and clients
+//! pinned to `checkout@2` keep their steps; a reference without a version
+//! picks the latest one. Definitions are kept in Postgres, shared by every
+//! instance, or in process memory.
+
+use crate::core::saga_orchestrator::SagaRequest;
+use crate::storage::postgres::PostgresManager;
+use crate::{Result, SyrosError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// One version of a named saga definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaDefinition {
+    pub name: String,
+    /// 1 for the first definition registered under the name, then counting up
+    pub version: u32,
+    pub definition: SagaRequest,
+    /// Principal that registered the version
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SagaDefinition {
+    /// Reference pinning this version, e.g. `checkout@2`.
+    pub fn reference(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+}
+
+/// Splits a definition reference into its name and version, e.g.
+/// `checkout@2`; the version is `None` for a bare name, meaning the latest.
+pub fn parse_reference(reference: &str) -> Result<(&str, Option<u32>)> {
+    let (name, version) = match reference.rsplit_once('@') {
+        Some((name, version)) => {
+            let version = version.parse().ok().filter(|version| *version > 0);
+            match version {
+                Some(version) => (name, Some(version)),
+                None => {
+                    return Err(SyrosError::SagaError(format!(
+                    "Invalid saga definition reference {}: the version must be a positive number",
+                    reference
+                )))
+                }
+            }
+        }
+        None => (reference, None),
+    };
+    if name.trim().is_empty() {
+        return Err(SyrosError::SagaError(format!(
+            "Invalid saga definition reference {}: the name must not be empty",
+            reference
+        )));
+    }
+    Ok((name, version))
+}
+
+/// Storage behind [`SagaDefinitions`].
+#[derive(Clone)]
+enum DefinitionBackend {
+    Postgres(PostgresManager),
+    /// Versions of every definition, oldest first, by name
+    Memory(Arc<RwLock<HashMap<String, Vec<SagaDefinition>>>>),
+}
+
+type DefinitionRow = (
+    String,
+    i32,
+    serde_json::Value,
+    Option<String>,
+    DateTime<Utc>,
+);
+
+fn from_row(
+    (name, version, definition, created_by, created_at): DefinitionRow,
+) -> Result<SagaDefinition> {
+    let definition = serde_json::from_value(definition).map_err(|e| {
+        SyrosError::StorageError(format!(
+            "Invalid saga definition {}@{}: {}",
+            name, version, e
+        ))
+    })?;
+    Ok(SagaDefinition {
+        name,
+        version: version.max(0) as u32,
+        definition,
+        created_by,
+        created_at,
+    })
+}
+
+/// Registry of saga definitions, by name and version.
+#[derive(Clone)]
+pub struct SagaDefinitions {
+    backend: DefinitionBackend,
+}
+
+impl Default for SagaDefinitions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SagaDefinitions {
+    /// Creates a registry that keeps its definitions in memory.
+    pub fn new() -> Self {
+        Self {
+            backend: DefinitionBackend::Memory(Arc::default()),
+        }
+    }
+
+    /// Creates a registry that keeps its definitions in Postgres.
+    pub fn with_postgres(pg: PostgresManager) -> Self {
+        Self {
+            backend: DefinitionBackend::Postgres(pg),
+        }
+    }
+
+    /// Stores `definition` as the next version of `name` and returns it.
+    ///
+    /// Fails with `Conflict` if another version of the name was registered
+    /// at the same time through another instance.
+    pub async fn register(
+        &self,
+        name: &str,
+        definition: SagaRequest,
+        created_by: Option<String>,
+    ) -> Result<SagaDefinition> {
+        let pg = match &self.backend {
+            DefinitionBackend::Postgres(pg) => pg,
+            DefinitionBackend::Memory(definitions) => {
+                let mut definitions = definitions.write().await;
+                let versions = definitions.entry(name.to_string()).or_default();
+                let registered = SagaDefinition {
+                    name: name.to_string(),
+                    version: versions.len() as u32 + 1,
+                    definition,
+                    created_by,
+                    created_at: Utc::now(),
+                };
+                versions.push(registered.clone());
+                return Ok(registered);
+            }
+        };
+
+        let value = serde_json::to_value(&definition).map_err(|e| {
+            SyrosError::SagaError(format!("Invalid saga definition {}: {}", name, e))
+        })?;
+        let created_at = Utc::now();
+        let version: i32 = sqlx::query_scalar(
+            "INSERT INTO saga_definitions (name, version, definition, created_by, created_at)
+             SELECT $1, COALESCE(MAX(version), 0) + 1, $2, $3, $4 FROM saga_definitions WHERE name = $1
+             RETURNING version",
+        )
+        .bind(name)
+        .bind(value)
+        .bind(&created_by)
+        .bind(created_at)
+        .fetch_one(pg.get_pool())
+        .await
+        .map_err(|e| {
+            if e.as_database_error()
+                .is_some_and(|db| db.is_unique_violation())
+            {
+                SyrosError::Conflict(format!(
+                    "Saga definition {} was registered concurrently, try again",
+                    name
+                ))
+            } else {
+                SyrosError::StorageError(e.to_string())
+            }
+        })?;
+        Ok(SagaDefinition {
+            name: name.to_string(),
+            version: version.max(0) as u32,
+            definition,
+            created_by,
+            created_at,
+        })
+    }
+
+    /// Stores `definition` as the first version of `name` unless the name
+    /// is taken.
+    ///
+    /// Returns whether the definition was stored.
+    pub async fn register_if_absent(&self, name: &str, definition: SagaRequest) -> Result<bool> {
+        if self.get(name, None).await?.is_some() {
+            return Ok(false);
+        }
+        match self.register(name, definition, None).await {
+            Ok(_) => Ok(true),
+            Err(SyrosError::Conflict(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Version `version` of the definition registered under `name`, or its
+    /// latest version when `None`.
+    pub async fn get(&self, name: &str, version: Option<u32>) -> Result<Option<SagaDefinition>> {
+        let pg = match &self.backend {
+            DefinitionBackend::Postgres(pg) => pg,
+            DefinitionBackend::Memory(definitions) => {
+                let definitions = definitions.read().await;
+                let versions = definitions.get(name).map(Vec::as_slice).unwrap_or_default();
+                let found = match version {
+                    Some(version) => versions.get((version as usize).wrapping_sub(1)),
+                    None => versions.last(),
+                };
+                return Ok(found.cloned());
+            }
+        };
+
+        let row: Option<DefinitionRow> = sqlx::query_as(
+            "SELECT name, version, definition, created_by, created_at FROM saga_definitions
+             WHERE name = $1 AND ($2::INTEGER IS NULL OR version = $2)
+             ORDER BY version DESC LIMIT 1",
+        )
+        .bind(name)
+        .bind(version.map(|version| version.min(i32::MAX as u32) as i32))
+        .fetch_optional(pg.get_pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        row.map(from_row).transpose()
+    }
+
+    /// The definition `reference` points to, e.g. `checkout@2`, or the
+    /// latest version of `checkout`.
+    ///
+    /// Fails with `NotFound` if no such definition is registered, and with
+    /// `SagaError` for a malformed reference.
+    pub async fn resolve(&self, reference: &str) -> Result<SagaDefinition> {
+        let (name, version) = parse_reference(reference)?;
+        self.get(name, version)
+            .await?
+            .ok_or_else(|| SyrosError::NotFound(format!("Saga definition {} not found", reference)))
+    }
+
+    /// Every version of the definition registered under `name`, oldest
+    /// first.
+    pub async fn versions(&self, name: &str) -> Result<Vec<SagaDefinition>> {
+        let pg = match &self.backend {
+            DefinitionBackend::Postgres(pg) => pg,
+            DefinitionBackend::Memory(definitions) => {
+                return Ok(definitions
+                    .read()
+                    .await
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_default())
+            }
+        };
+
+        let rows: Vec<DefinitionRow> = sqlx::query_as(
+            "SELECT name, version, definition, created_by, created_at FROM saga_definitions
+             WHERE name = $1 ORDER BY version",
+        )
+        .bind(name)
+        .fetch_all(pg.get_pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /// The latest version of every definition, sorted by name.
+    pub async fn list(&self) -> Result<Vec<SagaDefinition>> {
+        let pg = match &self.backend {
+            DefinitionBackend::Postgres(pg) => pg,
+            DefinitionBackend::Memory(definitions) => {
+                let mut latest: Vec<SagaDefinition> = definitions
+                    .read()
+                    .await
+                    .values()
+                    .filter_map(|versions| versions.last().cloned())
+                    .collect();
+                latest.sort_by(|a, b| a.name.cmp(&b.name));
+                return Ok(latest);
+            }
+        };
+
+        let rows: Vec<DefinitionRow> = sqlx::query_as(
+            "SELECT DISTINCT ON (name) name, version, definition, created_by, created_at
+             FROM saga_definitions ORDER BY name, version DESC",
+        )
+        .fetch_all(pg.get_pool())
+        .await
+        .map_err(|e| SyrosError::StorageError(e.to_string()))?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /// Names of every registered definition, sorted.
+    pub async fn names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .map(|definition| definition.name)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: &str) -> SagaRequest {
+        SagaRequest {
+            name: name.to_string(),
+            steps: vec![],
+            metadata: None,
+            max_duration: None,
+            start_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(parse_reference("checkout").unwrap(), ("checkout", None));
+        assert_eq!(
+            parse_reference("checkout@2").unwrap(),
+            ("checkout", Some(2))
+        );
+        for invalid in ["checkout@", "checkout@0", "checkout@latest", "@2", " "] {
+            assert!(parse_reference(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registering_a_name_again_adds_a_version() {
+        let definitions = SagaDefinitions::new();
+        let first = definitions
+            .register(
+                "checkout",
+                request("checkout-v1"),
+                Some("alice".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.reference(), "checkout@1");
+        definitions
+            .register("checkout", request("checkout-v2"), None)
+            .await
+            .unwrap();
+        assert!(!definitions
+            .register_if_absent("checkout", request("seeded"))
+            .await
+            .unwrap());
+        assert!(definitions
+            .register_if_absent("refund", request("refund"))
+            .await
+            .unwrap());
+
+        let latest = definitions.resolve("checkout").await.unwrap();
+        assert_eq!(
+            (latest.version, latest.definition.name.as_str()),
+            (2, "checkout-v2")
+        );
+        let pinned = definitions.resolve("checkout@1").await.unwrap();
+        assert_eq!(pinned.created_by.as_deref(), Some("alice"));
+        assert!(matches!(
+            definitions.resolve("checkout@3").await,
+            Err(SyrosError::NotFound(_))
+        ));
+        assert_eq!(definitions.versions("checkout").await.unwrap().len(), 2);
+        assert_eq!(definitions.names().await.unwrap(), ["checkout", "refund"]);
+    }
+}
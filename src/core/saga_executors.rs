@@ -0,0 +1,360 @@
+//! Executors running the actions and compensations of saga steps.
+//!
+//! A step names the executor that runs it in its `executor` field, looked up
+//! in the [`StepExecutors`] of the orchestrator by name. Steps naming none
+//! run through the default executor, if one is set, then through `http`, and
+//! are only simulated when neither is available.
+//!
+//! Two executors ship with Syros: [`HttpStepClient`] is registered as `http`
+//! when the orchestrator calls step services over HTTP, and every
+//! orchestrator has a `manual` [`ManualStepExecutor`] parking steps until an
+//! operator or an outside system confirms them. The server also registers
+//! its [`SagaWorkerRegistry`] as `worker`, queuing steps for pull-mode
+//! workers. Other transports, e.g. gRPC
+//! or a message queue, implement [`SagaStepExecutor`] and are registered
+//! under a name of their own with
+//! [`SagaOrchestrator::with_executor`](crate::core::SagaOrchestrator::with_executor).
+//!
+//! [`HttpStepClient`]: crate::core::saga_http::HttpStepClient
+//! [`SagaWorkerRegistry`]: crate::core::SagaWorkerRegistry
+
+use crate::core::saga_orchestrator::{SagaStep, StepCallContext};
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Name of the executor calling step services over HTTP.
+pub const HTTP_EXECUTOR: &str = "http";
+/// Name of the executor parking steps until they are confirmed.
+pub const MANUAL_EXECUTOR: &str = "manual";
+/// Name of the executor queuing steps for pull-mode workers.
+pub const WORKER_EXECUTOR: &str = "worker";
+
+/// What the action of a step produced.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepOutcome {
+    /// Answer recorded as the step's result, within the result limits;
+    /// nothing is recorded when `None`
+    pub output: Option<Vec<u8>>,
+}
+
+impl StepOutcome {
+    /// An outcome recording `output` as the step's result.
+    pub fn with_output(output: Vec<u8>) -> Self {
+        Self {
+            output: Some(output),
+        }
+    }
+}
+
+/// Runs the action and compensation of saga steps over some transport.
+///
+/// Both calls get the step with its payload references resolved and the
+/// saga metadata. The orchestrator applies the step's timeout and retry
+/// policy around them, so an error fails the attempt.
+#[async_trait]
+pub trait SagaStepExecutor: Send + Sync {
+    /// Runs the action of `step`.
+    async fn execute(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<StepOutcome>;
+
+    /// Undoes the action of `step`; `context` is a compensation call.
+    async fn compensate(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<()>;
+}
+
+/// Runs the action of a saga step, or its compensation when the context is
+/// a compensation call, see
+/// [`SagaOrchestrator::with_step_executor`](crate::core::SagaOrchestrator::with_step_executor).
+pub type SagaStepHandler =
+    Arc<dyn Fn(SagaStep, StepCallContext) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// Handlers record no result.
+#[async_trait]
+impl SagaStepExecutor for SagaStepHandler {
+    async fn execute(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        _metadata: &HashMap<String, String>,
+    ) -> Result<StepOutcome> {
+        self(step.clone(), context.clone()).await?;
+        Ok(StepOutcome::default())
+    }
+
+    async fn compensate(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        _metadata: &HashMap<String, String>,
+    ) -> Result<()> {
+        self(step.clone(), context.clone()).await
+    }
+}
+
+/// Executors of an orchestrator, by name.
+#[derive(Clone, Default)]
+pub struct StepExecutors {
+    executors: HashMap<String, Arc<dyn SagaStepExecutor>>,
+    /// Runs the steps naming no executor, before `http`
+    default: Option<Arc<dyn SagaStepExecutor>>,
+}
+
+impl StepExecutors {
+    /// Runs the steps naming `name` through `executor`, replacing any
+    /// executor registered under that name.
+    pub fn register(&mut self, name: &str, executor: Arc<dyn SagaStepExecutor>) {
+        self.executors.insert(name.to_string(), executor);
+    }
+
+    /// Runs the steps naming no executor through `executor`.
+    pub fn set_default(&mut self, executor: Arc<dyn SagaStepExecutor>) {
+        self.default = Some(executor);
+    }
+
+    /// Whether an executor is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.executors.contains_key(name)
+    }
+
+    /// Names of the registered executors, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.executors.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Executor running `step`, or `None` if it names none and there is
+    /// neither a default nor an `http` executor, in which case the step is
+    /// simulated.
+    ///
+    /// Fails if the step names an executor that is not registered.
+    pub fn for_step(&self, step: &SagaStep) -> Result<Option<Arc<dyn SagaStepExecutor>>> {
+        match &step.executor {
+            Some(name) => self.executors.get(name).cloned().map(Some).ok_or_else(|| {
+                SyrosError::SagaError(format!(
+                    "Step {} names unknown executor {}",
+                    step.name, name
+                ))
+            }),
+            None => Ok(self
+                .default
+                .clone()
+                .or_else(|| self.executors.get(HTTP_EXECUTOR).cloned())),
+        }
+    }
+}
+
+/// Confirmations awaited by parked steps, by saga ID and step name.
+type ParkedSteps = Mutex<HashMap<(String, String), oneshot::Sender<Result<StepOutcome>>>>;
+
+/// Parks steps until they are confirmed through
+/// [`confirm`](Self::confirm), for actions done by hand or by systems that
+/// report back on their own.
+///
+/// A parked step waits up to its timeout like any other call, so it fails,
+/// and is retried per its retry policy, if nobody confirms it in time.
+/// Steps are parked in the memory of the instance running the saga, which
+/// is the one that must confirm them. Compensating a manual step does
+/// nothing: whoever carried it out undoes it.
+#[derive(Clone, Default)]
+pub struct ManualStepExecutor {
+    parked: Arc<ParkedSteps>,
+}
+
+impl ManualStepExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether step `step` of saga `saga_id` is parked waiting for
+    /// confirmation.
+    pub fn is_parked(&self, saga_id: &str, step: &str) -> bool {
+        self.parked
+            .lock()
+            .unwrap()
+            .get(&(saga_id.to_string(), step.to_string()))
+            .is_some_and(|confirmation| !confirmation.is_closed())
+    }
+
+    /// Ends the parked step `step` of saga `saga_id` with `outcome`: an
+    /// outcome completes the attempt, an error fails it.
+    ///
+    /// Fails with `Conflict` if the step is not parked on this instance,
+    /// e.g. it has not started yet or already ended.
+    pub fn confirm(&self, saga_id: &str, step: &str, outcome: Result<StepOutcome>) -> Result<()> {
+        let confirmation = self
+            .parked
+            .lock()
+            .unwrap()
+            .remove(&(saga_id.to_string(), step.to_string()));
+        match confirmation.map(|confirmation| confirmation.send(outcome)) {
+            Some(Ok(())) => Ok(()),
+            _ => Err(SyrosError::Conflict(format!(
+                "Step {} of saga {} is not waiting for confirmation",
+                step, saga_id
+            ))),
+        }
+    }
+}
+
+/// A parked step, no longer awaiting confirmation once dropped.
+struct Parked<'a> {
+    parked: &'a ParkedSteps,
+    key: (String, String),
+    confirmation: oneshot::Receiver<Result<StepOutcome>>,
+}
+
+impl Drop for Parked<'_> {
+    fn drop(&mut self) {
+        self.confirmation.close();
+        let mut parked = self.parked.lock().unwrap();
+        // A later attempt of the step may have parked it again
+        if parked
+            .get(&self.key)
+            .is_some_and(|confirmation| confirmation.is_closed())
+        {
+            parked.remove(&self.key);
+        }
+    }
+}
+
+#[async_trait]
+impl SagaStepExecutor for ManualStepExecutor {
+    async fn execute(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        _metadata: &HashMap<String, String>,
+    ) -> Result<StepOutcome> {
+        let key = (context.saga_id.clone(), step.name.clone());
+        let (confirm, confirmation) = oneshot::channel();
+        self.parked.lock().unwrap().insert(key.clone(), confirm);
+        tracing::info!(
+            saga_id = %context.saga_id,
+            step = %step.name,
+            "Saga step parked until it is confirmed"
+        );
+
+        let mut parked = Parked {
+            parked: &self.parked,
+            key,
+            confirmation,
+        };
+        (&mut parked.confirmation).await.unwrap_or_else(|_| {
+            Err(SyrosError::SagaError(format!(
+                "Step {} was parked again before it was confirmed",
+                step.name
+            )))
+        })
+    }
+
+    async fn compensate(
+        &self,
+        _step: &SagaStep,
+        _context: &StepCallContext,
+        _metadata: &HashMap<String, String>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn step(name: &str, executor: Option<&str>) -> SagaStep {
+        SagaStep {
+            name: name.to_string(),
+            service: "inventory".to_string(),
+            action: "reserve".to_string(),
+            compensation: "release".to_string(),
+            timeout: Duration::from_secs(5),
+            retry_policy: None,
+            payload: None,
+            acquired_locks: vec![],
+            cache_keys: vec![],
+            group: None,
+            executor: executor.map(|executor| executor.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_steps_resolve_their_executor_by_name() {
+        let mut executors = StepExecutors::default();
+        assert!(executors.for_step(&step("a", None)).unwrap().is_none());
+        assert!(matches!(
+            executors.for_step(&step("a", Some("queue"))),
+            Err(SyrosError::SagaError(_))
+        ));
+
+        executors.register(MANUAL_EXECUTOR, Arc::new(ManualStepExecutor::new()));
+        executors.register(HTTP_EXECUTOR, Arc::new(ManualStepExecutor::new()));
+        assert_eq!(executors.names(), [HTTP_EXECUTOR, MANUAL_EXECUTOR]);
+        assert!(executors.for_step(&step("a", None)).unwrap().is_some());
+        assert!(executors
+            .for_step(&step("a", Some(MANUAL_EXECUTOR)))
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_manual_steps_wait_for_confirmation() {
+        let manual = ManualStepExecutor::new();
+        let metadata = HashMap::new();
+        assert!(matches!(
+            manual.confirm("saga-1", "approve", Ok(StepOutcome::default())),
+            Err(SyrosError::Conflict(_))
+        ));
+
+        let parked = {
+            let manual = manual.clone();
+            tokio::spawn(async move {
+                let context = StepCallContext::new("saga-1", "approve", 1, None);
+                manual
+                    .execute(&step("approve", Some(MANUAL_EXECUTOR)), &context, &metadata)
+                    .await
+            })
+        };
+        while !manual.is_parked("saga-1", "approve") {
+            tokio::task::yield_now().await;
+        }
+        manual
+            .confirm(
+                "saga-1",
+                "approve",
+                Ok(StepOutcome::with_output(b"{\"approved\":true}".to_vec())),
+            )
+            .unwrap();
+        assert_eq!(
+            parked.await.unwrap().unwrap().output.as_deref(),
+            Some(&b"{\"approved\":true}"[..])
+        );
+        assert!(!manual.is_parked("saga-1", "approve"));
+
+        // A step that stopped waiting, e.g. on its timeout, cannot be confirmed.
+        let context = StepCallContext::new("saga-1", "approve", 2, None);
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(10),
+            manual.execute(&step("approve", None), &context, &HashMap::new()),
+        )
+        .await;
+        assert!(waiting.is_err());
+        assert!(!manual.is_parked("saga-1", "approve"));
+        assert!(manual
+            .confirm("saga-1", "approve", Ok(StepOutcome::default()))
+            .is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! HTTP calls to the services saga steps name, the `http` step executor.
+//!
+//! A step's action is a `POST {base}/{action}` to its service, and its
+//! compensation a `POST {base}/{compensation}`. The base URL of a service is
+//! looked up in the saga metadata under `service_url.<service>`, then in the
+//! `[services]` section of the configuration, and otherwise defaults to
+//! `http://<service>`.
+//!
+//! The request body carries the step payload, its references resolved by the
+//! orchestrator, and the saga metadata, and the headers of the
+//! [`StepCallContext`] identify the call. A `2xx` answer
+//! succeeds with the response body; any other status, a connection error or
+//! exceeding the step's timeout, as `Timeout`, fails the step.
+
+use crate::core::saga_executors::{SagaStepExecutor, StepOutcome};
+use crate::core::saga_orchestrator::{SagaStep, StepCallContext};
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Prefix of the saga metadata keys overriding a service's base URL, e.g.
+/// `service_url.inventory`.
+pub const SERVICE_URL_METADATA_PREFIX: &str = "service_url.";
+
+/// Body posted to a step service.
+#[derive(Debug, Serialize)]
+struct StepCallBody<'a> {
+    saga_id: &'a str,
+    step: &'a str,
+    /// Whether this is a compensation call
+    compensation: bool,
+    payload: Option<&'a serde_json::Value>,
+    metadata: &'a HashMap<String, String>,
+}
+
+/// Calls step services over HTTP.
+#[derive(Clone, Default)]
+pub struct HttpStepClient {
+    client: reqwest::Client,
+    /// Base URL of each service, by name
+    services: HashMap<String, String>,
+}
+
+impl HttpStepClient {
+    /// Creates a client resolving services through `services`, e.g. the
+    /// `[services]` section of the configuration.
+    pub fn new(services: HashMap<String, String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            services,
+        }
+    }
+
+    /// Base URL of `service` for a saga with `metadata`, without a trailing
+    /// `/`.
+    pub fn service_url(&self, service: &str, metadata: &HashMap<String, String>) -> String {
+        let url = metadata
+            .get(&format!("{}{}", SERVICE_URL_METADATA_PREFIX, service))
+            .or_else(|| self.services.get(service))
+            .cloned()
+            .unwrap_or_else(|| format!("http://{}", service));
+        url.trim_end_matches('/').to_string()
+    }
+
+    /// Calls the action of `step`, or its compensation when `context` is a
+    /// compensation call, and returns the response body.
+    ///
+    /// The payload of `step` is sent as is, so its references must already
+    /// be resolved, see [`render_payload`](crate::core::saga_template::render_payload).
+    ///
+    /// A step without a compensation has nothing to undo, so compensating it
+    /// makes no call and returns an empty body.
+    pub async fn call(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<Vec<u8>> {
+        let action = if context.compensation {
+            &step.compensation
+        } else {
+            &step.action
+        };
+        if context.compensation && action.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let url = format!(
+            "{}/{}",
+            self.service_url(&step.service, metadata),
+            action.trim_start_matches('/')
+        );
+        let mut request = self.client.post(&url).json(&StepCallBody {
+            saga_id: &context.saga_id,
+            step: &step.name,
+            compensation: context.compensation,
+            payload: step.payload.as_ref(),
+            metadata,
+        });
+        for (name, value) in context.headers() {
+            request = request.header(name, value);
+        }
+        if !step.timeout.is_zero() {
+            request = request.timeout(step.timeout);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                SyrosError::Timeout(format!(
+                    "Calling {} timed out after {:?}",
+                    url, step.timeout
+                ))
+            } else {
+                SyrosError::SagaError(format!("Calling {} failed: {}", url, e))
+            }
+        })?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(|e| {
+            SyrosError::SagaError(format!("Reading the answer of {} failed: {}", url, e))
+        })?;
+        if !status.is_success() {
+            return Err(SyrosError::SagaError(format!(
+                "{} answered {}: {}",
+                url,
+                status,
+                String::from_utf8_lossy(&body)
+            )));
+        }
+        Ok(body.to_vec())
+    }
+}
+
+/// The answer of an action is recorded as the step's result.
+#[async_trait]
+impl SagaStepExecutor for HttpStepClient {
+    async fn execute(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<StepOutcome> {
+        let body = self.call(step, context, metadata).await?;
+        Ok(StepOutcome::with_output(body))
+    }
+
+    async fn compensate(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<()> {
+        self.call(step, context, metadata).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_url_resolution() {
+        let client = HttpStepClient::new(HashMap::from([(
+            "inventory".to_string(),
+            "http://inventory.internal:8080/".to_string(),
+        )]));
+        let mut metadata = HashMap::new();
+
+        assert_eq!(
+            client.service_url("inventory", &metadata),
+            "http://inventory.internal:8080"
+        );
+        assert_eq!(client.service_url("payments", &metadata), "http://payments");
+
+        metadata.insert(
+            "service_url.inventory".to_string(),
+            "http://127.0.0.1:9000".to_string(),
+        );
+        assert_eq!(
+            client.service_url("inventory", &metadata),
+            "http://127.0.0.1:9000"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Idempotency keys for saga starts.
+//!
+//! A client that times out starting a saga cannot tell whether the saga was
+//! created, so it retries with the same idempotency key. The first start
+//! under a key reserves it for the saga it creates; replays within the TTL
+//! get that saga back instead of a new one, and a different request under
+//! the same key is a conflict. Keys are remembered by the instance that
+//! received the start, in memory.
+
+use crate::core::saga_orchestrator::{SagaRequest, REQUEST_ID_METADATA_KEY};
+use crate::{Result, SyrosError};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Header carrying the idempotency key of a saga start.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Longest idempotency key accepted, in bytes.
+pub const MAX_IDEMPOTENCY_KEY_BYTES: usize = 255;
+/// How long a key is remembered unless configured otherwise.
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Fails if `key` is empty or longer than [`MAX_IDEMPOTENCY_KEY_BYTES`].
+pub fn check_key(key: &str) -> Result<()> {
+    if key.trim().is_empty() {
+        return Err(SyrosError::ApiError(
+            "Idempotency key must not be empty".to_string(),
+        ));
+    }
+    if key.len() > MAX_IDEMPOTENCY_KEY_BYTES {
+        return Err(SyrosError::ApiError(format!(
+            "Idempotency key must be at most {} bytes",
+            MAX_IDEMPOTENCY_KEY_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// Digest of what `request` asks for, telling replays from other requests.
+///
+/// The request ID is left out, since each retry of a client usually carries
+/// a new one.
+pub fn fingerprint(request: &SagaRequest) -> [u8; 32] {
+    let metadata: BTreeMap<&String, &String> = request
+        .metadata
+        .iter()
+        .flatten()
+        .filter(|(key, _)| key.as_str() != REQUEST_ID_METADATA_KEY)
+        .collect();
+    let canonical = serde_json::json!({
+        "name": request.name,
+        "steps": request.steps,
+        "metadata": metadata,
+        "max_duration": request.max_duration,
+        "start_at": request.start_at,
+    });
+    Sha256::digest(canonical.to_string().as_bytes()).into()
+}
+
+/// Saga an idempotency key was reserved for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reservation {
+    /// The key was free and now belongs to this new saga ID
+    New(String),
+    /// The key was already used for the same request, which started this saga
+    Existing(String),
+}
+
+struct KeyEntry {
+    saga_id: String,
+    fingerprint: [u8; 32],
+    expires_at: Instant,
+}
+
+/// Idempotency keys of recent saga starts and the sagas they started.
+#[derive(Clone)]
+pub struct IdempotencyKeys {
+    ttl: Duration,
+    keys: Arc<Mutex<HashMap<String, KeyEntry>>>,
+}
+
+impl Default for IdempotencyKeys {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDEMPOTENCY_TTL)
+    }
+}
+
+impl IdempotencyKeys {
+    /// Creates a registry remembering each key for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            keys: Arc::default(),
+        }
+    }
+
+    /// How long each key is remembered.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Reserves `key` for a new saga started from `request`, or returns the
+    /// saga an earlier start of the same request reserved it for.
+    ///
+    /// Fails with `Conflict` if the key was used for a different request
+    /// within the TTL.
+    pub fn reserve(&self, key: &str, request: &SagaRequest) -> Result<Reservation> {
+        let fingerprint = fingerprint(request);
+        let now = Instant::now();
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|_, entry| entry.expires_at > now);
+        if let Some(entry) = keys.get(key) {
+            if entry.fingerprint != fingerprint {
+                return Err(SyrosError::Conflict(format!(
+                    "Idempotency key {} was already used for a different saga request",
+                    key
+                )));
+            }
+            return Ok(Reservation::Existing(entry.saga_id.clone()));
+        }
+        let saga_id = uuid::Uuid::new_v4().to_string();
+        keys.insert(
+            key.to_string(),
+            KeyEntry {
+                saga_id: saga_id.clone(),
+                fingerprint,
+                expires_at: now + self.ttl,
+            },
+        );
+        Ok(Reservation::New(saga_id))
+    }
+
+    /// Frees `key` if it is still reserved for `saga_id`, after the saga
+    /// failed to start.
+    pub fn release(&self, key: &str, saga_id: &str) {
+        let mut keys = self.keys.lock().unwrap();
+        if keys.get(key).is_some_and(|entry| entry.saga_id == saga_id) {
+            keys.remove(key);
+        }
+    }
+
+    /// Number of keys currently remembered, including expired ones not yet
+    /// dropped.
+    pub fn len(&self) -> usize {
+        self.keys.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: &str, request_id: &str) -> SagaRequest {
+        SagaRequest {
+            name: name.to_string(),
+            steps: vec![],
+            metadata: Some(HashMap::from([
+                ("customer".to_string(), "c-1".to_string()),
+                (REQUEST_ID_METADATA_KEY.to_string(), request_id.to_string()),
+            ])),
+            max_duration: None,
+            start_at: None,
+        }
+    }
+
+    #[test]
+    fn test_replay_returns_the_reserved_saga() {
+        let keys = IdempotencyKeys::default();
+        let Reservation::New(saga_id) = keys
+            .reserve("order-42", &request("checkout", "r-1"))
+            .unwrap()
+        else {
+            panic!("first start must reserve the key");
+        };
+        // A retry carries a new request ID but asks for the same saga.
+        assert_eq!(
+            keys.reserve("order-42", &request("checkout", "r-2"))
+                .unwrap(),
+            Reservation::Existing(saga_id)
+        );
+        assert!(matches!(
+            keys.reserve("order-42", &request("refund", "r-3")),
+            Err(SyrosError::Conflict(_))
+        ));
+        assert!(matches!(
+            keys.reserve("order-43", &request("refund", "r-3")).unwrap(),
+            Reservation::New(_)
+        ));
+    }
+
+    #[test]
+    fn test_keys_expire_after_their_ttl() {
+        let keys = IdempotencyKeys::new(Duration::from_millis(20));
+        let first = keys
+            .reserve("order-42", &request("checkout", "r-1"))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+
+        let second = keys.reserve("order-42", &request("refund", "r-2")).unwrap();
+        assert!(matches!(second, Reservation::New(_)));
+        assert_ne!(first, second);
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn test_release_frees_only_the_own_reservation() {
+        let keys = IdempotencyKeys::default();
+        let Reservation::New(saga_id) = keys
+            .reserve("order-42", &request("checkout", "r-1"))
+            .unwrap()
+        else {
+            panic!("first start must reserve the key");
+        };
+        keys.release("order-42", "another-saga");
+        assert_eq!(keys.len(), 1);
+        keys.release("order-42", &saga_id);
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_covers_the_start_time() {
+        let now = request("checkout", "r-1");
+        let later = SagaRequest {
+            start_at: Some(chrono::Utc::now() + chrono::Duration::minutes(30)),
+            ..request("checkout", "r-1")
+        };
+        assert_eq!(fingerprint(&now), fingerprint(&request("checkout", "r-2")));
+        assert_ne!(fingerprint(&now), fingerprint(&later));
+    }
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("order-42").is_ok());
+        assert!(check_key(" ").is_err());
+        assert!(check_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_BYTES + 1)).is_err());
+    }
+}
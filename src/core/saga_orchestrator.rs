@@ -3,13 +3,38 @@
 //! This module provides a saga orchestrator that manages distributed transactions
 //! using the saga pattern, including compensation logic for rollback scenarios.
 
+use crate::core::cache_manager::{CacheManager, DeleteCacheRequest};
+use crate::core::lock_manager::{
+    ExtendLockRequest, LockManager, LockRequest, LockState, ReleaseLockRequest,
+};
+use crate::core::saga_dead_letter::DeadLetterQueue;
+use crate::core::saga_executors::{
+    ManualStepExecutor, SagaStepExecutor, SagaStepHandler, StepExecutors, StepOutcome,
+    HTTP_EXECUTOR, MANUAL_EXECUTOR,
+};
+use crate::core::saga_http::HttpStepClient;
+use crate::core::saga_idempotency::{IdempotencyKeys, Reservation};
+use crate::core::saga_plan::{self, SagaPlan, SagaValidationError};
+use crate::core::saga_results::{StepResult, StepResultLimits};
+use crate::core::saga_template::{self, TemplateRef};
+use crate::core::service_discovery::ServiceDiscovery;
+use crate::core::task_tracker::TaskTracker;
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
 use crate::storage::postgres::PostgresManager;
 use crate::{Result, SyrosError};
 use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use futures::FutureExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::ops::Range;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, Notify, RwLock};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 /// Represents a single step in a saga transaction.
@@ -27,6 +52,52 @@ pub struct SagaStep {
     pub timeout: Duration,
     /// Retry policy for this step
     pub retry_policy: Option<RetryPolicy>,
+    /// Body sent to the service; string values may hold `{{...}}`
+    /// references (see [`saga_template`](crate::core::saga_template))
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+    /// Locks the step acquires, released when the step is compensated
+    #[serde(default)]
+    pub acquired_locks: Vec<StepLock>,
+    /// Cache keys the step writes, deleted when the step is compensated
+    #[serde(default)]
+    pub cache_keys: Vec<String>,
+    /// Steps listed one after another with the same group run concurrently;
+    /// see [`step_stages`]
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Name of the executor running the step; the default executor, else
+    /// `http`, when unset (see [`saga_executors`])
+    #[serde(default)]
+    pub executor: Option<String>,
+}
+
+/// Splits `steps` into the stages they execute in, in order.
+///
+/// Consecutive steps of the same group form one stage whose steps run
+/// concurrently; every other step is a stage of its own. A stage starts once
+/// all steps of the previous one completed.
+pub fn step_stages(steps: &[SagaStep]) -> Vec<Range<usize>> {
+    let mut stages: Vec<Range<usize>> = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        match stages.last_mut() {
+            Some(stage) if step.group.is_some() && steps[stage.start].group == step.group => {
+                stage.end = index + 1;
+            }
+            _ => stages.push(index..index + 1),
+        }
+    }
+    stages
+}
+
+/// A lock held by a saga step, declared so compensation can release it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepLock {
+    /// Lock key
+    pub key: String,
+    /// Owner the lock is acquired with; defaults to the saga ID
+    #[serde(default)]
+    pub owner: Option<String>,
 }
 
 /// Retry policy configuration for saga steps.
@@ -40,6 +111,30 @@ pub struct RetryPolicy {
     pub initial_delay: Duration,
 }
 
+impl RetryPolicy {
+    /// Most retries a policy may allow.
+    pub const MAX_RETRIES: u32 = 100;
+    /// Longest initial delay a policy may ask for.
+    pub const MAX_INITIAL_DELAY: Duration = Duration::from_secs(60 * 60);
+
+    /// Attempts made in total: the first one and the retries.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_retries.saturating_add(1)
+    }
+
+    /// Delay before retrying after the `attempt`-th failed attempt, saturating
+    /// at [`Duration::MAX`].
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match self.backoff_strategy {
+            BackoffStrategy::Fixed => self.initial_delay,
+            BackoffStrategy::Linear => self.initial_delay.saturating_mul(attempt),
+            BackoffStrategy::Exponential => self
+                .initial_delay
+                .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))),
+        }
+    }
+}
+
 /// Backoff strategies for retry policies.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BackoffStrategy {
@@ -51,13 +146,34 @@ pub enum BackoffStrategy {
     Fixed,
 }
 
+impl std::str::FromStr for BackoffStrategy {
+    type Err = SyrosError;
+
+    /// Parses `"linear"`, `"exponential"` or `"fixed"`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "linear" => Ok(BackoffStrategy::Linear),
+            "exponential" => Ok(BackoffStrategy::Exponential),
+            "fixed" => Ok(BackoffStrategy::Fixed),
+            other => Err(SyrosError::SagaError(format!(
+                "Unknown backoff strategy {:?}; expected linear, exponential or fixed",
+                other
+            ))),
+        }
+    }
+}
+
 /// Status of a saga transaction.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SagaStatus {
+    /// Saga waits for its `start_at` time before it becomes pending
+    Scheduled,
     /// Saga is pending execution
     Pending,
     /// Saga is currently running
     Running,
+    /// An operator paused the saga; it waits before its next step until resumed
+    Paused,
     /// Saga completed successfully
     Completed,
     /// Saga failed and needs compensation
@@ -66,19 +182,62 @@ pub enum SagaStatus {
     Compensating,
     /// Saga compensation completed
     Compensated,
+    /// A compensation exhausted its retries; the saga was escalated
+    CompensationFailed,
+    /// The saga was cancelled on request and its started steps compensated
+    Cancelled,
 }
 
 use std::fmt;
 
+impl SagaStatus {
+    /// Every status, in lifecycle order.
+    pub const ALL: [SagaStatus; 10] = [
+        SagaStatus::Scheduled,
+        SagaStatus::Pending,
+        SagaStatus::Running,
+        SagaStatus::Paused,
+        SagaStatus::Completed,
+        SagaStatus::Failed,
+        SagaStatus::Compensating,
+        SagaStatus::Compensated,
+        SagaStatus::CompensationFailed,
+        SagaStatus::Cancelled,
+    ];
+
+    /// The status named `name`, ignoring case, e.g. `running`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the saga can no longer change state.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            SagaStatus::Completed
+                | SagaStatus::Failed
+                | SagaStatus::Compensated
+                | SagaStatus::CompensationFailed
+                | SagaStatus::Cancelled
+        )
+    }
+}
+
 impl fmt::Display for SagaStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
+            SagaStatus::Scheduled => "Scheduled",
             SagaStatus::Pending => "Pending",
             SagaStatus::Running => "Running",
+            SagaStatus::Paused => "Paused",
             SagaStatus::Completed => "Completed",
             SagaStatus::Failed => "Failed",
             SagaStatus::Compensating => "Compensating",
             SagaStatus::Compensated => "Compensated",
+            SagaStatus::CompensationFailed => "CompensationFailed",
+            SagaStatus::Cancelled => "Cancelled",
         };
         write!(f, "{}", s)
     }
@@ -89,17 +248,160 @@ impl std::str::FromStr for SagaStatus {
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s {
+            "Scheduled" => Ok(SagaStatus::Scheduled),
             "Pending" => Ok(SagaStatus::Pending),
             "Running" => Ok(SagaStatus::Running),
+            "Paused" => Ok(SagaStatus::Paused),
             "Completed" => Ok(SagaStatus::Completed),
             "Failed" => Ok(SagaStatus::Failed),
             "Compensating" => Ok(SagaStatus::Compensating),
             "Compensated" => Ok(SagaStatus::Compensated),
+            "CompensationFailed" => Ok(SagaStatus::CompensationFailed),
+            "Cancelled" => Ok(SagaStatus::Cancelled),
             _ => Err(()),
         }
     }
 }
 
+/// Retries of a compensation whose step has no retry policy.
+pub const DEFAULT_COMPENSATION_RETRIES: u32 = 3;
+/// Delay between compensation retries when the step has no retry policy.
+pub const DEFAULT_COMPENSATION_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// States in which a saga can still be cancelled for exceeding its budget.
+const ACTIVE_STATUSES: [SagaStatus; 4] = [
+    SagaStatus::Scheduled,
+    SagaStatus::Pending,
+    SagaStatus::Running,
+    SagaStatus::Paused,
+];
+
+/// States from which a saga can be retried, see
+/// [`SagaOrchestrator::retry_saga`].
+const RETRYABLE_STATUSES: [SagaStatus; 2] = [SagaStatus::Failed, SagaStatus::CompensationFailed];
+
+/// How often a paused saga checks whether it was resumed, to notice resumes
+/// made through another instance.
+const PAUSE_RECHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Failure reason recorded on sagas cancelled for exceeding their budget.
+pub const SAGA_TIMEOUT_REASON: &str = "saga timeout";
+
+/// Header carrying the saga ID on every step call.
+pub const SAGA_ID_HEADER: &str = "X-Syros-Saga-Id";
+/// Header carrying the step name on every step call.
+pub const STEP_HEADER: &str = "X-Syros-Step";
+/// Header carrying the 1-based attempt number of the call.
+pub const ATTEMPT_HEADER: &str = "X-Syros-Attempt";
+/// Header carrying the ID of the request that started the saga.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// Saga metadata key under which the originating request ID is stored.
+pub const REQUEST_ID_METADATA_KEY: &str = "request_id";
+/// Saga metadata key holding the principal that started the saga.
+pub const OWNER_METADATA_KEY: &str = "owner";
+/// Saga metadata key holding the client ID the saga was started for.
+pub const CLIENT_ID_METADATA_KEY: &str = "client_id";
+/// Saga metadata key holding the key of the lock held while the saga runs.
+pub const LOCK_KEY_METADATA_KEY: &str = "lock_key";
+/// Saga metadata key holding the ID of the lock held while the saga runs.
+pub const LOCK_ID_METADATA_KEY: &str = "lock_id";
+/// Saga metadata key holding the owner of the lock held while the saga runs.
+pub const LOCK_OWNER_METADATA_KEY: &str = "lock_owner";
+/// Saga metadata key holding why the saga was cancelled.
+pub const CANCEL_REASON_METADATA_KEY: &str = "cancel_reason";
+/// Saga metadata key holding the definition the saga was started from, e.g.
+/// `checkout@2`.
+pub const DEFINITION_METADATA_KEY: &str = "definition";
+/// Saga metadata key counting the manual retries of the saga, see
+/// [`SagaOrchestrator::retry_saga`].
+pub const RETRY_COUNT_METADATA_KEY: &str = "retry_count";
+
+/// Error recorded on a step attempt that exceeded the step's timeout.
+pub const STEP_TIMEOUT_ERROR: &str = "timeout";
+
+/// Called once with the final state of a saga, see
+/// [`SagaOrchestrator::start_saga_with_completion_hook`].
+pub type SagaCompletionHook = Box<dyn FnOnce(&Saga) + Send>;
+
+/// Failure reason prefix of sagas whose execution panicked.
+pub const SAGA_PANIC_REASON: &str = "panicked";
+
+/// Lock a saga started with [`SagaOrchestrator::start_saga_with_lock`] holds
+/// until it ends.
+#[derive(Debug, Clone)]
+pub struct SagaLock {
+    pub key: String,
+    pub ttl: Duration,
+    pub owner: String,
+    /// How long to wait for a held lock before giving up
+    pub wait_timeout: Option<Duration>,
+    /// Authenticated principal acquiring the lock
+    pub created_by: Option<String>,
+}
+
+/// Outcome of [`SagaOrchestrator::start_saga_with_lock`].
+#[derive(Debug)]
+pub enum LockedSagaStart {
+    /// The lock was acquired and the saga started
+    Started { saga: SagaResponse, lock_id: String },
+    /// The lock could not be acquired, so no saga was created
+    Conflict {
+        message: String,
+        /// Current holder of the lock, if it is still held
+        holder: Option<LockState>,
+    },
+}
+
+/// Tracing identifiers attached to a single action or compensation call.
+///
+/// Step executors send these as HTTP headers so downstream services can
+/// correlate their logs with the saga; the context of the call whose answer
+/// became a step's result is recorded with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepCallContext {
+    /// Saga the call belongs to
+    pub saga_id: String,
+    /// Name of the step being executed or compensated
+    pub step: String,
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// ID of the request that started the saga, if known
+    pub request_id: Option<String>,
+    /// Whether this is a compensation call
+    pub compensation: bool,
+}
+
+impl StepCallContext {
+    pub fn new(saga_id: &str, step: &str, attempt: u32, request_id: Option<String>) -> Self {
+        Self {
+            saga_id: saga_id.to_string(),
+            step: step.to_string(),
+            attempt,
+            request_id,
+            compensation: false,
+        }
+    }
+
+    /// Returns the same context for a compensation call.
+    pub fn for_compensation(mut self) -> Self {
+        self.compensation = true;
+        self
+    }
+
+    /// HTTP headers identifying this call.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            (SAGA_ID_HEADER, self.saga_id.clone()),
+            (STEP_HEADER, self.step.clone()),
+            (ATTEMPT_HEADER, self.attempt.to_string()),
+        ];
+        if let Some(request_id) = &self.request_id {
+            headers.push((REQUEST_ID_HEADER, request_id.clone()));
+        }
+        headers
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Saga {
     pub id: String,
@@ -112,6 +414,217 @@ pub struct Saga {
     pub updated_at: DateTime<Utc>,
     #[sqlx(json)]
     pub metadata: serde_json::Value,
+    /// Time by which the saga must finish, if it has a global budget
+    pub deadline_at: Option<DateTime<Utc>>,
+    /// Why the saga stopped before completing, e.g. [`SAGA_TIMEOUT_REASON`]
+    pub failure_reason: Option<String>,
+    /// Execution state of every step, in step order
+    #[sqlx(json)]
+    #[serde(default)]
+    pub step_results: Vec<StepExecution>,
+    /// Time a saga started as `Scheduled` begins executing
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+}
+
+impl Saga {
+    /// Budget left before the saga is cancelled; `None` if it has no deadline.
+    pub fn remaining_budget(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.deadline_at
+            .map(|deadline| (deadline - now).to_std().unwrap_or(Duration::ZERO))
+    }
+
+    /// Whether the saga can no longer change state.
+    pub fn is_terminal(&self) -> bool {
+        self.status
+            .parse::<SagaStatus>()
+            .is_ok_and(|status| status.is_terminal())
+    }
+
+    /// Whether any step of the saga calls `service`.
+    pub fn calls_service(&self, service: &str) -> bool {
+        self.steps.as_array().is_some_and(|steps| {
+            steps
+                .iter()
+                .any(|step| step.get("service").and_then(|s| s.as_str()) == Some(service))
+        })
+    }
+
+    /// Name of the step the saga executes or last executed.
+    pub fn current_step_name(&self) -> Option<&str> {
+        let current = self.current_step? as usize;
+        self.steps.get(current)?.get("name")?.as_str()
+    }
+
+    /// Service called by the step the saga starts after the current one.
+    pub fn next_step_service(&self) -> Option<&str> {
+        let next = self.current_step.map_or(0, |step| step as usize + 1);
+        self.steps.get(next)?.get("service")?.as_str()
+    }
+
+    /// Stored result of the step named `step`, once its service answered.
+    pub fn step_result(&self, step: &str) -> Option<StepResult> {
+        let step = self
+            .steps
+            .as_array()?
+            .iter()
+            .find(|s| s.get("name").and_then(|n| n.as_str()) == Some(step))?;
+        serde_json::from_value(step.get("result")?.clone()).ok()
+    }
+
+    /// Attempts made at the action of the step named `step`, oldest first.
+    pub fn step_attempts(&self, step: &str) -> Vec<StepAttempt> {
+        self.steps
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|s| s.get("name").and_then(|n| n.as_str()) == Some(step))
+            .and_then(|step| step.get("attempts"))
+            .and_then(|attempts| serde_json::from_value(attempts.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// The string values of the saga metadata, by key.
+    pub fn metadata_map(&self) -> HashMap<String, String> {
+        self.metadata
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+            .collect()
+    }
+
+    /// Principal that started the saga, from [`OWNER_METADATA_KEY`].
+    pub fn created_by(&self) -> Option<String> {
+        self.metadata
+            .get(OWNER_METADATA_KEY)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// Manual retries of the saga so far, from [`RETRY_COUNT_METADATA_KEY`].
+    pub fn retry_count(&self) -> u32 {
+        self.metadata
+            .get(RETRY_COUNT_METADATA_KEY)
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+/// Criteria for listing sagas.
+#[derive(Debug, Clone, Default)]
+pub struct SagaFilter {
+    /// Only sagas in this status
+    pub status: Option<SagaStatus>,
+    /// Only sagas that have not finished
+    pub active_only: bool,
+    /// Only sagas whose name starts with this prefix
+    pub name_prefix: Option<String>,
+    /// Only sagas created after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only sagas with a step calling this service
+    pub service: Option<String>,
+    /// Only sagas started by this principal, from [`OWNER_METADATA_KEY`]
+    pub owner: Option<String>,
+    /// Most sagas to return; all if `None`
+    pub limit: Option<usize>,
+    /// Matching sagas to skip, oldest first
+    pub offset: usize,
+}
+
+impl SagaFilter {
+    /// Checks whether a saga satisfies the filter, ignoring the page.
+    pub fn matches(&self, saga: &Saga) -> bool {
+        if let Some(status) = &self.status {
+            if saga.status != status.to_string() {
+                return false;
+            }
+        }
+        if self.active_only && saga.is_terminal() {
+            return false;
+        }
+        if let Some(prefix) = &self.name_prefix {
+            if !saga.name.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(created_after) = self.created_after {
+            if saga.created_at <= created_after {
+                return false;
+            }
+        }
+        if let Some(service) = &self.service {
+            if !saga.calls_service(service) {
+                return false;
+            }
+        }
+        if let Some(owner) = &self.owner {
+            if saga.created_by().as_ref() != Some(owner) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Summary of a saga, as listed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaSummary {
+    pub saga_id: String,
+    pub name: String,
+    pub status: String,
+    /// Index of the step being executed or last executed
+    pub current_step: Option<usize>,
+    pub created_at: DateTime<Utc>,
+    /// When the saga finished; `None` while it is active
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl SagaSummary {
+    pub fn from_saga(saga: &Saga) -> Self {
+        Self {
+            saga_id: saga.id.clone(),
+            name: saga.name.clone(),
+            status: saga.status.clone(),
+            current_step: saga.current_step.map(|step| step.max(0) as usize),
+            created_at: saga.created_at,
+            completed_at: saga.is_terminal().then_some(saga.updated_at),
+        }
+    }
+}
+
+/// A saga status transition, published to status subscribers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaStatusUpdate {
+    pub saga_id: String,
+    pub name: String,
+    pub status: String,
+    /// Principal that started the saga, from [`OWNER_METADATA_KEY`]
+    pub owner: Option<String>,
+    /// Client the saga was started for, from [`CLIENT_ID_METADATA_KEY`]
+    pub client_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl SagaStatusUpdate {
+    /// Describes the current state of `saga`.
+    pub fn from_saga(saga: &Saga) -> Self {
+        let metadata = |key: &str| {
+            saga.metadata
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        };
+        Self {
+            saga_id: saga.id.clone(),
+            name: saga.name.clone(),
+            status: saga.status.clone(),
+            owner: metadata(OWNER_METADATA_KEY),
+            client_id: metadata(CLIENT_ID_METADATA_KEY),
+            timestamp: saga.updated_at,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,6 +632,12 @@ pub struct SagaRequest {
     pub name: String,
     pub steps: Vec<SagaStep>,
     pub metadata: Option<HashMap<String, String>>,
+    /// Global budget for the whole saga; exceeding it cancels and compensates
+    #[serde(default)]
+    pub max_duration: Option<Duration>,
+    /// Time to begin executing the saga at; until then it is `Scheduled`
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,158 +647,3524 @@ pub struct SagaResponse {
     pub message: String,
 }
 
+/// Outcome of [`SagaOrchestrator::start_saga_idempotent`].
+#[derive(Debug, Clone)]
+pub enum IdempotentStart {
+    /// A new saga was started
+    Started(SagaResponse),
+    /// The key was replayed; the saga it started earlier is returned
+    Replayed(SagaResponse),
+}
+
+impl IdempotentStart {
+    /// The started or replayed saga.
+    pub fn into_saga_response(self) -> SagaResponse {
+        match self {
+            IdempotentStart::Started(response) | IdempotentStart::Replayed(response) => response,
+        }
+    }
+}
+
+/// One attempt at a step's action, recorded on the saga.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepAttempt {
+    /// 1-based attempt number
+    pub attempt: u32,
+    /// Why the attempt failed; `None` if it succeeded
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Execution status of a saga step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    /// The step has not started
+    Pending,
+    /// The step's action is being attempted
+    Running,
+    /// The step's action succeeded
+    Completed,
+    /// The step's action failed once its retries were exhausted
+    Failed,
+    /// The step's compensation succeeded
+    Compensated,
+    /// The step's compensation failed once its retries were exhausted
+    CompensationFailed,
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            StepStatus::Pending => "Pending",
+            StepStatus::Running => "Running",
+            StepStatus::Completed => "Completed",
+            StepStatus::Failed => "Failed",
+            StepStatus::Compensated => "Compensated",
+            StepStatus::CompensationFailed => "CompensationFailed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Execution state of a saga step, updated as the saga runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepExecution {
+    /// Name of the step
+    pub step: String,
+    pub status: StepStatus,
+    /// Error of the last failed attempt, or of the failed compensation
+    pub error: Option<String>,
+    /// When the first attempt at the step's action started
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the step's action succeeded or finally failed
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Attempts made at the step's action
+    pub attempts: u32,
+    /// Attempts made at the step's compensation
+    #[serde(default)]
+    pub compensation_attempts: u32,
+    /// When the step's compensation succeeded or finally failed
+    #[serde(default)]
+    pub compensated_at: Option<DateTime<Utc>>,
+}
+
+impl StepExecution {
+    /// State of `step` before it starts.
+    pub fn pending(step: &str) -> Self {
+        Self {
+            step: step.to_string(),
+            status: StepStatus::Pending,
+            error: None,
+            started_at: None,
+            completed_at: None,
+            attempts: 0,
+            compensation_attempts: 0,
+            compensated_at: None,
+        }
+    }
+}
+
+/// A compensation that still failed after exhausting its retries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompensationFailure {
+    pub step: String,
+    pub attempts: u32,
+    pub error: String,
+}
+
+/// Storage behind a [`SagaOrchestrator`].
+#[derive(Clone)]
+enum SagaBackend {
+    Postgres(PostgresManager),
+    Memory(Arc<RwLock<HashMap<String, Saga>>>),
+}
+
 #[derive(Clone)]
 pub struct SagaOrchestrator {
-    pg: PostgresManager,
+    backend: SagaBackend,
+    dead_letters: Option<DeadLetterQueue>,
+    step_results: StepResultLimits,
+    service_discovery: Option<Arc<ServiceDiscovery>>,
+    /// Managers that compensation releases declared step resources through
+    lock_manager: Option<LockManager>,
+    cache_manager: Option<CacheManager>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
+    executors: StepExecutors,
+    /// Steps parked by the `manual` executor
+    manual_steps: ManualStepExecutor,
+    /// Idempotency keys of recent starts, by key
+    idempotency_keys: IdempotencyKeys,
+    /// Spawns the saga execution and lock holder tasks
+    tasks: TaskTracker,
+    /// Execution tasks of sagas started by this instance, by saga ID
+    running: Arc<std::sync::Mutex<HashMap<String, JoinHandle<()>>>>,
+    status_updates: broadcast::Sender<SagaStatusUpdate>,
+    /// Hooks to run once a saga started by this instance ends, by saga ID
+    completion_hooks: Arc<std::sync::Mutex<HashMap<String, Vec<SagaCompletionHook>>>>,
+    /// Woken when sagas are resumed through this instance
+    resumed: Arc<Notify>,
 }
 
 impl SagaOrchestrator {
     pub fn new(pg: PostgresManager) -> Self {
-        Self { pg }
+        Self::with_backend(SagaBackend::Postgres(pg))
     }
 
-    pub async fn start_saga(&self, request: SagaRequest) -> Result<SagaResponse> {
-        let saga_id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-        let pool = self.pg.get_pool();
+    /// Creates an orchestrator that keeps its sagas in memory.
+    pub fn in_memory() -> Self {
+        Self::with_backend(SagaBackend::Memory(Arc::default()))
+    }
 
-        let metadata = request.metadata.unwrap_or_default();
+    /// Name of the storage the sagas are kept in.
+    pub fn backend_name(&self) -> &'static str {
+        match &self.backend {
+            SagaBackend::Postgres(_) => "postgres",
+            SagaBackend::Memory(_) => "memory",
+        }
+    }
 
-        sqlx::query(
-            "INSERT INTO sagas (id, name, status, steps, created_at, updated_at, metadata) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7)",
-        )
-        .bind(Uuid::parse_str(&saga_id).unwrap_or_default())
-        .bind(&request.name)
-        .bind("Pending")
-        .bind(sqlx::types::Json(
-            serde_json::to_value(&request.steps).unwrap_or_default(),
-        ))
-        .bind(now)
-        .bind(now)
-        .bind(sqlx::types::Json(
-            serde_json::to_value(&metadata).unwrap_or_default(),
-        ))
-        .execute(pool)
-        .await
-        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    fn with_backend(backend: SagaBackend) -> Self {
+        let (status_updates, _) = broadcast::channel(1000);
+        let manual_steps = ManualStepExecutor::new();
+        let mut executors = StepExecutors::default();
+        executors.register(MANUAL_EXECUTOR, Arc::new(manual_steps.clone()));
+        Self {
+            backend,
+            dead_letters: None,
+            step_results: StepResultLimits::default(),
+            service_discovery: None,
+            lock_manager: None,
+            cache_manager: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            executors,
+            manual_steps,
+            idempotency_keys: IdempotencyKeys::default(),
+            tasks: TaskTracker::new(),
+            running: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            status_updates,
+            completion_hooks: Arc::default(),
+            resumed: Arc::default(),
+        }
+    }
 
-        let orchestrator_clone = Arc::new(self.clone());
-        let saga_id_clone = saga_id.clone();
-        tokio::spawn(async move {
-            if let Err(e) = orchestrator_clone.execute_saga(&saga_id_clone).await {
-                eprintln!("Error executing saga {}: {}", saga_id_clone, e);
-            }
-        });
+    /// Escalates sagas whose compensation fails to the given dead-letter queue.
+    pub fn with_dead_letter_queue(mut self, dead_letters: DeadLetterQueue) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
 
-        Ok(SagaResponse {
-            saga_id,
-            success: true,
-            message: "Saga started successfully".to_string(),
-        })
+    /// Applies `limits` to the results returned by step services.
+    pub fn with_step_result_limits(mut self, limits: StepResultLimits) -> Self {
+        self.step_results = limits;
+        self
     }
 
-    pub async fn execute_saga(&self, saga_id: &str) -> Result<()> {
-        let pool = self.pg.get_pool();
+    /// Resolves step services against `discovery` when planning sagas.
+    pub fn with_service_discovery(mut self, discovery: Arc<ServiceDiscovery>) -> Self {
+        self.service_discovery = Some(discovery);
+        self
+    }
 
-        sqlx::query("UPDATE sagas SET status = 'Running', updated_at = NOW() WHERE id = $1")
-            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-            .execute(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    /// Releases the `acquired_locks` of compensated steps through `locks`.
+    pub fn with_lock_manager(mut self, locks: LockManager) -> Self {
+        self.lock_manager = Some(locks);
+        self
+    }
 
-        let steps_count = self.get_saga_steps_count(saga_id).await?;
+    /// Deletes the `cache_keys` of compensated steps from `cache`.
+    pub fn with_cache_manager(mut self, cache: CacheManager) -> Self {
+        self.cache_manager = Some(cache);
+        self
+    }
 
-        for step_index in 0..steps_count {
-            if let Err(e) = self.execute_step(saga_id, step_index).await {
-                // If it fails, start compensation
-                self.compensate_saga(saga_id).await?;
-                return Err(e);
-            }
-        }
+    /// Runs the actions and compensations of steps naming no executor
+    /// through `executor` instead of over HTTP or only simulating them.
+    pub fn with_step_executor(mut self, executor: SagaStepHandler) -> Self {
+        self.executors.set_default(Arc::new(executor));
+        self
+    }
 
-        sqlx::query("UPDATE sagas SET status = 'Completed', updated_at = NOW() WHERE id = $1")
-            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-            .execute(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    /// Runs the steps whose `executor` is `name` through `executor`,
+    /// replacing any executor registered under that name.
+    pub fn with_executor(mut self, name: &str, executor: Arc<dyn SagaStepExecutor>) -> Self {
+        self.executors.register(name, executor);
+        self
+    }
 
-        Ok(())
+    /// Calls the services of step actions and compensations over HTTP
+    /// through `client`, recording the body of each successful action as the
+    /// step's result.
+    ///
+    /// The client is registered as the `http` executor, which also runs the
+    /// steps naming no executor unless
+    /// [`with_step_executor`](Self::with_step_executor) set another.
+    pub fn with_http_steps(self, client: HttpStepClient) -> Self {
+        self.with_executor(HTTP_EXECUTOR, Arc::new(client))
     }
 
-    async fn execute_step(&self, saga_id: &str, step_index: usize) -> Result<()> {
-        let pool = self.pg.get_pool();
+    /// Remembers the idempotency keys of saga starts for `ttl`, see
+    /// [`start_saga_idempotent`](Self::start_saga_idempotent).
+    pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency_keys = IdempotencyKeys::new(ttl);
+        self
+    }
 
-        sqlx::query("UPDATE sagas SET current_step = $1, updated_at = NOW() WHERE id = $2")
-            .bind(step_index as i32)
-            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-            .execute(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    /// Spawns the tasks executing sagas through `tasks`.
+    pub fn with_task_tracker(mut self, tasks: TaskTracker) -> Self {
+        self.tasks = tasks;
+        self
+    }
 
-        tokio::time::sleep(Duration::from_millis(100)).await;
+    /// Counts failed sagas, step compensations and step resources that
+    /// compensation failed to release.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
 
-        // Artificial failure probability
-        if fastrand::f32() < 0.1 {
-            // We could log failure here
-            // For improvement, let's remove it or make it very rare to keep system stable
-            // return Err(crate::SyrosError::SagaError("Step execution failed (simulated)".to_string()));
+    /// Validates `request` and returns its execution plan without persisting
+    /// or running anything.
+    ///
+    /// Steps naming an executor this orchestrator does not have are errors.
+    pub async fn plan_saga(&self, request: &SagaRequest) -> SagaPlan {
+        // Discovery is shared without an outer lock, so no guard is held
+        // while its backend is queried.
+        let mut plan = saga_plan::plan(request, self.service_discovery.as_deref()).await;
+        for step in &request.steps {
+            let Some(executor) = &step.executor else {
+                continue;
+            };
+            if !executor.trim().is_empty() && !self.executors.contains(executor) {
+                plan.errors.push(SagaValidationError::new(
+                    Some(&step.name),
+                    "executor",
+                    format!(
+                        "unknown executor {}, expected one of: {}",
+                        executor,
+                        self.executors.names().join(", ")
+                    ),
+                ));
+            }
         }
+        plan
+    }
 
-        Ok(())
+    /// Builds the stored result of a step from the body its service
+    /// returned, truncating or offloading it if it exceeds the size limit.
+    pub async fn capture_step_result(
+        &self,
+        saga_id: &str,
+        step: &str,
+        body: &[u8],
+    ) -> Result<StepResult> {
+        self.step_results.capture(saga_id, step, body).await
     }
 
-    async fn compensate_saga(&self, saga_id: &str) -> Result<()> {
-        let pool = self.pg.get_pool();
+    /// Subscribes to the status transitions of sagas run by this instance.
+    pub fn subscribe_status_updates(&self) -> broadcast::Receiver<SagaStatusUpdate> {
+        self.status_updates.subscribe()
+    }
 
-        sqlx::query("UPDATE sagas SET status = 'Compensating', updated_at = NOW() WHERE id = $1")
-            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-            .execute(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    /// Publishes the current status of a saga to status subscribers and, once
+    /// it is terminal, runs its completion hooks.
+    async fn publish_status(&self, saga_id: &str) {
+        let has_hooks = self.completion_hooks.lock().unwrap().contains_key(saga_id);
+        if self.status_updates.receiver_count() == 0 && !has_hooks {
+            return;
+        }
+        match self.get_saga_status(saga_id).await {
+            Ok(Some(saga)) => {
+                let _ = self.status_updates.send(SagaStatusUpdate::from_saga(&saga));
+                if saga.is_terminal() {
+                    self.run_completion_hooks(&saga);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(saga_id = %saga_id, "Failed to publish saga status: {}", e),
+        }
+    }
 
-        let steps_count = self.get_saga_steps_count(saga_id).await?;
-        for step_index in (0..steps_count).rev() {
-            self.compensate_step(saga_id, step_index).await?;
+    fn run_completion_hooks(&self, saga: &Saga) {
+        let hooks = self.completion_hooks.lock().unwrap().remove(&saga.id);
+        for hook in hooks.into_iter().flatten() {
+            hook(saga);
         }
+    }
 
-        sqlx::query("UPDATE sagas SET status = 'Compensated', updated_at = NOW() WHERE id = $1")
-            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-            .execute(pool)
+    pub async fn start_saga(&self, request: SagaRequest) -> Result<SagaResponse> {
+        self.start(Uuid::new_v4().to_string(), request, None).await
+    }
+
+    /// Starts a saga unless one was already started under `key` within the
+    /// idempotency TTL, in which case that saga is returned as a replay.
+    ///
+    /// Concurrent starts under the same key create a single saga. Fails with
+    /// `Conflict` if the key was used for a different request, the request
+    /// ID aside.
+    pub async fn start_saga_idempotent(
+        &self,
+        key: &str,
+        request: SagaRequest,
+    ) -> Result<IdempotentStart> {
+        match self.idempotency_keys.reserve(key, &request)? {
+            Reservation::Existing(saga_id) => Ok(IdempotentStart::Replayed(SagaResponse {
+                saga_id,
+                success: true,
+                message: "Saga already started with this idempotency key".to_string(),
+            })),
+            Reservation::New(saga_id) => match self.start(saga_id.clone(), request, None).await {
+                Ok(response) => Ok(IdempotentStart::Started(response)),
+                Err(e) => {
+                    self.idempotency_keys.release(key, &saga_id);
+                    Err(e)
+                }
+            },
+        }
+    }
+
+    /// Starts a saga and calls `hook` once it reaches a terminal state.
+    ///
+    /// The hook runs on the instance that started the saga, when it sees the
+    /// saga end; it does not run if the process stops first.
+    pub async fn start_saga_with_completion_hook(
+        &self,
+        request: SagaRequest,
+        hook: SagaCompletionHook,
+    ) -> Result<SagaResponse> {
+        self.start(Uuid::new_v4().to_string(), request, Some(hook))
             .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    }
 
-        Ok(())
+    async fn start(
+        &self,
+        saga_id: String,
+        request: SagaRequest,
+        hook: Option<SagaCompletionHook>,
+    ) -> Result<SagaResponse> {
+        let now = Utc::now();
+
+        let metadata = request.metadata.unwrap_or_default();
+        // The budget of a scheduled saga runs from the time it starts.
+        let start_at = request.start_at.filter(|start_at| *start_at > now);
+        let deadline_at = request
+            .max_duration
+            .and_then(|budget| chrono::Duration::from_std(budget).ok())
+            .and_then(|budget| start_at.unwrap_or(now).checked_add_signed(budget));
+        let status = match start_at {
+            Some(_) => SagaStatus::Scheduled,
+            None => SagaStatus::Pending,
+        };
+
+        self.insert_saga(Saga {
+            id: saga_id.clone(),
+            name: request.name,
+            status: status.to_string(),
+            steps: serde_json::to_value(&request.steps).unwrap_or_default(),
+            current_step: None,
+            created_at: now,
+            updated_at: now,
+            metadata: serde_json::to_value(&metadata).unwrap_or_default(),
+            deadline_at,
+            failure_reason: None,
+            step_results: request
+                .steps
+                .iter()
+                .map(|step| StepExecution::pending(&step.name))
+                .collect(),
+            start_at,
+        })
+        .await?;
+        self.count_saga_started();
+        if let Some(hook) = hook {
+            self.completion_hooks
+                .lock()
+                .unwrap()
+                .entry(saga_id.clone())
+                .or_default()
+                .push(hook);
+        }
+        self.publish_status(&saga_id).await;
+        if let Some(start_at) = start_at {
+            return Ok(SagaResponse {
+                saga_id,
+                success: true,
+                message: format!("Saga scheduled to start at {}", start_at.to_rfc3339()),
+            });
+        }
+        self.spawn_saga(&saga_id, false);
+
+        Ok(SagaResponse {
+            saga_id,
+            success: true,
+            message: "Saga started successfully".to_string(),
+        })
     }
 
-    async fn compensate_step(&self, _saga_id: &str, _step_index: usize) -> Result<()> {
-        tokio::time::sleep(Duration::from_millis(50)).await;
-        Ok(())
+    /// Starts the scheduled sagas whose `start_at` time has come and returns
+    /// their IDs.
+    ///
+    /// Each saga moves from `Scheduled` to `Pending` and executes on the
+    /// instance that moved it, so instances sharing the storage start every
+    /// saga once.
+    pub async fn start_due_sagas(&self) -> Result<Vec<String>> {
+        let mut started = Vec::new();
+        for saga_id in self.due_saga_ids(Utc::now()).await? {
+            if !self
+                .set_status(
+                    &saga_id,
+                    SagaStatus::Pending,
+                    &[SagaStatus::Scheduled],
+                    None,
+                )
+                .await?
+            {
+                continue;
+            }
+            tracing::info!(saga_id = %saga_id, "Starting scheduled saga");
+            self.publish_status(&saga_id).await;
+            self.spawn_saga(&saga_id, false);
+            started.push(saga_id);
+        }
+        Ok(started)
     }
 
-    async fn get_saga_steps_count(&self, saga_id: &str) -> Result<usize> {
-        let pool = self.pg.get_pool();
+    /// Runs the saga in a task of its own, tracked as running on this
+    /// instance until it stops; `recovered` sagas pick up where they were
+    /// left, see [`recover_pending_sagas`](Self::recover_pending_sagas).
+    fn spawn_saga(&self, saga_id: &str, recovered: bool) {
+        let orchestrator = self.clone();
+        let id = saga_id.to_string();
+        // Hold the registry while spawning so the task cannot deregister first.
+        let mut running = self.running.lock().unwrap();
+        let task = self.tasks.spawn("saga", async move {
+            let execution = AssertUnwindSafe(async {
+                if recovered {
+                    orchestrator.resume_saga(&id).await
+                } else {
+                    orchestrator.execute_saga(&id).await
+                }
+            });
+            match execution.catch_unwind().await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => eprintln!("Error executing saga {}: {}", id, e),
+                Err(panic) => {
+                    orchestrator
+                        .fail_panicked_saga(&id, panic_message(&*panic))
+                        .await
+                }
+            }
+            orchestrator.running.lock().unwrap().remove(&id);
+        });
+        running.insert(saga_id.to_string(), task);
+    }
 
-        // Retrieve steps JSONB to count it
-        let steps: sqlx::types::Json<serde_json::Value> =
-            sqlx::query_scalar("SELECT steps FROM sagas WHERE id = $1")
-                .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-                .fetch_one(pool)
-                .await
-                .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+    /// Resumes the active sagas that no instance is executing after a
+    /// restart, and returns their IDs, oldest first.
+    ///
+    /// Running, pending and paused sagas continue with their first step that
+    /// has not completed; a step interrupted while running is executed
+    /// again. Compensating sagas compensate the started steps not yet
+    /// compensated. Scheduled sagas are left to
+    /// [`start_due_sagas`](Self::start_due_sagas). Sagas executing on this
+    /// instance are left alone, but those of other instances sharing the
+    /// storage are not told apart, so only one instance should recover at a
+    /// time.
+    pub async fn recover_pending_sagas(&self) -> Result<Vec<String>> {
+        let mut recovered = Vec::new();
+        for saga in self.list_active_sagas(None).await? {
+            if saga.status == SagaStatus::Scheduled.to_string()
+                || self.running.lock().unwrap().contains_key(&saga.id)
+            {
+                continue;
+            }
+            tracing::info!(saga_id = %saga.id, status = %saga.status, "Recovering saga");
+            self.count_saga_resumed();
+            self.spawn_saga(&saga.id, true);
+            recovered.push(saga.id);
+        }
+        Ok(recovered)
+    }
 
-        Ok(steps.0.as_array().map(|a| a.len()).unwrap_or(0))
+    /// Continues a saga recovered by [`recover_pending_sagas`](Self::recover_pending_sagas)
+    /// from its recorded state.
+    async fn resume_saga(&self, saga_id: &str) -> Result<()> {
+        let Some(saga) = self.get_saga_status(saga_id).await? else {
+            return Ok(());
+        };
+        match saga.status.parse::<SagaStatus>() {
+            Ok(SagaStatus::Compensating) => {
+                // Cancellations end as cancelled, timeouts and failures as
+                // compensated.
+                let cancelled = saga
+                    .metadata_map()
+                    .get(CANCEL_REASON_METADATA_KEY)
+                    .is_some_and(|reason| reason != SAGA_TIMEOUT_REASON);
+                let compensated = if cancelled {
+                    SagaStatus::Cancelled
+                } else {
+                    SagaStatus::Compensated
+                };
+                self.compensate_saga(saga_id, compensated).await
+            }
+            Ok(status) if status.is_terminal() => Ok(()),
+            // Started by `start_due_sagas` once its time comes.
+            Ok(SagaStatus::Scheduled) => Ok(()),
+            _ => self.execute_saga(saga_id).await,
+        }
     }
 
-    pub async fn get_saga_status(&self, saga_id: &str) -> Result<Option<Saga>> {
-        let pool = self.pg.get_pool();
+    /// Acquires `lock`, then starts `request` holding it until the saga ends.
+    ///
+    /// The lock is recorded in the saga metadata, extended while the saga
+    /// runs and released once the saga reaches a terminal state. If the lock
+    /// cannot be acquired, no saga is created.
+    pub async fn start_saga_with_lock(
+        &self,
+        lock: SagaLock,
+        mut request: SagaRequest,
+    ) -> Result<LockedSagaStart> {
+        let locks = self.lock_manager.clone().ok_or_else(|| {
+            SyrosError::SagaError("No lock manager to acquire saga locks through".to_string())
+        })?;
+        let acquired = locks
+            .acquire_lock(LockRequest {
+                key: lock.key.clone(),
+                ttl: lock.ttl,
+                metadata: None,
+                owner: lock.owner.clone(),
+                wait_timeout: lock.wait_timeout,
+                priority: Default::default(),
+                created_by: lock.created_by.clone(),
+                reentrant: false,
+                session_id: None,
+            })
+            .await?;
+        if !acquired.success {
+            let holder = locks.get_lock_status(&lock.key).await?;
+            return Ok(LockedSagaStart::Conflict {
+                message: acquired.message,
+                holder,
+            });
+        }
 
-        let saga: Option<Saga> = sqlx::query_as("SELECT id, name, status, steps, current_step, created_at, updated_at, metadata FROM sagas WHERE id = $1")
-            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
-            .fetch_optional(pool)
-            .await
-            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        let metadata = request.metadata.get_or_insert_with(HashMap::new);
+        metadata.insert(LOCK_KEY_METADATA_KEY.to_string(), lock.key.clone());
+        metadata.insert(LOCK_ID_METADATA_KEY.to_string(), acquired.lock_id.clone());
+        metadata.insert(LOCK_OWNER_METADATA_KEY.to_string(), lock.owner.clone());
 
-        Ok(saga)
+        let held = ReleaseLockRequest {
+            key: lock.key,
+            lock_id: acquired.lock_id,
+            owner: lock.owner,
+        };
+        let (finished, finished_rx) = oneshot::channel();
+        let hook: SagaCompletionHook = Box::new(move |_| {
+            let _ = finished.send(());
+        });
+        match self
+            .start(Uuid::new_v4().to_string(), request, Some(hook))
+            .await
+        {
+            Ok(saga) => {
+                let lock_id = held.lock_id.clone();
+                self.tasks.spawn(
+                    "saga_lock",
+                    self.clone().hold_saga_lock(
+                        locks,
+                        saga.saga_id.clone(),
+                        held,
+                        lock.ttl,
+                        finished_rx,
+                    ),
+                );
+                Ok(LockedSagaStart::Started { saga, lock_id })
+            }
+            Err(e) => {
+                if let Err(release_error) = locks.release_lock(held).await {
+                    tracing::warn!(
+                        "Failed to release lock of unstarted saga: {}",
+                        release_error
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Extends `lock` while the saga runs and releases it once it ends.
+    ///
+    /// Besides waiting for the completion hook, the saga is checked before
+    /// each extension, so the lock is also released if another instance
+    /// finished it.
+    async fn hold_saga_lock(
+        self,
+        locks: LockManager,
+        saga_id: String,
+        lock: ReleaseLockRequest,
+        ttl: Duration,
+        mut finished: oneshot::Receiver<()>,
+    ) {
+        let interval = (ttl / 3).max(Duration::from_millis(10));
+        let mut extended_at = tokio::time::Instant::now();
+        loop {
+            tokio::select! {
+                _ = &mut finished => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+            match self.get_saga_status(&saga_id).await {
+                Ok(Some(saga)) if saga.is_terminal() => {
+                    self.run_completion_hooks(&saga);
+                    break;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => tracing::warn!(saga_id = %saga_id, "Failed to check saga: {}", e),
+            }
+
+            // Extend by the time elapsed, keeping the expiry `ttl` ahead.
+            let now = tokio::time::Instant::now();
+            let extension = ExtendLockRequest {
+                key: lock.key.clone(),
+                lock_id: lock.lock_id.clone(),
+                owner: lock.owner.clone(),
+                ttl: now - extended_at,
+            };
+            extended_at = now;
+            match locks.extend_lock(extension).await {
+                Ok(response) if response.success => {}
+                Ok(response) => {
+                    tracing::warn!(saga_id = %saga_id, key = %lock.key, "Saga lock lost: {}", response.message);
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!(saga_id = %saga_id, key = %lock.key, "Failed to extend saga lock: {}", e)
+                }
+            }
+        }
+
+        let key = lock.key.clone();
+        if let Err(e) = locks.release_lock(lock).await {
+            tracing::warn!(saga_id = %saga_id, key = %key, "Failed to release saga lock: {}", e);
+        }
+    }
+
+    /// Waits until `saga_id` is no longer paused.
+    ///
+    /// Returns whether the saga is running, rather than cancelled or gone.
+    async fn wait_while_paused(&self, saga_id: &str) -> Result<bool> {
+        loop {
+            let resumed = self.resumed.notified();
+            tokio::pin!(resumed);
+            resumed.as_mut().enable();
+
+            let status = self
+                .get_saga_status(saga_id)
+                .await?
+                .and_then(|saga| saga.status.parse::<SagaStatus>().ok());
+            match status {
+                Some(SagaStatus::Running) => return Ok(true),
+                Some(SagaStatus::Paused) => {
+                    let _ = tokio::time::timeout(PAUSE_RECHECK_INTERVAL, resumed).await;
+                }
+                _ => return Ok(false),
+            }
+        }
+    }
+
+    /// Active sagas, oldest first, limited to those with a step calling
+    /// `service` if given.
+    ///
+    /// Compensating sagas count as active, as their compensations still call
+    /// the services of their steps.
+    pub async fn list_active_sagas(&self, service: Option<&str>) -> Result<Vec<Saga>> {
+        self.find_sagas(&SagaFilter {
+            active_only: true,
+            service: service.map(str::to_string),
+            ..SagaFilter::default()
+        })
+        .await
+    }
+
+    /// Summaries of the sagas matching `filter`, oldest first.
+    pub async fn list_sagas(&self, filter: &SagaFilter) -> Result<Vec<SagaSummary>> {
+        Ok(self
+            .find_sagas(filter)
+            .await?
+            .iter()
+            .map(SagaSummary::from_saga)
+            .collect())
+    }
+
+    /// The sagas matching `filter`, oldest first.
+    pub async fn find_sagas(&self, filter: &SagaFilter) -> Result<Vec<Saga>> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                let mut found: Vec<Saga> = sagas
+                    .read()
+                    .await
+                    .values()
+                    .filter(|saga| filter.matches(saga))
+                    .cloned()
+                    .collect();
+                found.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+                return Ok(found
+                    .into_iter()
+                    .skip(filter.offset)
+                    .take(filter.limit.unwrap_or(usize::MAX))
+                    .collect());
+            }
+        };
+
+        let excluded = if filter.active_only {
+            terminal_statuses()
+        } else {
+            Vec::new()
+        };
+        let containment = filter
+            .service
+            .as_ref()
+            .map(|service| serde_json::json!([{ "service": service }]));
+        sqlx::query_as(
+            "SELECT id::text, name, status, steps, current_step, created_at, updated_at, metadata, deadline_at, failure_reason, step_results, start_at \
+             FROM sagas WHERE status <> ALL($1) AND ($2::jsonb IS NULL OR steps @> $2) \
+             AND ($3::text IS NULL OR status = $3) AND ($4::text IS NULL OR starts_with(name, $4)) \
+             AND ($5::timestamptz IS NULL OR created_at > $5) AND ($6::text IS NULL OR metadata->>'owner' = $6) \
+             ORDER BY created_at, id LIMIT $7 OFFSET $8",
+        )
+        .bind(excluded)
+        .bind(containment.map(sqlx::types::Json))
+        .bind(filter.status.as_ref().map(|status| status.to_string()))
+        .bind(filter.name_prefix.as_deref())
+        .bind(filter.created_after)
+        .bind(filter.owner.as_deref())
+        .bind(filter.limit.map(|limit| limit.min(i64::MAX as usize) as i64))
+        .bind(filter.offset.min(i64::MAX as usize) as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))
+    }
+
+    /// Estimated bytes held by the sagas kept in memory; 0 over Postgres.
+    pub async fn estimated_bytes(&self) -> u64 {
+        match &self.backend {
+            SagaBackend::Postgres(_) => 0,
+            SagaBackend::Memory(sagas) => sagas
+                .read()
+                .await
+                .iter()
+                .map(|(saga_id, saga)| crate::core::memory::entry_size(saga_id, saga))
+                .sum::<usize>() as u64,
+        }
+    }
+
+    /// Number of active sagas with a step calling each service.
+    pub async fn active_saga_counts_by_service(&self) -> Result<BTreeMap<String, u64>> {
+        let mut counts = BTreeMap::new();
+        for saga in self.list_active_sagas(None).await? {
+            let services: HashSet<&str> = saga
+                .steps
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|step| step.get("service")?.as_str())
+                .collect();
+            for service in services {
+                *counts.entry(service.to_string()).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Pauses the pending and running sagas whose next step calls `service`.
+    ///
+    /// Steps already executing finish; the sagas then wait until resumed.
+    /// Returns the IDs of the paused sagas.
+    pub async fn pause_sagas_for_service(&self, service: &str) -> Result<Vec<String>> {
+        let mut paused = Vec::new();
+        for saga in self.list_active_sagas(Some(service)).await? {
+            if saga.next_step_service() != Some(service) {
+                continue;
+            }
+            let from = [SagaStatus::Pending, SagaStatus::Running];
+            if self
+                .set_status(&saga.id, SagaStatus::Paused, &from, None)
+                .await?
+            {
+                self.publish_status(&saga.id).await;
+                paused.push(saga.id);
+            }
+        }
+        Ok(paused)
+    }
+
+    /// Resumes the paused sagas whose next step calls `service`.
+    ///
+    /// Returns the IDs of the resumed sagas.
+    pub async fn resume_sagas_for_service(&self, service: &str) -> Result<Vec<String>> {
+        let mut resumed = Vec::new();
+        for saga in self.list_active_sagas(Some(service)).await? {
+            if saga.next_step_service() != Some(service) {
+                continue;
+            }
+            if self
+                .set_status(&saga.id, SagaStatus::Running, &[SagaStatus::Paused], None)
+                .await?
+            {
+                self.publish_status(&saga.id).await;
+                resumed.push(saga.id);
+            }
+        }
+        self.resumed.notify_waiters();
+        Ok(resumed)
+    }
+
+    /// Marks a saga whose execution panicked as failed and escalates it to
+    /// the dead-letter queue.
+    async fn fail_panicked_saga(&self, saga_id: &str, message: &str) {
+        tracing::error!(saga_id = %saga_id, "Saga execution panicked: {}", message);
+        let reason = format!("{}: {}", SAGA_PANIC_REASON, message);
+        match self
+            .set_status(saga_id, SagaStatus::Failed, &[], Some(&reason))
+            .await
+        {
+            Ok(updated) => {
+                if updated {
+                    self.count_saga_end(saga_id, SagaStatus::Failed).await;
+                }
+                self.publish_status(saga_id).await
+            }
+            Err(e) => tracing::error!(saga_id = %saga_id, "Failed to fail panicked saga: {}", e),
+        }
+
+        if let Some(dead_letters) = &self.dead_letters {
+            if let Ok(Some(saga)) = self.get_saga_status(saga_id).await {
+                dead_letters
+                    .escalate(&saga, saga.current_step_name(), &reason)
+                    .await;
+            }
+        }
+    }
+
+    /// Aborts the sagas executing on this instance and waits until their
+    /// tasks have stopped.
+    ///
+    /// The sagas keep the status they had when aborted.
+    pub async fn shutdown(&self) {
+        let tasks: Vec<_> = self.running.lock().unwrap().drain().collect();
+        for (_, task) in &tasks {
+            task.abort();
+        }
+        for (saga_id, task) in tasks {
+            if let Err(e) = task.await {
+                if !e.is_cancelled() {
+                    tracing::warn!(saga_id = %saga_id, "Saga task failed during shutdown: {}", e);
+                }
+            }
+        }
+    }
+
+    pub async fn execute_saga(&self, saga_id: &str) -> Result<()> {
+        let started = self
+            .set_status(saga_id, SagaStatus::Running, &[SagaStatus::Pending], None)
+            .await?;
+        if started {
+            self.publish_status(saga_id).await;
+        }
+
+        let (steps, metadata, executions) = self.get_saga_steps(saga_id).await?;
+        let request_id = metadata.get(REQUEST_ID_METADATA_KEY).cloned();
+        let deadline = self
+            .get_saga_status(saga_id)
+            .await?
+            .and_then(|saga| saga.deadline_at);
+
+        for stage in step_stages(&steps) {
+            // Completed before a restart, see `recover_pending_sagas`.
+            let pending: Vec<usize> = stage
+                .filter(|&step_index| {
+                    !executions
+                        .get(step_index)
+                        .is_some_and(|execution| execution.status == StepStatus::Completed)
+                })
+                .collect();
+            if pending.is_empty() {
+                continue;
+            }
+            if deadline.is_some_and(|deadline| Utc::now() >= deadline) {
+                self.time_out(saga_id).await?;
+                return Ok(());
+            }
+            if !self.wait_while_paused(saga_id).await? {
+                // Cancelled before the stage began.
+                return Ok(());
+            }
+
+            // Every branch of the stage runs to its end, so a failure
+            // compensates the branches that completed alongside it.
+            let outcomes = join_all(pending.iter().map(|&step_index| {
+                self.execute_step_with_retries(
+                    saga_id,
+                    step_index,
+                    &steps[step_index],
+                    &request_id,
+                    &metadata,
+                    deadline,
+                )
+            }))
+            .await;
+            // Cancelled while waiting to retry
+            if outcomes.iter().any(|outcome| matches!(outcome, Ok(false))) {
+                return Ok(());
+            }
+            if let Some(Err(e)) = outcomes.into_iter().find(Result::is_err) {
+                if deadline.is_some_and(|deadline| Utc::now() >= deadline) {
+                    // A step ran into the saga deadline
+                    self.time_out(saga_id).await?;
+                    return Ok(());
+                }
+                // Retries are exhausted, so start compensation
+                self.compensate_saga(saga_id, SagaStatus::Compensated)
+                    .await?;
+                return Err(e);
+            }
+        }
+
+        if self
+            .set_status(saga_id, SagaStatus::Completed, &[SagaStatus::Running], None)
+            .await?
+        {
+            self.count_saga_end(saga_id, SagaStatus::Completed).await;
+        }
+        self.publish_status(saga_id).await;
+
+        Ok(())
+    }
+
+    /// Runs the action of `step`, retrying it per its retry policy and
+    /// recording every attempt on the saga.
+    ///
+    /// Every attempt gets the step's timeout, cut short by the saga
+    /// `deadline`. Returns the error of the last attempt once the retries are
+    /// exhausted, or `false` if the saga was cancelled while waiting to retry.
+    async fn execute_step_with_retries(
+        &self,
+        saga_id: &str,
+        step_index: usize,
+        step: &SagaStep,
+        request_id: &Option<String>,
+        metadata: &HashMap<String, String>,
+        deadline: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let max_attempts = step
+            .retry_policy
+            .as_ref()
+            .map_or(1, RetryPolicy::max_attempts);
+        let mut execution = StepExecution {
+            status: StepStatus::Running,
+            started_at: Some(Utc::now()),
+            ..StepExecution::pending(&step.name)
+        };
+        self.record_step_execution(saga_id, step_index, &execution)
+            .await?;
+
+        // Retrying cannot resolve a missing reference, so the step fails
+        // before it is called.
+        let step = match self.render_step(saga_id, step, metadata).await {
+            Ok(step) => step,
+            Err(e) => {
+                execution.status = StepStatus::Failed;
+                execution.error = Some(e.to_string());
+                execution.completed_at = Some(Utc::now());
+                self.record_step_execution(saga_id, step_index, &execution)
+                    .await?;
+                return Err(e);
+            }
+        };
+        let step = &step;
+
+        let mut attempt = 1;
+        loop {
+            let context = StepCallContext::new(saga_id, &step.name, attempt, request_id.clone());
+            let called_at = std::time::Instant::now();
+            let outcome = within_timeout(
+                step_call_timeout(step, deadline, Utc::now()),
+                &step.name,
+                self.execute_step(step_index, step, &context, metadata),
+            )
+            .await;
+            self.observe_step(&step.name, called_at.elapsed());
+            let record = StepAttempt {
+                attempt,
+                error: outcome.as_ref().err().map(attempt_error),
+                at: Utc::now(),
+            };
+            self.record_step_attempt(saga_id, step_index, &record)
+                .await?;
+
+            execution.attempts = attempt;
+            execution.error = record.error;
+            // No retry is started that could only begin past the deadline
+            let retry = step.retry_policy.as_ref().filter(|policy| {
+                outcome.is_err()
+                    && attempt < max_attempts
+                    && !deadline.is_some_and(|deadline| {
+                        chrono::Duration::from_std(policy.delay(attempt))
+                            .ok()
+                            .and_then(|delay| Utc::now().checked_add_signed(delay))
+                            .is_none_or(|retry_at| retry_at >= deadline)
+                    })
+            });
+            if retry.is_none() {
+                execution.status = match outcome {
+                    Ok(()) => StepStatus::Completed,
+                    Err(_) => StepStatus::Failed,
+                };
+                execution.completed_at = Some(record.at);
+            }
+            self.record_step_execution(saga_id, step_index, &execution)
+                .await?;
+
+            let Err(e) = outcome else {
+                return Ok(true);
+            };
+            let Some(policy) = retry else {
+                return Err(e);
+            };
+            tracing::warn!(
+                saga_id = %saga_id,
+                step = %step.name,
+                attempt,
+                "Saga step failed, retrying: {}",
+                e
+            );
+            tokio::time::sleep(policy.delay(attempt)).await;
+            if !self.wait_while_paused(saga_id).await? {
+                return Ok(false);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Runs the action of `step` through its executor, recording the output
+    /// as the step's result, or else simulates it.
+    async fn execute_step(
+        &self,
+        step_index: usize,
+        step: &SagaStep,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<()> {
+        self.set_current_step(&context.saga_id, step_index).await?;
+
+        tracing::debug!(
+            saga_id = %context.saga_id,
+            step = %context.step,
+            attempt = context.attempt,
+            request_id = context.request_id.as_deref().unwrap_or("-"),
+            "Executing saga step"
+        );
+
+        let Some(executor) = self.executors.for_step(step)? else {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            return Ok(());
+        };
+
+        let outcome = executor.execute(step, context, metadata).await.map_err(|e| {
+            tracing::warn!(saga_id = %context.saga_id, step = %context.step, "Saga step failed: {}", e);
+            e
+        })?;
+        let Some(output) = outcome.output else {
+            return Ok(());
+        };
+        let mut result = self
+            .capture_step_result(&context.saga_id, &step.name, &output)
+            .await?;
+        result.call = Some(context.clone());
+        self.record_step_result(&context.saga_id, step_index, &result)
+            .await
+    }
+
+    /// Compensates the steps of a saga that started, in reverse order, and
+    /// moves it to `compensated` once they all are.
+    ///
+    /// Steps that never started have nothing to undo and are skipped, as are
+    /// steps compensated before a restart; a step interrupted while running
+    /// is compensated, as its call may have gone through. The resources
+    /// started steps declare are released even if their compensation fails.
+    async fn compensate_saga(&self, saga_id: &str, compensated: SagaStatus) -> Result<()> {
+        self.set_status(saga_id, SagaStatus::Compensating, &[], None)
+            .await?;
+        self.publish_status(saga_id).await;
+
+        let (steps, metadata, executions) = self.get_saga_steps(saga_id).await?;
+        let request_id = metadata.get(REQUEST_ID_METADATA_KEY).cloned();
+        // Sagas stored before step states were tracked have none recorded,
+        // and steps compensated before a restart are not compensated again.
+        // Steps failing before their first attempt, e.g. on an unresolvable
+        // payload reference, were never called.
+        let started: Vec<SagaStep> = steps
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                executions.get(*index).is_none_or(|execution| {
+                    !matches!(
+                        (execution.status, execution.attempts),
+                        (StepStatus::Pending | StepStatus::Compensated, _)
+                            | (StepStatus::Failed, 0)
+                    )
+                })
+            })
+            .map(|(_, step)| step.clone())
+            .collect();
+        // A compensated step keeps the error its action failed with, if any.
+        let execution = |index: usize, status: StepStatus, error: Option<String>, attempts| {
+            let execution = executions
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| StepExecution::pending(&steps[index].name));
+            StepExecution {
+                status,
+                error: error.or(execution.error.clone()),
+                compensation_attempts: attempts,
+                compensated_at: Some(Utc::now()),
+                ..execution
+            }
+        };
+        let outcome = compensate_steps(saga_id, &started, request_id, |context| {
+            let index = steps.iter().position(|step| step.name == context.step);
+            let step = index.map(|index| &steps[index]);
+            let metadata = &metadata;
+            let execution = &execution;
+            async move {
+                self.compensate_step(step, &context, metadata).await?;
+                self.count_compensation("succeeded");
+                if let (Some(index), Some(step)) = (index, step) {
+                    self.release_step_resources(saga_id, step).await;
+                    let compensated =
+                        execution(index, StepStatus::Compensated, None, context.attempt);
+                    self.record_step_execution(saga_id, index, &compensated)
+                        .await?;
+                }
+                Ok(())
+            }
+        })
+        .await;
+
+        if let Err(failure) = outcome {
+            self.count_compensation("failed");
+            // The failed step and those left uncompensated before it still
+            // release what they declare.
+            let uncompensated = started
+                .iter()
+                .position(|step| step.name == failure.step)
+                .map_or(started.len(), |index| index + 1);
+            for step in started[..uncompensated].iter().rev() {
+                self.release_step_resources(saga_id, step).await;
+            }
+            if let Some(index) = steps.iter().position(|step| step.name == failure.step) {
+                let failed = execution(
+                    index,
+                    StepStatus::CompensationFailed,
+                    Some(failure.error.clone()),
+                    failure.attempts,
+                );
+                self.record_step_execution(saga_id, index, &failed).await?;
+            }
+            if self
+                .set_status(saga_id, SagaStatus::CompensationFailed, &[], None)
+                .await?
+            {
+                self.count_saga_end(saga_id, SagaStatus::CompensationFailed)
+                    .await;
+            }
+            self.publish_status(saga_id).await;
+
+            if let Some(dead_letters) = &self.dead_letters {
+                if let Some(saga) = self.get_saga_status(saga_id).await? {
+                    dead_letters
+                        .escalate(&saga, Some(&failure.step), &failure.error)
+                        .await;
+                }
+            }
+
+            return Err(SyrosError::SagaError(format!(
+                "Compensation of step {} failed after {} attempts: {}",
+                failure.step, failure.attempts, failure.error
+            )));
+        }
+
+        if self
+            .set_status(saga_id, compensated.clone(), &[], None)
+            .await?
+        {
+            self.count_saga_end(saga_id, compensated).await;
+        }
+        self.publish_status(saga_id).await;
+
+        Ok(())
+    }
+
+    async fn compensate_step(
+        &self,
+        step: Option<&SagaStep>,
+        context: &StepCallContext,
+        metadata: &HashMap<String, String>,
+    ) -> Result<()> {
+        tracing::debug!(
+            saga_id = %context.saga_id,
+            step = %context.step,
+            attempt = context.attempt,
+            request_id = context.request_id.as_deref().unwrap_or("-"),
+            "Compensating saga step"
+        );
+
+        let Some(step) = step else {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            return Ok(());
+        };
+        let step = &self.render_step(&context.saga_id, step, metadata).await?;
+        let timeout = (!step.timeout.is_zero()).then_some(step.timeout);
+        let executor = self.executors.for_step(step)?;
+        within_timeout(timeout, &step.name, async {
+            match executor {
+                Some(executor) => executor.compensate(step, context, metadata).await,
+                None => {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(())
+                }
+            }
+        })
+        .await
+    }
+
+    /// `step` with the references in its payload resolved against the saga
+    /// metadata and the recorded results of the steps before it, see
+    /// [`saga_template::render_payload`].
+    async fn render_step(
+        &self,
+        saga_id: &str,
+        step: &SagaStep,
+        metadata: &HashMap<String, String>,
+    ) -> Result<SagaStep> {
+        let Some(payload) = &step.payload else {
+            return Ok(step.clone());
+        };
+        let reads_outputs = saga_template::references(payload)?
+            .iter()
+            .any(|reference| matches!(reference, TemplateRef::StepOutput { .. }));
+        let saga = match reads_outputs {
+            true => self.get_saga_status(saga_id).await?,
+            false => None,
+        };
+        let payload = saga_template::render_payload(payload, metadata, &|name| {
+            saga.as_ref().and_then(|saga| saga.step_result(name))
+        })?;
+        Ok(SagaStep {
+            payload: Some(payload),
+            ..step.clone()
+        })
+    }
+
+    /// Releases the locks and deletes the cache keys `step` declares, once
+    /// its compensation succeeded or failed for good.
+    ///
+    /// Failures are logged and counted but never fail the compensation.
+    async fn release_step_resources(&self, saga_id: &str, step: &SagaStep) {
+        for lock in &step.acquired_locks {
+            let owner = lock.owner.as_deref().unwrap_or(saga_id);
+            if let Err(e) = self.release_step_lock(&lock.key, owner).await {
+                self.resource_release_failed(saga_id, step, "lock", &lock.key, &e);
+            }
+        }
+        for key in &step.cache_keys {
+            if let Err(e) = self.delete_step_cache_key(key).await {
+                self.resource_release_failed(saga_id, step, "cache", key, &e);
+            }
+        }
+    }
+
+    /// Releases the lock on `key` if `owner` still holds it.
+    async fn release_step_lock(&self, key: &str, owner: &str) -> Result<()> {
+        let locks = self.lock_manager.as_ref().ok_or_else(|| {
+            SyrosError::SagaError("No lock manager to release locks through".to_string())
+        })?;
+        let Some(lock) = locks.get_lock_status(key).await? else {
+            return Ok(());
+        };
+        if lock.owner != owner {
+            // Expired and taken over since; not ours to release.
+            tracing::debug!(key = %key, owner = %lock.owner, "Step lock held by another owner");
+            return Ok(());
+        }
+
+        locks
+            .release_lock(ReleaseLockRequest {
+                key: key.to_string(),
+                lock_id: lock.id,
+                owner: owner.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_step_cache_key(&self, key: &str) -> Result<()> {
+        let cache = self.cache_manager.as_ref().ok_or_else(|| {
+            SyrosError::SagaError("No cache manager to delete cache keys from".to_string())
+        })?;
+        cache
+            .delete(DeleteCacheRequest {
+                key: key.to_string(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn resource_release_failed(
+        &self,
+        saga_id: &str,
+        step: &SagaStep,
+        resource: &str,
+        key: &str,
+        error: &SyrosError,
+    ) {
+        tracing::warn!(
+            saga_id = %saga_id,
+            step = %step.name,
+            resource,
+            key = %key,
+            "Failed to release step resource during compensation: {}",
+            error
+        );
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_saga_resource_release_failures(resource);
+        }
+    }
+
+    /// Counts a step compensation that ended with `outcome`, `succeeded` or
+    /// `failed`.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn count_compensation(&self, outcome: &str) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_saga_compensations(outcome);
+        }
+    }
+
+    /// Counts a saga created on this instance as started.
+    fn count_saga_started(&self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_sagas_started();
+        }
+    }
+
+    /// Counts a saga that ended before and executes again, or that a restart
+    /// left active, as running on this instance.
+    fn count_saga_resumed(&self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_sagas_resumed();
+        }
+    }
+
+    /// Counts a saga that just ended in `status` and records how long it ran
+    /// from its start time: `Completed` counts as completed, `Compensated`
+    /// and `Cancelled` as compensated and any other end as failed.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    async fn count_saga_end(&self, saga_id: &str, status: SagaStatus) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            match status {
+                SagaStatus::Completed => metrics.increment_sagas_completed(),
+                SagaStatus::Compensated | SagaStatus::Cancelled => {
+                    metrics.increment_sagas_compensated()
+                }
+                _ => metrics.increment_sagas_failed(),
+            }
+            if let Ok(Some(saga)) = self.get_saga_status(saga_id).await {
+                let started_at = saga.start_at.unwrap_or(saga.created_at);
+                let duration = (saga.updated_at - started_at).to_std().unwrap_or_default();
+                metrics.record_saga_execution(duration.as_secs_f64());
+            }
+        }
+    }
+
+    /// Records how long an attempt of the action of step `step` took.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn observe_step(&self, step: &str, duration: Duration) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_saga_step(step, duration.as_secs_f64());
+        }
+    }
+
+    /// Cancels and compensates every active saga whose deadline has passed.
+    ///
+    /// Returns the number of sagas cancelled by this call.
+    pub async fn cancel_expired_sagas(&self) -> Result<usize> {
+        let expired = self.expired_saga_ids(Utc::now()).await?;
+
+        let mut cancelled = 0;
+        for saga_id in expired {
+            match self.cancel_for_timeout(&saga_id).await {
+                Ok(true) => cancelled += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::error!(saga_id = %saga_id, "Failed to cancel timed out saga: {}", e)
+                }
+            }
+        }
+        Ok(cancelled)
+    }
+
+    /// Spawns a watchdog that cancels sagas exceeding their `max_duration`
+    /// and starts scheduled sagas once their time comes.
+    pub fn start_timeout_watchdog(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let orchestrator = self.clone();
+        self.tasks
+            .spawn_background("saga_timeout_watchdog", async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    if let Err(e) = orchestrator.cancel_expired_sagas().await {
+                        tracing::error!("Saga timeout watchdog failed: {}", e);
+                    }
+                    if let Err(e) = orchestrator.start_due_sagas().await {
+                        tracing::error!("Saga scheduler failed: {}", e);
+                    }
+                }
+            })
+    }
+
+    /// Cancels a scheduled, pending, running or paused saga: stops its
+    /// execution, compensates the steps that started and moves it to
+    /// `Cancelled`.
+    ///
+    /// `reason` is recorded as the failure reason and under
+    /// [`CANCEL_REASON_METADATA_KEY`]. Fails with `NotFound` for an unknown
+    /// saga and with `Conflict` if the saga already left the active states,
+    /// e.g. because it finished or another instance cancelled it first.
+    pub async fn cancel_saga(&self, saga_id: &str, reason: &str) -> Result<()> {
+        if self.cancel(saga_id, reason, SagaStatus::Cancelled).await? {
+            return Ok(());
+        }
+        Err(match self.get_saga_status(saga_id).await? {
+            None => SyrosError::NotFound(format!("Saga {} not found", saga_id)),
+            Some(saga) => SyrosError::Conflict(format!(
+                "Saga {} is {} and cannot be cancelled",
+                saga_id, saga.status
+            )),
+        })
+    }
+
+    /// Executes a `Failed` or `CompensationFailed` saga again, from
+    /// `from_step` or else from its first step that did not complete.
+    ///
+    /// The steps before that keep their results; the others are reset to
+    /// `Pending` and run again as if the saga had just reached them. The
+    /// retry is counted under [`RETRY_COUNT_METADATA_KEY`] and resolves the
+    /// saga's dead-letter entry, if any. A saga deadline still applies.
+    ///
+    /// Fails with `NotFound` for an unknown saga, with `Conflict` for a saga
+    /// in any other state, and with `SagaError` if `from_step` lies past a
+    /// step that did not complete.
+    pub async fn retry_saga(&self, saga_id: &str, from_step: Option<usize>) -> Result<()> {
+        let saga = self
+            .get_saga_status(saga_id)
+            .await?
+            .ok_or_else(|| SyrosError::NotFound(format!("Saga {} not found", saga_id)))?;
+        if !RETRYABLE_STATUSES
+            .iter()
+            .any(|status| saga.status == status.to_string())
+        {
+            return Err(SyrosError::Conflict(format!(
+                "Saga {} is {} and cannot be retried",
+                saga_id, saga.status
+            )));
+        }
+
+        let retries = saga.retry_count() + 1;
+        let (steps, _, executions) = saga_steps(saga)?;
+        let first_incomplete = (0..steps.len())
+            .find(|&index| {
+                !executions
+                    .get(index)
+                    .is_some_and(|execution| execution.status == StepStatus::Completed)
+            })
+            .unwrap_or(steps.len());
+        let from_step = from_step.unwrap_or(first_incomplete);
+        if from_step > first_incomplete {
+            return Err(SyrosError::SagaError(format!(
+                "Step {} of saga {} did not complete, so it cannot be retried from step {}",
+                first_incomplete, saga_id, from_step
+            )));
+        }
+        let executions: Vec<StepExecution> = steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| match executions.get(index) {
+                Some(execution) if index < from_step => execution.clone(),
+                _ => StepExecution::pending(&step.name),
+            })
+            .collect();
+
+        if !self.reset_for_retry(saga_id, &executions, retries).await? {
+            return Err(SyrosError::Conflict(format!(
+                "Saga {} changed state and cannot be retried",
+                saga_id
+            )));
+        }
+        tracing::info!(saga_id = %saga_id, from_step, retries, "Retrying saga");
+        self.count_saga_resumed();
+
+        if let Some(dead_letters) = &self.dead_letters {
+            match dead_letters
+                .resolve(saga_id, None, Some("Saga retried".to_string()))
+                .await
+            {
+                Ok(_) | Err(SyrosError::NotFound(_)) | Err(SyrosError::Conflict(_)) => {}
+                Err(e) => {
+                    tracing::warn!(saga_id = %saga_id, "Failed to resolve dead-letter entry: {}", e)
+                }
+            }
+        }
+        self.publish_status(saga_id).await;
+        self.spawn_saga(saga_id, false);
+        Ok(())
+    }
+
+    /// Confirms the step `step` of saga `saga_id`, parked by the `manual`
+    /// executor, with `outcome`: an outcome completes the step and records
+    /// its output as the step's result, an error fails the attempt.
+    ///
+    /// Fails with `NotFound` for an unknown saga or step, with `SagaError`
+    /// for a step not run by the `manual` executor, and with `Conflict` if
+    /// the step is not parked on this instance, e.g. it has not started yet
+    /// or already ended.
+    pub async fn complete_step(
+        &self,
+        saga_id: &str,
+        step: &str,
+        outcome: Result<StepOutcome>,
+    ) -> Result<()> {
+        let saga = self
+            .get_saga_status(saga_id)
+            .await?
+            .ok_or_else(|| SyrosError::NotFound(format!("Saga {} not found", saga_id)))?;
+        let (steps, _, _) = saga_steps(saga)?;
+        let parked = steps
+            .iter()
+            .find(|candidate| candidate.name == step)
+            .ok_or_else(|| {
+                SyrosError::NotFound(format!("Saga {} has no step {}", saga_id, step))
+            })?;
+        if parked.executor.as_deref() != Some(MANUAL_EXECUTOR) {
+            return Err(SyrosError::SagaError(format!(
+                "Step {} is not run by the {} executor",
+                step, MANUAL_EXECUTOR
+            )));
+        }
+        self.manual_steps.confirm(saga_id, step, outcome)
+    }
+
+    /// Claims an active saga, stops its execution here if it runs on this
+    /// instance and compensates it into `compensated`.
+    ///
+    /// Executions running on other instances stop before their next step, as
+    /// the saga is no longer running. Returns `false` if the saga is not
+    /// active.
+    async fn cancel(&self, saga_id: &str, reason: &str, compensated: SagaStatus) -> Result<bool> {
+        if !self.claim_for_cancel(saga_id, reason, true).await? {
+            return Ok(false);
+        }
+
+        // Compensate in a task of its own, so a caller giving up, e.g. on a
+        // request deadline, cannot leave the saga half compensated.
+        let orchestrator = self.clone();
+        let id = saga_id.to_string();
+        self.tasks
+            .spawn("saga_compensation", async move {
+                orchestrator.compensate_saga(&id, compensated).await
+            })
+            .await
+            .map_err(|e| {
+                SyrosError::InternalError(format!("Compensation of saga {} failed: {}", saga_id, e))
+            })??;
+        Ok(true)
+    }
+
+    /// Moves an active saga to `Compensating` for `reason` and, with `abort`,
+    /// stops its execution here if it runs on this instance.
+    ///
+    /// Returns `false` if the saga is not active.
+    async fn claim_for_cancel(&self, saga_id: &str, reason: &str, abort: bool) -> Result<bool> {
+        let claimed = self
+            .set_status(
+                saga_id,
+                SagaStatus::Compensating,
+                &ACTIVE_STATUSES,
+                Some(reason),
+            )
+            .await?;
+        if !claimed {
+            return Ok(false);
+        }
+
+        if abort {
+            if let Some(task) = self.running.lock().unwrap().remove(saga_id) {
+                task.abort();
+            }
+        }
+        tracing::warn!(saga_id = %saga_id, "Saga cancelled ({}), compensating", reason);
+        self.set_metadata_value(saga_id, CANCEL_REASON_METADATA_KEY, reason)
+            .await?;
+        Ok(true)
+    }
+
+    /// Compensates a saga its own execution found past its deadline, as the
+    /// timeout watchdog would.
+    async fn time_out(&self, saga_id: &str) -> Result<()> {
+        if self
+            .claim_for_cancel(saga_id, SAGA_TIMEOUT_REASON, false)
+            .await?
+        {
+            self.compensate_saga(saga_id, SagaStatus::Compensated)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Claims a timed out saga and compensates it; see [`cancel`](Self::cancel).
+    async fn cancel_for_timeout(&self, saga_id: &str) -> Result<bool> {
+        self.cancel(saga_id, SAGA_TIMEOUT_REASON, SagaStatus::Compensated)
+            .await
+    }
+
+    /// Loads the steps of a saga together with its metadata and the
+    /// execution state of its steps.
+    async fn get_saga_steps(&self, saga_id: &str) -> Result<SagaSteps> {
+        let saga = self
+            .get_saga_status(saga_id)
+            .await?
+            .ok_or_else(|| SyrosError::SagaError(format!("Saga {} not found", saga_id)))?;
+        saga_steps(saga)
+    }
+
+    pub async fn get_saga_status(&self, saga_id: &str) -> Result<Option<Saga>> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => return Ok(sagas.read().await.get(saga_id).cloned()),
+        };
+
+        let saga: Option<Saga> = sqlx::query_as("SELECT id::text, name, status, steps, current_step, created_at, updated_at, metadata, deadline_at, failure_reason, step_results, start_at FROM sagas WHERE id = $1")
+            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+
+        Ok(saga)
+    }
+
+    async fn insert_saga(&self, saga: Saga) -> Result<()> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                sagas.write().await.insert(saga.id.clone(), saga);
+                return Ok(());
+            }
+        };
+
+        sqlx::query(
+            "INSERT INTO sagas (id, name, status, steps, created_at, updated_at, metadata, deadline_at, step_results, start_at) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(Uuid::parse_str(&saga.id).unwrap_or_default())
+        .bind(&saga.name)
+        .bind(&saga.status)
+        .bind(sqlx::types::Json(&saga.steps))
+        .bind(saga.created_at)
+        .bind(saga.updated_at)
+        .bind(sqlx::types::Json(&saga.metadata))
+        .bind(saga.deadline_at)
+        .bind(sqlx::types::Json(&saga.step_results))
+        .bind(saga.start_at)
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Moves a saga to `status`, recording `failure_reason` if given.
+    ///
+    /// With a non-empty `from`, the saga only moves if it is in one of those
+    /// states. Returns whether the saga was updated.
+    async fn set_status(
+        &self,
+        saga_id: &str,
+        status: SagaStatus,
+        from: &[SagaStatus],
+        failure_reason: Option<&str>,
+    ) -> Result<bool> {
+        let from: Vec<String> = from.iter().map(|s| s.to_string()).collect();
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                let mut sagas = sagas.write().await;
+                let Some(saga) = sagas.get_mut(saga_id) else {
+                    return Ok(false);
+                };
+                if !from.is_empty() && !from.contains(&saga.status) {
+                    return Ok(false);
+                }
+                saga.status = status.to_string();
+                if let Some(reason) = failure_reason {
+                    saga.failure_reason = Some(reason.to_string());
+                }
+                saga.updated_at = Utc::now();
+                return Ok(true);
+            }
+        };
+
+        let updated = sqlx::query(
+            "UPDATE sagas SET status = $2, failure_reason = COALESCE($3, failure_reason), updated_at = NOW() \
+             WHERE id = $1 AND (cardinality($4::text[]) = 0 OR status = ANY($4))",
+        )
+        .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+        .bind(status.to_string())
+        .bind(failure_reason)
+        .bind(&from)
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?
+        .rows_affected()
+            == 1;
+        Ok(updated)
+    }
+
+    /// Moves a retryable saga back to `Pending` with `executions` as its step
+    /// states and `retries` as its retry count, clearing its failure reason
+    /// and cancel reason.
+    ///
+    /// Returns `false` if the saga is no longer retryable.
+    async fn reset_for_retry(
+        &self,
+        saga_id: &str,
+        executions: &[StepExecution],
+        retries: u32,
+    ) -> Result<bool> {
+        let retryable: Vec<String> = RETRYABLE_STATUSES.iter().map(|s| s.to_string()).collect();
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                let mut sagas = sagas.write().await;
+                let Some(saga) = sagas.get_mut(saga_id) else {
+                    return Ok(false);
+                };
+                if !retryable.contains(&saga.status) {
+                    return Ok(false);
+                }
+                saga.status = SagaStatus::Pending.to_string();
+                saga.failure_reason = None;
+                saga.step_results = executions.to_vec();
+                if !saga.metadata.is_object() {
+                    saga.metadata = serde_json::json!({});
+                }
+                if let Some(metadata) = saga.metadata.as_object_mut() {
+                    metadata.remove(CANCEL_REASON_METADATA_KEY);
+                    metadata.insert(
+                        RETRY_COUNT_METADATA_KEY.to_string(),
+                        serde_json::Value::String(retries.to_string()),
+                    );
+                }
+                saga.updated_at = Utc::now();
+                return Ok(true);
+            }
+        };
+
+        let updated = sqlx::query(
+            "UPDATE sagas SET status = $2, failure_reason = NULL, step_results = $3, \
+             metadata = jsonb_set(COALESCE(metadata, '{}'::jsonb) - $4, ARRAY[$5], to_jsonb($6::text)), updated_at = NOW() \
+             WHERE id = $1 AND status = ANY($7)",
+        )
+        .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+        .bind(SagaStatus::Pending.to_string())
+        .bind(sqlx::types::Json(executions))
+        .bind(CANCEL_REASON_METADATA_KEY)
+        .bind(RETRY_COUNT_METADATA_KEY)
+        .bind(retries.to_string())
+        .bind(&retryable)
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?
+        .rows_affected()
+            == 1;
+        Ok(updated)
+    }
+
+    /// Sets the metadata `key` of a saga to `value`.
+    async fn set_metadata_value(&self, saga_id: &str, key: &str, value: &str) -> Result<()> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                if let Some(saga) = sagas.write().await.get_mut(saga_id) {
+                    if !saga.metadata.is_object() {
+                        saga.metadata = serde_json::json!({});
+                    }
+                    saga.metadata[key] = serde_json::Value::String(value.to_string());
+                    saga.updated_at = Utc::now();
+                }
+                return Ok(());
+            }
+        };
+
+        sqlx::query(
+            "UPDATE sagas SET metadata = jsonb_set(COALESCE(metadata, '{}'::jsonb), ARRAY[$1], to_jsonb($2::text)), updated_at = NOW() WHERE id = $3",
+        )
+        .bind(key)
+        .bind(value)
+        .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Stores `execution` as the state of the saga's step at `step_index`.
+    async fn record_step_execution(
+        &self,
+        saga_id: &str,
+        step_index: usize,
+        execution: &StepExecution,
+    ) -> Result<()> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                if let Some(saga) = sagas.write().await.get_mut(saga_id) {
+                    if saga.step_results.len() <= step_index {
+                        saga.step_results.resize_with(step_index + 1, || {
+                            StepExecution::pending(&execution.step)
+                        });
+                    }
+                    saga.step_results[step_index] = execution.clone();
+                    saga.updated_at = Utc::now();
+                }
+                return Ok(());
+            }
+        };
+
+        sqlx::query(
+            "UPDATE sagas SET step_results = jsonb_set(step_results, ARRAY[$1::text], $2), updated_at = NOW() WHERE id = $3",
+        )
+        .bind(step_index.to_string())
+        .bind(sqlx::types::Json(execution))
+        .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Appends `attempt` to the `attempts` of the saga's step at `step_index`.
+    async fn record_step_attempt(
+        &self,
+        saga_id: &str,
+        step_index: usize,
+        attempt: &StepAttempt,
+    ) -> Result<()> {
+        let attempt =
+            serde_json::to_value(attempt).map_err(|e| SyrosError::SagaError(e.to_string()))?;
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                if let Some(saga) = sagas.write().await.get_mut(saga_id) {
+                    if let Some(step) = saga.steps.get_mut(step_index) {
+                        match step["attempts"].as_array_mut() {
+                            Some(attempts) => attempts.push(attempt),
+                            None => step["attempts"] = serde_json::Value::Array(vec![attempt]),
+                        }
+                    }
+                    saga.updated_at = Utc::now();
+                }
+                return Ok(());
+            }
+        };
+
+        sqlx::query(
+            "UPDATE sagas SET steps = jsonb_set(steps, ARRAY[$1::text, 'attempts'], COALESCE(steps -> $2 -> 'attempts', '[]'::jsonb) || jsonb_build_array($3::jsonb)), updated_at = NOW() WHERE id = $4",
+        )
+        .bind(step_index.to_string())
+        .bind(step_index as i32)
+        .bind(attempt)
+        .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Stores `result` as the `result` of the saga's step at `step_index`.
+    async fn record_step_result(
+        &self,
+        saga_id: &str,
+        step_index: usize,
+        result: &StepResult,
+    ) -> Result<()> {
+        let result =
+            serde_json::to_value(result).map_err(|e| SyrosError::SagaError(e.to_string()))?;
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                if let Some(saga) = sagas.write().await.get_mut(saga_id) {
+                    if let Some(step) = saga.steps.get_mut(step_index) {
+                        step["result"] = result;
+                    }
+                    saga.updated_at = Utc::now();
+                }
+                return Ok(());
+            }
+        };
+
+        sqlx::query(
+            "UPDATE sagas SET steps = jsonb_set(steps, ARRAY[$1::text, 'result'], $2), updated_at = NOW() WHERE id = $3",
+        )
+        .bind(step_index.to_string())
+        .bind(result)
+        .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+        .execute(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn set_current_step(&self, saga_id: &str, step_index: usize) -> Result<()> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                if let Some(saga) = sagas.write().await.get_mut(saga_id) {
+                    saga.current_step = Some(step_index as i32);
+                    saga.updated_at = Utc::now();
+                }
+                return Ok(());
+            }
+        };
+
+        sqlx::query("UPDATE sagas SET current_step = $1, updated_at = NOW() WHERE id = $2")
+            .bind(step_index as i32)
+            .bind(Uuid::parse_str(saga_id).unwrap_or_default())
+            .execute(pool)
+            .await
+            .map_err(|e| crate::SyrosError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// IDs of active sagas whose deadline is at or before `now`.
+    async fn expired_saga_ids(&self, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                let active: Vec<String> = ACTIVE_STATUSES.iter().map(|s| s.to_string()).collect();
+                return Ok(sagas
+                    .read()
+                    .await
+                    .values()
+                    .filter(|saga| saga.deadline_at.is_some_and(|deadline| deadline <= now))
+                    .filter(|saga| active.contains(&saga.status))
+                    .map(|saga| saga.id.clone())
+                    .collect());
+            }
+        };
+
+        let active: Vec<String> = ACTIVE_STATUSES.iter().map(|s| s.to_string()).collect();
+        sqlx::query_scalar(
+            "SELECT id::text FROM sagas WHERE deadline_at <= $1 AND status = ANY($2)",
+        )
+        .bind(now)
+        .bind(&active)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))
+    }
+
+    /// IDs of scheduled sagas whose start time is at or before `now`, the
+    /// earliest first.
+    async fn due_saga_ids(&self, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let scheduled = SagaStatus::Scheduled.to_string();
+        let pool = match &self.backend {
+            SagaBackend::Postgres(pg) => pg.get_pool(),
+            SagaBackend::Memory(sagas) => {
+                let sagas = sagas.read().await;
+                let mut due: Vec<&Saga> = sagas
+                    .values()
+                    .filter(|saga| saga.status == scheduled)
+                    .filter(|saga| saga.start_at.is_none_or(|start_at| start_at <= now))
+                    .collect();
+                due.sort_by_key(|saga| saga.start_at);
+                return Ok(due.into_iter().map(|saga| saga.id.clone()).collect());
+            }
+        };
+
+        sqlx::query_scalar(
+            "SELECT id::text FROM sagas WHERE status = $1 AND (start_at IS NULL OR start_at <= $2) \
+             ORDER BY start_at",
+        )
+        .bind(scheduled)
+        .bind(now)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| crate::SyrosError::StorageError(e.to_string()))
+    }
+}
+
+/// Steps of a saga together with its metadata and the execution state of
+/// its steps.
+type SagaSteps = (Vec<SagaStep>, HashMap<String, String>, Vec<StepExecution>);
+
+/// Reads the steps of `saga`; steps that cannot be read back are a storage
+/// error.
+fn saga_steps(saga: Saga) -> Result<SagaSteps> {
+    let metadata = saga.metadata_map();
+    let steps: Vec<SagaStep> = serde_json::from_value(saga.steps).map_err(|e| {
+        SyrosError::StorageError(format!("Invalid steps for saga {}: {}", saga.id, e))
+    })?;
+    Ok((steps, metadata, saga.step_results))
+}
+
+/// Names of the states a saga cannot leave.
+fn terminal_statuses() -> Vec<String> {
+    [
+        SagaStatus::Completed,
+        SagaStatus::Failed,
+        SagaStatus::Compensated,
+        SagaStatus::CompensationFailed,
+        SagaStatus::Cancelled,
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Longest a call of `step` may take at `now`: its own timeout, cut short by
+/// what is left until the saga `deadline`. A zero step timeout means none;
+/// `None` if neither applies.
+fn step_call_timeout(
+    step: &SagaStep,
+    deadline: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    let own = (!step.timeout.is_zero()).then_some(step.timeout);
+    let remaining = deadline.map(|deadline| (deadline - now).to_std().unwrap_or(Duration::ZERO));
+    match (own, remaining) {
+        (Some(own), Some(remaining)) => Some(own.min(remaining)),
+        (own, remaining) => own.or(remaining),
+    }
+}
+
+/// Runs `call` to a step, failing with `Timeout` if it takes longer than
+/// `timeout`.
+async fn within_timeout<T>(
+    timeout: Option<Duration>,
+    step: &str,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return call.await;
+    };
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or_else(|_| {
+            Err(SyrosError::Timeout(format!(
+                "Step {} did not finish within {:?}",
+                step, timeout
+            )))
+        })
+}
+
+/// Error recorded for a failed step attempt; [`STEP_TIMEOUT_ERROR`] if it
+/// ran out of time.
+fn attempt_error(error: &SyrosError) -> String {
+    match error {
+        SyrosError::Timeout(_) => STEP_TIMEOUT_ERROR.to_string(),
+        error => error.to_string(),
+    }
+}
+
+/// Compensates `steps` in reverse order, retrying each per its retry policy.
+///
+/// Stops at the first compensation that still fails once its retries are
+/// exhausted; the steps before it are left for an operator to handle.
+async fn compensate_steps<F, Fut>(
+    saga_id: &str,
+    steps: &[SagaStep],
+    request_id: Option<String>,
+    mut compensate: F,
+) -> std::result::Result<(), CompensationFailure>
+where
+    F: FnMut(StepCallContext) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    for step in steps.iter().rev() {
+        let max_attempts = step
+            .retry_policy
+            .as_ref()
+            .map_or(DEFAULT_COMPENSATION_RETRIES + 1, RetryPolicy::max_attempts);
+
+        let mut attempt = 1;
+        loop {
+            let context = StepCallContext::new(saga_id, &step.name, attempt, request_id.clone())
+                .for_compensation();
+            match compensate(context).await {
+                Ok(()) => break,
+                Err(e) if attempt >= max_attempts => {
+                    return Err(CompensationFailure {
+                        step: step.name.clone(),
+                        attempts: attempt,
+                        error: e.to_string(),
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        saga_id = %saga_id,
+                        step = %step.name,
+                        attempt,
+                        "Compensation failed, retrying: {}",
+                        e
+                    );
+                    tokio::time::sleep(compensation_retry_delay(step, attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Delay before retrying a compensation after its `attempt`-th failure.
+fn compensation_retry_delay(step: &SagaStep, attempt: u32) -> Duration {
+    step.retry_policy
+        .as_ref()
+        .map_or(DEFAULT_COMPENSATION_RETRY_DELAY, |policy| {
+            policy.delay(attempt)
+        })
+}
+
+/// Message of a caught panic, when it carries one.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_call_headers() {
+        let context = StepCallContext::new("saga-1", "reserve-stock", 2, Some("req-9".to_string()));
+
+        let headers = context.headers();
+        assert!(headers.contains(&(SAGA_ID_HEADER, "saga-1".to_string())));
+        assert!(headers.contains(&(STEP_HEADER, "reserve-stock".to_string())));
+        assert!(headers.contains(&(ATTEMPT_HEADER, "2".to_string())));
+        assert!(headers.contains(&(REQUEST_ID_HEADER, "req-9".to_string())));
+
+        let compensation = context.clone().for_compensation();
+        assert!(compensation.compensation);
+        assert_eq!(compensation.headers(), context.headers());
+    }
+
+    fn step(name: &str, max_retries: u32) -> SagaStep {
+        SagaStep {
+            name: name.to_string(),
+            service: "inventory".to_string(),
+            action: format!("{}-action", name),
+            compensation: format!("{}-undo", name),
+            timeout: Duration::from_secs(1),
+            retry_policy: Some(RetryPolicy {
+                max_retries,
+                backoff_strategy: BackoffStrategy::Fixed,
+                initial_delay: Duration::from_millis(1),
+            }),
+            payload: None,
+            acquired_locks: vec![],
+            cache_keys: vec![],
+            group: None,
+            executor: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compensation_exhausts_retries() {
+        let steps = vec![step("reserve", 2), step("charge", 2), step("ship", 2)];
+        let calls = std::sync::Mutex::new(Vec::new());
+
+        let outcome = compensate_steps("saga-1", &steps, None, |context| {
+            calls.lock().unwrap().push((
+                context.step.clone(),
+                context.attempt,
+                context.compensation,
+            ));
+            let fail = context.step == "charge";
+            async move {
+                if fail {
+                    Err(SyrosError::SagaError("payment gateway down".to_string()))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        let failure = outcome.unwrap_err();
+        assert_eq!(failure.step, "charge");
+        assert_eq!(failure.attempts, 3);
+        assert!(failure.error.contains("payment gateway down"));
+
+        // Compensation runs in reverse and stops at the failing step.
+        let calls = calls.into_inner().unwrap();
+        assert_eq!(
+            calls,
+            vec![
+                ("ship".to_string(), 1, true),
+                ("charge".to_string(), 1, true),
+                ("charge".to_string(), 2, true),
+                ("charge".to_string(), 3, true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compensation_retry_recovers() {
+        let steps = vec![step("reserve", 1)];
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        let outcome = compensate_steps("saga-1", &steps, None, |_| {
+            let attempt = attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            async move {
+                if attempt == 1 {
+                    Err(SyrosError::SagaError("transient".to_string()))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        assert!(outcome.is_ok());
+        assert_eq!(attempts.into_inner(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_compensation_retries_do_not_overflow() {
+        let steps = vec![step("reserve", u32::MAX)];
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        let outcome = compensate_steps("saga-1", &steps, None, |context| {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                if context.attempt == 1 {
+                    Err(SyrosError::SagaError("transient".to_string()))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        assert!(outcome.is_ok());
+        assert_eq!(attempts.into_inner(), 2);
+        let mut longest = step("reserve", u32::MAX);
+        if let Some(policy) = longest.retry_policy.as_mut() {
+            policy.backoff_strategy = BackoffStrategy::Exponential;
+            policy.initial_delay = Duration::MAX;
+        }
+        assert_eq!(compensation_retry_delay(&longest, u32::MAX), Duration::MAX);
+    }
+
+    #[test]
+    fn test_compensation_retry_delay_follows_backoff() {
+        let mut exponential = step("charge", 3);
+        if let Some(policy) = exponential.retry_policy.as_mut() {
+            policy.backoff_strategy = BackoffStrategy::Exponential;
+            policy.initial_delay = Duration::from_millis(100);
+        }
+        assert_eq!(
+            compensation_retry_delay(&exponential, 3),
+            Duration::from_millis(400)
+        );
+
+        let mut no_policy = step("charge", 0);
+        no_policy.retry_policy = None;
+        assert_eq!(
+            compensation_retry_delay(&no_policy, 1),
+            DEFAULT_COMPENSATION_RETRY_DELAY
+        );
+    }
+
+    #[test]
+    fn test_remaining_budget() {
+        let now = Utc::now();
+        let mut saga = Saga {
+            id: "saga-1".to_string(),
+            name: "checkout".to_string(),
+            status: SagaStatus::Running.to_string(),
+            steps: serde_json::json!([]),
+            current_step: Some(0),
+            created_at: now,
+            updated_at: now,
+            metadata: serde_json::json!({}),
+            deadline_at: None,
+            failure_reason: None,
+            step_results: vec![],
+            start_at: None,
+        };
+        assert_eq!(saga.remaining_budget(now), None);
+
+        saga.deadline_at = Some(now + chrono::Duration::milliseconds(1500));
+        assert_eq!(
+            saga.remaining_budget(now),
+            Some(Duration::from_millis(1500))
+        );
+
+        // An overdue saga has no budget left rather than a negative one.
+        assert_eq!(
+            saga.remaining_budget(now + chrono::Duration::seconds(5)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    fn request(steps: usize, max_duration: Option<Duration>) -> SagaRequest {
+        SagaRequest {
+            name: "checkout".to_string(),
+            steps: (0..steps)
+                .map(|i| step(&format!("step-{}", i), 0))
+                .collect(),
+            metadata: None,
+            max_duration,
+            start_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_saga_completes_and_publishes_transitions() {
+        let orchestrator = SagaOrchestrator::in_memory();
+        let mut updates = orchestrator.subscribe_status_updates();
+
+        let saga_id = orchestrator
+            .start_saga(request(2, None))
+            .await
+            .unwrap()
+            .saga_id;
+
+        let mut statuses = Vec::new();
+        while statuses.last().map(String::as_str) != Some("Completed") {
+            let update = tokio::time::timeout(Duration::from_secs(2), updates.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(update.saga_id, saga_id);
+            statuses.push(update.status);
+        }
+        assert_eq!(statuses, vec!["Pending", "Running", "Completed"]);
+
+        let saga = orchestrator
+            .get_saga_status(&saga_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saga.current_step, Some(1));
+        assert!(orchestrator
+            .get_saga_status("missing")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_idempotent_starts_create_one_saga() {
+        let orchestrator = SagaOrchestrator::in_memory();
+
+        let starts = join_all(
+            (0..8).map(|_| orchestrator.start_saga_idempotent("order-42", request(1, None))),
+        )
+        .await;
+        let started = starts
+            .iter()
+            .filter(|start| matches!(start, Ok(IdempotentStart::Started(_))))
+            .count();
+        assert_eq!(started, 1);
+        let saga_ids: HashSet<String> = starts
+            .into_iter()
+            .map(|start| start.unwrap().into_saga_response().saga_id)
+            .collect();
+        assert_eq!(saga_ids.len(), 1);
+        let sagas = orchestrator
+            .list_sagas(&SagaFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(sagas.len(), 1);
+
+        assert!(matches!(
+            orchestrator
+                .start_saga_idempotent("order-42", request(2, None))
+                .await,
+            Err(SyrosError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_saga_starts_when_due_or_cancels() {
+        let orchestrator = SagaOrchestrator::in_memory();
+        let mut scheduled = request(1, None);
+        scheduled.start_at = Some(Utc::now() + chrono::Duration::milliseconds(200));
+        let saga_id = orchestrator
+            .start_saga(scheduled.clone())
+            .await
+            .unwrap()
+            .saga_id;
+        let saga = orchestrator
+            .get_saga_status(&saga_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saga.status, "Scheduled");
+        assert_eq!(saga.start_at, scheduled.start_at);
+        // Not due yet, so nothing starts.
+        assert!(orchestrator.start_due_sagas().await.unwrap().is_empty());
+
+        let cancelled_id = orchestrator.start_saga(scheduled).await.unwrap().saga_id;
+        orchestrator
+            .cancel_saga(&cancelled_id, "no longer needed")
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(
+            orchestrator.start_due_sagas().await.unwrap(),
+            vec![saga_id.clone()]
+        );
+        let completed = wait_until_terminal(&orchestrator, &saga_id).await;
+        assert_eq!(completed.status, "Completed");
+        let cancelled = orchestrator
+            .get_saga_status(&cancelled_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cancelled.status, "Cancelled");
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_saga_timeout_is_claimed_once() {
+        let orchestrator = SagaOrchestrator::in_memory();
+        let saga_id = orchestrator
+            .start_saga(request(5, Some(Duration::from_millis(150))))
+            .await
+            .unwrap()
+            .saga_id;
+        // Left to the watchdog, as if it ran on an instance that went away.
+        orchestrator.shutdown().await;
+        assert_eq!(orchestrator.cancel_expired_sagas().await.unwrap(), 0);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(orchestrator.cancel_expired_sagas().await.unwrap(), 1);
+        assert_eq!(orchestrator.cancel_expired_sagas().await.unwrap(), 0);
+
+        let saga = orchestrator
+            .get_saga_status(&saga_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saga.status, "Compensated");
+        assert_eq!(saga.failure_reason.as_deref(), Some(SAGA_TIMEOUT_REASON));
+    }
+
+    /// Executor whose action attempts sleep for the given durations, then
+    /// succeed.
+    fn sleeping_executor(sleeps: Vec<Duration>) -> SagaStepHandler {
+        Arc::new(move |_step, context| {
+            let sleep = match context.compensation {
+                true => Duration::ZERO,
+                false => sleeps
+                    .get(context.attempt as usize - 1)
+                    .copied()
+                    .unwrap_or_default(),
+            };
+            async move {
+                tokio::time::sleep(sleep).await;
+                Ok(())
+            }
+            .boxed()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_step_timeout_is_retried() {
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(sleeping_executor(vec![
+                Duration::from_secs(5),
+                Duration::ZERO,
+            ]));
+        let mut request = request(1, None);
+        request.steps[0].timeout = Duration::from_millis(50);
+        request.steps[0].retry_policy.as_mut().unwrap().max_retries = 1;
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "Completed");
+        let attempts = saga.step_attempts("step-0");
+        assert_eq!(attempts[0].error.as_deref(), Some(STEP_TIMEOUT_ERROR));
+        assert!(attempts[1].error.is_none());
+        assert!(attempts[1].at - attempts[0].at < chrono::Duration::seconds(1));
+    }
+
+    #[tokio::test]
+    async fn test_step_timeout_compensates_once_retries_are_exhausted() {
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_step_executor(sleeping_executor(vec![Duration::from_secs(5)]));
+        let mut request = request(1, None);
+        request.steps[0].timeout = Duration::from_millis(50);
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "Compensated");
+        assert!(saga.failure_reason.is_none());
+        let execution = &saga.step_results[0];
+        assert_eq!(execution.status, StepStatus::Compensated);
+        assert_eq!(execution.error.as_deref(), Some(STEP_TIMEOUT_ERROR));
+        assert_eq!(execution.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_saga_deadline_stops_its_execution() {
+        // Without a step timeout, the first step would hang until the
+        // watchdog, which is not running here, cancels the saga.
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_step_executor(sleeping_executor(vec![Duration::from_secs(5)]));
+        let mut request = request(3, Some(Duration::from_millis(100)));
+        request.steps[0].timeout = Duration::ZERO;
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "Compensated");
+        assert_eq!(saga.failure_reason.as_deref(), Some(SAGA_TIMEOUT_REASON));
+        assert_eq!(
+            saga.step_results[0].error.as_deref(),
+            Some(STEP_TIMEOUT_ERROR)
+        );
+        let statuses: Vec<_> = saga.step_results.iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                StepStatus::Compensated,
+                StepStatus::Pending,
+                StepStatus::Pending
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compensation_releases_declared_step_resources() {
+        use crate::core::cache_manager::{CacheRequest, CacheSetMode};
+        use crate::core::lock_manager::LockRequest;
+
+        let locks = LockManager::in_memory();
+        let cache = CacheManager::new();
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_lock_manager(locks.clone())
+            .with_cache_manager(cache.clone());
+
+        for (key, owner) in [("stock:sku-1", "checkout"), ("stock:sku-2", "someone-else")] {
+            let acquired = locks
+                .acquire_lock(LockRequest {
+                    key: key.to_string(),
+                    ttl: Duration::from_secs(60),
+                    metadata: None,
+                    owner: owner.to_string(),
+                    wait_timeout: None,
+                    priority: Default::default(),
+                    created_by: None,
+                    reentrant: false,
+                    session_id: None,
+                })
+                .await
+                .unwrap();
+            assert!(acquired.success);
+        }
+        cache
+            .set(CacheRequest {
+                key: "reservation:sku-1".to_string(),
+                value: serde_json::json!({ "qty": 1 }),
+                ttl: None,
+                tags: vec![],
+                mode: CacheSetMode::Upsert,
+                created_by: None,
+                cache_negative: false,
+            })
+            .await
+            .unwrap();
+
+        let mut request = request(5, Some(Duration::from_millis(150)));
+        request.steps[0].acquired_locks = ["stock:sku-1", "stock:sku-2", "stock:never-held"]
+            .into_iter()
+            .map(|key| StepLock {
+                key: key.to_string(),
+                owner: Some("checkout".to_string()),
+            })
+            .collect();
+        request.steps[0].cache_keys = vec!["reservation:sku-1".to_string()];
+        let saga_id = orchestrator.start_saga(request).await.unwrap().saga_id;
+
+        // Force compensation by letting the saga exceed its budget.
+        let saga = wait_until_terminal(&orchestrator, &saga_id).await;
+        assert_eq!(saga.status, "Compensated");
+        assert_eq!(saga.failure_reason.as_deref(), Some(SAGA_TIMEOUT_REASON));
+        assert!(locks
+            .get_lock_status("stock:sku-1")
+            .await
+            .unwrap()
+            .is_none());
+        // Locks held by another owner are left alone.
+        assert!(locks
+            .get_lock_status("stock:sku-2")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(cache
+            .get_entry("reservation:sku-1")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_compensation_still_releases_declared_step_resources() {
+        use crate::core::lock_manager::LockRequest;
+
+        let locks = LockManager::in_memory();
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_lock_manager(locks.clone())
+            .with_step_executor(Arc::new(|step, context| {
+                let failed = matches!(
+                    (step.name.as_str(), context.compensation),
+                    ("step-2", false) | ("step-1", true)
+                );
+                async move {
+                    if failed {
+                        Err(SyrosError::SagaError(format!("{} failed", step.name)))
+                    } else {
+                        Ok(())
+                    }
+                }
+                .boxed()
+            }));
+
+        let mut request = request(3, None);
+        request.steps = vec![step("step-0", 0), step("step-1", 0), step("step-2", 0)];
+        for (index, key) in ["stock:sku-0", "stock:sku-1", "stock:sku-2"]
+            .into_iter()
+            .enumerate()
+        {
+            let acquired = locks
+                .acquire_lock(LockRequest {
+                    key: key.to_string(),
+                    ttl: Duration::from_secs(60),
+                    metadata: None,
+                    owner: "checkout".to_string(),
+                    wait_timeout: None,
+                    priority: Default::default(),
+                    created_by: None,
+                    reentrant: false,
+                    session_id: None,
+                })
+                .await
+                .unwrap();
+            assert!(acquired.success);
+            request.steps[index].acquired_locks = vec![StepLock {
+                key: key.to_string(),
+                owner: Some("checkout".to_string()),
+            }];
+        }
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "CompensationFailed");
+        // Step 0 is never compensated, yet its lock goes with the others.
+        for key in ["stock:sku-0", "stock:sku-1", "stock:sku-2"] {
+            assert!(
+                locks.get_lock_status(key).await.unwrap().is_none(),
+                "{}",
+                key
+            );
+        }
+    }
+
+    fn saga_lock(key: &str, owner: &str, ttl: Duration) -> SagaLock {
+        SagaLock {
+            key: key.to_string(),
+            ttl,
+            owner: owner.to_string(),
+            wait_timeout: None,
+            created_by: None,
+        }
+    }
+
+    /// Waits for the lock on `key` to be released.
+    async fn wait_for_release(locks: &LockManager, key: &str) {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while locks.get_lock_status(key).await.unwrap().is_some() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("saga lock was not released");
+    }
+
+    #[tokio::test]
+    async fn test_saga_lock_is_extended_while_running_then_released() {
+        let locks = LockManager::in_memory();
+        let orchestrator = SagaOrchestrator::in_memory().with_lock_manager(locks.clone());
+
+        // Eight simulated steps take ~800ms, well past the lock's TTL.
+        let started = orchestrator
+            .start_saga_with_lock(
+                saga_lock("order:1", "checkout", Duration::from_millis(300)),
+                request(8, None),
+            )
+            .await
+            .unwrap();
+        let LockedSagaStart::Started { saga, lock_id } = started else {
+            panic!("expected the saga to start: {:?}", started);
+        };
+        let metadata = orchestrator
+            .get_saga_status(&saga.saga_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .metadata;
+        assert_eq!(metadata[LOCK_KEY_METADATA_KEY], "order:1");
+        assert_eq!(metadata[LOCK_ID_METADATA_KEY], lock_id.as_str());
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let held = locks.get_lock_status("order:1").await.unwrap().unwrap();
+        assert_eq!(held.id, lock_id);
+
+        wait_for_release(&locks, "order:1").await;
+        let saga = orchestrator
+            .get_saga_status(&saga.saga_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saga.status, "Completed");
+    }
+
+    #[tokio::test]
+    async fn test_completed_saga_leaves_no_tracked_tasks() {
+        let tasks = TaskTracker::new();
+        let locks = LockManager::in_memory();
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_lock_manager(locks.clone())
+            .with_task_tracker(tasks.clone());
+
+        let started = orchestrator
+            .start_saga_with_lock(
+                saga_lock("order:5", "checkout", Duration::from_secs(30)),
+                request(2, None),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(started, LockedSagaStart::Started { .. }));
+        assert_eq!(tasks.live_named("saga"), 1);
+        assert_eq!(tasks.live_named("saga_lock"), 1);
+
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while tasks.live() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("saga tasks outlived the saga");
+        let inventory = tasks.inventory();
+        assert_eq!(inventory.spawned, 2);
+        assert!(inventory.tasks.iter().all(|task| task.live == 0));
+        assert!(locks.get_lock_status("order:5").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_saga_lock_conflict_creates_no_saga() {
+        let locks = LockManager::in_memory();
+        let orchestrator = SagaOrchestrator::in_memory().with_lock_manager(locks.clone());
+        let first = orchestrator
+            .start_saga_with_lock(
+                saga_lock("order:2", "first", Duration::from_secs(30)),
+                request(20, None),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(first, LockedSagaStart::Started { .. }));
+
+        let second = orchestrator
+            .start_saga_with_lock(
+                saga_lock("order:2", "second", Duration::from_secs(30)),
+                request(1, None),
+            )
+            .await
+            .unwrap();
+        let LockedSagaStart::Conflict { holder, .. } = second else {
+            panic!("expected a lock conflict: {:?}", second);
+        };
+        assert_eq!(holder.unwrap().owner, "first");
+        let SagaBackend::Memory(sagas) = &orchestrator.backend else {
+            unreachable!();
+        };
+        assert_eq!(sagas.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_saga_lock_released_when_saga_is_compensated() {
+        let locks = LockManager::in_memory();
+        let orchestrator = SagaOrchestrator::in_memory().with_lock_manager(locks.clone());
+        let started = orchestrator
+            .start_saga_with_lock(
+                saga_lock("order:3", "checkout", Duration::from_secs(30)),
+                request(5, Some(Duration::from_millis(150))),
+            )
+            .await
+            .unwrap();
+        let LockedSagaStart::Started { saga, .. } = started else {
+            panic!("saga lock not acquired");
+        };
+
+        let saga = wait_until_terminal(&orchestrator, &saga.saga_id).await;
+        assert_eq!(saga.status, "Compensated");
+        wait_for_release(&locks, "order:3").await;
+    }
+
+    #[tokio::test]
+    async fn test_panicking_step_fails_the_saga() {
+        let dead_letters = DeadLetterQueue::new();
+        let mut notifications = dead_letters.subscribe();
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_dead_letter_queue(dead_letters.clone())
+            .with_step_executor(Arc::new(|step, _context| {
+                async move {
+                    if step.name == "step-1" {
+                        panic!("payment service client exploded");
+                    }
+                    Ok(())
+                }
+                .boxed()
+            }));
+        let mut updates = orchestrator.subscribe_status_updates();
+
+        let saga_id = orchestrator
+            .start_saga(request(3, None))
+            .await
+            .unwrap()
+            .saga_id;
+        loop {
+            let update = tokio::time::timeout(Duration::from_secs(2), updates.recv())
+                .await
+                .expect("saga hung after its step panicked")
+                .unwrap();
+            if update.status == "Failed" {
+                break;
+            }
+        }
+
+        let saga = orchestrator
+            .get_saga_status(&saga_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saga.current_step, Some(1));
+        assert_eq!(
+            saga.failure_reason.as_deref(),
+            Some("panicked: payment service client exploded")
+        );
+
+        let notification = tokio::time::timeout(Duration::from_secs(2), notifications.recv())
+            .await
+            .expect("failed saga never escalated")
+            .unwrap();
+        assert_eq!(notification.kind, "saga.failed");
+        let entry = dead_letters.get(&saga_id).await.unwrap();
+        assert_eq!(entry.failed_step.as_deref(), Some("step-1"));
+        assert_eq!(entry.reason, "panicked: payment service client exploded");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(orchestrator.running.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retry_resumes_a_failed_saga_from_its_failed_step() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let down = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let orchestrator = SagaOrchestrator::in_memory().with_step_executor({
+            let (calls, down) = (calls.clone(), down.clone());
+            Arc::new(move |step, _context| {
+                calls.lock().unwrap().push(step.name.clone());
+                let down = down.load(std::sync::atomic::Ordering::SeqCst);
+                async move {
+                    if down && step.name == "step-1" {
+                        panic!("payment service unreachable");
+                    }
+                    Ok(())
+                }
+                .boxed()
+            })
+        });
+        let saga = run_to_end(&orchestrator, request(3, None)).await;
+        assert_eq!(saga.status, "Failed");
+        assert!(matches!(
+            orchestrator.retry_saga(&saga.id, Some(2)).await,
+            Err(SyrosError::SagaError(_))
+        ));
+
+        down.store(false, std::sync::atomic::Ordering::SeqCst);
+        orchestrator.retry_saga(&saga.id, None).await.unwrap();
+        let saga = wait_until_terminal(&orchestrator, &saga.id).await;
+
+        assert_eq!(saga.status, "Completed");
+        assert_eq!(saga.failure_reason, None);
+        assert_eq!(saga.retry_count(), 1);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["step-0", "step-1", "step-1", "step-2"]
+        );
+        assert!(matches!(
+            orchestrator.retry_saga(&saga.id, None).await,
+            Err(SyrosError::Conflict(_))
+        ));
+        assert!(matches!(
+            orchestrator.retry_saga("missing", None).await,
+            Err(SyrosError::NotFound(_))
+        ));
+    }
+
+    /// Starts `request` and waits until the saga ends.
+    async fn run_to_end(orchestrator: &SagaOrchestrator, request: SagaRequest) -> Saga {
+        let mut updates = orchestrator.subscribe_status_updates();
+        let saga_id = orchestrator.start_saga(request).await.unwrap().saga_id;
+        loop {
+            let update = tokio::time::timeout(Duration::from_secs(2), updates.recv())
+                .await
+                .expect("saga never ended")
+                .unwrap();
+            if update
+                .status
+                .parse()
+                .is_ok_and(|s: SagaStatus| s.is_terminal())
+            {
+                break;
+            }
+        }
+        orchestrator
+            .get_saga_status(&saga_id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Waits until `saga_id` ends and returns its final state.
+    async fn wait_until_terminal(orchestrator: &SagaOrchestrator, saga_id: &str) -> Saga {
+        for _ in 0..200 {
+            let saga = orchestrator
+                .get_saga_status(saga_id)
+                .await
+                .unwrap()
+                .unwrap();
+            if saga.is_terminal() {
+                return saga;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("saga {} never ended", saga_id);
+    }
+
+    /// Executor whose actions fail their first `failures` attempts.
+    fn failing_executor(failures: u32) -> SagaStepHandler {
+        Arc::new(move |_step, context| {
+            async move {
+                if !context.compensation && context.attempt <= failures {
+                    Err(SyrosError::SagaError(format!(
+                        "attempt {} failed",
+                        context.attempt
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            .boxed()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_step_retries_with_backoff_until_it_succeeds() {
+        let orchestrator = SagaOrchestrator::in_memory().with_step_executor(failing_executor(2));
+        let mut request = request(1, None);
+        request.steps[0].retry_policy = Some(RetryPolicy {
+            max_retries: 3,
+            backoff_strategy: BackoffStrategy::Exponential,
+            initial_delay: Duration::from_millis(20),
+        });
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "Completed");
+        let attempts = saga.step_attempts("step-0");
+        let numbers: Vec<_> = attempts.iter().map(|a| a.attempt).collect();
+        assert_eq!(numbers, [1, 2, 3]);
+        assert_eq!(
+            attempts[0].error.as_deref(),
+            Some("Saga error: attempt 1 failed")
+        );
+        assert!(attempts[2].error.is_none());
+        let execution = &saga.step_results[0];
+        assert_eq!(execution.status, StepStatus::Completed);
+        assert_eq!((execution.attempts, execution.error.as_ref()), (3, None));
+        assert!(execution.started_at <= execution.completed_at);
+
+        // Exponential backoff waits 20ms, then 40ms.
+        let first_wait = attempts[1].at - attempts[0].at;
+        let second_wait = attempts[2].at - attempts[1].at;
+        assert!(first_wait >= chrono::Duration::milliseconds(20));
+        assert!(second_wait >= chrono::Duration::milliseconds(40));
+    }
+
+    #[tokio::test]
+    async fn test_step_compensates_once_retries_are_exhausted() {
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(failing_executor(u32::MAX));
+        let mut request = request(1, None);
+        request.steps[0] = step("step-0", 2);
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "Compensated");
+        let attempts = saga.step_attempts("step-0");
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts.iter().all(|a| a.error.is_some()));
+        let execution = &saga.step_results[0];
+        assert_eq!(execution.status, StepStatus::Compensated);
+        assert_eq!(execution.attempts, 3);
+        assert_eq!(
+            execution.error.as_deref(),
+            Some("Saga error: attempt 3 failed")
+        );
+        assert!(execution.completed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_step_is_not_retried_past_the_deadline() {
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(failing_executor(u32::MAX));
+        let mut request = request(1, Some(Duration::from_secs(60)));
+        request.steps[0].retry_policy = Some(RetryPolicy {
+            max_retries: 3,
+            backoff_strategy: BackoffStrategy::Exponential,
+            initial_delay: Duration::MAX,
+        });
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "Compensated");
+        assert_eq!(saga.step_attempts("step-0").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_compensation_outcomes_are_recorded_per_step() {
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(Arc::new(|step, context| {
+                let failed = match (step.name.as_str(), context.compensation) {
+                    ("step-1", false) => true,
+                    ("step-1", true) => context.attempt == 1,
+                    ("step-0", true) => true,
+                    _ => false,
+                };
+                async move {
+                    if failed {
+                        Err(SyrosError::SagaError(format!("{} failed", step.name)))
+                    } else {
+                        Ok(())
+                    }
+                }
+                .boxed()
+            }));
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(Metrics::new().unwrap());
+        #[cfg(feature = "metrics")]
+        let orchestrator = orchestrator.with_metrics(metrics.clone());
+        let mut request = request(2, None);
+        request.steps = vec![step("step-0", 1), step("step-1", 1)];
+
+        let saga = run_to_end(&orchestrator, request).await;
+        assert_eq!(saga.status, "CompensationFailed");
+        let compensated = &saga.step_results[1];
+        assert_eq!(compensated.status, StepStatus::Compensated);
+        assert_eq!(compensated.compensation_attempts, 2);
+        assert!(compensated.compensated_at.is_some());
+        let failed = &saga.step_results[0];
+        assert_eq!(failed.status, StepStatus::CompensationFailed);
+        assert_eq!(failed.compensation_attempts, 2);
+        assert_eq!(failed.error.as_deref(), Some("Saga error: step-0 failed"));
+
+        #[cfg(feature = "metrics")]
+        {
+            let outcomes = &metrics.saga_compensations_total;
+            assert_eq!(outcomes.with_label_values(&["succeeded"]).get(), 1.0);
+            assert_eq!(outcomes.with_label_values(&["failed"]).get(), 1.0);
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_saga_outcomes_and_durations_are_recorded() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let orchestrator = SagaOrchestrator::in_memory()
+            .with_metrics(metrics.clone())
+            .with_step_executor(Arc::new(|step, context| {
+                let failed = step.name == "step-1" && !context.compensation;
+                async move {
+                    if failed {
+                        Err(SyrosError::SagaError(format!("{} failed", step.name)))
+                    } else {
+                        Ok(())
+                    }
+                }
+                .boxed()
+            }));
+
+        let completed = run_to_end(&orchestrator, request(1, None)).await;
+        assert_eq!(completed.status, "Completed");
+        let compensated = run_to_end(&orchestrator, request(2, None)).await;
+        assert_eq!(compensated.status, "Compensated");
+
+        assert_eq!(metrics.sagas_started_total.get(), 2.0);
+        assert_eq!(metrics.sagas_completed_total.get(), 1.0);
+        assert_eq!(metrics.sagas_compensated_total.get(), 1.0);
+        assert_eq!(metrics.sagas_failed_total.get(), 0.0);
+        assert_eq!(metrics.active_sagas.get(), 0.0);
+        assert_eq!(metrics.saga_execution_duration.get_sample_count(), 2);
+        let step_duration = |name: &str| {
+            metrics
+                .saga_step_duration
+                .with_label_values(&[name])
+                .get_sample_count()
+        };
+        assert_eq!(step_duration("step-0"), 2);
+        assert_eq!(step_duration("step-1"), 1);
+    }
+
+    #[test]
+    fn test_retry_delay_grows_with_the_backoff() {
+        let policy = |backoff_strategy| RetryPolicy {
+            max_retries: 5,
+            backoff_strategy,
+            initial_delay: Duration::from_millis(100),
+        };
+        let delays = |policy: RetryPolicy| (1..=4).map(|a| policy.delay(a)).collect::<Vec<_>>();
+
+        assert_eq!(
+            delays(policy(BackoffStrategy::Exponential)),
+            [100, 200, 400, 800].map(Duration::from_millis)
+        );
+        assert_eq!(
+            delays(policy(BackoffStrategy::Linear)),
+            [100, 200, 300, 400].map(Duration::from_millis)
+        );
+        assert_eq!(
+            delays(policy(BackoffStrategy::Fixed)),
+            [100; 4].map(Duration::from_millis)
+        );
+
+        // Delays too long to represent saturate instead of overflowing.
+        let longest = |backoff_strategy| RetryPolicy {
+            max_retries: u32::MAX,
+            backoff_strategy,
+            initial_delay: Duration::MAX,
+        };
+        assert_eq!(longest(BackoffStrategy::Fixed).max_attempts(), u32::MAX);
+        assert_eq!(
+            longest(BackoffStrategy::Linear).delay(u32::MAX),
+            Duration::MAX
+        );
+        assert_eq!(
+            longest(BackoffStrategy::Exponential).delay(u32::MAX),
+            Duration::MAX
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_sagas_filters_and_pages() {
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(Arc::new(|step, context| {
+                let fail = step.service == "payments" && !context.compensation;
+                async move {
+                    if fail {
+                        Err(SyrosError::SagaError("card declined".to_string()))
+                    } else {
+                        Ok(())
+                    }
+                }
+                .boxed()
+            }));
+        let named = |name: &str, service: &str| {
+            let mut request = request(1, None);
+            request.name = name.to_string();
+            request.steps[0] = step("step-0", 0);
+            request.steps[0].service = service.to_string();
+            request
+        };
+        let checkout = run_to_end(&orchestrator, named("checkout", "inventory")).await;
+        let express = run_to_end(&orchestrator, named("checkout-express", "payments")).await;
+        let restock = run_to_end(&orchestrator, named("restock", "inventory")).await;
+        let ids =
+            |sagas: Vec<SagaSummary>| sagas.into_iter().map(|s| s.saga_id).collect::<Vec<_>>();
+
+        let compensated = SagaFilter {
+            status: SagaStatus::from_name("compensated"),
+            ..SagaFilter::default()
+        };
+        let listed = orchestrator.list_sagas(&compensated).await.unwrap();
+        assert_eq!(listed, vec![SagaSummary::from_saga(&express)]);
+        assert_eq!(listed[0].completed_at, Some(express.updated_at));
+        assert_eq!(listed[0].current_step, Some(0));
+
+        let by_prefix = SagaFilter {
+            name_prefix: Some("checkout".to_string()),
+            ..SagaFilter::default()
+        };
+        assert_eq!(
+            ids(orchestrator.list_sagas(&by_prefix).await.unwrap()),
+            [checkout.id.clone(), express.id.clone()]
+        );
+        let after_checkout = SagaFilter {
+            created_after: Some(checkout.created_at),
+            ..SagaFilter::default()
+        };
+        assert_eq!(
+            ids(orchestrator.list_sagas(&after_checkout).await.unwrap()),
+            [express.id.clone(), restock.id.clone()]
+        );
+        let second_page = SagaFilter {
+            limit: Some(1),
+            offset: 1,
+            ..SagaFilter::default()
+        };
+        assert_eq!(
+            ids(orchestrator.list_sagas(&second_page).await.unwrap()),
+            std::slice::from_ref(&express.id)
+        );
+        assert!(orchestrator
+            .list_active_sagas(None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(SagaStatus::from_name("bogus"), None);
+    }
+
+    /// Executor recording the steps it is called for, whose calls to `hang`
+    /// never return.
+    fn recording_executor(
+        calls: Arc<std::sync::Mutex<Vec<(String, bool)>>>,
+        hang: Option<(&'static str, bool)>,
+        fail: Option<&'static str>,
+    ) -> SagaStepHandler {
+        Arc::new(move |step, context| {
+            calls
+                .lock()
+                .unwrap()
+                .push((step.name.clone(), context.compensation));
+            if hang == Some((step.name.as_str(), context.compensation)) {
+                return futures::future::pending().boxed();
+            }
+            let fail = fail == Some(step.name.as_str()) && !context.compensation;
+            async move {
+                if fail {
+                    Err(SyrosError::SagaError("step failed".to_string()))
+                } else {
+                    Ok(())
+                }
+            }
+            .boxed()
+        })
+    }
+
+    /// Waits until the execution of step `index` is recorded as `status`.
+    async fn wait_for_step(
+        orchestrator: &SagaOrchestrator,
+        saga_id: &str,
+        index: usize,
+        status: StepStatus,
+    ) {
+        for _ in 0..200 {
+            let saga = orchestrator
+                .get_saga_status(saga_id)
+                .await
+                .unwrap()
+                .unwrap();
+            if saga.step_results[index].status == status {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("step {} never became {}", index, status);
+    }
+
+    #[tokio::test]
+    async fn test_recovered_saga_continues_after_its_completed_steps() {
+        let before = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let crashed = SagaOrchestrator::in_memory().with_step_executor(recording_executor(
+            before.clone(),
+            Some(("step-1", false)),
+            None,
+        ));
+        let saga_id = crashed.start_saga(request(3, None)).await.unwrap().saga_id;
+        wait_for_step(&crashed, &saga_id, 1, StepStatus::Running).await;
+        crashed.shutdown().await;
+
+        // A new instance over the same storage picks the saga up.
+        let after = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let restarted = SagaOrchestrator::with_backend(crashed.backend.clone())
+            .with_step_executor(recording_executor(after.clone(), None, None));
+        let mut updates = restarted.subscribe_status_updates();
+        assert_eq!(
+            restarted.recover_pending_sagas().await.unwrap(),
+            std::slice::from_ref(&saga_id)
+        );
+        assert!(restarted.recover_pending_sagas().await.unwrap().is_empty());
+        let update = tokio::time::timeout(Duration::from_secs(2), updates.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.status, "Completed");
+
+        let names = |calls: &std::sync::Mutex<Vec<(String, bool)>>| {
+            calls
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(step, _)| step.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&before), ["step-0", "step-1"]);
+        assert_eq!(names(&after), ["step-1", "step-2"]);
+        let saga = restarted.get_saga_status(&saga_id).await.unwrap().unwrap();
+        assert!(saga
+            .step_results
+            .iter()
+            .all(|execution| execution.status == StepStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_recovered_compensation_skips_compensated_steps() {
+        let before = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let crashed = SagaOrchestrator::in_memory().with_step_executor(recording_executor(
+            before.clone(),
+            Some(("step-0", true)),
+            Some("step-2"),
+        ));
+        let saga_id = crashed.start_saga(request(3, None)).await.unwrap().saga_id;
+        wait_for_step(&crashed, &saga_id, 1, StepStatus::Compensated).await;
+        crashed.shutdown().await;
+
+        let after = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let restarted = SagaOrchestrator::with_backend(crashed.backend.clone())
+            .with_step_executor(recording_executor(after.clone(), None, None));
+        assert_eq!(
+            restarted.recover_pending_sagas().await.unwrap(),
+            std::slice::from_ref(&saga_id)
+        );
+        let saga = wait_until_terminal(&restarted, &saga_id).await;
+
+        assert_eq!(saga.status, "Compensated");
+        assert_eq!(*after.lock().unwrap(), [("step-0".to_string(), true)]);
+        let statuses: Vec<_> = saga.step_results.iter().map(|e| e.status).collect();
+        assert_eq!(statuses, [StepStatus::Compensated; 3]);
+    }
+
+    /// Request whose first two steps form the parallel group `prepare`.
+    fn parallel_request() -> SagaRequest {
+        let mut request = request(3, None);
+        for step in &mut request.steps[..2] {
+            step.group = Some("prepare".to_string());
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_parallel_steps_run_concurrently() {
+        // Each branch waits for the other, so sequential steps would time out.
+        let both_started = Arc::new(tokio::sync::Barrier::new(2));
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(Arc::new(move |step, context| {
+                let both_started = both_started.clone();
+                async move {
+                    if !context.compensation && step.group.is_some() {
+                        both_started.wait().await;
+                    }
+                    Ok(())
+                }
+                .boxed()
+            }));
+
+        let saga_id = orchestrator
+            .start_saga(parallel_request())
+            .await
+            .unwrap()
+            .saga_id;
+        let saga = wait_until_terminal(&orchestrator, &saga_id).await;
+
+        assert_eq!(saga.status, "Completed");
+        assert!(saga
+            .step_results
+            .iter()
+            .all(|execution| execution.status == StepStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_failed_parallel_step_compensates_every_branch() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(Arc::new(move |step, context| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((step.name.clone(), context.compensation));
+                async move {
+                    match (step.name.as_str(), context.compensation) {
+                        ("step-0", false) => Err(SyrosError::SagaError("step failed".to_string())),
+                        // Still running when its sibling fails
+                        ("step-1", false) => {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok(())
+                        }
+                        _ => Ok(()),
+                    }
+                }
+                .boxed()
+            }));
+
+        let saga_id = orchestrator
+            .start_saga(parallel_request())
+            .await
+            .unwrap()
+            .saga_id;
+        let saga = wait_until_terminal(&orchestrator, &saga_id).await;
+
+        assert_eq!(saga.status, "Compensated");
+        let statuses: Vec<_> = saga.step_results.iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                StepStatus::Compensated,
+                StepStatus::Compensated,
+                StepStatus::Pending
+            ]
+        );
+        let calls = calls.lock().unwrap();
+        assert!(!calls.contains(&("step-2".to_string(), false)));
+        assert!(calls.contains(&("step-0".to_string(), true)));
+        assert!(calls.contains(&("step-1".to_string(), true)));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_aborts_running_sagas() {
+        let orchestrator =
+            SagaOrchestrator::in_memory().with_step_executor(Arc::new(|_step, _context| {
+                futures::future::pending().boxed()
+            }));
+        for _ in 0..3 {
+            orchestrator.start_saga(request(1, None)).await.unwrap();
+        }
+        assert_eq!(orchestrator.running.lock().unwrap().len(), 3);
+
+        tokio::time::timeout(Duration::from_secs(1), orchestrator.shutdown())
+            .await
+            .expect("shutdown waited on a stuck saga");
+        assert!(orchestrator.running.lock().unwrap().is_empty());
     }
 }
@@ -0,0 +1,467 @@
+//! Validation and execution plans of saga definitions.
+//!
+//! [`plan`] checks a [`SagaRequest`] without running it: step fields, retry
+//! policies, parallel groups and payload references are validated, payload metadata
+//! references are rendered, and each step's service is looked up in service
+//! discovery when it is available. Problems that prevent the saga from
+//! running are errors; services without registered instances are only
+//! warnings, since they may be registered before the saga runs.
+
+use crate::core::saga_orchestrator::{step_stages, RetryPolicy, SagaRequest, StepLock};
+use crate::core::saga_template::{self, TemplateRef};
+use crate::core::service_discovery::ServiceDiscovery;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A problem in a saga definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaValidationError {
+    /// Step the problem is in; `None` for the saga itself
+    pub step: Option<String>,
+    /// Field the problem is in, e.g. `retry_policy.backoff_strategy`
+    pub field: String,
+    pub message: String,
+}
+
+impl SagaValidationError {
+    pub fn new(step: Option<&str>, field: &str, message: impl Into<String>) -> Self {
+        Self {
+            step: step.map(|s| s.to_string()),
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A step as it would be executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedStep {
+    pub index: usize,
+    /// Stage the step runs in; steps of the same stage run concurrently
+    pub stage: usize,
+    pub group: Option<String>,
+    pub name: String,
+    /// Executor running the step; the orchestrator's default when `None`
+    pub executor: Option<String>,
+    pub service: String,
+    pub action: String,
+    pub compensation: String,
+    pub timeout_ms: u64,
+    pub retry_policy: Option<RetryPolicy>,
+    /// Payload with metadata references rendered; step output references
+    /// are resolved at execution time
+    pub payload: Option<serde_json::Value>,
+    /// Locks released and cache keys deleted when the step is compensated
+    pub acquired_locks: Vec<StepLock>,
+    pub cache_keys: Vec<String>,
+    /// Addresses of the service's registered instances; `None` when
+    /// service discovery is not available
+    pub instances: Option<Vec<String>>,
+}
+
+/// The fully expanded execution plan of a saga definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaPlan {
+    pub name: String,
+    pub steps: Vec<PlannedStep>,
+    pub max_duration_ms: Option<u64>,
+    pub metadata: HashMap<String, String>,
+    pub errors: Vec<SagaValidationError>,
+    pub warnings: Vec<String>,
+}
+
+impl SagaPlan {
+    /// Whether the definition can be started.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Validates `request` and expands it into its execution plan.
+pub async fn plan(request: &SagaRequest, discovery: Option<&ServiceDiscovery>) -> SagaPlan {
+    let metadata = request.metadata.clone().unwrap_or_default();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    if request.name.trim().is_empty() {
+        errors.push(SagaValidationError::new(None, "name", "must not be empty"));
+    }
+    if request.steps.is_empty() {
+        errors.push(SagaValidationError::new(
+            None,
+            "steps",
+            "a saga needs at least one step",
+        ));
+    }
+    if request.max_duration.is_some_and(|budget| budget.is_zero()) {
+        errors.push(SagaValidationError::new(
+            None,
+            "max_duration",
+            "must be greater than zero",
+        ));
+    }
+
+    let stages = step_stages(&request.steps);
+    // Stage of every step seen so far, by name
+    let mut earlier_steps = HashMap::new();
+    let mut earlier_groups = HashSet::new();
+    let mut steps = Vec::with_capacity(request.steps.len());
+    for (index, step) in request.steps.iter().enumerate() {
+        let name = Some(step.name.as_str());
+        let stage = stages
+            .iter()
+            .position(|stage| stage.contains(&index))
+            .unwrap_or_default();
+        let mut error = |field: &str, message: String| {
+            errors.push(SagaValidationError::new(name, field, message))
+        };
+
+        for (field, value) in [
+            ("name", &step.name),
+            ("service", &step.service),
+            ("action", &step.action),
+            ("compensation", &step.compensation),
+        ] {
+            if value.trim().is_empty() {
+                error(field, "must not be empty".to_string());
+            }
+        }
+        if earlier_steps.contains_key(step.name.as_str()) {
+            error("name", format!("duplicate step name {}", step.name));
+        }
+        if let Some(group) = &step.group {
+            if group.trim().is_empty() {
+                error("group", "must not be empty".to_string());
+            } else if stages[stage].start == index && earlier_groups.contains(group.as_str()) {
+                error(
+                    "group",
+                    format!("steps of group {} must be listed one after another", group),
+                );
+            }
+        }
+        if step
+            .executor
+            .as_ref()
+            .is_some_and(|executor| executor.trim().is_empty())
+        {
+            error("executor", "must not be empty".to_string());
+        }
+        if step.timeout.is_zero() {
+            error("timeout", "must be greater than zero".to_string());
+        }
+        if step
+            .acquired_locks
+            .iter()
+            .any(|lock| lock.key.trim().is_empty())
+        {
+            error("acquired_locks", "lock keys must not be empty".to_string());
+        }
+        if step.cache_keys.iter().any(|key| key.trim().is_empty()) {
+            error("cache_keys", "cache keys must not be empty".to_string());
+        }
+        if let Some(policy) = &step.retry_policy {
+            if policy.max_retries > RetryPolicy::MAX_RETRIES {
+                error(
+                    "retry_policy.max_retries",
+                    format!("must be at most {}", RetryPolicy::MAX_RETRIES),
+                );
+            }
+            if policy.max_retries > 0 && policy.initial_delay.is_zero() {
+                error(
+                    "retry_policy.initial_delay",
+                    "must be greater than zero when retries are allowed".to_string(),
+                );
+            }
+            if policy.initial_delay > RetryPolicy::MAX_INITIAL_DELAY {
+                error(
+                    "retry_policy.initial_delay",
+                    format!(
+                        "must be at most {}s",
+                        RetryPolicy::MAX_INITIAL_DELAY.as_secs()
+                    ),
+                );
+            }
+        }
+
+        let payload = match &step.payload {
+            Some(payload) => {
+                match saga_template::references(payload) {
+                    Ok(references) => {
+                        for reference in references {
+                            if let TemplateRef::StepOutput { step: source, .. } = reference {
+                                if !earlier_steps
+                                    .get(source.as_str())
+                                    .is_some_and(|source_stage| *source_stage < stage)
+                                {
+                                    error(
+                                        "payload",
+                                        format!(
+                                            "references step {} which does not run before it",
+                                            source
+                                        ),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => error("payload", e.to_string()),
+                }
+                match saga_template::render_metadata(payload, &metadata) {
+                    Ok(rendered) => Some(rendered),
+                    Err(e) => {
+                        error("payload", e.to_string());
+                        Some(payload.clone())
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let instances = match discovery {
+            Some(discovery) if !step.service.trim().is_empty() => {
+                let instances = discovery
+                    .discover_services(&step.service)
+                    .await
+                    .unwrap_or_default();
+                if instances.is_empty() {
+                    warnings.push(format!(
+                        "Step {}: service {} has no registered instances",
+                        step.name, step.service
+                    ));
+                }
+                Some(
+                    instances
+                        .iter()
+                        .map(|instance| format!("{}:{}", instance.address, instance.port))
+                        .collect(),
+                )
+            }
+            _ => None,
+        };
+
+        earlier_steps.insert(step.name.as_str(), stage);
+        if let Some(group) = &step.group {
+            earlier_groups.insert(group.as_str());
+        }
+        steps.push(PlannedStep {
+            index,
+            stage,
+            group: step.group.clone(),
+            name: step.name.clone(),
+            executor: step.executor.clone(),
+            service: step.service.clone(),
+            action: step.action.clone(),
+            compensation: step.compensation.clone(),
+            timeout_ms: step.timeout.as_millis() as u64,
+            retry_policy: step.retry_policy.clone(),
+            payload,
+            acquired_locks: step.acquired_locks.clone(),
+            cache_keys: step.cache_keys.clone(),
+            instances,
+        });
+    }
+
+    SagaPlan {
+        name: request.name.clone(),
+        steps,
+        max_duration_ms: request.max_duration.map(|budget| budget.as_millis() as u64),
+        metadata,
+        errors,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::saga_orchestrator::SagaStep;
+    use crate::core::service_discovery::ServiceRegistration;
+    use std::time::Duration;
+
+    fn step(name: &str, service: &str, payload: Option<serde_json::Value>) -> SagaStep {
+        SagaStep {
+            name: name.to_string(),
+            service: service.to_string(),
+            action: "run".to_string(),
+            compensation: "undo".to_string(),
+            timeout: Duration::from_secs(5),
+            retry_policy: None,
+            payload,
+            acquired_locks: vec![],
+            cache_keys: vec![],
+            group: None,
+            executor: None,
+        }
+    }
+
+    fn request(steps: Vec<SagaStep>) -> SagaRequest {
+        SagaRequest {
+            name: "checkout".to_string(),
+            steps,
+            metadata: Some(HashMap::from([("customer".to_string(), "c-1".to_string())])),
+            max_duration: None,
+            start_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_renders_payloads_and_warns_on_unresolvable_services() {
+        let discovery = ServiceDiscovery::in_memory();
+        discovery
+            .register_service(ServiceRegistration {
+                id: "inventory-1".to_string(),
+                name: "inventory".to_string(),
+                address: "10.0.0.5".to_string(),
+                port: 8080,
+                tags: vec![],
+                meta: HashMap::new(),
+                check: None,
+            })
+            .await
+            .unwrap();
+
+        let plan = plan(
+            &request(vec![
+                step(
+                    "reserve",
+                    "inventory",
+                    Some(serde_json::json!({ "customer": "{{saga.metadata.customer}}" })),
+                ),
+                step(
+                    "ship",
+                    "shipping",
+                    Some(serde_json::json!({ "reservation": "{{steps.reserve.output.id}}" })),
+                ),
+            ]),
+            Some(&discovery),
+        )
+        .await;
+
+        assert!(plan.is_valid(), "{:?}", plan.errors);
+        assert_eq!(
+            plan.steps[0].payload,
+            Some(serde_json::json!({ "customer": "c-1" }))
+        );
+        assert_eq!(
+            plan.steps[0].instances,
+            Some(vec!["10.0.0.5:8080".to_string()])
+        );
+        assert_eq!(plan.steps[1].instances, Some(vec![]));
+        assert_eq!(
+            plan.warnings,
+            ["Step ship: service shipping has no registered instances"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_reports_errors_per_step() {
+        let mut charge = step(
+            "charge",
+            "",
+            Some(serde_json::json!({ "id": "{{steps.ship.output.id}}" })),
+        );
+        charge.timeout = Duration::ZERO;
+        let plan = plan(
+            &request(vec![
+                charge,
+                step(
+                    "ship",
+                    "shipping",
+                    Some(serde_json::json!("{{saga.metadata.missing}}")),
+                ),
+            ]),
+            None,
+        )
+        .await;
+
+        assert!(!plan.is_valid());
+        let fields: Vec<_> = plan
+            .errors
+            .iter()
+            .map(|e| (e.step.as_deref().unwrap(), e.field.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("charge", "service"),
+                ("charge", "timeout"),
+                ("charge", "payload"),
+                ("ship", "payload"),
+            ]
+        );
+        assert!(plan.steps.iter().all(|step| step.instances.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_plan_bounds_retry_policies() {
+        let retried = |name: &str, max_retries, initial_delay| SagaStep {
+            retry_policy: Some(RetryPolicy {
+                max_retries,
+                backoff_strategy: crate::core::saga_orchestrator::BackoffStrategy::Exponential,
+                initial_delay,
+            }),
+            ..step(name, "inventory", None)
+        };
+        let plan = plan(
+            &request(vec![
+                retried(
+                    "bounded",
+                    RetryPolicy::MAX_RETRIES,
+                    RetryPolicy::MAX_INITIAL_DELAY,
+                ),
+                retried("retries", u32::MAX, Duration::from_millis(100)),
+                retried("delay", 3, Duration::MAX),
+            ]),
+            None,
+        )
+        .await;
+
+        let fields: Vec<_> = plan
+            .errors
+            .iter()
+            .map(|e| (e.step.as_deref().unwrap(), e.field.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("retries", "retry_policy.max_retries"),
+                ("delay", "retry_policy.initial_delay"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_checks_parallel_groups() {
+        let grouped = |name: &str, group: &str, payload: Option<serde_json::Value>| SagaStep {
+            group: Some(group.to_string()),
+            ..step(name, "inventory", payload)
+        };
+        let plan = plan(
+            &request(vec![
+                grouped("reserve", "prepare", None),
+                grouped(
+                    "charge",
+                    "prepare",
+                    Some(serde_json::json!("{{steps.reserve.output.id}}")),
+                ),
+                step(
+                    "ship",
+                    "shipping",
+                    Some(serde_json::json!("{{steps.reserve.output.id}}")),
+                ),
+                grouped("notify", "prepare", None),
+            ]),
+            None,
+        )
+        .await;
+
+        let stages: Vec<_> = plan.steps.iter().map(|step| step.stage).collect();
+        assert_eq!(stages, [0, 0, 1, 2]);
+        let fields: Vec<_> = plan
+            .errors
+            .iter()
+            .map(|e| (e.step.as_deref().unwrap(), e.field.as_str()))
+            .collect();
+        assert_eq!(fields, [("charge", "payload"), ("notify", "group")]);
+    }
+}
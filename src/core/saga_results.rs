@@ -0,0 +1,274 @@
+//! Size limits for saga step results.
+//!
+//! Step services may answer with arbitrarily large bodies. Results above the
+//! configured size are never stored verbatim: depending on the
+//! [`OversizedResultPolicy`] they are either truncated or moved to the cache,
+//! and in both cases the SHA-256 of the full body is recorded so it can still
+//! be verified against the downstream service's logs.
+
+use crate::config::SagaConfig;
+use crate::core::cache_manager::{CacheManager, CacheRequest, CacheSetMode};
+use crate::core::saga_orchestrator::StepCallContext;
+use crate::{Result, SyrosError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Default largest step result stored verbatim, in bytes.
+pub const DEFAULT_MAX_STEP_RESULT_BYTES: usize = 256 * 1024;
+
+/// Default time an offloaded step result is kept in the cache.
+pub const DEFAULT_OFFLOADED_RESULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Prefix of the cache keys holding offloaded step results.
+pub const STEP_RESULT_CACHE_PREFIX: &str = "saga-step-result:";
+
+/// What happens to a step result larger than the configured limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizedResultPolicy {
+    /// Keep the first bytes of the body, up to the limit
+    #[default]
+    Truncate,
+    /// Store the full body in the cache and keep only a reference to it
+    Offload,
+}
+
+/// Stored outcome body of a saga step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepResult {
+    /// Body as stored: complete, truncated, or empty when offloaded
+    pub body: String,
+    /// Size of the full body returned by the step service, in bytes
+    pub size_bytes: usize,
+    /// Whether `body` is only a prefix of the full body
+    pub truncated: bool,
+    /// Hex SHA-256 of the full body; set whenever it is not stored verbatim
+    pub sha256: Option<String>,
+    /// Cache key holding the full body, when offloaded
+    pub offloaded_to: Option<String>,
+    /// Tracing identifiers of the call that returned the body
+    #[serde(default)]
+    pub call: Option<StepCallContext>,
+}
+
+impl StepResult {
+    /// Whether the full body is available in `body`.
+    pub fn is_complete(&self) -> bool {
+        !self.truncated && self.offloaded_to.is_none()
+    }
+
+    /// Value at the JSON `pointer` of the result, e.g. `/reservation/id`,
+    /// for piping into a later step's payload.
+    ///
+    /// Fails when the result was truncated or offloaded, since the field
+    /// cannot be read reliably from a partial body.
+    pub fn field(&self, pointer: &str) -> Result<serde_json::Value> {
+        if !self.is_complete() {
+            let stored = if self.truncated {
+                "truncated"
+            } else {
+                "offloaded"
+            };
+            return Err(SyrosError::SagaError(format!(
+                "Cannot read {} from a step result of {} bytes that was {}; \
+                 raise sagas.max_step_result_bytes to pipe it",
+                pointer, self.size_bytes, stored
+            )));
+        }
+
+        let value: serde_json::Value = serde_json::from_str(&self.body)
+            .map_err(|e| SyrosError::SagaError(format!("Step result is not JSON: {}", e)))?;
+        value.pointer(pointer).cloned().ok_or_else(|| {
+            SyrosError::SagaError(format!("Step result has no field at {}", pointer))
+        })
+    }
+}
+
+/// Applies the step result size limit before results are stored.
+#[derive(Clone)]
+pub struct StepResultLimits {
+    max_bytes: usize,
+    policy: OversizedResultPolicy,
+    cache: Option<CacheManager>,
+    offload_ttl: Duration,
+}
+
+impl Default for StepResultLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_STEP_RESULT_BYTES,
+            policy: OversizedResultPolicy::default(),
+            cache: None,
+            offload_ttl: DEFAULT_OFFLOADED_RESULT_TTL,
+        }
+    }
+}
+
+impl StepResultLimits {
+    pub fn new(max_bytes: usize, policy: OversizedResultPolicy) -> Self {
+        Self {
+            max_bytes,
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Limits configured under `[sagas]`.
+    pub fn from_config(config: &SagaConfig) -> Self {
+        Self::new(config.max_step_result_bytes, config.oversized_step_results)
+    }
+
+    /// Cache that offloaded results are stored in; without one, oversized
+    /// results are truncated whatever the policy.
+    pub fn with_cache(mut self, cache: CacheManager) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets how long offloaded results are kept in the cache.
+    pub fn with_offload_ttl(mut self, ttl: Duration) -> Self {
+        self.offload_ttl = ttl;
+        self
+    }
+
+    /// Builds the stored result of `step` in `saga_id` from the body its
+    /// service returned.
+    pub async fn capture(&self, saga_id: &str, step: &str, body: &[u8]) -> Result<StepResult> {
+        if body.len() <= self.max_bytes {
+            return Ok(StepResult {
+                body: String::from_utf8_lossy(body).into_owned(),
+                size_bytes: body.len(),
+                truncated: false,
+                sha256: None,
+                offloaded_to: None,
+                call: None,
+            });
+        }
+
+        let sha256 = Some(sha256_hex(body));
+        if let (OversizedResultPolicy::Offload, Some(cache)) = (self.policy, &self.cache) {
+            let key = format!("{}{}:{}", STEP_RESULT_CACHE_PREFIX, saga_id, step);
+            cache
+                .set(CacheRequest {
+                    key: key.clone(),
+                    value: serde_json::Value::String(String::from_utf8_lossy(body).into_owned()),
+                    ttl: Some(self.offload_ttl),
+                    tags: vec![format!("saga:{}", saga_id)],
+                    mode: CacheSetMode::Upsert,
+                    created_by: None,
+                    cache_negative: false,
+                })
+                .await?;
+            return Ok(StepResult {
+                body: String::new(),
+                size_bytes: body.len(),
+                truncated: false,
+                sha256,
+                offloaded_to: Some(key),
+                call: None,
+            });
+        }
+
+        Ok(StepResult {
+            body: truncate_utf8(body, self.max_bytes),
+            size_bytes: body.len(),
+            truncated: true,
+            sha256,
+            offloaded_to: None,
+            call: None,
+        })
+    }
+}
+
+/// The longest prefix of `body` within `max_bytes` that does not split a
+/// UTF-8 character.
+fn truncate_utf8(body: &[u8], max_bytes: usize) -> String {
+    let prefix = &body[..max_bytes.min(body.len())];
+    match std::str::from_utf8(prefix) {
+        Ok(text) => text.to_string(),
+        Err(e) if e.error_len().is_none() => {
+            String::from_utf8_lossy(&prefix[..e.valid_up_to()]).into_owned()
+        }
+        Err(_) => String::from_utf8_lossy(prefix).into_owned(),
+    }
+}
+
+fn sha256_hex(body: &[u8]) -> String {
+    Sha256::digest(body)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_body() -> Vec<u8> {
+        let items: Vec<_> = (0..20_000)
+            .map(|i| serde_json::json!({ "sku": format!("sku-{}", i), "qty": i }))
+            .collect();
+        serde_json::to_vec(&serde_json::json!({ "reservation": { "id": "r-1" }, "items": items }))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_small_results_are_stored_verbatim() {
+        let limits = StepResultLimits::new(1024, OversizedResultPolicy::Truncate);
+        let result = limits
+            .capture("saga-1", "reserve", br#"{"reservation":{"id":"r-1"}}"#)
+            .await
+            .unwrap();
+
+        assert!(result.is_complete());
+        assert_eq!(result.sha256, None);
+        assert_eq!(result.field("/reservation/id").unwrap(), "r-1");
+        assert!(result.field("/missing").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_results_are_truncated_with_hash() {
+        let body = large_body();
+        let limits = StepResultLimits::new(4096, OversizedResultPolicy::Truncate);
+        let result = limits.capture("saga-1", "reserve", &body).await.unwrap();
+
+        assert!(result.truncated);
+        assert_eq!(result.body.len(), 4096);
+        assert!(body.starts_with(result.body.as_bytes()));
+        assert_eq!(result.size_bytes, body.len());
+        assert_eq!(result.sha256.as_deref(), Some(sha256_hex(&body).as_str()));
+
+        let error = result.field("/reservation/id").unwrap_err().to_string();
+        assert!(error.contains("truncated"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_results_are_offloaded_to_the_cache() {
+        let body = large_body();
+        let cache = CacheManager::new();
+        let limits =
+            StepResultLimits::new(4096, OversizedResultPolicy::Offload).with_cache(cache.clone());
+        let result = limits.capture("saga-1", "reserve", &body).await.unwrap();
+
+        assert!(!result.truncated);
+        assert!(result.body.is_empty());
+        assert_eq!(result.sha256.as_deref(), Some(sha256_hex(&body).as_str()));
+        let key = result.offloaded_to.clone().unwrap();
+        assert_eq!(key, "saga-step-result:saga-1:reserve");
+
+        let stored = cache.get(&key).await.unwrap().value.unwrap();
+        assert_eq!(stored.as_str().unwrap().as_bytes(), body.as_slice());
+        assert!(result
+            .field("/reservation/id")
+            .unwrap_err()
+            .to_string()
+            .contains("offloaded"));
+    }
+
+    #[test]
+    fn test_truncation_keeps_whole_characters() {
+        assert_eq!(truncate_utf8("açaí".as_bytes(), 2), "a");
+        assert_eq!(truncate_utf8("açaí".as_bytes(), 3), "aç");
+    }
+}
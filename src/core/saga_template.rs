@@ -0,0 +1,299 @@
+//! Placeholders in saga step payloads.
+//!
+//! String values in a step payload may contain `{{...}}` references:
+//!
+//! - `{{saga.metadata.<key>}}` — a value from the saga's metadata
+//! - `{{steps.<name>.output.<path>}}` — a field of an earlier step's result,
+//!   where `<path>` is a JSON pointer (`/reservation/id`) or a dotted path
+//!   (`reservation.id`)
+//!
+//! A string that is exactly one reference is replaced by the referenced
+//! JSON value; references embedded in longer strings are replaced by their
+//! text. Metadata references can be checked when a saga is planned, while
+//! step outputs are only known once the step ran, so [`render_payload`]
+//! resolves both right before a step is called.
+
+use crate::core::saga_results::StepResult;
+use crate::{Result, SyrosError};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A `{{...}}` reference in a step payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateRef {
+    /// `{{saga.metadata.<key>}}`
+    Metadata(String),
+    /// `{{steps.<name>.output.<path>}}`, with the path as a JSON pointer
+    StepOutput { step: String, pointer: String },
+}
+
+impl TemplateRef {
+    /// Parses the expression between the braces.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        if let Some(key) = expression.strip_prefix("saga.metadata.") {
+            if !key.is_empty() {
+                return Ok(TemplateRef::Metadata(key.to_string()));
+            }
+        }
+        if let Some(rest) = expression.strip_prefix("steps.") {
+            if let Some((step, path)) = rest.split_once(".output") {
+                let pointer = match path.strip_prefix('.') {
+                    Some(path) if path.starts_with('/') => path.to_string(),
+                    Some(path) if !path.is_empty() => format!("/{}", path.replace('.', "/")),
+                    None if path.is_empty() => String::new(),
+                    _ => return Err(invalid(expression)),
+                };
+                if !step.is_empty() {
+                    return Ok(TemplateRef::StepOutput {
+                        step: step.to_string(),
+                        pointer,
+                    });
+                }
+            }
+        }
+        Err(invalid(expression))
+    }
+}
+
+fn invalid(expression: &str) -> SyrosError {
+    SyrosError::SagaError(format!(
+        "Invalid reference {{{{{}}}}}: expected saga.metadata.<key> or steps.<name>.output.<path>",
+        expression
+    ))
+}
+
+/// Every reference in `value`, in document order.
+pub fn references(value: &Value) -> Result<Vec<TemplateRef>> {
+    let mut found = Vec::new();
+    render(value, &mut |reference| {
+        found.push(reference.clone());
+        Ok(None)
+    })?;
+    Ok(found)
+}
+
+/// Replaces the references `resolve` returns a value for; the others are
+/// left in place.
+pub fn render(
+    value: &Value,
+    resolve: &mut dyn FnMut(&TemplateRef) -> Result<Option<Value>>,
+) -> Result<Value> {
+    Ok(match value {
+        Value::String(text) => render_string(text, resolve)?,
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, resolve))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field)| Ok((name.clone(), render(field, resolve)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Resolves `{{saga.metadata.<key>}}` references from `metadata`, failing
+/// on missing keys, and leaves step output references in place.
+pub fn render_metadata(value: &Value, metadata: &HashMap<String, String>) -> Result<Value> {
+    render(value, &mut |reference| match reference {
+        TemplateRef::Metadata(key) => metadata
+            .get(key)
+            .map(|value| Some(Value::String(value.clone())))
+            .ok_or_else(|| SyrosError::SagaError(format!("Saga metadata has no key {}", key))),
+        TemplateRef::StepOutput { .. } => Ok(None),
+    })
+}
+
+/// Resolves every reference in `value`: metadata references from
+/// `metadata` and step output references from the recorded result of the
+/// named step, as returned by `results`.
+///
+/// Fails on missing metadata keys, on steps without a recorded result and
+/// on results lacking the referenced field.
+pub fn render_payload(
+    value: &Value,
+    metadata: &HashMap<String, String>,
+    results: &dyn Fn(&str) -> Option<StepResult>,
+) -> Result<Value> {
+    render(value, &mut |reference| match reference {
+        TemplateRef::Metadata(key) => metadata
+            .get(key)
+            .map(|value| Some(Value::String(value.clone())))
+            .ok_or_else(|| SyrosError::SagaError(format!("Saga metadata has no key {}", key))),
+        TemplateRef::StepOutput { step, pointer } => {
+            let result = results(step).ok_or_else(|| {
+                SyrosError::SagaError(format!("Step {} has no recorded result", step))
+            })?;
+            match result.field(pointer) {
+                Ok(value) => Ok(Some(value)),
+                Err(SyrosError::SagaError(message)) => Err(SyrosError::SagaError(format!(
+                    "Output of step {}: {}",
+                    step, message
+                ))),
+                Err(e) => Err(e),
+            }
+        }
+    })
+}
+
+fn render_string(
+    text: &str,
+    resolve: &mut dyn FnMut(&TemplateRef) -> Result<Option<Value>>,
+) -> Result<Value> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .ok_or_else(|| SyrosError::SagaError(format!("Unclosed reference in {:?}", text)))?;
+        let placeholder = &rest[start..end + 2];
+        let reference = TemplateRef::parse(&rest[start + 2..end])?;
+        let resolved = resolve(&reference)?;
+
+        // A string that is a single reference takes the referenced value as is
+        if placeholder.len() == text.len() {
+            return Ok(resolved.unwrap_or_else(|| Value::String(text.to_string())));
+        }
+
+        rendered.push_str(&rest[..start]);
+        match resolved {
+            Some(Value::String(value)) => rendered.push_str(&value),
+            Some(value) => rendered.push_str(&value.to_string()),
+            None => rendered.push_str(placeholder),
+        }
+        rest = &rest[end + 2..];
+    }
+
+    rendered.push_str(rest);
+    Ok(Value::String(rendered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(
+            TemplateRef::parse("saga.metadata.customer").unwrap(),
+            TemplateRef::Metadata("customer".to_string())
+        );
+        assert_eq!(
+            TemplateRef::parse(" steps.reserve.output./reservation/id ").unwrap(),
+            TemplateRef::StepOutput {
+                step: "reserve".to_string(),
+                pointer: "/reservation/id".to_string(),
+            }
+        );
+        assert_eq!(
+            TemplateRef::parse("steps.reserve.output.reservation.id").unwrap(),
+            TemplateRef::StepOutput {
+                step: "reserve".to_string(),
+                pointer: "/reservation/id".to_string(),
+            }
+        );
+        assert!(TemplateRef::parse("saga.name").is_err());
+        assert!(TemplateRef::parse("steps..output.id").is_err());
+    }
+
+    #[test]
+    fn test_render_metadata_keeps_step_outputs() {
+        let payload = json!({
+            "customer": "{{saga.metadata.customer}}",
+            "note": "order {{saga.metadata.order}} for {{saga.metadata.customer}}",
+            "items": ["{{steps.reserve.output.items}}"],
+            "quantity": 2,
+        });
+        let metadata = HashMap::from([
+            ("customer".to_string(), "c-1".to_string()),
+            ("order".to_string(), "o-9".to_string()),
+        ]);
+
+        let rendered = render_metadata(&payload, &metadata).unwrap();
+        assert_eq!(
+            rendered,
+            json!({
+                "customer": "c-1",
+                "note": "order o-9 for c-1",
+                "items": ["{{steps.reserve.output.items}}"],
+                "quantity": 2,
+            })
+        );
+        assert_eq!(references(&payload).unwrap().len(), 4);
+
+        let error = render_metadata(&payload, &HashMap::new()).unwrap_err();
+        assert!(error.to_string().contains("customer"), "{}", error);
+    }
+
+    fn result(body: Value) -> StepResult {
+        let body = body.to_string();
+        StepResult {
+            size_bytes: body.len(),
+            body,
+            truncated: false,
+            sha256: None,
+            offloaded_to: None,
+            call: None,
+        }
+    }
+
+    #[test]
+    fn test_render_payload_reads_nested_step_outputs() {
+        let reserve = result(json!({
+            "reservation": { "id": "r-7", "items": [{ "sku": "sku-1" }, { "sku": "sku-2" }] },
+        }));
+        let results = |step: &str| (step == "reserve").then(|| reserve.clone());
+        let metadata = HashMap::from([("customer".to_string(), "c-1".to_string())]);
+        let payload = json!({
+            "reservation": "{{steps.reserve.output.reservation.id}}",
+            "second_sku": "{{steps.reserve.output./reservation/items/1/sku}}",
+            "items": "{{steps.reserve.output.reservation.items}}",
+            "label": "{{saga.metadata.customer}}/{{steps.reserve.output.reservation.id}}",
+        });
+
+        assert_eq!(
+            render_payload(&payload, &metadata, &results).unwrap(),
+            json!({
+                "reservation": "r-7",
+                "second_sku": "sku-2",
+                "items": [{ "sku": "sku-1" }, { "sku": "sku-2" }],
+                "label": "c-1/r-7",
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_payload_fails_on_missing_outputs() {
+        let reserve = result(json!({ "reservation": { "id": "r-7" } }));
+        let results = |step: &str| (step == "reserve").then(|| reserve.clone());
+        let metadata = HashMap::new();
+        let render = |payload: Value| {
+            render_payload(&payload, &metadata, &results)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let missing_field = render(json!("{{steps.reserve.output.reservation.sku}}"));
+        assert!(
+            missing_field.contains("reserve") && missing_field.contains("/reservation/sku"),
+            "{}",
+            missing_field
+        );
+        let missing_step = render(json!({ "id": "{{steps.charge.output.id}}" }));
+        assert!(
+            missing_step.contains("Step charge has no recorded result"),
+            "{}",
+            missing_step
+        );
+        let missing_key = render(json!("{{saga.metadata.customer}}"));
+        assert!(missing_key.contains("customer"), "{}", missing_key);
+    }
+}
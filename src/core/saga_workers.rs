@@ -0,0 +1,684 @@
+//! Worker registry for pull-mode saga step execution.
+//!
+//! Workers register with the service they execute steps for, send periodic
+//! heartbeats, and pull pending steps as leased claims. Claims held by workers
+//! that stop heartbeating, or whose lease runs out, are put back at the front
+//! of the queue so another worker can pick them up; the `saga_worker_liveness`
+//! background task looks for them.
+//!
+//! The registry is the `worker` executor of the orchestrator: steps naming it
+//! are queued for the workers of their service with their action and rendered
+//! payload, and their attempt ends when a worker completes the claim, whose
+//! output becomes the step's result.
+
+use crate::core::saga_executors::{SagaStepExecutor, StepOutcome};
+use crate::core::saga_orchestrator::{SagaStep, StepCallContext};
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, RwLock};
+use uuid::Uuid;
+
+/// Default time without a heartbeat after which a worker is considered dead.
+pub const DEFAULT_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Default lease granted on each claimed step.
+pub const DEFAULT_CLAIM_LEASE: Duration = Duration::from_secs(300);
+
+/// Request to register a saga worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerRegistration {
+    /// Service whose steps the worker executes
+    pub service: String,
+    /// Maximum number of steps the worker holds at once
+    pub capacity: u32,
+}
+
+/// A saga step waiting to be pulled by a worker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingStep {
+    pub saga_id: String,
+    pub step: String,
+    pub service: String,
+    /// Attempt of the step the worker runs, starting at 1
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Whether the worker undoes the step rather than runs it
+    #[serde(default)]
+    pub compensation: bool,
+    /// Action to run: the step's compensation action when compensating
+    #[serde(default)]
+    pub action: String,
+    /// Body of the step, with its references resolved
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+}
+
+/// A step leased to a worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepClaim {
+    pub saga_id: String,
+    pub step: String,
+    pub service: String,
+    /// Attempt of the step the worker runs, starting at 1
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Whether the worker undoes the step rather than runs it
+    #[serde(default)]
+    pub compensation: bool,
+    /// Action to run: the step's compensation action when compensating
+    #[serde(default)]
+    pub action: String,
+    /// Body of the step, with its references resolved
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+    pub worker_id: String,
+    pub claimed_at: DateTime<Utc>,
+    pub lease_expires_at: DateTime<Utc>,
+}
+
+/// A registered worker and the claims it currently holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaWorker {
+    pub id: String,
+    pub service: String,
+    pub capacity: u32,
+    pub registered_at: DateTime<Utc>,
+    pub last_heartbeat_at: DateTime<Utc>,
+    pub claims: Vec<StepClaim>,
+}
+
+#[derive(Default)]
+struct WorkerState {
+    workers: HashMap<String, SagaWorker>,
+    pending: HashMap<String, VecDeque<PendingStep>>,
+}
+
+/// A dispatched attempt: saga ID, step name, attempt number and whether it
+/// compensates the step.
+type AttemptKey = (String, String, u32, bool);
+
+/// Outcomes awaited by the attempts dispatched to workers.
+type DispatchedSteps = std::sync::Mutex<HashMap<AttemptKey, oneshot::Sender<Result<StepOutcome>>>>;
+
+/// Tracks pull-mode workers, their heartbeats and their step claims.
+#[derive(Clone)]
+pub struct SagaWorkerRegistry {
+    state: Arc<RwLock<WorkerState>>,
+    dispatched: Arc<DispatchedSteps>,
+    heartbeat_timeout: Duration,
+    claim_lease: Duration,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl Default for SagaWorkerRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEARTBEAT_TIMEOUT, DEFAULT_CLAIM_LEASE)
+    }
+}
+
+impl SagaWorkerRegistry {
+    pub fn new(heartbeat_timeout: Duration, claim_lease: Duration) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(WorkerState::default())),
+            dispatched: Arc::default(),
+            heartbeat_timeout,
+            claim_lease,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Reports `workers_active` and `claims_reassigned_total` to `metrics`.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub async fn register(&self, registration: WorkerRegistration) -> Result<SagaWorker> {
+        if registration.service.is_empty() {
+            return Err(SyrosError::SagaError(
+                "Worker service name must not be empty".to_string(),
+            ));
+        }
+        if registration.capacity == 0 {
+            return Err(SyrosError::SagaError(
+                "Worker capacity must be at least 1".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let worker = SagaWorker {
+            id: Uuid::new_v4().to_string(),
+            service: registration.service,
+            capacity: registration.capacity,
+            registered_at: now,
+            last_heartbeat_at: now,
+            claims: Vec::new(),
+        };
+
+        let mut state = self.state.write().await;
+        state.workers.insert(worker.id.clone(), worker.clone());
+        self.report_workers(&state);
+
+        Ok(worker)
+    }
+
+    /// Records a heartbeat and renews the leases of the worker's claims.
+    pub async fn heartbeat(&self, worker_id: &str) -> Result<SagaWorker> {
+        let now = Utc::now();
+        let lease = self.lease_deadline(now);
+
+        let mut state = self.state.write().await;
+        let worker = state
+            .workers
+            .get_mut(worker_id)
+            .ok_or_else(|| not_registered(worker_id))?;
+
+        worker.last_heartbeat_at = now;
+        for claim in &mut worker.claims {
+            claim.lease_expires_at = lease;
+        }
+
+        Ok(worker.clone())
+    }
+
+    /// Queues a step for workers of `step.service` to pull, unless it is
+    /// already queued.
+    pub async fn enqueue_step(&self, step: PendingStep) {
+        let mut state = self.state.write().await;
+        let queue = state.pending.entry(step.service.clone()).or_default();
+        if !queue.contains(&step) {
+            queue.push_back(step);
+        }
+    }
+
+    /// Leases the next pending step of the worker's service.
+    ///
+    /// Returns `None` when the queue is empty or the worker is at capacity.
+    pub async fn claim_next(&self, worker_id: &str) -> Result<Option<StepClaim>> {
+        let now = Utc::now();
+        let lease = self.lease_deadline(now);
+
+        let mut state = self.state.write().await;
+        let WorkerState { workers, pending } = &mut *state;
+        let worker = workers
+            .get_mut(worker_id)
+            .ok_or_else(|| not_registered(worker_id))?;
+
+        if worker.claims.len() >= worker.capacity as usize {
+            return Ok(None);
+        }
+
+        let Some(step) = pending
+            .get_mut(&worker.service)
+            .and_then(|queue| queue.pop_front())
+        else {
+            return Ok(None);
+        };
+
+        let claim = StepClaim {
+            saga_id: step.saga_id,
+            step: step.step,
+            service: step.service,
+            attempt: step.attempt,
+            compensation: step.compensation,
+            action: step.action,
+            payload: step.payload,
+            worker_id: worker.id.clone(),
+            claimed_at: now,
+            lease_expires_at: lease,
+        };
+        worker.claims.push(claim.clone());
+
+        Ok(Some(claim))
+    }
+
+    /// Releases a finished claim, ending the attempt of the saga step it
+    /// was claimed for with `outcome`; an output becomes the step's result.
+    ///
+    /// Returns `false` if the worker no longer holds the claim. Completing
+    /// the claim of an attempt that already timed out changes nothing, even
+    /// if a later attempt of the step is running.
+    pub async fn complete_claim(
+        &self,
+        worker_id: &str,
+        claim: &ClaimedAttempt,
+        outcome: Result<StepOutcome>,
+    ) -> Result<bool> {
+        let mut state = self.state.write().await;
+        let worker = state
+            .workers
+            .get_mut(worker_id)
+            .ok_or_else(|| not_registered(worker_id))?;
+
+        let before = worker.claims.len();
+        worker.claims.retain(|held| !claim.is(held));
+        if worker.claims.len() == before {
+            return Ok(false);
+        }
+
+        let dispatched = self.dispatched.lock().unwrap().remove(&claim.key());
+        if let Some(dispatched) = dispatched {
+            // The attempt may have timed out in the meantime
+            let _ = dispatched.send(outcome);
+        }
+        Ok(true)
+    }
+
+    pub async fn list_workers(&self) -> Vec<SagaWorker> {
+        let state = self.state.read().await;
+        let mut workers: Vec<SagaWorker> = state.workers.values().cloned().collect();
+        workers.sort_by_key(|worker| worker.registered_at);
+        workers
+    }
+
+    /// Removes workers that missed their heartbeat and requeues their claims,
+    /// along with any claim whose lease has run out.
+    ///
+    /// Returns the number of claims put back for reassignment.
+    pub async fn reap(&self, now: DateTime<Utc>) -> usize {
+        let heartbeat_timeout = chrono::Duration::from_std(self.heartbeat_timeout)
+            .unwrap_or_else(|_| chrono::Duration::seconds(15));
+
+        let mut state = self.state.write().await;
+        let WorkerState { workers, pending } = &mut *state;
+
+        let mut released = Vec::new();
+        workers.retain(|_, worker| {
+            if worker.last_heartbeat_at + heartbeat_timeout < now {
+                tracing::warn!(
+                    "Saga worker {} ({}) missed its heartbeat; releasing {} claim(s)",
+                    worker.id,
+                    worker.service,
+                    worker.claims.len()
+                );
+                released.append(&mut worker.claims);
+                return false;
+            }
+
+            let (expired, live): (Vec<StepClaim>, Vec<StepClaim>) = worker
+                .claims
+                .drain(..)
+                .partition(|claim| claim.lease_expires_at <= now);
+            worker.claims = live;
+            released.extend(expired);
+            true
+        });
+
+        // Released claims go to the front so they are picked up before newer work
+        for claim in released.iter().rev() {
+            pending
+                .entry(claim.service.clone())
+                .or_default()
+                .push_front(PendingStep {
+                    saga_id: claim.saga_id.clone(),
+                    step: claim.step.clone(),
+                    service: claim.service.clone(),
+                    attempt: claim.attempt,
+                    compensation: claim.compensation,
+                    action: claim.action.clone(),
+                    payload: claim.payload.clone(),
+                });
+        }
+
+        // Drained queues hold nothing worth keeping; enqueue_step recreates them.
+        let queues = pending.len();
+        pending.retain(|_, queue| !queue.is_empty());
+        #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+        let reclaimed = (queues - pending.len()) as u64;
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_claims_reassigned(released.len() as u64);
+            metrics.increment_idle_state_reclaimed("worker_queue", reclaimed);
+        }
+        self.report_workers(&state);
+
+        released.len()
+    }
+
+    fn lease_deadline(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + chrono::Duration::from_std(self.claim_lease)
+            .unwrap_or_else(|_| chrono::Duration::seconds(300))
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn report_workers(&self, state: &WorkerState) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.set_workers_active(state.workers.len() as f64);
+        }
+    }
+
+    /// Queues the attempt of `step` in `context` for the workers of its
+    /// service and waits for one to complete it.
+    async fn dispatch(&self, step: &SagaStep, context: &StepCallContext) -> Result<StepOutcome> {
+        let key = (
+            context.saga_id.clone(),
+            step.name.clone(),
+            context.attempt,
+            context.compensation,
+        );
+        let (done, outcome) = oneshot::channel();
+        self.dispatched.lock().unwrap().insert(key.clone(), done);
+        let mut dispatched = Dispatched {
+            dispatched: &self.dispatched,
+            key,
+            outcome,
+        };
+
+        self.enqueue_step(PendingStep {
+            saga_id: context.saga_id.clone(),
+            step: step.name.clone(),
+            service: step.service.clone(),
+            attempt: context.attempt,
+            compensation: context.compensation,
+            action: match context.compensation {
+                true => step.compensation.clone(),
+                false => step.action.clone(),
+            },
+            payload: step.payload.clone(),
+        })
+        .await;
+        (&mut dispatched.outcome).await.unwrap_or_else(|_| {
+            Err(SyrosError::SagaError(format!(
+                "Step {} was dispatched again before a worker completed it",
+                step.name
+            )))
+        })
+    }
+}
+
+/// A step dispatched to workers, no longer awaited once dropped, e.g. when
+/// its attempt times out.
+struct Dispatched<'a> {
+    dispatched: &'a DispatchedSteps,
+    key: AttemptKey,
+    outcome: oneshot::Receiver<Result<StepOutcome>>,
+}
+
+impl Drop for Dispatched<'_> {
+    fn drop(&mut self) {
+        self.outcome.close();
+        let mut dispatched = self.dispatched.lock().unwrap();
+        // The same attempt may have been dispatched again
+        if dispatched
+            .get(&self.key)
+            .is_some_and(|outcome| outcome.is_closed())
+        {
+            dispatched.remove(&self.key);
+        }
+    }
+}
+
+#[async_trait]
+impl SagaStepExecutor for SagaWorkerRegistry {
+    async fn execute(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        _metadata: &HashMap<String, String>,
+    ) -> Result<StepOutcome> {
+        self.dispatch(step, context).await
+    }
+
+    async fn compensate(
+        &self,
+        step: &SagaStep,
+        context: &StepCallContext,
+        _metadata: &HashMap<String, String>,
+    ) -> Result<()> {
+        self.dispatch(step, context).await.map(|_| ())
+    }
+}
+
+/// The attempt a claim was made for, as named by the worker completing it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaimedAttempt {
+    pub saga_id: String,
+    pub step: String,
+    pub attempt: u32,
+    #[serde(default)]
+    pub compensation: bool,
+}
+
+impl ClaimedAttempt {
+    fn is(&self, claim: &StepClaim) -> bool {
+        claim.saga_id == self.saga_id
+            && claim.step == self.step
+            && claim.attempt == self.attempt
+            && claim.compensation == self.compensation
+    }
+
+    fn key(&self) -> AttemptKey {
+        (
+            self.saga_id.clone(),
+            self.step.clone(),
+            self.attempt,
+            self.compensation,
+        )
+    }
+}
+
+fn first_attempt() -> u32 {
+    1
+}
+
+fn not_registered(worker_id: &str) -> SyrosError {
+    SyrosError::SagaError(format!("Saga worker {} is not registered", worker_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(saga_id: &str, step: &str, service: &str) -> PendingStep {
+        PendingStep {
+            saga_id: saga_id.to_string(),
+            step: step.to_string(),
+            service: service.to_string(),
+            attempt: 1,
+            compensation: false,
+            action: "run".to_string(),
+            payload: None,
+        }
+    }
+
+    fn attempt(saga_id: &str, step: &str, attempt: u32) -> ClaimedAttempt {
+        ClaimedAttempt {
+            saga_id: saga_id.to_string(),
+            step: step.to_string(),
+            attempt,
+            compensation: false,
+        }
+    }
+
+    fn registration(service: &str) -> WorkerRegistration {
+        WorkerRegistration {
+            service: service.to_string(),
+            capacity: 2,
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_claim_reassigned_when_worker_misses_heartbeats() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let registry = SagaWorkerRegistry::new(Duration::from_millis(50), DEFAULT_CLAIM_LEASE)
+            .with_metrics(metrics.clone());
+
+        let worker_a = registry.register(registration("payments")).await.unwrap();
+        let worker_b = registry.register(registration("payments")).await.unwrap();
+        assert_eq!(metrics.workers_active.get(), 2.0);
+
+        registry
+            .enqueue_step(pending("saga-1", "charge", "payments"))
+            .await;
+
+        let claim = registry.claim_next(&worker_a.id).await.unwrap().unwrap();
+        assert_eq!(claim.worker_id, worker_a.id);
+        assert!(registry.claim_next(&worker_b.id).await.unwrap().is_none());
+
+        // Worker A stops heartbeating while B stays alive
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        registry.heartbeat(&worker_b.id).await.unwrap();
+
+        assert_eq!(registry.reap(Utc::now()).await, 1);
+        assert!(registry.heartbeat(&worker_a.id).await.is_err());
+        assert_eq!(metrics.workers_active.get(), 1.0);
+        assert_eq!(metrics.claims_reassigned_total.get(), 1.0);
+
+        let reassigned = registry.claim_next(&worker_b.id).await.unwrap().unwrap();
+        assert_eq!(reassigned.saga_id, "saga-1");
+        assert_eq!(reassigned.step, "charge");
+        assert_eq!(reassigned.worker_id, worker_b.id);
+
+        let workers = registry.list_workers().await;
+        assert_eq!(workers.len(), 1);
+        assert_eq!(workers[0].claims.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_claims_respect_capacity_and_lease() {
+        let registry = SagaWorkerRegistry::new(DEFAULT_HEARTBEAT_TIMEOUT, Duration::from_secs(1));
+        let worker = registry.register(registration("inventory")).await.unwrap();
+
+        for i in 0..3 {
+            registry
+                .enqueue_step(pending(&format!("saga-{}", i), "reserve", "inventory"))
+                .await;
+        }
+
+        assert!(registry.claim_next(&worker.id).await.unwrap().is_some());
+        assert!(registry.claim_next(&worker.id).await.unwrap().is_some());
+        assert!(registry.claim_next(&worker.id).await.unwrap().is_none());
+
+        assert!(registry
+            .complete_claim(
+                &worker.id,
+                &attempt("saga-0", "reserve", 1),
+                Ok(StepOutcome::default())
+            )
+            .await
+            .unwrap());
+        assert!(registry.claim_next(&worker.id).await.unwrap().is_some());
+
+        // Both remaining claims outlive their lease and go back to the queue
+        let later = Utc::now() + chrono::Duration::seconds(5);
+        assert_eq!(registry.reap(later).await, 2);
+        assert!(registry.list_workers().await[0].claims.is_empty());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_reap_reclaims_drained_queues() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let registry = SagaWorkerRegistry::default().with_metrics(metrics.clone());
+        let worker = registry.register(registration("shipping")).await.unwrap();
+        let step = |saga_id: &str| pending(saga_id, "ship", "shipping");
+
+        registry.enqueue_step(step("saga-1")).await;
+        registry.claim_next(&worker.id).await.unwrap().unwrap();
+        registry.reap(Utc::now()).await;
+        assert!(registry.state.read().await.pending.is_empty());
+        assert_eq!(
+            metrics
+                .idle_state_reclaimed_total
+                .with_label_values(&["worker_queue"])
+                .get(),
+            1.0
+        );
+
+        // A step enqueued after the sweep gets a fresh queue.
+        registry.enqueue_step(step("saga-2")).await;
+        let claim = registry.claim_next(&worker.id).await.unwrap().unwrap();
+        assert_eq!(claim.saga_id, "saga-2");
+    }
+
+    #[tokio::test]
+    async fn test_stale_attempt_does_not_complete_retry() {
+        let registry = Arc::new(SagaWorkerRegistry::default());
+        let worker = registry.register(registration("payments")).await.unwrap();
+        let step = SagaStep {
+            name: "charge".to_string(),
+            service: "payments".to_string(),
+            action: "charge_card".to_string(),
+            compensation: "refund_card".to_string(),
+            timeout: Duration::from_secs(5),
+            retry_policy: None,
+            payload: Some(serde_json::json!({ "amount": 10 })),
+            acquired_locks: Vec::new(),
+            cache_keys: Vec::new(),
+            group: None,
+            executor: Some("worker".to_string()),
+        };
+        let context = |attempt| StepCallContext::new("saga-1", "charge", attempt, None);
+
+        // The first attempt's claim outlives the attempt itself
+        registry
+            .enqueue_step(PendingStep {
+                attempt: 1,
+                ..pending("saga-1", "charge", "payments")
+            })
+            .await;
+        let stale = registry.claim_next(&worker.id).await.unwrap().unwrap();
+
+        let retry = tokio::spawn({
+            let registry = registry.clone();
+            let step = step.clone();
+            async move { registry.execute(&step, &context(2), &HashMap::new()).await }
+        });
+        let claim = loop {
+            if let Some(claim) = registry.claim_next(&worker.id).await.unwrap() {
+                break claim;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(claim.attempt, 2);
+        assert_eq!(claim.action, "charge_card");
+        assert_eq!(claim.payload, step.payload);
+
+        assert!(registry
+            .complete_claim(
+                &worker.id,
+                &attempt("saga-1", "charge", stale.attempt),
+                Ok(StepOutcome::default())
+            )
+            .await
+            .unwrap());
+        assert!(!retry.is_finished());
+
+        let output = StepOutcome {
+            output: Some(b"{\"charge\":\"ch-1\"}".to_vec()),
+        };
+        assert!(registry
+            .complete_claim(
+                &worker.id,
+                &attempt("saga-1", "charge", 2),
+                Ok(output.clone())
+            )
+            .await
+            .unwrap());
+        assert_eq!(retry.await.unwrap().unwrap().output, output.output);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_zero_capacity() {
+        let registry = SagaWorkerRegistry::default();
+        let result = registry
+            .register(WorkerRegistration {
+                service: "payments".to_string(),
+                capacity: 0,
+            })
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! Service discovery through the HTTP API of a Consul agent.
+//!
+//! Instances are registered with `PUT /v1/agent/service/register`, along
+//! with their check, which the agent then runs itself; they are removed with
+//! `PUT /v1/agent/service/deregister/:id`. Discovery reads
+//! `GET /v1/health/service/:name?passing=true`, so only instances whose
+//! checks pass are returned. Service IDs and names are percent-encoded
+//! into these paths.
+
+use crate::core::service_discovery::{
+    ServiceDiscoveryBackend, ServiceHealth, ServiceInfo, ServiceRegistration,
+};
+use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Longest wait to connect to the agent.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Longest wait for the agent to answer a request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Body of an agent service registration.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct AgentServiceRegistration<'a> {
+    #[serde(rename = "ID")]
+    id: &'a str,
+    name: &'a str,
+    address: &'a str,
+    port: u16,
+    tags: &'a [String],
+    meta: &'a HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    check: Option<AgentServiceCheck<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct AgentServiceCheck<'a> {
+    #[serde(rename = "HTTP", skip_serializing_if = "Option::is_none")]
+    http: Option<&'a str>,
+    #[serde(rename = "TCP", skip_serializing_if = "Option::is_none")]
+    tcp: Option<&'a str>,
+    interval: &'a str,
+    timeout: &'a str,
+}
+
+/// An instance as listed by the health endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServiceEntry {
+    node: Node,
+    service: AgentService,
+    #[serde(default)]
+    checks: Vec<HealthCheck>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Node {
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AgentService {
+    #[serde(rename = "ID")]
+    id: String,
+    service: String,
+    /// Empty when the instance registered without one, leaving the node's
+    /// address to be used.
+    #[serde(default)]
+    address: String,
+    port: u16,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    #[serde(default)]
+    meta: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HealthCheck {
+    status: String,
+}
+
+/// Registry kept by a Consul agent.
+pub struct ConsulBackend {
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+impl ConsulBackend {
+    /// Talks to the agent at `consul_url`, e.g. `http://localhost:8500`.
+    pub fn new(consul_url: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| {
+                SyrosError::ServiceDiscoveryError(format!("Failed to create Consul client: {}", e))
+            })?;
+        let url = reqwest::Url::parse(consul_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| {
+                SyrosError::ServiceDiscoveryError(format!("Invalid Consul URL {}", consul_url))
+            })?;
+        Ok(Self { client, url })
+    }
+
+    /// URL of the agent endpoint at `path`, followed by `segment`, if any,
+    /// percent-encoded as a single path segment.
+    fn endpoint(&self, path: &str, segment: Option<&str>) -> reqwest::Url {
+        let mut url = self.url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(path.split('/'));
+            if let Some(segment) = segment {
+                segments.push(segment);
+            }
+        }
+        url
+    }
+
+    /// Sends `request`; fails with the status and body of any non-2xx
+    /// response.
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        action: &str,
+    ) -> Result<reqwest::Response> {
+        let response = request.send().await.map_err(|e| {
+            SyrosError::ServiceDiscoveryError(format!("Consul request to {} failed: {}", action, e))
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(SyrosError::ServiceDiscoveryError(format!(
+                "Consul returned {} to {}: {}",
+                status,
+                action,
+                body.trim()
+            )));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ServiceDiscoveryBackend for ConsulBackend {
+    async fn register(&self, service: &ServiceRegistration) -> Result<()> {
+        let registration = AgentServiceRegistration {
+            id: &service.id,
+            name: &service.name,
+            address: &service.address,
+            port: service.port,
+            tags: &service.tags,
+            meta: &service.meta,
+            check: service.check.as_ref().map(|check| AgentServiceCheck {
+                http: check.http.as_deref(),
+                tcp: check.tcp.as_deref(),
+                interval: &check.interval,
+                timeout: &check.timeout,
+            }),
+        };
+        let request = self
+            .client
+            .put(self.endpoint("v1/agent/service/register", None))
+            .json(&registration);
+        self.send(request, &format!("register {}", service.id))
+            .await?;
+        Ok(())
+    }
+
+    async fn deregister(&self, service_id: &str) -> Result<()> {
+        let request = self
+            .client
+            .put(self.endpoint("v1/agent/service/deregister", Some(service_id)));
+        self.send(request, &format!("deregister {}", service_id))
+            .await?;
+        Ok(())
+    }
+
+    async fn discover(&self, service_name: &str) -> Result<Vec<ServiceInfo>> {
+        let request = self
+            .client
+            .get(self.endpoint("v1/health/service", Some(service_name)))
+            .query(&[("passing", "true")]);
+        let entries: Vec<ServiceEntry> = self
+            .send(request, &format!("discover {}", service_name))
+            .await?
+            .json()
+            .await
+            .map_err(|e| {
+                SyrosError::ServiceDiscoveryError(format!(
+                    "Invalid Consul response discovering {}: {}",
+                    service_name, e
+                ))
+            })?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let health = aggregate_health(&entry.checks);
+                let service = entry.service;
+                ServiceInfo {
+                    id: service.id,
+                    name: service.service,
+                    address: if service.address.is_empty() {
+                        entry.node.address
+                    } else {
+                        service.address
+                    },
+                    port: service.port,
+                    tags: service.tags.unwrap_or_default(),
+                    meta: service.meta.unwrap_or_default(),
+                    health,
+                    last_checked: None,
+                    failure_reason: None,
+                }
+            })
+            .collect())
+    }
+}
+
+/// The worst state among an instance's checks; passing if it has none.
+fn aggregate_health(checks: &[HealthCheck]) -> ServiceHealth {
+    let has = |status: &str| checks.iter().any(|check| check.status == status);
+    if has("critical") {
+        ServiceHealth::Critical
+    } else if has("warning") {
+        ServiceHealth::Warning
+    } else {
+        ServiceHealth::Passing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::service_discovery::ServiceCheck;
+    use axum::{
+        extract::{Path, Query, State},
+        http::StatusCode,
+        routing::{get, put},
+        Json, Router,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Requests received by the fake agent, as method and path or body.
+    type Received = Arc<Mutex<Vec<String>>>;
+
+    async fn fake_agent() -> (String, Received) {
+        let received = Received::default();
+        let app = Router::new()
+            .route(
+                "/v1/agent/service/register",
+                put(
+                    |State(received): State<Received>, Json(body): Json<serde_json::Value>| async move {
+                        received.lock().unwrap().push(body.to_string());
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .route(
+                "/v1/agent/service/deregister/:id",
+                put(
+                    |State(received): State<Received>, Path(id): Path<String>| async move {
+                        if id == "unknown" {
+                            return (StatusCode::NOT_FOUND, "Unknown service ID");
+                        }
+                        received.lock().unwrap().push(format!("deregister {}", id));
+                        (StatusCode::OK, "")
+                    },
+                ),
+            )
+            .route(
+                "/v1/health/service/:name",
+                get(
+                    |Path(name): Path<String>,
+                     Query(query): Query<HashMap<String, String>>| async move {
+                        assert_eq!(query.get("passing").map(String::as_str), Some("true"));
+                        Json(serde_json::json!([{
+                            "Node": { "Node": "node-1", "Address": "10.0.0.1" },
+                            "Service": {
+                                "ID": format!("{}-1", name),
+                                "Service": name,
+                                "Address": "",
+                                "Port": 8080,
+                                "Tags": null,
+                                "Meta": { "zone": "a" }
+                            },
+                            "Checks": [
+                                { "Status": "passing" },
+                                { "Status": "passing" }
+                            ]
+                        }]))
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("http://{}/", addr), received)
+    }
+
+    #[tokio::test]
+    async fn test_registers_discovers_and_deregisters_through_the_agent() {
+        let (url, received) = fake_agent().await;
+        let consul = ConsulBackend::new(&url).unwrap();
+
+        consul
+            .register(&ServiceRegistration {
+                id: "syros-1".to_string(),
+                name: "syros".to_string(),
+                address: "127.0.0.1".to_string(),
+                port: 8080,
+                tags: vec!["api".to_string()],
+                meta: HashMap::new(),
+                check: Some(ServiceCheck {
+                    http: Some("http://127.0.0.1:8080/health".to_string()),
+                    tcp: None,
+                    interval: "10s".to_string(),
+                    timeout: "5s".to_string(),
+                }),
+            })
+            .await
+            .unwrap();
+        let registration: serde_json::Value =
+            serde_json::from_str(&received.lock().unwrap()[0]).unwrap();
+        assert_eq!(registration["ID"], "syros-1");
+        assert_eq!(registration["Name"], "syros");
+        assert_eq!(registration["Port"], 8080);
+        assert_eq!(
+            registration["Check"],
+            serde_json::json!({
+                "HTTP": "http://127.0.0.1:8080/health",
+                "Interval": "10s",
+                "Timeout": "5s"
+            })
+        );
+
+        let instances = consul.discover("inventory").await.unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].id, "inventory-1");
+        assert_eq!(instances[0].address, "10.0.0.1");
+        assert_eq!(instances[0].health, ServiceHealth::Passing);
+        assert!(instances[0].tags.is_empty());
+        assert_eq!(instances[0].meta["zone"], "a");
+
+        consul.deregister("syros-1").await.unwrap();
+        assert_eq!(received.lock().unwrap()[1], "deregister syros-1");
+        // IDs are sent as one path segment, whatever they contain.
+        consul.deregister("syros/2?x=1 #a").await.unwrap();
+        assert_eq!(received.lock().unwrap()[2], "deregister syros/2?x=1 #a");
+        let Err(SyrosError::ServiceDiscoveryError(message)) = consul.deregister("unknown").await
+        else {
+            panic!("deregistering an unknown ID should fail");
+        };
+        assert!(message.contains("404"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_agent_is_a_service_discovery_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let consul = ConsulBackend::new(&url).unwrap();
+        assert!(matches!(
+            consul.discover("syros").await,
+            Err(SyrosError::ServiceDiscoveryError(_))
+        ));
+    }
+
+    #[test]
+    fn test_health_is_the_worst_check() {
+        let checks = |statuses: &[&str]| -> Vec<HealthCheck> {
+            statuses
+                .iter()
+                .map(|status| HealthCheck {
+                    status: status.to_string(),
+                })
+                .collect()
+        };
+        assert_eq!(aggregate_health(&[]), ServiceHealth::Passing);
+        assert_eq!(
+            aggregate_health(&checks(&["passing", "warning"])),
+            ServiceHealth::Warning
+        );
+        assert_eq!(
+            aggregate_health(&checks(&["warning", "critical"])),
+            ServiceHealth::Critical
+        );
+    }
+}
@@ -2,12 +2,33 @@
 //!
 //! This module provides service discovery functionality for registering
 //! and discovering services in a distributed system.
+//!
+//! Instances are kept by a [`ServiceDiscoveryBackend`]: a Consul agent
+//! ([`ConsulBackend`]) or, in tests and single-process deployments, a
+//! [`MemoryDiscoveryBackend`].
 
+use crate::config::ServiceDiscoveryConfig;
+use crate::core::service_consul::ConsulBackend;
 use crate::{Result, SyrosError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::Duration;
-use tokio::time::interval;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Timeout applied when a check does not specify a usable one.
+const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest check interval or timeout accepted.
+const MAX_CHECK_DURATION: Duration = Duration::from_secs(24 * 3600);
+
+/// Consecutive failed checks after which an instance is reported as warning.
+pub const DEFAULT_WARNING_AFTER_FAILURES: u32 = 1;
+
+/// Consecutive failed checks after which an instance is reported as critical.
+pub const DEFAULT_CRITICAL_AFTER_FAILURES: u32 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceInfo {
@@ -18,9 +39,15 @@ pub struct ServiceInfo {
     pub tags: Vec<String>,
     pub meta: HashMap<String, String>,
     pub health: ServiceHealth,
+    /// When the instance's check last ran; `None` if it has no check or has not run yet
+    #[serde(default)]
+    pub last_checked: Option<DateTime<Utc>>,
+    /// Why the last check did not pass
+    #[serde(default)]
+    pub failure_reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ServiceHealth {
     Passing,
     Warning,
@@ -28,6 +55,109 @@ pub enum ServiceHealth {
     Unknown,
 }
 
+/// Last observed health of a registered instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthStatus {
+    pub health: ServiceHealth,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub failure_reason: Option<String>,
+    /// Checks failed since the last one that passed
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// When a check last passed
+    #[serde(default)]
+    pub last_success: Option<DateTime<Utc>>,
+}
+
+/// Consecutive failed checks after which an instance turns warning, then
+/// critical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthThresholds {
+    pub warning_after: u32,
+    pub critical_after: u32,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            warning_after: DEFAULT_WARNING_AFTER_FAILURES,
+            critical_after: DEFAULT_CRITICAL_AFTER_FAILURES,
+        }
+    }
+}
+
+impl HealthThresholds {
+    /// Thresholds set in `config`, defaulting those left unset. Both are at
+    /// least one, and critical never comes before warning.
+    pub fn from_config(config: &ServiceDiscoveryConfig) -> Self {
+        let warning_after = config
+            .warning_after_failures
+            .unwrap_or(DEFAULT_WARNING_AFTER_FAILURES)
+            .max(1);
+        let critical_after = config
+            .critical_after_failures
+            .unwrap_or(DEFAULT_CRITICAL_AFTER_FAILURES)
+            .max(warning_after);
+        Self {
+            warning_after,
+            critical_after,
+        }
+    }
+}
+
+impl HealthStatus {
+    /// State of a freshly registered instance: instances without a check are
+    /// considered passing, the rest are unknown until their first check runs.
+    fn initial(check: Option<&ServiceCheck>) -> Self {
+        Self {
+            health: if check.is_some() {
+                ServiceHealth::Unknown
+            } else {
+                ServiceHealth::Passing
+            },
+            last_checked: None,
+            failure_reason: None,
+            consecutive_failures: 0,
+            last_success: None,
+        }
+    }
+
+    /// Records the outcome of a check run at `now`.
+    ///
+    /// A passing check resets the failure count. Failed checks only change
+    /// the reported health once `thresholds` are reached, so a single
+    /// failure does not take a passing instance out of rotation; a check
+    /// that observed a warning (e.g. a `429`) never makes it critical.
+    fn record(
+        &mut self,
+        observed: ServiceHealth,
+        failure_reason: Option<String>,
+        now: DateTime<Utc>,
+        thresholds: HealthThresholds,
+    ) {
+        self.last_checked = Some(now);
+        self.failure_reason = failure_reason;
+        match observed {
+            ServiceHealth::Passing => {
+                self.health = ServiceHealth::Passing;
+                self.consecutive_failures = 0;
+                self.last_success = Some(now);
+            }
+            ServiceHealth::Unknown => self.health = ServiceHealth::Unknown,
+            ServiceHealth::Warning | ServiceHealth::Critical => {
+                self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                if self.consecutive_failures >= thresholds.critical_after
+                    && observed == ServiceHealth::Critical
+                {
+                    self.health = ServiceHealth::Critical;
+                } else if self.consecutive_failures >= thresholds.warning_after {
+                    self.health = ServiceHealth::Warning;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceRegistration {
     pub id: String,
@@ -47,122 +177,446 @@ pub struct ServiceCheck {
     pub timeout: String,
 }
 
+/// Registry that instances are registered with and discovered from.
+#[async_trait]
+pub trait ServiceDiscoveryBackend: Send + Sync {
+    /// Registers `service`, along with its check if it has one.
+    async fn register(&self, service: &ServiceRegistration) -> Result<()>;
+
+    /// Removes the instance registered as `service_id`.
+    async fn deregister(&self, service_id: &str) -> Result<()>;
+
+    /// Instances of `service_name` the registry lists, with their health;
+    /// Consul only lists those whose checks pass.
+    async fn discover(&self, service_name: &str) -> Result<Vec<ServiceInfo>>;
+}
+
+/// Registry kept in this process; instances registered elsewhere are not
+/// seen.
+#[derive(Default)]
+pub struct MemoryDiscoveryBackend {
+    services: RwLock<HashMap<String, ServiceRegistration>>,
+}
+
+impl MemoryDiscoveryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ServiceDiscoveryBackend for MemoryDiscoveryBackend {
+    async fn register(&self, service: &ServiceRegistration) -> Result<()> {
+        self.services
+            .write()
+            .await
+            .insert(service.id.clone(), service.clone());
+        Ok(())
+    }
+
+    async fn deregister(&self, service_id: &str) -> Result<()> {
+        self.services.write().await.remove(service_id);
+        Ok(())
+    }
+
+    /// Every instance of `service_name`, passing if it has no check and
+    /// unknown otherwise.
+    async fn discover(&self, service_name: &str) -> Result<Vec<ServiceInfo>> {
+        Ok(self
+            .services
+            .read()
+            .await
+            .values()
+            .filter(|service| service.name == service_name)
+            .map(unchecked_info)
+            .collect())
+    }
+}
+
+/// An instance as listed before any of its checks ran: passing if it has
+/// no check and unknown otherwise.
+fn unchecked_info(service: &ServiceRegistration) -> ServiceInfo {
+    let status = HealthStatus::initial(service.check.as_ref());
+    ServiceInfo {
+        id: service.id.clone(),
+        name: service.name.clone(),
+        address: service.address.clone(),
+        port: service.port,
+        tags: service.tags.clone(),
+        meta: service.meta.clone(),
+        health: status.health,
+        last_checked: status.last_checked,
+        failure_reason: status.failure_reason,
+    }
+}
+
+/// Service registry.
+///
+/// Registrations go to the backend and are also kept locally, and the local
+/// copies answer discovery while the backend cannot be reached. No lock is
+/// held while waiting for the backend. Instances
+/// registered here with a [`ServiceCheck`] are checked on the check's
+/// interval by [`run_due_checks`](Self::run_due_checks), which the
+/// `discovery_health` background task calls, and the last observed state is
+/// reported by
+/// [`discover_services`](Self::discover_services) and
+/// [`get_service_health`](Self::get_service_health). Instances turn
+/// warning, then critical, after the [`HealthThresholds`] of consecutive
+/// failed checks; critical ones are no longer discovered.
 pub struct ServiceDiscovery {
-    consul_url: String,
-    registered_services: HashMap<String, ServiceRegistration>,
+    backend: Arc<dyn ServiceDiscoveryBackend>,
+    /// Instances registered here, including those whose registration with
+    /// the backend is still in flight
+    registered_services: std::sync::RwLock<HashMap<String, ServiceRegistration>>,
+    health: Arc<RwLock<HashMap<String, HealthStatus>>>,
+    thresholds: HealthThresholds,
+    checks: std::sync::Mutex<HashMap<String, ScheduledCheck>>,
+    /// Runs the HTTP checks; redirects could lead them away from the instance
+    client: reqwest::Client,
+}
+
+/// Health check of an instance and when it is next due.
+struct ScheduledCheck {
+    check: ServiceCheck,
+    interval: Duration,
+    timeout: Duration,
+    next_run: Instant,
+}
+
+impl ScheduledCheck {
+    /// Due right away, like the first check Consul runs.
+    fn new(check: ServiceCheck, interval: Duration) -> Self {
+        let timeout = parse_check_duration(&check.timeout).unwrap_or(DEFAULT_CHECK_TIMEOUT);
+        Self {
+            check,
+            interval,
+            timeout,
+            next_run: Instant::now(),
+        }
+    }
 }
 
 impl ServiceDiscovery {
+    /// Registers and discovers instances through the Consul agent at
+    /// `consul_url`.
     pub fn new(consul_url: &str) -> Result<Self> {
-        Ok(Self {
-            consul_url: consul_url.to_string(),
-            registered_services: HashMap::new(),
-        })
+        Ok(Self::with_backend(Arc::new(ConsulBackend::new(
+            consul_url,
+        )?)))
+    }
+
+    /// Keeps instances in this process only.
+    pub fn in_memory() -> Self {
+        Self::with_backend(Arc::new(MemoryDiscoveryBackend::new()))
+    }
+
+    pub fn with_backend(backend: Arc<dyn ServiceDiscoveryBackend>) -> Self {
+        Self {
+            backend,
+            registered_services: Default::default(),
+            health: Arc::new(RwLock::new(HashMap::new())),
+            thresholds: HealthThresholds::default(),
+            checks: Default::default(),
+            client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Applies the failure thresholds of `config`.
+    pub fn with_health_thresholds(mut self, config: &ServiceDiscoveryConfig) -> Self {
+        self.thresholds = HealthThresholds::from_config(config);
+        self
     }
 
-    pub async fn register_service(&mut self, service: ServiceRegistration) -> Result<()> {
+    /// Registers an instance.
+    ///
+    /// A health check may only target the instance's own address. Fails
+    /// with a conflict if an instance with the same ID is already
+    /// registered; deregister it first to replace it.
+    pub async fn register_service(&self, service: ServiceRegistration) -> Result<()> {
         let service_name = service.name.clone();
         let service_id = service.id.clone();
-        self.registered_services.insert(service_id.clone(), service);
 
-        tracing::info!(
-            "Serviço registrado localmente: {} ({})",
-            service_name,
-            service_id
+        if service_id.trim().is_empty() || service_name.trim().is_empty() {
+            return Err(SyrosError::ApiError(
+                "Service ID and name must not be empty".to_string(),
+            ));
+        }
+        let check_interval = match &service.check {
+            Some(check) => {
+                validate_check_target(&service.address, check)?;
+                Some(parse_check_duration(&check.interval)?)
+            }
+            None => None,
+        };
+        {
+            // Claimed before the backend is called, so a concurrent
+            // registration of the same ID conflicts.
+            let mut registered = self.registered_services.write().unwrap();
+            if let Some(existing) = registered.get(&service_id) {
+                return Err(SyrosError::Conflict(format!(
+                    "Service instance {} is already registered for {}",
+                    service_id, existing.name
+                )));
+            }
+            registered.insert(service_id.clone(), service.clone());
+        }
+        if let Err(e) = self.backend.register(&service).await {
+            self.registered_services
+                .write()
+                .unwrap()
+                .remove(&service_id);
+            return Err(e);
+        }
+        self.health.write().await.insert(
+            service_id.clone(),
+            HealthStatus::initial(service.check.as_ref()),
         );
+        if let (Some(check), Some(check_interval)) = (service.check, check_interval) {
+            self.checks.lock().unwrap().insert(
+                service_id.clone(),
+                ScheduledCheck::new(check, check_interval),
+            );
+        }
+
+        tracing::info!("Serviço registrado: {} ({})", service_name, service_id);
         Ok(())
     }
 
-    pub async fn deregister_service(&mut self, service_id: &str) -> Result<()> {
-        self.registered_services.remove(service_id);
+    /// Deregisters an instance registered here; fails with not found for
+    /// any other ID.
+    pub async fn deregister_service(&self, service_id: &str) -> Result<()> {
+        if !self
+            .registered_services
+            .read()
+            .unwrap()
+            .contains_key(service_id)
+        {
+            return Err(SyrosError::NotFound(format!(
+                "Service instance {} is not registered",
+                service_id
+            )));
+        }
+        self.backend.deregister(service_id).await?;
+        self.registered_services.write().unwrap().remove(service_id);
+        self.checks.lock().unwrap().remove(service_id);
+        self.health.write().await.remove(service_id);
         tracing::info!("Serviço desregistrado: {}", service_id);
         Ok(())
     }
 
+    /// Instances of `service_name` the backend lists, except critical ones;
+    /// those checked here report the last local check instead.
+    ///
+    /// If the backend fails, the instances of `service_name` registered here
+    /// are listed instead; with none, the backend's error is returned.
     pub async fn discover_services(&self, service_name: &str) -> Result<Vec<ServiceInfo>> {
-        let mut service_infos = Vec::new();
-
-        for (_, service) in &self.registered_services {
-            if service.name == service_name {
-                service_infos.push(ServiceInfo {
-                    id: service.id.clone(),
-                    name: service.name.clone(),
-                    address: service.address.clone(),
-                    port: service.port,
-                    tags: service.tags.clone(),
-                    meta: service.meta.clone(),
-                    health: ServiceHealth::Passing, // We assume it's healthy for now
-                });
+        let mut service_infos = match self.backend.discover(service_name).await {
+            Ok(service_infos) => service_infos,
+            Err(e) => {
+                let registered = self.registered_instances(service_name);
+                if registered.is_empty() {
+                    return Err(e);
+                }
+                tracing::warn!(
+                    "Discovering {} from local registrations: {}",
+                    service_name,
+                    e
+                );
+                registered
+            }
+        };
+        let health = self.health.read().await;
+        for service in &mut service_infos {
+            if let Some(status) = health
+                .get(&service.id)
+                .filter(|status| status.last_checked.is_some())
+            {
+                service.health = status.health;
+                service.last_checked = status.last_checked;
+                service.failure_reason = status.failure_reason.clone();
             }
         }
+        service_infos.retain(|service| service.health != ServiceHealth::Critical);
 
         Ok(service_infos)
     }
 
+    /// Instances of `service_name` that are passing or, below the critical
+    /// threshold, warning.
     pub async fn get_healthy_services(&self, service_name: &str) -> Result<Vec<ServiceInfo>> {
-        self.discover_services(service_name).await
+        let mut services = self.discover_services(service_name).await?;
+        services.retain(|service| {
+            matches!(
+                service.health,
+                ServiceHealth::Passing | ServiceHealth::Warning
+            )
+        });
+        Ok(services)
     }
 
+    /// Health of every known instance of `service_name` by ID, critical ones
+    /// included: those the backend lists, overlaid with the checks run
+    /// here, and those registered here that the backend no longer lists.
+    pub async fn get_health_map(
+        &self,
+        service_name: &str,
+    ) -> Result<HashMap<String, HealthStatus>> {
+        let mut statuses: HashMap<String, HealthStatus> = self
+            .backend
+            .discover(service_name)
+            .await?
+            .into_iter()
+            .map(|service| {
+                let status = HealthStatus {
+                    health: service.health,
+                    last_checked: service.last_checked,
+                    failure_reason: service.failure_reason,
+                    consecutive_failures: 0,
+                    last_success: None,
+                };
+                (service.id, status)
+            })
+            .collect();
+
+        let registered: Vec<ServiceRegistration> = self
+            .registered_services
+            .read()
+            .unwrap()
+            .values()
+            .filter(|service| service.name == service_name)
+            .cloned()
+            .collect();
+        let health = self.health.read().await;
+        for service in &registered {
+            let status = health
+                .get(&service.id)
+                .cloned()
+                .unwrap_or_else(|| HealthStatus::initial(service.check.as_ref()));
+            if status.last_checked.is_some() || !statuses.contains_key(&service.id) {
+                statuses.insert(service.id.clone(), status);
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    /// Last observed health of one instance of `service_name`.
     pub async fn get_service_health(
         &self,
-        _service_name: &str,
-        _service_id: &str,
+        service_name: &str,
+        service_id: &str,
     ) -> Result<ServiceHealth> {
-        Ok(ServiceHealth::Passing)
+        Ok(self
+            .get_health_status(service_name, service_id)
+            .await?
+            .health)
+    }
+
+    /// Last observed health of one instance, with when it was checked and why it failed.
+    pub async fn get_health_status(
+        &self,
+        service_name: &str,
+        service_id: &str,
+    ) -> Result<HealthStatus> {
+        let check = self
+            .registered_services
+            .read()
+            .unwrap()
+            .get(service_id)
+            .filter(|service| service.name == service_name)
+            .map(|service| service.check.clone())
+            .ok_or_else(|| {
+                SyrosError::NotFound(format!(
+                    "Service instance {} of {} is not registered",
+                    service_id, service_name
+                ))
+            })?;
+
+        Ok(self
+            .health
+            .read()
+            .await
+            .get(service_id)
+            .cloned()
+            .unwrap_or_else(|| HealthStatus::initial(check.as_ref())))
     }
 
+    /// Checks `check_url` every `interval_secs`, recording the result as the
+    /// health of `service_id`.
     pub async fn start_health_checker(
         &self,
         service_id: &str,
         check_url: &str,
         interval_secs: u64,
     ) -> Result<()> {
-        let service_id = service_id.to_string();
-        let check_url = check_url.to_string();
-
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(interval_secs));
-
-            loop {
-                interval.tick().await;
-
-                if let Err(e) = Self::perform_health_check(&check_url).await {
-                    tracing::warn!("Health check failed for service {}: {}", service_id, e);
-                }
-            }
-        });
-
+        let check = ServiceCheck {
+            http: Some(check_url.to_string()),
+            tcp: None,
+            interval: format!("{}s", interval_secs),
+            timeout: format!("{}s", DEFAULT_CHECK_TIMEOUT.as_secs()),
+        };
+        self.checks.lock().unwrap().insert(
+            service_id.to_string(),
+            ScheduledCheck::new(check, Duration::from_secs(interval_secs)),
+        );
         Ok(())
     }
 
-    async fn perform_health_check(check_url: &str) -> Result<()> {
-        let client = reqwest::Client::new();
-        let response = client
-            .get(check_url)
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await
-            .map_err(|e| {
-                SyrosError::ServiceDiscoveryError(format!("Health check request failed: {}", e))
-            })?;
+    /// Runs, concurrently, the checks whose interval elapsed since they last
+    /// ran and records their results. Returns how many ran.
+    pub async fn run_due_checks(&self) -> usize {
+        let now = Instant::now();
+        let due: Vec<(String, ServiceCheck, Duration)> = self
+            .checks
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|(_, scheduled)| scheduled.next_run <= now)
+            .map(|(service_id, scheduled)| {
+                scheduled.next_run = now + scheduled.interval;
+                (
+                    service_id.clone(),
+                    scheduled.check.clone(),
+                    scheduled.timeout,
+                )
+            })
+            .collect();
 
-        if !response.status().is_success() {
-            return Err(SyrosError::ServiceDiscoveryError(format!(
-                "Health check failed with status: {}",
-                response.status()
-            )));
-        }
+        let results = futures::future::join_all(
+            due.iter()
+                .map(|(_, check, timeout)| perform_health_check(&self.client, check, *timeout)),
+        )
+        .await;
 
-        Ok(())
+        let mut health = self.health.write().await;
+        for ((service_id, check, _), (state, failure_reason)) in due.iter().zip(results) {
+            // Deregistered while its check ran
+            if !self.checks.lock().unwrap().contains_key(service_id) {
+                continue;
+            }
+            if let Some(reason) = &failure_reason {
+                tracing::warn!("Health check failed for service {}: {}", service_id, reason);
+            }
+            health
+                .entry(service_id.clone())
+                .or_insert_with(|| HealthStatus::initial(Some(check)))
+                .record(state, failure_reason, Utc::now(), self.thresholds);
+        }
+        due.len()
     }
 
+    /// Names of the services with instances registered here, sorted.
     pub async fn list_all_services(&self) -> Result<Vec<String>> {
         let mut service_names = Vec::new();
-        for (_, service) in &self.registered_services {
+        for service in self.registered_services.read().unwrap().values() {
             if !service_names.contains(&service.name) {
                 service_names.push(service.name.clone());
             }
         }
+        service_names.sort();
         Ok(service_names)
     }
 
@@ -170,8 +624,20 @@ impl ServiceDiscovery {
         self.discover_services(service_name).await
     }
 
-    pub fn get_registered_services(&self) -> &HashMap<String, ServiceRegistration> {
-        &self.registered_services
+    pub fn get_registered_services(&self) -> HashMap<String, ServiceRegistration> {
+        self.registered_services.read().unwrap().clone()
+    }
+
+    /// Instances of `service_name` registered here, as the backend would
+    /// list them before any check.
+    fn registered_instances(&self, service_name: &str) -> Vec<ServiceInfo> {
+        self.registered_services
+            .read()
+            .unwrap()
+            .values()
+            .filter(|service| service.name == service_name)
+            .map(unchecked_info)
+            .collect()
     }
 }
 
@@ -181,6 +647,114 @@ impl Default for ServiceDiscovery {
     }
 }
 
+/// Runs one HTTP or TCP check, returning the observed state and, unless it
+/// passed, the reason.
+///
+/// As in Consul, a 2xx response passes, 429 is a warning and anything else
+/// is critical.
+async fn perform_health_check(
+    client: &reqwest::Client,
+    check: &ServiceCheck,
+    timeout: Duration,
+) -> (ServiceHealth, Option<String>) {
+    if let Some(url) = &check.http {
+        return match client.get(url).timeout(timeout).send().await {
+            Ok(response) if response.status().is_success() => (ServiceHealth::Passing, None),
+            Ok(response) => {
+                let state = if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    ServiceHealth::Warning
+                } else {
+                    ServiceHealth::Critical
+                };
+                (
+                    state,
+                    Some(format!("HTTP check returned status {}", response.status())),
+                )
+            }
+            Err(e) => (
+                ServiceHealth::Critical,
+                Some(format!("HTTP check request failed: {}", e)),
+            ),
+        };
+    }
+
+    if let Some(addr) = &check.tcp {
+        return match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => (ServiceHealth::Passing, None),
+            Ok(Err(e)) => (
+                ServiceHealth::Critical,
+                Some(format!("TCP check connection failed: {}", e)),
+            ),
+            Err(_) => (
+                ServiceHealth::Critical,
+                Some(format!("TCP check timed out after {:?}", timeout)),
+            ),
+        };
+    }
+
+    (
+        ServiceHealth::Unknown,
+        Some("Check has neither an HTTP nor a TCP target".to_string()),
+    )
+}
+
+/// Ensures the HTTP and TCP targets of `check` are on `address`, so a
+/// registration cannot make this process probe arbitrary hosts.
+fn validate_check_target(address: &str, check: &ServiceCheck) -> Result<()> {
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let invalid = |target: &str| {
+        SyrosError::ApiError(format!(
+            "Health check target {} is not on the instance address {}",
+            target, address
+        ))
+    };
+
+    if let Some(target) = &check.http {
+        let url = reqwest::Url::parse(target).map_err(|_| invalid(target))?;
+        let host = url
+            .host_str()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'));
+        if !matches!(url.scheme(), "http" | "https")
+            || !host.is_some_and(|host| host.eq_ignore_ascii_case(address))
+        {
+            return Err(invalid(target));
+        }
+    }
+    if let Some(target) = &check.tcp {
+        let host = target
+            .rsplit_once(':')
+            .map(|(host, _)| host.trim_start_matches('[').trim_end_matches(']'));
+        if !host.is_some_and(|host| host.eq_ignore_ascii_case(address)) {
+            return Err(invalid(target));
+        }
+    }
+    Ok(())
+}
+
+/// Parses a check duration such as `"10s"`, `"500ms"` or `"1m"`, of at
+/// most [`MAX_CHECK_DURATION`].
+fn parse_check_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let invalid = || SyrosError::ApiError(format!("Invalid check duration: {}", value));
+
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(amount),
+        "s" | "" => Duration::from_secs(amount),
+        "m" => Duration::from_secs(amount.checked_mul(60).ok_or_else(invalid)?),
+        "h" => Duration::from_secs(amount.checked_mul(3600).ok_or_else(invalid)?),
+        _ => return Err(invalid()),
+    };
+    if duration.is_zero() || duration > MAX_CHECK_DURATION {
+        return Err(invalid());
+    }
+    Ok(duration)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,7 +767,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_service_registration() {
-        let mut discovery = ServiceDiscovery::new("http://localhost:8500").unwrap();
+        let discovery = ServiceDiscovery::in_memory();
 
         let service = ServiceRegistration {
             id: "test-service-1".to_string(),
@@ -213,4 +787,337 @@ mod tests {
         let result = discovery.register_service(service).await;
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parse_check_duration() {
+        assert_eq!(
+            parse_check_duration("10s").unwrap(),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            parse_check_duration("250ms").unwrap(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            parse_check_duration("2m").unwrap(),
+            Duration::from_secs(120)
+        );
+        assert!(parse_check_duration("0s").is_err());
+        assert!(parse_check_duration("soon").is_err());
+        assert!(parse_check_duration("25h").is_err());
+        assert!(parse_check_duration(&format!("{}h", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_checks_may_only_target_the_instance() {
+        let check = |http: Option<&str>, tcp: Option<&str>| ServiceCheck {
+            http: http.map(str::to_string),
+            tcp: tcp.map(str::to_string),
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+        };
+        for allowed in [
+            check(Some("http://10.0.0.12:8080/health"), None),
+            check(Some("https://10.0.0.12/health"), None),
+            check(None, Some("10.0.0.12:5432")),
+        ] {
+            assert!(validate_check_target("10.0.0.12", &allowed).is_ok());
+        }
+        assert!(validate_check_target("::1", &check(Some("http://[::1]:8080/"), None)).is_ok());
+        for rejected in [
+            check(Some("http://169.254.169.254/latest/meta-data"), None),
+            check(Some("http://10.0.0.12.evil.example/health"), None),
+            check(Some("file:///etc/passwd"), None),
+            check(Some("not a url"), None),
+            check(None, Some("10.0.0.13:5432")),
+            check(None, Some("10.0.0.12")),
+        ] {
+            assert!(matches!(
+                validate_check_target("10.0.0.12", &rejected),
+                Err(SyrosError::ApiError(_))
+            ));
+        }
+    }
+
+    async fn wait_for_health(
+        discovery: &ServiceDiscovery,
+        expected: ServiceHealth,
+    ) -> HealthStatus {
+        for _ in 0..100 {
+            discovery.run_due_checks().await;
+            let status = discovery
+                .get_health_status("flaky", "flaky-1")
+                .await
+                .unwrap();
+            if status.health == expected {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("service never became {:?}", expected);
+    }
+
+    #[tokio::test]
+    async fn test_health_follows_http_check() {
+        use axum::{extract::State, http::StatusCode, routing::get, Router};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let healthy = Arc::new(AtomicBool::new(true));
+        let app = Router::new()
+            .route(
+                "/health",
+                get(|State(healthy): State<Arc<AtomicBool>>| async move {
+                    if healthy.load(Ordering::SeqCst) {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                }),
+            )
+            .with_state(healthy.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let discovery = ServiceDiscovery::in_memory();
+        discovery
+            .register_service(ServiceRegistration {
+                id: "flaky-1".to_string(),
+                name: "flaky".to_string(),
+                address: addr.ip().to_string(),
+                port: addr.port(),
+                tags: vec![],
+                meta: HashMap::new(),
+                check: Some(ServiceCheck {
+                    http: Some(format!("http://{}/health", addr)),
+                    tcp: None,
+                    interval: "20ms".to_string(),
+                    timeout: "1s".to_string(),
+                }),
+            })
+            .await
+            .unwrap();
+
+        let passing = wait_for_health(&discovery, ServiceHealth::Passing).await;
+        assert!(passing.last_checked.is_some());
+        assert_eq!(
+            discovery.get_healthy_services("flaky").await.unwrap().len(),
+            1
+        );
+
+        healthy.store(false, Ordering::SeqCst);
+        let critical = wait_for_health(&discovery, ServiceHealth::Critical).await;
+        assert!(critical.failure_reason.unwrap().contains("500"));
+        assert!(discovery
+            .get_healthy_services("flaky")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(critical.consecutive_failures >= DEFAULT_CRITICAL_AFTER_FAILURES);
+        assert!(discovery
+            .discover_services("flaky")
+            .await
+            .unwrap()
+            .is_empty());
+        let health = discovery.get_health_map("flaky").await.unwrap();
+        assert_eq!(health["flaky-1"].health, ServiceHealth::Critical);
+
+        healthy.store(true, Ordering::SeqCst);
+        let recovered = wait_for_health(&discovery, ServiceHealth::Passing).await;
+        assert!(recovered.failure_reason.is_none());
+        assert!(recovered.last_checked > passing.last_checked);
+        assert_eq!(recovered.consecutive_failures, 0);
+        assert_eq!(recovered.last_success, recovered.last_checked);
+
+        discovery.deregister_service("flaky-1").await.unwrap();
+        assert_eq!(discovery.run_due_checks().await, 0);
+        assert!(discovery
+            .get_health_status("flaky", "flaky-1")
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_failed_checks_escalate_through_thresholds() {
+        let check = ServiceCheck {
+            http: Some("http://127.0.0.1:8080/health".to_string()),
+            tcp: None,
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+        };
+        let thresholds = HealthThresholds::from_config(&ServiceDiscoveryConfig {
+            warning_after_failures: Some(2),
+            critical_after_failures: Some(3),
+            ..Default::default()
+        });
+        let now = Utc::now();
+        let failure = || Some("HTTP check returned status 500".to_string());
+
+        let mut status = HealthStatus::initial(Some(&check));
+        status.record(ServiceHealth::Passing, None, now, thresholds);
+        assert_eq!(status.last_success, Some(now));
+
+        status.record(ServiceHealth::Critical, failure(), now, thresholds);
+        assert_eq!(status.health, ServiceHealth::Passing);
+        assert_eq!(status.consecutive_failures, 1);
+        status.record(ServiceHealth::Critical, failure(), now, thresholds);
+        assert_eq!(status.health, ServiceHealth::Warning);
+        status.record(ServiceHealth::Critical, failure(), now, thresholds);
+        assert_eq!(status.health, ServiceHealth::Critical);
+        assert_eq!(status.last_success, Some(now));
+
+        status.record(ServiceHealth::Passing, None, now, thresholds);
+        assert_eq!(status.health, ServiceHealth::Passing);
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(status.failure_reason.is_none());
+
+        // Rate-limited answers never take an instance past warning.
+        for _ in 0..5 {
+            status.record(ServiceHealth::Warning, failure(), now, thresholds);
+        }
+        assert_eq!(status.health, ServiceHealth::Warning);
+
+        let clamped = HealthThresholds::from_config(&ServiceDiscoveryConfig {
+            warning_after_failures: Some(0),
+            critical_after_failures: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(
+            clamped,
+            HealthThresholds {
+                warning_after: 1,
+                critical_after: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_instances_of_one_service_need_distinct_ids() {
+        let config = crate::config::ServiceDiscoveryConfig {
+            service_name: "syros".to_string(),
+            service_id: "syros-1".to_string(),
+            auto_instance_id: true,
+            ..Default::default()
+        };
+        let instance = |id: String| ServiceRegistration {
+            id,
+            name: "syros".to_string(),
+            address: "127.0.0.1".to_string(),
+            port: 8080,
+            tags: vec![],
+            meta: HashMap::new(),
+            check: None,
+        };
+
+        let first = config.generate_instance_id();
+        let second = config.generate_instance_id();
+        assert!(first.starts_with("syros-1-"), "{}", first);
+        assert_ne!(first, second);
+
+        let discovery = ServiceDiscovery::in_memory();
+        discovery
+            .register_service(instance(first.clone()))
+            .await
+            .unwrap();
+        discovery
+            .register_service(instance(second.clone()))
+            .await
+            .unwrap();
+        let mut ids: Vec<String> = discovery
+            .discover_services("syros")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|service| service.id)
+            .collect();
+        ids.sort();
+        let mut expected = vec![first.clone(), second];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let duplicate = discovery.register_service(instance(first.clone())).await;
+        assert!(matches!(duplicate, Err(SyrosError::Conflict(_))));
+
+        discovery.deregister_service(&first).await.unwrap();
+        assert_eq!(discovery.discover_services("syros").await.unwrap().len(), 1);
+        discovery.register_service(instance(first)).await.unwrap();
+    }
+
+    /// Registry that accepts registrations but cannot be read.
+    struct UnreadableBackend;
+
+    #[async_trait]
+    impl ServiceDiscoveryBackend for UnreadableBackend {
+        async fn register(&self, _service: &ServiceRegistration) -> Result<()> {
+            Ok(())
+        }
+
+        async fn deregister(&self, _service_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn discover(&self, _service_name: &str) -> Result<Vec<ServiceInfo>> {
+            Err(SyrosError::ServiceDiscoveryError(
+                "Consul is unreachable".to_string(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_registrations_answer_while_the_backend_fails() {
+        let discovery = ServiceDiscovery::with_backend(Arc::new(UnreadableBackend));
+        discovery
+            .register_service(ServiceRegistration {
+                id: "static-1".to_string(),
+                name: "static".to_string(),
+                address: "127.0.0.1".to_string(),
+                port: 9000,
+                tags: vec![],
+                meta: HashMap::new(),
+                check: None,
+            })
+            .await
+            .unwrap();
+
+        let instances = discovery.discover_services("static").await.unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].id, "static-1");
+        assert!(matches!(
+            discovery.discover_services("other").await,
+            Err(SyrosError::ServiceDiscoveryError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_services_without_check_are_passing() {
+        let discovery = ServiceDiscovery::in_memory();
+        discovery
+            .register_service(ServiceRegistration {
+                id: "static-1".to_string(),
+                name: "static".to_string(),
+                address: "127.0.0.1".to_string(),
+                port: 9000,
+                tags: vec![],
+                meta: HashMap::new(),
+                check: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            discovery
+                .get_service_health("static", "static-1")
+                .await
+                .unwrap(),
+            ServiceHealth::Passing
+        );
+        assert!(discovery
+            .get_service_health("other", "static-1")
+            .await
+            .is_err());
+        assert!(matches!(
+            discovery.deregister_service("static-2").await,
+            Err(SyrosError::NotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,282 @@
+//! Inventory of the Tokio tasks spawned by this process.
+//!
+//! Components spawn through a shared [`TaskTracker`] instead of calling
+//! `tokio::spawn` directly, naming each task after what it does. The tracker
+//! counts the live and spawned tasks by name for the admin tasks endpoint and
+//! the `tasks_live` metric, so a name whose live count only ever grows points
+//! at a leak.
+//!
+//! Tasks spawned with [`TaskTracker::spawn_background`] run until shutdown,
+//! e.g. sweepers and relays; [`TaskTracker::drain`] stops them and waits for
+//! the remaining tasks, such as running sagas, to finish.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+
+/// Live and spawned tasks of one name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskCount {
+    pub name: String,
+    pub live: usize,
+    /// Tasks of this name spawned since the process started
+    pub spawned: u64,
+    /// Spawn time of the longest running live task
+    pub oldest_spawned_at: Option<DateTime<Utc>>,
+}
+
+/// Tasks of this process as shown by the admin tasks endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskInventory {
+    pub live: usize,
+    pub spawned: u64,
+    /// Counts by task name, ordered by name
+    pub tasks: Vec<TaskCount>,
+}
+
+struct LiveTask {
+    name: String,
+    spawned_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct TaskState {
+    live: HashMap<u64, LiveTask>,
+    spawned: BTreeMap<String, u64>,
+}
+
+struct TrackerInner {
+    state: Mutex<TaskState>,
+    next_id: AtomicU64,
+    /// Woken whenever the last live task finishes
+    idle: Notify,
+    /// Set once the tracker drains, stopping the background tasks
+    stopping: watch::Sender<bool>,
+}
+
+/// Spawns named tasks and keeps count of them, shared by the components of
+/// a process.
+#[derive(Clone)]
+pub struct TaskTracker {
+    inner: Arc<TrackerInner>,
+}
+
+impl Default for TaskTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskTracker {
+    pub fn new() -> Self {
+        let (stopping, _) = watch::channel(false);
+        Self {
+            inner: Arc::new(TrackerInner {
+                state: Mutex::default(),
+                next_id: AtomicU64::new(0),
+                idle: Notify::new(),
+                stopping,
+            }),
+        }
+    }
+
+    /// Spawns `future` as a task named `name`.
+    ///
+    /// Callers keep the returned handle to await or abort the task, or drop
+    /// it to detach the task; the tracker counts it either way until it ends.
+    pub fn spawn<F>(&self, name: &str, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio::spawn(self.track(name, future))
+    }
+
+    /// Spawns `future` as a task named `name` that is stopped when the
+    /// tracker drains, for tasks that would otherwise run forever.
+    pub fn spawn_background<F>(&self, name: &str, future: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut stopping = self.inner.stopping.subscribe();
+        self.spawn(name, async move {
+            tokio::select! {
+                _ = future => {}
+                _ = stopping.wait_for(|stopping| *stopping) => {}
+            }
+        })
+    }
+
+    /// Counts `future` as a live task named `name` until it completes or is
+    /// dropped, for futures that are spawned elsewhere, e.g. by axum.
+    pub fn track<F: Future>(&self, name: &str, future: F) -> impl Future<Output = F::Output> {
+        let guard = self.register(name);
+        async move {
+            let _guard = guard;
+            future.await
+        }
+    }
+
+    /// Number of live tasks.
+    pub fn live(&self) -> usize {
+        self.inner.state.lock().unwrap().live.len()
+    }
+
+    /// Live tasks named `name`.
+    pub fn live_named(&self, name: &str) -> usize {
+        let state = self.inner.state.lock().unwrap();
+        state.live.values().filter(|task| task.name == name).count()
+    }
+
+    /// Live and spawned tasks by name.
+    pub fn inventory(&self) -> TaskInventory {
+        let state = self.inner.state.lock().unwrap();
+        let mut tasks: BTreeMap<&str, TaskCount> = state
+            .spawned
+            .iter()
+            .map(|(name, spawned)| {
+                let count = TaskCount {
+                    name: name.clone(),
+                    live: 0,
+                    spawned: *spawned,
+                    oldest_spawned_at: None,
+                };
+                (name.as_str(), count)
+            })
+            .collect();
+        for task in state.live.values() {
+            if let Some(count) = tasks.get_mut(task.name.as_str()) {
+                count.live += 1;
+                count.oldest_spawned_at = Some(match count.oldest_spawned_at {
+                    Some(oldest) => oldest.min(task.spawned_at),
+                    None => task.spawned_at,
+                });
+            }
+        }
+
+        TaskInventory {
+            live: state.live.len(),
+            spawned: state.spawned.values().sum(),
+            tasks: tasks.into_values().collect(),
+        }
+    }
+
+    /// Stops the background tasks and waits up to `timeout` for the other
+    /// tasks to finish.
+    ///
+    /// Returns the number of tasks still live when it gave up.
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.inner.stopping.send_replace(true);
+        let _ = tokio::time::timeout(timeout, self.wait_idle()).await;
+        self.live()
+    }
+
+    async fn wait_idle(&self) {
+        loop {
+            let idle = self.inner.idle.notified();
+            if self.live() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    fn register(&self, name: &str) -> TaskGuard {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.inner.state.lock().unwrap();
+        *state.spawned.entry(name.to_string()).or_default() += 1;
+        state.live.insert(
+            id,
+            LiveTask {
+                name: name.to_string(),
+                spawned_at: Utc::now(),
+            },
+        );
+        TaskGuard {
+            inner: self.inner.clone(),
+            id,
+        }
+    }
+}
+
+/// Removes a task from the live tasks when it ends.
+struct TaskGuard {
+    inner: Arc<TrackerInner>,
+    id: u64,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.live.remove(&self.id);
+        if state.live.is_empty() {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_counts_tasks_by_name() {
+        let tasks = TaskTracker::new();
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+
+        let waiting = tasks.spawn("waiter", async move {
+            let _ = released.await;
+        });
+        tasks.spawn("quick", async {}).await.unwrap();
+        tasks.spawn("quick", async {}).await.unwrap();
+
+        let inventory = tasks.inventory();
+        assert_eq!(inventory.live, 1);
+        assert_eq!(inventory.spawned, 3);
+        assert_eq!(inventory.tasks[0].name, "quick");
+        assert_eq!(inventory.tasks[0].live, 0);
+        assert_eq!(inventory.tasks[0].spawned, 2);
+        assert_eq!(inventory.tasks[0].oldest_spawned_at, None);
+        assert_eq!(inventory.tasks[1].name, "waiter");
+        assert_eq!(inventory.tasks[1].live, 1);
+        assert!(inventory.tasks[1].oldest_spawned_at.is_some());
+
+        release.send(()).unwrap();
+        waiting.await.unwrap();
+        assert_eq!(tasks.live_named("waiter"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_aborted_tasks_are_no_longer_live() {
+        let tasks = TaskTracker::new();
+        let task = tasks.spawn("stuck", std::future::pending::<()>());
+        assert_eq!(tasks.live(), 1);
+
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert_eq!(tasks.live(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_stops_background_tasks_and_waits_for_others() {
+        let tasks = TaskTracker::new();
+        let sweeper = tasks.spawn_background("sweeper", std::future::pending());
+        let finishing = tasks.spawn("finishing", async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+
+        assert_eq!(tasks.drain(Duration::from_secs(2)).await, 0);
+        assert!(finishing.is_finished());
+        sweeper.await.unwrap();
+
+        // Stuck tasks are given up on after the timeout.
+        let _stuck = tasks.spawn("stuck", std::future::pending::<()>());
+        assert_eq!(tasks.drain(Duration::from_millis(20)).await, 1);
+    }
+}
@@ -0,0 +1,290 @@
+//! Syros embedded in a host application, without any API server.
+//!
+//! [`SyrosEmbedded`] builds the lock manager, saga orchestrator, event store
+//! and cache from an [`EmbeddedConfig`] and hands them out as typed handles.
+//! Saga steps run in process: the host registers a closure per service and
+//! action, and the orchestrator calls it for every step naming them, with
+//! the step's `compensation` action looked up the same way when the saga
+//! rolls back. The sweepers run on the host's runtime until
+//! [`SyrosEmbedded::shutdown`].
+//!
+//! Only the coordination primitives are needed, so this works with
+//! `default-features = false`.
+
+use crate::config::{BackgroundTasksConfig, CachePersistenceConfig, DatabaseConfig};
+use crate::core::saga_executors::SagaStepHandler;
+use crate::core::saga_orchestrator::{Saga, SagaRequest, SagaStep, StepCallContext};
+use crate::core::saga_results::StepResultLimits;
+use crate::core::{
+    CacheManager, ComponentRegistry, DeadLetterQueue, EventStore, LockManager, SagaOrchestrator,
+    TaskSpawner, TaskTracker,
+};
+use crate::storage::postgres::PostgresManager;
+use crate::storage::redis::RedisManager;
+use crate::{Result, SyrosError};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Backends and schedules of an embedded Syros.
+///
+/// The default keeps everything in process memory.
+#[derive(Debug, Clone)]
+pub struct EmbeddedConfig {
+    /// Redis the locks are kept in; in process memory when `None`
+    pub redis_url: Option<String>,
+    /// Postgres the sagas and events are kept in; in process memory when `None`.
+    /// Sagas a previous run left active there are resumed at start.
+    pub database: Option<DatabaseConfig>,
+    /// Write-behind journal of the cache; memory only when `None`
+    pub cache_persistence: Option<CachePersistenceConfig>,
+    /// Schedules of `locks_sweep`, `cache_sweep`, `saga_scheduler` and
+    /// `event_retention`; the other tasks belong to the server and are not run
+    pub background_tasks: BackgroundTasksConfig,
+    /// How long [`SyrosEmbedded::shutdown`] waits for running sagas
+    pub drain_timeout: Duration,
+}
+
+impl Default for EmbeddedConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            database: None,
+            cache_persistence: None,
+            background_tasks: BackgroundTasksConfig::default(),
+            drain_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Registers the step handlers and backends of a [`SyrosEmbedded`].
+#[derive(Default)]
+pub struct SyrosEmbeddedBuilder {
+    config: EmbeddedConfig,
+    handlers: HashMap<(String, String), SagaStepHandler>,
+}
+
+impl SyrosEmbeddedBuilder {
+    /// Uses the backends and schedules in `config`.
+    pub fn with_config(mut self, config: EmbeddedConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Runs `handler` for saga steps calling `action` on `service`, as their
+    /// action or their compensation.
+    ///
+    /// Registering the same service and action again replaces the handler.
+    pub fn with_step<F, Fut>(mut self, service: &str, action: &str, handler: F) -> Self
+    where
+        F: Fn(SagaStep, StepCallContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler: SagaStepHandler =
+            Arc::new(move |step, context| handler(step, context).boxed());
+        self.handlers
+            .insert((service.to_string(), action.to_string()), handler);
+        self
+    }
+
+    /// Connects the configured backends and starts the sweepers on the
+    /// current runtime.
+    ///
+    /// Fails if a backend cannot be reached or the cache journal cannot be
+    /// replayed, or if the background task schedules are invalid.
+    pub async fn start(self) -> Result<SyrosEmbedded> {
+        let config = self.config;
+        let tasks = TaskTracker::new();
+
+        let locks = match &config.redis_url {
+            Some(url) => LockManager::new(RedisManager::new(url)?),
+            None => LockManager::in_memory(),
+        }
+        .with_task_tracker(tasks.clone());
+
+        let (events, sagas) = match &config.database {
+            Some(database) => {
+                let pg = PostgresManager::new(&database.url, database.pool_size).await?;
+                (EventStore::new(pg.clone()), SagaOrchestrator::new(pg))
+            }
+            None => (EventStore::in_memory(), SagaOrchestrator::in_memory()),
+        };
+        let dead_letters = DeadLetterQueue::new().with_event_store(events.clone());
+        if config.database.is_some() {
+            if let Err(e) = dead_letters.restore().await {
+                tracing::error!("Error restoring saga dead-letter queue: {}", e);
+            }
+        }
+
+        let cache = match &config.cache_persistence {
+            Some(persistence) => CacheManager::with_persistence(persistence, &tasks).await?,
+            None => CacheManager::new(),
+        };
+
+        let sagas = sagas
+            .with_dead_letter_queue(dead_letters)
+            .with_step_result_limits(StepResultLimits::default().with_cache(cache.clone()))
+            .with_lock_manager(locks.clone())
+            .with_cache_manager(cache.clone())
+            .with_task_tracker(tasks.clone())
+            .with_step_executor(step_executor(self.handlers));
+
+        if config.database.is_some() {
+            let recovered = sagas.recover_pending_sagas().await?;
+            if !recovered.is_empty() {
+                tracing::info!("Recovered {} sagas left active", recovered.len());
+            }
+        }
+
+        let components = ComponentRegistry::new();
+        let mut sweepers = TaskSpawner::new(components.clone()).with_task_tracker(tasks.clone());
+        sweepers.register_sweepers(&locks, &cache, &sagas);
+        sweepers.register_event_retention(&events);
+        sweepers.apply(&config.background_tasks)?;
+
+        Ok(SyrosEmbedded {
+            locks,
+            sagas,
+            events,
+            cache,
+            tasks,
+            components,
+            sweepers,
+            drain_timeout: config.drain_timeout,
+        })
+    }
+}
+
+/// Syros running inside the host process.
+///
+/// ```
+/// use syros::core::cache_manager::{CacheRequest, CacheSetMode};
+/// use syros::SyrosEmbedded;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> syros::Result<()> {
+/// let syros = SyrosEmbedded::builder().start().await?;
+/// syros
+///     .cache()
+///     .set(CacheRequest {
+///         key: "greeting".to_string(),
+///         value: serde_json::json!("hello"),
+///         ttl: None,
+///         tags: vec![],
+///         mode: CacheSetMode::Upsert,
+///         created_by: None,
+///         cache_negative: false,
+///     })
+///     .await?;
+/// assert!(syros.cache().get("greeting").await?.found);
+/// syros.shutdown().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SyrosEmbedded {
+    locks: LockManager,
+    sagas: SagaOrchestrator,
+    events: EventStore,
+    cache: CacheManager,
+    tasks: TaskTracker,
+    components: ComponentRegistry,
+    /// Stops the sweepers when dropped
+    sweepers: TaskSpawner,
+    drain_timeout: Duration,
+}
+
+impl SyrosEmbedded {
+    pub fn builder() -> SyrosEmbeddedBuilder {
+        SyrosEmbeddedBuilder::default()
+    }
+
+    pub fn locks(&self) -> &LockManager {
+        &self.locks
+    }
+
+    pub fn sagas(&self) -> &SagaOrchestrator {
+        &self.sagas
+    }
+
+    pub fn events(&self) -> &EventStore {
+        &self.events
+    }
+
+    pub fn cache(&self) -> &CacheManager {
+        &self.cache
+    }
+
+    /// Starts a saga and waits until it ends, returning its final state:
+    /// `Completed`, or e.g. `Compensated` if a step failed.
+    pub async fn run_saga(&self, request: SagaRequest) -> Result<Saga> {
+        let (done, ended) = oneshot::channel();
+        self.sagas
+            .start_saga_with_completion_hook(
+                request,
+                Box::new(move |saga| {
+                    let _ = done.send(saga.clone());
+                }),
+            )
+            .await?;
+        ended
+            .await
+            .map_err(|_| SyrosError::SagaError("Saga stopped before it ended".to_string()))
+    }
+
+    /// Tasks spawned by the managers and sweepers.
+    pub fn tasks(&self) -> &TaskTracker {
+        &self.tasks
+    }
+
+    /// Schedules and activity of the sweepers.
+    pub fn components(&self) -> &ComponentRegistry {
+        &self.components
+    }
+
+    /// Stops the sweepers, flushes the cache journal and waits up to the
+    /// configured drain timeout for running sagas to finish.
+    ///
+    /// Returns the number of tasks still running when it gave up.
+    pub async fn shutdown(self) -> usize {
+        drop(self.sweepers);
+        if let Err(e) = self.cache.flush().await {
+            tracing::error!("Error flushing the cache journal: {}", e);
+        }
+        let still_running = self.tasks.drain(self.drain_timeout).await;
+        if still_running > 0 {
+            tracing::warn!(
+                "Stopping with {} tasks still running after {:?}",
+                still_running,
+                self.drain_timeout
+            );
+        }
+        still_running
+    }
+}
+
+/// Dispatches each step to the handler of its service and action, or of its
+/// compensation when rolling back. A step without a compensation has nothing
+/// to undo.
+fn step_executor(handlers: HashMap<(String, String), SagaStepHandler>) -> SagaStepHandler {
+    Arc::new(move |step, context| -> BoxFuture<'static, Result<()>> {
+        let action = if context.compensation {
+            &step.compensation
+        } else {
+            &step.action
+        };
+        if context.compensation && action.is_empty() {
+            return async { Ok(()) }.boxed();
+        }
+        match handlers.get(&(step.service.clone(), action.clone())) {
+            Some(handler) => handler(step, context),
+            None => {
+                let message = format!("No handler registered for {}.{}", step.service, action);
+                async move { Err(SyrosError::SagaError(message)) }.boxed()
+            }
+        }
+    })
+}
@@ -35,4 +35,30 @@ pub enum SyrosError {
 
     #[error("Clock error: {0}")]
     ClockError(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Namespace limit exceeded: {0}")]
+    NamespaceLimitExceeded(String),
+
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    #[error("Stream deleted: {0}")]
+    StreamDeleted(String),
+
+    #[error("Value too large: {0}")]
+    ValueTooLarge(String),
+
+    #[error(
+        "Version conflict: expected version {expected}, but the stream is at version {actual}"
+    )]
+    VersionConflict { expected: u64, actual: u64 },
 }
@@ -4,6 +4,14 @@ use volo::FastStr;
 use volo_grpc::body::BoxBody;
 use volo_grpc::{Request, Response, Status};
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockPriority {
+    #[default]
+    Normal,
+    Low,
+    High,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LockRequest {
     pub key: FastStr,
@@ -11,6 +19,7 @@ pub struct LockRequest {
     pub ttl_seconds: u64,
     pub metadata: Option<FastStr>,
     pub wait_timeout_seconds: Option<u64>,
+    pub priority: LockPriority,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +27,7 @@ pub struct LockResponse {
     pub lock_id: FastStr,
     pub success: bool,
     pub message: FastStr,
+    pub fence_token: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +77,7 @@ pub struct LockInfo {
     pub owner: FastStr,
     pub expires_at: u64,
     pub metadata: Option<FastStr>,
+    pub fence_token: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +85,9 @@ pub struct SagaRequest {
     pub name: FastStr,
     pub steps: Vec<SagaStep>,
     pub metadata: HashMap<FastStr, FastStr>,
+    pub max_duration_seconds: Option<u64>,
+    pub idempotency_key: Option<FastStr>,
+    pub start_at: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,6 +99,16 @@ pub struct SagaStep {
     pub timeout_seconds: Option<u64>,
     pub retry_policy: Option<RetryPolicy>,
     pub payload: Option<FastStr>,
+    pub acquired_locks: Vec<StepLock>,
+    pub cache_keys: Vec<FastStr>,
+    pub group: Option<FastStr>,
+    pub executor: Option<FastStr>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepLock {
+    pub key: FastStr,
+    pub owner: Option<FastStr>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,6 +149,8 @@ pub struct StepResult {
     pub error: Option<FastStr>,
     pub started_at: u64,
     pub completed_at: Option<u64>,
+    pub compensation_attempts: u32,
+    pub compensated_at: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +170,9 @@ pub struct ListSagasRequest {
     pub status: Option<FastStr>,
     pub owner: Option<FastStr>,
     pub limit: Option<u32>,
+    pub name_prefix: Option<FastStr>,
+    pub created_after: Option<u64>,
+    pub offset: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -169,6 +198,9 @@ pub struct EventRequest {
     pub event_type: FastStr,
     pub data: FastStr,
     pub metadata: HashMap<FastStr, FastStr>,
+    pub expected_version: Option<u64>,
+    pub event_id: Option<FastStr>,
+    pub causation_id: Option<FastStr>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -179,12 +211,22 @@ pub struct EventResponse {
     pub message: FastStr,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReadDirection {
+    #[default]
+    Forward,
+    Backward,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetEventsRequest {
     pub stream_id: FastStr,
     pub from_version: Option<u64>,
     pub to_version: Option<u64>,
     pub limit: Option<u32>,
+    pub event_types: Vec<FastStr>,
+    pub direction: ReadDirection,
+    pub metadata: HashMap<FastStr, FastStr>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -234,6 +276,24 @@ pub struct GetCacheResponse {
     pub tags: Vec<FastStr>,
     pub success: bool,
     pub message: FastStr,
+    pub source: Option<FastStr>,
+    pub state: CacheLookupState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheLookupState {
+    #[default]
+    Miss,
+    Hit,
+    NegativeHit,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheSetMode {
+    #[default]
+    Upsert,
+    CreateOnly,
+    UpdateOnly,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -242,6 +302,8 @@ pub struct SetCacheRequest {
     pub value: FastStr,
     pub ttl_seconds: Option<u64>,
     pub tags: Vec<FastStr>,
+    pub mode: CacheSetMode,
+    pub cache_negative: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -252,6 +314,7 @@ pub struct SetCacheResponse {
     pub tags: Vec<FastStr>,
     pub success: bool,
     pub message: FastStr,
+    pub state: CacheLookupState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -279,6 +342,64 @@ pub struct ListCacheResponse {
     pub message: FastStr,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementCacheRequest {
+    pub key: FastStr,
+    pub delta: i64,
+    pub ttl_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementCacheResponse {
+    pub key: FastStr,
+    pub value: i64,
+    pub success: bool,
+    pub message: FastStr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetCacheBatchRequest {
+    pub keys: Vec<FastStr>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetCacheBatchResponse {
+    pub items: Vec<GetCacheResponse>,
+    pub success: bool,
+    pub message: FastStr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetCacheBatchRequest {
+    pub items: Vec<SetCacheRequest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetCacheBatchResponse {
+    pub items: Vec<SetCacheResponse>,
+    pub success: bool,
+    pub message: FastStr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCacheBatchRequest {
+    pub keys: Vec<FastStr>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCacheBatchItem {
+    pub key: FastStr,
+    pub success: bool,
+    pub message: FastStr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCacheBatchResponse {
+    pub items: Vec<DeleteCacheBatchItem>,
+    pub success: bool,
+    pub message: FastStr,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheItem {
     pub key: FastStr,
@@ -349,6 +470,22 @@ pub trait SyrosService {
         &self,
         request: Request<ListCacheRequest>,
     ) -> Result<Response<ListCacheResponse>, Status>;
+    async fn increment_cache(
+        &self,
+        request: Request<IncrementCacheRequest>,
+    ) -> Result<Response<IncrementCacheResponse>, Status>;
+    async fn get_cache_batch(
+        &self,
+        request: Request<GetCacheBatchRequest>,
+    ) -> Result<Response<GetCacheBatchResponse>, Status>;
+    async fn set_cache_batch(
+        &self,
+        request: Request<SetCacheBatchRequest>,
+    ) -> Result<Response<SetCacheBatchResponse>, Status>;
+    async fn delete_cache_batch(
+        &self,
+        request: Request<DeleteCacheBatchRequest>,
+    ) -> Result<Response<DeleteCacheBatchResponse>, Status>;
 }
 
 #[derive(Clone)]
@@ -15,28 +15,85 @@
 //! - **Multiple APIs**: REST, gRPC, WebSocket, and GraphQL
 //! - **Observability**: Metrics, logging, and tracing
 //!
+//! # Cargo Features
+//!
+//! The coordination primitives in [`core`] are always built. Everything else
+//! can be left out with `default-features = false`:
+//!
+//! - `rest`: REST API, [`server`] and the `syros` binary
+//! - `grpc`: gRPC service and its generated types
+//! - `graphql`: GraphQL endpoint (implies `rest`)
+//! - `websocket`: WebSocket endpoint (implies `rest`)
+//! - `metrics`: Prometheus metrics and the `/metrics` endpoint
+//!
 //! # Quick Start
 //!
+//! The `syros` binary runs the APIs as a server. To use the primitives from
+//! a Rust application instead, embed them with [`SyrosEmbedded`]; saga steps
+//! then run as closures in the same process:
+//!
 //! ```rust
-//! use syros_platform::{server, cli};
+//! use std::time::Duration;
+//! use syros::core::saga_orchestrator::{SagaRequest, SagaStep};
+//! use syros::SyrosEmbedded;
 //!
 //! #[tokio::main]
-//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let cli = cli::parse_args();
-//!     // Start the server with your configuration
+//! async fn main() -> syros::Result<()> {
+//!     let syros = SyrosEmbedded::builder()
+//!         .with_step("inventory", "reserve", |_step, _context| async { Ok(()) })
+//!         .start()
+//!         .await?;
+//!
+//!     let saga = syros
+//!         .run_saga(SagaRequest {
+//!             name: "checkout".to_string(),
+//!             steps: vec![SagaStep {
+//!                 name: "reserve_stock".to_string(),
+//!                 service: "inventory".to_string(),
+//!                 action: "reserve".to_string(),
+//!                 compensation: String::new(),
+//!                 timeout: Duration::from_secs(10),
+//!                 retry_policy: None,
+//!                 payload: None,
+//!                 acquired_locks: vec![],
+//!                 cache_keys: vec![],
+//!                 group: None,
+//!                 executor: None,
+//!             }],
+//!             metadata: None,
+//!             max_duration: None,
+//!             start_at: None,
+//!         })
+//!         .await?;
+//!     assert_eq!(saga.status, "Completed");
+//!
+//!     syros.shutdown().await;
 //!     Ok(())
 //! }
 //! ```
 
+#[cfg(any(feature = "rest", feature = "grpc"))]
 pub mod api;
 pub mod auth;
 pub mod cli;
 pub mod config;
 pub mod core;
+pub mod embedded;
 pub mod errors;
+#[cfg(feature = "grpc")]
 pub mod generated;
+#[cfg(feature = "metrics")]
 pub mod metrics;
+#[cfg(feature = "rest")]
+pub mod seed;
+#[cfg(feature = "rest")]
 pub mod server;
 pub mod storage;
 
+pub use embedded::{EmbeddedConfig, SyrosEmbedded};
 pub use errors::{Result, SyrosError};
+
+/// Compiles and runs the Rust examples of the README.
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
@@ -4,7 +4,10 @@
 //! service that provides distributed locks, saga orchestration, event sourcing,
 //! and caching capabilities for microservices architectures.
 
+use syros::core::event_transfer::{export_to, EventImporter, ImportOptions};
+use syros::core::EventStore;
 use syros::{cli, server};
+use tokio::io::AsyncWriteExt;
 
 /// Main entry point for the Syros application.
 ///
@@ -94,6 +97,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
         }
+        Some(cli::Commands::Events { command }) => {
+            run_events_command(command, cli.quiet).await?;
+        }
         Some(cli::Commands::Info) => {
             println!("Syros - Distributed Coordination Service");
             println!("Version: {}", env!("CARGO_PKG_VERSION"));
@@ -149,3 +155,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Runs an `events` subcommand against the configured Postgres event store.
+async fn run_events_command(
+    command: cli::EventsCommand,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = syros::config::Config::load()?;
+    if let Ok(url) = std::env::var("DATABASE_URL") {
+        config.storage.database.url = url;
+    }
+    let pg_manager = syros::storage::postgres::PostgresManager::new(
+        &config.storage.database.url,
+        config.storage.database.pool_size,
+    )
+    .await?;
+    let event_store = EventStore::new(pg_manager);
+
+    match command {
+        cli::EventsCommand::Export { stream, out } => {
+            let mut writer = tokio::io::BufWriter::new(tokio::fs::File::create(&out).await?);
+            let exported = export_to(&event_store, &stream, &mut writer).await?;
+            writer.flush().await?;
+            if !quiet {
+                println!("Exported {} events from {} to {}", exported, stream, out);
+            }
+        }
+        cli::EventsCommand::Import { input, force } => {
+            let file = tokio::fs::File::open(&input).await?;
+            let summary = EventImporter::new(
+                event_store,
+                ImportOptions {
+                    force_renumber: force,
+                },
+            )
+            .import_from(file)
+            .await?;
+            if !quiet {
+                println!(
+                    "Imported {} events into {} streams from {}",
+                    summary.imported,
+                    summary.streams.len(),
+                    input
+                );
+                if summary.renumbered > 0 {
+                    println!("Renumbered {} events", summary.renumbered);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -3,9 +3,12 @@
 //! This module provides metrics collection using Prometheus for monitoring
 //! the Syros's performance and health.
 
+use crate::core::memory::MemoryUsage;
+use crate::core::task_tracker::TaskCount;
+use prometheus::core::Collector;
 use prometheus::{
-    Counter, CounterVec, Encoder, Gauge, Histogram, HistogramOpts, HistogramVec, Opts, Registry,
-    TextEncoder,
+    Counter, CounterVec, Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, Opts,
+    Registry, TextEncoder,
 };
 use std::sync::Arc;
 use std::time::Instant;
@@ -15,12 +18,16 @@ pub struct Metrics {
     pub http_requests_total: CounterVec,
     pub grpc_requests_total: CounterVec,
     pub websocket_connections_total: Counter,
+    pub websocket_commands_rejected_total: CounterVec,
 
     pub locks_acquired_total: Counter,
     pub locks_released_total: Counter,
+    pub locks_expired_total: Counter,
     pub sagas_started_total: Counter,
     pub sagas_completed_total: Counter,
     pub sagas_failed_total: Counter,
+    pub sagas_compensated_total: Counter,
+    pub sagas_resumed_total: Counter,
     pub events_appended_total: Counter,
     pub cache_hits_total: Counter,
     pub cache_misses_total: Counter,
@@ -29,18 +36,108 @@ pub struct Metrics {
     pub grpc_request_duration: HistogramVec,
     pub lock_operation_duration: HistogramVec,
     pub saga_execution_duration: Histogram,
+    pub saga_step_duration: HistogramVec,
     pub cache_operation_duration: HistogramVec,
 
     pub active_locks: Gauge,
     pub active_sagas: Gauge,
     pub cache_size: Gauge,
+    pub cache_value_bytes: Gauge,
     pub websocket_connections: Gauge,
 
+    pub workers_active: Gauge,
+    pub claims_reassigned_total: Counter,
+    pub idle_state_reclaimed_total: CounterVec,
+    pub lock_queue_jumps_total: CounterVec,
+    pub lock_wait_duration: HistogramVec,
+    pub lock_waiters: Histogram,
+    pub lock_hold_duration: Histogram,
+    pub event_streams: GaugeVec,
+    pub saga_resource_release_failures_total: CounterVec,
+    pub saga_compensations_total: CounterVec,
+    pub saga_dead_letter_size: Gauge,
+    pub cache_hits_by_source_total: CounterVec,
+    pub cache_evictions_total: Counter,
+    pub cache_stampedes_prevented_total: Counter,
+    pub tasks_live: GaugeVec,
+    pub memory_bytes: GaugeVec,
+
+    /// Tokio runtime gauges, when runtime metrics are enabled
+    pub runtime: Option<RuntimeMetrics>,
+
     pub registry: Arc<Registry>,
 }
 
+/// Gauges sampled from the Tokio runtime by [`Metrics::sample_runtime`].
+#[derive(Clone)]
+pub struct RuntimeMetrics {
+    pub workers: Gauge,
+    pub alive_tasks: Gauge,
+    pub global_queue_depth: Gauge,
+    /// Only sampled in builds with `--cfg tokio_unstable`
+    pub budget_forced_yields: Gauge,
+}
+
+impl RuntimeMetrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            workers: Gauge::new("tokio_workers", "Number of Tokio runtime worker threads")?,
+            alive_tasks: Gauge::new("tokio_alive_tasks", "Number of alive Tokio tasks")?,
+            global_queue_depth: Gauge::new(
+                "tokio_global_queue_depth",
+                "Number of tasks waiting in the Tokio global queue",
+            )?,
+            budget_forced_yields: Gauge::new(
+                "tokio_budget_forced_yields",
+                "Times Tokio tasks were forced to yield after exhausting their budget",
+            )?,
+        })
+    }
+
+    fn collectors(&self) -> Vec<Box<dyn Collector>> {
+        vec![
+            Box::new(self.workers.clone()),
+            Box::new(self.alive_tasks.clone()),
+            Box::new(self.global_queue_depth.clone()),
+            Box::new(self.budget_forced_yields.clone()),
+        ]
+    }
+
+    /// Updates the gauges from the runtime behind `handle`.
+    pub fn sample(&self, handle: &tokio::runtime::Handle) {
+        let runtime = handle.metrics();
+        self.workers.set(runtime.num_workers() as f64);
+        self.alive_tasks.set(runtime.num_alive_tasks() as f64);
+        self.global_queue_depth
+            .set(runtime.global_queue_depth() as f64);
+        #[cfg(tokio_unstable)]
+        self.budget_forced_yields
+            .set(runtime.budget_forced_yield_count() as f64);
+    }
+}
+
 impl Metrics {
     pub fn new() -> Result<Self, prometheus::Error> {
+        Self::with_collectors(Vec::new())
+    }
+
+    /// Creates the metrics with process metrics (CPU, RSS, file descriptors,
+    /// start time) and Tokio runtime gauges registered alongside them.
+    pub fn with_runtime_metrics() -> Result<Self, prometheus::Error> {
+        let runtime = RuntimeMetrics::new()?;
+        let mut collectors = runtime.collectors();
+        #[cfg(target_os = "linux")]
+        collectors.push(Box::new(
+            prometheus::process_collector::ProcessCollector::for_self(),
+        ));
+
+        let mut metrics = Self::with_collectors(collectors)?;
+        metrics.runtime = Some(runtime);
+        Ok(metrics)
+    }
+
+    /// Creates the metrics with `collectors` registered in the same registry.
+    pub fn with_collectors(collectors: Vec<Box<dyn Collector>>) -> Result<Self, prometheus::Error> {
         let registry = Arc::new(Registry::new());
 
         let http_requests_total = CounterVec::new(
@@ -56,16 +153,39 @@ impl Metrics {
         let websocket_connections_total =
             Counter::new("websocket_connections_total", "Total WebSocket connections")?;
 
+        let websocket_commands_rejected_total = CounterVec::new(
+            Opts::new(
+                "websocket_commands_rejected_total",
+                "Total WebSocket commands rejected by per-connection limits",
+            ),
+            &["reason"],
+        )?;
+
         let locks_acquired_total = Counter::new("locks_acquired_total", "Total locks acquired")?;
 
         let locks_released_total = Counter::new("locks_released_total", "Total locks released")?;
 
+        let locks_expired_total = Counter::new(
+            "locks_expired_total",
+            "Total locks removed by the expiry sweep without being released",
+        )?;
+
         let sagas_started_total = Counter::new("sagas_started_total", "Total sagas started")?;
 
         let sagas_completed_total = Counter::new("sagas_completed_total", "Total sagas completed")?;
 
         let sagas_failed_total = Counter::new("sagas_failed_total", "Total sagas failed")?;
 
+        let sagas_compensated_total = Counter::new(
+            "sagas_compensated_total",
+            "Total sagas rolled back after failing, timing out or being cancelled",
+        )?;
+
+        let sagas_resumed_total = Counter::new(
+            "sagas_resumed_total",
+            "Total sagas executed again after a retry or a restart",
+        )?;
+
         let events_appended_total = Counter::new("events_appended_total", "Total events appended")?;
 
         let cache_hits_total = Counter::new("cache_hits_total", "Total cache hits")?;
@@ -100,6 +220,15 @@ impl Metrics {
                 .buckets(vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]),
         )?;
 
+        let saga_step_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "saga_step_duration_seconds",
+                "Duration of each attempt of a saga step's action",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            &["step"],
+        )?;
+
         let cache_operation_duration = HistogramVec::new(
             HistogramOpts::new(
                 "cache_operation_duration_seconds",
@@ -112,18 +241,99 @@ impl Metrics {
         let active_locks = Gauge::new("active_locks", "Number of active locks")?;
         let active_sagas = Gauge::new("active_sagas", "Number of active sagas")?;
         let cache_size = Gauge::new("cache_size", "Number of items in cache")?;
+        let cache_value_bytes = Gauge::new(
+            "cache_value_bytes",
+            "Approximate bytes of the live cache values, as serialized JSON",
+        )?;
         let websocket_connections = Gauge::new(
             "websocket_connections",
             "Number of active WebSocket connections",
         )?;
+        let workers_active = Gauge::new("workers_active", "Number of live pull-mode saga workers")?;
+        let claims_reassigned_total = Counter::new(
+            "claims_reassigned_total",
+            "Total saga step claims released for reassignment",
+        )?;
+        let idle_state_reclaimed_total = CounterVec::new(
+            Opts::new(
+                "idle_state_reclaimed_total",
+                "Total idle per-key entries reclaimed by background sweeps",
+            ),
+            &["structure"],
+        )?;
+        let lock_queue_jumps_total = CounterVec::new(
+            Opts::new(
+                "lock_queue_jumps_total",
+                "Total earlier-arriving lock waiters overtaken by a later one",
+            ),
+            &["priority"],
+        )?;
+        let lock_wait_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "lock_wait_duration_seconds",
+                "Time lock requests spent queued before acquiring",
+            )
+            .buckets(vec![0.001, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0]),
+            &["priority"],
+        )?;
+        let lock_waiters = Histogram::with_opts(
+            HistogramOpts::new(
+                "lock_waiters",
+                "Requests queued on a lock key, observed as each request joins the queue",
+            )
+            .buckets(vec![1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0]),
+        )?;
+        let lock_hold_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "lock_hold_duration_seconds",
+                "Time locks were held from acquisition to release",
+            )
+            .buckets(vec![0.001, 0.01, 0.1, 0.5, 1.0, 5.0, 30.0, 60.0, 300.0]),
+        )?;
+        let event_streams = GaugeVec::new(
+            Opts::new(
+                "event_streams",
+                "Number of streams in the in-memory event stream directory",
+            ),
+            &["state"],
+        )?;
+        let saga_resource_release_failures_total = CounterVec::new(
+            Opts::new(
+                "saga_resource_release_failures_total",
+                "Total step locks and cache keys that saga compensation failed to release",
+            ),
+            &["resource"],
+        )?;
+        let saga_compensations_total = CounterVec::new(
+            Opts::new(
+                "saga_compensations_total",
+                "Total step compensations, by whether they succeeded or failed once retries were exhausted",
+            ),
+            &["outcome"],
+        )?;
+        let saga_dead_letter_size = Gauge::new(
+            "saga_dead_letter_size",
+            "Number of dead-lettered sagas not yet requeued or acknowledged",
+        )?;
+        let cache_hits_by_source_total = CounterVec::new(
+            Opts::new(
+                "cache_hits_by_source_total",
+                "Total cache reads served, by the layer that served them",
+            ),
+            &["source"],
+        )?;
         registry.register(Box::new(http_requests_total.clone()))?;
         registry.register(Box::new(grpc_requests_total.clone()))?;
         registry.register(Box::new(websocket_connections_total.clone()))?;
+        registry.register(Box::new(websocket_commands_rejected_total.clone()))?;
         registry.register(Box::new(locks_acquired_total.clone()))?;
         registry.register(Box::new(locks_released_total.clone()))?;
+        registry.register(Box::new(locks_expired_total.clone()))?;
         registry.register(Box::new(sagas_started_total.clone()))?;
         registry.register(Box::new(sagas_completed_total.clone()))?;
         registry.register(Box::new(sagas_failed_total.clone()))?;
+        registry.register(Box::new(sagas_compensated_total.clone()))?;
+        registry.register(Box::new(sagas_resumed_total.clone()))?;
         registry.register(Box::new(events_appended_total.clone()))?;
         registry.register(Box::new(cache_hits_total.clone()))?;
         registry.register(Box::new(cache_misses_total.clone()))?;
@@ -131,21 +341,65 @@ impl Metrics {
         registry.register(Box::new(grpc_request_duration.clone()))?;
         registry.register(Box::new(lock_operation_duration.clone()))?;
         registry.register(Box::new(saga_execution_duration.clone()))?;
+        registry.register(Box::new(saga_step_duration.clone()))?;
         registry.register(Box::new(cache_operation_duration.clone()))?;
         registry.register(Box::new(active_locks.clone()))?;
         registry.register(Box::new(active_sagas.clone()))?;
         registry.register(Box::new(cache_size.clone()))?;
+        registry.register(Box::new(cache_value_bytes.clone()))?;
         registry.register(Box::new(websocket_connections.clone()))?;
+        registry.register(Box::new(workers_active.clone()))?;
+        registry.register(Box::new(claims_reassigned_total.clone()))?;
+        registry.register(Box::new(idle_state_reclaimed_total.clone()))?;
+        registry.register(Box::new(lock_queue_jumps_total.clone()))?;
+        registry.register(Box::new(lock_wait_duration.clone()))?;
+        registry.register(Box::new(lock_waiters.clone()))?;
+        registry.register(Box::new(lock_hold_duration.clone()))?;
+        registry.register(Box::new(event_streams.clone()))?;
+        registry.register(Box::new(saga_resource_release_failures_total.clone()))?;
+        registry.register(Box::new(saga_compensations_total.clone()))?;
+        registry.register(Box::new(saga_dead_letter_size.clone()))?;
+        let tasks_live = GaugeVec::new(
+            Opts::new("tasks_live", "Number of live tracked tasks, by task name"),
+            &["name"],
+        )?;
+        registry.register(Box::new(cache_hits_by_source_total.clone()))?;
+        let cache_evictions_total = Counter::new(
+            "cache_evictions_total",
+            "Total cache entries evicted to stay within the entry limit",
+        )?;
+        registry.register(Box::new(cache_evictions_total.clone()))?;
+        let cache_stampedes_prevented_total = Counter::new(
+            "cache_stampedes_prevented_total",
+            "Total cache fetches served a value another caller computed meanwhile",
+        )?;
+        registry.register(Box::new(cache_stampedes_prevented_total.clone()))?;
+        registry.register(Box::new(tasks_live.clone()))?;
+        let memory_bytes = GaugeVec::new(
+            Opts::new(
+                "syros_memory_bytes",
+                "Estimated bytes held in memory, by component",
+            ),
+            &["component"],
+        )?;
+        registry.register(Box::new(memory_bytes.clone()))?;
+        for collector in collectors {
+            registry.register(collector)?;
+        }
 
         Ok(Metrics {
             http_requests_total,
             grpc_requests_total,
             websocket_connections_total,
+            websocket_commands_rejected_total,
             locks_acquired_total,
             locks_released_total,
+            locks_expired_total,
             sagas_started_total,
             sagas_completed_total,
             sagas_failed_total,
+            sagas_compensated_total,
+            sagas_resumed_total,
             events_appended_total,
             cache_hits_total,
             cache_misses_total,
@@ -153,11 +407,30 @@ impl Metrics {
             grpc_request_duration,
             lock_operation_duration,
             saga_execution_duration,
+            saga_step_duration,
             cache_operation_duration,
             active_locks,
             active_sagas,
             cache_size,
+            cache_value_bytes,
             websocket_connections,
+            workers_active,
+            claims_reassigned_total,
+            idle_state_reclaimed_total,
+            lock_queue_jumps_total,
+            lock_wait_duration,
+            lock_waiters,
+            lock_hold_duration,
+            event_streams,
+            saga_resource_release_failures_total,
+            saga_compensations_total,
+            saga_dead_letter_size,
+            cache_hits_by_source_total,
+            cache_evictions_total,
+            cache_stampedes_prevented_total,
+            tasks_live,
+            memory_bytes,
+            runtime: None,
             registry,
         })
     }
@@ -196,6 +469,12 @@ impl Metrics {
         self.saga_execution_duration.observe(duration);
     }
 
+    pub fn record_saga_step(&self, step: &str, duration: f64) {
+        self.saga_step_duration
+            .with_label_values(&[step])
+            .observe(duration);
+    }
+
     pub fn increment_locks_acquired(&self) {
         self.locks_acquired_total.inc();
         self.active_locks.inc();
@@ -221,6 +500,16 @@ impl Metrics {
         self.active_sagas.dec();
     }
 
+    pub fn increment_sagas_compensated(&self) {
+        self.sagas_compensated_total.inc();
+        self.active_sagas.dec();
+    }
+
+    pub fn increment_sagas_resumed(&self) {
+        self.sagas_resumed_total.inc();
+        self.active_sagas.inc();
+    }
+
     pub fn increment_events_appended(&self) {
         self.events_appended_total.inc();
     }
@@ -242,10 +531,118 @@ impl Metrics {
         self.websocket_connections.dec();
     }
 
+    pub fn increment_websocket_commands_rejected(&self, reason: &str) {
+        self.websocket_commands_rejected_total
+            .with_label_values(&[reason])
+            .inc();
+    }
+
     pub fn set_cache_size(&self, size: f64) {
         self.cache_size.set(size);
     }
 
+    pub fn set_cache_value_bytes(&self, bytes: u64) {
+        self.cache_value_bytes.set(bytes as f64);
+    }
+
+    pub fn set_workers_active(&self, count: f64) {
+        self.workers_active.set(count);
+    }
+
+    pub fn increment_claims_reassigned(&self, count: u64) {
+        self.claims_reassigned_total.inc_by(count as f64);
+    }
+
+    pub fn increment_locks_expired(&self, count: u64) {
+        self.locks_expired_total.inc_by(count as f64);
+    }
+
+    pub fn increment_idle_state_reclaimed(&self, structure: &str, count: u64) {
+        self.idle_state_reclaimed_total
+            .with_label_values(&[structure])
+            .inc_by(count as f64);
+    }
+
+    pub fn record_lock_wait(&self, priority: &str, duration: f64, queue_jumps: u64) {
+        self.lock_wait_duration
+            .with_label_values(&[priority])
+            .observe(duration);
+        self.lock_queue_jumps_total
+            .with_label_values(&[priority])
+            .inc_by(queue_jumps as f64);
+    }
+
+    pub fn observe_lock_waiters(&self, waiters: usize) {
+        self.lock_waiters.observe(waiters as f64);
+    }
+
+    pub fn observe_lock_hold(&self, duration: f64) {
+        self.lock_hold_duration.observe(duration);
+    }
+
+    pub fn set_event_streams(&self, active: usize, archived: usize) {
+        self.event_streams
+            .with_label_values(&["active"])
+            .set(active as f64);
+        self.event_streams
+            .with_label_values(&["archived"])
+            .set(archived as f64);
+    }
+
+    pub fn increment_saga_resource_release_failures(&self, resource: &str) {
+        self.saga_resource_release_failures_total
+            .with_label_values(&[resource])
+            .inc();
+    }
+
+    pub fn increment_saga_compensations(&self, outcome: &str) {
+        self.saga_compensations_total
+            .with_label_values(&[outcome])
+            .inc();
+    }
+
+    pub fn set_saga_dead_letter_size(&self, size: usize) {
+        self.saga_dead_letter_size.set(size as f64);
+    }
+
+    pub fn increment_cache_source_hits(&self, source: &str) {
+        self.cache_hits_by_source_total
+            .with_label_values(&[source])
+            .inc();
+    }
+
+    pub fn increment_cache_evictions(&self, count: u64) {
+        self.cache_evictions_total.inc_by(count as f64);
+    }
+
+    pub fn increment_cache_stampedes_prevented(&self) {
+        self.cache_stampedes_prevented_total.inc();
+    }
+
+    pub fn set_tasks_live(&self, tasks: &[TaskCount]) {
+        for task in tasks {
+            self.tasks_live
+                .with_label_values(&[&task.name])
+                .set(task.live as f64);
+        }
+    }
+
+    pub fn set_memory_usage(&self, usage: &MemoryUsage) {
+        for (component, bytes) in usage.components() {
+            self.memory_bytes
+                .with_label_values(&[component])
+                .set(bytes as f64);
+        }
+    }
+
+    /// Samples the Tokio runtime gauges from the current runtime, if enabled.
+    pub fn sample_runtime(&self) {
+        if let (Some(runtime), Ok(handle)) = (&self.runtime, tokio::runtime::Handle::try_current())
+        {
+            runtime.sample(&handle);
+        }
+    }
+
     pub fn get_metrics(&self) -> Result<String, prometheus::Error> {
         let mut buffer = Vec::new();
         let encoder = TextEncoder::new();
@@ -255,6 +652,23 @@ impl Metrics {
     }
 }
 
+/// Metrics a component reports to, shared by all of its clones.
+///
+/// Setting them on one clone applies to every other, including those taken
+/// earlier, e.g. by the saga orchestrator before the server set up metrics.
+#[derive(Clone, Default)]
+pub struct MetricsHandle(Arc<std::sync::RwLock<Option<Arc<Metrics>>>>);
+
+impl MetricsHandle {
+    pub fn set(&self, metrics: Arc<Metrics>) {
+        *self.0.write().unwrap() = Some(metrics);
+    }
+
+    pub fn get(&self) -> Option<Arc<Metrics>> {
+        self.0.read().unwrap().clone()
+    }
+}
+
 pub struct MetricsTimer {
     start: Instant,
     metrics: Arc<Metrics>,
@@ -0,0 +1,269 @@
+//! Seed data for development environments.
+//!
+//! With `dev.seed_enabled`, the users, API keys, saga templates, cache
+//! entries and event files listed under `[dev.seed]` and in `dev.seed_file`
+//! are created at startup. Seeding is idempotent: anything that already
+//! exists is left untouched, so restarting against persistent storage does
+//! not duplicate resources. It refuses to run when `environment` is
+//! `production`.
+//!
+//! Event files are NDJSON exports, imported with the same
+//! [`EventImporter`] as `syros events import`.
+
+use crate::api::handlers::saga_handlers::StartSagaRequest;
+use crate::api::rest::ApiState;
+use crate::auth::api_keys::CreateApiKeyRequest;
+use crate::auth::rbac::Role;
+use crate::config::{Config, Environment};
+use crate::core::cache_manager::{CacheRequest, CacheSetMode};
+use crate::core::event_transfer::{EventImporter, ImportOptions};
+use crate::core::saga_orchestrator::REQUEST_ID_METADATA_KEY;
+use crate::core::saga_plan::SagaValidationError;
+use crate::{Result, SyrosError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Resources created by seeding.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeedData {
+    pub users: Vec<SeedUser>,
+    pub api_keys: Vec<SeedApiKey>,
+    /// Saga definitions, registered under their name
+    pub saga_templates: Vec<StartSagaRequest>,
+    pub cache: Vec<SeedCacheEntry>,
+    /// NDJSON event files; a file is imported only if none of its streams exist
+    pub event_files: Vec<String>,
+}
+
+/// A user to create, identified by its username.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedUser {
+    pub username: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub roles: Vec<Role>,
+}
+
+/// An API key with a fixed value, identified by the key's prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedApiKey {
+    pub name: String,
+    /// Full key, `sk_<8 letters or digits>_<secret>`
+    pub key: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// A cache entry to create; existing entries are not overwritten.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedCacheEntry {
+    pub key: String,
+    pub value: serde_json::Value,
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// What a seeding run created.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeedReport {
+    /// Usernames of the users created
+    pub users: Vec<String>,
+    /// Names of the API keys created
+    pub api_keys: Vec<String>,
+    pub saga_templates: Vec<String>,
+    pub cache_keys: Vec<String>,
+    /// Streams imported from event files
+    pub event_streams: Vec<String>,
+    /// Resources that already existed and were left unchanged
+    pub skipped: usize,
+}
+
+impl SeedReport {
+    /// Number of resources created.
+    pub fn created(&self) -> usize {
+        self.users.len()
+            + self.api_keys.len()
+            + self.saga_templates.len()
+            + self.cache_keys.len()
+            + self.event_streams.len()
+    }
+}
+
+impl SeedData {
+    /// Reads seed data from a TOML file with the layout of `[dev.seed]`.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            SyrosError::ConfigError(format!("Failed to read seed file {}: {}", path, e))
+        })?;
+        toml::from_str(&content).map_err(|e| {
+            SyrosError::ConfigError(format!("Failed to parse seed file {}: {}", path, e))
+        })
+    }
+
+    /// Appends the resources of `other`.
+    pub fn extend(&mut self, other: SeedData) {
+        self.users.extend(other.users);
+        self.api_keys.extend(other.api_keys);
+        self.saga_templates.extend(other.saga_templates);
+        self.cache.extend(other.cache);
+        self.event_files.extend(other.event_files);
+    }
+}
+
+/// Applies the seed data configured under `[dev]`, if seeding is enabled.
+///
+/// Returns `None` when seeding is disabled, and fails without creating
+/// anything when the environment is production.
+pub async fn apply_configured(state: &ApiState) -> Result<Option<SeedReport>> {
+    let config: &Config = &state.config;
+    if !config.dev.seed_enabled {
+        return Ok(None);
+    }
+    if config.environment == Environment::Production {
+        return Err(SyrosError::ConfigError(
+            "dev.seed_enabled is set but the environment is production; refusing to seed"
+                .to_string(),
+        ));
+    }
+
+    let mut seed = config.dev.seed.clone();
+    if let Some(path) = &config.dev.seed_file {
+        seed.extend(SeedData::load(path)?);
+    }
+    apply(state, &seed).await.map(Some)
+}
+
+/// Creates every resource in `seed` that does not exist yet.
+pub async fn apply(state: &ApiState, seed: &SeedData) -> Result<SeedReport> {
+    let mut report = SeedReport::default();
+
+    {
+        let rbac = &state.rbac_manager;
+        for user in &seed.users {
+            if rbac.get_user_by_username(&user.username).await?.is_some() {
+                report.skipped += 1;
+                continue;
+            }
+            rbac.create_user(
+                user.username.clone(),
+                user.email.clone(),
+                user.roles.clone(),
+            )
+            .await?;
+            report.users.push(user.username.clone());
+        }
+    }
+
+    let api_keys = &state.auth_middleware.api_key_manager;
+    for key in &seed.api_keys {
+        let request = CreateApiKeyRequest {
+            name: key.name.clone(),
+            description: key.description.clone(),
+            permissions: key.permissions.clone(),
+            expires_in_days: None,
+        };
+        match api_keys.import_api_key(&key.key, request).await? {
+            Some(_) => report.api_keys.push(key.name.clone()),
+            None => report.skipped += 1,
+        }
+    }
+
+    for template in &seed.saga_templates {
+        let name = template.name.clone();
+        let mut definition = template
+            .clone()
+            .into_saga_request(String::new(), None, &state.metadata_policy, None)
+            .map_err(|errors| invalid_template(&name, &errors))?;
+        // Every instance started from the template gets its own request ID.
+        if let Some(metadata) = definition.metadata.as_mut() {
+            metadata.remove(REQUEST_ID_METADATA_KEY);
+        }
+
+        let plan = state.saga_orchestrator.plan_saga(&definition).await;
+        if !plan.is_valid() {
+            return Err(invalid_template(&name, &plan.errors));
+        }
+        if state
+            .saga_definitions
+            .register_if_absent(&name, definition)
+            .await?
+        {
+            report.saga_templates.push(name);
+        } else {
+            report.skipped += 1;
+        }
+    }
+
+    for entry in &seed.cache {
+        let created = state
+            .cache_manager
+            .set(CacheRequest {
+                key: entry.key.clone(),
+                value: entry.value.clone(),
+                ttl: entry.ttl_seconds.map(Duration::from_secs),
+                tags: entry.tags.clone(),
+                mode: CacheSetMode::CreateOnly,
+                created_by: None,
+                cache_negative: false,
+            })
+            .await;
+        match created {
+            Ok(_) => report.cache_keys.push(entry.key.clone()),
+            Err(SyrosError::Conflict(_)) => report.skipped += 1,
+            Err(e) => return Err(e),
+        }
+    }
+
+    for path in &seed.event_files {
+        let content = tokio::fs::read(path).await.map_err(|e| {
+            SyrosError::ConfigError(format!("Failed to read seed event file {}: {}", path, e))
+        })?;
+        if any_stream_exists(state, &content).await? {
+            report.skipped += 1;
+            continue;
+        }
+
+        let mut importer = EventImporter::new(state.event_store.clone(), ImportOptions::default());
+        importer.import_bytes(&content).await?;
+        let summary = importer.finish().await?;
+        report.event_streams.extend(summary.streams);
+    }
+
+    Ok(report)
+}
+
+/// Whether any stream an NDJSON event file writes to already has events.
+async fn any_stream_exists(state: &ApiState, ndjson: &[u8]) -> Result<bool> {
+    for line in ndjson.split(|b| *b == b'\n') {
+        let Ok(event) = serde_json::from_slice::<serde_json::Value>(line) else {
+            continue;
+        };
+        if let Some(stream_id) = event.get("stream_id").and_then(|id| id.as_str()) {
+            if state.event_store.get_stream_version(stream_id).await? > 0 {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+fn invalid_template(name: &str, errors: &[SagaValidationError]) -> SyrosError {
+    let errors: Vec<String> = errors
+        .iter()
+        .map(|e| match &e.step {
+            Some(step) => format!("step {} {}: {}", step, e.field, e.message),
+            None => format!("{}: {}", e.field, e.message),
+        })
+        .collect();
+    SyrosError::ConfigError(format!(
+        "Seed saga template {} is invalid: {}",
+        name,
+        errors.join("; ")
+    ))
+}
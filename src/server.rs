@@ -4,21 +4,34 @@
 //! the various server components (REST, gRPC, WebSocket) and their
 //! associated services.
 
+#[cfg(feature = "grpc")]
 use crate::api::grpc::SyrosGrpcService;
 use crate::api::rest::{create_rest_router, ApiState};
+#[cfg(feature = "websocket")]
 use crate::api::websocket::WebSocketService;
 use crate::auth::AuthMiddleware;
 use crate::cli::ServerType;
-use crate::config::Config;
+use crate::config::{CacheStorage, Config, EventStorage, SagaStorage};
+#[cfg(feature = "metrics")]
+use crate::core::memory::MemoryUsage;
+use crate::core::saga_executors::WORKER_EXECUTOR;
+use crate::core::saga_http::HttpStepClient;
+use crate::core::saga_results::StepResultLimits;
 use crate::core::{
-    CacheManager, EventStore, LockManager, SagaOrchestrator, ServiceCheck, ServiceDiscovery,
-    ServiceRegistration,
+    CacheManager, ComponentRegistry, DeadLetterQueue, EventStore, LockManager, MetadataPolicy,
+    NamespaceFreezes, Projections, SagaDefinitions, SagaOrchestrator, SagaWorkerRegistry,
+    ServiceCheck, ServiceDiscovery, ServiceRegistration, TaskSpawner, TaskTracker,
 };
+#[cfg(feature = "metrics")]
 use crate::metrics::Metrics;
+use crate::storage::redis::RedisManager;
 use axum;
+use std::future::Future;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
+use tokio::sync::watch;
 
 /// Starts the Syros server with the specified configuration.
 ///
@@ -50,13 +63,19 @@ pub async fn start_server(
     interface: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut config = Config::load().unwrap_or_else(|_| Config {
+        environment: crate::config::Environment::default(),
         server: crate::config::ServerConfig {
             port,
             grpc_port,
             websocket_port,
             host: host.clone(),
+            drain_timeout_seconds: 30,
         },
         storage: crate::config::StorageConfig {
+            locks: crate::config::LockStorage::default(),
+            sagas: crate::config::SagaStorage::default(),
+            events: crate::config::EventStorage::default(),
+            cache: crate::config::CacheStorage::default(),
             redis: crate::config::RedisConfig {
                 url: "redis://localhost:6379".to_string(),
                 pool_size: 10,
@@ -85,7 +104,23 @@ pub async fn start_server(
             service_id: "syros-1".to_string(),
             health_check_interval: 10,
             tags: vec!["syros".to_string(), "platform".to_string()],
+            auto_instance_id: false,
+            warning_after_failures: None,
+            critical_after_failures: None,
         },
+        cache: crate::config::CacheConfig::default(),
+        websocket: crate::config::WebSocketConfig::default(),
+        sagas: crate::config::SagaConfig::default(),
+        events: crate::config::EventConfig::default(),
+        timeouts: crate::config::TimeoutConfig::default(),
+        background_tasks: crate::config::BackgroundTasksConfig::default(),
+        metrics: crate::config::MetricsConfig::default(),
+        metadata: crate::config::MetadataConfig::default(),
+        locks: crate::config::LockConfig::default(),
+        limits: crate::config::LimitsConfig::default(),
+        services: Default::default(),
+        apis: crate::config::ApisConfig::default(),
+        dev: crate::config::DevConfig::default(),
     });
 
     // Override with environment variables if present
@@ -98,10 +133,16 @@ pub async fn start_server(
 
     let should_start_rest =
         servers.contains(&ServerType::Rest) || servers.contains(&ServerType::All);
-    let should_start_grpc =
-        servers.contains(&ServerType::Grpc) || servers.contains(&ServerType::All);
-    let should_start_websocket =
-        servers.contains(&ServerType::Websocket) || servers.contains(&ServerType::All);
+    #[cfg(feature = "grpc")]
+    let should_start_grpc = config.apis.grpc
+        && (servers.contains(&ServerType::Grpc) || servers.contains(&ServerType::All));
+    #[cfg(not(feature = "grpc"))]
+    let should_start_grpc = false;
+    #[cfg(feature = "websocket")]
+    let should_start_websocket = config.apis.websocket
+        && (servers.contains(&ServerType::Websocket) || servers.contains(&ServerType::All));
+    #[cfg(not(feature = "websocket"))]
+    let should_start_websocket = false;
 
     if verbose {
         println!("Starting Syros...");
@@ -127,35 +168,23 @@ pub async fn start_server(
         }
     }
 
-    let redis_manager = crate::storage::redis::RedisManager::new(&config.storage.redis.url)
-        .map_err(|e| format!("Failed to initialize Redis Manager: {}", e))?;
-
-    let lock_manager = LockManager::new(redis_manager);
-    let pg_manager = crate::storage::postgres::PostgresManager::new(
-        &config.storage.database.url,
-        config.storage.database.pool_size,
-    )
-    .await
-    .map_err(|e| format!("Failed to initialize Postgres Manager: {}", e))?;
-
-    let saga_orchestrator = SagaOrchestrator::new(pg_manager.clone());
-    let event_store = EventStore::new(pg_manager);
-    let cache_manager = CacheManager::new();
+    let mut services = CoreServices::connect(&config, verbose).await?;
 
     if verbose {
         println!("Core components initialized");
     }
 
-    let mut service_discovery = if config.service_discovery.enabled {
+    let service_discovery = if config.service_discovery.enabled {
         match ServiceDiscovery::new(&config.service_discovery.consul_url) {
             Ok(sd) => {
+                let sd = sd.with_health_thresholds(&config.service_discovery);
                 if verbose {
                     println!(
                         "Service Discovery initialized with Consul at {}",
                         config.service_discovery.consul_url
                     );
                 }
-                Some(sd)
+                Some(Arc::new(sd))
             }
             Err(e) => {
                 eprintln!("Error initializing Service Discovery: {}", e);
@@ -172,49 +201,52 @@ pub async fn start_server(
         None
     };
 
-    let metrics = Arc::new(
-        Metrics::new()
-            .map_err(|e| {
-                eprintln!("Error initializing metrics: {}", e);
-                std::process::exit(1);
-            })
-            .unwrap(),
-    );
-
-    let websocket_service = Arc::new(WebSocketService::new(
-        lock_manager.clone(),
-        saga_orchestrator.clone(),
-        event_store.clone(),
-        cache_manager.clone(),
-    ));
-
-    let auth_middleware = AuthMiddleware::new(&config.security.jwt_secret);
-    let rbac_manager = Arc::new(tokio::sync::Mutex::new(crate::auth::RBACManager::new()));
+    if let Some(sd) = &service_discovery {
+        services.saga_orchestrator = services
+            .saga_orchestrator
+            .with_service_discovery(sd.clone());
+    }
+    services.service_discovery = service_discovery.clone();
+
+    let api_state = build_api_state(config.clone(), services)?;
+    if config.sagas.recover_on_startup {
+        match api_state.saga_orchestrator.recover_pending_sagas().await {
+            Ok(recovered) => {
+                if !quiet && !recovered.is_empty() {
+                    println!("Recovered {} sagas left active", recovered.len());
+                }
+            }
+            Err(e) => eprintln!("Error recovering sagas: {}", e),
+        }
+    }
+    if let Some(report) = crate::seed::apply_configured(&api_state).await? {
+        if !quiet {
+            println!(
+                "Seed data applied: {} created ({} users, {} API keys, {} saga templates, \
+                 {} cache entries, {} event streams), {} already present",
+                report.created(),
+                report.users.len(),
+                report.api_keys.len(),
+                report.saga_templates.len(),
+                report.cache_keys.len(),
+                report.event_streams.len(),
+                report.skipped
+            );
+        }
+    }
 
-    let api_state = ApiState {
-        config: config.clone(),
-        lock_manager,
-        saga_orchestrator,
-        event_store,
-        cache_manager,
-        websocket_service: websocket_service.clone(),
-        metrics: metrics.clone(),
-        auth_middleware,
-        rbac_manager,
-    };
+    let background_tasks = Arc::new(tokio::sync::Mutex::new(spawn_background_tasks(&api_state)?));
+    #[cfg(unix)]
+    reload_background_tasks_on_sighup(&api_state.tasks, background_tasks.clone());
 
     let app = create_rest_router(api_state.clone());
+    #[cfg(feature = "grpc")]
+    let grpc_service = build_grpc_service(&api_state);
 
-    let grpc_service = SyrosGrpcService::new(
-        api_state.lock_manager.clone(),
-        api_state.saga_orchestrator.clone(),
-        api_state.event_store.clone(),
-        api_state.cache_manager.clone(),
-    );
-
-    if let Some(ref mut sd) = service_discovery {
+    let mut shutdown = Shutdown::new(api_state.tasks.clone(), config.server.drain_timeout());
+    if let Some(sd) = &service_discovery {
         let service_registration = ServiceRegistration {
-            id: config.service_discovery.service_id.clone(),
+            id: api_state.instance_id.clone(),
             name: config.service_discovery.service_name.clone(),
             address: config.server.host.clone(),
             port: config.server.port,
@@ -233,11 +265,14 @@ pub async fn start_server(
 
         if let Err(e) = sd.register_service(service_registration).await {
             eprintln!("Error registering service in Service Discovery: {}", e);
-        } else if verbose {
-            println!(
-                "Service registered in Service Discovery: {} ({})",
-                config.service_discovery.service_name, config.service_discovery.service_id
-            );
+        } else {
+            if !quiet {
+                println!(
+                    "Service registered in Service Discovery: {} ({})",
+                    config.service_discovery.service_name, api_state.instance_id
+                );
+            }
+            shutdown = shutdown.with_registration(sd.clone(), api_state.instance_id.clone());
         }
     }
 
@@ -256,12 +291,15 @@ pub async fn start_server(
             println!("REST API documentation available at:");
             println!("   - Health: http://{}/health", rest_addr);
             println!("   - Ready: http://{}/ready", rest_addr);
+            #[cfg(feature = "metrics")]
             println!("   - Metrics: http://{}/metrics", rest_addr);
             println!("   - REST API: http://{}/api/v1/", rest_addr);
         }
 
-        let rest_task = tokio::spawn(async move {
-            let rest_server = axum::serve(rest_listener, app);
+        // Not a background task: draining waits for its in-flight requests.
+        let stopped = shutdown.stopped();
+        let rest_task = api_state.tasks.spawn("rest_server", async move {
+            let rest_server = axum::serve(rest_listener, app).with_graceful_shutdown(stopped);
             if let Err(e) = rest_server.await {
                 eprintln!("REST server error: {}", e);
             }
@@ -269,6 +307,7 @@ pub async fn start_server(
         tasks.push(rest_task);
     }
 
+    #[cfg(feature = "grpc")]
     if should_start_grpc {
         let grpc_addr: SocketAddr =
             format!("{}:{}", config.server.host, config.server.grpc_port).parse()?;
@@ -288,8 +327,12 @@ pub async fn start_server(
             }
         }
 
-        let grpc_task = tokio::spawn(async move {
-            if let Err(e) = grpc_service.start_grpc_server(grpc_addr).await {
+        let stopped = shutdown.stopped();
+        let grpc_task = api_state.tasks.spawn("grpc_server", async move {
+            if let Err(e) = grpc_service
+                .start_grpc_server_with_shutdown(grpc_addr, stopped)
+                .await
+            {
                 eprintln!("gRPC server error: {}", e);
             }
         });
@@ -315,53 +358,476 @@ pub async fn start_server(
         return Ok(());
     }
 
-    match tasks.len() {
-        1 => {
-            if let Some(task) = tasks.into_iter().next() {
-                let _ = task.await;
+    let servers = async move {
+        match tasks.len() {
+            1 => {
+                if let Some(task) = tasks.into_iter().next() {
+                    let _ = task.await;
+                }
+            }
+            2 => {
+                let mut tasks_iter = tasks.into_iter();
+                let task1 = tasks_iter.next().unwrap();
+                let task2 = tasks_iter.next().unwrap();
+
+                tokio::select! {
+                    _ = task1 => {},
+                    _ = task2 => {},
+                }
+            }
+            3 => {
+                let mut tasks_iter = tasks.into_iter();
+                let task1 = tasks_iter.next().unwrap();
+                let task2 = tasks_iter.next().unwrap();
+                let task3 = tasks_iter.next().unwrap();
+
+                tokio::select! {
+                    _ = task1 => {},
+                    _ = task2 => {},
+                    _ = task3 => {},
+                }
+            }
+            _ => {
+                let mut tasks_iter = tasks.into_iter();
+                let task1 = tasks_iter.next().unwrap();
+                let task2 = tasks_iter.next().unwrap();
+                let task3 = tasks_iter.next().unwrap();
+                let remaining: Vec<_> = tasks_iter.collect();
+
+                tokio::select! {
+                    _ = task1 => {},
+                    _ = task2 => {},
+                    _ = task3 => {},
+                    _ = async {
+                        for task in remaining {
+                            let _ = task.await;
+                        }
+                    } => {},
+                }
             }
         }
-        2 => {
-            let mut tasks_iter = tasks.into_iter();
-            let task1 = tasks_iter.next().unwrap();
-            let task2 = tasks_iter.next().unwrap();
-
-            tokio::select! {
-                _ = task1 => {},
-                _ = task2 => {},
+    };
+
+    tokio::select! {
+        _ = servers => {},
+        _ = shutdown_signal() => {
+            if !quiet {
+                println!("Shutting down...");
             }
+        },
+    }
+
+    let still_running = shutdown.run().await;
+    if still_running > 0 {
+        eprintln!(
+            "Stopping with {} tasks still running after {}s",
+            still_running, config.server.drain_timeout_seconds
+        );
+    }
+
+    Ok(())
+}
+
+/// Resolves on `SIGINT` (Ctrl-C) or, on Unix, `SIGTERM`.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+                return;
+            }
+            Err(e) => eprintln!("Error installing SIGTERM handler: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Ordered shutdown of a running server.
+///
+/// [`run`](Self::run) first deregisters the instance from service
+/// discovery, so no new traffic is routed to it, then stops the servers from
+/// accepting connections and waits for in-flight requests and the remaining
+/// tasks to finish.
+pub struct Shutdown {
+    stop: watch::Sender<bool>,
+    registration: Option<(Arc<ServiceDiscovery>, String)>,
+    tasks: TaskTracker,
+    drain_timeout: Duration,
+}
+
+impl Shutdown {
+    /// Drains `tasks` for up to `drain_timeout` once the servers stop.
+    pub fn new(tasks: TaskTracker, drain_timeout: Duration) -> Self {
+        let (stop, _) = watch::channel(false);
+        Self {
+            stop,
+            registration: None,
+            tasks,
+            drain_timeout,
         }
-        3 => {
-            let mut tasks_iter = tasks.into_iter();
-            let task1 = tasks_iter.next().unwrap();
-            let task2 = tasks_iter.next().unwrap();
-            let task3 = tasks_iter.next().unwrap();
-
-            tokio::select! {
-                _ = task1 => {},
-                _ = task2 => {},
-                _ = task3 => {},
+    }
+
+    /// Deregisters `instance_id` from `discovery` before the servers stop.
+    pub fn with_registration(
+        mut self,
+        discovery: Arc<ServiceDiscovery>,
+        instance_id: String,
+    ) -> Self {
+        self.registration = Some((discovery, instance_id));
+        self
+    }
+
+    /// Resolves once the servers are told to stop accepting connections;
+    /// pass it to their graceful shutdown.
+    pub fn stopped(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut stop = self.stop.subscribe();
+        async move {
+            let _ = stop.wait_for(|stop| *stop).await;
+        }
+    }
+
+    /// Deregisters the instance, stops the servers and waits up to the drain
+    /// timeout for in-flight requests and tasks.
+    ///
+    /// Returns the number of tasks still live when it gave up. A failed
+    /// deregistration is logged and does not hold up the rest.
+    pub async fn run(self) -> usize {
+        if let Some((discovery, instance_id)) = &self.registration {
+            match discovery.deregister_service(instance_id).await {
+                Ok(()) => tracing::info!(
+                    "Service deregistered from Service Discovery: {}",
+                    instance_id
+                ),
+                Err(e) => eprintln!("Error deregistering service from Service Discovery: {}", e),
             }
         }
-        _ => {
-            let mut tasks_iter = tasks.into_iter();
-            let task1 = tasks_iter.next().unwrap();
-            let task2 = tasks_iter.next().unwrap();
-            let task3 = tasks_iter.next().unwrap();
-            let remaining: Vec<_> = tasks_iter.collect();
-
-            tokio::select! {
-                _ = task1 => {},
-                _ = task2 => {},
-                _ = task3 => {},
-                _ = async {
-                    for task in remaining {
-                        let _ = task.await;
-                    }
-                } => {},
+        self.stop.send_replace(true);
+        self.tasks.drain(self.drain_timeout).await
+    }
+}
+
+/// Managers behind the REST, gRPC and WebSocket APIs.
+pub struct CoreServices {
+    pub lock_manager: LockManager,
+    pub saga_orchestrator: SagaOrchestrator,
+    pub dead_letters: DeadLetterQueue,
+    pub event_store: EventStore,
+    pub cache_manager: CacheManager,
+    /// Named saga definitions, kept with the sagas
+    pub saga_definitions: SagaDefinitions,
+    /// Projections of the event feed, kept with the events
+    pub projections: Projections,
+    /// Tasks spawned by the managers
+    pub tasks: TaskTracker,
+    /// Service registry, when discovery is enabled
+    pub service_discovery: Option<Arc<ServiceDiscovery>>,
+}
+
+impl CoreServices {
+    /// Connects the managers to the Redis and Postgres instances in `config`.
+    ///
+    /// Restores the saga dead-letter queue and, if configured, the cache
+    /// journal.
+    pub async fn connect(
+        config: &Config,
+        verbose: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let tasks = TaskTracker::new();
+        let lock_manager = LockManager::from_config(&config.storage)
+            .map_err(|e| format!("Failed to initialize lock storage: {}", e))?
+            .with_task_tracker(tasks.clone());
+        let pg_manager = crate::storage::postgres::PostgresManager::new(
+            &config.storage.database.url,
+            config.storage.database.pool_size,
+        )
+        .await
+        .map_err(|e| format!("Failed to initialize Postgres Manager: {}", e))?;
+
+        let event_store = match config.storage.events {
+            EventStorage::Postgres => EventStore::new(pg_manager.clone()),
+            EventStorage::Memory => EventStore::in_memory(),
+        }
+        .with_dedup_window(config.events.dedup_window);
+        let projections = match config.storage.events {
+            EventStorage::Postgres => {
+                Projections::with_postgres(event_store.clone(), pg_manager.clone())
             }
+            EventStorage::Memory => Projections::new(event_store.clone()),
+        }
+        .with_task_tracker(tasks.clone());
+        let mut dead_letters = DeadLetterQueue::new().with_event_store(event_store.clone());
+        if let Some(url) = &config.sagas.escalation_webhook_url {
+            dead_letters = dead_letters.with_webhook(url.clone());
         }
+        if let Err(e) = dead_letters.restore().await {
+            eprintln!("Error restoring saga dead-letter queue: {}", e);
+        }
+        let cache_manager = match (config.storage.cache, &config.cache.persistence) {
+            (CacheStorage::Redis, _) => {
+                let redis = RedisManager::new(&config.storage.redis.url)
+                    .map_err(|e| format!("Failed to configure Redis cache: {}", e))?;
+                CacheManager::with_redis(redis)
+            }
+            (CacheStorage::Memory, Some(persistence)) => {
+                let cache_manager = CacheManager::with_persistence(persistence, &tasks)
+                    .await
+                    .map_err(|e| format!("Failed to restore cache from journal: {}", e))?;
+                if verbose {
+                    println!("Cache journal enabled at {}", persistence.path);
+                }
+                cache_manager
+            }
+            (CacheStorage::Memory, None) => CacheManager::new(),
+        }
+        .with_limits(&config.cache);
+        let (saga_orchestrator, saga_definitions) = match config.storage.sagas {
+            SagaStorage::Postgres => (
+                SagaOrchestrator::new(pg_manager.clone()),
+                SagaDefinitions::with_postgres(pg_manager),
+            ),
+            SagaStorage::Memory => (SagaOrchestrator::in_memory(), SagaDefinitions::new()),
+        };
+        let saga_orchestrator = saga_orchestrator
+            .with_dead_letter_queue(dead_letters.clone())
+            .with_step_result_limits(
+                StepResultLimits::from_config(&config.sagas).with_cache(cache_manager.clone()),
+            )
+            .with_lock_manager(lock_manager.clone())
+            .with_cache_manager(cache_manager.clone())
+            .with_http_steps(HttpStepClient::new(config.services.clone()))
+            .with_idempotency_ttl(config.sagas.idempotency_ttl())
+            .with_task_tracker(tasks.clone());
+
+        Ok(Self {
+            lock_manager,
+            saga_orchestrator,
+            dead_letters,
+            event_store,
+            cache_manager,
+            saga_definitions,
+            projections,
+            tasks,
+            service_discovery: None,
+        })
     }
 
-    Ok(())
+    /// Managers that keep all their state in this process.
+    pub fn in_memory() -> Self {
+        let event_store = EventStore::in_memory();
+        let dead_letters = DeadLetterQueue::new().with_event_store(event_store.clone());
+        let cache_manager = CacheManager::new();
+        let tasks = TaskTracker::new();
+        let lock_manager = LockManager::in_memory().with_task_tracker(tasks.clone());
+        let projections = Projections::new(event_store.clone()).with_task_tracker(tasks.clone());
+        Self {
+            saga_orchestrator: SagaOrchestrator::in_memory()
+                .with_dead_letter_queue(dead_letters.clone())
+                .with_step_result_limits(
+                    StepResultLimits::default().with_cache(cache_manager.clone()),
+                )
+                .with_lock_manager(lock_manager.clone())
+                .with_cache_manager(cache_manager.clone())
+                .with_task_tracker(tasks.clone()),
+            lock_manager,
+            dead_letters,
+            event_store,
+            cache_manager,
+            saga_definitions: SagaDefinitions::new(),
+            projections,
+            tasks,
+            service_discovery: None,
+        }
+    }
+}
+
+/// Builds the state shared by the REST router and the WebSocket service.
+///
+/// Sets up metrics, the saga worker registry, which runs the steps of the
+/// `worker` executor, and the forwarding of notifications to WebSocket
+/// clients; background tasks are left to the caller.
+pub fn build_api_state(
+    config: Config,
+    services: CoreServices,
+) -> Result<ApiState, Box<dyn std::error::Error>> {
+    let tasks = services.tasks;
+    let saga_workers = SagaWorkerRegistry::default();
+    let event_store = services.event_store;
+    let saga_orchestrator = services
+        .saga_orchestrator
+        .with_executor(WORKER_EXECUTOR, Arc::new(saga_workers.clone()));
+    let cache_manager = services.cache_manager;
+    let lock_manager = services
+        .lock_manager
+        .with_namespace_limit(config.limits.max_locks_per_namespace)
+        .with_idle_ttl(config.locks.idle_ttl());
+
+    #[cfg(feature = "metrics")]
+    let metrics = {
+        let metrics = if config.metrics.runtime_metrics {
+            Metrics::with_runtime_metrics()
+        } else {
+            Metrics::new()
+        };
+        Arc::new(metrics.map_err(|e| format!("Failed to initialize metrics: {}", e))?)
+    };
+    #[cfg(feature = "metrics")]
+    let (saga_workers, event_store, saga_orchestrator, cache_manager, lock_manager) = (
+        saga_workers.with_metrics(metrics.clone()),
+        event_store.with_metrics(metrics.clone()),
+        saga_orchestrator.with_metrics(metrics.clone()),
+        cache_manager.with_metrics(metrics.clone()),
+        lock_manager.with_metrics(metrics.clone()),
+    );
+
+    services.projections.follow(event_store.subscribe("*"));
+    let metadata_policy = MetadataPolicy::from_config(&config.metadata);
+    let namespace_freezes = NamespaceFreezes::new().with_task_tracker(tasks.clone());
+
+    #[cfg(feature = "websocket")]
+    let websocket_service = {
+        let websocket_service = WebSocketService::new(
+            lock_manager.clone(),
+            saga_orchestrator.clone(),
+            event_store.clone(),
+            cache_manager.clone(),
+        )
+        .with_limits(config.websocket.clone())
+        .with_metadata_policy(metadata_policy.clone())
+        .with_namespace_freezes(namespace_freezes.clone())
+        .with_task_tracker(tasks.clone());
+        #[cfg(feature = "metrics")]
+        let websocket_service = websocket_service.with_metrics(metrics.clone());
+        let websocket_service = Arc::new(websocket_service);
+        websocket_service.forward_notifications(services.dead_letters.subscribe());
+        websocket_service.forward_notifications(namespace_freezes.subscribe());
+        websocket_service.forward_saga_updates(saga_orchestrator.subscribe_status_updates());
+        websocket_service.forward_appended_events(event_store.subscribe("*"));
+        websocket_service
+    };
+
+    let auth_middleware = AuthMiddleware::new(&config.security.jwt_secret);
+    let rbac_manager = Arc::new(crate::auth::RBACManager::new());
+
+    Ok(ApiState {
+        instance_id: config.service_discovery.generate_instance_id(),
+        config,
+        lock_manager,
+        saga_orchestrator,
+        saga_workers,
+        saga_definitions: services.saga_definitions,
+        projections: services.projections,
+        dead_letters: services.dead_letters,
+        event_store,
+        cache_manager,
+        #[cfg(feature = "websocket")]
+        websocket_service,
+        #[cfg(feature = "metrics")]
+        metrics,
+        auth_middleware,
+        rbac_manager,
+        components: ComponentRegistry::new(),
+        metadata_policy,
+        namespace_freezes,
+        service_discovery: services.service_discovery,
+        tasks,
+    })
+}
+
+/// Builds the gRPC service over the managers in `state`.
+#[cfg(feature = "grpc")]
+pub fn build_grpc_service(state: &ApiState) -> SyrosGrpcService {
+    SyrosGrpcService::new(
+        state.lock_manager.clone(),
+        state.saga_orchestrator.clone(),
+        state.event_store.clone(),
+        state.cache_manager.clone(),
+    )
+    .with_max_deadline(state.config.timeouts.grpc_max())
+    .with_auth(state.auth_middleware.clone())
+    .with_metadata_policy(state.metadata_policy.clone())
+    .with_lock_limits(state.config.locks.clone())
+    .with_namespace_freezes(state.namespace_freezes.clone())
+}
+
+/// Registers the periodic tasks of this process and schedules them as
+/// configured under `[background_tasks]`.
+pub fn spawn_background_tasks(state: &ApiState) -> crate::Result<TaskSpawner> {
+    let mut spawner =
+        TaskSpawner::new(state.components.clone()).with_task_tracker(state.tasks.clone());
+
+    spawner.register_sweepers(
+        &state.lock_manager,
+        &state.cache_manager,
+        &state.saga_orchestrator,
+    );
+    spawner.register_event_retention(&state.event_store);
+    spawner.register_saga_worker_liveness(&state.saga_workers);
+    if let Some(discovery) = &state.service_discovery {
+        spawner.register_discovery_health(discovery);
+    }
+
+    #[cfg(feature = "metrics")]
+    {
+        let state = state.clone();
+        spawner.register("metrics_sync", move || {
+            let state = state.clone();
+            async move {
+                if let Ok(stats) = state.cache_manager.get_stats().await {
+                    state.metrics.set_cache_size(stats.active_entries as f64);
+                }
+                state
+                    .metrics
+                    .set_saga_dead_letter_size(state.dead_letters.unresolved_count().await);
+                state.metrics.set_tasks_live(&state.tasks.inventory().tasks);
+                let memory = MemoryUsage::estimate(
+                    &state.lock_manager,
+                    &state.cache_manager,
+                    &state.event_store,
+                    &state.saga_orchestrator,
+                )
+                .await;
+                state.metrics.set_memory_usage(&memory);
+                state.metrics.sample_runtime();
+            }
+        });
+    }
+
+    spawner.apply(&state.config.background_tasks)?;
+    Ok(spawner)
+}
+
+/// Reloads the configuration on `SIGHUP` and reschedules the background
+/// tasks whose settings changed.
+#[cfg(unix)]
+fn reload_background_tasks_on_sighup(
+    tasks: &TaskTracker,
+    spawner: Arc<tokio::sync::Mutex<TaskSpawner>>,
+) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            eprintln!("Error installing SIGHUP handler: {}", e);
+            return;
+        }
+    };
+    tasks.spawn_background("config_reload", async move {
+        while hangups.recv().await.is_some() {
+            let result = match Config::load() {
+                Ok(config) => spawner.lock().await.apply(&config.background_tasks),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => tracing::info!("Background task configuration reloaded"),
+                Err(e) => tracing::error!("Keeping previous background task configuration: {}", e),
+            }
+        }
+    });
 }
@@ -0,0 +1,429 @@
+//! Tests for the embeddable core of the Syros.
+//!
+//! These only use the in-memory managers, without any API, so they also run
+//! with `--no-default-features`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use syros::config::TaskSchedule;
+use syros::core::cache_manager::{CacheRequest, CacheSetMode, DeleteCacheRequest};
+use syros::core::event_store::{EventFilter, EventRequest, GetEventsRequest, ReadDirection};
+use syros::core::lock_manager::{LockFilter, LockRequest, ReleaseLockRequest};
+use syros::core::saga_orchestrator::{SagaRequest, SagaStep, StepCallContext};
+use syros::core::{CacheManager, EventStore, LockManager, SagaOrchestrator};
+use syros::{EmbeddedConfig, SyrosEmbedded, SyrosError};
+
+fn lock_request(key: &str, owner: &str) -> LockRequest {
+    LockRequest {
+        key: key.to_string(),
+        ttl: Duration::from_secs(30),
+        metadata: None,
+        owner: owner.to_string(),
+        wait_timeout: None,
+        priority: Default::default(),
+        created_by: None,
+        reentrant: false,
+        session_id: None,
+    }
+}
+
+#[tokio::test]
+async fn test_locks_are_exclusive_until_released() {
+    let locks = LockManager::in_memory();
+
+    let held = locks
+        .acquire_lock(lock_request("orders", "alice"))
+        .await
+        .unwrap();
+    assert!(held.success);
+    assert!(
+        !locks
+            .acquire_lock(lock_request("orders", "bob"))
+            .await
+            .unwrap()
+            .success
+    );
+
+    let released = locks
+        .release_lock(ReleaseLockRequest {
+            key: "orders".to_string(),
+            lock_id: held.lock_id,
+            owner: "alice".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(released.success);
+    assert!(
+        locks
+            .acquire_lock(lock_request("orders", "bob"))
+            .await
+            .unwrap()
+            .success
+    );
+}
+
+#[tokio::test]
+async fn test_cache_set_get_delete() {
+    let cache = CacheManager::new();
+
+    cache
+        .set(CacheRequest {
+            key: "user:1".to_string(),
+            value: serde_json::json!({ "name": "Ada" }),
+            ttl: None,
+            tags: vec!["users".to_string()],
+            mode: CacheSetMode::Upsert,
+            created_by: None,
+            cache_negative: false,
+        })
+        .await
+        .unwrap();
+    let hit = cache.get("user:1").await.unwrap();
+    assert!(hit.found);
+    assert_eq!(hit.value, Some(serde_json::json!({ "name": "Ada" })));
+
+    cache
+        .delete(DeleteCacheRequest {
+            key: "user:1".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(!cache.get("user:1").await.unwrap().found);
+}
+
+#[tokio::test]
+async fn test_in_memory_saga_completes() {
+    let sagas = SagaOrchestrator::in_memory();
+    let mut updates = sagas.subscribe_status_updates();
+
+    let saga_id = sagas
+        .start_saga(SagaRequest {
+            name: "checkout".to_string(),
+            steps: vec![SagaStep {
+                name: "reserve".to_string(),
+                service: "inventory".to_string(),
+                action: "reserve".to_string(),
+                compensation: "release".to_string(),
+                timeout: Duration::from_secs(1),
+                retry_policy: None,
+                payload: None,
+                acquired_locks: vec![],
+                cache_keys: vec![],
+                group: None,
+                executor: None,
+            }],
+            metadata: None,
+            max_duration: None,
+            start_at: None,
+        })
+        .await
+        .unwrap()
+        .saga_id;
+
+    loop {
+        let update = tokio::time::timeout(Duration::from_secs(2), updates.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if update.status == "Completed" {
+            break;
+        }
+    }
+    let saga = sagas.get_saga_status(&saga_id).await.unwrap().unwrap();
+    assert_eq!(saga.status, "Completed");
+}
+
+#[tokio::test]
+async fn test_events_are_appended_in_order() {
+    let events = EventStore::in_memory();
+
+    for event_type in ["created", "paid"] {
+        events
+            .append_event(EventRequest {
+                stream_id: "order-1".to_string(),
+                event_type: event_type.to_string(),
+                data: serde_json::json!({}),
+                metadata: None,
+                created_by: None,
+                expected_version: None,
+                event_id: None,
+                correlation_id: None,
+                causation_id: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let stream = events
+        .get_events(GetEventsRequest {
+            stream_id: "order-1".to_string(),
+            from_version: None,
+            from_snapshot: false,
+            to_version: None,
+            filter: EventFilter::default(),
+            direction: ReadDirection::Forward,
+            limit: None,
+        })
+        .await
+        .unwrap();
+    let types: Vec<_> = stream
+        .events
+        .iter()
+        .map(|e| e.event_type.as_str())
+        .collect();
+    assert_eq!(types, vec!["created", "paid"]);
+}
+
+#[tokio::test]
+async fn test_subscribers_receive_appended_events() {
+    let events = EventStore::in_memory();
+    let mut orders = events.subscribe("order-*");
+    let append = |stream_id: &str| {
+        events.append_event(EventRequest {
+            stream_id: stream_id.to_string(),
+            event_type: "created".to_string(),
+            data: serde_json::json!({ "stream": stream_id }),
+            metadata: None,
+            created_by: None,
+            expected_version: None,
+            event_id: None,
+            correlation_id: None,
+            causation_id: None,
+        })
+    };
+
+    append("payment-1").await.unwrap();
+    append("order-1").await.unwrap();
+    let event = tokio::time::timeout(Duration::from_secs(1), orders.recv())
+        .await
+        .expect("appended event was not delivered")
+        .unwrap();
+    assert_eq!(event.stream_id, "order-1");
+    assert_eq!(event.version, 1);
+    assert_eq!(event.data["stream"], "order-1");
+}
+
+#[tokio::test]
+async fn test_events_carry_correlation_and_causation_ids() {
+    let events = EventStore::in_memory();
+    let append = |event_type: &str, correlation_id: Option<String>, causation_id| {
+        events.append_event(EventRequest {
+            stream_id: "order-1".to_string(),
+            event_type: event_type.to_string(),
+            data: serde_json::json!({}),
+            metadata: None,
+            created_by: None,
+            expected_version: None,
+            event_id: None,
+            correlation_id,
+            causation_id,
+        })
+    };
+
+    let created = append("created", None, None).await.unwrap();
+    let read = || {
+        events.get_events(GetEventsRequest {
+            stream_id: "order-1".to_string(),
+            from_version: None,
+            from_snapshot: false,
+            to_version: None,
+            filter: EventFilter::default(),
+            direction: ReadDirection::Forward,
+            limit: None,
+        })
+    };
+    let correlation_id = read().await.unwrap().events[0].metadata["correlation_id"].clone();
+    assert!(!correlation_id.is_empty());
+
+    append(
+        "paid",
+        Some(correlation_id.clone()),
+        Some(created.event_id.clone()),
+    )
+    .await
+    .unwrap();
+    append("shipped", None, None).await.unwrap();
+
+    let stream = read().await.unwrap();
+    assert_eq!(stream.events[1].metadata["correlation_id"], correlation_id);
+    assert_eq!(stream.events[1].metadata["causation_id"], created.event_id);
+    assert_ne!(stream.events[2].metadata["correlation_id"], correlation_id);
+    assert!(!stream.events[2].metadata.contains_key("causation_id"));
+}
+
+fn step(name: &str, service: &str, action: &str, compensation: &str) -> SagaStep {
+    SagaStep {
+        name: name.to_string(),
+        service: service.to_string(),
+        action: action.to_string(),
+        compensation: compensation.to_string(),
+        timeout: Duration::from_secs(1),
+        retry_policy: None,
+        payload: None,
+        acquired_locks: vec![],
+        cache_keys: vec![],
+        group: None,
+        executor: None,
+    }
+}
+
+fn saga_request(steps: Vec<SagaStep>) -> SagaRequest {
+    SagaRequest {
+        name: "checkout".to_string(),
+        steps,
+        metadata: None,
+        max_duration: None,
+        start_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_embedded_saga_runs_registered_steps() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let record = |calls: &Arc<Mutex<Vec<String>>>| {
+        let calls = calls.clone();
+        move |step: SagaStep, context: StepCallContext| {
+            let calls = calls.clone();
+            async move {
+                assert!(!context.saga_id.is_empty());
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:{}", step.service, step.name));
+                Ok(())
+            }
+        }
+    };
+    let syros = SyrosEmbedded::builder()
+        .with_step("inventory", "reserve", record(&calls))
+        .with_step("payments", "charge", record(&calls))
+        .start()
+        .await
+        .unwrap();
+
+    let saga = syros
+        .run_saga(saga_request(vec![
+            step("reserve", "inventory", "reserve", "release"),
+            step("charge", "payments", "charge", "refund"),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(saga.status, "Completed");
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec!["inventory:reserve", "payments:charge"]
+    );
+
+    let stored = syros.sagas().get_saga_status(&saga.id).await.unwrap();
+    assert_eq!(stored.unwrap().status, "Completed");
+    assert_eq!(syros.shutdown().await, 0);
+}
+
+#[tokio::test]
+async fn test_embedded_saga_compensates_through_registered_steps() {
+    let compensated = Arc::new(Mutex::new(Vec::new()));
+    let undo = |compensated: &Arc<Mutex<Vec<String>>>| {
+        let compensated = compensated.clone();
+        move |step: SagaStep, context: StepCallContext| {
+            let compensated = compensated.clone();
+            async move {
+                assert!(context.compensation);
+                compensated.lock().unwrap().push(step.name);
+                Ok(())
+            }
+        }
+    };
+    let syros = SyrosEmbedded::builder()
+        .with_step("inventory", "reserve", |_step, _context| async { Ok(()) })
+        .with_step("inventory", "release", undo(&compensated))
+        .with_step("payments", "charge", |_step, _context| async {
+            Err(SyrosError::SagaError("card declined".to_string()))
+        })
+        .with_step("payments", "refund", undo(&compensated))
+        .start()
+        .await
+        .unwrap();
+
+    let saga = syros
+        .run_saga(saga_request(vec![
+            step("reserve", "inventory", "reserve", "release"),
+            step("charge", "payments", "charge", "refund"),
+            // Never reached; without a compensation there is nothing to undo.
+            step("notify", "email", "send", ""),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(saga.status, "Compensated");
+    assert_eq!(*compensated.lock().unwrap(), vec!["charge", "reserve"]);
+
+    // A step whose action has no handler fails the saga.
+    let saga = syros
+        .run_saga(saga_request(vec![step("ship", "shipping", "ship", "")]))
+        .await
+        .unwrap();
+    assert_eq!(saga.status, "Compensated");
+    syros.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_embedded_sweepers_run_until_shutdown() {
+    let mut config = EmbeddedConfig::default();
+    config.background_tasks.locks_sweep = TaskSchedule::every_ms(100);
+    config.background_tasks.cache_sweep = TaskSchedule::every_ms(100);
+    let syros = SyrosEmbedded::builder()
+        .with_config(config)
+        .start()
+        .await
+        .unwrap();
+
+    let mut request = lock_request("orders", "alice");
+    request.ttl = Duration::from_millis(50);
+    assert!(syros.locks().acquire_lock(request).await.unwrap().success);
+    syros
+        .cache()
+        .set(CacheRequest {
+            key: "session".to_string(),
+            value: serde_json::json!("token"),
+            ttl: Some(Duration::from_millis(50)),
+            tags: vec![],
+            mode: CacheSetMode::Upsert,
+            created_by: None,
+            cache_negative: false,
+        })
+        .await
+        .unwrap();
+    syros
+        .events()
+        .append_event(EventRequest {
+            stream_id: "order-1".to_string(),
+            event_type: "created".to_string(),
+            data: serde_json::json!({}),
+            metadata: None,
+            created_by: None,
+            expected_version: None,
+            event_id: None,
+            correlation_id: None,
+            causation_id: None,
+        })
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    assert_eq!(syros.cache().get_stats().await.unwrap().total_entries, 0);
+    assert!(syros
+        .locks()
+        .list_locks(&LockFilter::default())
+        .await
+        .unwrap()
+        .is_empty());
+    let sweeps = syros.components().get("locks_sweep").unwrap();
+    assert!(sweeps.running && sweeps.runs > 0);
+    // Tasks of the server are not run embedded.
+    assert!(!syros.components().get("metrics_sync").unwrap().registered);
+
+    assert_eq!(syros.tasks().live_named("locks_sweep"), 1);
+    let tasks = syros.tasks().clone();
+    assert_eq!(syros.shutdown().await, 0);
+    assert_eq!(tasks.live(), 0);
+}
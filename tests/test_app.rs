@@ -0,0 +1,209 @@
+//! In-process Syros instance for integration tests.
+//!
+//! [`TestApp`] wires the real REST router, WebSocket service and gRPC service
+//! over in-memory managers, using the same builders as `start_server`, and
+//! serves them on ephemeral ports so tests can run in parallel without Redis
+//! or Postgres.
+
+use reqwest::{Client, RequestBuilder};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use syros::{
+    api::{
+        grpc::SyrosGrpcService,
+        rest::{create_rest_router, ApiState},
+    },
+    config::{Config, TaskSchedule},
+    core::TaskSpawner,
+    server::{build_api_state, build_grpc_service, spawn_background_tasks, CoreServices},
+};
+
+/// Secret the test instance signs and verifies JWTs with.
+pub const TEST_JWT_SECRET: &str = "integration-test-secret";
+
+/// A running Syros instance backed by in-memory managers.
+pub struct TestApp {
+    /// State shared by every API, for direct access to the managers
+    pub state: ApiState,
+    /// The service instance answering on [`grpc_addr`](Self::grpc_addr)
+    pub grpc: SyrosGrpcService,
+    pub rest_addr: SocketAddr,
+    pub grpc_addr: SocketAddr,
+    client: Client,
+    admin_token: String,
+    _background_tasks: TaskSpawner,
+    servers: Vec<JoinHandle<()>>,
+}
+
+impl TestApp {
+    /// Starts an instance with [`test_config`].
+    pub async fn spawn() -> Self {
+        Self::spawn_with_config(test_config()).await
+    }
+
+    /// Starts an instance with `config`; its ports are ignored in favour of
+    /// ephemeral ones.
+    pub async fn spawn_with_config(config: Config) -> Self {
+        Self::spawn_with_services(config, CoreServices::in_memory()).await
+    }
+
+    /// Starts an instance with `config` over `services`, e.g. in-memory
+    /// managers with some of them customised.
+    pub async fn spawn_with_services(config: Config, services: CoreServices) -> Self {
+        let state = build_api_state(config, services).expect("Failed to build API state");
+        syros::seed::apply_configured(&state)
+            .await
+            .expect("Failed to apply seed data");
+        let background_tasks =
+            spawn_background_tasks(&state).expect("Failed to start background tasks");
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind REST listener");
+        let rest_addr = listener.local_addr().unwrap();
+        let app = create_rest_router(state.clone());
+        let rest = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .await
+                .expect("REST server failed");
+        });
+
+        let grpc = build_grpc_service(&state);
+        let grpc_addr = free_port();
+        let grpc_server = grpc.clone();
+        let grpc_task = tokio::spawn(async move {
+            if let Err(e) = grpc_server.start_grpc_server(grpc_addr).await {
+                eprintln!("gRPC server error: {}", e);
+            }
+        });
+        wait_until_listening(grpc_addr).await;
+
+        let admin_token = state
+            .auth_middleware
+            .jwt_auth
+            .generate_token("test-admin".to_string(), "admin".to_string(), 1)
+            .expect("Failed to sign admin token");
+
+        Self {
+            state,
+            grpc,
+            rest_addr,
+            grpc_addr,
+            client: Client::new(),
+            admin_token,
+            _background_tasks: background_tasks,
+            servers: vec![rest, grpc_task],
+        }
+    }
+
+    /// Absolute REST URL of `path`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.rest_addr, path)
+    }
+
+    /// WebSocket URL of `path`.
+    pub fn ws_url(&self, path: &str) -> String {
+        format!("ws://{}{}", self.rest_addr, path)
+    }
+
+    /// Signs a token for `user_id` with `role`, e.g. `"viewer"`.
+    pub fn token_for(&self, user_id: &str, role: &str) -> String {
+        self.state
+            .auth_middleware
+            .jwt_auth
+            .generate_token(user_id.to_string(), role.to_string(), 1)
+            .expect("Failed to sign token")
+    }
+
+    /// Client for requests without credentials.
+    pub fn anonymous(&self) -> &Client {
+        &self.client
+    }
+
+    /// GET `path` as an administrator.
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.client
+            .get(self.url(path))
+            .bearer_auth(&self.admin_token)
+    }
+
+    /// POST `path` as an administrator.
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.client
+            .post(self.url(path))
+            .bearer_auth(&self.admin_token)
+    }
+
+    /// PUT `path` as an administrator.
+    pub fn put(&self, path: &str) -> RequestBuilder {
+        self.client
+            .put(self.url(path))
+            .bearer_auth(&self.admin_token)
+    }
+
+    /// DELETE `path` as an administrator.
+    pub fn delete(&self, path: &str) -> RequestBuilder {
+        self.client
+            .delete(self.url(path))
+            .bearer_auth(&self.admin_token)
+    }
+
+    /// Runs a GraphQL `query` authenticated with `token`, if any, and returns
+    /// the response body.
+    pub async fn graphql(&self, query: &str, token: Option<&str>) -> serde_json::Value {
+        let mut request = self
+            .client
+            .post(self.url("/graphql"))
+            .json(&serde_json::json!({ "query": query }));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request
+            .send()
+            .await
+            .expect("Failed to send GraphQL request")
+            .json()
+            .await
+            .expect("Invalid GraphQL response")
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        for server in &self.servers {
+            server.abort();
+        }
+    }
+}
+
+/// Default configuration of a [`TestApp`].
+///
+/// The saga scheduler runs every 100ms so saga timeouts are noticed quickly.
+pub fn test_config() -> Config {
+    let mut config = Config::default();
+    config.server.host = "127.0.0.1".to_string();
+    config.security.jwt_secret = TEST_JWT_SECRET.to_string();
+    config.background_tasks.saga_scheduler = TaskSchedule::every_ms(100);
+    config.background_tasks.discovery_health = TaskSchedule::every_ms(100);
+    config
+}
+
+/// Reserves an ephemeral port for a server that binds its own listener.
+fn free_port() -> SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("No free port")
+}
+
+async fn wait_until_listening(addr: SocketAddr) {
+    for _ in 0..100 {
+        if tokio::net::TcpStream::connect(addr).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Server did not start listening on {}", addr);
+}